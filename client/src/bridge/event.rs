@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use wasm_bindgen::JsCast;
 
 use crate::viewport::ViewportPoint;
@@ -189,6 +191,9 @@ pub struct InputEvent {
     pub shift: bool,
     pub ctrl: bool,
     pub alt: bool,
+
+    /// Pressure of a stylus, between 0 and 1. None for other inputs.
+    pub pressure: Option<f32>,
 }
 
 impl InputEvent {
@@ -200,10 +205,108 @@ impl InputEvent {
             "mousedown" | "mouseenter" | "mouseleave" | "mousemove" | "mouseup" | "wheel" => {
                 Self::from_mouse(event.unchecked_ref::<web_sys::MouseEvent>())
             }
+            "pointerdown" | "pointermove" | "pointerup" => {
+                Self::from_pointer(event.unchecked_ref::<web_sys::PointerEvent>())
+            }
+            "touchstart" | "touchmove" | "touchend" => {
+                Self::from_touch(event.unchecked_ref::<web_sys::TouchEvent>())
+            }
             _ => None,
         }
     }
 
+    /// Single touches are treated as the left mouse button. Two finger
+    /// pinches are treated as ctrl + scroll, which zooms the viewport.
+    fn from_touch(event: &web_sys::TouchEvent) -> Option<InputEvent> {
+        const PINCH_COEFFICIENT: f32 = 4.0;
+
+        thread_local! {
+            // Distance between the fingers of an ongoing pinch, in pixels.
+            static PINCH_DISTANCE: Cell<Option<f32>> = const { Cell::new(None) };
+        }
+
+        let point = |touch: web_sys::Touch| ViewportPoint::new(touch.client_x(), touch.client_y());
+        let distance = |a: ViewportPoint, b: ViewportPoint| (a.x - b.x).hypot(a.y - b.y);
+        let touches = event.touches();
+        let pinch = PINCH_DISTANCE.get();
+
+        let (at, action, ctrl) = match event.type_().as_str() {
+            "touchstart" if touches.length() == 1 => {
+                (point(touches.get(0)?), MouseAction::Down, false)
+            }
+            "touchstart" if touches.length() == 2 => {
+                let (a, b) = (point(touches.get(0)?), point(touches.get(1)?));
+                PINCH_DISTANCE.set(Some(distance(a, b)));
+
+                // Release whatever the first touch was doing.
+                (a, MouseAction::Up, false)
+            }
+            "touchmove" if touches.length() == 1 && pinch.is_none() => {
+                (point(touches.get(0)?), MouseAction::Move, false)
+            }
+            "touchmove" if touches.length() == 2 => {
+                let (a, b) = (point(touches.get(0)?), point(touches.get(1)?));
+                let current = distance(a, b);
+                PINCH_DISTANCE.set(Some(current));
+                let delta = (pinch.unwrap_or(current) - current) * PINCH_COEFFICIENT;
+                let midpoint = ViewportPoint {
+                    x: (a.x + b.x) / 2.0,
+                    y: (a.y + b.y) / 2.0,
+                };
+                (midpoint, MouseAction::Wheel(delta), true)
+            }
+            "touchend" => {
+                PINCH_DISTANCE.set(None);
+                if pinch.is_some() || touches.length() > 0 {
+                    return None;
+                }
+                (
+                    point(event.changed_touches().get(0)?),
+                    MouseAction::Up,
+                    false,
+                )
+            }
+            _ => return None,
+        };
+
+        Some(InputEvent {
+            input: Input::Mouse(at, action, MouseButton::Left),
+            shift: event.shift_key(),
+            ctrl,
+            alt: event.alt_key(),
+            pressure: None,
+        })
+    }
+
+    /// Pointer events are handled as mouse events, with the pressure of
+    /// styluses. Touches are handled by the touch events.
+    fn from_pointer(event: &web_sys::PointerEvent) -> Option<InputEvent> {
+        let action = match event.type_().as_str() {
+            "pointerdown" => MouseAction::Down,
+            "pointermove" => MouseAction::Move,
+            "pointerup" => MouseAction::Up,
+            _ => return None,
+        };
+
+        let pressure = match event.pointer_type().as_str() {
+            "pen" => Some(event.pressure()),
+            "touch" => return None,
+            _ => None,
+        };
+
+        Some(InputEvent {
+            input: Input::Mouse(
+                ViewportPoint::new(event.x(), event.y()),
+                action,
+                MouseButton::from(event.button()),
+            ),
+            shift: event.shift_key(),
+            ctrl: event.ctrl_key(),
+            alt: event.alt_key(),
+            pressure,
+        })
+    }
+
     fn from_mouse(event: &web_sys::MouseEvent) -> Option<InputEvent> {
         let action = match event.type_().as_str() {
             "mousedown" => MouseAction::Down,
@@ -230,6 +333,7 @@ impl InputEvent {
             shift: event.shift_key(),
             ctrl: event.ctrl_key(),
             alt: event.alt_key(),
+            pressure: None,
         })
     }
 
@@ -245,6 +349,7 @@ impl InputEvent {
             shift: event.shift_key(),
             ctrl: event.ctrl_key(),
             alt: event.alt_key(),
+            pressure: None,
         })
     }
 }
@@ -10,8 +10,9 @@ use wasm_bindgen::JsCast;
 use web_sys::Headers;
 use web_sys::Response;
 use web_sys::{
-    Blob, Document, FileReader, HtmlCanvasElement, HtmlElement, HtmlImageElement, HtmlInputElement,
-    ProgressEvent, Request, RequestInit, UiEvent, Url, WebGl2RenderingContext, Window,
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, Document, FileReader, HtmlCanvasElement,
+    HtmlElement, HtmlImageElement, HtmlInputElement, ProgressEvent, Request, RequestInit, UiEvent,
+    Url, WebGl2RenderingContext, Window,
 };
 
 use crate::dom::element::Element;
@@ -42,7 +43,11 @@ extern "C" {
     pub fn log_js_value(v: &JsValue);
 
     // Upload a thumbnail using the current canvas.
-    pub fn upload_thumbnail(scene_uuid: &str);
+    pub fn upload_thumbnail(project_uuid: &str, scene_uuid: &str);
+
+    // Open a window for the player screen, returning a canvas in it which
+    // fills the window.
+    fn open_player_window() -> Option<HtmlCanvasElement>;
 
     // Expose closures
     #[wasm_bindgen]
@@ -54,6 +59,9 @@ extern "C" {
     #[wasm_bindgen(js_name = expose_closure)]
     pub fn expose_closure_f64x2_string(name: &str, closure: &Closure<dyn FnMut(f64, f64, String)>);
 
+    #[wasm_bindgen(js_name = expose_closure)]
+    pub fn expose_closure_string_in(name: &str, closure: &Closure<dyn FnMut(String)>);
+
     #[wasm_bindgen(js_name = expose_closure)]
     pub fn expose_closure_string_out(name: &str, closure: &Closure<dyn FnMut() -> String>);
 }
@@ -105,13 +113,15 @@ impl Canvas {
         Ok(canvas)
     }
 
-    /// Set the canvas' dimensions to those of the viewport.
+    /// Set the canvas' dimensions to those of the viewport, in device
+    /// pixels so that rendering is sharp on high-DPI displays.
     /// This is static as it's useful to call it from closures
     fn fill_window(canvas: &HtmlCanvasElement) -> Res<()> {
         let (vp_w, vp_h) = get_window_dimensions()?;
+        let (w, h) = physical_dimensions((vp_w, vp_h), device_pixel_ratio(&window()?));
 
-        canvas.set_width(vp_w);
-        canvas.set_height(vp_h);
+        canvas.set_width(w);
+        canvas.set_height(h);
 
         Ok(())
     }
@@ -141,11 +151,18 @@ impl Canvas {
             left: 0;
             position: absolute;
             top: 0;
+            width: 100%;
+            height: 100%;
         }
         */
         self.set_css("position", "absolute")?;
         self.set_css("top", "0")?;
         self.set_css("left", "0")?;
+        self.set_css("width", "100%")?;
+        self.set_css("height", "100%")?;
+
+        // Stop styluses from scrolling the page, so that they can draw.
+        self.set_css("touch-action", "none")?;
 
         Ok(())
     }
@@ -183,21 +200,28 @@ impl Canvas {
             listener.forget();
         }
 
+        // Pointer events are used in place of mouse events for presses and
+        // movement so that stylus pressure is available.
         for event_name in [
-            "mousedown",
-            "mouseup",
+            "pointerdown",
+            "pointerup",
             "mouseenter",
             "mouseleave",
-            "mousemove",
+            "pointermove",
             "wheel",
             "keydown",
             "keyup",
+            "touchstart",
+            "touchmove",
+            "touchend",
         ] {
             let events = self.events.clone();
             let listener = Closure::wrap(Box::new(move |event: web_sys::UiEvent| {
                 events.push(&event);
 
-                if event_name == "wheel" {
+                // Touch events are also prevented so that the browser doesn't
+                // scroll the page or emulate mouse events from them.
+                if event_name == "wheel" || event_name.starts_with("touch") {
                     event.prevent_default();
                 }
             }) as Box<dyn FnMut(web_sys::UiEvent)>);
@@ -221,68 +245,16 @@ impl Canvas {
         let result = {
             let c_input = input.clone();
             let closure = Closure::wrap(Box::new(move |_event: web_sys::InputEvent| {
-                let file = match c_input.files() {
-                    Some(fs) => match fs.get(0) {
-                        Some(f) => f,
-                        None => return,
-                    },
-                    None => return,
-                };
-
-                let file_reader = match FileReader::new() {
-                    Ok(fr) => Rc::new(fr),
-                    Err(_) => return,
+                let Some(files) = c_input.files() else {
+                    return;
                 };
 
-                // File load handling
-                let fr_ref = file_reader.clone();
-                let tq_ref = texture_queue.clone();
-                let closure = Closure::wrap(Box::new(move |_event: ProgressEvent| {
-                    let file = match fr_ref.result() {
-                        Ok(f) => f,
-                        Err(_) => return,
-                    };
-
-                    let array = js_sys::Array::new();
-                    array.push(&file);
-
-                    let blob = match Blob::new_with_buffer_source_sequence(&array) {
-                        Ok(b) => b,
-                        Err(_) => return,
-                    };
-
-                    let src = match Url::create_object_url_with_blob(&blob) {
-                        Ok(s) => s,
-                        Err(_) => return,
-                    };
-
-                    let image = match HtmlImageElement::new() {
-                        Ok(i) => Rc::new(i),
-                        Err(_) => return,
-                    };
-
-                    {
-                        let im_ref = image.clone();
-                        let tq_ref = tq_ref.clone();
-                        let closure = Closure::wrap(Box::new(move || {
-                            tq_ref.push(&im_ref);
-                        }) as Box<dyn FnMut()>);
-                        image.set_onload(Some(closure.as_ref().unchecked_ref()));
-                        closure.forget();
-                    }
-
-                    image.set_src(&src);
-                }) as Box<dyn FnMut(_)>);
-
-                if file_reader
-                    .add_event_listener_with_callback("loadend", closure.as_ref().unchecked_ref())
-                    .is_err()
-                {
-                    return;
+                for file in (0..files.length()).filter_map(|i| files.get(i)) {
+                    let tq_ref = texture_queue.clone();
+                    load_image_file(&file, move |image| {
+                        tq_ref.push(image);
+                    });
                 }
-                closure.forget();
-
-                file_reader.read_as_array_buffer(&file).ok();
             }) as Box<dyn FnMut(_)>);
             let result =
                 input.add_event_listener_with_callback("input", closure.as_ref().unchecked_ref());
@@ -307,6 +279,153 @@ impl Canvas {
         }
         .map_err(|e| format!("Failed to add click listener: {e:?}."))
     }
+
+    /// Adds an event listener which loads images pasted onto the canvas,
+    /// giving each a local media key. Loaded images are pushed to both
+    /// `texture_queue` and `pasted`.
+    fn configure_paste(&self, texture_queue: Rc<Array>, pasted: Rc<Array>) -> Res<()> {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::ClipboardEvent| {
+            let Some(files) = event.clipboard_data().and_then(|data| data.files()) else {
+                return;
+            };
+
+            for i in 0..files.length() {
+                let Some(file) = files.get(i) else {
+                    continue;
+                };
+                if !file.type_().starts_with("image/") {
+                    continue;
+                }
+
+                event.prevent_default();
+                let tq_ref = texture_queue.clone();
+                let pasted_ref = pasted.clone();
+                load_image_file(&file, move |image| {
+                    let half = || (js_sys::Math::random() * u32::MAX as f64) as u64;
+                    let key = (half() << 32) | half();
+                    image
+                        .set_attribute("data-media_key", &format!("{key:016X}"))
+                        .ok();
+                    tq_ref.push(image);
+                    pasted_ref.push(image);
+                });
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        let result = self
+            .element
+            .add_event_listener_with_callback("paste", closure.as_ref().unchecked_ref());
+        closure.forget();
+        result.map_err(|e| format!("Failed to add paste listener: {e:?}."))
+    }
+}
+
+/// Read `file` and load it as an image, calling `on_load` with the image
+/// element once it's ready.
+fn load_image_file<F: Fn(&HtmlImageElement) + 'static>(file: &web_sys::File, on_load: F) {
+    let file_reader = match FileReader::new() {
+        Ok(fr) => Rc::new(fr),
+        Err(_) => return,
+    };
+
+    // File load handling
+    let fr_ref = file_reader.clone();
+    let on_load = Rc::new(on_load);
+    let closure = Closure::wrap(Box::new(move |_event: ProgressEvent| {
+        let file = match fr_ref.result() {
+            Ok(f) => f,
+            Err(_) => return,
+        };
+
+        let array = js_sys::Array::new();
+        array.push(&file);
+
+        let blob = match Blob::new_with_buffer_source_sequence(&array) {
+            Ok(b) => b,
+            Err(_) => return,
+        };
+
+        let src = match Url::create_object_url_with_blob(&blob) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        let image = match HtmlImageElement::new() {
+            Ok(i) => Rc::new(i),
+            Err(_) => return,
+        };
+
+        {
+            let im_ref = image.clone();
+            let on_load = on_load.clone();
+            let closure = Closure::wrap(Box::new(move || {
+                on_load(&im_ref);
+            }) as Box<dyn FnMut()>);
+            image.set_onload(Some(closure.as_ref().unchecked_ref()));
+            closure.forget();
+        }
+
+        image.set_src(&src);
+    }) as Box<dyn FnMut(_)>);
+
+    if file_reader
+        .add_event_listener_with_callback("loadend", closure.as_ref().unchecked_ref())
+        .is_err()
+    {
+        return;
+    }
+    closure.forget();
+
+    file_reader.read_as_array_buffer(file).ok();
+}
+
+/// A canvas in a separate window, which a second screen facing the players
+/// can display.
+struct PlayerScreen {
+    window: Window,
+    canvas: HtmlCanvasElement,
+    gl: Rc<Gl>,
+    renderer: WebGlRenderer,
+}
+
+impl PlayerScreen {
+    fn open() -> Res<PlayerScreen> {
+        let Some(canvas) = open_player_window() else {
+            return err("Failed to open player window.");
+        };
+        let Some(window) = canvas.owner_document().and_then(|d| d.default_view()) else {
+            return err("Player canvas has no window.");
+        };
+
+        let gl = Rc::new(create_context(&canvas)?);
+        let renderer = WebGlRenderer::new(gl.clone())?;
+        let screen = PlayerScreen {
+            window,
+            canvas,
+            gl,
+            renderer,
+        };
+        screen.fit_window();
+        Ok(screen)
+    }
+
+    /// Set the canvas' dimensions to those of its window, in device pixels.
+    /// Returns true if they changed.
+    fn fit_window(&self) -> bool {
+        let Ok(dimensions) = window_dimensions(&self.window) else {
+            return false;
+        };
+        let (w, h) = physical_dimensions(dimensions, device_pixel_ratio(&self.window));
+
+        if self.canvas.width() == w && self.canvas.height() == h {
+            return false;
+        }
+
+        self.canvas.set_width(w);
+        self.canvas.set_height(h);
+        self.gl.viewport(0, 0, w as i32, h as i32);
+        true
+    }
 }
 
 pub struct Context {
@@ -325,6 +444,13 @@ pub struct Context {
     // then loads any images waiting in the queue before rendering each frame.
     // Wrapped in Rc such that it can be accessed from a closure passed to JS.
     texture_queue: Rc<Array>,
+
+    // Images pasted onto the canvas, which should be added to the scene as
+    // sprites once loaded.
+    pasted: Rc<Array>,
+
+    // Canvas in a second window showing the scene as players see it.
+    player_screen: Option<PlayerScreen>,
 }
 
 impl Context {
@@ -336,7 +462,11 @@ impl Context {
             canvas,
             renderer,
             texture_queue: Rc::new(get_texture_queue()),
+            pasted: Rc::new(Array::new()),
+            player_screen: None,
         };
+        ctx.canvas
+            .configure_paste(ctx.texture_queue.clone(), ctx.pasted.clone())?;
 
         Ok(ctx)
     }
@@ -347,8 +477,18 @@ impl Context {
             .ok();
     }
 
+    /// Dimensions of the canvas in CSS pixels.
     pub fn viewport_size(&self) -> (u32, u32) {
-        (self.canvas.element.width(), self.canvas.element.height())
+        let ratio = self.pixel_ratio();
+        (
+            (self.canvas.element.width() as f32 / ratio).round() as u32,
+            (self.canvas.element.height() as f32 / ratio).round() as u32,
+        )
+    }
+
+    /// Device pixels per CSS pixel on the display.
+    pub fn pixel_ratio(&self) -> f32 {
+        window().map_or(1.0, |w| device_pixel_ratio(&w))
     }
 
     pub fn events(&self) -> Option<Vec<event::InputEvent>> {
@@ -384,10 +524,31 @@ impl Context {
             // such elements, so this cast is safe.
             let img = img.unchecked_ref::<HtmlImageElement>();
             self.renderer.load_image(img);
+            if let Some(screen) = &mut self.player_screen {
+                screen.renderer.load_image(img);
+            }
         }
         true
     }
 
+    /// Media key and dimensions in pixels of each image pasted since the last
+    /// call. The textures for these images are in the texture queue.
+    pub fn pasted_textures(&mut self) -> Vec<(scene::Id, u32, u32)> {
+        let mut pasted = Vec::new();
+        while self.pasted.length() > 0 {
+            let img = self.pasted.pop();
+            let img = img.unchecked_ref::<HtmlImageElement>();
+            if let Some(key) = img.get_attribute("data-media_key") {
+                pasted.push((
+                    crate::render::parse_media_key(&key),
+                    img.natural_width(),
+                    img.natural_height(),
+                ));
+            }
+        }
+        pasted
+    }
+
     pub fn set_cursor(&self, cursor: Cursor) {
         cursor.set_for(&self.canvas.element).ok();
     }
@@ -395,6 +556,47 @@ impl Context {
     pub fn renderer(&mut self) -> &mut WebGlRenderer {
         &mut self.renderer
     }
+
+    pub fn open_player_screen(&mut self) -> Res<()> {
+        if self.player_screen.is_none() {
+            self.player_screen = Some(PlayerScreen::open()?);
+        }
+        Ok(())
+    }
+
+    pub fn close_player_screen(&mut self) {
+        if let Some(screen) = self.player_screen.take() {
+            screen.window.close().ok();
+        }
+    }
+
+    /// Resize the player screen to fill its window, or drop it if the window
+    /// has been closed. Returns None if there's no player screen, else
+    /// whether it was resized.
+    pub fn update_player_screen(&mut self) -> Option<bool> {
+        if self
+            .player_screen
+            .as_ref()
+            .is_some_and(|screen| screen.window.closed().unwrap_or(true))
+        {
+            self.player_screen = None;
+        }
+
+        self.player_screen.as_ref().map(PlayerScreen::fit_window)
+    }
+
+    /// Renderer for the player screen, with the size of its canvas in CSS
+    /// pixels and its device pixel ratio, if there's a player screen open.
+    pub fn player_renderer(&mut self) -> Option<(&mut WebGlRenderer, (u32, u32), f32)> {
+        self.player_screen.as_mut().map(|screen| {
+            let ratio = device_pixel_ratio(&screen.window);
+            let size = (
+                (screen.canvas.width() as f32 / ratio).round() as u32,
+                (screen.canvas.height() as f32 / ratio).round() as u32,
+            );
+            (&mut screen.renderer, size, ratio)
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -546,7 +748,47 @@ fn prepend_child(parent: &HtmlElement, child: &HtmlElement) {
         .ok();
 }
 
-pub fn websocket_url() -> Res<Option<(String, String)>> {
+/// Scene and view to open a game at, from the page URL, e.g.
+/// /game/KEY?scene=UUID&vx=10.5&vy=20.3&gs=64. Invalid parameters are
+/// ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeepLink {
+    pub scene: Option<uuid::Uuid>,
+
+    /// Top left corner of the view, in tiles.
+    pub at: Option<scene::Point>,
+
+    /// Pixels per tile at which to render the view.
+    pub grid_size: Option<f32>,
+}
+
+impl DeepLink {
+    fn parse(query: &str) -> Self {
+        let mut link = DeepLink::default();
+        let (mut x, mut y) = (None, None);
+        let number = |value: &str| value.parse::<f32>().ok().filter(|v| v.is_finite());
+        for (key, value) in query
+            .trim_start_matches('?')
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+        {
+            match key {
+                "scene" => link.scene = uuid::Uuid::try_parse(value).ok(),
+                "vx" => x = number(value),
+                "vy" => y = number(value),
+                "gs" => link.grid_size = number(value).filter(|&gs| gs > 0.0),
+                _ => {}
+            }
+        }
+
+        if let (Some(x), Some(y)) = (x, y) {
+            link.at = Some(scene::Point::new(x, y));
+        }
+        link
+    }
+}
+
+pub fn websocket_url() -> Res<Option<(String, String, DeepLink)>> {
     let win = match window() {
         Ok(w) => w,
         Err(_) => return err("Failed to read window Location."),
@@ -557,6 +799,9 @@ pub fn websocket_url() -> Res<Option<(String, String)>> {
         Err(_) => return err("Failed to read window host."),
     };
 
+    // Query string is passed along to the server, e.g. ?role=observer.
+    let query = loc.search().unwrap_or_default();
+
     match (loc.pathname(), loc.protocol()) {
         (Ok(path), Ok(protocol)) => {
             let mut parts = path.split('/').collect::<Vec<&str>>();
@@ -564,12 +809,14 @@ pub fn websocket_url() -> Res<Option<(String, String)>> {
             match parts[..] {
                 ["game", game_key] => Ok(Some((
                     format!(
-                        "{}://{}/api/game/{}",
+                        "{}://{}/api/game/{}{}",
                         if protocol.contains('s') { "wss" } else { "ws" },
                         &host,
                         game_key,
+                        query,
                     ),
                     game_key.to_string(),
+                    DeepLink::parse(&query),
                 ))),
                 _ => Ok(None),
             }
@@ -579,8 +826,10 @@ pub fn websocket_url() -> Res<Option<(String, String)>> {
 }
 
 fn get_window_dimensions() -> Res<(u32, u32)> {
-    let win = window()?;
+    window_dimensions(&window()?)
+}
 
+fn window_dimensions(win: &Window) -> Res<(u32, u32)> {
     match (win.inner_width(), win.inner_height()) {
         (Ok(w), Ok(h)) => match (w.as_f64(), h.as_f64()) {
             (Some(w), Some(h)) => Ok((w as u32, h as u32)),
@@ -590,11 +839,67 @@ fn get_window_dimensions() -> Res<(u32, u32)> {
     }
 }
 
+/// Read a value persisted in the browser's local storage.
+pub fn local_storage_get(key: &str) -> Option<String> {
+    window().ok()?.local_storage().ok()??.get_item(key).ok()?
+}
+
+/// Persist a value in the browser's local storage.
+pub fn local_storage_set(key: &str, value: &str) -> Res<()> {
+    match window()?.local_storage() {
+        Ok(Some(storage)) => storage
+            .set_item(key, value)
+            .map_err(|e| format!("Failed to write local storage: {e:?}.")),
+        _ => err("Local storage unavailable."),
+    }
+}
+
+/// Device pixels per CSS pixel in `win`.
+fn device_pixel_ratio(win: &Window) -> f32 {
+    let ratio = win.device_pixel_ratio() as f32;
+    if ratio > 0.0 {
+        ratio
+    } else {
+        1.0
+    }
+}
+
+/// Dimensions in device pixels of an area measured in CSS pixels.
+fn physical_dimensions((w, h): (u32, u32), ratio: f32) -> (u32, u32) {
+    (
+        (w as f32 * ratio).round() as u32,
+        (h as f32 * ratio).round() as u32,
+    )
+}
+
+/// Read the RGBA pixel data of a loaded image by drawing it to a canvas.
+pub fn image_pixels(image: &HtmlImageElement) -> Res<Vec<u8>> {
+    let (w, h) = (image.natural_width(), image.natural_height());
+    let canvas = Element::try_new("canvas")?
+        .raw()
+        .unchecked_into::<HtmlCanvasElement>();
+    canvas.set_width(w);
+    canvas.set_height(h);
+
+    let context = match canvas.get_context("2d") {
+        Ok(Some(context)) => context.unchecked_into::<CanvasRenderingContext2d>(),
+        _ => return err("Failed to get 2D canvas context."),
+    };
+    context
+        .draw_image_with_html_image_element(image, 0.0, 0.0)
+        .map_err(|e| format!("Failed to draw image: {e:?}"))?;
+    context
+        .get_image_data(0.0, 0.0, w as f64, h as f64)
+        .map(|data| data.data().0)
+        .map_err(|e| format!("Failed to read image data: {e:?}"))
+}
+
 fn create_file_upload() -> Res<HtmlInputElement> {
     let element = Element::try_new("input")?;
 
     element.set_attr("type", "file");
     element.set_attr("accept", "image/*");
+    element.set_attr("multiple", "true");
 
     element
         .raw()
@@ -657,6 +962,16 @@ pub fn redirect_to(url: &str) {
     }
 }
 
+/// Ask the user for a line of text using the browser's prompt dialog. Returns
+/// None if the dialog was cancelled.
+pub fn prompt(message: &str, default: &str) -> Option<String> {
+    window()
+        .ok()?
+        .prompt_with_message_and_default(message, default)
+        .ok()
+        .flatten()
+}
+
 pub fn game_over_redirect() {
     const HREF: &str = "/game_over";
     redirect_to(HREF);
@@ -666,6 +981,23 @@ pub fn timestamp_ms() -> u64 {
     js_sys::Date::new_0().get_time() as u64
 }
 
+/// Offer `bytes` to the user as a PNG file download named `filename`.
+pub fn download_png(bytes: &[u8], filename: &str) -> Res<()> {
+    let array = Array::new();
+    array.push(&Uint8Array::from(bytes));
+
+    let mut options = BlobPropertyBag::new();
+    options.type_("image/png");
+    let blob = Blob::new_with_u8_array_sequence_and_options(&array, &options).map_err(js_err)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(js_err)?;
+
+    Element::anchor()
+        .with_attrs(&[("href", &url), ("download", filename)])
+        .click();
+
+    Url::revoke_object_url(&url).map_err(js_err)
+}
+
 fn js_err(v: JsValue) -> String {
     if let Some(s) = v.as_string() {
         s
@@ -762,7 +1094,12 @@ pub fn save_project(project: &Project, active_scene: &str) -> Res<ReqState> {
         loading.set_attr("title", "Saving scene");
     }
 
+    // Saves carry an idempotency key so that a retried save isn't applied
+    // twice by the server.
     let headers = headers()?;
+    headers
+        .set("X-Idempotency-Key", &uuid::Uuid::now_v7().to_string())
+        .map_err(js_err)?;
     let mut init = RequestInit::new();
     init.method(METHOD)
         .headers(&headers)
@@ -770,13 +1107,18 @@ pub fn save_project(project: &Project, active_scene: &str) -> Res<ReqState> {
     let req = Request::new_with_str_and_init(PATH, &init).map_err(js_err)?;
     let promise = window()?.fetch_with_request(&req);
 
-    upload_thumbnail(active_scene);
-
+    let project_uuid = project.uuid.simple().to_string();
+    let scene_uuid = active_scene.to_string();
     Ok(ReqState::basic(
-        |resp: JsValue| {
+        move |resp: JsValue| {
+            let ok = resp.unchecked_into::<Response>().ok();
+            if ok {
+                upload_thumbnail(&project_uuid, &scene_uuid);
+            }
+
             if let Some(loading) = Element::by_id("canvas_loading_icon") {
                 loading.remove_class("loading-loading");
-                if resp.unchecked_into::<Response>().ok() {
+                if ok {
                     loading.hide();
                 } else {
                     loading.add_class("loading-error");
@@ -795,6 +1137,13 @@ pub fn save_project(project: &Project, active_scene: &str) -> Res<ReqState> {
     ))
 }
 
+/// Whether this page is a read-only view of a shared scene.
+pub fn is_share_page() -> bool {
+    window()
+        .and_then(|w| w.location().pathname().map_err(js_err))
+        .is_ok_and(|path| path.trim_start_matches('/').starts_with("share/"))
+}
+
 fn project_save_url() -> Res<Option<String>> {
     let path = window()?.location().pathname().map_err(js_err)?;
     match path
@@ -804,6 +1153,7 @@ fn project_save_url() -> Res<Option<String>> {
         .as_slice()
     {
         ["project", uuid, "edit"] => Ok(Some(format!("/api/project/{uuid}/save"))),
+        ["share", token] => Ok(Some(format!("/api/share/{token}"))),
         _ => Ok(None),
     }
 }
@@ -846,3 +1196,233 @@ pub fn load_project(vp: crate::start::VpRef) -> Res<()> {
         Err(_) => err("Failed to lock viewport to set request state."),
     }
 }
+
+/// UUID of the project being edited, if this is a project editing page.
+fn project_page_uuid() -> Res<Option<String>> {
+    let path = window()?.location().pathname().map_err(js_err)?;
+    match path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        ["project", uuid, "edit"] => Ok(Some(uuid.to_string())),
+        _ => Ok(None),
+    }
+}
+
+const SPRITE_TEMPLATES_PATH: &str = "/api/user/sprite-templates";
+
+/// Fetch the user's sprite templates, including those shared with the
+/// project being edited.
+pub fn load_sprite_templates(vp: crate::start::VpRef) -> Res<()> {
+    const METHOD: &str = "GET";
+
+    #[derive(serde::Deserialize)]
+    struct TemplateInfo {
+        template: scene::SpriteTmpl,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Resp {
+        templates: Vec<TemplateInfo>,
+    }
+
+    if is_share_page() {
+        return Ok(());
+    }
+
+    let path = match project_page_uuid()? {
+        Some(uuid) => format!("{SPRITE_TEMPLATES_PATH}?project={uuid}"),
+        None => SPRITE_TEMPLATES_PATH.to_string(),
+    };
+
+    let headers = headers()?;
+    let mut init = RequestInit::new();
+    init.method(METHOD).headers(&headers);
+    let req = Request::new_with_str_and_init(&path, &init).map_err(js_err)?;
+    let promise = window()?.fetch_with_request(&req);
+
+    let cb_vp = vp.clone();
+    let state = ReqState::body(
+        move |buf: JsValue| {
+            let bytes = Uint8Array::new(&buf).to_vec();
+            match serde_json::from_slice::<Resp>(&bytes) {
+                Ok(resp) => match cb_vp.lock() {
+                    Ok(mut vp) => vp.set_sprite_templates(
+                        resp.templates.into_iter().map(|t| t.template).collect(),
+                    ),
+                    Err(_) => console_err("Failed to lock viewport to update templates."),
+                },
+                Err(e) => console_err(&format!("Failed to decode sprite templates: {e}")),
+            }
+        },
+        |err| console_err(&js_err(err)),
+        promise,
+    );
+
+    match vp.lock() {
+        Ok(mut vp) => {
+            vp.set_templates_state(state);
+            Ok(())
+        }
+        Err(_) => err("Failed to lock viewport to set request state."),
+    }
+}
+
+/// Save a sprite template, shared with `project` if given.
+pub fn save_sprite_template(
+    template: &scene::SpriteTmpl,
+    project: Option<String>,
+) -> Res<ReqState> {
+    const METHOD: &str = "POST";
+
+    #[derive(serde::Serialize)]
+    struct Req<'a> {
+        template: &'a scene::SpriteTmpl,
+        project: Option<String>,
+    }
+
+    let body = serde_json::to_string(&Req { template, project }).map_err(|e| e.to_string())?;
+    let headers = headers()?;
+    let mut init = RequestInit::new();
+    init.method(METHOD)
+        .headers(&headers)
+        .body(Some(&JsValue::from_str(&body)));
+    let req = Request::new_with_str_and_init(SPRITE_TEMPLATES_PATH, &init).map_err(js_err)?;
+    let promise = window()?.fetch_with_request(&req);
+
+    Ok(ReqState::basic(
+        |resp: JsValue| {
+            if !resp.unchecked_into::<Response>().ok() {
+                console_err("Failed to save sprite template.");
+            }
+        },
+        |err| console_err(&format!("Network error saving template: {}", js_err(err))),
+        promise,
+    ))
+}
+
+const PALETTES_PATH: &str = "/api/user/palettes";
+
+/// Fetch the user's saved colour palettes.
+pub fn load_palettes(vp: crate::start::VpRef) -> Res<()> {
+    const METHOD: &str = "GET";
+
+    #[derive(serde::Deserialize)]
+    struct Resp {
+        palettes: Vec<scene::ColourPalette>,
+    }
+
+    if is_share_page() {
+        return Ok(());
+    }
+
+    let headers = headers()?;
+    let mut init = RequestInit::new();
+    init.method(METHOD).headers(&headers);
+    let req = Request::new_with_str_and_init(PALETTES_PATH, &init).map_err(js_err)?;
+    let promise = window()?.fetch_with_request(&req);
+
+    let cb_vp = vp.clone();
+    let state = ReqState::body(
+        move |buf: JsValue| {
+            let bytes = Uint8Array::new(&buf).to_vec();
+            match serde_json::from_slice::<Resp>(&bytes) {
+                Ok(resp) => match cb_vp.lock() {
+                    Ok(mut vp) => vp.set_palettes(resp.palettes),
+                    Err(_) => console_err("Failed to lock viewport to update palettes."),
+                },
+                Err(e) => console_err(&format!("Failed to decode palettes: {e}")),
+            }
+        },
+        |err| console_err(&js_err(err)),
+        promise,
+    );
+
+    match vp.lock() {
+        Ok(mut vp) => {
+            vp.set_palettes_state(state);
+            Ok(())
+        }
+        Err(_) => err("Failed to lock viewport to set request state."),
+    }
+}
+
+/// Local storage key holding the time at which the session cookie was set, in
+/// milliseconds. Also set by the login page.
+pub const SESSION_START_KEY: &str = "session_start";
+
+/// Replace the session with a new one, recording the time on success.
+pub fn refresh_session() -> Res<ReqState> {
+    const METHOD: &str = "PUT";
+    const PATH: &str = "/api/auth/refresh";
+
+    let headers = headers()?;
+    let mut init = RequestInit::new();
+    init.method(METHOD).headers(&headers);
+    let req = Request::new_with_str_and_init(PATH, &init).map_err(js_err)?;
+    let promise = window()?.fetch_with_request(&req);
+
+    Ok(ReqState::basic(
+        |resp: JsValue| {
+            if resp.unchecked_into::<Response>().ok() {
+                local_storage_set(SESSION_START_KEY, &timestamp_ms().to_string()).ok();
+            }
+        },
+        |err| {
+            console_err(&format!(
+                "Network error refreshing session: {}",
+                js_err(err)
+            ))
+        },
+        promise,
+    ))
+}
+
+/// Save a colour palette, replacing any of the user's palettes with the same
+/// name.
+pub fn save_palette(palette: &scene::ColourPalette) -> Res<ReqState> {
+    const METHOD: &str = "POST";
+
+    let body = serde_json::to_string(palette).map_err(|e| e.to_string())?;
+    let headers = headers()?;
+    let mut init = RequestInit::new();
+    init.method(METHOD)
+        .headers(&headers)
+        .body(Some(&JsValue::from_str(&body)));
+    let req = Request::new_with_str_and_init(PALETTES_PATH, &init).map_err(js_err)?;
+    let promise = window()?.fetch_with_request(&req);
+
+    Ok(ReqState::basic(
+        |resp: JsValue| {
+            if !resp.unchecked_into::<Response>().ok() {
+                console_err("Failed to save palette.");
+            }
+        },
+        |err| console_err(&format!("Network error saving palette: {}", js_err(err))),
+        promise,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::DeepLink;
+
+    #[test]
+    fn test_deep_link() {
+        let scene = uuid::Uuid::from_u128(2374);
+        let link = DeepLink::parse(&format!(
+            "?role=player&scene={}&vx=10.5&vy=20.3&gs=64",
+            scene.simple()
+        ));
+        assert_eq!(link.scene, Some(scene));
+        assert_eq!(link.at, Some(scene::Point::new(10.5, 20.3)));
+        assert_eq!(link.grid_size, Some(64.0));
+
+        // Invalid parameters are ignored, and a position needs both axes.
+        let link = DeepLink::parse("scene=arena&vx=1&vy=inf&gs=-2");
+        assert_eq!(link, DeepLink::default());
+        assert_eq!(DeepLink::parse(""), DeepLink::default());
+    }
+}
@@ -1,13 +1,15 @@
+use std::cell::Cell;
 use std::rc::Rc;
 use std::sync::Mutex;
 
-use bincode::{deserialize, serialize};
 use js_sys::{ArrayBuffer, Uint8Array};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{CloseEvent, ErrorEvent, MessageEvent, WebSocket};
 
-use crate::bridge::{console_log, log, log_js_value, timestamp_ms, websocket_url};
-use crate::scene::comms::{ClientEvent, ClientMessage, ServerEvent};
+use crate::bridge::{console_log, log, log_js_value, timestamp_ms, websocket_url, DeepLink};
+use crate::scene::comms::{
+    decode_message, encode_message, ClientEvent, ClientMessage, ServerEvent,
+};
 use crate::Res;
 
 pub struct Client {
@@ -30,9 +32,10 @@ impl Client {
     /// If the page URL is /game/GAME_KEY/client/CLIENT_KEY, this will attempt
     /// to connect to the appropriate game websocket. If the URL doesn't match
     /// will return Ok(None). On successfully connection returns
-    /// Ok(Some(Client)) on a failed connection returns Err.
-    pub fn new() -> Res<Option<Client>> {
-        let (url, game_key) = match websocket_url() {
+    /// Ok(Some(Client)), along with the scene and view linked to by the URL,
+    /// on a failed connection returns Err.
+    pub fn new() -> Res<Option<(Client, DeepLink)>> {
+        let (url, game_key, link) = match websocket_url() {
             Ok(Some(val)) => val,
             _ => return Ok(None),
         };
@@ -40,11 +43,14 @@ impl Client {
         let incoming_events = Rc::new(Mutex::new(Vec::new()));
         let sock = Sock::new(url, incoming_events)?;
 
-        Ok(Some(Client {
-            sock,
-            counter: 0,
-            game_key,
-        }))
+        Ok(Some((
+            Client {
+                sock,
+                counter: 0,
+                game_key,
+            },
+            link,
+        )))
     }
 
     // Returns vector of events ordered from newest to oldest.
@@ -90,6 +96,12 @@ impl Client {
 type SockRef = Rc<Mutex<WebSocket>>;
 type EventsRef = Rc<Mutex<Vec<ServerEvent>>>;
 
+/// Sequence number of the last journalled event received from the server.
+type SequenceRef = Rc<Cell<Option<u64>>>;
+
+/// Time at which the last message was received from the server.
+type ReceivedRef = Rc<Cell<u64>>;
+
 // https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/readyState
 #[derive(Debug, Eq, PartialEq)]
 enum ReadyState {
@@ -110,6 +122,8 @@ struct Sock {
     url: String,
     socket: WebSocket,
     events: EventsRef,
+    sequence: SequenceRef,
+    last_received: ReceivedRef,
     reconnect_attempts: u32,
     last_connected: u64,
     last_reconnect_attempt: u64,
@@ -120,12 +134,26 @@ impl Sock {
     const BACKOFF_COEFFICIENT: u32 = 2;
     const RECONNECT_MAX_DURATION_MS: u64 = 60 * 1000;
 
+    /// The server sends health checks to quiet clients, so if nothing has
+    /// been received in this time the connection has likely dropped.
+    const SILENCE_TIMEOUT_MS: u64 = 45 * 1000;
+
     fn new(url: String, events: EventsRef) -> Res<Self> {
-        let socket = create_websocket(&url, events.clone())?;
+        let sequence = Rc::new(Cell::new(None));
+        let last_received = Rc::new(Cell::new(timestamp_ms()));
+        let socket = create_websocket(
+            &url,
+            events.clone(),
+            sequence.clone(),
+            last_received.clone(),
+            None,
+        )?;
         Ok(Self {
             url,
             socket,
             events,
+            sequence,
+            last_received,
             reconnect_attempts: 0,
             last_connected: timestamp_ms(),
             last_reconnect_attempt: 0,
@@ -152,7 +180,7 @@ impl Sock {
             return;
         }
 
-        if let Ok(data) = serialize(message) {
+        if let Ok(data) = encode_message(message) {
             if let Err(v) = self.socket.send_with_u8_array(&data) {
                 console_log("Failed to send event. Reason:");
                 log_js_value(&v);
@@ -177,6 +205,10 @@ impl Sock {
         match ready_state {
             ReadyState::Open => {
                 self.last_connected = now_ms;
+                if now_ms.saturating_sub(self.last_received.get()) > Self::SILENCE_TIMEOUT_MS {
+                    console_log("No messages received recently, reconnecting.");
+                    self.connect();
+                }
             }
             ReadyState::Closed => {
                 let since_last_reconnect = now_ms.saturating_sub(self.last_reconnect_attempt);
@@ -222,9 +254,25 @@ impl Sock {
             self.reconnect_attempts
         );
 
-        if let Ok(socket) = create_websocket(&self.url, self.events.clone()) {
+        // If we've received a sequence number, the server can send just the
+        // events we missed rather than the whole game state.
+        let sequence = self.sequence.get();
+        let url = match sequence {
+            Some(_) if self.url.contains('?') => format!("{}&reconnect=true", self.url),
+            Some(_) => format!("{}?reconnect=true", self.url),
+            None => self.url.clone(),
+        };
+
+        if let Ok(socket) = create_websocket(
+            &url,
+            self.events.clone(),
+            self.sequence.clone(),
+            self.last_received.clone(),
+            sequence,
+        ) {
             // Close existing socket.
             self.socket.close().ok();
+            self.last_received.set(timestamp_ms());
 
             // Set new socket.
             self.socket = socket;
@@ -239,14 +287,31 @@ impl Sock {
 
 fn deserialise_message(message: JsValue) -> Res<ServerEvent> {
     match message.dyn_into::<ArrayBuffer>() {
-        Ok(b) => Ok(deserialize(&Uint8Array::new(&b).to_vec()).map_err(|e| e.to_string())?),
+        Ok(b) => decode_message(&Uint8Array::new(&b).to_vec()),
         Err(e) => Err(format!(
             "WebSocket message could not be cast to ArrayBuffer: {e:?}."
         )),
     }
 }
 
-fn create_websocket(url: &str, events: EventsRef) -> Res<WebSocket> {
+/// Record the sequence number of the latest journalled event in `event`.
+fn track_sequence(event: &ServerEvent, sequence: &SequenceRef) {
+    match event {
+        ServerEvent::Sequence(n) => sequence.set(Some(*n)),
+        ServerEvent::EventSet(events) => events.iter().for_each(|e| track_sequence(e, sequence)),
+        _ => {}
+    }
+}
+
+/// Open a WebSocket to `url`. If `reconnect` is given, the socket will send
+/// `ClientEvent::Reconnect` once open to catch up on events since then.
+fn create_websocket(
+    url: &str,
+    events: EventsRef,
+    sequence: SequenceRef,
+    last_received: ReceivedRef,
+    reconnect: Option<u64>,
+) -> Res<WebSocket> {
     log!("Connecting WebSocket.");
 
     let ws = match WebSocket::new(url) {
@@ -259,19 +324,42 @@ fn create_websocket(url: &str, events: EventsRef) -> Res<WebSocket> {
     // https://rustwasm.github.io/wasm-bindgen/examples/websockets.html
     ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
 
-    let onmessage =
-        Closure::wrap(
-            Box::new(move |e: MessageEvent| match deserialise_message(e.data()) {
-                Ok(event) => match events.try_lock() {
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        last_received.set(timestamp_ms());
+        match deserialise_message(e.data()) {
+            Ok(event) => {
+                track_sequence(&event, &sequence);
+                match events.try_lock() {
                     Ok(mut lock) => lock.push(event),
                     Err(_) => console_log("Failed to lock events."),
-                },
-                Err(s) => log!("WebSocket decode error: {s}"),
-            }) as Box<dyn FnMut(MessageEvent)>,
-        );
+                }
+            }
+            Err(s) => log!("WebSocket decode error: {s}"),
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
     ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
     onmessage.forget();
 
+    if let Some(last_seen) = reconnect {
+        let sock = ws.clone();
+        let onopen = Closure::wrap(Box::new(move |_: JsValue| {
+            let message = ClientMessage {
+                id: 0,
+                event: ClientEvent::Reconnect(last_seen),
+            };
+            match encode_message(&message) {
+                Ok(data) => {
+                    if sock.send_with_u8_array(&data).is_err() {
+                        console_log("Failed to send reconnect event.");
+                    }
+                }
+                Err(_) => console_log("Failed to serialise reconnect event."),
+            }
+        }) as Box<dyn FnMut(JsValue)>);
+        ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        onopen.forget();
+    }
+
     let onerror = Closure::wrap(Box::new(move |e: ErrorEvent| {
         log!("Closed due to error: {:?}", e.as_string());
     }) as Box<dyn FnMut(ErrorEvent)>);
@@ -0,0 +1,71 @@
+use std::{cell::Cell, rc::Rc};
+
+use scene::Point;
+use wasm_bindgen::JsCast;
+
+use super::element::Element;
+use crate::{bridge::console_log, start::VpRef, viewport::ViewportPoint};
+
+/// Inline text input shown over the canvas where a text annotation is being
+/// placed. Pressing enter places the annotation, escape cancels.
+pub struct AnnotationInput {
+    input: Element,
+    at: Rc<Cell<Point>>,
+}
+
+impl AnnotationInput {
+    pub fn new(vp: VpRef) -> Self {
+        let mut input = Element::new("input")
+            .with_classes(&["annotation-input", "form-control", "form-control-sm"])
+            .with_attrs(&[
+                ("type", "text"),
+                ("maxlength", &scene::Annotation::MAX_LENGTH.to_string()),
+                ("placeholder", "Label"),
+            ]);
+        let at = Rc::new(Cell::new(Point::ORIGIN));
+
+        let input_ref = input.clone();
+        let at_ref = at.clone();
+        input.set_onkeydown(Box::new(move |event| {
+            match event
+                .unchecked_ref::<web_sys::KeyboardEvent>()
+                .key()
+                .as_str()
+            {
+                "Enter" => {
+                    let text = input_ref.value_string();
+                    if let Ok(mut lock) = vp.try_lock() {
+                        lock.annotate(at_ref.get(), text);
+                    } else {
+                        console_log("Failed to lock viewport to place annotation.");
+                        return;
+                    }
+                }
+                "Escape" => {}
+                _ => return,
+            }
+
+            input_ref.clear_value();
+            input_ref.hide();
+        }));
+
+        input.hide();
+        if let Some(root) = Element::by_id("canvas_text") {
+            root.append_child(&input);
+        } else {
+            input.add_to_page();
+        }
+
+        Self { input, at }
+    }
+
+    /// Show the input at viewport position `at`, to place an annotation at
+    /// scene position `scene_at`.
+    pub fn open(&self, at: ViewportPoint, scene_at: Point) {
+        self.at.set(scene_at);
+        self.input.clear_value();
+        self.input.set_pos(at);
+        self.input.show();
+        self.input.focus();
+    }
+}
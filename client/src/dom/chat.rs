@@ -0,0 +1,153 @@
+use wasm_bindgen::JsCast;
+
+use super::{element::Element, icon::Icon};
+use crate::{bridge::console_log, start::VpRef};
+
+/// An entry in the chat log.
+pub enum ChatEntry {
+    Dice(String, String),                         // (name, result)
+    Message(String, String, u64, Option<String>), // (name, text, timestamp_s, avatar)
+}
+
+/// Collapsible overlay with a scrolling log of chat messages and dice rolls
+/// made in the game. Text entered in the input is sent as a chat message, or
+/// rolled as dice if prefixed with `/roll` or `/r`.
+pub struct ChatLog {
+    root: Element,
+    log: Element,
+}
+
+impl ChatLog {
+    pub fn new(vp: VpRef) -> Self {
+        let root = Element::default().with_class("chat-log");
+
+        let header = root
+            .child("div")
+            .with_classes(&["d-flex", "justify-content-between", "mb-1"]);
+        header.child("h6").with_class("mb-0").set_text("Chat");
+        let mut collapse = header
+            .child("button")
+            .with_classes(&["btn", "btn-sm", "py-0"])
+            .with_attr("type", "button");
+        let icon = collapse.icon(Icon::Down);
+
+        let body = root.child("div");
+        let log = body
+            .child("div")
+            .with_classes(&["chat-log-entries", "mb-1"]);
+
+        let body_ref = body.clone();
+        collapse.set_onclick(Box::new(move |_| {
+            let collapsed = body_ref.has_class("d-none");
+            if collapsed {
+                body_ref.show();
+            } else {
+                body_ref.hide();
+            }
+
+            let (from, to) = if collapsed {
+                (Icon::Up, Icon::Down)
+            } else {
+                (Icon::Down, Icon::Up)
+            };
+            icon.remove_class(&from.class());
+            icon.add_class(&to.class());
+        }));
+
+        let group = body
+            .child("div")
+            .with_classes(&["input-group", "input-group-sm"]);
+        let mut input = group
+            .child("input")
+            .with_class("form-control")
+            .with_attrs(&[
+                ("type", "text"),
+                ("maxlength", "512"),
+                ("placeholder", "Message, or /roll 2d6+3"),
+            ]);
+
+        let input_ref = input.clone();
+        let vp_ref = vp.clone();
+        input.set_onkeydown(Box::new(move |event| {
+            if event.unchecked_ref::<web_sys::KeyboardEvent>().key() == "Enter" {
+                Self::submit(&vp_ref, &input_ref);
+            }
+        }));
+
+        let mut submit = group
+            .child("button")
+            .with_classes(&["btn", "btn-sm", "btn-outline-primary"])
+            .with_attr("type", "button");
+        submit.append_child(&Icon::Plus.element());
+        submit.set_onclick(Box::new(move |_| Self::submit(&vp, &input)));
+
+        root.hide();
+        root.add_to_page();
+
+        Self { root, log }
+    }
+
+    fn submit(vp: &VpRef, input: &Element) {
+        let text = input.value_string();
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+
+        let Ok(mut lock) = vp.try_lock() else {
+            console_log("Failed to lock viewport to send chat message.");
+            return;
+        };
+
+        let notation = text
+            .strip_prefix("/roll ")
+            .or_else(|| text.strip_prefix("/r "));
+        if let Some(notation) = notation {
+            lock.int.roll_dice(notation.to_string());
+        } else {
+            lock.int.send_chat(text.to_string());
+        }
+        input.clear_value();
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        if visible {
+            self.root.show();
+        } else {
+            self.root.hide();
+        }
+    }
+
+    /// Append entries to the log, scrolling to show them.
+    pub fn append(&self, entries: &[ChatEntry]) {
+        for entry in entries {
+            let el = self.log.child("div");
+            match entry {
+                ChatEntry::Dice(username, result) => {
+                    el.add_class("fst-italic");
+                    el.child("strong").set_text(username);
+                    el.child("span").set_text(&format!(" {result}"));
+                }
+                ChatEntry::Message(username, text, timestamp, avatar) => {
+                    el.child("small")
+                        .with_class("text-body-secondary")
+                        .set_text(&format!("{} ", format_time(*timestamp)));
+                    if let Some(key) = avatar {
+                        el.child("img")
+                            .with_classes(&["chat-avatar", "rounded-circle", "me-1"])
+                            .with_attr("src", &format!("/api/media/{key}/file"));
+                    }
+                    el.child("strong").set_text(&format!("{username}:"));
+                    el.child("span").set_text(&format!(" {text}"));
+                }
+            }
+        }
+        self.log.scroll_to_bottom();
+    }
+}
+
+/// Format a UNIX timestamp in seconds as a local `HH:MM` time.
+fn format_time(timestamp: u64) -> String {
+    let date = js_sys::Date::new(&((timestamp * 1000) as f64).into());
+    format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
@@ -0,0 +1,93 @@
+use std::{cell::Cell, rc::Rc};
+
+use super::element::Element;
+use crate::{bridge::console_log, start::VpRef, viewport::ViewportPoint};
+
+/// Popover showing a scene comment, opened by clicking its marker.
+pub struct CommentPopover {
+    root: Element,
+    author: Element,
+    time: Element,
+    text: Element,
+    index: Rc<Cell<usize>>,
+}
+
+impl CommentPopover {
+    pub fn new(vp: VpRef) -> Self {
+        let root = Element::default().with_class("comment-popover");
+        let index = Rc::new(Cell::new(0));
+
+        let header = root
+            .child("div")
+            .with_classes(&["d-flex", "justify-content-between", "mb-1"]);
+        let title = header.child("div");
+        let author = title.child("strong");
+        let time = title.child("small").with_classes(&["text-muted", "ms-1"]);
+        let mut close = header
+            .child("button")
+            .with_class("btn-close")
+            .with_attrs(&[("type", "button"), ("aria-label", "Close")]);
+        let root_ref = root.clone();
+        close.set_onclick(Box::new(move |_| root_ref.hide()));
+
+        let text = root.child("p").with_class("mb-1");
+
+        let mut delete = root
+            .child("button")
+            .with_classes(&["btn", "btn-sm", "btn-outline-danger"])
+            .with_attr("type", "button")
+            .with_text("Delete");
+        let root_ref = root.clone();
+        let index_ref = index.clone();
+        delete.set_onclick(Box::new(move |_| {
+            if let Ok(mut lock) = vp.try_lock() {
+                lock.int.remove_comment(index_ref.get());
+                root_ref.hide();
+            } else {
+                console_log("Failed to lock viewport to delete comment.");
+            }
+        }));
+
+        root.hide();
+        if let Some(parent) = Element::by_id("canvas_text") {
+            parent.append_child(&root);
+        } else {
+            root.add_to_page();
+        }
+
+        Self {
+            root,
+            author,
+            time,
+            text,
+            index,
+        }
+    }
+
+    /// Show the comment at `index` in the scene at viewport position `at`.
+    pub fn open(&self, at: ViewportPoint, index: usize, comment: &scene::Comment) {
+        self.index.set(index);
+        self.author.set_text(&comment.author);
+        self.time.set_text(&format_date(comment.timestamp));
+        self.text.set_text(&comment.text);
+        self.root.set_pos(at);
+        self.root.show();
+    }
+
+    pub fn close(&self) {
+        self.root.hide();
+    }
+}
+
+/// Format a UNIX timestamp in seconds as a local date and time.
+fn format_date(timestamp: u64) -> String {
+    let date = js_sys::Date::new(&((timestamp * 1000) as f64).into());
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date(),
+        date.get_hours(),
+        date.get_minutes()
+    )
+}
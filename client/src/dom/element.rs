@@ -176,10 +176,18 @@ impl Element {
         self.add_event_listener("click", handler);
     }
 
+    pub fn set_onkeydown(&mut self, handler: Box<dyn FnMut(web_sys::Event)>) {
+        self.add_event_listener("keydown", handler);
+    }
+
     pub fn set_oninput(&mut self, handler: Box<dyn FnMut(web_sys::Event)>) {
         self.add_event_listener("input", handler);
     }
 
+    pub fn set_onchange(&mut self, handler: Box<dyn FnMut(web_sys::Event)>) {
+        self.add_event_listener("change", handler);
+    }
+
     pub fn set_pos(&self, pos: ViewportPoint) {
         self.set_css("left", &format!("{}px", pos.x));
         self.set_css("top", &format!("{}px", pos.y));
@@ -225,6 +233,10 @@ impl Element {
         self.set_inner_html("");
     }
 
+    pub fn scroll_to_bottom(&self) {
+        self.element.set_scroll_top(self.element.scroll_height());
+    }
+
     /// Replace body of this element (assumed a select input) with a list of
     /// option elements specified by the (label, value) pairs provided.
     pub fn set_options<T: AsRef<str>>(&self, options: &[(T, T)]) {
@@ -244,6 +256,10 @@ impl Element {
         self.element.click();
     }
 
+    pub fn focus(&self) {
+        self.element.focus().ok();
+    }
+
     pub fn event(&self, event: &str) {
         if let Ok(event) = web_sys::Event::new(event) {
             self.element.dispatch_event(&event).ok();
@@ -0,0 +1,54 @@
+use super::element::Element;
+use crate::{bridge::console_log, start::VpRef};
+
+/// Overlay showing an image which an editor has handed out to players.
+pub struct HandoutView {
+    root: Element,
+    title: Element,
+    image: Element,
+}
+
+impl HandoutView {
+    pub fn new(vp: VpRef) -> Self {
+        let root = Element::default().with_class("handout");
+
+        let header = root
+            .child("div")
+            .with_classes(&["d-flex", "justify-content-between", "mb-1"]);
+        let title = header.child("h6").with_class("mb-0");
+        let mut close = header
+            .child("button")
+            .with_class("btn-close")
+            .with_attrs(&[("type", "button"), ("aria-label", "Close")]);
+        close.set_onclick(Box::new(move |_| {
+            if let Ok(mut lock) = vp.try_lock() {
+                lock.int.close_handout();
+            } else {
+                console_log("Failed to lock viewport to close handout.");
+            }
+        }));
+
+        let image = root
+            .child("img")
+            .with_classes(&["handout-image", "rounded"])
+            .with_attr("alt", "Handout");
+
+        root.hide();
+        root.add_to_page();
+
+        Self { root, title, image }
+    }
+
+    /// Show the handout with `media_key` from `sender`, or hide the overlay
+    /// if None.
+    pub fn update(&self, handout: Option<&(String, String)>) {
+        if let Some((media_key, sender)) = handout {
+            self.title.set_text(&format!("Handout from {sender}"));
+            self.image
+                .set_attr("src", format!("/api/media/{media_key}/file"));
+            self.root.show();
+        } else {
+            self.root.hide();
+        }
+    }
+}
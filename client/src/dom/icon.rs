@@ -5,23 +5,33 @@ pub enum Icon {
     Arrows,
     Brush,
     Circle,
+    Comment,
     Cursor,
     Down,
     Edit,
+    Eraser,
     Eye,
     EyeSlash,
     Fog,
+    Invert,
+    Light,
     Line,
     Lock,
+    Map,
     Ok,
+    Person,
     Plus,
     PlusSquare,
+    Ruler,
     Square,
     Target,
+    Template,
+    Text,
     Trash,
     Triangle,
     Unlock,
     Up,
+    ZoomIn,
 }
 
 impl Icon {
@@ -30,23 +40,33 @@ impl Icon {
             Icon::Arrows => "arrows-move",
             Icon::Brush => "brush",
             Icon::Circle => "circle",
+            Icon::Comment => "chat-left-text-fill",
             Icon::Cursor => "cursor",
             Icon::Down => "chevron-down",
             Icon::Edit => "pencil-square",
+            Icon::Eraser => "eraser",
             Icon::Eye => "eye",
             Icon::EyeSlash => "eye-slash",
             Icon::Fog => "cloud-fog2",
+            Icon::Invert => "subtract",
+            Icon::Light => "lightbulb",
             Icon::Line => "slash-lg",
             Icon::Lock => "lock",
+            Icon::Map => "map",
             Icon::Ok => "check-circle",
+            Icon::Person => "person-circle",
             Icon::Plus => "plus",
             Icon::PlusSquare => "plus-square",
+            Icon::Ruler => "rulers",
             Icon::Square => "square",
             Icon::Target => "bullseye",
+            Icon::Template => "magic",
+            Icon::Text => "fonts",
             Icon::Trash => "trash3",
             Icon::Triangle => "triangle",
             Icon::Unlock => "unlock",
             Icon::Up => "chevron-up",
+            Icon::ZoomIn => "zoom-in",
         };
         format!("bi-{suf}")
     }
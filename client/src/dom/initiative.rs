@@ -0,0 +1,98 @@
+use super::{element::Element, icon::Icon};
+use crate::{bridge::console_log, start::VpRef};
+
+/// Overlay showing the initiative order for the game, with inputs to submit a
+/// roll for a character.
+pub struct InitiativeTracker {
+    root: Element,
+    list: Element,
+    clear: Element,
+}
+
+impl InitiativeTracker {
+    pub fn new(vp: VpRef) -> Self {
+        let root = Element::default().with_class("initiative-tracker");
+        root.child("h6").with_class("mb-1").set_text("Initiative");
+
+        let list = root.child("ol").with_classes(&["mb-1", "ps-3"]);
+
+        let group = root
+            .child("div")
+            .with_classes(&["input-group", "input-group-sm"]);
+        let name = group
+            .child("input")
+            .with_class("form-control")
+            .with_attrs(&[
+                ("type", "text"),
+                ("maxlength", "64"),
+                ("placeholder", "Name"),
+            ]);
+        let roll = group
+            .child("input")
+            .with_class("form-control")
+            .with_attrs(&[("type", "number"), ("step", "1"), ("placeholder", "Roll")]);
+
+        let mut submit = Self::button(&group, Icon::Plus);
+        let vp_ref = vp.clone();
+        submit.set_onclick(Box::new(move |_| {
+            let title = name.value_string();
+            if title.is_empty() {
+                return;
+            }
+
+            if let Ok(mut lock) = vp_ref.try_lock() {
+                lock.int.set_initiative(title, roll.value_float() as i32);
+                roll.clear_value();
+            } else {
+                console_log("Failed to lock viewport to set initiative.");
+            }
+        }));
+
+        let mut clear = Self::button(&group, Icon::Trash);
+        clear.set_onclick(Box::new(move |_| {
+            if let Ok(mut lock) = vp.try_lock() {
+                lock.int.clear_initiative();
+            } else {
+                console_log("Failed to lock viewport to clear initiative.");
+            }
+        }));
+
+        root.hide();
+        root.add_to_page();
+
+        Self { root, list, clear }
+    }
+
+    fn button(parent: &Element, icon: Icon) -> Element {
+        let button = parent
+            .child("button")
+            .with_classes(&["btn", "btn-sm", "btn-outline-primary"])
+            .with_attr("type", "button");
+        button.append_child(&icon.element());
+        button
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        if visible {
+            self.root.show();
+        } else {
+            self.root.hide();
+        }
+    }
+
+    /// Only editors may clear the initiative order.
+    pub fn update_role(&self, role: scene::perms::Role) {
+        if role.editor() {
+            self.clear.show();
+        } else {
+            self.clear.hide();
+        }
+    }
+
+    pub fn update(&self, order: &[(String, i32)]) {
+        self.list.clear();
+        for (name, roll) in order {
+            self.list.child("li").set_text(&format!("{name} ({roll})"));
+        }
+    }
+}
@@ -95,6 +95,12 @@ impl InputGroup {
         }
     }
 
+    pub fn set_enabled(&self, key: &str, enabled: bool) {
+        if let Some(e) = self.inputs.get(key) {
+            e.set_enabled(enabled);
+        }
+    }
+
     pub fn set_float(&self, key: &str, value: f32) {
         if let Some(number_input) = self.inputs.get(key) {
             // Round to 2 decimal places for display.
@@ -152,6 +158,32 @@ impl InputGroup {
         }
     }
 
+    /// Replace the swatches in the row added with `add_swatches`. Clicking a
+    /// swatch calls `action` with its colour.
+    pub fn set_swatches<H: ValueHandler<Colour>>(&self, key: &str, colours: &[Colour], action: H) {
+        let Some(row) = self.inputs.get(key) else {
+            return;
+        };
+        row.clear();
+
+        let action_ref = std::rc::Rc::new(action);
+        for &colour in colours {
+            let mut el = button().with_attr("title", &colour_to_hex(colour));
+            el.set_css("background-color", &colour_to_hex(colour));
+            el.set_text("\u{00a0}");
+            let vp_ref = self.vp.clone();
+            let action_ref = action_ref.clone();
+            el.set_onclick(Box::new(move |_| {
+                if let Ok(mut lock) = vp_ref.try_lock() {
+                    action_ref(&mut lock, colour);
+                } else {
+                    console_log("Failed to lock viewport for swatch click.");
+                }
+            }));
+            row.append_child(&el);
+        }
+    }
+
     pub fn add_line(&mut self) {
         self.line = input_group().with_class("mt-1");
         self.root.append_child(&self.line);
@@ -217,6 +249,26 @@ impl InputGroup {
         );
     }
 
+    /// Add a multi-line text input, with the key as a placeholder. The
+    /// action is called when the value is committed, rather than on each
+    /// keystroke.
+    pub fn add_textarea_handler<H: ValueHandler<String>>(&mut self, key: &str, action: H) {
+        let mut el = textarea(key);
+        self.line.append_child(&el);
+        self.add_input(key, el.clone());
+
+        let vp_ref = self.vp.clone();
+        el.set_onchange(Box::new(move |event: web_sys::Event| {
+            if let Some(el) = event.target().map(Element::from) {
+                if let Ok(mut lock) = vp_ref.try_lock() {
+                    action(&mut lock, el.value_string());
+                } else {
+                    console_log("Failed to lock viewport for textarea change.");
+                }
+            }
+        }));
+    }
+
     pub fn add_float(&mut self, key: &str, min: Option<i32>, max: Option<i32>, step: Option<f32>) {
         self.add_entry(key, float(min, max, step));
     }
@@ -233,6 +285,12 @@ impl InputGroup {
         self.set_input_handler(key, move |vp, el| action(vp, el.value_float() as f32));
     }
 
+    /// Add a slider for values between 0 and 1, in increments of `step`.
+    pub fn add_slider_handler<H: ValueHandler<f32>>(&mut self, key: &str, step: f32, action: H) {
+        self.add_entry(key, slider(step));
+        self.set_input_handler(key, move |vp, el| action(vp, el.value_float() as f32));
+    }
+
     pub fn add_select(&mut self, key: &str, options: &[(&str, &str)]) {
         self.add_entry(key, select(options));
     }
@@ -298,9 +356,25 @@ impl InputGroup {
     }
 
     pub fn add_button<H: Handler>(&mut self, icon: Icon, action: H) {
-        let mut el = button();
+        let el = button();
+        el.child("i").with_class(&icon.class());
+        self.add_button_element(el, action);
+    }
+
+    /// Add an icon button with the key as a tooltip, which can be enabled
+    /// and disabled by key.
+    pub fn add_named_button<H: Handler>(&mut self, key: &str, icon: Icon, action: H) {
+        let el = button().with_attr("title", key);
         el.child("i").with_class(&icon.class());
+        self.add_input(key, el.clone());
+        self.add_button_element(el, action);
+    }
+
+    pub fn add_text_button<H: Handler>(&mut self, label: &str, action: H) {
+        self.add_button_element(button().with_text(label), action);
+    }
 
+    fn add_button_element<H: Handler>(&mut self, mut el: Element, action: H) {
         let vp = self.vp.clone();
         el.set_onclick(Box::new(move |_| {
             if let Ok(mut lock) = vp.try_lock() {
@@ -380,6 +454,16 @@ impl InputGroup {
         });
     }
 
+    /// Add a row of colour swatches, populated with `set_swatches`.
+    pub fn add_swatches(&mut self, key: &str) {
+        let el = self
+            .line
+            .child("div")
+            .with_class("btn-group")
+            .with_attr("role", "group");
+        self.add_input(key, el);
+    }
+
     fn icon_radio_input_id(key: &str, icon: Icon) -> String {
         format!("{key}_option_{}", icon.class())
     }
@@ -433,6 +517,19 @@ fn string() -> Element {
         .with_attr("type", "text")
 }
 
+fn textarea(placeholder: &str) -> Element {
+    Element::new("textarea")
+        .with_class("form-control")
+        .with_attrs(&[
+            ("rows", "2"),
+            ("placeholder", placeholder),
+            (
+                "maxlength",
+                &scene::Layer::MAX_DESCRIPTION_LENGTH.to_string(),
+            ),
+        ])
+}
+
 fn float(min: Option<i32>, max: Option<i32>, step: Option<f32>) -> Element {
     let el = Element::input()
         .with_class("form-control")
@@ -453,6 +550,17 @@ fn float(min: Option<i32>, max: Option<i32>, step: Option<f32>) -> Element {
     el
 }
 
+fn slider(step: f32) -> Element {
+    Element::input()
+        .with_classes(&["form-control", "form-range"])
+        .with_attrs(&[
+            ("type", "range"),
+            ("min", "0"),
+            ("max", "1"),
+            ("step", &step.to_string()),
+        ])
+}
+
 fn text(text: &str) -> Element {
     Element::span()
         .with_class("input-group-text")
@@ -4,7 +4,7 @@ use crate::{
     dom::{element::Element, icon::Icon, input::InputGroup},
     interactor::details::SpriteDetails,
     start::VpRef,
-    viewport::DrawTool,
+    viewport::{DrawTool, TemplateShape},
 };
 
 pub struct DrawMenu {
@@ -17,8 +17,19 @@ impl DrawMenu {
     const DRAW_TOOL: &'static str = "draw_tool";
     const CAP_START: &'static str = "Start";
     const CAP_END: &'static str = "End";
+    const TAPER_START: &'static str = "Taper Start";
+    const TAPER_END: &'static str = "Taper End";
     const STROKE: &'static str = "Stroke";
     const SOLID: &'static str = "Solid";
+    const TEMPLATE: &'static str = "Template";
+    const SPRITE_TEMPLATE: &'static str = "Sprite";
+    const PALETTE: &'static str = "Palette";
+    const SWATCHES: &'static str = "swatches";
+    const TEMPLATE_OPTIONS: &'static [(&'static str, &'static str)] = &[
+        ("15ft Cone", "cone"),
+        ("20ft Sphere", "sphere"),
+        ("30ft Line", "line"),
+    ];
 
     pub fn new(vp: VpRef) -> Self {
         let mut inputs = InputGroup::new(vp);
@@ -28,6 +39,15 @@ impl DrawMenu {
 
         inputs.add_line();
 
+        inputs.add_select_handler(Self::PALETTE, &[], |vp, name| vp.set_palette(&name));
+        inputs.add_text_button("Save", |vp| vp.save_palette());
+
+        inputs.add_line();
+
+        inputs.add_swatches(Self::SWATCHES);
+
+        inputs.add_line();
+
         inputs.add_float(Self::STROKE, Some(0), None, Some(0.1));
         inputs.set_float(Self::STROKE, scene::Sprite::DEFAULT_STROKE);
 
@@ -40,6 +60,19 @@ impl DrawMenu {
         inputs.set_string(Self::CAP_START, scene::Cap::DEFAULT_START.to_str());
         inputs.add_select(Self::CAP_END, super::CAP_OPTIONS);
         inputs.set_string(Self::CAP_END, scene::Cap::DEFAULT_END.to_str());
+        inputs.add_checkbox(Self::TAPER_START);
+        inputs.set_bool(Self::TAPER_START, false);
+        inputs.add_checkbox(Self::TAPER_END);
+        inputs.set_bool(Self::TAPER_END, false);
+
+        inputs.add_line();
+
+        inputs.add_select(Self::TEMPLATE, Self::TEMPLATE_OPTIONS);
+
+        inputs.add_line();
+
+        inputs.add_select(Self::SPRITE_TEMPLATE, &[]);
+        inputs.add_button(Icon::PlusSquare, |vp| vp.place_sprite_template());
 
         inputs.add_line();
 
@@ -52,6 +85,11 @@ impl DrawMenu {
                 Icon::Circle,
                 Icon::Target,
                 Icon::Triangle,
+                Icon::Light,
+                Icon::Eraser,
+                Icon::Text,
+                Icon::Ruler,
+                Icon::Template,
             ],
             |vp, icon| {
                 vp.set_draw_tool(match icon {
@@ -61,6 +99,11 @@ impl DrawMenu {
                     Icon::Circle => DrawTool::Ellipse,
                     Icon::Target => DrawTool::Circle,
                     Icon::Triangle => DrawTool::Cone,
+                    Icon::Light => DrawTool::Light,
+                    Icon::Eraser => DrawTool::Erase,
+                    Icon::Text => DrawTool::Text,
+                    Icon::Ruler => DrawTool::Ruler,
+                    Icon::Template => DrawTool::Template(TemplateShape::DEFAULT),
                     _ => DrawTool::Freehand,
                 });
             },
@@ -77,6 +120,47 @@ impl DrawMenu {
         self.inputs.root()
     }
 
+    pub fn set_sprite_templates(&self, templates: &[scene::SpriteTmpl]) {
+        let options: Vec<(String, String)> = templates
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.name.clone(), i.to_string()))
+            .collect();
+        self.inputs.set_options(Self::SPRITE_TEMPLATE, &options);
+    }
+
+    pub fn sprite_template(&self) -> Option<usize> {
+        self.inputs
+            .get_string(Self::SPRITE_TEMPLATE)
+            .and_then(|i| i.parse().ok())
+    }
+
+    pub fn set_palettes(&self, palettes: &[scene::ColourPalette]) {
+        let options: Vec<(&str, &str)> = palettes
+            .iter()
+            .map(|p| (p.name.as_str(), p.name.as_str()))
+            .collect();
+        self.inputs.set_options(Self::PALETTE, &options);
+    }
+
+    /// Show the colours of `palette` as swatches, which set the draw colour
+    /// when clicked.
+    pub fn set_palette(&self, palette: &scene::ColourPalette) {
+        self.inputs.set_string(Self::PALETTE, &palette.name);
+        self.inputs
+            .set_swatches(Self::SWATCHES, &palette.colours, |vp, colour| {
+                vp.set_draw_colour(colour)
+            });
+    }
+
+    pub fn palette(&self) -> Option<String> {
+        self.inputs.get_string(Self::PALETTE)
+    }
+
+    pub fn set_colour(&self, colour: scene::Colour) {
+        self.inputs.set_colour(Self::COLOUR, colour);
+    }
+
     pub fn change_stroke(&self, delta: f32) {
         /// This coefficient is based on scroll delta sizes observed in firefox.
         /// Could maybe be abstracted a bit more.
@@ -97,7 +181,14 @@ impl DrawMenu {
             shape: match self.tool {
                 DrawTool::Circle | DrawTool::Ellipse => Some(scene::Shape::Ellipse),
                 DrawTool::Rectangle => Some(scene::Shape::Rectangle),
-                DrawTool::Cone | DrawTool::Freehand | DrawTool::Line => None,
+                DrawTool::Cone
+                | DrawTool::Erase
+                | DrawTool::Freehand
+                | DrawTool::Light
+                | DrawTool::Line
+                | DrawTool::Ruler
+                | DrawTool::Template(_)
+                | DrawTool::Text => None,
             },
             stroke: self.inputs.get_f32(Self::STROKE),
             solid: self.inputs.get_bool(Self::SOLID),
@@ -110,6 +201,8 @@ impl DrawMenu {
                 .inputs
                 .get_string(Self::CAP_END)
                 .map(|name| scene::Cap::from(&name)),
+            taper_start: self.inputs.get_bool(Self::TAPER_START),
+            taper_end: self.inputs.get_bool(Self::TAPER_END),
             ..Default::default()
         }
     }
@@ -130,10 +223,23 @@ impl DrawMenu {
         if let Some(cap) = details.cap_end {
             self.inputs.set_string(Self::CAP_END, cap.to_str());
         }
+
+        if let Some(taper) = details.taper_start {
+            self.inputs.set_bool(Self::TAPER_START, taper);
+        }
+
+        if let Some(taper) = details.taper_end {
+            self.inputs.set_bool(Self::TAPER_END, taper);
+        }
     }
 
     pub fn get_draw_tool(&self) -> DrawTool {
-        self.tool
+        match self.tool {
+            DrawTool::Template(_) => DrawTool::Template(TemplateShape::from(
+                &self.inputs.get_string(Self::TEMPLATE).unwrap_or_default(),
+            )),
+            tool => tool,
+        }
     }
 
     pub fn set_draw_tool(&mut self, draw_tool: DrawTool) {
@@ -152,11 +258,19 @@ impl DrawMenu {
                 deets.shape = Some(::scene::Shape::Ellipse);
                 Icon::Circle
             }
+            DrawTool::Erase => {
+                deets.shape = None;
+                Icon::Eraser
+            }
             DrawTool::Freehand => {
                 deets.shape = None;
                 deets.cap_end = Some(::scene::Cap::Round);
                 Icon::Brush
             }
+            DrawTool::Light => {
+                deets.shape = None;
+                Icon::Light
+            }
             DrawTool::Line => {
                 deets.shape = None;
                 deets.cap_end = Some(::scene::Cap::Arrow);
@@ -166,6 +280,24 @@ impl DrawMenu {
                 deets.shape = Some(::scene::Shape::Rectangle);
                 Icon::Square
             }
+            DrawTool::Ruler => {
+                deets.shape = None;
+                Icon::Ruler
+            }
+            DrawTool::Template(shape) => {
+                deets.shape = None;
+                let name = match shape {
+                    TemplateShape::Cone(_) => "cone",
+                    TemplateShape::Sphere(_) => "sphere",
+                    TemplateShape::Line(..) => "line",
+                };
+                self.inputs.set_string(Self::TEMPLATE, name);
+                Icon::Template
+            }
+            DrawTool::Text => {
+                deets.shape = None;
+                Icon::Text
+            }
         };
 
         self.update(&deets);
@@ -7,12 +7,30 @@ use crate::{bridge::console_log, dom::element::Element, viewport::ViewportPoint}
 
 #[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
 pub enum CanvasDropdownEvent {
+    Assign(Option<uuid::Uuid>), // (owner) or None to clear
     Aura,
+    BringForward,
     Clone,
+    Comment,
+    Condition(usize), // (index into scene::Condition::COMMON)
     Delete,
+    DismissHandout,
+    FlipCaps,
     Group,
+    JoinDrawings,
     Layer(scene::Id),
+    PlayPath,
+    RecordPath,
+    ReverseDrawing,
+    SelectColour,
+    SelectShape,
+    SelectSimilar,
+    SelectTexture,
+    SendBack,
+    ShowHandout,
+    SplitDrawing,
     Ungroup,
+    Waypoint,
 }
 
 struct DropdownItem<T: Serialize> {
@@ -56,6 +74,14 @@ fn item(label: &str) -> Element {
     Element::item().with_child(&link(label))
 }
 
+fn capitalise(label: &str) -> String {
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 fn submenu(label: &str) -> Element {
     Element::item().with_class("dropend").with_child(
         &link(label)
@@ -73,6 +99,10 @@ pub struct Dropdown {
     items: Vec<DropdownItem<CanvasDropdownEvent>>,
     layers_menu: Element,
     layers: Vec<DropdownItem<CanvasDropdownEvent>>,
+    conditions: Vec<DropdownItem<CanvasDropdownEvent>>,
+    players_item: Element,
+    players_menu: Element,
+    players: Vec<DropdownItem<CanvasDropdownEvent>>,
 }
 
 impl Dropdown {
@@ -87,6 +117,10 @@ impl Dropdown {
             items: Vec::new(),
             layers_menu: Self::element(),
             layers: Vec::new(),
+            conditions: Vec::new(),
+            players_item: submenu("Assign to Player"),
+            players_menu: Self::element(),
+            players: Vec::new(),
         };
 
         // Ensure we can place it on the canvas
@@ -95,10 +129,23 @@ impl Dropdown {
 
         for (label, event) in [
             ("Add aura", CanvasDropdownEvent::Aura),
+            ("Bring Forward", CanvasDropdownEvent::BringForward),
+            ("Send Back", CanvasDropdownEvent::SendBack),
             ("Clone", CanvasDropdownEvent::Clone),
             ("Delete", CanvasDropdownEvent::Delete),
             ("Group Selection", CanvasDropdownEvent::Group),
             ("Ungroup", CanvasDropdownEvent::Ungroup),
+            ("Select Same Colour", CanvasDropdownEvent::SelectColour),
+            ("Select Same Shape", CanvasDropdownEvent::SelectShape),
+            ("Select Same Texture", CanvasDropdownEvent::SelectTexture),
+            ("Select Similar", CanvasDropdownEvent::SelectSimilar),
+            ("Record Path", CanvasDropdownEvent::RecordPath),
+            ("Play Path", CanvasDropdownEvent::PlayPath),
+            ("Reverse Drawing", CanvasDropdownEvent::ReverseDrawing),
+            ("Join Drawings", CanvasDropdownEvent::JoinDrawings),
+            ("Split Drawing Here", CanvasDropdownEvent::SplitDrawing),
+            ("Swap Caps", CanvasDropdownEvent::FlipCaps),
+            ("Show as Handout", CanvasDropdownEvent::ShowHandout),
         ] {
             dropdown.add_item(dropdown.new_item(label, event));
         }
@@ -108,6 +155,50 @@ impl Dropdown {
         layer_item.append_child(&dropdown.layers_menu);
         dropdown.element.append_child(&layer_item);
 
+        // Toggle condition dropdown
+        let conditions_menu = Self::element();
+        for (idx, condition) in scene::Condition::COMMON.iter().enumerate() {
+            let item = dropdown.new_item(
+                &capitalise(condition.to_str()),
+                CanvasDropdownEvent::Condition(idx),
+            );
+            conditions_menu.append_child(&item.element);
+            dropdown.conditions.push(item);
+        }
+        let conditions_item = submenu("Conditions");
+        conditions_item.append_child(&conditions_menu);
+        dropdown.element.append_child(&conditions_item);
+
+        // Assign owner dropdown, only shown to editors
+        dropdown.players_item.append_child(&dropdown.players_menu);
+        dropdown.element.append_child(&dropdown.players_item);
+        dropdown.players_item.hide();
+        dropdown.update_players(&[]);
+
+        dropdown
+    }
+
+    /// Dropdown shown when right clicking the scene background.
+    pub fn background() -> Self {
+        let mut dropdown = Self {
+            element: Self::element(),
+            event: Rc::new(Mutex::new(None)),
+            items: Vec::new(),
+            layers_menu: Self::element(),
+            layers: Vec::new(),
+            conditions: Vec::new(),
+            players_item: Self::element(),
+            players_menu: Self::element(),
+            players: Vec::new(),
+        };
+
+        dropdown.element.set_css("position", "absolute");
+        dropdown.element.add_to_page();
+        dropdown.add_item(dropdown.new_item("Add Waypoint Here", CanvasDropdownEvent::Waypoint));
+        dropdown.add_item(dropdown.new_item("Add Comment Here", CanvasDropdownEvent::Comment));
+        dropdown
+            .add_item(dropdown.new_item("Dismiss Handouts", CanvasDropdownEvent::DismissHandout));
+
         dropdown
     }
 
@@ -178,6 +269,26 @@ impl Dropdown {
         }
     }
 
+    pub fn update_players(&mut self, players: &[(String, uuid::Uuid)]) {
+        self.players.clear();
+        let nobody = self.new_item("Nobody", CanvasDropdownEvent::Assign(None));
+        self.players_menu.append_child(&nobody.element);
+        self.players.push(nobody);
+        for (name, user) in players {
+            let item = self.new_item(name, CanvasDropdownEvent::Assign(Some(*user)));
+            self.players_menu.append_child(&item.element);
+            self.players.push(item);
+        }
+    }
+
+    pub fn update_role(&self, role: scene::perms::Role) {
+        if role.editor() {
+            self.players_item.show();
+        } else {
+            self.players_item.hide();
+        }
+    }
+
     pub fn update_options(&self, hide: &[CanvasDropdownEvent]) {
         for item in &self.items {
             if hide.contains(&item.value) {
@@ -1,4 +1,4 @@
-use scene::{Id, Layer};
+use scene::{perms::LayerPerms, Id, Layer};
 
 use crate::{
     bridge::console_log,
@@ -9,10 +9,15 @@ use crate::{
 pub struct LayerInfo {
     pub id: Id,
     pub title: String,
+    pub description: Option<String>,
     pub z: i32,
     pub visible: bool,
     pub locked: bool,
+    pub perms: LayerPerms,
     pub n_sprites: usize,
+
+    /// Opacity of the layer's sprites when viewed as an editor.
+    pub gm_opacity: f32,
 }
 
 impl LayerInfo {
@@ -20,10 +25,13 @@ impl LayerInfo {
         LayerInfo {
             id: layer.id,
             title: layer.title.clone(),
+            description: layer.description.clone(),
             z: layer.z,
             visible: layer.visible,
             locked: layer.locked,
+            perms: layer.perms,
             n_sprites: layer.sprites.len(),
+            gm_opacity: 1.0,
         }
     }
 }
@@ -64,7 +72,9 @@ impl LayersMenu {
         &self.root
     }
 
-    pub fn update(&self, selected: Id, layers: &[LayerInfo]) {
+    /// Rebuild the list of layers. Descriptions and GM opacity sliders are only
+    /// shown to editors.
+    pub fn update(&self, selected: Id, layers: &[LayerInfo], editor: bool) {
         self.list.clear();
         let mut background = false;
         for layer in layers {
@@ -104,6 +114,37 @@ impl LayersMenu {
             input.add_button(Icon::Down, move |vp| vp.int.move_layer(id, false));
 
             input.add_button(Icon::Trash, move |vp| vp.int.remove_layer(id));
+
+            // Permissions granted to players on this layer.
+            input.add_line();
+            input.add_checkbox_handler("Move Own", move |vp, value| {
+                vp.int
+                    .update_layer_perms(id, |perms| perms.can_move_own = value)
+            });
+            input.set_bool("Move Own", layer.perms.can_move_own);
+            input.add_checkbox_handler("Move Any", move |vp, value| {
+                vp.int
+                    .update_layer_perms(id, |perms| perms.can_move_others = value)
+            });
+            input.set_bool("Move Any", layer.perms.can_move_others);
+            input.add_checkbox_handler("Add", move |vp, value| {
+                vp.int.update_layer_perms(id, |perms| perms.can_add = value)
+            });
+            input.set_bool("Add", layer.perms.can_add);
+
+            if editor {
+                input.add_line();
+                input.add_textarea_handler("Notes", move |vp, description| {
+                    vp.int.set_layer_description(id, description)
+                });
+                input.set_string("Notes", layer.description.as_deref().unwrap_or(""));
+
+                input.add_line();
+                input.add_slider_handler("Opacity", 0.05, move |vp, opacity| {
+                    vp.int.set_layer_gm_opacity(id, opacity)
+                });
+                input.set_float("Opacity", layer.gm_opacity);
+            }
         }
     }
 }
@@ -2,7 +2,16 @@ use ::scene::{perms::Role, Id};
 
 pub use self::dropdown::CanvasDropdownEvent;
 pub use self::layers::LayerInfo;
-use super::{element::Element, set_visible};
+use super::{
+    annotation::AnnotationInput,
+    chat::{ChatEntry, ChatLog},
+    comment::CommentPopover,
+    element::Element,
+    handout::HandoutView,
+    icon::Icon,
+    initiative::InitiativeTracker,
+    set_visible,
+};
 use crate::{interactor::details::SceneDetails, start::VpRef, viewport::ViewportPoint};
 
 mod draw;
@@ -95,13 +104,31 @@ fn set_accordion_visible(key: &str, visible: bool) {
     }
 }
 
+/// Padlock shown over the canvas while the scene is locked.
+fn lock_overlay() -> Element {
+    let overlay = Element::default()
+        .with_class("scene-lock")
+        .with_attr("title", "Scene locked");
+    overlay.append_child(&Icon::Lock.element());
+    overlay.hide();
+    overlay.add_to_page();
+    overlay
+}
+
 pub struct Menu {
     dropdown: dropdown::Dropdown,
+    background_dropdown: dropdown::Dropdown,
     layers: layers::LayersMenu,
     scene: scene::SceneMenu,
     draw: draw::DrawMenu,
     sprite: sprite::SpriteMenu,
     tools: tools::ToolsMenu,
+    chat: ChatLog,
+    handout: HandoutView,
+    initiative: InitiativeTracker,
+    annotation: AnnotationInput,
+    comment: CommentPopover,
+    lock: Element,
     vp: VpRef,
     role: Role,
 }
@@ -116,11 +143,18 @@ impl Menu {
     pub fn new(vp: VpRef, role: Role) -> Self {
         let menu = Self {
             dropdown: dropdown::Dropdown::new(),
+            background_dropdown: dropdown::Dropdown::background(),
             layers: layers::LayersMenu::new(vp.clone()),
             scene: scene::SceneMenu::new(vp.clone()),
             draw: draw::DrawMenu::new(vp.clone()),
-            sprite: sprite::SpriteMenu::new(vp.clone()),
+            sprite: sprite::SpriteMenu::new(vp.clone(), role),
             tools: tools::ToolsMenu::new(vp.clone(), role),
+            chat: ChatLog::new(vp.clone()),
+            handout: HandoutView::new(vp.clone()),
+            initiative: InitiativeTracker::new(vp.clone()),
+            annotation: AnnotationInput::new(vp.clone()),
+            comment: CommentPopover::new(vp.clone()),
+            lock: lock_overlay(),
             vp,
             role,
         };
@@ -167,19 +201,45 @@ impl Menu {
         self.draw.set_draw_tool(draw_tool);
     }
 
+    pub fn open_annotation(&self, at: ViewportPoint, scene_at: ::scene::Point) {
+        self.annotation.open(at, scene_at);
+    }
+
+    pub fn open_comment(&self, at: ViewportPoint, index: usize, comment: &::scene::Comment) {
+        self.comment.open(at, index, comment);
+    }
+
+    pub fn close_comment(&self) {
+        self.comment.close();
+    }
+
     pub fn show_dropdown(&self, at: ViewportPoint) {
+        self.background_dropdown.hide();
         self.dropdown.show(at);
     }
 
+    pub fn show_background_dropdown(&self, at: ViewportPoint) {
+        self.dropdown.hide();
+        self.background_dropdown.show(at);
+    }
+
     pub fn hide_dropdown(&self) {
         self.dropdown.hide();
+        self.background_dropdown.hide();
     }
 
     pub fn dropdown_event(&mut self) -> Option<dropdown::CanvasDropdownEvent> {
-        self.dropdown.event()
+        self.dropdown
+            .event()
+            .or_else(|| self.background_dropdown.event())
     }
 
     pub fn set_scene_details(&mut self, details: SceneDetails) {
+        if details.locked.unwrap_or(false) {
+            self.lock.show();
+        } else {
+            self.lock.hide();
+        }
         self.scene.set_details(details);
     }
 
@@ -191,12 +251,20 @@ impl Menu {
         self.scene.set_scene_list(list);
     }
 
+    pub fn set_waypoints(&mut self, waypoints: &[::scene::Waypoint]) {
+        self.scene.set_waypoints(waypoints);
+    }
+
     pub fn set_fog_brush(&mut self, brush: f32) {
         self.scene.set_fog_brush(brush);
     }
 
-    pub fn set_layer_info(&mut self, selected: Id, layers: &[LayerInfo]) {
-        self.layers.update(selected, layers);
+    pub fn set_player_screen(&mut self, open: bool) {
+        self.scene.set_player_screen(open);
+    }
+
+    pub fn set_layer_info(&mut self, selected: Id, layers: &[LayerInfo], editor: bool) {
+        self.layers.update(selected, layers, editor);
         self.dropdown.update_layers(layers);
     }
 
@@ -204,11 +272,70 @@ impl Menu {
         self.sprite.set_sprite_info(details);
     }
 
+    /// Update the players which may be assigned sprites.
+    pub fn set_players(&mut self, players: &[(String, uuid::Uuid)]) {
+        self.sprite.set_players(players);
+        self.dropdown.update_players(players);
+    }
+
+    pub fn set_own_sprite(&mut self, found: bool) {
+        self.tools.set_own_sprite(found);
+    }
+
+    pub fn set_palettes(&mut self, palettes: &[::scene::ColourPalette]) {
+        self.draw.set_palettes(palettes);
+    }
+
+    pub fn set_palette(&mut self, palette: &::scene::ColourPalette) {
+        self.draw.set_palette(palette);
+    }
+
+    /// Name of the palette chosen in the draw menu.
+    pub fn palette(&self) -> Option<String> {
+        self.draw.palette()
+    }
+
+    pub fn set_draw_colour(&mut self, colour: ::scene::Colour) {
+        self.draw.set_colour(colour);
+    }
+
+    pub fn set_sprite_templates(&mut self, templates: &[::scene::SpriteTmpl]) {
+        self.draw.set_sprite_templates(templates);
+    }
+
+    /// Index of the sprite template chosen to place.
+    pub fn sprite_template(&self) -> Option<usize> {
+        self.draw.sprite_template()
+    }
+
+    pub fn set_initiative_visible(&self, visible: bool) {
+        self.initiative.set_visible(visible);
+    }
+
+    pub fn set_initiative(&self, order: &[(String, i32)]) {
+        self.initiative.update(order);
+    }
+
+    pub fn set_chat_visible(&self, visible: bool) {
+        self.chat.set_visible(visible);
+    }
+
+    pub fn append_chat(&self, entries: &[ChatEntry]) {
+        self.chat.append(entries);
+    }
+
+    pub fn set_handout(&self, handout: Option<&(String, String)>) {
+        self.handout.update(handout);
+    }
+
     pub fn update_selection(&mut self, has_selection: bool) {
         set_accordion_visible(Self::SPRITE, has_selection);
     }
 
     pub fn update_role(&mut self, role: ::scene::perms::Role) {
+        self.initiative.update_role(role);
+        self.sprite.update_role(role);
+        self.dropdown.update_role(role);
         if role == self.role {
             // Already have the correct role.
             return;
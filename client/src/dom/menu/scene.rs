@@ -1,16 +1,20 @@
+use crate::bridge::console_log;
 use crate::dom::element::Element;
 use crate::dom::icon::Icon;
 use crate::dom::input::InputGroup;
 use crate::interactor::details::SceneDetails;
 use crate::start::VpRef;
+use crate::viewport::Viewport;
 
 pub struct SceneMenu {
     inputs: InputGroup,
+    waypoints: Element,
+    vp: VpRef,
 }
 
 impl SceneMenu {
     pub fn new(vp: VpRef) -> Self {
-        let mut inputs = InputGroup::new(vp);
+        let mut inputs = InputGroup::new(vp.clone());
 
         inputs.add_toggle_string("Title", true, |vp, title| {
             vp.int.scene_details(SceneDetails {
@@ -54,15 +58,105 @@ impl SceneMenu {
             vp.int.set_fog_brush(brush)
         });
         inputs.add_line();
+        inputs.add_text_button("Fill Fog", |vp| vp.int.fill_fog());
+        inputs.add_text_button("Clear Fog", |vp| vp.int.clear_fog());
+        inputs.add_text_button("Download Fog Mask", |vp| vp.int.export_fog());
+        inputs.add_line();
+        inputs.add_colour_handler("Grid", |vp, colour| {
+            vp.int.scene_details(SceneDetails {
+                grid_colour: Some(colour),
+                ..Default::default()
+            });
+        });
+        inputs.add_colour_handler("Background", |vp, colour| {
+            vp.int.scene_details(SceneDetails {
+                background: Some(colour),
+                ..Default::default()
+            });
+        });
+        inputs.add_line();
+        let units: Vec<(&str, &str)> = scene::UnitSystem::ALL
+            .iter()
+            .map(|u| (u.to_str(), u.to_str()))
+            .collect();
+        inputs.add_select_handler("Units", &units, |vp, name| {
+            vp.int.scene_details(SceneDetails {
+                unit_system: scene::UnitSystem::from(&name),
+                ..Default::default()
+            });
+        });
+        inputs.add_checkbox_handler("Lock Scene", |vp, locked| {
+            vp.int.scene_details(SceneDetails {
+                locked: Some(locked),
+                ..Default::default()
+            });
+        });
+        inputs.add_line();
+        inputs.add_toggle_string("Map", true, |vp, key| {
+            vp.int.scene_details(SceneDetails {
+                background_texture: Some(crate::render::parse_media_key(&key)),
+                ..Default::default()
+            });
+        });
+        inputs.add_button(Icon::Trash, |vp| vp.int.set_background_texture(None));
+        inputs.add_line();
         inputs.add_select_handler("Change Scene", &[], |vp, uuid| {
             if let Ok(uuid) = uuid::Uuid::try_parse(&uuid) {
-                crate::bridge::upload_thumbnail(&vp.int.scene_uuid());
+                crate::bridge::upload_thumbnail(&vp.int.project_uuid(), &vp.int.scene_uuid());
                 vp.int.change_scene(uuid);
             }
         });
         inputs.add_button(Icon::PlusSquare, |vp| vp.int.new_scene());
+        inputs.add_line();
+        inputs.add_float_handler(
+            "Camera Pan (ms)",
+            Some(0),
+            Some(Viewport::MAX_VIEW_DURATION_MS as i32),
+            Some(50.0),
+            |vp, ms| vp.set_view_duration(ms as u64),
+        );
+        inputs.set_float("Camera Pan (ms)", Viewport::DEFAULT_VIEW_DURATION_MS as f32);
+        inputs.add_line();
+        inputs.add_float_handler(
+            "Nameplate Zoom (px)",
+            Some(0),
+            None,
+            Some(5.0),
+            |vp, zoom| vp.set_nameplate_zoom(zoom),
+        );
+        inputs.set_float("Nameplate Zoom (px)", Viewport::DEFAULT_NAMEPLATE_ZOOM);
+        inputs.add_float_handler(
+            "Measurement Grid",
+            Some(1),
+            None,
+            Some(1.0),
+            |vp, interval| vp.set_measurement_interval(interval as u32),
+        );
+        inputs.set_float(
+            "Measurement Grid",
+            Viewport::DEFAULT_MEASUREMENT_INTERVAL as f32,
+        );
+        inputs.add_checkbox_handler("Zoom to Cursor", |vp, zoom_to_cursor| {
+            vp.set_zoom_to_cursor(zoom_to_cursor)
+        });
+        inputs.set_bool("Zoom to Cursor", true);
+        inputs.add_checkbox_handler("Player Screen", |vp, open| vp.set_player_screen(open));
+        inputs.add_checkbox_handler("Overlap Check", |vp, check| vp.int.set_overlap_check(check));
+        inputs.add_float_handler("Edge Snap", Some(0), Some(2), Some(0.05), |vp, radius| {
+            vp.int.set_snap_radius(radius)
+        });
+        inputs.set_float(
+            "Edge Snap",
+            crate::interactor::Interactor::DEFAULT_SNAP_RADIUS,
+        );
 
-        Self { inputs }
+        let waypoints = inputs.root().child("div").with_class("mt-1");
+
+        Self {
+            inputs,
+            waypoints,
+            vp,
+        }
     }
 
     pub fn root(&self) -> &Element {
@@ -99,11 +193,78 @@ impl SceneMenu {
         );
         self.inputs
             .set_bool("Fog of War", details.fog.unwrap_or(false));
+        self.inputs.set_colour(
+            "Grid",
+            details
+                .grid_colour
+                .unwrap_or(scene::Scene::DEFAULT_GRID_COLOUR),
+        );
+        self.inputs.set_colour(
+            "Background",
+            details
+                .background
+                .unwrap_or(scene::Scene::DEFAULT_BACKGROUND),
+        );
+        self.inputs
+            .set_string("Units", details.unit_system.unwrap_or_default().to_str());
+        self.inputs
+            .set_bool("Lock Scene", details.locked.unwrap_or(false));
+        match details.background_texture {
+            Some(texture) if texture != 0 => {
+                self.inputs.set_string("Map", &format!("{texture:016X}"))
+            }
+            _ => self.inputs.set_string("Map", ""),
+        }
         if let Some(scene) = details.uuid {
             self.set_scene(scene.simple().to_string());
         }
     }
 
+    /// Replace the list of waypoint buttons. Clicking a waypoint moves the
+    /// camera there.
+    pub fn set_waypoints(&mut self, waypoints: &[scene::Waypoint]) {
+        self.waypoints.clear();
+        for waypoint in waypoints {
+            let group = self.waypoints.child("div").with_classes(&[
+                "btn-group",
+                "btn-group-sm",
+                "me-1",
+                "mt-1",
+            ]);
+
+            let mut go = Self::button(&group).with_text(&waypoint.name);
+            let vp = self.vp.clone();
+            let (viewport, grid_size) = (waypoint.viewport, waypoint.grid_size);
+            go.set_onclick(Box::new(move |_| {
+                if let Ok(mut lock) = vp.try_lock() {
+                    let duration = lock.view_duration();
+                    lock.animate_to(viewport, grid_size, duration);
+                } else {
+                    console_log("Failed to lock viewport to move to waypoint.");
+                }
+            }));
+
+            let mut remove = Self::button(&group);
+            remove.append_child(&Icon::Trash.element());
+            let vp = self.vp.clone();
+            let name = waypoint.name.clone();
+            remove.set_onclick(Box::new(move |_| {
+                if let Ok(mut lock) = vp.try_lock() {
+                    lock.int.remove_waypoint(&name);
+                } else {
+                    console_log("Failed to lock viewport to remove waypoint.");
+                }
+            }));
+        }
+    }
+
+    fn button(parent: &Element) -> Element {
+        parent
+            .child("button")
+            .with_classes(&["btn", "btn-outline-primary"])
+            .with_attr("type", "button")
+    }
+
     pub fn set_brush(&mut self, brush: u32) {
         self.inputs.set_float("Brush", brush as f32);
     }
@@ -114,6 +275,12 @@ impl SceneMenu {
             w: self.width(),
             h: self.height(),
             fog: self.fog_of_war(),
+            grid_colour: self.inputs.get_colour("Grid"),
+            background: self.inputs.get_colour("Background"),
+            unit_system: self
+                .inputs
+                .get_string("Units")
+                .and_then(|name| scene::UnitSystem::from(&name)),
             ..Default::default()
         }
     }
@@ -128,6 +295,10 @@ impl SceneMenu {
         self.inputs.set_float("Brush", brush);
     }
 
+    pub fn set_player_screen(&self, open: bool) {
+        self.inputs.set_bool("Player Screen", open);
+    }
+
     pub fn scene(&self) -> Option<String> {
         self.inputs.get_string("Change Scene")
     }
@@ -11,6 +11,7 @@ use crate::{
 
 pub struct SpriteMenu {
     inputs: InputGroup,
+    gm_inputs: InputGroup,
     selected_id: Rc<AtomicI64>,
 }
 
@@ -24,15 +25,62 @@ impl SpriteMenu {
     const COLOUR: &'static str = "Colour";
     const STROKE: &'static str = "Stroke Width";
     const SOLID: &'static str = "Solid";
+    const PATTERN: &'static str = "Pattern";
+    const GAP: &'static str = "Gap";
     const CAP_START: &'static str = "Start";
     const CAP_END: &'static str = "End";
+    const TAPER_START: &'static str = "Taper Start";
+    const TAPER_END: &'static str = "Taper End";
     const SHAPE: &'static str = "Shape";
+    const HP_CURRENT: &'static str = "Current HP";
+    const HP_MAX: &'static str = "Max HP";
+    const LIGHT_RADIUS: &'static str = "Light Radius";
+    const LABEL: &'static str = "Label";
+    const GM_NOTES: &'static str = "GM Notes";
+    const OWNER: &'static str = "Owner";
+    const NO_OWNER: (&'static str, &'static str) = ("None", "");
 
-    pub fn new(vp: VpRef) -> Self {
-        let mut inputs = InputGroup::new(vp);
+    pub fn new(vp: VpRef, role: scene::perms::Role) -> Self {
+        let mut inputs = InputGroup::new(vp.clone());
 
         let selected_id = Rc::new(AtomicI64::new(Self::NO_SELECTION));
 
+        let id_ref = selected_id.clone();
+        inputs.add_toggle_string(Self::LABEL, true, move |vp, label| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    label: Some(label),
+                    ..Default::default()
+                },
+            );
+        });
+
+        // Notes are only shown to editors, in their own group so that they
+        // can be hidden as a whole.
+        let mut gm_inputs = InputGroup::new(vp);
+        let id_ref = selected_id.clone();
+        gm_inputs.add_textarea_handler(Self::GM_NOTES, move |vp, notes| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    gm_notes: Some(notes),
+                    ..Default::default()
+                },
+            );
+        });
+        gm_inputs.add_line();
+        let id_ref = selected_id.clone();
+        gm_inputs.add_select_handler(Self::OWNER, &[Self::NO_OWNER], move |vp, owner| {
+            vp.int.set_sprite_owner(
+                id_ref.load(Ordering::Relaxed),
+                uuid::Uuid::try_parse(&owner).ok(),
+            );
+        });
+        inputs.root().append_child(gm_inputs.root());
+
+        inputs.add_line();
+
         let id_ref = selected_id.clone();
         inputs.add_float_handler(Self::X, None, None, None, move |vp, x| {
             vp.int.sprite_details(
@@ -116,6 +164,41 @@ impl SpriteMenu {
 
         inputs.add_line();
 
+        let id_ref = selected_id.clone();
+        inputs.add_select_handler(
+            Self::PATTERN,
+            &[
+                ("Solid", "solid"),
+                ("Dashed", "dashed"),
+                ("Dotted", "dotted"),
+            ],
+            move |vp, name| {
+                vp.int.sprite_details(
+                    id_ref.load(Ordering::Relaxed),
+                    SpriteDetails {
+                        pattern: Some(scene::StrokePattern::from(
+                            &name,
+                            scene::StrokePattern::DEFAULT_GAP,
+                        )),
+                        ..Default::default()
+                    },
+                )
+            },
+        );
+
+        let id_ref = selected_id.clone();
+        inputs.add_float_handler(Self::GAP, Some(0), None, Some(0.05), move |vp, gap| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    pattern: Some(scene::StrokePattern::Dashed(gap)),
+                    ..Default::default()
+                },
+            );
+        });
+
+        inputs.add_line();
+
         let id_ref = selected_id.clone();
         inputs.add_select_handler(Self::CAP_START, super::CAP_OPTIONS, move |vp, name| {
             vp.int.sprite_details(
@@ -138,6 +221,28 @@ impl SpriteMenu {
             )
         });
 
+        let id_ref = selected_id.clone();
+        inputs.add_checkbox_handler(Self::TAPER_START, move |vp, taper| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    taper_start: Some(taper),
+                    ..Default::default()
+                },
+            );
+        });
+
+        let id_ref = selected_id.clone();
+        inputs.add_checkbox_handler(Self::TAPER_END, move |vp, taper| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    taper_end: Some(taper),
+                    ..Default::default()
+                },
+            );
+        });
+
         let id_ref = selected_id.clone();
         inputs.add_select_handler(
             Self::SHAPE,
@@ -157,18 +262,99 @@ impl SpriteMenu {
             },
         );
 
-        SpriteMenu {
+        inputs.add_line();
+
+        let id_ref = selected_id.clone();
+        inputs.add_float_handler(Self::HP_CURRENT, None, None, Some(1.0), move |vp, hp| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    hp_current: Some(hp as i32),
+                    ..Default::default()
+                },
+            );
+        });
+
+        let id_ref = selected_id.clone();
+        inputs.add_float_handler(Self::HP_MAX, Some(0), None, Some(1.0), move |vp, hp| {
+            vp.int.sprite_details(
+                id_ref.load(Ordering::Relaxed),
+                SpriteDetails {
+                    hp_max: Some(hp as i32),
+                    ..Default::default()
+                },
+            );
+        });
+
+        inputs.add_line();
+
+        let id_ref = selected_id.clone();
+        inputs.add_float_handler(
+            Self::LIGHT_RADIUS,
+            Some(0),
+            Some(scene::Sprite::MAX_LIGHT_RADIUS as i32),
+            Some(0.5),
+            move |vp, radius| {
+                vp.int.sprite_details(
+                    id_ref.load(Ordering::Relaxed),
+                    SpriteDetails {
+                        light_radius: Some(radius),
+                        ..Default::default()
+                    },
+                );
+            },
+        );
+
+        inputs.add_line();
+
+        inputs.add_text_button("Save Template", |vp| vp.save_sprite_template(false));
+        inputs.add_text_button("Save to Project", |vp| vp.save_sprite_template(true));
+
+        let menu = SpriteMenu {
             inputs,
+            gm_inputs,
             selected_id,
-        }
+        };
+        menu.update_role(role);
+        menu
     }
 
     pub fn root(&self) -> &Element {
         self.inputs.root()
     }
 
+    /// Replace the players which may be chosen as a sprite's owner.
+    pub fn set_players(&self, players: &[(String, uuid::Uuid)]) {
+        let mut options = vec![(Self::NO_OWNER.0.to_string(), Self::NO_OWNER.1.to_string())];
+        options.extend(
+            players
+                .iter()
+                .map(|(name, user)| (name.clone(), user.simple().to_string())),
+        );
+        self.gm_inputs.set_options(Self::OWNER, &options);
+    }
+
+    pub fn update_role(&self, role: scene::perms::Role) {
+        if role.editor() {
+            self.gm_inputs.root().show();
+        } else {
+            self.gm_inputs.root().hide();
+        }
+    }
+
     pub fn set_sprite_info(&mut self, details: Option<SpriteDetails>) {
         let id = if let Some(details) = details {
+            self.inputs
+                .set_or_clear_string(Self::LABEL, details.label.as_deref());
+            self.gm_inputs
+                .set_string(Self::GM_NOTES, details.gm_notes.as_deref().unwrap_or(""));
+            self.gm_inputs.set_string(
+                Self::OWNER,
+                &details
+                    .owner
+                    .map(|user| user.simple().to_string())
+                    .unwrap_or_default(),
+            );
             self.inputs.set_or_clear_float(Self::X, details.x);
             self.inputs.set_or_clear_float(Self::Y, details.y);
             self.inputs.set_or_clear_float(Self::WIDTH, details.w);
@@ -177,12 +363,26 @@ impl SpriteMenu {
                 .set_or_clear_colour(Self::COLOUR, details.colour);
             self.inputs.set_or_clear_float(Self::STROKE, details.stroke);
             self.inputs.set_or_clear_bool(Self::SOLID, details.solid);
+            self.inputs
+                .set_or_clear_string(Self::PATTERN, details.pattern.map(|p| p.to_str()));
+            self.inputs
+                .set_or_clear_float(Self::GAP, details.pattern.and_then(|p| p.gap()));
             self.inputs
                 .set_or_clear_string(Self::CAP_START, details.cap_start.map(|c| c.to_str()));
             self.inputs
                 .set_or_clear_string(Self::CAP_END, details.cap_end.map(|c| c.to_str()));
+            self.inputs
+                .set_or_clear_bool(Self::TAPER_START, details.taper_start);
+            self.inputs
+                .set_or_clear_bool(Self::TAPER_END, details.taper_end);
             self.inputs
                 .set_or_clear_string(Self::SHAPE, details.shape.map(|c| c.to_str()));
+            self.inputs
+                .set_or_clear_float(Self::HP_CURRENT, details.hp_current.map(|hp| hp as f32));
+            self.inputs
+                .set_or_clear_float(Self::HP_MAX, details.hp_max.map(|hp| hp as f32));
+            self.inputs
+                .set_or_clear_float(Self::LIGHT_RADIUS, details.light_radius);
             details.id
         } else {
             Self::NO_SELECTION
@@ -2,8 +2,9 @@ use scene::perms::Role;
 
 use crate::{
     dom::{element::Element, icon::Icon, input::InputGroup},
+    render::ColourBlindMode,
     start::VpRef,
-    viewport::Tool,
+    viewport::{Tool, Viewport},
 };
 
 pub struct ToolsMenu {
@@ -12,6 +13,10 @@ pub struct ToolsMenu {
 
 impl ToolsMenu {
     const KEY: &'static str = "Tool";
+    const COLOUR_BLIND_KEY: &'static str = "Colour Blindness";
+    const LOCK_KEY: &'static str = "Lock View";
+    const FIND_KEY: &'static str = "Find Me";
+    const INVERT_KEY: &'static str = "Invert Selection";
 
     const EDITOR_TOOLS: &'static [Icon] = &[Icon::Cursor, Icon::Arrows, Icon::Brush, Icon::Fog];
     const PLAYER_TOOLS: &'static [Icon] = &[Icon::Cursor, Icon::Arrows, Icon::Brush];
@@ -36,6 +41,29 @@ impl ToolsMenu {
             },
         );
 
+        inputs.add_button(Icon::ZoomIn, |vp| vp.zoom_to_selection());
+        inputs.add_named_button(Self::INVERT_KEY, Icon::Invert, |vp| {
+            vp.int.invert_selection()
+        });
+        inputs.add_button(Icon::Map, |vp| vp.toggle_minimap());
+        inputs.add_named_button(Self::FIND_KEY, Icon::Person, |vp| vp.centre_on_own_sprite());
+        inputs.set_enabled(Self::FIND_KEY, false);
+        inputs.add_toggle(Self::LOCK_KEY, Icon::Unlock, Icon::Lock, |vp, locked| {
+            vp.lock_viewport(locked)
+        });
+
+        let modes: Vec<(&str, &str)> = ColourBlindMode::ALL
+            .iter()
+            .map(|m| (m.to_str(), m.to_str()))
+            .collect();
+        inputs.add_select_handler(Self::COLOUR_BLIND_KEY, &modes, |vp, name| {
+            vp.set_colour_blind_mode(ColourBlindMode::from(&name).unwrap_or_default())
+        });
+        inputs.set_string(
+            Self::COLOUR_BLIND_KEY,
+            Viewport::saved_colour_blind_mode().to_str(),
+        );
+
         inputs.root().add_classes(&["accordion-item", "p-2"]);
 
         Self { inputs }
@@ -53,6 +81,11 @@ impl ToolsMenu {
         );
     }
 
+    /// Enable the find me button if the player has a token to find.
+    pub fn set_own_sprite(&self, found: bool) {
+        self.inputs.set_enabled(Self::FIND_KEY, found);
+    }
+
     pub fn root(&self) -> &Element {
         self.inputs.root()
     }
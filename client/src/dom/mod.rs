@@ -1,5 +1,10 @@
+pub mod annotation;
+pub mod chat;
+pub mod comment;
 pub mod element;
+pub mod handout;
 pub mod icon;
+pub mod initiative;
 pub mod input;
 pub mod menu;
 
@@ -15,6 +15,15 @@ pub struct Changes {
 
     // A change to the scene list or active scene.
     scene: bool,
+
+    // A change to the initiative order.
+    initiative: bool,
+
+    // A handout being shown or dismissed.
+    handout: bool,
+
+    // A change to the sprite belonging to this player.
+    own_sprite: bool,
 }
 
 impl Changes {
@@ -25,6 +34,9 @@ impl Changes {
             sprite: true,
             selected: true,
             scene: true,
+            initiative: true,
+            handout: false,
+            own_sprite: false,
         }
     }
 
@@ -116,4 +128,34 @@ impl Changes {
         self.scene = false;
         ret
     }
+
+    pub fn initiative_change(&mut self) {
+        self.initiative = true;
+    }
+
+    pub fn handle_initiative_change(&mut self) -> bool {
+        let ret = self.initiative;
+        self.initiative = false;
+        ret
+    }
+
+    pub fn handout_change(&mut self) {
+        self.handout = true;
+    }
+
+    pub fn handle_handout_change(&mut self) -> bool {
+        let ret = self.handout;
+        self.handout = false;
+        ret
+    }
+
+    pub fn own_sprite_change(&mut self) {
+        self.own_sprite = true;
+    }
+
+    pub fn handle_own_sprite_change(&mut self) -> bool {
+        let ret = self.own_sprite;
+        self.own_sprite = false;
+        ret
+    }
 }
@@ -1,4 +1,7 @@
-use scene::{comms::SceneEvent, Colour, Dimension, Id, Scene, Shape, Sprite, SpriteVisual};
+use scene::{
+    comms::SceneEvent, Colour, Dimension, Id, Scene, Shape, Sprite, SpriteVisual, StrokePattern,
+    UnitSystem,
+};
 use uuid::Uuid;
 
 #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
@@ -9,6 +12,12 @@ pub struct SceneDetails {
     pub w: Option<u32>,
     pub h: Option<u32>,
     pub fog: Option<bool>,
+    pub grid_colour: Option<Colour>,
+    pub background: Option<Colour>,
+    /// Background texture. A texture of 0 clears the background texture.
+    pub background_texture: Option<Id>,
+    pub unit_system: Option<UnitSystem>,
+    pub locked: Option<bool>,
 }
 
 impl SceneDetails {
@@ -19,6 +28,11 @@ impl SceneDetails {
             w: Some(scene.w()),
             h: Some(scene.h()),
             fog: Some(scene.fog.active),
+            grid_colour: Some(scene.grid_colour),
+            background: Some(scene.background),
+            background_texture: Some(scene.background_texture.unwrap_or(0)),
+            unit_system: Some(scene.unit_system),
+            locked: Some(scene.locked),
         }
     }
 
@@ -45,6 +59,36 @@ impl SceneDetails {
             }
         }
 
+        if let Some(colour) = self.grid_colour
+            && let Some(event) = scene.set_grid_colour(colour)
+        {
+            events.push(event);
+        }
+
+        if let Some(colour) = self.background
+            && let Some(event) = scene.set_background(colour)
+        {
+            events.push(event);
+        }
+
+        if let Some(texture) = self.background_texture
+            && let Some(event) = scene.set_background_texture((texture != 0).then_some(texture))
+        {
+            events.push(event);
+        }
+
+        if let Some(unit_system) = self.unit_system
+            && let Some(event) = scene.set_unit_system(unit_system)
+        {
+            events.push(event);
+        }
+
+        if let Some(locked) = self.locked
+            && let Some(event) = scene.set_locked(locked)
+        {
+            events.push(event);
+        }
+
         SceneEvent::set(events)
     }
 }
@@ -60,10 +104,23 @@ pub struct SpriteDetails {
     pub shape: Option<Shape>,
     pub stroke: Option<f32>,
     pub solid: Option<bool>,
+    pub pattern: Option<StrokePattern>,
     pub colour: Option<Colour>,
     pub texture: Option<Id>,
     pub cap_start: Option<scene::Cap>,
     pub cap_end: Option<scene::Cap>,
+    pub taper_start: Option<bool>,
+    pub taper_end: Option<bool>,
+    pub hp_current: Option<i32>,
+    pub hp_max: Option<i32>,
+    pub light_radius: Option<f32>,
+    /// Nameplate text. An empty label removes the nameplate.
+    pub label: Option<String>,
+    /// Editor-only notes. Empty notes are removed.
+    pub gm_notes: Option<String>,
+    /// Player whose token the sprite is, for display only. Owners are set
+    /// with `Interactor::set_sprite_owner`.
+    pub owner: Option<Uuid>,
 }
 
 impl SpriteDetails {
@@ -77,10 +134,19 @@ impl SpriteDetails {
             shape: sprite.visual.shape(),
             stroke: sprite.visual.stroke(),
             solid: sprite.visual.solid(),
+            pattern: sprite.visual.pattern(),
             colour: sprite.visual.colour(),
             texture: sprite.visual.texture(),
             cap_start: sprite.visual.cap_start(),
             cap_end: sprite.visual.cap_end(),
+            taper_start: sprite.visual.taper_start(),
+            taper_end: sprite.visual.taper_end(),
+            hp_current: sprite.hp.map(|(current, _)| current),
+            hp_max: sprite.hp.map(|(_, max)| max),
+            light_radius: sprite.visual.light_radius(),
+            label: sprite.label.clone(),
+            gm_notes: sprite.gm_notes.clone(),
+            owner: sprite.owner,
         }
     }
 
@@ -96,6 +162,8 @@ impl SpriteDetails {
             colour: self.colour(),
             cap_start: self.cap_start(),
             cap_end: self.cap_end(),
+            taper_start: self.taper_start.unwrap_or(false),
+            taper_end: self.taper_end.unwrap_or(false),
             stroke,
         }
     }
@@ -130,6 +198,10 @@ impl SpriteDetails {
             self.solid = other.solid;
         }
 
+        if other.pattern.is_some() {
+            self.pattern = other.pattern;
+        }
+
         if other.colour.is_some() {
             self.colour = other.colour;
         }
@@ -145,6 +217,34 @@ impl SpriteDetails {
         if other.cap_end.is_some() {
             self.cap_end = other.cap_end;
         }
+
+        if other.taper_start.is_some() {
+            self.taper_start = other.taper_start;
+        }
+
+        if other.taper_end.is_some() {
+            self.taper_end = other.taper_end;
+        }
+
+        if other.hp_current.is_some() {
+            self.hp_current = other.hp_current;
+        }
+
+        if other.hp_max.is_some() {
+            self.hp_max = other.hp_max;
+        }
+
+        if other.light_radius.is_some() {
+            self.light_radius = other.light_radius;
+        }
+
+        if other.label.is_some() {
+            self.label.clone_from(&other.label);
+        }
+
+        if other.gm_notes.is_some() {
+            self.gm_notes.clone_from(&other.gm_notes);
+        }
     }
 
     pub fn colour(&self) -> Colour {
@@ -180,6 +280,10 @@ impl SpriteDetails {
             self.solid = None;
         }
 
+        if self.pattern.is_some() && self.pattern != sprite.visual.pattern() {
+            self.pattern = None;
+        }
+
         if self.colour.is_some() && self.colour != sprite.visual.colour() {
             self.colour = None;
         }
@@ -195,6 +299,38 @@ impl SpriteDetails {
         if self.cap_end.is_some() && self.cap_end != sprite.visual.cap_end() {
             self.cap_end = None;
         }
+
+        if self.taper_start.is_some() && self.taper_start != sprite.visual.taper_start() {
+            self.taper_start = None;
+        }
+
+        if self.taper_end.is_some() && self.taper_end != sprite.visual.taper_end() {
+            self.taper_end = None;
+        }
+
+        if self.hp_current.is_some() && self.hp_current != sprite.hp.map(|(current, _)| current) {
+            self.hp_current = None;
+        }
+
+        if self.hp_max.is_some() && self.hp_max != sprite.hp.map(|(_, max)| max) {
+            self.hp_max = None;
+        }
+
+        if self.light_radius.is_some() && self.light_radius != sprite.visual.light_radius() {
+            self.light_radius = None;
+        }
+
+        if self.label.is_some() && self.label != sprite.label {
+            self.label = None;
+        }
+
+        if self.gm_notes.is_some() && self.gm_notes != sprite.gm_notes {
+            self.gm_notes = None;
+        }
+
+        if self.owner.is_some() && self.owner != sprite.owner {
+            self.owner = None;
+        }
     }
 
     pub fn update_sprite(&self, sprite: &mut Sprite) -> Option<SceneEvent> {
@@ -233,6 +369,12 @@ impl SpriteDetails {
             }
         }
 
+        if let Some(pattern) = self.pattern {
+            if let Some(event) = sprite.set_pattern(pattern) {
+                events.push(event);
+            }
+        }
+
         if let Some(c) = self.colour {
             if let Some(event) = sprite.set_colour(c) {
                 events.push(event);
@@ -249,9 +391,53 @@ impl SpriteDetails {
             events.push(event);
         }
 
+        if let Some(event) = sprite.set_taper(self.taper_start, self.taper_end) {
+            events.push(event);
+        }
+
+        if let Some(event) = self.hp(sprite).and_then(|hp| sprite.set_hp(hp)) {
+            events.push(event);
+        }
+
+        if let Some(radius) = self.light_radius {
+            if let Some(event) = sprite.set_light_radius(radius) {
+                events.push(event);
+            }
+        }
+
+        if let Some(label) = &self.label {
+            if let Some(event) = sprite.set_label(Some(label.clone())) {
+                events.push(event);
+            }
+        }
+
+        if let Some(notes) = &self.gm_notes {
+            if let Some(event) = sprite.set_gm_notes(Some(notes.clone())) {
+                events.push(event);
+            }
+        }
+
         SceneEvent::set(events)
     }
 
+    /// New HP for `sprite`, if either HP value is set. If the sprite doesn't
+    /// yet track HP, a missing value is assumed to equal the provided one. A
+    /// max HP of zero or less stops tracking HP.
+    fn hp(&self, sprite: &Sprite) -> Option<Option<(i32, i32)>> {
+        let (current, max) = match sprite.hp {
+            Some((current, max)) => (
+                self.hp_current.unwrap_or(current),
+                self.hp_max.unwrap_or(max),
+            ),
+            None => (
+                self.hp_current.or(self.hp_max)?,
+                self.hp_max.or(self.hp_current)?,
+            ),
+        };
+
+        Some(if max > 0 { Some((current, max)) } else { None })
+    }
+
     pub fn stroke(&self) -> f32 {
         self.stroke.unwrap_or(Sprite::DEFAULT_STROKE)
     }
@@ -0,0 +1,188 @@
+use scene::{Point, Rect};
+
+/// A line across the viewport showing that the held sprite is aligned with
+/// another sprite.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Guide {
+    /// A horizontal line at the given y coordinate.
+    Horizontal(f32),
+    /// A vertical line at the given x coordinate.
+    Vertical(f32),
+}
+
+/// Sprites further than this many tiles from the held sprite are ignored.
+pub const NEARBY_DISTANCE: f32 = 5.0;
+
+/// Alignments closer than this many tiles snap the held sprite.
+const SNAP_DISTANCE: f32 = 0.05;
+
+/// Lines closer than this after snapping are considered aligned.
+const ALIGNED_DISTANCE: f32 = 0.001;
+
+/// Region within which sprites are considered when aligning `rect`.
+pub fn nearby(rect: Rect) -> Rect {
+    let rect = rect.positive_dimensions();
+    Rect::new(
+        rect.x - NEARBY_DISTANCE,
+        rect.y - NEARBY_DISTANCE,
+        rect.w + NEARBY_DISTANCE * 2.0,
+        rect.h + NEARBY_DISTANCE * 2.0,
+    )
+}
+
+/// Left, centre and right of `rect`, or top, centre and bottom.
+fn lines(start: f32, length: f32) -> [f32; 3] {
+    [start, start + length / 2.0, start + length]
+}
+
+fn vertical_lines(rect: Rect) -> [f32; 3] {
+    let rect = rect.positive_dimensions();
+    lines(rect.x, rect.w)
+}
+
+fn horizontal_lines(rect: Rect) -> [f32; 3] {
+    let rect = rect.positive_dimensions();
+    lines(rect.y, rect.h)
+}
+
+/// Smallest offset which would align one of `held` with one of `others`, if
+/// there is one within the snapping distance.
+fn closest(held: [f32; 3], others: &[f32]) -> f32 {
+    let mut best = 0.0;
+    let mut best_dist = SNAP_DISTANCE;
+    for line in held {
+        for &other in others {
+            let dist = (other - line).abs();
+            if dist <= best_dist {
+                best = other - line;
+                best_dist = dist;
+            }
+        }
+    }
+    best
+}
+
+/// Lines in `others` which one of `held` lies along.
+fn aligned(held: [f32; 3], others: &[f32], guide: fn(f32) -> Guide, to: &mut Vec<Guide>) {
+    for &other in others {
+        if held.iter().any(|&l| (other - l).abs() <= ALIGNED_DISTANCE) {
+            let guide = guide(other);
+            if !to.contains(&guide) {
+                to.push(guide);
+            }
+        }
+    }
+}
+
+/// Align `rect` with the edges and centres of `others`. Returns the offset
+/// by which `rect` should be moved to snap into alignment, and the guides to
+/// show once it has been moved.
+pub fn align(rect: Rect, others: &[Rect]) -> (Point, Vec<Guide>) {
+    let xs: Vec<f32> = others.iter().flat_map(|&r| vertical_lines(r)).collect();
+    let ys: Vec<f32> = others.iter().flat_map(|&r| horizontal_lines(r)).collect();
+
+    let delta = Point::new(
+        closest(vertical_lines(rect), &xs),
+        closest(horizontal_lines(rect), &ys),
+    );
+
+    let snapped = rect.translate(delta);
+    let mut guides = Vec::new();
+    aligned(vertical_lines(snapped), &xs, Guide::Vertical, &mut guides);
+    aligned(
+        horizontal_lines(snapped),
+        &ys,
+        Guide::Horizontal,
+        &mut guides,
+    );
+    (delta, guides)
+}
+
+/// Whether spans starting at `a` and `b` with lengths `a_len` and `b_len`
+/// overlap or come within `radius` of overlapping.
+fn overlaps(a: f32, a_len: f32, b: f32, b_len: f32, radius: f32) -> bool {
+    a <= b + b_len + radius && b <= a + a_len + radius
+}
+
+/// Closest of `deltas` within `radius`, if it is closer than `best`.
+fn nearest(best: Option<f32>, deltas: [f32; 2], radius: f32) -> Option<f32> {
+    deltas
+        .into_iter()
+        .filter(|d| d.abs() <= radius)
+        .chain(best)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+/// Offset which would move `rect` flush against the nearest edge of one of
+/// `others`, considering edges within `radius` tiles. None if there are no
+/// such edges.
+pub fn abut(rect: Rect, others: &[Rect], radius: f32) -> Option<Point> {
+    let rect = rect.positive_dimensions();
+    let mut dx = None;
+    let mut dy = None;
+    for other in others {
+        let other = other.positive_dimensions();
+        if overlaps(rect.y, rect.h, other.y, other.h, radius) {
+            let deltas = [other.x + other.w - rect.x, other.x - (rect.x + rect.w)];
+            dx = nearest(dx, deltas, radius);
+        }
+        if overlaps(rect.x, rect.w, other.x, other.w, radius) {
+            let deltas = [other.y + other.h - rect.y, other.y - (rect.y + rect.h)];
+            dy = nearest(dy, deltas, radius);
+        }
+    }
+
+    if dx.is_some() || dy.is_some() {
+        Some(Point::new(dx.unwrap_or(0.0), dy.unwrap_or(0.0)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use scene::{Point, Rect};
+
+    use super::{abut, align, Guide};
+
+    #[test]
+    fn test_align() {
+        let other = Rect::new(0.0, 0.0, 2.0, 2.0);
+
+        // Left edge close to the other's centre snaps onto it.
+        let (delta, guides) = align(Rect::new(1.03, 4.0, 1.0, 1.0), &[other]);
+        assert!((delta.x + 0.03).abs() < 0.0001);
+        assert_eq!(delta.y, 0.0);
+        assert_eq!(guides, vec![Guide::Vertical(1.0), Guide::Vertical(2.0)]);
+
+        // Out of snapping range, nothing changes.
+        let (delta, guides) = align(Rect::new(1.2, 4.0, 1.0, 1.0), &[other]);
+        assert_eq!(delta, Point::ORIGIN);
+        assert!(guides.is_empty());
+
+        // Negative dimensions are handled, aligning on both axes.
+        let (delta, guides) = align(Rect::new(3.0, 2.96, -1.0, -1.0), &[other]);
+        assert_eq!(delta.x, 0.0);
+        assert!((delta.y - 0.04).abs() < 0.0001);
+        assert_eq!(guides, vec![Guide::Vertical(2.0), Guide::Horizontal(2.0)]);
+    }
+
+    #[test]
+    fn test_abut() {
+        let other = Rect::new(0.0, 0.0, 2.0, 2.0);
+
+        // Left edge just right of the other's right edge snaps flush.
+        let delta = abut(Rect::new(2.2, 0.5, 1.0, 1.0), &[other], 0.25).unwrap();
+        assert!((delta.x + 0.2).abs() < 0.0001);
+        assert_eq!(delta.y, 0.0);
+
+        // Bottom edge just above the other's top edge.
+        let delta = abut(Rect::new(0.5, -1.1, 1.0, 1.0), &[other], 0.25).unwrap();
+        assert_eq!(delta.x, 0.0);
+        assert!((delta.y - 0.1).abs() < 0.0001);
+
+        // No snapping to edges out of range, or when not side by side.
+        assert!(abut(Rect::new(2.5, 0.5, 1.0, 1.0), &[other], 0.25).is_none());
+        assert!(abut(Rect::new(2.1, 3.0, 1.0, 1.0), &[other], 0.25).is_none());
+    }
+}
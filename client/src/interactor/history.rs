@@ -2,16 +2,40 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicI64, Ordering};
 
 use scene::comms::{ClientEvent, ClientMessage, SceneEvent, ServerEvent};
-use scene::Id;
+use scene::{Id, Point, Rect};
 
+use crate::bridge::timestamp_ms;
 use crate::client::Client;
 
+/// Minimum time between sending batches of sprite moves to the server.
+const MOVE_INTERVAL_MS: u64 = 50;
+
+/// Minimum time between sending cursor positions to the server.
+const CURSOR_INTERVAL_MS: u64 = 100;
+
+/// Default number of entries kept on the undo stack. Once the stack is this
+/// deep, the oldest entries are dropped as new ones are added, so they can
+/// no longer be undone.
+const DEFAULT_MAX_DEPTH: usize = 100;
+
 pub struct History {
     client: Option<Client>,
     modified: bool,
     history: Vec<SceneEvent>,
     redo_history: Vec<SceneEvent>,
+
+    /// Maximum number of entries in `history`.
+    max_depth: usize,
+
     issued_events: Vec<ClientMessage>,
+
+    /// Sprite moves not yet sent to the server, as (from, to) for each sprite.
+    pending_moves: HashMap<Id, (Rect, Rect)>,
+    last_moves_sent: u64,
+
+    /// Local cursor position not yet sent to the server.
+    pending_cursor: Option<(Point, Rect)>,
+    last_cursor_sent: u64,
 }
 
 impl History {
@@ -21,7 +45,12 @@ impl History {
             modified: false,
             history: vec![],
             redo_history: vec![],
+            max_depth: DEFAULT_MAX_DEPTH,
             issued_events: vec![],
+            pending_moves: HashMap::new(),
+            last_moves_sent: 0,
+            pending_cursor: None,
+            last_cursor_sent: 0,
         }
     }
 
@@ -75,11 +104,103 @@ impl History {
         }
     }
 
+    fn is_move(event: &SceneEvent) -> bool {
+        match event {
+            SceneEvent::SpriteMove(..) => true,
+            SceneEvent::EventSet(events) => !events.is_empty() && events.iter().all(Self::is_move),
+            _ => false,
+        }
+    }
+
+    fn queue_move(&mut self, event: SceneEvent) {
+        match event {
+            SceneEvent::SpriteMove(id, from, to) => {
+                self.pending_moves
+                    .entry(id)
+                    .and_modify(|(_, dest)| *dest = to)
+                    .or_insert((from, to));
+            }
+            SceneEvent::EventSet(events) => {
+                events.into_iter().for_each(|e| self.queue_move(e));
+            }
+            _ => {}
+        }
+    }
+
+    /// Send queued sprite moves to the server as a single event. Unless
+    /// `force` is set, this does nothing if moves were sent recently.
+    fn send_moves(&mut self, force: bool) {
+        if self.pending_moves.is_empty() {
+            return;
+        }
+
+        let now = timestamp_ms();
+        if !force && now.saturating_sub(self.last_moves_sent) < MOVE_INTERVAL_MS {
+            return;
+        }
+
+        let moves = self
+            .pending_moves
+            .drain()
+            .map(|(id, (from, to))| SceneEvent::SpriteMove(id, from, to))
+            .collect();
+        if let Some(event) = SceneEvent::set(moves) {
+            self.issue_message(ClientEvent::SceneUpdate(event));
+        }
+        self.last_moves_sent = now;
+    }
+
+    /// Send any queued sprite moves if enough time has passed since the last
+    /// batch. Should be called every frame.
+    pub fn send_pending_moves(&mut self) {
+        self.send_moves(false);
+        self.send_cursor();
+    }
+
+    /// Queue the local cursor position and viewport, in scene units, to be
+    /// shared with other players.
+    pub fn move_cursor(&mut self, at: Point, viewport: Rect) {
+        if self.client.is_some() {
+            self.pending_cursor = Some((at, viewport));
+        }
+    }
+
+    /// Send the latest cursor position if enough time has passed since the
+    /// last was sent. Cursor positions aren't tracked for approval.
+    fn send_cursor(&mut self) {
+        let now = timestamp_ms();
+        if now.saturating_sub(self.last_cursor_sent) < CURSOR_INTERVAL_MS {
+            return;
+        }
+
+        if let Some(client) = &mut self.client
+            && let Some((at, viewport)) = self.pending_cursor.take()
+        {
+            client.send_message(&ClientMessage {
+                id: 0,
+                event: ClientEvent::CursorMove(at, viewport),
+            });
+            self.last_cursor_sent = now;
+        }
+    }
+
     /// Internal common backend for `issue_event` and `issue_event_no_history`,
     /// handles creating a `ClientEvent` from a `SceneEvent` and pushing along
-    /// to be sent in a message.
+    /// to be sent in a message. Sprite moves are batched, sending the latest
+    /// position of each sprite at most every `MOVE_INTERVAL_MS`.
     fn _issue_event(&mut self, event: SceneEvent) {
-        self.issue_message(ClientEvent::SceneUpdate(event));
+        if self.client.is_none() {
+            return;
+        }
+
+        if Self::is_move(&event) {
+            self.queue_move(event);
+            self.send_moves(false);
+        } else {
+            // Ensure the server receives events in order.
+            self.send_moves(true);
+            self.issue_message(ClientEvent::SceneUpdate(event));
+        }
     }
 
     /// Issue an event, publishing it to the server and adding it to the
@@ -97,10 +218,36 @@ impl History {
         // When adding a new entry to the history, all undone events are lost.
         self.redo_history.clear();
         self.history.push(event);
+        self.truncate();
 
         self.modified = true;
     }
 
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.truncate();
+    }
+
+    /// Drop the oldest entries until the history is within `max_depth`. A
+    /// `Dummy` marker is dropped along with the events of its move group,
+    /// which run until the next marker. The most recent group may still be
+    /// in progress, so is never split; it will be truncated once grouped.
+    fn truncate(&mut self) {
+        while self.history.len() > self.max_depth {
+            if matches!(self.history.first(), Some(SceneEvent::Dummy)) {
+                let Some(end) = self.history[1..]
+                    .iter()
+                    .position(|e| matches!(e, SceneEvent::Dummy))
+                else {
+                    break;
+                };
+                self.history.drain(..=end);
+            } else {
+                self.history.remove(0);
+            }
+        }
+    }
+
     /// Issue an event to the server without affecting the history stack.
     pub fn issue_event_no_history(&mut self, event: SceneEvent) {
         self._issue_event(event);
@@ -202,8 +349,39 @@ impl History {
         }
     }
 
+    /// Whether `event` only erases drawing points.
+    fn is_erase(event: &SceneEvent) -> bool {
+        match event {
+            SceneEvent::SpriteDrawingErase(..) => true,
+            SceneEvent::EventSet(events) => events.iter().all(Self::is_erase),
+            _ => false,
+        }
+    }
+
+    /// Combine the erases made while holding the eraser into a single undo
+    /// step, which restores all of the erased points.
+    fn group_moves_erase(&mut self, last: SceneEvent) {
+        self.history.push(last);
+        let mut erases = self.drain_history_until(Self::is_erase);
+        erases.reverse();
+
+        let events = erases
+            .into_iter()
+            .flat_map(|e| match e {
+                SceneEvent::EventSet(events) => events,
+                e => vec![e],
+            })
+            .collect();
+        if let Some(event) = SceneEvent::set(events) {
+            self.history.push(event);
+        }
+    }
+
     pub fn group_moves_drawing(&mut self, last: SceneEvent) {
-        let SceneEvent::SpriteDrawingPoint(drawing, _) = last else {
+        let (SceneEvent::SpriteDrawingPoint(drawing, _)
+        | SceneEvent::SpriteDrawingPressure(drawing, ..)
+        | SceneEvent::SpriteDrawingAnnotate(drawing, ..)) = last
+        else {
             return;
         };
 
@@ -211,7 +389,9 @@ impl History {
         // remove the drawing and sprite.
         let mut events = Vec::new();
         self.consume_history_until(|e| match e {
-            SceneEvent::SpriteDrawingPoint(id, ..) => *id == drawing,
+            SceneEvent::SpriteDrawingPoint(id, ..) | SceneEvent::SpriteDrawingPressure(id, ..) => {
+                *id == drawing
+            }
             SceneEvent::SpriteNew(sprite, _) => {
                 if let Some(id) = sprite.visual.drawing() {
                     if id == drawing {
@@ -279,22 +459,58 @@ impl History {
     }
 
     pub fn end_move_group(&mut self) {
+        self.send_moves(true);
+
         let opt = self.history.pop();
         if let Some(event) = opt {
             match event {
                 SceneEvent::SpriteMove(..) => self.group_moves_single(event),
+                ref e if Self::is_erase(e) => self.group_moves_erase(event),
                 SceneEvent::EventSet(..) => self.group_moves_set(event),
-                SceneEvent::SpriteDrawingPoint(..) => self.group_moves_drawing(event),
+                SceneEvent::SpriteDrawingPoint(..)
+                | SceneEvent::SpriteDrawingPressure(..)
+                | SceneEvent::SpriteDrawingAnnotate(..) => self.group_moves_drawing(event),
                 _ => self.history.push(event),
             };
         }
+        self.truncate();
     }
 
     pub fn change_scene(&mut self, scene: uuid::Uuid) -> bool {
+        self.send_moves(true);
         self.issue_message(ClientEvent::SceneChange(scene));
         self.client.is_some()
     }
 
+    /// Whether this history is connected to a game server.
+    pub fn online(&self) -> bool {
+        self.client.is_some()
+    }
+
+    pub fn send_chat(&mut self, text: String) {
+        self.issue_message(ClientEvent::ChatMessage(text));
+    }
+
+    pub fn roll_dice(&mut self, notation: String) {
+        self.issue_message(ClientEvent::DiceRoll(notation));
+    }
+
+    pub fn show_handout(&mut self, media_key: String, recipient: Option<uuid::Uuid>) {
+        self.issue_message(ClientEvent::ShowHandout(media_key, recipient));
+    }
+
+    pub fn dismiss_handout(&mut self) {
+        self.issue_message(ClientEvent::DismissHandout);
+    }
+
+    pub fn set_initiative(&mut self, name: String, roll: i32) {
+        self.issue_message(ClientEvent::InitiativeSet(name, roll));
+    }
+
+    pub fn clear_initiative(&mut self) {
+        self.issue_message(ClientEvent::InitiativeClear);
+    }
+
     pub fn erase_item(&mut self, id_to_erase: Id) {
         let predicate = |e: &SceneEvent| {
             if let Some(id) = e.item() {
@@ -321,10 +537,49 @@ impl History {
 
 #[cfg(test)]
 mod test {
-    use scene::{comms::SceneEvent, Point};
+    use scene::{comms::SceneEvent, Point, Rect};
 
+    use super::{History, DEFAULT_MAX_DEPTH};
     use crate::interactor::Interactor;
 
+    #[test]
+    fn test_history_depth() {
+        let mut history = History::new(None);
+        let rect = Rect::new(0.0, 0.0, 1.0, 1.0);
+        let event = |i: usize| SceneEvent::SpriteMove(i as i64, rect, rect);
+
+        for i in 0..DEFAULT_MAX_DEPTH * 2 {
+            history.issue_event(event(i));
+        }
+        assert_eq!(history.history.len(), DEFAULT_MAX_DEPTH);
+        assert!(matches!(
+            history.history.first(),
+            Some(SceneEvent::SpriteMove(id, ..)) if *id == DEFAULT_MAX_DEPTH as i64
+        ));
+
+        // Undoing beyond the truncation point finds nothing.
+        for _ in 0..DEFAULT_MAX_DEPTH {
+            assert!(history.pop().is_some());
+        }
+        assert!(history.pop().is_none());
+
+        // Move group markers are dropped with their group.
+        history.set_max_depth(4);
+        history.start_move_group();
+        history.issue_event(event(1));
+        history.issue_event(event(2));
+        history.start_move_group();
+        history.issue_event(event(3));
+        history.issue_event(event(4));
+        assert_eq!(history.history.len(), 3);
+        assert!(matches!(history.history.first(), Some(SceneEvent::Dummy)));
+
+        // The group in progress is never split.
+        history.issue_event(event(5));
+        history.issue_event(event(6));
+        assert_eq!(history.history.len(), 5);
+    }
+
     #[test]
     fn test_group_drawing_events() {
         let mut int = Interactor::new(None, None);
@@ -359,4 +614,38 @@ mod test {
             .iter()
             .any(|e| matches!(e, SceneEvent::SpriteNew(..))));
     }
+
+    #[test]
+    fn test_group_erase_events() {
+        let mut int = Interactor::new(None, None);
+        let draw = |int: &mut Interactor, tool, at| {
+            int.start_draw(at, false, false, Default::default(), tool);
+        };
+
+        draw(&mut int, crate::viewport::DrawTool::Freehand, Point::ORIGIN);
+        for i in 1..=6 {
+            int.drag(Point::same(i as f32 * 0.5), false);
+        }
+        int.release(false, false);
+        let Some(SceneEvent::EventSet(events)) = int.history.history.last() else {
+            panic!("Drawing should be grouped into an event set.");
+        };
+        let drawing = events.iter().find_map(|e| e.drawing()).unwrap();
+        let n = int.scene.get_drawing(drawing).unwrap().n_points();
+
+        // Each erase is grouped into a single undo step.
+        draw(&mut int, crate::viewport::DrawTool::Erase, Point::same(1.0));
+        int.drag(Point::same(2.0), false);
+        int.release(false, false);
+        let erased = int.scene.get_drawing(drawing).unwrap().n_points();
+        assert!(erased < n - 1);
+        assert!(History::is_erase(int.history.history.last().unwrap()));
+
+        // Undoing restores all of the erased points, and redoing erases them
+        // again.
+        int.undo();
+        assert_eq!(int.scene.get_drawing(drawing).unwrap().n_points(), n);
+        int.redo();
+        assert_eq!(int.scene.get_drawing(drawing).unwrap().n_points(), erased);
+    }
 }
@@ -10,12 +10,19 @@ pub enum HeldObject {
     Circle(Id, Point, bool),
     /// (drawing, sprite, ephemeral, measurement)
     Drawing(Id, Id, bool, bool),
+    /// Erasing points from drawings under the cursor.
+    Erase,
     Marquee(Point),
     None,
+    /// (start, end)
+    Ruler(Point, Point),
     Selection(Point),
 
     /// (sprite, delta, starting_rect)
     Sprite(Id, Point, Rect),
+
+    /// (sprite, drawing, origin, length, ephemeral)
+    Template(Id, Option<Id>, Point, f32, bool),
 }
 
 impl HeldObject {
@@ -28,7 +35,8 @@ impl HeldObject {
             Self::Anchor(id, ..)
             | Self::Circle(id, _, _)
             | Self::Drawing(_, id, ..)
-            | Self::Sprite(id, ..) => Some(*id),
+            | Self::Sprite(id, ..)
+            | Self::Template(id, ..) => Some(*id),
             _ => None,
         }
     }
@@ -122,7 +130,9 @@ impl HeldObject {
                 let theta = centre.angle(at);
                 Cursor::for_angle(theta)
             }
-            Self::Drawing(..) => Cursor::Crosshair,
+            Self::Drawing(..) | Self::Erase | Self::Ruler(..) | Self::Template(..) => {
+                Cursor::Crosshair
+            }
             Self::Marquee(..) | Self::None => Cursor::Default,
             Self::Selection(..) | Self::Sprite(..) => Cursor::Move,
         }
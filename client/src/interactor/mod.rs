@@ -1,22 +1,29 @@
+use std::collections::{HashMap, HashSet};
+
 use bincode::serialize;
 use scene::comms::ServerEvent;
 use scene::Outline;
 use scene::Project;
 use uuid::Uuid;
 
+use self::guides::Guide;
 use self::holding::HeldObject;
+use crate::dom::chat::ChatEntry;
 use crate::dom::menu::CanvasDropdownEvent;
 use crate::dom::menu::LayerInfo;
 use crate::scene::{
-    comms::SceneEvent, perms::Perms, Dimension, Id, Layer, Point, Rect, Scene, Shape, Sprite,
-    SpriteVisual,
+    comms::SceneEvent,
+    perms::{LayerPerms, Perms},
+    Dimension, Id, Layer, Point, Rect, Scene, Shape, Sprite, SpriteVisual,
 };
 use crate::{bridge::Cursor, client::Client};
 
 pub mod changes;
 pub mod details;
+pub mod guides;
 pub mod history;
 pub mod holding;
+pub mod path;
 
 #[cfg(test)]
 mod test;
@@ -27,10 +34,66 @@ pub struct Interactor {
     pub role: scene::perms::Role,
     project: Project,
     copied: Option<Vec<Sprite>>,
+
+    /// Sprites whose moves were rejected by the server, likely because
+    /// another editor moved them at the same time, and when the latest
+    /// rejection was received.
+    conflict_sprites: HashSet<Id>,
+    conflict_timestamp: u64,
+
+    /// Last known cursor positions of other players in the game.
+    cursors: HashMap<Uuid, Point>,
+
+    /// Whether the held sprite was last snapped flush against a neighbour.
+    edge_snapped: bool,
     fog_brush: f32,
+
+    /// Alignment guides shown while dragging a sprite.
+    guides: Vec<Guide>,
+
+    /// Media key of the handout being shown and the name of its sender.
+    handout: Option<(String, String)>,
     history: history::History,
     holding: HeldObject,
+    initiative: Vec<(String, i32)>,
+
+    /// Opacity of sprites on each layer when viewed as an editor, between 0
+    /// and 1. Layers not present are drawn fully opaque. This is local to
+    /// this client and not shared with the game.
+    layer_gm_opacity: HashMap<Id, f32>,
+
+    /// Entries received for the chat log, not yet shown.
+    log: Vec<ChatEntry>,
+
+    /// Events issued while recording a macro.
+    macro_events: Vec<SceneEvent>,
+
+    /// Whether to check for overlapping sprites as they change.
+    overlap_check: bool,
+
+    /// Pairs of sprites found overlapping by the last check.
+    overlaps: Vec<(Id, Id)>,
+
+    /// Sprite which is this player's token, if any.
+    own_sprite: Option<Id>,
+
+    /// Sprite moving along a path, if any.
+    path_animation: Option<path::PathAnimation>,
+
+    /// Positions of the sprite whose path is being recorded, or of the last
+    /// sprite whose path was recorded.
+    path_points: Vec<Point>,
+
+    /// Sprite whose path is being recorded, if any.
+    path_recording: Option<Id>,
     perms: Perms,
+
+    /// Pressure of the stylus providing the current input, if any.
+    pressure: Option<f32>,
+
+    /// Display names and avatar media keys of players in the game.
+    players: HashMap<Uuid, (String, Option<String>)>,
+    recording: bool,
     scene: Scene,
     selected_layer: Id,
     selected_sprites: Vec<Id>,
@@ -38,11 +101,23 @@ pub struct Interactor {
     /// Whether all sprites in the selection are aligned to the grid.
     selection_aligned: bool,
     selection_marquee: Option<Rect>,
+
+    /// Distance in tiles within which dragged sprites snap flush against the
+    /// edges of neighbouring sprites.
+    snap_radius: f32,
     user: Uuid,
+
+    /// Last known viewports of other editors in the game, in scene units.
+    viewports: HashMap<Uuid, Rect>,
 }
 
 impl Interactor {
     pub const DEFAULT_FOG_BRUSH: f32 = 1.0;
+    pub const DEFAULT_SNAP_RADIUS: f32 = 0.25;
+    pub const DEFAULT_PATH_DURATION_MS: u64 = 3000;
+
+    /// How long sprites are marked after a conflicting move.
+    pub const CONFLICT_DURATION_MS: u64 = 2000;
 
     /// This special ID will not belong to any sprite, and will instead be used
     /// to refer to all currently selected sprites.
@@ -56,25 +131,150 @@ impl Interactor {
             changes: changes::Changes::new(),
             role: scene::perms::Role::Owner,
             copied: None,
+            conflict_sprites: HashSet::new(),
+            conflict_timestamp: 0,
+            cursors: HashMap::new(),
+            edge_snapped: false,
             fog_brush: Self::DEFAULT_FOG_BRUSH,
+            guides: Vec::new(),
             history: history::History::new(client),
             holding: HeldObject::None,
+            initiative: Vec::new(),
+            handout: None,
+            layer_gm_opacity: HashMap::new(),
+            log: Vec::new(),
+            macro_events: Vec::new(),
+            overlap_check: false,
+            overlaps: Vec::new(),
+            own_sprite: None,
+            path_animation: None,
+            path_points: Vec::new(),
+            path_recording: None,
             perms: Perms::new(),
+            pressure: None,
+            players: HashMap::new(),
             project,
+            recording: false,
             scene,
             selected_layer,
             selected_sprites: Vec::new(),
             selection_aligned: true,
             selection_marquee: None,
+            snap_radius: Self::DEFAULT_SNAP_RADIUS,
             user: scene::perms::CANONICAL_UPDATER,
+            viewports: HashMap::new(),
+        }
+    }
+
+    pub fn project_uuid(&self) -> String {
+        self.project.uuid.simple().to_string()
+    }
+
+    /// Whether this interactor is connected to a game.
+    pub fn online(&self) -> bool {
+        self.history.online()
+    }
+
+    pub fn initiative(&self) -> &[(String, i32)] {
+        &self.initiative
+    }
+
+    pub fn handout(&self) -> Option<&(String, String)> {
+        self.handout.as_ref()
+    }
+
+    /// Stop showing the current handout.
+    pub fn close_handout(&mut self) {
+        if self.handout.take().is_some() {
+            self.changes.handout_change();
+        }
+    }
+
+    /// Show the image `media_key` to `recipient`, or to all players if None.
+    pub fn show_handout(&mut self, media_key: String, recipient: Option<Uuid>) {
+        if self.role.editor() {
+            self.history.show_handout(media_key, recipient);
         }
     }
 
+    /// Close handouts for all players, including those still to be sent.
+    pub fn dismiss_handout(&mut self) {
+        if self.role.editor() {
+            self.history.dismiss_handout();
+        }
+    }
+
+    pub fn set_initiative(&mut self, name: String, roll: i32) {
+        self.history.set_initiative(name, roll);
+    }
+
+    pub fn clear_initiative(&mut self) {
+        self.history.clear_initiative();
+    }
+
+    pub fn roll_dice(&mut self, notation: String) {
+        self.history.roll_dice(notation);
+    }
+
+    pub fn send_chat(&mut self, text: String) {
+        self.history.send_chat(text);
+    }
+
+    /// Share the local cursor position and viewport with other players.
+    pub fn move_cursor(&mut self, at: Point, viewport: Rect) {
+        self.history.move_cursor(at, viewport);
+    }
+
+    /// Cursor positions of other players, each with a colour derived from
+    /// the player's ID so that it is consistent between clients, and the
+    /// player's display name if known.
+    pub fn cursors(&self) -> Vec<(Point, scene::Colour, Option<&str>)> {
+        const PALETTE: [[f32; 3]; 8] = [
+            [0.90, 0.10, 0.29],
+            [0.24, 0.71, 0.29],
+            [0.00, 0.51, 0.78],
+            [0.96, 0.51, 0.19],
+            [0.57, 0.12, 0.71],
+            [0.27, 0.94, 0.94],
+            [0.94, 0.20, 0.90],
+            [0.82, 0.96, 0.24],
+        ];
+
+        self.cursors
+            .iter()
+            .map(|(user, at)| {
+                let [r, g, b] = PALETTE[(user.as_u128() % PALETTE.len() as u128) as usize];
+                let name = self.players.get(user).map(|(name, _)| name.as_str());
+                (*at, scene::Colour([r, g, b, 1.0]), name)
+            })
+            .collect()
+    }
+
+    /// Viewports of other editors, each with a translucent colour whose hue
+    /// is derived from the editor's ID so that it is consistent between
+    /// clients.
+    pub fn viewports(&self) -> Vec<(Rect, scene::Colour)> {
+        self.viewports
+            .iter()
+            .map(|(user, &viewport)| {
+                let hue = (user.as_u128() % 360) as f32 / 360.0;
+                (viewport, scene::Colour::hsv(hue, 0.7, 0.8, 0.3))
+            })
+            .collect()
+    }
+
+    /// Take log entries received since the last call.
+    pub fn take_log(&mut self) -> Vec<ChatEntry> {
+        std::mem::take(&mut self.log)
+    }
+
     pub fn scene_uuid(&self) -> String {
         self.scene.uuid.simple().to_string()
     }
 
     pub fn process_server_events(&mut self) -> Option<(Vec<(String, String)>, String)> {
+        self.history.send_pending_moves();
+
         let mut ret = None;
         if let Some(events) = self.history.server_events() {
             for event in events {
@@ -94,6 +294,24 @@ impl Interactor {
     ) -> Option<(Vec<(String, String)>, String)> {
         match event {
             ServerEvent::Approval(id) => self.history.approve_event(id),
+            ServerEvent::ChatMessage(user, name, text, timestamp) => {
+                let avatar = self
+                    .players
+                    .get(&user)
+                    .and_then(|(_, avatar)| avatar.clone());
+                self.log
+                    .push(ChatEntry::Message(name, text, timestamp, avatar));
+            }
+            ServerEvent::DiceResult(_, username, notation, rolls, total) => {
+                let text = if rolls.is_empty() {
+                    format!("rolled {notation}: {total}")
+                } else {
+                    let rolls: Vec<String> = rolls.iter().map(|r| r.to_string()).collect();
+                    format!("rolled {notation}: {} = {total}", rolls.join(", "))
+                };
+                self.log.push(ChatEntry::Dice(username, text));
+            }
+            ServerEvent::DismissHandout => self.close_handout(),
             ServerEvent::EventSet(events) => {
                 for event in events {
                     self.process_server_event(event);
@@ -105,7 +323,29 @@ impl Interactor {
             ServerEvent::GameOver => {
                 crate::bridge::game_over_redirect();
             }
+            ServerEvent::Handout(media_key, sender) => {
+                self.handout = Some((media_key, sender));
+                self.changes.handout_change();
+            }
             ServerEvent::HealthCheck => self.history.reply_to_health_check(),
+            ServerEvent::InitiativeList(list) => {
+                self.initiative = list;
+                self.changes.initiative_change();
+            }
+            ServerEvent::PlayerCursor(user, at) => {
+                if let Some(at) = at {
+                    self.cursors.insert(user, at);
+                } else {
+                    self.cursors.remove(&user);
+                    self.viewports.remove(&user);
+                }
+            }
+            ServerEvent::PlayerViewport(user, viewport) => {
+                self.viewports.insert(user, viewport);
+            }
+            ServerEvent::PlayerInfo(user, name, avatar) => {
+                self.players.insert(user, (name, avatar));
+            }
             ServerEvent::Rejection(id) => {
                 if let Some(event) = self.history.take_event(id) {
                     self.unwind_event(event);
@@ -135,14 +375,26 @@ impl Interactor {
             }
             ServerEvent::SceneUpdate(scene_event) => {
                 self.changes.layer_change_if(scene_event.is_layer());
+                self.changes.scene_change_if(scene_event.is_scene());
+                let is_sprite = scene_event.is_sprite();
                 self.scene.apply_event(scene_event);
+                if is_sprite {
+                    self.update_overlaps();
+                    self.update_own_sprite();
+                }
             }
             ServerEvent::SelectedLayer(layer) => {
                 self.selected_layer = layer;
             }
+            ServerEvent::Sequence(_) => {} // Tracked by the socket for reconnection.
             ServerEvent::UserId(id) => {
                 self.user = id;
                 self.update_role();
+                self.update_own_sprite();
+            }
+            ServerEvent::OwnSprite(id) => {
+                self.own_sprite = Some(id);
+                self.changes.own_sprite_change();
             }
         };
 
@@ -157,9 +409,40 @@ impl Interactor {
             self.holding = HeldObject::None;
         }
 
+        self.mark_conflicts(&event, crate::bridge::timestamp_ms());
         self.changes.layer_change_if(event.is_layer());
         self.changes.sprite_selected_change();
         self.scene.unwind_event(event);
+        self.update_overlaps();
+    }
+
+    /// Mark the sprites moved by a rejected `event`, received at time `now`,
+    /// so that the user can see why they jumped back.
+    fn mark_conflicts(&mut self, event: &SceneEvent, now: u64) {
+        match event {
+            SceneEvent::SpriteMove(id, ..) => {
+                self.conflict_sprites.insert(*id);
+                self.conflict_timestamp = now;
+            }
+            SceneEvent::EventSet(events) => {
+                events.iter().for_each(|e| self.mark_conflicts(e, now));
+            }
+            _ => {}
+        }
+    }
+
+    /// Outlines of sprites marked by conflicting moves.
+    pub fn conflicts(&self) -> Vec<Outline> {
+        self.conflict_sprites
+            .iter()
+            .filter_map(|&id| self.scene.sprite_ref(id))
+            .map(Sprite::outline)
+            .collect()
+    }
+
+    /// Time since the latest conflicting move at time `now`.
+    pub fn conflict_age(&self, now: u64) -> u64 {
+        now.saturating_sub(self.conflict_timestamp)
     }
 
     fn change_if(&mut self, event: &SceneEvent) {
@@ -177,12 +460,23 @@ impl Interactor {
     }
 
     fn scene_event(&mut self, event: SceneEvent) {
-        let layer = self.scene.event_layer(&event);
-        if self.perms.permitted(self.user, &event, layer) {
+        if self.scene.permitted(&self.perms, self.user, &event) {
+            // The server grants the same overrides once it accepts the event,
+            // but later events may rely on them before then.
+            self.perms.grant_new_sprites(self.user, &event);
             self.change_if(&event);
+            if event.is_sprite() {
+                self.update_overlaps();
+                self.update_own_sprite();
+            }
+            if self.recording {
+                self.macro_events.push(event.clone());
+            }
             self.history.issue_event(event);
         } else {
             crate::bridge::log!("forbidden: {event:?}");
+            // Refresh the scene menu, which may show the forbidden change.
+            self.changes.scene_change_if(event.is_scene());
             self.scene.unwind_event(event);
         }
     }
@@ -212,6 +506,7 @@ impl Interactor {
                 self.changes.sprite_selected_change();
             }
             self.history.issue_redo(opt);
+            self.update_overlaps();
         }
     }
 
@@ -223,9 +518,51 @@ impl Interactor {
                 self.changes.layer_change_if(layers_changed);
                 self.changes.sprite_selected_change();
             }
+            self.update_overlaps();
+        }
+    }
+
+    /// Set the pressure of the stylus providing the current input, which
+    /// scales the width of freehand drawings. None for other inputs.
+    pub fn set_pressure(&mut self, pressure: Option<f32>) {
+        self.pressure = pressure;
+    }
+
+    /// Enable or disable checking for overlapping sprites. As every pair of
+    /// sprites is compared, this may be slow in busy scenes.
+    pub fn set_overlap_check(&mut self, check: bool) {
+        self.overlap_check = check;
+        if check {
+            self.update_overlaps();
+        } else if !self.overlaps.is_empty() {
+            self.overlaps.clear();
+            self.changes.sprite_change();
         }
     }
 
+    fn update_overlaps(&mut self) {
+        if !self.overlap_check {
+            return;
+        }
+
+        let overlaps = self.scene.overlapping_sprites();
+        if overlaps != self.overlaps {
+            self.overlaps = overlaps;
+            self.changes.sprite_change();
+        }
+    }
+
+    /// Outlines of each sprite overlapping another sprite.
+    pub fn overlaps(&self) -> Vec<Outline> {
+        let mut ids: Vec<Id> = self.overlaps.iter().flat_map(|&(a, b)| [a, b]).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter()
+            .filter_map(|id| self.scene.sprite_ref(id))
+            .map(Sprite::outline)
+            .collect()
+    }
+
     pub fn save_required(&self) -> bool {
         self.history.save_required()
     }
@@ -285,6 +622,185 @@ impl Interactor {
         self.scene_events(events);
     }
 
+    /// Start recording scene events for playback as a macro.
+    pub fn start_recording(&mut self) {
+        self.macro_events.clear();
+        self.recording = true;
+    }
+
+    /// Stop recording, returning the events recorded.
+    pub fn stop_recording(&mut self) -> Vec<SceneEvent> {
+        self.recording = false;
+        std::mem::take(&mut self.macro_events)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Start recording the positions `sprite` is dragged through, for
+    /// playback with `play_path`.
+    pub fn start_path_record(&mut self, sprite: Id) {
+        self.path_points.clear();
+        if let Some(sprite) = self.sprite_ref(sprite) {
+            self.path_points.push(sprite.rect.top_left());
+        }
+        self.path_recording = Some(sprite);
+    }
+
+    /// Stop recording a path, returning the positions recorded.
+    pub fn stop_path_record(&mut self) -> Vec<Point> {
+        self.path_recording = None;
+        self.path_points.clone()
+    }
+
+    pub fn is_recording_path(&self) -> bool {
+        self.path_recording.is_some()
+    }
+
+    /// Positions recorded by the last path recording.
+    pub fn recorded_path(&self) -> &[Point] {
+        &self.path_points
+    }
+
+    /// Add the position of the sprite whose path is being recorded, if it has
+    /// moved.
+    fn record_path_point(&mut self) {
+        if let Some(at) = self
+            .path_recording
+            .and_then(|id| self.sprite_ref(id))
+            .map(|s| s.rect.top_left())
+            && self.path_points.last() != Some(&at)
+        {
+            self.path_points.push(at);
+        }
+    }
+
+    /// Move `sprite` along `path` over `duration_ms`, advanced by `tick`. The
+    /// animation is undone in a single step.
+    pub fn play_path(&mut self, sprite: Id, path: &[Point], duration_ms: u64) {
+        self.stop_path();
+        if path.is_empty() || self.sprite_ref(sprite).is_none() {
+            return;
+        }
+
+        self.history.start_move_group();
+        self.path_animation = Some(path::PathAnimation::new(sprite, path, duration_ms));
+    }
+
+    /// Stop any path animation, leaving the sprite where it is.
+    pub fn stop_path(&mut self) {
+        if self.path_animation.take().is_some() {
+            self.history.end_move_group();
+            self.changes.sprite_selected_change();
+        }
+    }
+
+    pub fn is_playing_path(&self) -> bool {
+        self.path_animation.is_some()
+    }
+
+    /// Advance the path animation, if any, to time `now`, and clear conflict
+    /// markers once they have been shown for long enough.
+    pub fn tick(&mut self, now: u64) {
+        if !self.conflict_sprites.is_empty() {
+            if self.conflict_age(now) >= Self::CONFLICT_DURATION_MS {
+                self.conflict_sprites.clear();
+            }
+            // Redraw each frame while markers are shown, as they pulse.
+            self.changes.sprite_change();
+        }
+
+        let Some(animation) = &mut self.path_animation else {
+            return;
+        };
+
+        let sprite = animation.sprite;
+        let (at, done) = animation.step(now);
+        let opt = self
+            .scene
+            .sprite(sprite)
+            .filter(|s| s.rect.top_left() != at)
+            .map(|s| s.set_pos(at));
+        self.scene_option(opt);
+
+        if done || self.sprite_ref(sprite).is_none() {
+            self.stop_path();
+        }
+    }
+
+    /// Replay a recorded macro, translated so that the first sprite it
+    /// placed or moved lands at `at`. Sprites created by the macro are
+    /// created anew and later events in the macro apply to the new sprites.
+    /// Events affecting other sprites are skipped.
+    pub fn play_macro(&mut self, events: &[SceneEvent], at: Point) {
+        let Some(first) = events.iter().find_map(SceneEvent::first_point) else {
+            return;
+        };
+
+        let delta = at - first;
+        let mut ids = HashMap::new();
+        let mut replayed = Vec::new();
+        for event in events {
+            self.replay_event(event, delta, &mut ids, &mut replayed);
+        }
+        self.scene_events(replayed);
+    }
+
+    fn replay_event(
+        &mut self,
+        event: &SceneEvent,
+        delta: Point,
+        ids: &mut HashMap<Id, Id>,
+        to: &mut Vec<SceneEvent>,
+    ) {
+        let sprite = event.sprite().and_then(|id| ids.get(&id)).copied();
+        let event = match event {
+            SceneEvent::EventSet(events) => {
+                for event in events {
+                    self.replay_event(event, delta, ids, to);
+                }
+                return;
+            }
+            SceneEvent::SpriteNew(sprite, layer) => {
+                let layer = if self.scene.layer_ref(*layer).is_some() {
+                    *layer
+                } else {
+                    self.selected_layer
+                };
+                let mut new = sprite.clone();
+                new.rect = new.rect.translate(delta);
+                let event = self.scene.add_sprite_copy(new, layer);
+                if let Some(SceneEvent::SpriteNew(new, _)) = &event {
+                    ids.insert(sprite.id, new.id);
+                }
+                event
+            }
+            SceneEvent::SpriteMove(_, _, rect) => sprite
+                .and_then(|id| self.scene.sprite(id))
+                .map(|s| s.set_rect(rect.translate(delta))),
+            SceneEvent::SpriteVisual(_, _, visual) => sprite
+                .and_then(|id| self.scene.sprite(id))
+                .map(|s| s.set_visual(visual.clone())),
+            SceneEvent::SpriteStrokePattern(_, _, pattern) => sprite
+                .and_then(|id| self.scene.sprite(id))
+                .and_then(|s| s.set_pattern(*pattern)),
+            SceneEvent::SpriteRemove(..) => sprite.and_then(|id| self.scene.remove_sprite(id)),
+            _ => None,
+        };
+
+        if let Some(event) = event {
+            to.push(event);
+        }
+    }
+
+    /// Make the scene read-only by acting as a user with no role, who is
+    /// treated as a spectator.
+    pub fn spectate(&mut self) {
+        self.user = Uuid::now_v7();
+        self.update_role();
+    }
+
     fn update_role(&mut self) {
         self.role = self.perms.get_role(self.user);
         crate::dom::update_interface(self.role);
@@ -346,7 +862,9 @@ impl Interactor {
 
     fn selectable(&self, sprite: &Sprite, require_visible: bool) -> bool {
         if let Some(layer) = self.scene.get_sprite_layer(sprite.id) {
-            self.perms.selectable(self.user, sprite.id, layer)
+            let layer_perms = self.scene.layer_ref(layer).map(|l| &l.perms);
+            self.perms
+                .selectable(self.user, sprite.id, layer, layer_perms, sprite.owner)
                 && (!require_visible
                     || self.role.editor()
                     || !self.scene.fog.rect_occluded(sprite.rect))
@@ -373,7 +891,11 @@ impl Interactor {
 
     /// Select a sprite.
     fn select(&mut self, id: Id) -> bool {
-        if let Some(g) = self.scene.sprite_group(id).map(|g| g.sprites().to_owned()) {
+        if let Some(g) = self
+            .scene
+            .sprite_group(id)
+            .map(|g| self.scene.group_members(g.id))
+        {
             let mut successful = false;
             for id in g {
                 successful |= self._select(id, false);
@@ -390,6 +912,74 @@ impl Interactor {
         }
     }
 
+    /// Add all selectable sprites whose visual satisfies `predicate` to the
+    /// selection.
+    fn select_matching<F: Fn(&SpriteVisual) -> bool>(&mut self, predicate: F) {
+        let ids: Vec<Id> = self
+            .scene
+            .sprites()
+            .filter(|s| predicate(&s.visual))
+            .map(|s| s.id)
+            .collect();
+        for id in ids {
+            self._select(id, true);
+        }
+    }
+
+    pub fn select_by_colour(&mut self, colour: scene::Colour) {
+        self.select_matching(|v| v.colour() == Some(colour));
+    }
+
+    pub fn select_by_shape(&mut self, shape: Shape) {
+        self.select_matching(|v| v.shape() == Some(shape));
+    }
+
+    pub fn select_by_texture(&mut self, id: Id) {
+        self.select_matching(|v| v.texture() == Some(id));
+    }
+
+    /// Add sprites which look like `id` to the selection. Textured sprites
+    /// match sprites with the same texture and shapes match shapes of the
+    /// same kind and colour.
+    pub fn select_similar_to(&mut self, id: Id) {
+        let Some(visual) = self.sprite_ref(id).map(|s| s.visual.clone()) else {
+            return;
+        };
+
+        match visual {
+            SpriteVisual::Texture { id, .. } => self.select_by_texture(id),
+            SpriteVisual::Shape { shape, colour, .. } => {
+                self.select_matching(|v| v.shape() == Some(shape) && v.colour() == Some(colour))
+            }
+            SpriteVisual::Drawing { .. } | SpriteVisual::Light { .. } => {}
+        }
+    }
+
+    /// Add sprites which look like the first selected sprite to the
+    /// selection.
+    pub fn select_similar_to_selection(&mut self) {
+        if let Some(&id) = self.selected_sprites.first() {
+            self.select_similar_to(id);
+        }
+    }
+
+    /// Replace the selection with every selectable sprite on visible,
+    /// unlocked layers which isn't currently selected.
+    pub fn invert_selection(&mut self) {
+        let ids: Vec<Id> = self
+            .scene
+            .sprites()
+            .filter(|s| !self.is_selected(s.id) && self.selectable(s, true))
+            .map(|s| s.id)
+            .collect();
+        self.selection_aligned = ids
+            .iter()
+            .filter_map(|&id| self.sprite_ref(id))
+            .all(|s| s.rect.is_aligned());
+        self.selected_sprites = ids;
+        self.changes.sprite_selected_change();
+    }
+
     pub fn select_all(&mut self) {
         if let Some(l) = self.scene.layer(self.selected_layer) {
             self.selected_sprites = l.sprites.iter().map(|s| s.id).collect();
@@ -397,6 +987,202 @@ impl Interactor {
         }
     }
 
+    /// Reverse the direction of each selected drawing.
+    pub fn reverse_selected_drawings(&mut self) {
+        let drawings: Vec<Id> = self
+            .selected_sprites
+            .iter()
+            .filter_map(|&id| self.scene.sprite_ref(id)?.visual.drawing())
+            .collect();
+        let events = drawings
+            .into_iter()
+            .filter_map(|id| self.scene.reverse_drawing(id))
+            .collect();
+        self.scene_events(events);
+    }
+
+    /// Join the drawing of sprite `b` onto that of sprite `a`, connecting
+    /// their closest pair of endpoints, and remove `b`. At least one of the
+    /// drawings must be freehand.
+    pub fn join_drawings(&mut self, a: Id, b: Id) {
+        let drawing = |int: &Self, id: Id| {
+            let drawing = int.scene.sprite_ref(id)?.visual.drawing()?;
+            let freehand = int.scene.get_drawing(drawing)?.mode == scene::DrawingMode::Freehand;
+            Some((drawing, freehand))
+        };
+        let (Some((da, freehand_a)), Some((db, freehand_b))) = (drawing(self, a), drawing(self, b))
+        else {
+            return;
+        };
+
+        // Only freehand drawings can be joined onto.
+        let ((a, da), (b, db)) = match (freehand_a, freehand_b) {
+            (true, _) => ((a, da), (b, db)),
+            (false, true) => ((b, db), (a, da)),
+            (false, false) => return,
+        };
+
+        let (Some((a_start, a_end)), Some((b_start, b_end))) =
+            (self.scene.drawing_ends(da), self.scene.drawing_ends(db))
+        else {
+            return;
+        };
+
+        // Points are joined onto the end of the drawing, so if its start is
+        // closer, reverse it first, keeping its caps in place.
+        let mut events = Vec::new();
+        let to_start = a_start.dist(b_start).min(a_start.dist(b_end));
+        let to_end = a_end.dist(b_start).min(a_end.dist(b_end));
+        if to_start < to_end {
+            events.extend(self.scene.reverse_drawing(da));
+            events.extend(self.scene.sprite(a).and_then(Sprite::flip_caps));
+        }
+
+        match self.scene.join_drawings(da, db) {
+            Some(event) => events.push(event),
+            None => {
+                for event in events.into_iter().rev() {
+                    self.scene.unwind_event(event);
+                }
+                return;
+            }
+        }
+        events.extend(self.scene.remove_sprite(b));
+
+        if self.is_selected(b) {
+            self.selected_sprites.retain(|&id| id != b);
+            self.changes.sprite_selected_change();
+        }
+        self.scene_events(events);
+    }
+
+    /// Join the two selected drawings into one.
+    pub fn join_selected_drawings(&mut self) {
+        if let &[a, b] = self.selected_sprites.as_slice() {
+            self.join_drawings(a, b);
+        }
+    }
+
+    /// Split freehand drawing `drawing_id` in two at `point_index`, which
+    /// ends the first drawing and starts the second. The new drawings copy
+    /// the original sprite, with its start cap and taper kept on the first
+    /// and its end cap and taper on the second, and the original is removed.
+    pub fn split_drawing_at(&mut self, drawing_id: Id, point_index: usize) {
+        let Some(vector) = self.scene.drawing_points(drawing_id) else {
+            return;
+        };
+        let mut points = Vec::new();
+        vector.iter(|point| points.push(point));
+        if point_index == 0 || point_index + 1 >= points.len() {
+            return;
+        }
+
+        let Some(original) = self
+            .scene
+            .sprite_drawing(drawing_id)
+            .and_then(|id| self.scene.sprite_ref(id))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(layer) = self.scene.get_sprite_layer(original.id) else {
+            return;
+        };
+        let pressures = self
+            .scene
+            .get_drawing(drawing_id)
+            .and_then(|d| d.pressures())
+            .map(<[f32]>::to_vec);
+
+        let mut events = Vec::new();
+        let mut halves = Vec::new();
+        for range in [0..point_index + 1, point_index..points.len()] {
+            let start = points[range.start];
+            let (id, event) = self
+                .scene
+                .start_drawing(scene::DrawingMode::Freehand, start);
+            events.extend(event);
+            halves.push((id, range));
+        }
+
+        for (id, range) in &halves {
+            for i in range.clone().skip(1) {
+                let pressure = pressures.as_ref().and_then(|p| p.get(i).copied());
+                events.extend(
+                    self.scene
+                        .add_drawing_point_with_pressure(*id, points[i], pressure),
+                );
+            }
+        }
+
+        for (i, (id, _)) in halves.into_iter().enumerate() {
+            let Some(rect) = self.scene.get_drawing(id).map(scene::Drawing::rect) else {
+                continue;
+            };
+            let mut sprite = original.clone();
+            sprite.rect = rect;
+            if let SpriteVisual::Drawing {
+                drawing,
+                cap_start,
+                cap_end,
+                taper_start,
+                taper_end,
+                ..
+            } = &mut sprite.visual
+            {
+                *drawing = id;
+                if i == 0 {
+                    *cap_end = scene::Cap::None;
+                    *taper_end = false;
+                } else {
+                    *cap_start = scene::Cap::None;
+                    *taper_start = false;
+                }
+            }
+            events.extend(self.scene.add_sprite_copy(sprite, layer));
+        }
+        events.extend(self.scene.remove_sprite(original.id));
+
+        if self.is_selected(original.id) {
+            self.selected_sprites.retain(|&id| id != original.id);
+            self.changes.sprite_selected_change();
+        }
+        self.scene_events(events);
+    }
+
+    /// Split the selected drawing at its point closest to `at`.
+    pub fn split_selected_drawing(&mut self, at: Point) {
+        let Some(drawing) = self
+            .selected_id()
+            .and_then(|id| self.sprite_ref(id))
+            .and_then(|sprite| sprite.visual.drawing())
+        else {
+            return;
+        };
+        let Some(points) = self.scene.drawing_points(drawing) else {
+            return;
+        };
+
+        let mut closest = None;
+        let mut i = 0;
+        points.iter(|point| {
+            let dist = point.dist(at);
+            if closest.is_none_or(|(_, best)| dist < best) {
+                closest = Some((i, dist));
+            }
+            i += 1;
+        });
+
+        if let Some((index, _)) = closest {
+            self.split_drawing_at(drawing, index);
+        }
+    }
+
+    /// Swap the start and end caps of each selected drawing.
+    pub fn flip_selected_caps(&mut self) {
+        self.selection_effect(Sprite::flip_caps);
+    }
+
     /// Apply a closure to each selected sprite, issuing the resulting vector
     /// of events as a single EventSet event.
     fn selection_effect<F: Fn(&mut Sprite) -> Option<SceneEvent>>(&mut self, effect: F) {
@@ -508,6 +1294,31 @@ impl Interactor {
                     self.new_held_shape(details.shape.unwrap(), at, !alt, ephemeral, details);
                 }
             }
+            DrawTool::Ruler => self.holding = HeldObject::Ruler(at, at),
+            DrawTool::Template(template) => self.new_template(template, at, ephemeral, details),
+            // Text is placed with annotate once it has been entered.
+            DrawTool::Text => {}
+            DrawTool::Erase => {
+                self.holding = HeldObject::Erase;
+                self.history.start_move_group();
+                self.erase_drawings(at);
+            }
+            DrawTool::Light => {
+                const LIGHT_OPACITY: f32 = 0.4;
+
+                // Place the light in the tile clicked, or centred on the
+                // cursor if not snapping to the grid.
+                let at = if alt {
+                    at - Point::same(0.5)
+                } else {
+                    Point::new(at.x.floor(), at.y.floor())
+                };
+                let visual = SpriteVisual::Light {
+                    radius: Sprite::DEFAULT_LIGHT_RADIUS,
+                    colour: details.colour().with_opacity(LIGHT_OPACITY),
+                };
+                self.new_sprite_at(Some(visual), None, Rect::at(at, 1.0, 1.0));
+            }
             DrawTool::Cone | DrawTool::Freehand | DrawTool::Line => {
                 let Some(mode) = tool.mode() else {
                     return;
@@ -529,6 +1340,111 @@ impl Interactor {
         }
     }
 
+    /// Create an area of effect template at `at`, on the foreground layer.
+    /// Spheres are centred on `at` and follow the cursor while held. Cones
+    /// and lines start at `at` and rotate to point towards the cursor.
+    fn new_template(
+        &mut self,
+        template: crate::viewport::TemplateShape,
+        at: Point,
+        ephemeral: bool,
+        details: details::SpriteDetails,
+    ) {
+        use crate::viewport::TemplateShape;
+
+        const TEMPLATE_OPACITY: f32 = 0.3;
+
+        let colour = details.colour().with_opacity(TEMPLATE_OPACITY);
+        let layer = Some(self.scene.first_layer());
+        let (mode, length, stroke) = match template {
+            TemplateShape::Sphere(radius) => {
+                let visual =
+                    SpriteVisual::new_shape(colour, Shape::Ellipse, details.stroke(), true);
+                let rect = Rect::at(at - Point::same(radius), radius * 2.0, radius * 2.0);
+                if let Some(id) = self.new_sprite_at(Some(visual), layer, rect) {
+                    self.holding = HeldObject::Template(id, None, at, radius, ephemeral);
+                }
+                return;
+            }
+            TemplateShape::Cone(length) => (scene::DrawingMode::Cone, length, details.stroke()),
+            TemplateShape::Line(length, width) => (scene::DrawingMode::Line, length, width),
+        };
+
+        let (drawing, event_option) = self.scene.start_drawing(mode, at);
+        self.scene_option(event_option);
+        let visual = SpriteVisual::Drawing {
+            drawing,
+            colour,
+            stroke,
+            cap_start: scene::Cap::None,
+            cap_end: scene::Cap::None,
+            taper_start: false,
+            taper_end: false,
+        };
+        if let Some(id) = self.new_sprite_at(Some(visual), layer, Rect::at(at, 0.0, 0.0)) {
+            let opt = self
+                .scene
+                .add_drawing_point(drawing, at + Point::new(length, 0.0));
+            self.scene_option(opt);
+            self.holding = HeldObject::Template(id, Some(drawing), at, length, ephemeral);
+        }
+    }
+
+    fn drag_template(
+        &mut self,
+        sprite: Id,
+        drawing: Option<Id>,
+        origin: Point,
+        length: f32,
+        at: Point,
+    ) {
+        if let Some(drawing) = drawing {
+            let dist = origin.dist(at);
+            if dist > 0.0 {
+                let end = origin + (at - origin) * (length / dist);
+                let opt = self.scene.add_drawing_point(drawing, end);
+                self.scene_option(opt);
+            }
+        } else if let Some(s) = self.scene.sprite(sprite) {
+            let event = s.set_pos(at - Point::same(length));
+            self.scene_event(event);
+        }
+    }
+
+    /// Remove the template being placed, if any.
+    pub fn discard_template(&mut self) {
+        if let HeldObject::Template(sprite, ..) = self.holding {
+            self.remove_sprite(sprite);
+            self.history.erase_item(sprite);
+            self.holding = HeldObject::None;
+        }
+    }
+
+    /// Place a text drawing showing `text` at `at`.
+    pub fn annotate(&mut self, at: Point, text: String, details: details::SpriteDetails) {
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let mut visual = details.drawing();
+        let (drawing_id, event_option) = self.scene.start_drawing(scene::DrawingMode::Text, at);
+        self.scene_option(event_option);
+        if let SpriteVisual::Drawing { drawing, .. } = &mut visual {
+            *drawing = drawing_id;
+        }
+
+        if self
+            .new_sprite_at(Some(visual), None, Rect::at(at, 0.0, 0.0))
+            .is_some()
+        {
+            let opt = self.scene.annotate_drawing(drawing_id, text, at);
+            self.scene_option(opt);
+        }
+
+        // Group the drawing and sprite creation into a single undo step.
+        self.history.end_move_group();
+    }
+
     fn update_held_sprite(&mut self, at: Point, maintain_aspect_ratio: bool) {
         let held = self.holding.clone();
         let sprite = if let Some(s) = self.held_sprite_mut() {
@@ -547,7 +1463,11 @@ impl Interactor {
                     h: 2.0 * r,
                 })
             }
-            HeldObject::Sprite(_, offset, _) => sprite.set_pos(at - offset),
+            HeldObject::Sprite(id, offset, _) => {
+                let to = self.snap_to_edges(id, at - offset);
+                self.drag_sprite(id, to);
+                return;
+            }
             HeldObject::Anchor(_, dx, dy, starting_rect, _) => {
                 let Point {
                     x: delta_x,
@@ -581,12 +1501,85 @@ impl Interactor {
         self.holding = HeldObject::Selection(to);
     }
 
+    /// Rects of sprites other than `id` on its layer which intersect
+    /// `region`.
+    fn neighbours(&self, id: Id, region: Rect) -> Vec<Rect> {
+        self.scene
+            .get_sprite_layer(id)
+            .and_then(|layer| self.scene.layer_ref(layer))
+            .map(|layer| {
+                layer
+                    .sprites
+                    .iter()
+                    .filter(|s| s.id != id && s.rect.intersects(region))
+                    .map(|s| s.rect)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Adjust `to`, a new position for sprite `id`, so that the sprite sits
+    /// flush against any neighbouring sprite edge within the snap radius.
+    fn snap_to_edges(&mut self, id: Id, to: Point) -> Point {
+        let Some(sprite) = self.scene.sprite_ref(id) else {
+            return to;
+        };
+
+        let rect = sprite.rect.moved_to(to).positive_dimensions();
+        let region = Rect::new(
+            rect.x - self.snap_radius,
+            rect.y - self.snap_radius,
+            rect.w + self.snap_radius * 2.0,
+            rect.h + self.snap_radius * 2.0,
+        );
+        let others = self.neighbours(id, region);
+        let delta = guides::abut(rect, &others, self.snap_radius);
+        self.edge_snapped = delta.is_some();
+        to + delta.unwrap_or(Point::ORIGIN)
+    }
+
+    pub fn set_snap_radius(&mut self, radius: f32) {
+        self.snap_radius = radius.max(0.0);
+    }
+
+    /// Move sprite `id` to `to`, snapping it into alignment with nearby
+    /// sprites on the same layer.
+    fn drag_sprite(&mut self, id: Id, to: Point) {
+        let Some(sprite) = self.scene.sprite_ref(id) else {
+            return;
+        };
+
+        let rect = sprite.rect.moved_to(to);
+        let others = self.neighbours(id, guides::nearby(rect));
+        let (delta, guides) = guides::align(rect, &others);
+        self.guides = guides;
+        if let Some(sprite) = self.scene.sprite(id) {
+            let event = sprite.set_pos(to + delta);
+            self.scene_event(event);
+        }
+    }
+
+    /// Alignment guides to show for the sprite being dragged.
+    pub fn guides(&self) -> &[Guide] {
+        &self.guides
+    }
+
     pub fn drag(&mut self, at: Point, shift: bool) {
         match self.holding {
             HeldObject::Drawing(d, _sprite, _ephemeral, _measurement) => {
-                let opt = self.scene.add_drawing_point(d, at);
+                let opt = self
+                    .scene
+                    .add_drawing_point_with_pressure(d, at, self.pressure);
                 self.scene_option(opt);
             }
+            HeldObject::Erase => self.erase_drawings(at),
+            HeldObject::Ruler(from, _) => {
+                self.holding = HeldObject::Ruler(from, at);
+                self.changes.sprite_selected_change();
+            }
+            HeldObject::Template(sprite, drawing, origin, length, _) => {
+                self.drag_template(sprite, drawing, origin, length, at)
+            }
             HeldObject::Marquee(from) => {
                 self.selection_marquee = Some(from.rect(at));
                 self.changes.sprite_selected_change();
@@ -597,6 +1590,12 @@ impl Interactor {
                 self.update_held_sprite(at, shift)
             }
         };
+        self.record_path_point();
+    }
+
+    fn erase_drawings(&mut self, at: Point) {
+        let opt = self.scene.erase_drawings(at);
+        self.scene_option(opt);
     }
 
     fn add_sprite_measurements(&self, sprite: Id, to: &mut Vec<(Point, f32)>) {
@@ -613,6 +1612,7 @@ impl Interactor {
                         ));
                     }
                 }
+                SpriteVisual::Light { radius, .. } => to.push((sprite.rect.centre(), radius)),
                 SpriteVisual::Shape { shape, .. } | SpriteVisual::Texture { shape, .. } => {
                     match shape {
                         Shape::Ellipse | Shape::Hexagon if sprite.rect.w == sprite.rect.h => {
@@ -650,6 +1650,14 @@ impl Interactor {
         measurements
     }
 
+    /// Start and end of the ruler being dragged, if any.
+    pub fn ruler(&self) -> Option<(Point, Point)> {
+        match self.holding {
+            HeldObject::Ruler(from, to) => Some((from, to)),
+            _ => None,
+        }
+    }
+
     pub fn sprite_ref(&self, id: Id) -> Option<&Sprite> {
         self.scene.sprite_ref(id)
     }
@@ -706,7 +1714,10 @@ impl Interactor {
     }
 
     fn finish_sprite_drag(&mut self, id: Id, starting_rect: Rect, switch_align: bool) {
+        // Sprites snapped flush against a neighbour stay put rather than
+        // snapping to the grid.
         if !self.apply_ignore_threshold(id, starting_rect)
+            && !self.edge_snapped
             && (starting_rect.is_aligned() ^ switch_align)
         {
             if let Some(s) = self.scene.sprite(id) {
@@ -735,14 +1746,18 @@ impl Interactor {
         match self.holding {
             HeldObject::Anchor(sprite, _, _, _, true)
             | HeldObject::Circle(sprite, _, true)
-            | HeldObject::Drawing(_, sprite, true, _) => {
+            | HeldObject::Drawing(_, sprite, true, _)
+            | HeldObject::Template(sprite, .., true) => {
                 // Ephemeral
                 self.remove_sprite(sprite);
                 self.history.erase_item(sprite);
             }
             HeldObject::Circle(id, _, _) => self.finish_circle(id, !alt),
-            HeldObject::Drawing(..) => self.history.end_move_group(),
+            HeldObject::Drawing(..) | HeldObject::Erase | HeldObject::Template(..) => {
+                self.history.end_move_group()
+            }
             HeldObject::None => {}
+            HeldObject::Ruler(..) => self.changes.sprite_selected_change(),
             HeldObject::Marquee(_) => {
                 if !ctrl {
                     self.clear_selection();
@@ -764,6 +1779,8 @@ impl Interactor {
             self.history.end_move_group();
         }
 
+        self.guides.clear();
+        self.edge_snapped = false;
         self.holding = HeldObject::None;
     }
 
@@ -845,6 +1862,43 @@ impl Interactor {
         self.scene_option(event_option);
     }
 
+    /// Reveal or occlude the fog in the rectangle with corners `from` and
+    /// `to`.
+    pub fn set_fog_rect(&mut self, from: Point, to: Point, reveal: bool) {
+        let rect = Rect::at(from, to.x - from.x, to.y - from.y);
+        let event_option = if reveal {
+            self.scene.fog.reveal_rect(rect)
+        } else {
+            self.scene.fog.occlude_rect(rect)
+        };
+        self.scene_option(event_option);
+    }
+
+    pub fn fill_fog(&mut self) {
+        let event_option = self.scene.fog.fill_all();
+        self.scene_option(event_option);
+    }
+
+    pub fn clear_fog(&mut self) {
+        let event_option = self.scene.fog.clear_all();
+        self.scene_option(event_option);
+    }
+
+    /// Download the scene fog as a greyscale PNG mask.
+    pub fn export_fog(&self) {
+        let title = &self.scene.title;
+        let name = if title.is_empty() { "scene" } else { title };
+        let result = self
+            .scene
+            .fog
+            .to_png()
+            .and_then(|bytes| crate::bridge::download_png(&bytes, &format!("{name}-fog.png")));
+
+        if let Err(e) = result {
+            crate::bridge::console_err(&format!("Failed to export fog: {e}"));
+        }
+    }
+
     #[must_use]
     pub fn export(&self) -> Vec<u8> {
         serialize(&self.scene).unwrap_or_default()
@@ -885,6 +1939,8 @@ impl Interactor {
         self.selected_sprites.clear();
         self.scene = new;
         self.changes.all_change();
+        self.update_overlaps();
+        self.update_own_sprite();
     }
 
     pub fn get_scene_details(&self) -> details::SceneDetails {
@@ -914,6 +1970,54 @@ impl Interactor {
         self.changes.sprite_change();
     }
 
+    pub fn set_background_texture(&mut self, texture: Option<Id>) {
+        let opt = self.scene.set_background_texture(texture);
+        self.scene_option(opt);
+        self.changes.sprite_change();
+    }
+
+    pub fn waypoints(&self) -> &[scene::Waypoint] {
+        &self.scene.waypoints
+    }
+
+    pub fn add_waypoint(&mut self, name: &str, viewport: Rect, grid_size: f32) {
+        if let Some(waypoint) = scene::Waypoint::new(name, viewport, grid_size) {
+            let opt = self.scene.add_waypoint(waypoint);
+            self.scene_option(opt);
+        }
+    }
+
+    pub fn remove_waypoint(&mut self, name: &str) {
+        let opt = self.scene.remove_waypoint(name);
+        self.scene_option(opt);
+    }
+
+    pub fn comments(&self) -> &[scene::Comment] {
+        &self.scene.comments
+    }
+
+    /// Add a comment by the local user, written at `timestamp` seconds since
+    /// the epoch, at scene position `at` if given.
+    pub fn add_comment(&mut self, text: &str, at: Option<Point>, timestamp: u64) {
+        let author = self
+            .players
+            .get(&self.user)
+            .map(|(name, _)| name.as_str())
+            .unwrap_or("GM");
+        if let Some(comment) = scene::Comment::new(author, text, at, timestamp) {
+            let event = self.scene.add_comment(comment);
+            self.scene_event(event);
+        }
+    }
+
+    pub fn remove_comment(&mut self, index: usize) {
+        let Some(comment) = self.scene.comments.get(index).cloned() else {
+            return;
+        };
+        let opt = self.scene.remove_comment(&comment);
+        self.scene_option(opt);
+    }
+
     pub fn new_layer(&mut self) {
         let z = self
             .scene
@@ -940,6 +2044,11 @@ impl Interactor {
         self.scene_option(opt);
     }
 
+    pub fn set_layer_description(&mut self, layer: Id, description: String) {
+        let opt = self.scene.set_layer_description(layer, Some(description));
+        self.scene_option(opt);
+    }
+
     pub fn select_layer(&mut self, layer: Id) {
         self.selected_layer = layer;
     }
@@ -960,6 +2069,15 @@ impl Interactor {
         }
     }
 
+    pub fn update_layer_perms<F: FnOnce(&mut LayerPerms)>(&mut self, layer: Id, update: F) {
+        if let Some(l) = self.scene.layer(layer) {
+            let mut perms = l.perms;
+            update(&mut perms);
+            let opt = l.set_perms(perms);
+            self.scene_option(opt);
+        }
+    }
+
     pub fn move_layer(&mut self, layer: Id, up: bool) {
         let opt = self.scene.move_layer(layer, up);
         self.scene_option(opt);
@@ -967,7 +2085,40 @@ impl Interactor {
     }
 
     pub fn layer_info(&self) -> Vec<LayerInfo> {
-        self.scene.layers.iter().map(LayerInfo::from).collect()
+        self.scene
+            .layers
+            .iter()
+            .map(|layer| {
+                let mut info = LayerInfo::from(layer);
+                info.gm_opacity = self.layer_gm_opacity(layer.id);
+                info
+            })
+            .collect()
+    }
+
+    pub fn layer_gm_opacity(&self, layer: Id) -> f32 {
+        self.layer_gm_opacity.get(&layer).copied().unwrap_or(1.0)
+    }
+
+    /// Set the opacity of sprites on `layer` when viewed as an editor.
+    pub fn set_layer_gm_opacity(&mut self, layer: Id, opacity: f32) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        if opacity == 1.0 {
+            self.layer_gm_opacity.remove(&layer);
+        } else {
+            self.layer_gm_opacity.insert(layer, opacity);
+        }
+        self.changes.sprite_change();
+    }
+
+    /// Sprite opacity for each layer to render with, if rendering as an
+    /// editor.
+    pub fn layer_opacity(&self) -> Option<&HashMap<Id, f32>> {
+        if self.role.editor() {
+            Some(&self.layer_gm_opacity)
+        } else {
+            None
+        }
     }
 
     fn new_sprite_common(
@@ -994,6 +2145,36 @@ impl Interactor {
         ret
     }
 
+    /// Create a sprite from `template` with its top left corner at `at`.
+    pub fn new_sprite_from_template(
+        &mut self,
+        template: &scene::SpriteTmpl,
+        at: Point,
+    ) -> Option<Id> {
+        let mut sprite = Sprite::new(0, Some(template.visual.clone()));
+        sprite.rect = template.rect.moved_to(at);
+        sprite.set_label(template.label.clone());
+
+        // Added as a copy so the sprite is created with its label in a single
+        // event, and so a single undo step.
+        let opt = self.scene.add_sprite_copy(sprite, self.selected_layer);
+        let id = opt.as_ref().and_then(SceneEvent::sprite);
+        self.scene_option(opt);
+        id
+    }
+
+    /// Template named `name` for the selected sprite, if a single sprite is
+    /// selected.
+    pub fn selected_template(&self, name: &str) -> Option<scene::SpriteTmpl> {
+        if !self.single_selected() {
+            return None;
+        }
+
+        self.selected_id()
+            .and_then(|id| self.sprite_ref(id))
+            .and_then(|s| scene::SpriteTmpl::of(name, s))
+    }
+
     fn new_sprite(&mut self, visual: Option<SpriteVisual>, layer: Option<Id>) -> Option<Id> {
         self.new_sprite_common(visual, layer, None)
     }
@@ -1100,6 +2281,7 @@ impl Interactor {
                     stroke: Sprite::SOLID_STROKE,
                     solid: true,
                     colour: colour.with_opacity(0.6),
+                    pattern: scene::StrokePattern::Solid,
                 },
                 Rect::at(c - Point::same(r), 2.0 * r, 2.0 * r),
             )
@@ -1116,6 +2298,74 @@ impl Interactor {
         }
     }
 
+    /// Keep track of this player's token as sprites change hands or are
+    /// removed.
+    fn update_own_sprite(&mut self) {
+        let user = self.user;
+        let own = self
+            .own_sprite
+            .filter(|&id| {
+                self.scene
+                    .sprite_ref(id)
+                    .is_some_and(|s| s.owner == Some(user))
+            })
+            .or_else(|| self.scene.owned_sprite(user));
+        if own != self.own_sprite {
+            self.own_sprite = own;
+            self.changes.own_sprite_change();
+        }
+    }
+
+    /// Area of this player's token, if they have one.
+    pub fn own_sprite_rect(&self) -> Option<Rect> {
+        self.own_sprite
+            .and_then(|id| self.scene.sprite_ref(id))
+            .map(|s| s.rect)
+    }
+
+    /// Make `owner` the player whose token `sprite` is, or clear the owner
+    /// if None.
+    pub fn set_sprite_owner(&mut self, sprite: Id, owner: Option<Uuid>) {
+        let opt = self.scene.sprite(sprite).and_then(|s| s.set_owner(owner));
+        self.scene_option(opt);
+    }
+
+    /// Display names of players in the game, sorted by name.
+    pub fn player_names(&self) -> Vec<(String, Uuid)> {
+        let mut names: Vec<(String, Uuid)> = self
+            .players
+            .iter()
+            .map(|(&user, (name, _))| (name.clone(), user))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Bounding rect of all selected sprites, padded by a tile on each side.
+    pub fn selection_bounds(&self) -> Option<Rect> {
+        const PADDING: f32 = 1.0;
+
+        let mut rects = self
+            .selected_sprites
+            .iter()
+            .filter_map(|&id| self.scene.sprite_ref(id))
+            .map(|s| s.rect.positive_dimensions());
+        let first = rects.next()?;
+        let (min, max) = rects.fold(
+            (first.top_left(), first.top_left() + first.dimensions()),
+            |(min, max), r| {
+                let (tl, br) = (r.top_left(), r.top_left() + r.dimensions());
+                (
+                    Point::new(min.x.min(tl.x), min.y.min(tl.y)),
+                    Point::new(max.x.max(br.x), max.y.max(br.y)),
+                )
+            },
+        );
+        let min = min - Point::same(PADDING);
+        let max = max + Point::same(PADDING);
+        Some(Rect::at(min, max.x - min.x, max.y - min.y))
+    }
+
     fn selected_id(&self) -> Option<Id> {
         match self.selected_sprites.len() {
             1 => Some(self.selected_sprites[0]),
@@ -1162,6 +2412,34 @@ impl Interactor {
         self.selection_effect(|s| Some(s.move_by(delta)));
     }
 
+    /// Move each selected sprite one place up or down within its layer.
+    fn reorder_selected(&mut self, forward: bool) {
+        let mut events = Vec::with_capacity(self.selected_sprites.len());
+        for &id in &self.selected_sprites {
+            let opt = if forward {
+                self.scene.bring_forward(id)
+            } else {
+                self.scene.send_back(id)
+            };
+
+            if let Some(event) = opt {
+                events.push(event);
+            }
+        }
+        self.scene_events(events);
+    }
+
+    /// Toggle a condition on the selected sprites. If all selected sprites have
+    /// the condition it is removed, otherwise it is added to all of them.
+    fn toggle_condition_selected(&mut self, condition: scene::Condition) {
+        let active = !self
+            .selected_sprites
+            .iter()
+            .filter_map(|&id| self.sprite_ref(id))
+            .all(|s| s.has_condition(&condition));
+        self.selection_effect(|s| s.set_condition(condition.clone(), active));
+    }
+
     fn group_selected(&mut self) {
         let event_option = self.scene.group_sprites(&self.selected_sprites);
         self.scene_option(event_option);
@@ -1182,11 +2460,22 @@ impl Interactor {
         details: details::SpriteDetails,
     ) {
         match event {
+            CanvasDropdownEvent::Assign(owner) => {
+                if let &[sprite] = &self.selected_sprites[..] {
+                    self.set_sprite_owner(sprite, owner);
+                }
+            }
             CanvasDropdownEvent::Aura => {
                 if let Some(id) = self.selected_id() {
                     self.sprite_aura(id, details.colour());
                 }
             }
+            CanvasDropdownEvent::BringForward => self.reorder_selected(true),
+            CanvasDropdownEvent::Condition(idx) => {
+                if let Some(condition) = scene::Condition::COMMON.get(idx) {
+                    self.toggle_condition_selected(condition.clone());
+                }
+            }
             CanvasDropdownEvent::Clone => {
                 if let Some(id) = self.selected_id() {
                     self.clone_sprite(id);
@@ -1197,8 +2486,44 @@ impl Interactor {
                     self.remove_sprite(id);
                 }
             }
+            CanvasDropdownEvent::DismissHandout => self.dismiss_handout(),
+            CanvasDropdownEvent::FlipCaps => self.flip_selected_caps(),
             CanvasDropdownEvent::Group => self.group_selected(),
+            CanvasDropdownEvent::JoinDrawings => self.join_selected_drawings(),
+            CanvasDropdownEvent::PlayPath => {
+                if let Some(id) = self.selected_id() {
+                    let path = self.stop_path_record();
+                    self.play_path(id, &path, Self::DEFAULT_PATH_DURATION_MS);
+                }
+            }
+            CanvasDropdownEvent::RecordPath => {
+                if self.is_recording_path() {
+                    self.stop_path_record();
+                } else if let Some(id) = self.selected_id() {
+                    self.start_path_record(id);
+                }
+            }
+            CanvasDropdownEvent::SelectColour
+            | CanvasDropdownEvent::SelectShape
+            | CanvasDropdownEvent::SelectTexture => self.select_similar(event),
+            CanvasDropdownEvent::SelectSimilar => self.select_similar_to_selection(),
+            CanvasDropdownEvent::ReverseDrawing => self.reverse_selected_drawings(),
+            CanvasDropdownEvent::SendBack => self.reorder_selected(false),
+            CanvasDropdownEvent::ShowHandout => {
+                if let Some(texture) = self
+                    .selected_id()
+                    .and_then(|id| self.sprite_ref(id))
+                    .and_then(|sprite| sprite.visual.texture())
+                {
+                    self.show_handout(format!("{texture:016X}"), None);
+                }
+            }
             CanvasDropdownEvent::Ungroup => self.ungroup_selected(),
+            // Comments, waypoints and splits are positioned by the viewport,
+            // which handles these.
+            CanvasDropdownEvent::Comment
+            | CanvasDropdownEvent::SplitDrawing
+            | CanvasDropdownEvent::Waypoint => {}
             CanvasDropdownEvent::Layer(layer) => {
                 if let Some(sprite) = self.selected_id() {
                     self.sprite_layer(sprite, layer)
@@ -1207,6 +2532,37 @@ impl Interactor {
         }
     }
 
+    /// Add sprites like the first selected sprite to the selection.
+    fn select_similar(&mut self, event: CanvasDropdownEvent) {
+        let Some(visual) = self
+            .selected_sprites
+            .first()
+            .and_then(|&id| self.sprite_ref(id))
+            .map(|s| s.visual.clone())
+        else {
+            return;
+        };
+
+        match event {
+            CanvasDropdownEvent::SelectColour => {
+                if let Some(colour) = visual.colour() {
+                    self.select_by_colour(colour);
+                }
+            }
+            CanvasDropdownEvent::SelectShape => {
+                if let Some(shape) = visual.shape() {
+                    self.select_by_shape(shape);
+                }
+            }
+            CanvasDropdownEvent::SelectTexture => {
+                if let Some(texture) = visual.texture() {
+                    self.select_by_texture(texture);
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn allowed_options(&self) -> &[CanvasDropdownEvent] {
         if self.selected_sprites.len() > 1 {
             if let Some(&id) = self.selected_sprites.first() {
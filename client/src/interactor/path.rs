@@ -0,0 +1,85 @@
+use scene::{Id, Point};
+
+/// A sprite moving along a recorded path.
+pub struct PathAnimation {
+    pub sprite: Id,
+    points: Vec<Point>,
+    duration_ms: u64,
+
+    /// Time of the first frame of the animation, once it has started.
+    start: Option<u64>,
+}
+
+impl PathAnimation {
+    pub fn new(sprite: Id, points: &[Point], duration_ms: u64) -> Self {
+        Self {
+            sprite,
+            points: points.to_vec(),
+            duration_ms,
+            start: None,
+        }
+    }
+
+    /// Position of the sprite at time `now`, and whether the animation has
+    /// finished.
+    pub fn step(&mut self, now: u64) -> (Point, bool) {
+        let start = *self.start.get_or_insert(now);
+        let t = if self.duration_ms == 0 {
+            1.0
+        } else {
+            now.saturating_sub(start) as f32 / self.duration_ms as f32
+        };
+        (point_at(&self.points, t), t >= 1.0)
+    }
+}
+
+/// Total length of the path through `points`.
+fn length(points: &[Point]) -> f32 {
+    points.windows(2).map(|w| w[0].dist(w[1])).sum()
+}
+
+/// Point a fraction `t` of the way along the path through `points`,
+/// measured by distance so that the path is travelled at constant speed.
+pub fn point_at(points: &[Point], t: f32) -> Point {
+    let Some(&first) = points.first() else {
+        return Point::ORIGIN;
+    };
+
+    let mut remaining = length(points) * t.clamp(0.0, 1.0);
+    for w in points.windows(2) {
+        let (p, q) = (w[0], w[1]);
+        let d = p.dist(q);
+        if remaining <= d && d > 0.0 {
+            return p + (q - p) * (remaining / d);
+        }
+        remaining -= d;
+    }
+    points.last().copied().unwrap_or(first)
+}
+
+#[cfg(test)]
+mod test {
+    use scene::Point;
+
+    use super::{point_at, PathAnimation};
+
+    #[test]
+    fn test_point_at() {
+        let path = [
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+        ];
+        assert_eq!(point_at(&path, 0.0), Point::new(0.0, 0.0));
+        assert_eq!(point_at(&path, 0.25), Point::new(1.0, 0.0));
+        assert_eq!(point_at(&path, 0.75), Point::new(2.0, 1.0));
+        assert_eq!(point_at(&path, 2.0), Point::new(2.0, 2.0));
+        assert_eq!(point_at(&path[..1], 0.5), Point::new(0.0, 0.0));
+
+        let mut animation = PathAnimation::new(1, &path, 1000);
+        assert_eq!(animation.step(5000), (Point::new(0.0, 0.0), false));
+        assert_eq!(animation.step(5500), (Point::new(2.0, 0.0), false));
+        assert_eq!(animation.step(6000), (Point::new(2.0, 2.0), true));
+    }
+}
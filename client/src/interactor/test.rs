@@ -1,6 +1,6 @@
 use uuid::Uuid;
 
-use self::details::SceneDetails;
+use self::details::{SceneDetails, SpriteDetails};
 use super::*;
 
 fn generate_uuid() -> Uuid {
@@ -40,6 +40,7 @@ fn test_select_behind_forbidden() {
         stroke: 1.,
         solid: false,
         colour: scene::Colour::DEFAULT,
+        pattern: scene::StrokePattern::Solid,
     };
     let mut server_sprite = Sprite::new(server_sprite_id, Some(visual.clone()));
     server_sprite.set_rect(Rect::new(-2., -2., 4., 4.)); // 4x4 covering the origin.
@@ -78,7 +79,7 @@ fn test_select_player_sprite() {
     )));
     let sprite = int.scene.sprite_ref(sprite).unwrap();
 
-    assert!(int.perms.selectable(owner, sprite.id, layer));
+    assert!(int.perms.selectable(owner, sprite.id, layer, None, None));
     assert!(int.selectable(sprite, true));
 }
 
@@ -95,3 +96,742 @@ fn test_fog_active_triggers_scene() {
     });
     assert!(int.save_required());
 }
+
+#[test]
+fn test_fog_rect() {
+    let mut int = fresh_interactor();
+
+    // Dragging up and to the left reveals the same cells.
+    int.set_fog_rect(Point::new(4., 3.), Point::new(1., 1.), true);
+    assert_eq!(int.fog().n_revealed, 3 * 2);
+    assert!(!int.fog().occluded(1, 1) && !int.fog().occluded(3, 2));
+    assert!(int.fog().occluded(4, 2));
+
+    int.set_fog_rect(Point::new(1., 1.), Point::new(2., 3.), false);
+    assert_eq!(int.fog().n_revealed, 2 * 2);
+
+    // The reveal is undone as a single step.
+    int.undo();
+    int.undo();
+    assert_eq!(int.fog().n_revealed, 0);
+}
+
+#[test]
+fn test_drag_alignment_guides() {
+    let mut int = fresh_interactor();
+    let layer = int.scene.first_layer();
+    int.new_sprite_at(None, Some(layer), Rect::new(0., 0., 2., 2.))
+        .unwrap();
+    let far = int
+        .new_sprite_at(None, Some(layer), Rect::new(20.03, 0., 1., 1.))
+        .unwrap();
+    let held = int
+        .new_sprite_at(None, Some(layer), Rect::new(3., 4., 1., 1.))
+        .unwrap();
+
+    // Drag the sprite so its left edge is just off the other's right edge.
+    int.grab(Point::new(3.5, 4.5), false);
+    int.drag(Point::new(2.53, 4.5), false);
+    let rect = int.sprite_ref(held).unwrap().rect;
+    assert!((rect.x - 2.).abs() < 0.0001);
+    assert_eq!(int.guides(), &[guides::Guide::Vertical(2.)]);
+
+    // Sprites more than a few tiles away don't produce guides.
+    assert_eq!(int.sprite_ref(far).unwrap().rect.x, 20.03);
+
+    int.release(true, false);
+    assert!(int.guides().is_empty());
+}
+
+#[test]
+fn test_drag_snaps_to_edges() {
+    let mut int = fresh_interactor();
+    let layer = int.scene.first_layer();
+    int.new_sprite_at(None, Some(layer), Rect::new(0.3, 0.3, 2., 2.))
+        .unwrap();
+    let held = int
+        .new_sprite_at(None, Some(layer), Rect::new(4., 0., 1., 1.))
+        .unwrap();
+
+    // Dropping just right of the neighbour leaves the sprite flush against
+    // it and aligned with its top, even though the sprite started aligned
+    // to the grid.
+    int.grab(Point::new(4.5, 0.5), false);
+    int.drag(Point::new(2.95, 0.82), false);
+    int.release(false, false);
+    let rect = int.sprite_ref(held).unwrap().rect;
+    assert!((rect.x - 2.3).abs() < 0.0001);
+    assert!((rect.y - 0.3).abs() < 0.0001);
+}
+
+#[test]
+fn test_move_selection_undo() {
+    let mut int = fresh_interactor();
+    let layer = int.scene.first_layer();
+    let a = int
+        .new_sprite_at(None, Some(layer), Rect::new(1., 1., 1., 1.))
+        .unwrap();
+    let b = int
+        .new_sprite_at(None, Some(layer), Rect::new(3., 1., 1., 1.))
+        .unwrap();
+    int.select_multiple(&[a, b]);
+
+    int.move_selection(Point::new(0., 0.1));
+    int.move_selection(Point::new(-1., 0.));
+    assert_eq!(
+        int.sprite_ref(a).unwrap().rect.top_left(),
+        Point::new(0., 1.1)
+    );
+    assert_eq!(
+        int.sprite_ref(b).unwrap().rect.top_left(),
+        Point::new(2., 1.1)
+    );
+
+    // Each movement is undone in a single step, moving all sprites together.
+    int.undo();
+    assert_eq!(
+        int.sprite_ref(a).unwrap().rect.top_left(),
+        Point::new(1., 1.1)
+    );
+    assert_eq!(
+        int.sprite_ref(b).unwrap().rect.top_left(),
+        Point::new(3., 1.1)
+    );
+    int.undo();
+    assert_eq!(
+        int.sprite_ref(a).unwrap().rect.top_left(),
+        Point::new(1., 1.)
+    );
+}
+
+#[test]
+fn test_macro_playback() {
+    let mut int = fresh_interactor();
+    let layer = int.scene.first_layer();
+
+    int.start_recording();
+    assert!(int.is_recording());
+    let goblin = int
+        .new_sprite_at(None, Some(layer), Rect::new(2., 2., 1., 1.))
+        .unwrap();
+    int.new_sprite_at(None, Some(layer), Rect::new(4., 2., 1., 1.))
+        .unwrap();
+    let opt = int
+        .scene
+        .sprite(goblin)
+        .map(|s| s.set_rect(Rect::new(2., 3., 1., 1.)));
+    int.scene_option(opt);
+    let events = int.stop_recording();
+    assert!(!int.is_recording());
+
+    // Macros survive a round trip through JSON.
+    let json = serde_json::to_string(&events).unwrap();
+    let events: Vec<SceneEvent> = serde_json::from_str(&json).unwrap();
+
+    int.play_macro(&events, Point::new(10., 10.));
+    let layer = int.scene.layer_ref(layer).unwrap();
+    assert_eq!(layer.sprites.len(), 4);
+    let rects: Vec<Rect> = layer.sprites[2..].iter().map(|s| s.rect).collect();
+    assert_eq!(
+        rects,
+        vec![Rect::new(10., 11., 1., 1.), Rect::new(12., 10., 1., 1.)]
+    );
+
+    // Playing back a macro is a single undo step.
+    int.undo();
+    assert_eq!(
+        int.scene
+            .layer_ref(int.scene.first_layer())
+            .unwrap()
+            .sprites
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_sprite_templates() {
+    let mut int = fresh_interactor();
+    let id = int
+        .new_sprite_at(None, None, Rect::new(1., 1., 2., 3.))
+        .unwrap();
+    let opt = int
+        .scene
+        .sprite(id)
+        .and_then(|s| s.set_label(Some("Ogre".to_string())));
+    int.scene_option(opt);
+
+    assert!(int.selected_template("Ogre").is_none());
+    int.select(id);
+    let template = int.selected_template("Ogre").unwrap();
+
+    let new = int
+        .new_sprite_from_template(&template, Point::new(5., 5.))
+        .unwrap();
+    let sprite = int.sprite_ref(new).unwrap();
+    assert_ne!(new, id);
+    assert_eq!(sprite.rect, Rect::new(5., 5., 2., 3.));
+    assert_eq!(sprite.label.as_deref(), Some("Ogre"));
+
+    // Creation is a single undo step.
+    int.undo();
+    assert!(int.sprite_ref(new).is_none());
+}
+
+#[test]
+fn test_select_by_visual() {
+    let mut int = fresh_interactor();
+    let red = scene::Colour([1., 0., 0., 1.]);
+    let shape = |colour, shape| SpriteVisual::Shape {
+        shape,
+        stroke: 1.,
+        solid: true,
+        colour,
+        pattern: scene::StrokePattern::Solid,
+    };
+    let texture = |id, shape| SpriteVisual::Texture { id, shape };
+    let rect = Rect::new(0., 0., 1., 1.);
+
+    let a = int
+        .new_sprite_at(Some(shape(red, Shape::Rectangle)), None, rect)
+        .unwrap();
+    let b = int
+        .new_sprite_at(Some(shape(red, Shape::Ellipse)), None, rect)
+        .unwrap();
+    let c = int
+        .new_sprite_at(
+            Some(shape(scene::Colour::DEFAULT, Shape::Ellipse)),
+            None,
+            rect,
+        )
+        .unwrap();
+    let d = int
+        .new_sprite_at(Some(texture(7, Shape::Rectangle)), None, rect)
+        .unwrap();
+    let e = int
+        .new_sprite_at(Some(texture(8, Shape::Ellipse)), None, rect)
+        .unwrap();
+
+    int.select_by_colour(red);
+    assert_eq!(int.selected_sprites, vec![b, a]);
+
+    // Selection is added to, not replaced.
+    int.select_by_shape(Shape::Ellipse);
+    assert_eq!(int.selected_sprites, vec![b, a, e, c]);
+
+    int.clear_selection();
+    int.select_by_texture(7);
+    assert_eq!(int.selected_sprites, vec![d]);
+
+    // Similar shapes share both shape and colour.
+    let f = int
+        .new_sprite_at(Some(shape(red, Shape::Ellipse)), None, rect)
+        .unwrap();
+    int.clear_selection();
+    int.select_similar_to(b);
+    assert_eq!(int.selected_sprites, vec![f, b]);
+
+    let g = int
+        .new_sprite_at(Some(texture(8, Shape::Rectangle)), None, rect)
+        .unwrap();
+    int.clear_selection();
+    int.select(e);
+    int.select_similar_to_selection();
+    assert_eq!(int.selected_sprites, vec![e, g]);
+}
+
+#[test]
+fn test_stroke_pattern() {
+    let mut int = fresh_interactor();
+    let rect = Rect::new(0., 0., 2., 2.);
+    let shape = int
+        .new_sprite_at(
+            Some(SpriteVisual::new_shape(
+                scene::Colour::DEFAULT,
+                Shape::Rectangle,
+                0.1,
+                false,
+            )),
+            None,
+            rect,
+        )
+        .unwrap();
+    let texture = int
+        .new_sprite_at(
+            Some(SpriteVisual::Texture {
+                shape: Shape::Rectangle,
+                id: 1,
+            }),
+            None,
+            rect,
+        )
+        .unwrap();
+
+    let dashed = scene::StrokePattern::Dashed(0.5);
+    for id in [shape, texture] {
+        int.sprite_details(
+            id,
+            SpriteDetails {
+                pattern: Some(dashed),
+                ..Default::default()
+            },
+        );
+    }
+    assert_eq!(
+        int.sprite_ref(shape).unwrap().visual.pattern(),
+        Some(dashed)
+    );
+    assert_eq!(int.sprite_ref(texture).unwrap().visual.pattern(), None);
+
+    int.undo();
+    assert_eq!(
+        int.sprite_ref(shape).unwrap().visual.pattern(),
+        Some(scene::StrokePattern::Solid)
+    );
+}
+
+#[test]
+fn test_path_animation() {
+    let mut int = fresh_interactor();
+    let id = int
+        .new_sprite_at(None, None, Rect::new(0., 0., 1., 1.))
+        .unwrap();
+    let at = |int: &Interactor| int.sprite_ref(id).unwrap().rect.top_left();
+
+    int.start_path_record(id);
+    int.grab(Point::new(0.5, 0.5), false);
+    int.drag(Point::new(3.5, 0.5), false);
+    int.drag(Point::new(3.5, 2.5), false);
+    int.release(false, false);
+    let path = int.stop_path_record();
+    assert!(!int.is_recording_path());
+    assert_eq!(
+        path,
+        vec![Point::new(0., 0.), Point::new(3., 0.), Point::new(3., 2.)]
+    );
+
+    // The sprite returns to the start of the path and moves along it.
+    int.play_path(id, &path, 1000);
+    int.tick(10_000);
+    assert_eq!(at(&int), Point::new(0., 0.));
+    int.tick(10_600);
+    assert_eq!(at(&int), Point::new(3., 0.));
+    int.tick(11_000);
+    assert_eq!(at(&int), Point::new(3., 2.));
+    assert!(!int.is_playing_path());
+
+    // The animation is undone in one step.
+    int.undo();
+    assert_eq!(at(&int), Point::new(3., 2.));
+
+    // Stopping the animation leaves the sprite part way along.
+    int.play_path(id, &path, 1000);
+    int.tick(20_000);
+    int.tick(20_300);
+    int.stop_path();
+    int.tick(21_000);
+    assert_eq!(at(&int), Point::new(1.5, 0.));
+}
+
+#[test]
+fn test_handout() {
+    let mut int = fresh_interactor();
+    assert!(int.handout().is_none());
+
+    int.process_server_event(ServerEvent::Handout("00000000000000AB".into(), "GM".into()));
+    assert!(int.changes.handle_handout_change());
+    assert_eq!(
+        int.handout(),
+        Some(&("00000000000000AB".to_string(), "GM".to_string()))
+    );
+
+    int.process_server_event(ServerEvent::DismissHandout);
+    assert!(int.changes.handle_handout_change());
+    assert!(int.handout().is_none());
+
+    // Closing when no handout is shown changes nothing.
+    int.close_handout();
+    assert!(!int.changes.handle_handout_change());
+}
+
+#[test]
+fn test_layer_gm_opacity() {
+    let mut int = fresh_interactor();
+    let layer = int.selected_layer();
+    assert_eq!(int.layer_gm_opacity(layer), 1.0);
+    assert!(int.layer_opacity().unwrap().is_empty());
+
+    int.set_layer_gm_opacity(layer, 0.25);
+    assert!(int.changes.handle_sprite_change());
+    assert_eq!(int.layer_opacity().unwrap().get(&layer), Some(&0.25));
+    let info = int.layer_info();
+    assert_eq!(
+        info.iter().find(|l| l.id == layer).unwrap().gm_opacity,
+        0.25
+    );
+
+    // Out of range values are clamped, and opaque layers are forgotten.
+    int.set_layer_gm_opacity(layer, -1.0);
+    assert_eq!(int.layer_gm_opacity(layer), 0.0);
+    int.set_layer_gm_opacity(layer, 2.0);
+    assert!(int.layer_opacity().unwrap().is_empty());
+
+    // Players always see layers at full opacity.
+    int.set_layer_gm_opacity(layer, 0.5);
+    int.role = scene::perms::Role::Player;
+    assert!(int.layer_opacity().is_none());
+}
+
+#[test]
+fn test_overlap_check() {
+    let mut int = fresh_interactor();
+    let layer = int.scene.first_layer();
+    let a = int
+        .new_sprite_at(None, Some(layer), Rect::new(0., 0., 1., 1.))
+        .unwrap();
+    int.new_sprite_at(None, Some(layer), Rect::new(2., 0., 1., 1.))
+        .unwrap();
+
+    // Overlaps aren't tracked until the check is enabled.
+    int.select_multiple(&[a]);
+    int.move_selection(Point::new(1.5, 0.));
+    assert!(int.overlaps().is_empty());
+
+    int.set_overlap_check(true);
+    assert_eq!(int.overlaps().len(), 2);
+    int.changes.handle_sprite_change();
+
+    // Moving the sprite clear removes the outlines.
+    int.move_selection(Point::new(-0.5, 0.));
+    assert!(int.overlaps().is_empty());
+    assert!(int.changes.handle_sprite_change());
+
+    // Undoing the move brings them back.
+    int.undo();
+    assert_eq!(int.overlaps().len(), 2);
+
+    int.set_overlap_check(false);
+    assert!(int.overlaps().is_empty());
+}
+
+#[test]
+fn test_own_sprite() {
+    let mut int = fresh_interactor();
+    let user = int.user;
+    let layer = int.scene.first_layer();
+    let rect = Rect::new(3., 4., 1., 1.);
+    let sprite = int.new_sprite_at(None, Some(layer), rect).unwrap();
+    assert!(int.own_sprite_rect().is_none());
+
+    int.set_sprite_owner(sprite, Some(user));
+    assert!(int.changes.handle_own_sprite_change());
+    assert_eq!(int.own_sprite_rect(), Some(rect));
+
+    // Giving the sprite away leaves the player without a token.
+    int.set_sprite_owner(sprite, Some(generate_uuid()));
+    assert!(int.changes.handle_own_sprite_change());
+    assert!(int.own_sprite_rect().is_none());
+
+    // The server tells the player which sprite is theirs on joining.
+    int.process_server_event(ServerEvent::OwnSprite(sprite));
+    assert!(int.changes.handle_own_sprite_change());
+    assert_eq!(int.own_sprite_rect(), Some(rect));
+}
+
+#[test]
+fn test_move_own_sprite() {
+    let player = generate_uuid();
+    let mut int = fresh_interactor();
+    int.perms.role_change(
+        scene::perms::CANONICAL_UPDATER,
+        player,
+        scene::perms::Role::Player,
+    );
+    int.user = player;
+    int.role = scene::perms::Role::Player;
+
+    let layer = int.scene.first_layer();
+    let mut token = Sprite::new(3, None);
+    token.owner = Some(player);
+    int.process_server_event(ServerEvent::SceneUpdate(SceneEvent::SpriteNew(
+        token, layer,
+    )));
+    int.process_server_event(ServerEvent::SceneUpdate(SceneEvent::SpriteNew(
+        Sprite::new(4, None),
+        layer,
+    )));
+
+    // Only the player's own token may be selected and moved.
+    int.select_multiple(&[3, 4]);
+    assert_eq!(int.selected_sprites, vec![3]);
+    let x = int.scene.sprite_ref(3).unwrap().rect.x;
+    int.move_selection(Point::new(1., 0.));
+    assert_eq!(int.scene.sprite_ref(3).unwrap().rect.x, x + 1.);
+}
+
+#[test]
+fn test_invert_selection() {
+    let mut int = fresh_interactor();
+    let rect = Rect::new(0., 0., 1., 1.);
+    let a = int.new_sprite_at(None, None, rect).unwrap();
+    let b = int.new_sprite_at(None, None, rect).unwrap();
+    let c = int.new_sprite_at(None, None, rect).unwrap();
+    int.changes.handle_selected_change();
+
+    int.select(b);
+    int.invert_selection();
+    assert!(int.changes.handle_selected_change());
+    assert_eq!(int.selected_sprites.len(), 2);
+    assert!(int.selected_sprites.contains(&a) && int.selected_sprites.contains(&c));
+
+    // Sprites on hidden layers aren't selected.
+    let layer = int.scene.get_sprite_layer(a).unwrap();
+    int.set_layer_visible(layer, false);
+    int.invert_selection();
+    assert!(int.selected_sprites.is_empty());
+}
+
+#[test]
+fn test_reverse_selected_drawings() {
+    let mut int = fresh_interactor();
+    let (drawing, event) = int
+        .scene
+        .start_drawing(scene::DrawingMode::Line, Point::same(1.));
+    int.scene_option(event);
+    let event = int.scene.add_drawing_point(drawing, Point::same(2.));
+    int.scene_option(event);
+    let sprite = int
+        .new_sprite_at(
+            Some(SpriteVisual::Drawing {
+                drawing,
+                colour: scene::Colour::DEFAULT,
+                stroke: Sprite::DEFAULT_STROKE,
+                cap_start: scene::Cap::Round,
+                cap_end: scene::Cap::Arrow,
+                taper_start: false,
+                taper_end: false,
+            }),
+            None,
+            Rect::new(0., 0., 2., 2.),
+        )
+        .unwrap();
+
+    int.select(sprite);
+    int.reverse_selected_drawings();
+    assert_eq!(
+        int.scene.get_drawing(drawing).unwrap().line(),
+        (Point::same(2.), Point::same(1.))
+    );
+    int.flip_selected_caps();
+    assert_eq!(
+        int.scene.sprite_ref(sprite).unwrap().visual.cap_start(),
+        Some(scene::Cap::Arrow)
+    );
+
+    // Both are undone, one at a time.
+    int.undo();
+    assert_eq!(
+        int.scene.sprite_ref(sprite).unwrap().visual.cap_start(),
+        Some(scene::Cap::Round)
+    );
+    int.undo();
+    assert_eq!(
+        int.scene.get_drawing(drawing).unwrap().line(),
+        (Point::same(1.), Point::same(2.))
+    );
+}
+
+#[test]
+fn test_join_drawings() {
+    let mut int = fresh_interactor();
+    let drawing = |int: &mut Interactor, from: Point, to: Point| {
+        let (drawing, event) = int.scene.start_drawing(scene::DrawingMode::Freehand, from);
+        int.scene_option(event);
+        let event = int.scene.add_drawing_point(drawing, to);
+        int.scene_option(event);
+        let rect = int.scene.get_drawing(drawing).unwrap().rect();
+        let sprite = int
+            .new_sprite_at(
+                Some(SpriteVisual::Drawing {
+                    drawing,
+                    colour: scene::Colour::DEFAULT,
+                    stroke: Sprite::DEFAULT_STROKE,
+                    cap_start: scene::Cap::Round,
+                    cap_end: scene::Cap::Arrow,
+                    taper_start: false,
+                    taper_end: false,
+                }),
+                None,
+                rect,
+            )
+            .unwrap();
+        (drawing, sprite)
+    };
+    let (a, sprite_a) = drawing(&mut int, Point::same(1.), Point::same(2.));
+    let (_, sprite_b) = drawing(&mut int, Point::ORIGIN, Point::same(0.5));
+
+    // The start of the first drawing is nearest the second, so the first is
+    // reversed, keeping its caps in place, and the second joined on.
+    int.select(sprite_a);
+    int.select(sprite_b);
+    int.join_selected_drawings();
+    assert_eq!(
+        int.scene.get_drawing(a).unwrap().points().unwrap().data,
+        vec![2., 2., 1., 1., 0.5, 0.5, 0., 0.]
+    );
+    let sprite = int.scene.sprite_ref(sprite_a).unwrap();
+    assert_eq!(sprite.rect, Rect::new(0., 0., 2., 2.));
+    assert_eq!(sprite.visual.cap_start(), Some(scene::Cap::Arrow));
+    assert!(int.scene.sprite_ref(sprite_b).is_none());
+    assert_eq!(int.selected_sprites, vec![sprite_a]);
+
+    // Undone all at once.
+    int.undo();
+    assert_eq!(
+        int.scene.get_drawing(a).unwrap().line(),
+        (Point::same(1.), Point::same(2.))
+    );
+    let sprite = int.scene.sprite_ref(sprite_a).unwrap();
+    assert_eq!(sprite.rect, Rect::new(1., 1., 1., 1.));
+    assert_eq!(sprite.visual.cap_start(), Some(scene::Cap::Round));
+    assert!(int.scene.sprite_ref(sprite_b).is_some());
+}
+
+#[test]
+fn test_split_drawing() {
+    let mut int = fresh_interactor();
+    let (drawing, event) = int
+        .scene
+        .start_drawing(scene::DrawingMode::Freehand, Point::ORIGIN);
+    int.scene_option(event);
+    for point in [Point::new(1., 1.), Point::new(2., 0.), Point::new(3., 1.)] {
+        let event = int.scene.add_drawing_point(drawing, point);
+        int.scene_option(event);
+    }
+    let layer = int.selected_layer;
+    let sprite = int
+        .new_sprite_at(
+            Some(SpriteVisual::Drawing {
+                drawing,
+                colour: scene::Colour::DEFAULT,
+                stroke: Sprite::DEFAULT_STROKE,
+                cap_start: scene::Cap::Round,
+                cap_end: scene::Cap::Arrow,
+                taper_start: false,
+                taper_end: true,
+            }),
+            Some(layer),
+            Rect::new(10., 10., 6., 2.),
+        )
+        .unwrap();
+
+    // Split at the second point, which is shared by both halves. Points are
+    // carried over in scene coordinates.
+    int.select(sprite);
+    int.split_selected_drawing(Point::new(12.1, 12.));
+    assert!(int.scene.sprite_ref(sprite).is_none());
+    assert!(int.selected_sprites.is_empty());
+    let sprites = &int.scene.layer_ref(layer).unwrap().sprites;
+    assert_eq!(sprites.len(), 2);
+    let points = |s: &Sprite| {
+        int.scene
+            .get_drawing(s.visual.drawing().unwrap())
+            .unwrap()
+            .points()
+            .unwrap()
+            .data
+            .clone()
+    };
+    assert_eq!(points(&sprites[0]), vec![10., 10., 12., 12.]);
+    assert_eq!(points(&sprites[1]), vec![12., 12., 14., 10., 16., 12.]);
+    assert_eq!(sprites[0].rect, Rect::new(10., 10., 2., 2.));
+    assert_eq!(sprites[1].rect, Rect::new(12., 10., 4., 2.));
+
+    // Each half keeps the cap and taper of its own end.
+    assert_eq!(sprites[0].visual.cap_start(), Some(scene::Cap::Round));
+    assert_eq!(sprites[0].visual.cap_end(), Some(scene::Cap::None));
+    assert_eq!(sprites[1].visual.cap_start(), Some(scene::Cap::None));
+    assert_eq!(sprites[1].visual.cap_end(), Some(scene::Cap::Arrow));
+    assert_eq!(sprites[1].visual.taper_end(), Some(true));
+
+    // Drawings can't be split at their ends.
+    let first = sprites[0].visual.drawing().unwrap();
+    int.split_drawing_at(first, 1);
+    assert_eq!(int.scene.layer_ref(layer).unwrap().sprites.len(), 2);
+
+    // Undone all at once.
+    int.undo();
+    let sprites = &int.scene.layer_ref(layer).unwrap().sprites;
+    assert_eq!(sprites.len(), 1);
+    assert_eq!(sprites[0].id, sprite);
+}
+
+#[test]
+fn test_comments() {
+    let mut int = fresh_interactor();
+    let user = int.user;
+    int.players.insert(user, ("Owen".to_string(), None));
+
+    int.add_comment("  Swap the trap for a mimic. ", Some(Point::same(2.)), 60);
+    int.add_comment(" ", None, 60);
+    assert_eq!(int.comments().len(), 1);
+    let comment = &int.comments()[0];
+    assert_eq!(comment.author, "Owen");
+    assert_eq!(comment.text, "Swap the trap for a mimic.");
+    assert_eq!(comment.at, Some(Point::same(2.)));
+
+    int.remove_comment(0);
+    assert!(int.comments().is_empty());
+
+    int.add_comment("Reinforcements in round 3.", None, 120);
+    int.undo();
+    assert!(int.comments().is_empty());
+}
+
+#[test]
+fn test_viewports() {
+    let mut int = fresh_interactor();
+    let editor = Uuid::from_u128(120);
+    let viewport = Rect::new(4., 2., 16., 9.);
+
+    int.process_server_event(ServerEvent::PlayerViewport(editor, viewport));
+    let viewports = int.viewports();
+    assert_eq!(viewports.len(), 1);
+    let (rect, colour) = viewports[0];
+    assert_eq!(rect, viewport);
+
+    // A hue of one third is green.
+    assert!(colour.g() > colour.r() && colour.g() > colour.b());
+    assert_eq!(colour.a(), 0.3);
+
+    // Viewports are forgotten when their editor leaves.
+    int.process_server_event(ServerEvent::PlayerCursor(editor, None));
+    assert!(int.viewports().is_empty());
+}
+
+#[test]
+fn test_conflicts() {
+    let mut int = fresh_interactor();
+    let sprite = int
+        .new_sprite_at(None, None, Rect::new(1., 1., 1., 1.))
+        .unwrap();
+    let other = int
+        .new_sprite_at(None, None, Rect::new(3., 1., 1., 1.))
+        .unwrap();
+
+    // Only rejected moves are marked.
+    let rejected = SceneEvent::EventSet(vec![
+        SceneEvent::SpriteMove(sprite, Rect::new(1., 1., 1., 1.), Rect::new(2., 2., 1., 1.)),
+        SceneEvent::SpriteLabel(other, None, Some("Orc".to_string())),
+    ]);
+    int.mark_conflicts(&rejected, 1000);
+    let conflicts = int.conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].rect, Rect::new(1., 1., 1., 1.));
+
+    // Markers are cleared after they've been shown for long enough.
+    int.tick(1000 + Interactor::CONFLICT_DURATION_MS - 1);
+    assert_eq!(int.conflicts().len(), 1);
+    int.tick(1000 + Interactor::CONFLICT_DURATION_MS);
+    assert!(int.conflicts().is_empty());
+}
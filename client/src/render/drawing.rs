@@ -30,6 +30,9 @@ struct MeshProps {
 
     /// End cap of the sprite.
     cap_end: scene::Cap,
+
+    /// Whether the line tapers at its start and end.
+    taper: (bool, bool),
 }
 
 impl MeshProps {
@@ -39,6 +42,7 @@ impl MeshProps {
         stroke: f32,
         cap_start: scene::Cap,
         cap_end: scene::Cap,
+        taper: (bool, bool),
     ) -> Self {
         Self {
             w: position.w,
@@ -48,6 +52,7 @@ impl MeshProps {
             n: drawing.n_points(),
             cap_start,
             cap_end,
+            taper,
         }
     }
 }
@@ -95,6 +100,7 @@ impl DrawingRenderer {
         stroke: f32,
         cap_start: scene::Cap,
         cap_end: scene::Cap,
+        taper: (bool, bool),
     ) -> Res<()> {
         let mut points = match drawing.mode {
             scene::DrawingMode::Freehand => {
@@ -108,22 +114,31 @@ impl DrawingRenderer {
                 points.translate(-drawing_rect.top_left());
                 points.scale_asymmetric(position.w / drawing_rect.w, position.h / drawing_rect.h);
 
-                super::shapes::freehand(&points, stroke, cap_start, cap_end)
+                super::shapes::freehand(
+                    &points,
+                    stroke,
+                    cap_start,
+                    cap_end,
+                    taper,
+                    drawing.pressures(),
+                )
             }
             scene::DrawingMode::Line => super::shapes::line(
                 Self::drawing_line(position, drawing),
                 stroke,
                 cap_start,
                 cap_end,
+                taper,
             ),
             scene::DrawingMode::Cone => super::shapes::cone(Self::drawing_line(position, drawing)),
+            scene::DrawingMode::Text => return Err("Text drawings have no mesh.".to_string()),
         };
 
         points.scale(self.grid_size);
         let mut mesh = self.renderer.mesh(&points.data)?;
         mesh.set_transforms(false, true);
 
-        let key = MeshProps::new(position, drawing, stroke, cap_start, cap_end);
+        let key = MeshProps::new(position, drawing, stroke, cap_start, cap_end, taper);
         let drawing_meshes = if let Some(meshes) = self.drawings.get_mut(&drawing.id) {
             meshes
         } else {
@@ -148,9 +163,10 @@ impl DrawingRenderer {
         stroke: f32,
         start: scene::Cap,
         end: scene::Cap,
+        taper: (bool, bool),
     ) -> Option<&Mesh> {
         if let Some(meshes) = self.drawings.get(&drawing.id) {
-            let key = MeshProps::new(position, drawing, stroke, start, end);
+            let key = MeshProps::new(position, drawing, stroke, start, end, taper);
             for (mesh_key, mesh) in meshes {
                 if *mesh_key == key {
                     return Some(mesh);
@@ -173,21 +189,27 @@ impl DrawingRenderer {
         stroke: f32,
         start: scene::Cap,
         end: scene::Cap,
+        taper: (bool, bool),
         colour: scene::Colour,
         viewport: Rect,
         position: Rect,
         grid_size: f32,
     ) {
+        // Text drawings are rendered as page elements, see draw_annotations.
+        if drawing.mode == scene::DrawingMode::Text {
+            return;
+        }
+
         self.update_grid_size(grid_size);
-        if let Some(mesh) = self.get_drawing(position, drawing, stroke, start, end) {
+        if let Some(mesh) = self.get_drawing(position, drawing, stroke, start, end, taper) {
             self.renderer
                 .draw(mesh, colour, viewport, position * grid_size);
         } else if self
-            .add_drawing(position, drawing, stroke, start, end)
+            .add_drawing(position, drawing, stroke, start, end, taper)
             .is_ok()
         {
             self.draw_drawing(
-                drawing, stroke, start, end, colour, viewport, position, grid_size,
+                drawing, stroke, start, end, taper, colour, viewport, position, grid_size,
             );
         }
     }
@@ -10,6 +10,9 @@ pub struct FogRenderer {
     current_dimensions: Option<(u32, u32)>,
     current_grid_size: Option<f32>,
     current_n_revelead: Option<u32>,
+
+    /// Whether to fill the area of the viewport beyond the scene bounds.
+    fill_outside: bool,
 }
 
 impl FogRenderer {
@@ -21,6 +24,16 @@ impl FogRenderer {
             current_dimensions: None,
             current_grid_size: None,
             current_n_revelead: None,
+            fill_outside: true,
+        }
+    }
+
+    /// A fog renderer which only draws occluded cells, leaving the area
+    /// outside the scene untouched.
+    pub fn cells_only(inner: SolidRenderer) -> Self {
+        Self {
+            fill_outside: false,
+            ..Self::new(inner)
         }
     }
 
@@ -61,7 +74,7 @@ impl FogRenderer {
         let grid_w = fog.w as f32 * grid_size;
         let grid_h = fog.h as f32 * grid_size;
 
-        if vp.x < 0.0 {
+        if self.fill_outside && vp.x < 0.0 {
             points.add_rect(Rect {
                 x: 0.0,
                 y: if vp.y < 0.0 { vp.y.abs() } else { 0.0 },
@@ -70,7 +83,7 @@ impl FogRenderer {
             });
         }
 
-        if vp.y < 0.0 {
+        if self.fill_outside && vp.y < 0.0 {
             points.add_rect(Rect {
                 x: 0.0,
                 y: 0.0,
@@ -79,7 +92,7 @@ impl FogRenderer {
             });
         }
 
-        if vp.x + vp.w > grid_w {
+        if self.fill_outside && vp.x + vp.w > grid_w {
             points.add_rect(Rect {
                 x: grid_w - vp.x,
                 y: 0.0,
@@ -88,7 +101,7 @@ impl FogRenderer {
             })
         }
 
-        if vp.y + vp.h > grid_h {
+        if self.fill_outside && vp.y + vp.h > grid_h {
             points.add_rect(Rect {
                 x: -vp.x,
                 y: grid_h - vp.y,
@@ -1,13 +1,13 @@
 use std::collections::HashMap;
 
-use scene::{Colour, Rect};
+use scene::{Colour, PointVector, Rect, StrokePattern};
 
 use super::webgl::{Mesh, SolidRenderer};
 use crate::Res;
 
 pub struct HollowRenderer {
     grid_size: f32,
-    meshes: HashMap<scene::Id, (u8, u32, f32, f32, f32, Mesh)>, // { id: (n, stroke, rect, mesh) }
+    meshes: HashMap<scene::Id, (u8, StrokePattern, f32, f32, f32, Mesh)>, // { id: (shape, pattern, stroke, rect, mesh) }
     renderer: SolidRenderer,
 }
 
@@ -24,23 +24,45 @@ impl HollowRenderer {
         &mut self,
         id: scene::Id,
         shape: scene::Shape,
+        pattern: StrokePattern,
         stroke: f32,
         viewport: Rect,
         position: Rect,
     ) -> Res<()> {
-        let points = super::shapes::hollow_shape(
-            shape,
-            Rect {
-                x: (stroke * self.grid_size) / viewport.w,
-                y: (stroke * self.grid_size) / viewport.h,
-                w: position.w / viewport.w,
-                h: position.h / viewport.h,
-            },
-        );
+        let points = match pattern {
+            StrokePattern::Solid => super::shapes::hollow_shape(
+                shape,
+                Rect {
+                    x: (stroke * self.grid_size) / viewport.w,
+                    y: (stroke * self.grid_size) / viewport.h,
+                    w: position.w / viewport.w,
+                    h: position.h / viewport.h,
+                },
+            ),
+            StrokePattern::Dashed(_) | StrokePattern::Dotted => {
+                // Dashes are measured in pixels so that they're the same
+                // length on each axis, then scaled to the viewport.
+                let stroke = stroke * self.grid_size;
+                let (dash, gap) = match pattern {
+                    StrokePattern::Dashed(gap) => (gap * self.grid_size, gap * self.grid_size),
+                    _ => (stroke, stroke * 2.0),
+                };
+                let mut points = PointVector::from(super::shapes::dashed_shape(
+                    shape,
+                    Rect::new(stroke, stroke, position.w, position.h),
+                    dash,
+                    gap,
+                ));
+                points.scale_asymmetric(1.0 / viewport.w, 1.0 / viewport.h);
+                points.data
+            }
+        };
         let mut mesh = self.renderer.mesh(&points)?;
         mesh.set_transforms(false, true);
-        self.meshes
-            .insert(id, (shape as u8, 1, stroke, position.w, position.h, mesh));
+        self.meshes.insert(
+            id,
+            (shape as u8, pattern, stroke, position.w, position.h, mesh),
+        );
         Ok(())
     }
 
@@ -48,13 +70,17 @@ impl HollowRenderer {
         &self,
         id: scene::Id,
         shape: scene::Shape,
-        points: u32,
+        pattern: StrokePattern,
         stroke: f32,
         rect: Rect,
     ) -> Option<&Mesh> {
-        if let Some((shp, n, s, w, h, mesh)) = self.meshes.get(&id) {
-            // If n is different, drawing has changed, we don't have it
-            if shape as u8 == *shp && points == *n && stroke == *s && rect.w == *w && rect.h == *h {
+        if let Some((shp, pat, s, w, h, mesh)) = self.meshes.get(&id) {
+            if shape as u8 == *shp
+                && pattern == *pat
+                && stroke == *s
+                && rect.w == *w
+                && rect.h == *h
+            {
                 return Some(mesh);
             }
         }
@@ -72,6 +98,7 @@ impl HollowRenderer {
         &mut self,
         id: scene::Id,
         shape: scene::Shape,
+        pattern: StrokePattern,
         colour: Colour,
         stroke: f32,
         viewport: Rect,
@@ -80,10 +107,13 @@ impl HollowRenderer {
     ) {
         let pos = position.positive_dimensions();
         self.update_grid_size(grid_size);
-        if let Some(shape) = self.get_mesh(id, shape, 1, stroke, pos) {
-            self.renderer.draw_unscaled(shape, colour, viewport, pos);
-        } else if self.add_shape(id, shape, stroke, viewport, pos).is_ok() {
-            self.draw_shape(id, shape, colour, stroke, viewport, pos, grid_size);
+        if let Some(mesh) = self.get_mesh(id, shape, pattern, stroke, pos) {
+            self.renderer.draw_unscaled(mesh, colour, viewport, pos);
+        } else if self
+            .add_shape(id, shape, pattern, stroke, viewport, pos)
+            .is_ok()
+        {
+            self.draw_shape(id, shape, pattern, colour, stroke, viewport, pos, grid_size);
         }
     }
 }
@@ -1,35 +1,49 @@
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
-use scene::{Cap, Colour, Drawing, Fog, Id, Outline, Point, Rect, Scene, Shape, Sprite};
+use scene::{
+    Cap, Colour, Comment, Drawing, Fog, Id, Outline, Point, Rect, Scene, Shape, Sprite,
+    StrokePattern,
+};
 use web_sys::{HtmlImageElement, WebGl2RenderingContext};
 
-use crate::{viewport::ViewportPoint, Res};
+use crate::{interactor::guides::Guide, viewport::ViewportPoint, Res};
 
 mod drawing;
 mod fog;
-mod grid;
 mod hollow;
 mod shapes;
 mod text;
 mod texture;
 mod webgl;
 
-pub use webgl::Gl;
+pub use webgl::{ColourBlindMode, Gl};
 
 #[derive(Clone, Copy, Debug)]
 pub struct ViewInfo {
     viewport: Rect,
     grid_size: f32,
+    /// Device pixels per CSS pixel. The viewport is in CSS pixels, while the
+    /// canvas' drawing buffer is in device pixels.
+    pixel_ratio: f32,
 }
 
 impl ViewInfo {
-    pub fn new(viewport: Rect, grid_size: f32) -> Self {
+    pub fn new(viewport: Rect, grid_size: f32, pixel_ratio: f32) -> Self {
         Self {
             viewport,
             grid_size,
+            pixel_ratio,
         }
     }
 
+    /// Dimensions of the drawing buffer in device pixels.
+    fn physical_size(&self) -> (u32, u32) {
+        (
+            (self.viewport.w * self.pixel_ratio).round() as u32,
+            (self.viewport.h * self.pixel_ratio).round() as u32,
+        )
+    }
+
     pub fn viewport_point(&self, scene_point: Point) -> ViewportPoint {
         let point = scene_point * self.grid_size;
         ViewportPoint {
@@ -37,13 +51,26 @@ impl ViewInfo {
             y: point.y - self.viewport.y,
         }
     }
+
+    /// Viewport bounds in scene units, padded by a tile on each side so that
+    /// strokes and drawing caps extending past a sprite's rect are retained.
+    fn visible_rect(&self) -> Rect {
+        let rect = self.viewport / self.grid_size;
+        Rect::new(rect.x - 1.0, rect.y - 1.0, rect.w + 2.0, rect.h + 2.0)
+    }
+
+    /// Whether any part of `rect`, in scene units, may be visible.
+    pub fn contains_rect(&self, rect: Rect) -> bool {
+        self.visible_rect().intersects(rect)
+    }
 }
 
 pub trait Renderer {
-    /// Clear the canvas.
+    /// Clear the canvas to a given colour.
     ///
-    /// * `vp` Viewport position and dimensions, tile size in pixels.
-    fn clear(&mut self, vp: ViewInfo);
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `colour` Background colour to fill canvas with.
+    fn clear(&mut self, vp: ViewInfo, colour: Colour);
 
     /// Draw a grid of a given size. Assume (0, 0) in scene space is the top
     /// left corner of the grid and each tile should be the size given the the
@@ -51,7 +78,8 @@ pub trait Renderer {
     ///
     /// * `vp`         Viewport position and dimensions, tile size in pixels.
     /// * `dimensions` `(width, height)` of grid.
-    fn draw_grid(&mut self, vp: ViewInfo, dimensions: (u32, u32));
+    /// * `colour`     Colour to draw grid lines in.
+    fn draw_grid(&mut self, vp: ViewInfo, dimensions: (u32, u32), colour: Colour);
 
     /// Render scene fog over the grid. This should be called after all sprites
     /// and the grid are rendered. The size of the fog should be the same size
@@ -79,6 +107,7 @@ pub trait Renderer {
     /// * `shape`    Shape to draw.
     /// * `colour`   Colour to draw shape in. May be transparent.
     /// * `stroke`   Width of the border of the hollow shape in scene units.
+    /// * `pattern`  Pattern of dashes in which to draw the border.
     fn draw_hollow(
         &mut self,
         vp: ViewInfo,
@@ -86,6 +115,7 @@ pub trait Renderer {
         shape: Shape,
         colour: Colour,
         stroke: f32,
+        pattern: StrokePattern,
     );
 
     /// Draw a one-pixel in a given shape at a given position.
@@ -104,7 +134,15 @@ pub trait Renderer {
     /// * `position` Position and dimensions of the shape, in scene units.
     /// * `shape`    Shape to form bounds of the texture.
     /// * `texture`  ID of the texture to render.
-    fn draw_texture(&mut self, vp: ViewInfo, position: Rect, shape: Shape, texture: Id);
+    /// * `opacity`  Opacity to draw the texture with, between 0 and 1.
+    fn draw_texture(
+        &mut self,
+        vp: ViewInfo,
+        position: Rect,
+        shape: Shape,
+        texture: Id,
+        opacity: f32,
+    );
 
     /// Draw a drawing at a given position. Start cap will be rendered pointing
     /// away from the angle formed by the first two points and end cap will be
@@ -118,6 +156,7 @@ pub trait Renderer {
     /// * `stroke`   Width of line, in scene units.
     /// * `start`    Cap to render at start of line.
     /// * `end`      Cap to render at end of line.
+    /// * `taper`    Whether the line narrows to its start and end.
     fn draw_drawing(
         &mut self,
         vp: ViewInfo,
@@ -127,6 +166,7 @@ pub trait Renderer {
         stroke: f32,
         start: Cap,
         end: Cap,
+        taper: (bool, bool),
     );
 
     /// Draw a collection of rectangular outlines onto the canvas.
@@ -141,6 +181,32 @@ pub trait Renderer {
         }
     }
 
+    /// Draw outlines around sprites which overlap other sprites.
+    ///
+    /// * `vp`       Viewport position and dimensions, tile size in pixels.
+    /// * `outlines` Outlines of overlapping sprites.
+    fn draw_overlaps(&mut self, vp: ViewInfo, outlines: &[Outline]) {
+        for &Outline { rect, shape } in outlines {
+            self.draw_outline(vp, rect, shape, Colour::RED);
+        }
+    }
+
+    /// Draw a pulsing red outline around sprites whose moves conflicted with
+    /// another editor's.
+    ///
+    /// * `vp`       Viewport position and dimensions, tile size in pixels.
+    /// * `outlines` Outlines of conflicting sprites.
+    /// * `age_ms`   Time since the conflict, to determine the pulse.
+    fn draw_conflicts(&mut self, vp: ViewInfo, outlines: &[Outline], age_ms: u64) {
+        const PULSE_MS: f32 = 500.0;
+
+        let phase = age_ms as f32 / PULSE_MS * std::f32::consts::TAU;
+        let colour = Colour::RED.with_opacity(0.6 + 0.4 * phase.cos());
+        for &Outline { rect, shape } in outlines {
+            self.draw_outline(vp, rect, shape, colour);
+        }
+    }
+
     /// Draw a sprite onto the grid, using the appropriate primitives. Places
     /// the sprite at the scene position indicated by its `rect` field. If the
     /// sprite has a `Visual::Drawing` and `drawing` is `None`, nothing will be
@@ -149,22 +215,31 @@ pub trait Renderer {
     /// * `vp`      Viewport position and dimensions, tile size in pixels.
     /// * `sprite`  Sprite to draw onto the grid.
     /// * `drawing` Drawing which is the sprite's visual, if applicable.
-    fn draw_sprite(&mut self, vp: ViewInfo, sprite: &Sprite, drawing: Option<&Drawing>) {
+    /// * `opacity` Factor to scale the opacity of the sprite's visual by.
+    fn draw_sprite(
+        &mut self,
+        vp: ViewInfo,
+        sprite: &Sprite,
+        drawing: Option<&Drawing>,
+        opacity: f32,
+    ) {
         let position = sprite.rect;
+        let fade = |colour: Colour| colour.with_opacity(colour.a() * opacity);
         match sprite.visual {
             scene::SpriteVisual::Texture { shape, id } => {
-                self.draw_texture(vp, position, shape, id)
+                self.draw_texture(vp, position, shape, id, opacity)
             }
             scene::SpriteVisual::Shape {
                 shape,
                 stroke,
                 solid: _,
                 colour,
+                pattern,
             } => {
                 if sprite.visual.is_solid() {
-                    self.draw_solid(vp, position, shape, colour);
+                    self.draw_solid(vp, position, shape, fade(colour));
                 } else {
-                    self.draw_hollow(vp, position, shape, colour, stroke);
+                    self.draw_hollow(vp, position, shape, fade(colour), stroke, pattern);
                 }
             }
             scene::SpriteVisual::Drawing {
@@ -173,42 +248,356 @@ pub trait Renderer {
                 stroke,
                 cap_start,
                 cap_end,
+                taper_start,
+                taper_end,
             } => {
                 if let Some(drawing) = drawing {
-                    self.draw_drawing(vp, position, drawing, colour, stroke, cap_start, cap_end);
+                    self.draw_drawing(
+                        vp,
+                        position,
+                        drawing,
+                        fade(colour),
+                        stroke,
+                        cap_start,
+                        cap_end,
+                        (taper_start, taper_end),
+                    );
                 }
             }
+            scene::SpriteVisual::Light { radius, colour } => {
+                self.draw_light(vp, position, radius, fade(colour))
+            }
+        }
+
+        self.draw_conditions(vp, sprite);
+        self.draw_health_bar(vp, sprite);
+    }
+
+    /// Draw a light source as a soft radial gradient, made of concentric
+    /// ellipses which are each drawn partially transparent so that the light
+    /// fades out towards its edge. The source itself is drawn as a solid
+    /// ellipse filling `position`.
+    ///
+    /// * `vp`       Viewport position and dimensions, tile size in pixels.
+    /// * `position` Position and dimensions of the light source sprite.
+    /// * `radius`   Radius of the lit area, in scene units.
+    /// * `colour`   Colour of the light. Opacity is that of the brightest part.
+    fn draw_light(&mut self, vp: ViewInfo, position: Rect, radius: f32, colour: Colour) {
+        const STEPS: u32 = 8;
+
+        let centre = position.centre();
+        let step_colour = colour.with_opacity(colour.a() / STEPS as f32);
+        for i in (1..=STEPS).rev() {
+            let r = radius * i as f32 / STEPS as f32;
+            let rect = Rect::at(centre - Point::same(r), r * 2.0, r * 2.0);
+            self.draw_solid(vp, rect, Shape::Ellipse, step_colour);
+        }
+        self.draw_solid(vp, position, Shape::Ellipse, colour.with_opacity(1.0));
+    }
+
+    /// Draw a health bar below `sprite` if it tracks HP and is damaged. The
+    /// bar shifts from green to red as the sprite loses HP.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `sprite` Sprite to draw health bar for.
+    fn draw_health_bar(&mut self, vp: ViewInfo, sprite: &Sprite) {
+        const BAR_HEIGHT: f32 = 0.1;
+        const BAR_BACKGROUND: Colour = Colour([0.2, 0.2, 0.2, 0.8]);
+
+        let Some((current, max)) = sprite.hp else {
+            return;
+        };
+        if current == max || max <= 0 {
+            return;
+        }
+
+        let ratio = (current as f32 / max as f32).clamp(0.0, 1.0);
+        let rect = sprite.rect.positive_dimensions();
+        let bar = Rect::new(rect.x, rect.y + rect.h, rect.w, BAR_HEIGHT);
+        self.draw_solid(vp, bar, Shape::Rectangle, BAR_BACKGROUND);
+        self.draw_solid(
+            vp,
+            bar.sized_as(rect.w * ratio, BAR_HEIGHT),
+            Shape::Rectangle,
+            Colour([1.0 - ratio, ratio, 0.0, 1.0]),
+        );
+    }
+
+    /// Draw a pip for each of the conditions on `sprite` along its bottom
+    /// edge, wrapping upwards if there are more than fit on one row.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `sprite` Sprite to draw condition indicators for.
+    fn draw_conditions(&mut self, vp: ViewInfo, sprite: &Sprite) {
+        const PIP_SIZE: f32 = 0.2;
+
+        let rect = sprite.rect.positive_dimensions();
+        let per_row = ((rect.w / PIP_SIZE).floor() as usize).max(1);
+        for (i, condition) in sprite.conditions.iter().enumerate() {
+            let col = (i % per_row) as f32;
+            let row = (i / per_row) as f32;
+            let at = Rect::new(
+                rect.x + col * PIP_SIZE,
+                rect.y + rect.h - (row + 1.0) * PIP_SIZE,
+                PIP_SIZE,
+                PIP_SIZE,
+            );
+            self.draw_solid(vp, at, Shape::Ellipse, condition.colour());
+        }
+    }
+
+    /// Draw a dot for each of the cursors of other players. The dots are a
+    /// fixed size on screen, regardless of zoom. These should be drawn after
+    /// everything else so that they're always on top.
+    ///
+    /// * `vp`      Viewport position and dimensions, tile size in pixels.
+    /// * `cursors` Positions of cursors in scene units, colours and names.
+    fn draw_cursors(&mut self, vp: ViewInfo, cursors: &[(Point, Colour, Option<&str>)]) {
+        const CURSOR_SIZE_PX: f32 = 10.0;
+
+        let size = CURSOR_SIZE_PX / vp.grid_size;
+        for &(at, colour, name) in cursors {
+            let rect = Rect::at(at - Point::same(size / 2.0), size, size);
+            self.draw_solid(vp, rect, Shape::Ellipse, colour);
+            if let Some(name) = name {
+                self.draw_text(vp, at + Point::same(size), name);
+            }
+        }
+    }
+
+    /// Outline the viewports of other editors, so that each can see where
+    /// the others are looking.
+    ///
+    /// * `vp`        Viewport position and dimensions, tile size in pixels.
+    /// * `viewports` Viewports in scene units and the colour of each.
+    fn draw_viewports(&mut self, vp: ViewInfo, viewports: &[(Rect, Colour)]) {
+        for &(viewport, colour) in viewports {
+            if vp.contains_rect(viewport) {
+                self.draw_outline(vp, viewport, Shape::Rectangle, colour);
+            }
         }
     }
 
     /// Draw a view of `scene` onto the canvas, with viewport dimensions and
     /// tile size as specified by `vp`.
     ///
-    /// * `vp`    Viewport position and dimensions, tile size in pixels.
-    /// * `scene` Scene to render view of.
-    fn draw_scene(&mut self, vp: ViewInfo, scene: &Scene) {
+    /// * `vp`            Viewport position and dimensions, tile size in pixels.
+    /// * `scene`         Scene to render view of.
+    /// * `layer_opacity` Opacity of sprites on each layer, if any are faded.
+    fn draw_scene(
+        &mut self,
+        vp: ViewInfo,
+        scene: &Scene,
+        layer_opacity: Option<&HashMap<Id, f32>>,
+    ) {
         let dimensions = (scene.w(), scene.h());
 
+        if let Some(texture) = scene.background_texture {
+            let bounds = Rect::new(0.0, 0.0, scene.w() as f32, scene.h() as f32);
+            self.draw_texture(vp, bounds, Shape::Rectangle, texture, 1.0);
+        }
+
         let mut background_drawn = false;
         for layer in scene.layers.iter().rev() {
             if !background_drawn && layer.z >= 0 {
-                self.draw_grid(vp, dimensions);
+                self.draw_grid(vp, dimensions, scene.grid_colour);
                 background_drawn = true;
             }
 
             if layer.visible {
-                for sprite in &layer.sprites {
+                let opacity = layer_opacity
+                    .and_then(|opacities| opacities.get(&layer.id))
+                    .copied()
+                    .unwrap_or(1.0);
+                for sprite in layer.sprites_in_view(vp.visible_rect()) {
                     let drawing = sprite.visual.drawing().and_then(|id| scene.get_drawing(id));
-                    self.draw_sprite(vp, sprite, drawing);
+                    self.draw_sprite(vp, sprite, drawing, opacity);
                 }
             }
         }
 
         if !background_drawn {
-            self.draw_grid(vp, dimensions);
+            self.draw_grid(vp, dimensions, scene.grid_colour);
+        }
+    }
+
+    /// Draw the text of each annotation drawing in view. As text is drawn
+    /// above the canvas, this should be called after drawing fog.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `scene`  Scene to draw annotations from.
+    /// * `fogged` Whether to draw annotations which are hidden by fog.
+    fn draw_annotations(&mut self, vp: ViewInfo, scene: &Scene, fogged: bool) {
+        for layer in scene.layers.iter().filter(|l| l.visible) {
+            for sprite in layer.sprites_in_view(vp.visible_rect()) {
+                let scene::SpriteVisual::Drawing {
+                    drawing, colour, ..
+                } = sprite.visual
+                else {
+                    continue;
+                };
+
+                let Some(drawing) = scene.get_drawing(drawing) else {
+                    continue;
+                };
+
+                let Some(annotation) = &drawing.annotation else {
+                    continue;
+                };
+
+                let centre = sprite.rect.centre();
+                if !fogged
+                    && scene.fog.active
+                    && scene.fog.occluded(centre.x as u32, centre.y as u32)
+                {
+                    continue;
+                }
+
+                // Scale the text with its sprite.
+                let height = drawing.rect().h;
+                let scale = if height > 0.0 {
+                    sprite.rect.h / height
+                } else {
+                    1.0
+                };
+
+                self.draw_annotation(
+                    vp,
+                    sprite.rect.top_left(),
+                    &annotation.text,
+                    annotation.font_size * scale,
+                    colour,
+                );
+            }
         }
     }
 
+    /// Draw the label of each labelled sprite in view centred beneath it, on
+    /// a translucent background so that it is legible over any map. As text
+    /// is drawn above the canvas, this should be called after drawing fog.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `scene`  Scene to draw nameplates from.
+    /// * `fogged` Whether to draw nameplates of sprites hidden by fog.
+    fn draw_nameplates(&mut self, vp: ViewInfo, scene: &Scene, fogged: bool) {
+        const FONT_SIZE: f32 = 0.25;
+        const CHAR_WIDTH: f32 = 0.6; // Approximate, as a proportion of font size.
+        const PADDING: f32 = 0.05;
+        const OFFSET: f32 = 0.1; // Clear of the health bar.
+        const BACKGROUND: Colour = Colour([0.0, 0.0, 0.0, 0.6]);
+
+        for layer in scene.layers.iter().filter(|l| l.visible) {
+            for sprite in layer.sprites_in_view(vp.visible_rect()) {
+                let Some(label) = &sprite.label else {
+                    continue;
+                };
+
+                let centre = sprite.rect.centre();
+                if !fogged
+                    && scene.fog.active
+                    && scene.fog.occluded(centre.x as u32, centre.y as u32)
+                {
+                    continue;
+                }
+
+                let at = sprite.rect.positive_dimensions().bottom_centre();
+                let w = label.chars().count() as f32 * FONT_SIZE * CHAR_WIDTH + PADDING * 2.0;
+                let h = FONT_SIZE + PADDING * 2.0;
+                self.draw_solid(
+                    vp,
+                    Rect::new(at.x - w / 2.0, at.y + OFFSET, w, h),
+                    Shape::Rectangle,
+                    BACKGROUND,
+                );
+                self.draw_nameplate(
+                    vp,
+                    Point::new(at.x, at.y + OFFSET + PADDING),
+                    label,
+                    FONT_SIZE,
+                );
+            }
+        }
+    }
+
+    /// Draw a marker at the position of each positioned comment in view.
+    ///
+    /// * `vp`       Viewport position and dimensions, tile size in pixels.
+    /// * `comments` Comments to draw markers for.
+    fn draw_comments(&mut self, vp: ViewInfo, comments: &[Comment]) {
+        for at in comments.iter().filter_map(|c| c.at) {
+            if vp.contains_rect(Rect::at(at, 0.0, 0.0)) {
+                self.draw_comment_marker(vp, at);
+            }
+        }
+    }
+
+    /// Draw a speech bubble marking a comment, centred on scene position `at`.
+    ///
+    /// * `vp` Viewport position and dimensions, tile size in pixels.
+    /// * `at` Position in scene of the comment.
+    fn draw_comment_marker(&mut self, vp: ViewInfo, at: Point);
+
+    /// Draw nameplate text with its top centre at scene position `at`.
+    ///
+    /// * `vp`        Viewport position and dimensions, tile size in pixels.
+    /// * `at`        Position in scene of the top centre of the text.
+    /// * `text`      Text to display.
+    /// * `font_size` Height of the text in scene units.
+    fn draw_nameplate(&mut self, vp: ViewInfo, at: Point, text: &str, font_size: f32);
+
+    /// Draw annotation text with its top left corner at scene position `at`.
+    ///
+    /// * `vp`        Viewport position and dimensions, tile size in pixels.
+    /// * `at`        Position in scene of the top left of the text.
+    /// * `text`      Text to display.
+    /// * `font_size` Height of the text in scene units.
+    /// * `colour`    Colour of the text.
+    fn draw_annotation(
+        &mut self,
+        vp: ViewInfo,
+        at: Point,
+        text: &str,
+        font_size: f32,
+        colour: Colour,
+    );
+
+    /// Draw a dashed line between two points in the scene.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `from`   Start of the line in scene units.
+    /// * `to`     End of the line in scene units.
+    /// * `colour` Colour of the line.
+    fn draw_dashed_line(&mut self, vp: ViewInfo, from: Point, to: Point, colour: Colour);
+
+    /// Draw alignment guides as thin lines across the full viewport.
+    ///
+    /// * `vp`     Viewport position and dimensions, tile size in pixels.
+    /// * `guides` Guides to draw.
+    fn draw_guides(&mut self, vp: ViewInfo, guides: &[Guide]);
+
+    /// Draw a sparse grid with lines every `interval` tiles, labelling each
+    /// intersection with its distance from the origin of the scene.
+    ///
+    /// * `vp`       Viewport position and dimensions, tile size in pixels.
+    /// * `scene`    Scene to draw grid over, for its dimensions and units.
+    /// * `interval` Number of tiles between grid lines.
+    /// * `labels`   Whether to label the intersections.
+    fn draw_measurement_grid(&mut self, vp: ViewInfo, scene: &Scene, interval: u32, labels: bool);
+
+    /// Draw a ruler from `from` to `to`, labelled with `label` at its
+    /// midpoint.
+    ///
+    /// * `vp`    Viewport position and dimensions, tile size in pixels.
+    /// * `from`  Start of the ruler in scene units.
+    /// * `to`    End of the ruler in scene units.
+    /// * `label` Text to show on the ruler, typically its length.
+    fn draw_ruler(&mut self, vp: ViewInfo, from: Point, to: Point, label: &str) {
+        const RULER_COLOUR: Colour = Colour([0.0, 0.0, 0.0, 0.8]);
+
+        self.draw_dashed_line(vp, from, to, RULER_COLOUR);
+        self.draw_text(vp, (from + to) / 2.0, label);
+    }
+
     /// Draw hover text bubble with given `text` at scene position `point` on
     /// viewport with details given by `vp`.
     ///
@@ -216,6 +605,14 @@ pub trait Renderer {
     /// * `at`   Position in scene at with to show text bubble.
     /// * `text` Text to display in bubble.
     fn draw_text(&mut self, vp: ViewInfo, at: Point, text: &str);
+
+    /// Render a view of `scene`, including the grid and fog, to an offscreen
+    /// buffer and return the result encoded as a PNG image.
+    ///
+    /// * `vp`          Viewport position and dimensions, tile size in pixels.
+    /// * `scene`       Scene to render view of.
+    /// * `transparent` `false` to render fog as opaque.
+    fn export_png(&mut self, vp: ViewInfo, scene: &Scene, transparent: bool) -> Res<Vec<u8>>;
 }
 
 pub struct WebGlRenderer {
@@ -226,9 +623,11 @@ pub struct WebGlRenderer {
     hollow_renderer: hollow::HollowRenderer,
     drawing_renderer: drawing::DrawingRenderer,
     line_renderer: webgl::LineRenderer,
-    grid_renderer: grid::GridRenderer,
+    grid_renderer: webgl::GridRenderer,
     fog_renderer: fog::FogRenderer,
+    minimap_fog_renderer: fog::FogRenderer,
     text_manager: text::HoverTextManager,
+    colour_filter: webgl::ColourFilter,
 }
 
 impl WebGlRenderer {
@@ -241,27 +640,169 @@ impl WebGlRenderer {
             hollow_renderer: hollow::HollowRenderer::new(webgl::SolidRenderer::new(gl.clone())?),
             drawing_renderer: drawing::DrawingRenderer::new(webgl::SolidRenderer::new(gl.clone())?),
             line_renderer: webgl::LineRenderer::new(gl.clone())?,
-            grid_renderer: grid::GridRenderer::new(webgl::LineRenderer::new(gl.clone())?),
+            grid_renderer: webgl::GridRenderer::new(gl.clone())?,
             fog_renderer: fog::FogRenderer::new(webgl::SolidRenderer::new(gl.clone())?),
+            minimap_fog_renderer: fog::FogRenderer::cells_only(webgl::SolidRenderer::new(
+                gl.clone(),
+            )?),
             text_manager: text::HoverTextManager::new(),
+            colour_filter: webgl::ColourFilter::new(gl.clone())?,
         })
     }
 
     pub fn load_image(&mut self, image: &HtmlImageElement) -> scene::Id {
         self.texture_library.load_image(image)
     }
+
+    pub fn set_colour_blind_mode(&mut self, mode: ColourBlindMode) {
+        self.colour_filter.set_mode(mode);
+    }
+
+    /// Begin drawing a frame to the canvas. Frames should be ended with
+    /// `end_frame`, which applies the colour blindness filter if one is
+    /// active.
+    pub fn begin_frame(&mut self, vp: ViewInfo) {
+        let (w, h) = vp.physical_size();
+        if let Err(e) = self.colour_filter.begin(w, h) {
+            crate::bridge::log!("Failed to apply colour filter: {e}");
+            self.colour_filter.set_mode(ColourBlindMode::None);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.colour_filter.finish();
+    }
+
+    /// Draw a simplified overview of `scene` into `minimap_rect`, with an
+    /// outline showing the area covered by the main view. Sprites are drawn
+    /// as solid shapes of their colour and textures are omitted.
+    ///
+    /// * `vp`           Main viewport position, dimensions and tile size.
+    /// * `scene`        Scene to draw an overview of.
+    /// * `minimap_rect` Area of the canvas to draw into, in viewport pixels.
+    /// * `transparent`  `false` to render fog as opaque.
+    pub fn draw_minimap(
+        &mut self,
+        vp: ViewInfo,
+        scene: &Scene,
+        minimap_rect: Rect,
+        transparent: bool,
+    ) {
+        const TEXTURE_COLOUR: Colour = Colour([0.6, 0.6, 0.6, 1.0]);
+        const DRAWING_OPACITY: f32 = 0.5;
+        const VIEW_COLOUR: Colour = Colour([0.6, 0.8, 1.0, 1.0]);
+        const FOG_OPACITY: f32 = 0.6;
+        const SCALE_OFFSET: f32 = 16.0;
+
+        let scale = (minimap_rect.w / scene.w() as f32).min(minimap_rect.h / scene.h() as f32);
+        let mvp = ViewInfo::new(
+            Rect::new(
+                -minimap_rect.x,
+                -minimap_rect.y,
+                vp.viewport.w,
+                vp.viewport.h,
+            ),
+            scale,
+            vp.pixel_ratio,
+        );
+        let bounds = Rect::new(0.0, 0.0, scene.w() as f32, scene.h() as f32);
+
+        // Clip to the minimap. GL coordinates start from the bottom left.
+        self.gl.enable(Gl::SCISSOR_TEST);
+        let ratio = vp.pixel_ratio;
+        self.gl.scissor(
+            (minimap_rect.x * ratio) as i32,
+            ((vp.viewport.h - minimap_rect.y - minimap_rect.h) * ratio) as i32,
+            (minimap_rect.w * ratio) as i32,
+            (minimap_rect.h * ratio) as i32,
+        );
+
+        self.draw_solid(mvp, bounds, Shape::Rectangle, scene.background);
+        for layer in scene.layers.iter().rev().filter(|l| l.visible) {
+            for sprite in layer.sprites_in_view(bounds) {
+                let colour = match sprite.visual {
+                    scene::SpriteVisual::Light { .. } => continue,
+                    scene::SpriteVisual::Texture { .. } => TEXTURE_COLOUR,
+                    scene::SpriteVisual::Shape { colour, .. } => colour,
+                    scene::SpriteVisual::Drawing { colour, .. } => {
+                        colour.with_opacity(DRAWING_OPACITY)
+                    }
+                };
+                let shape = sprite.visual.shape().unwrap_or(Shape::Rectangle);
+                self.draw_solid(mvp, sprite.rect, shape, colour);
+            }
+        }
+
+        if scene.fog.active {
+            let opacity = if transparent { FOG_OPACITY } else { 1.0 };
+            self.minimap_fog_renderer.render_fog(
+                mvp.viewport,
+                scale,
+                &scene.fog,
+                Colour([0.0, 0.0, 0.0, opacity]),
+            );
+        }
+
+        self.draw_outline(
+            mvp,
+            vp.viewport / vp.grid_size,
+            Shape::Rectangle,
+            VIEW_COLOUR,
+        );
+        self.gl.disable(Gl::SCISSOR_TEST);
+
+        self.draw_outline(mvp, bounds, Shape::Rectangle, scene.grid_colour);
+
+        // Scale indicator, showing the dimensions of the scene.
+        self.text_manager.render(
+            ViewportPoint {
+                x: minimap_rect.x + minimap_rect.w / 2.0,
+                y: minimap_rect.y - SCALE_OFFSET,
+            },
+            &format!(
+                "{} × {}",
+                scene.cell_size_display(scene.w() as f32),
+                scene.cell_size_display(scene.h() as f32)
+            ),
+        );
+    }
 }
 
 impl Renderer for WebGlRenderer {
-    fn clear(&mut self, vp: ViewInfo) {
+    fn clear(&mut self, vp: ViewInfo, colour: Colour) {
+        let (w, h) = vp.physical_size();
+        self.gl.viewport(0, 0, w as i32, h as i32);
         self.gl
-            .viewport(0, 0, vp.viewport.w as i32, vp.viewport.h as i32);
+            .clear_color(colour.r(), colour.g(), colour.b(), colour.a());
         self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
         self.text_manager.clear();
     }
 
-    fn draw_grid(&mut self, vp: ViewInfo, dimensions: (u32, u32)) {
-        self.grid_renderer.render_grid(vp, dimensions);
+    fn draw_grid(&mut self, vp: ViewInfo, dimensions: (u32, u32), colour: Colour) {
+        self.grid_renderer.render_grid(vp, dimensions, 1, colour);
+    }
+
+    fn draw_measurement_grid(&mut self, vp: ViewInfo, scene: &Scene, interval: u32, labels: bool) {
+        const GRID_COLOUR: Colour = Colour([0.1, 0.3, 0.8, 0.6]);
+        const FONT_SIZE: f32 = 0.4;
+        const OFFSET: f32 = 0.05;
+
+        let dimensions = (scene.w(), scene.h());
+        self.grid_renderer
+            .render_grid(vp, dimensions, interval, GRID_COLOUR);
+
+        if labels {
+            for (x, y) in webgl::grid_intersections(vp, dimensions, interval) {
+                let at = Point::new(x as f32, y as f32);
+                self.draw_annotation(
+                    vp,
+                    at + Point::same(OFFSET),
+                    &scene.cell_size_display(at.dist(Point::ORIGIN)),
+                    FONT_SIZE,
+                    GRID_COLOUR.with_opacity(1.0),
+                );
+            }
+        }
     }
 
     fn draw_fog(&mut self, vp: ViewInfo, fog: &Fog, transparent: bool) {
@@ -289,10 +830,12 @@ impl Renderer for WebGlRenderer {
         shape: Shape,
         colour: Colour,
         stroke: f32,
+        pattern: StrokePattern,
     ) {
         self.hollow_renderer.draw_shape(
             0,
             shape,
+            pattern,
             colour,
             stroke,
             vp.viewport,
@@ -319,13 +862,84 @@ impl Renderer for WebGlRenderer {
         self.line_renderer.render_line_loop(Some(colour));
     }
 
-    fn draw_texture(&mut self, vp: ViewInfo, position: Rect, shape: Shape, texture: Id) {
+    fn draw_nameplate(&mut self, vp: ViewInfo, at: Point, text: &str, font_size: f32) {
+        self.text_manager
+            .render_nameplate(vp.viewport_point(at), text, font_size * vp.grid_size);
+    }
+
+    fn draw_comment_marker(&mut self, vp: ViewInfo, at: Point) {
+        self.text_manager.render_comment(vp.viewport_point(at));
+    }
+
+    fn draw_dashed_line(&mut self, vp: ViewInfo, from: Point, to: Point, colour: Colour) {
+        const DASH_PX: f32 = 8.0;
+        const GAP_PX: f32 = 6.0;
+
+        let offset = Point::new(vp.viewport.x, vp.viewport.y);
+        let from = from * vp.grid_size - offset;
+        let to = to * vp.grid_size - offset;
+        let length = from.dist(to);
+        if length <= 0.0 {
+            return;
+        }
+
+        let direction = (to - from) / length;
+        let mut points = Vec::new();
+        let mut start = 0.0;
+        while start < length {
+            let end = (start + DASH_PX).min(length);
+            let p = from + direction * start;
+            let q = from + direction * end;
+            points.extend_from_slice(&[p.x, p.y, q.x, q.y]);
+            start = end + GAP_PX;
+        }
+
+        self.line_renderer
+            .scale_and_load_points(&mut points, vp.viewport.w, vp.viewport.h);
+        self.line_renderer.render_lines(Some(colour));
+    }
+
+    fn draw_guides(&mut self, vp: ViewInfo, guides: &[Guide]) {
+        const GUIDE_COLOUR: Colour = Colour([1.0, 0.2, 0.6, 0.9]);
+
+        if guides.is_empty() {
+            return;
+        }
+
+        let Rect { x, y, w, h } = vp.viewport;
+        let mut points = Vec::new();
+        for guide in guides {
+            match *guide {
+                Guide::Horizontal(at) => {
+                    let at = at * vp.grid_size - y;
+                    points.extend_from_slice(&[0.0, at, w, at]);
+                }
+                Guide::Vertical(at) => {
+                    let at = at * vp.grid_size - x;
+                    points.extend_from_slice(&[at, 0.0, at, h]);
+                }
+            }
+        }
+
+        self.line_renderer.scale_and_load_points(&mut points, w, h);
+        self.line_renderer.render_lines(Some(GUIDE_COLOUR));
+    }
+
+    fn draw_texture(
+        &mut self,
+        vp: ViewInfo,
+        position: Rect,
+        shape: Shape,
+        texture: Id,
+        opacity: f32,
+    ) {
         let texture = self.texture_library.get_texture(texture);
         self.texture_renderer.draw_texture(
             shape,
             texture,
             vp.viewport,
             position.scaled(vp.grid_size),
+            opacity,
         );
     }
 
@@ -338,12 +952,14 @@ impl Renderer for WebGlRenderer {
         stroke: f32,
         start: Cap,
         end: Cap,
+        taper: (bool, bool),
     ) {
         self.drawing_renderer.draw_drawing(
             drawing,
             stroke,
             start,
             end,
+            taper,
             colour,
             vp.viewport,
             position,
@@ -351,29 +967,68 @@ impl Renderer for WebGlRenderer {
         );
     }
 
+    fn draw_annotation(
+        &mut self,
+        vp: ViewInfo,
+        at: Point,
+        text: &str,
+        font_size: f32,
+        colour: Colour,
+    ) {
+        self.text_manager.render_annotation(
+            vp.viewport_point(at),
+            text,
+            font_size * vp.grid_size,
+            colour,
+        );
+    }
+
     fn draw_text(&mut self, vp: ViewInfo, at: Point, text: &str) {
         self.text_manager.render(vp.viewport_point(at), text);
     }
-}
 
-/// Parses a 16 digit hexadecimal media key string into an Id, returning 0
-/// on failure.
-pub fn parse_media_key(key: &str) -> scene::Id {
-    if key.len() != 16 {
-        return 0;
-    }
+    fn export_png(&mut self, vp: ViewInfo, scene: &Scene, transparent: bool) -> Res<Vec<u8>> {
+        let (width, height) = vp.physical_size();
+        let framebuffer = webgl::Framebuffer::new(self.gl.clone(), width, height)?;
 
-    let mut raw = [0; 8];
-    for (i, r) in raw.iter_mut().enumerate() {
-        let j = i * 2;
-        if let Ok(b) = u8::from_str_radix(&key[j..j + 2], 16) {
-            *r = b;
-        } else {
-            return 0;
+        framebuffer.bind();
+        self.clear(vp, scene.background);
+        self.draw_scene(vp, scene, None);
+        if scene.fog.active {
+            self.draw_fog(vp, &scene.fog, transparent);
         }
+        framebuffer.unbind();
+
+        encode_png(width, height, &framebuffer.read_pixels()?)
     }
+}
 
-    i64::from_be_bytes(raw)
+/// Encodes RGBA pixel data, ordered from top row to bottom, as a PNG image.
+fn encode_png(width: u32, height: u32, data: &[u8]) -> Res<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Failed to write PNG header: {e}"))?;
+    writer
+        .write_image_data(data)
+        .map_err(|e| format!("Failed to write PNG data: {e}"))?;
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to encode PNG: {e}"))?;
+
+    Ok(bytes)
+}
+
+/// Parses a hexadecimal media key string into an Id, returning 0 on failure.
+/// The server derives texture IDs in the same way when checking which media
+/// is used in scenes.
+pub fn parse_media_key(key: &str) -> scene::Id {
+    scene::media_key_id(key)
 }
 
 // Map value (as a proportion of scale) to [-1, 1]
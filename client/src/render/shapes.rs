@@ -66,6 +66,59 @@ fn add_hollow_ngon(dst: &mut PointVector, n: u32, rect: Rect) {
     }
 }
 
+/// Adds points for a closed outline running between the vertices `outer` and
+/// `inner`, broken up so that dashes of length `dash` alternate with gaps of
+/// length `gap`. Each edge is traversed by a parameter t from 0 to 1, with
+/// geometry emitted only for the intervals of t which fall within a dash. The
+/// pattern carries on from one edge to the next, measured along the outer
+/// edge.
+fn add_dashed_outline(
+    dst: &mut PointVector,
+    outer: &[Point],
+    inner: &[Point],
+    dash: f32,
+    gap: f32,
+) {
+    let period = dash + gap;
+    let n = outer.len();
+
+    // Distance into the pattern at the start of the current edge.
+    let mut offset = 0.0;
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (a0, a1, b0, b1) = (outer[i], outer[j], inner[i], inner[j]);
+        let length = a0.dist(a1);
+        if length <= 0.0 {
+            continue;
+        }
+
+        let mut d = 0.0;
+        while d < length {
+            let into = (offset + d) % period;
+            let (on, remaining) = if into < dash {
+                (true, dash - into)
+            } else {
+                (false, period - into)
+            };
+
+            // Ensure progress in spite of floating point error.
+            let end = (d + remaining.max(length * 1e-4)).min(length);
+            if on {
+                let (t0, t1) = (d / length, end / length);
+                let p = a0 + (a1 - a0) * t0;
+                let q = a0 + (a1 - a0) * t1;
+                let r = b0 + (b1 - b0) * t1;
+                let s = b0 + (b1 - b0) * t0;
+                dst.add_tri(p, q, s);
+                dst.add_tri(s, q, r);
+            }
+            d = end;
+        }
+
+        offset = (offset + length) % period;
+    }
+}
+
 /// Adds points for a semicircle to dst. The centre point of the semicircle is
 /// c, the radius is r. The semicircle arc is pi radians from start.
 fn add_semicircle(dst: &mut PointVector, c: Point, r: f32, start: f32) {
@@ -105,16 +158,31 @@ fn add_cap(dst: &mut PointVector, cap: scene::Cap, at: Point, direction: f32, st
     }
 }
 
+/// Proportion of the stroke width to draw at point i of n in a line, which
+/// narrows smoothly to nothing towards tapered ends.
+fn taper_width(i: usize, n: usize, (start, end): (bool, bool)) -> f32 {
+    let t = i as f32 / (n - 1) as f32;
+    match (start, end) {
+        (true, true) => (t * PI).sin(),
+        (true, false) => (t * PI / 2.0).sin(),
+        (false, true) => ((1.0 - t) * PI / 2.0).sin(),
+        (false, false) => 1.0,
+    }
+}
+
 /// Given a series of (x, y) coordinates, points, and a line width, produces a
 /// series of triangles (x1, y1, x2, y2, x3, y3) to render the drawing defined
 /// by those points. Assumes the input array is in scene units and produces
-/// points pre-scaled to [-1, 1] for drawing.
+/// points pre-scaled to [-1, 1] for drawing. If pressures are provided, the
+/// width at each point is scaled by the pressure at that point.
 fn add_line(
     dst: &mut PointVector,
     points: &PointVector,
     stroke: f32,
     cap_start: scene::Cap,
     cap_end: scene::Cap,
+    taper: (bool, bool),
+    pressures: Option<&[f32]>,
 ) {
     const CIRCLE_EDGES: u32 = 32;
 
@@ -124,7 +192,11 @@ fn add_line(
         return;
     }
 
-    let r = stroke / 2.0;
+    // Half the line width at each point
+    let r = |i: usize| {
+        let pressure = pressures.and_then(|p| p.get(i)).copied().unwrap_or(1.0);
+        taper_width(i, n, taper) * pressure * stroke / 2.0
+    };
 
     // Previous line endponts, used to close up gaps at corners
     let mut prev_c: Option<Point> = None;
@@ -157,14 +229,15 @@ fn add_line(
         let neg = theta - PI / 2.0;
 
         // Position changes to generate corner points
-        let above = Point::trig(pos) * r;
-        let below = Point::trig(neg) * r;
+        let above = Point::trig(pos);
+        let below = Point::trig(neg);
 
         // Calculate points
-        let a = p + above;
-        let b = p + below;
-        let c = q + below;
-        let d = q + above;
+        let (rp, rq) = (r(i - 1), r(i));
+        let a = p + above * rp;
+        let b = p + below * rp;
+        let c = q + below * rq;
+        let d = q + above * rq;
 
         // Draw line segment
         dst.add_tri(a, b, c);
@@ -172,10 +245,10 @@ fn add_line(
 
         // Draw caps for first and last line segment
         if i == 1 {
-            add_cap(dst, cap_start, p, theta - PI, stroke);
+            add_cap(dst, cap_start, p, theta - PI, rp * 2.0);
         }
         if i == last {
-            add_cap(dst, cap_end, q, theta, stroke);
+            add_cap(dst, cap_end, q, theta, rq * 2.0);
         }
 
         // Draw triangles over on the corner to close up the gap
@@ -266,6 +339,51 @@ pub fn hollow_shape(shape: scene::Shape, rect: Rect) -> Vec<f32> {
     }
 }
 
+/// Returns points for a hollow shape, with a stroke width given by the top
+/// left corner of rect and dimensions of the rect, where the stroke is broken
+/// into dashes of length `dash` separated by gaps of length `gap`.
+pub fn dashed_shape(shape: scene::Shape, rect: Rect, dash: f32, gap: f32) -> Vec<f32> {
+    let Rect { x, y, w, h } = rect;
+    let (outer, inner): (Vec<Point>, Vec<Point>) = match shape {
+        scene::Shape::Rectangle => (
+            vec![
+                Point::ORIGIN,
+                Point::new(w, 0.0),
+                Point::new(w, h),
+                Point::new(0.0, h),
+            ],
+            vec![
+                Point::new(x, y),
+                Point::new(w - x, y),
+                Point::new(w - x, h - y),
+                Point::new(x, h - y),
+            ],
+        ),
+        _ => {
+            let n = shape_edges(shape);
+            let c = Point::new(w / 2.0, h / 2.0);
+            let ra = c;
+            let rb = ra - rect.top_left();
+            let dt = TAU / n as f32;
+            (0..n)
+                .map(|i| {
+                    let delta = Point::trig(i as f32 * dt);
+                    (c + delta * ra, c + delta * rb)
+                })
+                .unzip()
+        }
+    };
+
+    let mut coords = PointVector::new();
+    if dash > 0.0 && gap > 0.0 {
+        add_dashed_outline(&mut coords, &outer, &inner, dash, gap);
+    } else {
+        // A single unbroken dash.
+        add_dashed_outline(&mut coords, &outer, &inner, f32::INFINITY, 0.0);
+    }
+    coords.data
+}
+
 pub fn outline_shape(shape: scene::Shape, rect: Rect) -> Vec<f32> {
     match shape {
         scene::Shape::Rectangle => {
@@ -281,14 +399,32 @@ pub fn line(
     stroke: f32,
     cap_start: scene::Cap,
     cap_end: scene::Cap,
+    taper: (bool, bool),
 ) -> PointVector {
+    // Tapered lines are split into segments so that the width can vary
+    // smoothly along the line.
+    const TAPER_SEGMENTS: u32 = 16;
+
+    let points = if taper.0 || taper.1 {
+        let mut points = PointVector::sized(TAPER_SEGMENTS + 1);
+        for i in 0..=TAPER_SEGMENTS {
+            let point = p + (q - p) * (i as f32 / TAPER_SEGMENTS as f32);
+            points.add_point(point.x, point.y);
+        }
+        points
+    } else {
+        PointVector::from(vec![p.x, p.y, q.x, q.y])
+    };
+
     let mut coords = PointVector::new();
     add_line(
         &mut coords,
-        &PointVector::from(vec![p.x, p.y, q.x, q.y]),
+        &points,
         stroke,
         cap_start,
         cap_end,
+        taper,
+        None,
     );
     coords
 }
@@ -298,9 +434,19 @@ pub fn freehand(
     stroke: f32,
     cap_start: scene::Cap,
     cap_end: scene::Cap,
+    taper: (bool, bool),
+    pressures: Option<&[f32]>,
 ) -> PointVector {
     let mut coords = PointVector::new();
-    add_line(&mut coords, points, stroke, cap_start, cap_end);
+    add_line(
+        &mut coords,
+        points,
+        stroke,
+        cap_start,
+        cap_end,
+        taper,
+        pressures,
+    );
     coords
 }
 
@@ -327,7 +473,7 @@ mod test {
         let p = Point::same(-1.0);
         let q = Point::same(1.0);
         let stroke = 1.0;
-        let points = line((p, q), stroke, Cap::None, Cap::None);
+        let points = line((p, q), stroke, Cap::None, Cap::None, (false, false));
 
         // Should have two triangles, splitting the diagonal of the line.
         assert_eq!(points.data.len(), 6 * 2);
@@ -345,4 +491,84 @@ mod test {
         assert_eq!(start, 3);
         assert_eq!(end, 3);
     }
+
+    #[test]
+    fn test_tapered_line() {
+        let p = Point::ORIGIN;
+        let q = Point::new(4.0, 0.0);
+        let stroke = 1.0;
+
+        // Width along the line, from the furthest point from the line at
+        // each x coordinate.
+        let width_at = |points: &PointVector, x: f32| {
+            let mut width: f32 = 0.0;
+            points.iter(|point| {
+                if float_eq(point.x, x) {
+                    width = width.max(point.y.abs() * 2.0);
+                }
+            });
+            width
+        };
+
+        // Both ends narrow to nothing, with full width in the middle.
+        let points = line((p, q), stroke, Cap::Round, Cap::Round, (true, true));
+        assert!(float_eq(width_at(&points, 0.0), 0.0));
+        assert!(float_eq(width_at(&points, 2.0), stroke));
+        assert!(float_eq(width_at(&points, 4.0), 0.0));
+        assert!(width_at(&points, 1.0) < stroke);
+
+        // Only the start narrows, widening towards the end.
+        let points = line((p, q), stroke, Cap::None, Cap::None, (true, false));
+        assert!(float_eq(width_at(&points, 0.0), 0.0));
+        assert!(width_at(&points, 1.0) < width_at(&points, 3.0));
+        assert!(float_eq(width_at(&points, 4.0), stroke));
+    }
+
+    #[test]
+    fn test_pressure_line() {
+        let points = PointVector::from(vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0]);
+        let stroke = 1.0;
+        let line = freehand(
+            &points,
+            stroke,
+            Cap::None,
+            Cap::None,
+            (false, false),
+            Some(&[1.0, 0.5, 0.0]),
+        );
+
+        // The half-width at each point is scaled by its pressure.
+        let mut widths = [0.0f32; 3];
+        line.iter(|point| {
+            let width = &mut widths[point.x.round() as usize];
+            *width = width.max(point.y.abs());
+        });
+        assert!(float_eq(widths[0], stroke / 2.0));
+        assert!(float_eq(widths[1], stroke / 4.0));
+        assert!(float_eq(widths[2], 0.0));
+    }
+
+    #[test]
+    fn test_dashed_rectangle() {
+        let rect = Rect::new(0.1, 0.1, 4.0, 2.0);
+
+        // Perimeter of 12 in dashes of 1 with gaps of 1 gives 6 dashes, each
+        // of two triangles, as the pattern lines up with the corners.
+        let points = dashed_shape(scene::Shape::Rectangle, rect, 1.0, 1.0);
+        assert_eq!(points.len(), 6 * 2 * 6);
+
+        // Every point lies on the outer or inner edge of the outline.
+        let points = PointVector::from(points);
+        points.iter(|p| {
+            let outer = float_eq(p.x, 0.0) || float_eq(p.x, 4.0) || float_eq(p.y, 0.0);
+            let outer = outer || float_eq(p.y, 2.0);
+            let inner = float_eq(p.x, 0.1) || float_eq(p.x, 3.9) || float_eq(p.y, 0.1);
+            let inner = inner || float_eq(p.y, 1.9);
+            assert!(outer || inner);
+        });
+
+        // Without gaps, each edge is a single dash.
+        let points = dashed_shape(scene::Shape::Rectangle, rect, 1.0, 0.0);
+        assert_eq!(points.len(), 4 * 2 * 6);
+    }
 }
@@ -1,4 +1,9 @@
-use crate::{dom::element::Element, viewport::ViewportPoint};
+use scene::Colour;
+
+use crate::{
+    dom::{element::Element, icon::Icon},
+    viewport::ViewportPoint,
+};
 
 const HOVER_ROOT_ID: &str = "canvas_text";
 
@@ -9,9 +14,47 @@ struct HoverText {
 impl HoverText {
     const HOVER_TEXT_CLASS: &'static str = "hover-text";
 
+    const ANNOTATION_CLASS: &'static str = "annotation-text";
+
+    const NAMEPLATE_CLASS: &'static str = "nameplate-text";
+
+    const COMMENT_CLASS: &'static str = "comment-marker";
+
     fn new(at: ViewportPoint, text: &str) -> Self {
+        Self::with_class(at, text, Self::HOVER_TEXT_CLASS)
+    }
+
+    fn annotation(at: ViewportPoint, text: &str, font_size: f32, colour: Colour) -> Self {
+        let text = Self::with_class(at, text, Self::ANNOTATION_CLASS);
+        text.element.set_css("font-size", &format!("{font_size}px"));
+        text.element.set_css(
+            "color",
+            &format!(
+                "rgba({}, {}, {}, {})",
+                colour.r() * 255.0,
+                colour.g() * 255.0,
+                colour.b() * 255.0,
+                colour.a()
+            ),
+        );
+        text
+    }
+
+    fn nameplate(at: ViewportPoint, text: &str, font_size: f32) -> Self {
+        let text = Self::with_class(at, text, Self::NAMEPLATE_CLASS);
+        text.element.set_css("font-size", &format!("{font_size}px"));
+        text
+    }
+
+    fn comment(at: ViewportPoint) -> Self {
+        let text = Self::with_class(at, "", Self::COMMENT_CLASS);
+        text.element.append_child(&Icon::Comment.element());
+        text
+    }
+
+    fn with_class(at: ViewportPoint, text: &str, class: &str) -> Self {
         let element = Element::new("div");
-        element.add_class(Self::HOVER_TEXT_CLASS);
+        element.add_class(class);
         element.set_css("left", &format!("{}px", at.x));
         element.set_css("top", &format!("{}px", at.y));
         element.set_text(text);
@@ -43,7 +86,31 @@ impl HoverTextManager {
     }
 
     pub fn render(&mut self, at: ViewportPoint, text: &str) {
-        let text = HoverText::new(at, text);
+        self.add(HoverText::new(at, text));
+    }
+
+    /// Render annotation text with its top left corner at `at`.
+    pub fn render_annotation(
+        &mut self,
+        at: ViewportPoint,
+        text: &str,
+        font_size: f32,
+        colour: Colour,
+    ) {
+        self.add(HoverText::annotation(at, text, font_size, colour));
+    }
+
+    /// Render nameplate text with its top centre at `at`.
+    pub fn render_nameplate(&mut self, at: ViewportPoint, text: &str, font_size: f32) {
+        self.add(HoverText::nameplate(at, text, font_size));
+    }
+
+    /// Render a comment marker centred at `at`.
+    pub fn render_comment(&mut self, at: ViewportPoint) {
+        self.add(HoverText::comment(at));
+    }
+
+    fn add(&mut self, text: HoverText) {
         self.element.append_child(&text.element);
         self.text.push(text);
     }
@@ -20,12 +20,20 @@ impl TextureRenderer {
         })
     }
 
-    pub fn draw_texture(&self, shape: Shape, texture: Texture, viewport: Rect, position: Rect) {
-        match shape {
-            Shape::Ellipse => self.ellipse.draw_texture(texture, viewport, position),
-            Shape::Hexagon => self.hexagon.draw_texture(texture, viewport, position),
-            Shape::Rectangle => self.rectangle.draw_texture(texture, viewport, position),
-            Shape::Triangle => self.triangle.draw_texture(texture, viewport, position),
-        }
+    pub fn draw_texture(
+        &self,
+        shape: Shape,
+        texture: Texture,
+        viewport: Rect,
+        position: Rect,
+        opacity: f32,
+    ) {
+        let renderer = match shape {
+            Shape::Ellipse => &self.ellipse,
+            Shape::Hexagon => &self.hexagon,
+            Shape::Rectangle => &self.rectangle,
+            Shape::Triangle => &self.triangle,
+        };
+        renderer.draw_texture(texture, viewport, position, opacity);
     }
 }
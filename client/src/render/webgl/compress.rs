@@ -0,0 +1,175 @@
+//! Compression of RGBA images to S3TC (DXT) blocks, for uploading large
+//! textures in a format which uses less GPU memory.
+
+/// Block compressed texture formats, with their WebGL constants from the
+/// `WEBGL_compressed_texture_s3tc` extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Opaque colour, 8 bytes per 4x4 block.
+    Dxt1 = 0x83F0,
+    /// Colour with interpolated alpha, 16 bytes per 4x4 block.
+    Dxt5 = 0x83F3,
+}
+
+/// Whether an image with these dimensions can be compressed. Blocks are 4x4
+/// pixels so both dimensions must be multiples of 4.
+pub fn compressible(width: u32, height: u32) -> bool {
+    width > 0 && height > 0 && width % 4 == 0 && height % 4 == 0
+}
+
+/// Compress `width` by `height` RGBA pixel data. Images which are fully
+/// opaque are compressed to DXT1, others to DXT5. The dimensions must be
+/// `compressible`.
+pub fn compress(width: u32, height: u32, rgba: &[u8]) -> (Format, Vec<u8>) {
+    let (width, height) = (width as usize, height as usize);
+    let opaque = rgba.iter().skip(3).step_by(4).all(|&a| a == u8::MAX);
+    let format = if opaque { Format::Dxt1 } else { Format::Dxt5 };
+
+    let mut out = Vec::with_capacity(width * height / if opaque { 2 } else { 1 });
+    let mut block = [[0u8; 4]; 16];
+    for by in (0..height).step_by(4) {
+        for bx in (0..width).step_by(4) {
+            for (i, pixel) in block.iter_mut().enumerate() {
+                let offset = ((by + i / 4) * width + bx + i % 4) * 4;
+                pixel.copy_from_slice(&rgba[offset..offset + 4]);
+            }
+
+            if !opaque {
+                alpha_block(&block, &mut out);
+            }
+            colour_block(&block, &mut out);
+        }
+    }
+
+    (format, out)
+}
+
+fn to_565([r, g, b, _]: [u8; 4]) -> u16 {
+    let scale = |v: u8, max: u32| ((v as u32 * max + 127) / 255) as u16;
+    (scale(r, 31) << 11) | (scale(g, 63) << 5) | scale(b, 31)
+}
+
+fn from_565(colour: u16) -> [i32; 3] {
+    let (r, g, b) = ((colour >> 11) & 31, (colour >> 5) & 63, colour & 31);
+    [
+        ((r << 3) | (r >> 2)) as i32,
+        ((g << 2) | (g >> 4)) as i32,
+        ((b << 3) | (b >> 2)) as i32,
+    ]
+}
+
+fn distance(a: [i32; 3], [r, g, b, _]: [u8; 4]) -> i32 {
+    let (dr, dg, db) = (a[0] - r as i32, a[1] - g as i32, a[2] - b as i32);
+    dr * dr + dg * dg + db * db
+}
+
+/// Encode the colours of a block as two endpoints, the most different
+/// colours in the block, and an index for each pixel into the four colours
+/// interpolated between them.
+fn colour_block(block: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let expand = |[r, g, b, _]: [u8; 4]| [r as i32, g as i32, b as i32];
+    let mut ends = (block[0], block[0]);
+    let mut furthest = 0;
+    for (i, &a) in block.iter().enumerate() {
+        for &b in &block[i + 1..] {
+            let dist = distance(expand(a), b);
+            if dist > furthest {
+                ends = (a, b);
+                furthest = dist;
+            }
+        }
+    }
+
+    // The first endpoint must be greater for four colour mode.
+    let (c0, c1) = (to_565(ends.0), to_565(ends.1));
+    let (c0, c1) = if c0 < c1 { (c1, c0) } else { (c0, c1) };
+
+    let mut indices = 0u32;
+    if c0 != c1 {
+        let (e0, e1) = (from_565(c0), from_565(c1));
+        let lerp = |a: i32, b: i32| (2 * a + b) / 3;
+        let palette = [
+            e0,
+            e1,
+            [0, 1, 2].map(|c| lerp(e0[c], e1[c])),
+            [0, 1, 2].map(|c| lerp(e1[c], e0[c])),
+        ];
+
+        for (i, &pixel) in block.iter().enumerate() {
+            let index = (0..4).min_by_key(|&j| distance(palette[j], pixel)).unwrap();
+            indices |= (index as u32) << (i * 2);
+        }
+    }
+
+    out.extend_from_slice(&c0.to_le_bytes());
+    out.extend_from_slice(&c1.to_le_bytes());
+    out.extend_from_slice(&indices.to_le_bytes());
+}
+
+/// Encode the alpha of a block as its greatest and least values, and an
+/// index for each pixel into the eight values interpolated between them.
+fn alpha_block(block: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let a0 = block.iter().map(|p| p[3]).max().unwrap_or(u8::MAX);
+    let a1 = block.iter().map(|p| p[3]).min().unwrap_or(u8::MAX);
+
+    let mut indices = 0u64;
+    if a0 != a1 {
+        let (a0, a1) = (a0 as i32, a1 as i32);
+        let value = |index: i32| match index {
+            0 => a0,
+            1 => a1,
+            i => ((8 - i) * a0 + (i - 1) * a1) / 7,
+        };
+
+        for (i, pixel) in block.iter().enumerate() {
+            let alpha = pixel[3] as i32;
+            let index = (0..8).min_by_key(|&j| (value(j) - alpha).abs()).unwrap();
+            indices |= (index as u64) << (i * 3);
+        }
+    }
+
+    out.push(a0);
+    out.push(a1);
+    out.extend_from_slice(&indices.to_le_bytes()[..6]);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compress, compressible, Format};
+
+    #[test]
+    fn test_compress() {
+        assert!(compressible(8, 4));
+        assert!(!compressible(6, 4));
+
+        // Two opaque blocks, the first red and the second half red, half blue.
+        let mut rgba = Vec::new();
+        for _ in 0..4 {
+            for x in 0..8 {
+                rgba.extend_from_slice(if x < 6 {
+                    &[255, 0, 0, 255]
+                } else {
+                    &[0, 0, 255, 255]
+                });
+            }
+        }
+        let (format, data) = compress(8, 4, &rgba);
+        assert_eq!(format, Format::Dxt1);
+        assert_eq!(data.len(), 16);
+
+        // A single colour block has both endpoints that colour.
+        assert_eq!(&data[..8], &[0x00, 0xF8, 0x00, 0xF8, 0, 0, 0, 0]);
+
+        // Red and blue endpoints, red pixels index 0 and blue index 1.
+        assert_eq!(&data[8..12], &[0x00, 0xF8, 0x1F, 0x00]);
+        assert_eq!(&data[12..], &[0b0101_0000; 4]);
+
+        // Transparency uses DXT5, with alpha endpoints before the colours.
+        rgba[3] = 0;
+        let (format, data) = compress(8, 4, &rgba);
+        assert_eq!(format, Format::Dxt5);
+        assert_eq!(data.len(), 32);
+        assert_eq!(&data[..2], &[255, 0]);
+        assert_eq!(data[2] & 0b111, 1);
+    }
+}
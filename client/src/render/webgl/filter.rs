@@ -0,0 +1,181 @@
+use std::rc::Rc;
+
+use js_sys::Float32Array;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlUniformLocation};
+
+use super::{create_buffer, create_program, get_uniform_location, Framebuffer, Gl};
+use crate::Res;
+
+/// Colour vision deficiencies which the renderer can simulate, so that
+/// colours can be chosen which all players can tell apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColourBlindMode {
+    #[default]
+    None,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl ColourBlindMode {
+    pub const ALL: [ColourBlindMode; 4] = [
+        ColourBlindMode::None,
+        ColourBlindMode::Protanopia,
+        ColourBlindMode::Deuteranopia,
+        ColourBlindMode::Tritanopia,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            ColourBlindMode::None => "None",
+            ColourBlindMode::Protanopia => "Protanopia",
+            ColourBlindMode::Deuteranopia => "Deuteranopia",
+            ColourBlindMode::Tritanopia => "Tritanopia",
+        }
+    }
+
+    pub fn from(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.to_str() == name)
+    }
+
+    /// Row-major linear RGB transformation simulating this deficiency, from
+    /// Machado, Oliveira and Fernandes (2009). This combines the conversion
+    /// to LMS cone responses, the loss of one cone type and the conversion
+    /// back. None if no filter should be applied.
+    fn matrix(self) -> Option<[f32; 9]> {
+        match self {
+            ColourBlindMode::None => None,
+            ColourBlindMode::Protanopia => Some([
+                0.152286, 1.052583, -0.204868, //
+                0.114503, 0.786281, 0.099216, //
+                -0.003882, -0.048116, 1.051998,
+            ]),
+            ColourBlindMode::Deuteranopia => Some([
+                0.367322, 0.860646, -0.227968, //
+                0.280085, 0.672501, 0.047413, //
+                -0.011820, 0.042940, 0.968881,
+            ]),
+            ColourBlindMode::Tritanopia => Some([
+                1.255528, -0.076749, -0.178779, //
+                -0.078411, 0.930809, 0.147602, //
+                0.004733, 0.691367, 0.303900,
+            ]),
+        }
+    }
+}
+
+/// Post-processing step applying a colour transformation to each frame. When
+/// a filter is active, the frame is rendered to an offscreen framebuffer,
+/// which is then drawn to the canvas through the filter.
+pub struct ColourFilter {
+    gl: Rc<Gl>,
+    program: WebGlProgram,
+    position_location: u32,
+    position_buffer: WebGlBuffer,
+    texture_location: WebGlUniformLocation,
+    matrix_location: WebGlUniformLocation,
+    mode: ColourBlindMode,
+    framebuffer: Option<Framebuffer>,
+}
+
+impl ColourFilter {
+    pub fn new(gl: Rc<Gl>) -> Res<ColourFilter> {
+        let program = create_program(
+            &gl,
+            include_str!("shaders/filter.vert"),
+            include_str!("shaders/filter.frag"),
+        )?;
+        let position_location = gl.get_attrib_location(&program, "a_position") as u32;
+        let position_buffer = create_buffer(
+            &gl,
+            Some(&Float32Array::from(
+                &[-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0][..],
+            )),
+        )?;
+
+        Ok(ColourFilter {
+            position_location,
+            position_buffer,
+            texture_location: get_uniform_location(&gl, &program, "u_texture")?,
+            matrix_location: get_uniform_location(&gl, &program, "u_matrix")?,
+            mode: ColourBlindMode::None,
+            framebuffer: None,
+            program,
+            gl,
+        })
+    }
+
+    pub fn set_mode(&mut self, mode: ColourBlindMode) {
+        self.mode = mode;
+        if mode.matrix().is_none() {
+            self.framebuffer = None;
+        }
+    }
+
+    /// Prepare to render a frame of `width` by `height` device pixels,
+    /// redirecting rendering offscreen if a filter is active.
+    pub fn begin(&mut self, width: u32, height: u32) -> Res<()> {
+        if self.mode.matrix().is_none() {
+            return Ok(());
+        }
+
+        if self
+            .framebuffer
+            .as_ref()
+            .is_none_or(|f| f.dimensions() != (width, height))
+        {
+            self.framebuffer = Some(Framebuffer::new(self.gl.clone(), width, height)?);
+        }
+
+        if let Some(framebuffer) = &self.framebuffer {
+            framebuffer.bind();
+        }
+        Ok(())
+    }
+
+    /// Draw the frame rendered since `begin` to the canvas through the
+    /// filter.
+    pub fn finish(&self) {
+        let (Some(matrix), Some(framebuffer)) = (self.mode.matrix(), &self.framebuffer) else {
+            return;
+        };
+        framebuffer.unbind();
+
+        let gl = &self.gl;
+        gl.use_program(Some(&self.program));
+        gl.enable_vertex_attrib_array(self.position_location);
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position_buffer));
+        gl.vertex_attrib_pointer_with_i32(self.position_location, 2, Gl::FLOAT, false, 0, 0);
+
+        gl.active_texture(Gl::TEXTURE0);
+        gl.bind_texture(Gl::TEXTURE_2D, Some(framebuffer.texture()));
+        gl.uniform1i(Some(&self.texture_location), 0);
+        gl.uniform_matrix3fv_with_f32_array(Some(&self.matrix_location), true, &matrix);
+
+        // The frame replaces the canvas' contents rather than blending.
+        gl.disable(Gl::BLEND);
+        gl.draw_arrays(Gl::TRIANGLE_STRIP, 0, 4);
+        gl.enable(Gl::BLEND);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColourBlindMode;
+
+    #[test]
+    fn test_colour_blind_mode() {
+        for mode in ColourBlindMode::ALL {
+            assert_eq!(ColourBlindMode::from(mode.to_str()), Some(mode));
+
+            // Each row sums to roughly one, so that white stays white.
+            if let Some(matrix) = mode.matrix() {
+                for row in matrix.chunks_exact(3) {
+                    assert!((row.iter().sum::<f32>() - 1.0).abs() < 0.001);
+                }
+            }
+        }
+        assert!(ColourBlindMode::None.matrix().is_none());
+        assert!(ColourBlindMode::from("Achromatopsia").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use std::rc::Rc;
+
+use web_sys::{WebGlFramebuffer, WebGlTexture};
+
+use super::Gl;
+use crate::{err, Res};
+
+/// An offscreen render target. While bound, all rendering is performed onto
+/// a texture of the given dimensions rather than the canvas.
+pub struct Framebuffer {
+    gl: Rc<Gl>,
+    framebuffer: WebGlFramebuffer,
+    texture: WebGlTexture,
+    width: u32,
+    height: u32,
+}
+
+impl Framebuffer {
+    pub fn new(gl: Rc<Gl>, width: u32, height: u32) -> Res<Self> {
+        let Some(texture) = gl.create_texture() else {
+            return err("Failed to create framebuffer texture.");
+        };
+
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+        if gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                Gl::TEXTURE_2D,
+                0,
+                Gl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                Gl::RGBA,
+                Gl::UNSIGNED_BYTE,
+                None,
+            )
+            .is_err()
+        {
+            gl.delete_texture(Some(&texture));
+            return err("Failed to allocate framebuffer texture.");
+        }
+
+        // Without mipmaps, the texture can only be sampled without them.
+        for (param, value) in [
+            (Gl::TEXTURE_MIN_FILTER, Gl::NEAREST),
+            (Gl::TEXTURE_MAG_FILTER, Gl::NEAREST),
+            (Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE),
+            (Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE),
+        ] {
+            gl.tex_parameteri(Gl::TEXTURE_2D, param, value as i32);
+        }
+
+        let Some(framebuffer) = gl.create_framebuffer() else {
+            gl.delete_texture(Some(&texture));
+            return err("Failed to create framebuffer.");
+        };
+
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            Gl::FRAMEBUFFER,
+            Gl::COLOR_ATTACHMENT0,
+            Gl::TEXTURE_2D,
+            Some(&texture),
+            0,
+        );
+        let status = gl.check_framebuffer_status(Gl::FRAMEBUFFER);
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+
+        let framebuffer = Self {
+            gl,
+            framebuffer,
+            texture,
+            width,
+            height,
+        };
+
+        if status == Gl::FRAMEBUFFER_COMPLETE {
+            Ok(framebuffer)
+        } else {
+            err(format!("Framebuffer incomplete (status {status})."))
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Texture which is rendered onto while the framebuffer is bound.
+    pub fn texture(&self) -> &WebGlTexture {
+        &self.texture
+    }
+
+    pub fn bind(&self) {
+        self.gl
+            .bind_framebuffer(Gl::FRAMEBUFFER, Some(&self.framebuffer));
+    }
+
+    pub fn unbind(&self) {
+        self.gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+    }
+
+    /// Read back the contents of the framebuffer as RGBA bytes, ordered from
+    /// the top row of pixels to the bottom.
+    pub fn read_pixels(&self) -> Res<Vec<u8>> {
+        let row = self.width as usize * 4;
+        let mut data = vec![0; row * self.height as usize];
+
+        self.bind();
+        let result = self.gl.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            Gl::RGBA,
+            Gl::UNSIGNED_BYTE,
+            Some(&mut data),
+        );
+        self.unbind();
+
+        if result.is_err() {
+            return err("Failed to read framebuffer pixels.");
+        }
+
+        // WebGL places the origin at the bottom left, so flip the rows.
+        let mut flipped = Vec::with_capacity(data.len());
+        for line in data.chunks_exact(row).rev() {
+            flipped.extend_from_slice(line);
+        }
+        Ok(flipped)
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffer(Some(&self.framebuffer));
+        self.gl.delete_texture(Some(&self.texture));
+    }
+}
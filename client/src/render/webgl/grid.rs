@@ -0,0 +1,153 @@
+use std::rc::Rc;
+
+use js_sys::Float32Array;
+use scene::Colour;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlUniformLocation};
+
+use super::{create_buffer, create_program, get_uniform_location, Gl};
+use crate::{render::ViewInfo, Res};
+
+/// Renders the grid lines of the scene. Each line is an instance of a unit
+/// line segment, positioned by the vertex shader, so no geometry needs to be
+/// generated as the viewport moves.
+pub struct GridRenderer {
+    gl: Rc<Gl>,
+    program: WebGlProgram,
+    position_location: u32,
+    position_buffer: WebGlBuffer,
+    colour_location: WebGlUniformLocation,
+    viewport_location: WebGlUniformLocation,
+    grid_size_location: WebGlUniformLocation,
+    dimensions_location: WebGlUniformLocation,
+    first_location: WebGlUniformLocation,
+    verticals_location: WebGlUniformLocation,
+}
+
+impl GridRenderer {
+    pub fn new(gl: Rc<Gl>) -> Res<GridRenderer> {
+        let program = create_program(
+            &gl,
+            include_str!("shaders/grid.vert"),
+            include_str!("shaders/grid.frag"),
+        )?;
+        let position_location = gl.get_attrib_location(&program, "a_position") as u32;
+        let position_buffer = create_buffer(&gl, Some(&Float32Array::from(&[0.0, 1.0][..])))?;
+
+        Ok(GridRenderer {
+            position_location,
+            position_buffer,
+            colour_location: get_uniform_location(&gl, &program, "u_colour")?,
+            viewport_location: get_uniform_location(&gl, &program, "u_viewport")?,
+            grid_size_location: get_uniform_location(&gl, &program, "u_grid_size")?,
+            dimensions_location: get_uniform_location(&gl, &program, "u_dimensions")?,
+            first_location: get_uniform_location(&gl, &program, "u_first")?,
+            verticals_location: get_uniform_location(&gl, &program, "u_verticals")?,
+            program,
+            gl,
+        })
+    }
+
+    /// Render grid lines every `spacing` tiles across a scene of `(w, h)`
+    /// tiles.
+    pub fn render_grid(&self, vp: ViewInfo, (w, h): (u32, u32), spacing: u32, colour: Colour) {
+        let gl = &self.gl;
+        let viewport = vp.viewport;
+        let spacing = spacing.max(1);
+        let size = vp.grid_size * spacing as f32;
+
+        let (first_x, verticals) = visible_lines(viewport.x, viewport.w, size, w / spacing);
+        let (first_y, horizontals) = visible_lines(viewport.y, viewport.h, size, h / spacing);
+        if verticals + horizontals == 0 {
+            return;
+        }
+
+        gl.use_program(Some(&self.program));
+        gl.enable_vertex_attrib_array(self.position_location);
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.position_buffer));
+        gl.vertex_attrib_pointer_with_i32(self.position_location, 1, Gl::FLOAT, false, 0, 0);
+
+        gl.uniform4fv_with_f32_array(Some(&self.colour_location), &colour.raw());
+        gl.uniform4f(
+            Some(&self.viewport_location),
+            viewport.x,
+            viewport.y,
+            viewport.w,
+            viewport.h,
+        );
+        gl.uniform1f(Some(&self.grid_size_location), size);
+        gl.uniform2f(
+            Some(&self.dimensions_location),
+            w as f32 / spacing as f32,
+            h as f32 / spacing as f32,
+        );
+        gl.uniform2f(Some(&self.first_location), first_x as f32, first_y as f32);
+        gl.uniform1i(Some(&self.verticals_location), verticals as i32);
+
+        gl.draw_arrays_instanced(Gl::LINES, 0, 2, (verticals + horizontals) as i32);
+    }
+}
+
+/// Tile coordinates of the intersections of grid lines every `spacing` tiles
+/// which are in view, for a scene of `(w, h)` tiles.
+pub fn grid_intersections(vp: ViewInfo, (w, h): (u32, u32), spacing: u32) -> Vec<(u32, u32)> {
+    let viewport = vp.viewport;
+    let spacing = spacing.max(1);
+    let size = vp.grid_size * spacing as f32;
+
+    let (first_x, verticals) = visible_lines(viewport.x, viewport.w, size, w / spacing);
+    let (first_y, horizontals) = visible_lines(viewport.y, viewport.h, size, h / spacing);
+    (first_x..first_x + verticals)
+        .flat_map(|x| (first_y..first_y + horizontals).map(move |y| (x * spacing, y * spacing)))
+        .collect()
+}
+
+/// Index of the first grid line in view along an axis and the number of lines
+/// in view, for a view starting at `start` with `length` pixels, `size`
+/// pixel tiles and a scene `tiles` long.
+fn visible_lines(start: f32, length: f32, size: f32, tiles: u32) -> (u32, u32) {
+    if size <= 0.0 {
+        return (0, 0);
+    }
+
+    let first = (start / size).ceil().max(0.0);
+    let last = ((start + length) / size).floor().min(tiles as f32);
+    if last < first {
+        (0, 0)
+    } else {
+        (first as u32, (last - first) as u32 + 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use scene::Rect;
+
+    use super::{grid_intersections, visible_lines};
+    use crate::render::ViewInfo;
+
+    #[test]
+    fn test_visible_lines() {
+        // Scene fully in view, with lines at both edges.
+        assert_eq!(visible_lines(-10.0, 200.0, 10.0, 5), (0, 6));
+
+        // View partway across the scene, with lines at 30, 40 and 50.
+        assert_eq!(visible_lines(25.0, 30.0, 10.0, 10), (3, 3));
+
+        // No lines when the scene is out of view.
+        assert_eq!(visible_lines(200.0, 50.0, 10.0, 10), (0, 0));
+        assert_eq!(visible_lines(-100.0, 50.0, 10.0, 10), (0, 0));
+    }
+
+    #[test]
+    fn test_grid_intersections() {
+        // 10px tiles, with a view of tiles 3 to 13 across and down.
+        let vp = ViewInfo::new(Rect::new(30.0, 30.0, 100.0, 100.0), 10.0, 1.0);
+        assert_eq!(
+            grid_intersections(vp, (12, 20), 5),
+            vec![(5, 5), (5, 10), (10, 5), (10, 10)]
+        );
+
+        // Lines every tile, limited to the scene.
+        assert_eq!(grid_intersections(vp, (3, 3), 1), vec![(3, 3)]);
+    }
+}
@@ -3,6 +3,10 @@ use web_sys::{
     WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlShader, WebGlUniformLocation,
 };
 
+mod compress;
+mod filter;
+mod framebuffer;
+mod grid;
 mod line;
 mod mesh;
 mod solid;
@@ -12,8 +16,9 @@ pub type Gl = WebGl2RenderingContext;
 pub type Texture<'a> = texture::TextureRef<'a>;
 
 pub use {
-    line::LineRenderer, mesh::Mesh, solid::SolidRenderer, texture::TextureManager,
-    texture::TextureShapeRenderer,
+    filter::ColourBlindMode, filter::ColourFilter, framebuffer::Framebuffer,
+    grid::grid_intersections, grid::GridRenderer, line::LineRenderer, mesh::Mesh,
+    solid::SolidRenderer, texture::TextureManager, texture::TextureShapeRenderer,
 };
 
 use crate::{err, Res};
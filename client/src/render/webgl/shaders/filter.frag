@@ -0,0 +1,20 @@
+#version 300 es
+
+precision mediump float;
+
+in vec2 v_texcoord;
+
+// Rendered frame to filter.
+uniform sampler2D u_texture;
+
+// Transformation applied to each colour, in linear RGB.
+uniform mat3 u_matrix;
+
+out vec4 colour;
+
+void main() {
+    vec4 texel = texture(u_texture, v_texcoord);
+    vec3 linear = pow(texel.rgb, vec3(2.2));
+    vec3 filtered = clamp(u_matrix * linear, 0.0, 1.0);
+    colour = vec4(pow(filtered, vec3(1.0 / 2.2)), texel.a);
+}
@@ -0,0 +1,11 @@
+#version 300 es
+
+// Corner of a quad covering the canvas, in clip space.
+in vec2 a_position;
+
+out vec2 v_texcoord;
+
+void main() {
+    v_texcoord = (a_position + 1.0) / 2.0;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 300 es
+
+precision mediump float;
+
+uniform vec4 u_colour;
+
+out vec4 colour;
+
+void main() {
+    colour = u_colour;
+}
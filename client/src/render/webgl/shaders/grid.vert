@@ -0,0 +1,39 @@
+#version 300 es
+
+// Position along a unit line segment, 0 at the start and 1 at the end.
+in float a_position;
+
+// Viewport position and dimensions, in pixels.
+uniform vec4 u_viewport;
+
+// Size of a grid tile, in pixels.
+uniform float u_grid_size;
+
+// Scene width and height, in tiles.
+uniform vec2 u_dimensions;
+
+// Indices of the first vertical and horizontal lines in view.
+uniform vec2 u_first;
+
+// Number of vertical lines. These are the first instances, with the
+// horizontal lines following.
+uniform int u_verticals;
+
+void main() {
+    // Lines span the part of the scene which is in view.
+    vec2 start = max(u_viewport.xy, vec2(0.0));
+    vec2 end = min(u_viewport.xy + u_viewport.zw, u_dimensions * u_grid_size);
+
+    vec2 point;
+    if (gl_InstanceID < u_verticals) {
+        float x = (u_first.x + float(gl_InstanceID)) * u_grid_size;
+        point = vec2(x, mix(start.y, end.y, a_position));
+    } else {
+        float y = (u_first.y + float(gl_InstanceID - u_verticals)) * u_grid_size;
+        point = vec2(mix(start.x, end.x, a_position), y);
+    }
+
+    // The y axis is flipped, as in clip space it points up.
+    vec2 unit = (point - u_viewport.xy) / u_viewport.zw * 2.0 - 1.0;
+    gl_Position = vec4(unit.x, -unit.y, 0.0, 1.0);
+}
@@ -4,7 +4,7 @@ use scene::{Rect, Shape};
 use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::{HtmlImageElement, WebGlBuffer, WebGlProgram, WebGlTexture, WebGlUniformLocation};
 
-use super::{create_buffer, create_program, get_uniform_location, mesh::Mesh, Gl};
+use super::{compress, create_buffer, create_program, get_uniform_location, mesh::Mesh, Gl};
 use crate::{err, render::parse_media_key, Res};
 
 pub struct TextureRef<'a>(&'a WebGlTexture);
@@ -94,6 +94,36 @@ impl Texture {
         Ok(())
     }
 
+    /// Load `image` as a block compressed texture. Fails if the image's
+    /// pixels can't be read or its dimensions aren't a multiple of the block
+    /// size.
+    fn from_compressed_image(gl: &Gl, image: &HtmlImageElement) -> Res<Texture> {
+        let (width, height) = (image.natural_width(), image.natural_height());
+        if !compress::compressible(width, height) {
+            return err("Image dimensions not compressible.");
+        }
+
+        let rgba = crate::bridge::image_pixels(image)?;
+        let (format, data) = compress::compress(width, height, &rgba);
+
+        let mut texture = Texture::new(gl)?;
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&texture.texture));
+        gl.compressed_tex_image_2d_with_u8_array(
+            Gl::TEXTURE_2D,
+            Self::GL_TEXTURE_DETAIL_LEVEL,
+            format as u32,
+            width as i32,
+            height as i32,
+            Self::GL_TEXTURE_BORDER_WIDTH,
+            &data,
+        );
+        texture.gen_mipmap(gl);
+        texture.width = width;
+        texture.height = height;
+
+        Ok(texture)
+    }
+
     fn load_html_image_gl_texture(
         gl: &Gl,
         image: &HtmlImageElement,
@@ -150,15 +180,27 @@ pub struct TextureManager {
     gl: Rc<Gl>,
     textures: HashMap<scene::Id, Texture>,
     loading: Vec<scene::Id>,
+
+    // Whether S3TC compressed textures are supported.
+    s3tc: bool,
 }
 
 impl TextureManager {
+    // Images with at least this many pixels are compressed when loaded, if
+    // compressed textures are supported.
+    const COMPRESS_MIN_PIXELS: u32 = 1024 * 1024;
+
     pub fn new(gl: Rc<Gl>) -> Res<TextureManager> {
         let missing_texture = Texture::from_u8_array(&gl, 1, 1, &[0, 0, 255, 255])?;
+        let s3tc = matches!(
+            gl.get_extension("WEBGL_compressed_texture_s3tc"),
+            Ok(Some(_))
+        );
         let mut tm = TextureManager {
             gl,
             textures: HashMap::new(),
             loading: Vec::new(),
+            s3tc,
         };
         tm.add_texture(0, missing_texture);
         Ok(tm)
@@ -171,7 +213,16 @@ impl TextureManager {
         };
 
         if id != 0 {
-            match Texture::from_html_image(&self.gl, image) {
+            let texture = if self.s3tc
+                && image.natural_width() * image.natural_height() >= Self::COMPRESS_MIN_PIXELS
+            {
+                Texture::from_compressed_image(&self.gl, image)
+                    .or_else(|_| Texture::from_html_image(&self.gl, image))
+            } else {
+                Texture::from_html_image(&self.gl, image)
+            };
+
+            match texture {
                 Ok(t) => self.textures.insert(id, t),
                 Err(_) => return 0,
             };
@@ -212,6 +263,7 @@ pub struct TextureShapeRenderer {
     texcoord_buffer: WebGlBuffer,
     texcoord_location: u32,
     texture_location: WebGlUniformLocation,
+    opacity_location: WebGlUniformLocation,
     shape: Mesh,
 }
 
@@ -228,6 +280,7 @@ impl TextureShapeRenderer {
         let texcoord_location = gl.get_attrib_location(&program, "a_texcoord") as u32;
         let texcoord_buffer = create_buffer(&gl, Some(shape.points()))?;
         let texture_location = get_uniform_location(&gl, &program, "u_texture")?;
+        let opacity_location = get_uniform_location(&gl, &program, "u_opacity")?;
 
         Ok(TextureShapeRenderer {
             gl,
@@ -235,11 +288,12 @@ impl TextureShapeRenderer {
             texcoord_buffer,
             texcoord_location,
             texture_location,
+            opacity_location,
             shape,
         })
     }
 
-    pub fn draw_texture(&self, texture: TextureRef, viewport: Rect, position: Rect) {
+    pub fn draw_texture(&self, texture: TextureRef, viewport: Rect, position: Rect, opacity: f32) {
         let gl = &self.gl;
 
         gl.bind_texture(Gl::TEXTURE_2D, Some(texture.0));
@@ -249,6 +303,7 @@ impl TextureShapeRenderer {
         gl.vertex_attrib_pointer_with_i32(self.texcoord_location, 2, Gl::FLOAT, false, 0, 0);
 
         gl.uniform1i(Some(&self.texture_location), 0);
+        gl.uniform1f(Some(&self.opacity_location), opacity);
         self.shape.draw(gl, viewport, position);
     }
 }
@@ -8,8 +8,8 @@ use std::sync::Mutex;
 use wasm_bindgen::{prelude::*, JsCast};
 
 use crate::bridge::{
-    console_err, console_log, expose_closure_f64x2_string, expose_closure_string_out, load_project,
-    log, request_animation_frame,
+    console_err, console_log, expose_closure_f64x2_string, expose_closure_string_in,
+    expose_closure_string_out, load_project, log, request_animation_frame, timestamp_ms, DeepLink,
 };
 use crate::client::Client;
 use crate::dom::menu::Menu;
@@ -44,12 +44,13 @@ fn parse_json<'a, T: serde::Deserialize<'a>>(json: &'a str) -> Option<T> {
 pub fn start() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
 
-    let client = match Client::new() {
-        Ok(c) => c,
+    let (client, link) = match Client::new() {
+        Ok(Some((client, link))) => (Some(client), link),
+        Ok(None) => (None, DeepLink::default()),
         Err(e) => return logged_error(&format!("Failed to connect to game: {e}")),
     };
 
-    let vp = match Viewport::new(client) {
+    let vp = match Viewport::new(client, link) {
         Ok(s) => Rc::new(Mutex::new(s)),
         Err(_) => return logged_error("Failed to create viewport."),
     };
@@ -58,6 +59,10 @@ pub fn start() -> Result<(), JsValue> {
         lock.add_menu(Menu::new(vp.clone(), scene::perms::Role::Owner))
     });
 
+    if crate::bridge::is_share_page() {
+        lock_and(&vp, |lock| lock.int.spectate());
+    }
+
     let vp_ref = vp.clone();
     let new_sprite_closure = Closure::wrap(Box::new(move |w: f64, h: f64, media_key: String| {
         let texture = crate::render::parse_media_key(&media_key);
@@ -76,6 +81,14 @@ pub fn start() -> Result<(), JsValue> {
     expose_closure_f64x2_string("new_sprite", &new_sprite_closure);
     new_sprite_closure.forget();
 
+    let vp_ref = vp.clone();
+    let set_background_closure = Closure::wrap(Box::new(move |media_key: String| {
+        let texture = crate::render::parse_media_key(&media_key);
+        lock_and(&vp_ref, |vp| vp.int.set_background_texture(Some(texture)))
+    }) as Box<dyn FnMut(String)>);
+    expose_closure_string_in("set_background", &set_background_closure);
+    set_background_closure.forget();
+
     let vp_ref = vp.clone();
     let active_scene_closure =
         Closure::wrap(
@@ -101,6 +114,14 @@ pub fn start() -> Result<(), JsValue> {
         .set_onbeforeunload(Some(before_unload_closure.as_ref().unchecked_ref()));
     before_unload_closure.forget();
 
+    if let Err(e) = crate::bridge::load_sprite_templates(vp.clone()) {
+        console_err(&e);
+    }
+
+    if let Err(e) = crate::bridge::load_palettes(vp.clone()) {
+        console_err(&e);
+    }
+
     if let Err(e) = load_project(vp.clone()) {
         console_err(&e);
     }
@@ -110,6 +131,7 @@ pub fn start() -> Result<(), JsValue> {
 
     *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
         if let Ok(mut lock) = vp.lock() {
+            lock.tick(timestamp_ms());
             lock.animation_frame();
         } else {
             console_log("Failed to lock viewport for animation frame.");
@@ -1,6 +1,8 @@
-use crate::bridge::{save_project, timestamp_ms, ReqState};
+use crate::bridge::{
+    local_storage_get, local_storage_set, prompt, save_project, timestamp_ms, DeepLink, ReqState,
+};
 use crate::dom::menu::{CanvasDropdownEvent, Menu};
-use crate::render::Renderer;
+use crate::render::{ColourBlindMode, Renderer};
 use crate::scene::{Point, Rect};
 use crate::Res;
 use crate::{
@@ -44,9 +46,34 @@ pub enum DrawTool {
     Circle,
     Cone,
     Ellipse,
+    Erase,
     Freehand,
+    Light,
     Line,
     Rectangle,
+    Ruler,
+    Template(TemplateShape),
+    Text,
+}
+
+/// Area of effect templates. Dimensions are in scene units.
+#[derive(Clone, Copy, Debug)]
+pub enum TemplateShape {
+    Cone(f32),      // (length)
+    Sphere(f32),    // (radius)
+    Line(f32, f32), // (length, width)
+}
+
+impl TemplateShape {
+    pub const DEFAULT: TemplateShape = TemplateShape::Cone(3.0);
+
+    pub fn from(name: &str) -> Self {
+        match name {
+            "sphere" => TemplateShape::Sphere(4.0),
+            "line" => TemplateShape::Line(6.0, 1.0),
+            _ => TemplateShape::DEFAULT,
+        }
+    }
 }
 
 impl DrawTool {
@@ -55,13 +82,59 @@ impl DrawTool {
             DrawTool::Circle => None,
             DrawTool::Cone => Some(scene::DrawingMode::Cone),
             DrawTool::Ellipse => None,
+            DrawTool::Erase => None,
             DrawTool::Freehand => Some(scene::DrawingMode::Freehand),
+            DrawTool::Light => None,
             DrawTool::Line => Some(scene::DrawingMode::Line),
             DrawTool::Rectangle => None,
+            DrawTool::Ruler => None,
+            DrawTool::Template(_) => None,
+            DrawTool::Text => Some(scene::DrawingMode::Text),
         }
     }
 }
 
+/// Camera movement from one view to another, e.g. when jumping to a
+/// waypoint.
+struct ViewAnimation {
+    from: Rect,
+    from_zoom: f32,
+    to: Rect,
+    to_zoom: f32,
+    start: u64,
+    deadline: u64,
+}
+
+impl ViewAnimation {
+    /// View and grid zoom for the time `now`, and whether the animation has
+    /// finished.
+    fn step(&self, now: u64) -> (Rect, f32, bool) {
+        let t = if now >= self.deadline {
+            1.0
+        } else {
+            now.saturating_sub(self.start) as f32 / (self.deadline - self.start) as f32
+        };
+        let t = smoothstep(t);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        (
+            Rect::new(
+                lerp(self.from.x, self.to.x),
+                lerp(self.from.y, self.to.y),
+                lerp(self.from.w, self.to.w),
+                lerp(self.from.h, self.to.h),
+            ),
+            lerp(self.from_zoom, self.to_zoom),
+            t >= 1.0,
+        )
+    }
+}
+
+/// Ease in and out of `t` in \[0, 1\].
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ViewportPoint {
     pub x: f32,
@@ -105,6 +178,9 @@ pub struct Viewport {
     /// the viewport.
     cursor_position: Option<ViewportPoint>,
 
+    /// Viewport last shared with other players.
+    shared_viewport: Rect,
+
     /// Whether the left mousebutton is currently being held down.
     mouse_down: Option<bool>,
 
@@ -114,6 +190,53 @@ pub struct Viewport {
     // Current grab for dragging on the viewport
     grabbed_at: Option<ViewportPoint>,
 
+    /// Whether panning and zooming the view by hand is disabled.
+    viewport_locked: bool,
+
+    /// Where the right mousebutton was pressed on the scene background.
+    right_click_at: Option<ViewportPoint>,
+
+    /// Scene point at which the background dropdown was opened, where
+    /// waypoints and comments are added.
+    background_at: Option<Point>,
+
+    /// Scene point at which the sprite dropdown was opened, where drawings
+    /// are split.
+    sprite_dropdown_at: Option<Point>,
+
+    /// Corner of the rectangle being revealed with the fog brush.
+    fog_rect_from: Option<Point>,
+
+    /// Scene linked to by the page URL, switched to once the list of scenes
+    /// is received from the server.
+    link_scene: Option<uuid::Uuid>,
+
+    /// Corner of the view linked to by the page URL, moved to once the linked
+    /// scene (or any scene, if none was linked) has loaded.
+    link_at: Option<(Point, Option<uuid::Uuid>)>,
+
+    /// Whether a player screen window is open.
+    player_screen: bool,
+
+    // Camera movement in progress
+    view_animation: Option<ViewAnimation>,
+
+    // Duration of camera movements, in milliseconds
+    view_duration: u64,
+
+    // Minimum zoom, in pixels per tile, at which nameplates are drawn
+    nameplate_zoom: f32,
+
+    // Tiles between lines of the measurement grid shown with the ruler
+    measurement_interval: u32,
+
+    // Whether scrolling to zoom keeps the point under the cursor in place,
+    // rather than the centre of the view
+    zoom_to_cursor: bool,
+
+    // Whether to draw the minimap overlay
+    minimap: bool,
+
     // Flag set true whenever something changes
     redraw_needed: bool,
 
@@ -122,13 +245,45 @@ pub struct Viewport {
 
     // Save progress
     save_state: Option<ReqState>,
+
+    // Saved sprite configurations available to place
+    sprite_templates: Vec<scene::SpriteTmpl>,
+
+    // Sprite template loading progress
+    templates_state: Option<ReqState>,
+
+    // Sprite template saving progress
+    template_save_state: Option<ReqState>,
+
+    // Built-in and saved colour palettes offered in the draw menu
+    palettes: Vec<scene::ColourPalette>,
+
+    // Palette loading progress
+    palettes_state: Option<ReqState>,
+
+    // Palette saving progress
+    palette_save_state: Option<ReqState>,
+
+    // Time at which to next check whether the session needs refreshing
+    session_check: u64,
+
+    // Session refresh progress
+    session_state: Option<ReqState>,
 }
 
 impl Viewport {
     const BASE_GRID_ZOOM: f32 = 50.0;
     const SAVE_INTERVAL_MS: u64 = 1000 * 60; // 1 minute.
-
-    pub fn new(client: Option<Client>) -> Res<Self> {
+    pub const DEFAULT_VIEW_DURATION_MS: u64 = 500;
+    pub const MAX_VIEW_DURATION_MS: u64 = 3000;
+    pub const DEFAULT_NAMEPLATE_ZOOM: f32 = Viewport::BASE_GRID_ZOOM / 2.0;
+    pub const DEFAULT_MEASUREMENT_INTERVAL: u32 = 5;
+    const MEASUREMENT_LABEL_ZOOM: f32 = Viewport::BASE_GRID_ZOOM / 2.0;
+    const ZOOM_MIN: f32 = Viewport::BASE_GRID_ZOOM / 5.0;
+    const ZOOM_MAX: f32 = Viewport::BASE_GRID_ZOOM * 5.0;
+    const COLOUR_BLIND_MODE_KEY: &'static str = "colour_blind_mode";
+
+    pub fn new(client: Option<Client>, link: DeepLink) -> Res<Self> {
         let scene = Interactor::new(client, None);
         let mut vp = Viewport {
             int: scene,
@@ -143,24 +298,103 @@ impl Viewport {
             },
             grid_zoom: Viewport::BASE_GRID_ZOOM,
             cursor_position: None,
+            shared_viewport: Rect::new(0.0, 0.0, 0.0, 0.0),
             mouse_down: None,
             ctrl_down: false,
             grabbed_at: None,
+            viewport_locked: false,
+            right_click_at: None,
+            background_at: None,
+            sprite_dropdown_at: None,
+            fog_rect_from: None,
+            link_scene: link.scene,
+            link_at: link.at.map(|at| (at, link.scene)),
+            player_screen: false,
+            view_animation: None,
+            view_duration: Viewport::DEFAULT_VIEW_DURATION_MS,
+            nameplate_zoom: Viewport::DEFAULT_NAMEPLATE_ZOOM,
+            measurement_interval: Viewport::DEFAULT_MEASUREMENT_INTERVAL,
+            zoom_to_cursor: true,
+            minimap: false,
             redraw_needed: true,
             last_save: timestamp_ms(),
             save_state: None,
+            sprite_templates: Vec::new(),
+            templates_state: None,
+            template_save_state: None,
+            palettes: scene::ColourPalette::builtin(),
+            palettes_state: None,
+            palette_save_state: None,
+            session_check: 0,
+            session_state: None,
         };
 
+        vp.context
+            .renderer()
+            .set_colour_blind_mode(Viewport::saved_colour_blind_mode());
         vp.update_viewport();
         vp.centre_viewport();
+        vp.follow_link_view(link);
 
         Ok(vp)
     }
 
+    /// Zoom to the grid size linked to by the page URL, if any. A grid size
+    /// outside of the allowed zoom range is ignored.
+    fn follow_link_view(&mut self, link: DeepLink) {
+        let grid_size = link
+            .grid_size
+            .filter(|gs| (Viewport::ZOOM_MIN..=Viewport::ZOOM_MAX).contains(gs))
+            .unwrap_or(self.grid_zoom);
+
+        self.set_view(self.viewport, grid_size);
+    }
+
+    /// Move the camera to the corner linked to by the page URL once the
+    /// linked scene has loaded. A corner outside of the scene is ignored.
+    fn follow_link_at(&mut self) {
+        let Some((at, scene)) = self.link_at else {
+            return;
+        };
+
+        // The placeholder scene used before the server responds has no
+        // project, and the linked scene may still be on its way.
+        let current = self.int.scene();
+        if current.project.is_nil() || scene.is_some_and(|s| s != current.uuid) {
+            return;
+        }
+        self.link_at = None;
+
+        let (w, h) = self.int.dimensions();
+        if (0.0..w as f32).contains(&at.x) && (0.0..h as f32).contains(&at.y) {
+            let (vw, vh) = (self.viewport.w, self.viewport.h);
+            self.set_view(Rect::new(at.x, at.y, vw, vh), self.grid_zoom);
+        }
+    }
+
+    /// Switch to the scene linked to by the page URL, if it's in `scenes`.
+    /// Only the owner is sent the list of scenes, and the server only allows
+    /// them to change scene.
+    fn follow_link_scene(&mut self, scenes: &[(String, String)]) {
+        let Some(scene) = self.link_scene.take() else {
+            return;
+        };
+
+        let key = scene.simple().to_string();
+        if key != self.int.scene_uuid() && scenes.iter().any(|(_, uuid)| *uuid == key) {
+            self.int.change_scene(scene);
+            self.menu().set_scene(key);
+        }
+    }
+
     pub fn add_menu(&mut self, menu: Menu) {
         self.menu = Some(menu);
+        let online = self.int.online();
+        self.menu().set_initiative_visible(online);
+        self.menu().set_chat_visible(online);
         self.update_layers_menu();
         self.update_scene_menu();
+        self.update_palettes_menu();
     }
 
     fn menu(&mut self) -> &mut Menu {
@@ -170,7 +404,8 @@ impl Viewport {
     fn update_layers_menu(&mut self) {
         let selected = self.int.selected_layer();
         let layers = self.int.layer_info();
-        self.menu().set_layer_info(selected, &layers);
+        let editor = self.int.role.editor();
+        self.menu().set_layer_info(selected, &layers, editor);
     }
 
     fn update_scene_menu(&mut self) {
@@ -179,6 +414,8 @@ impl Viewport {
         let scenes = self.int.get_scene_list();
         self.set_scene_list(scenes);
         self.menu().set_fog_brush(Interactor::DEFAULT_FOG_BRUSH);
+        let waypoints = self.int.waypoints().to_vec();
+        self.menu().set_waypoints(&waypoints);
     }
 
     fn set_scene_list(&mut self, scenes: Vec<(String, String)>) {
@@ -189,7 +426,11 @@ impl Viewport {
 
     fn update_cursor(&mut self, new: Option<Cursor>) {
         let cursor = if self.grabbed_at.is_some() {
-            Cursor::Grabbing
+            if self.viewport_locked {
+                Cursor::NotAllowed
+            } else {
+                Cursor::Grabbing
+            }
         } else {
             let at = self.scene_point(
                 self.cursor_position
@@ -214,12 +455,14 @@ impl Viewport {
             return;
         }
 
-        // As fog cursor is drawn, we'll need to redraw to get rid of it.
-        if matches!(self.tool, Tool::Fog) {
+        // As fog cursor is drawn, we'll need to redraw to get rid of it. The
+        // measurement grid is likewise drawn while the ruler is selected.
+        if matches!(self.tool, Tool::Fog | Tool::Draw) || tool == Tool::Draw {
             self.redraw_needed();
         }
 
         if tool.allowed(self.int.role) {
+            self.int.stop_path();
             self.tool = tool;
             self.update_cursor(None);
             self.menu().update_tool(tool);
@@ -235,6 +478,15 @@ impl Viewport {
     pub fn set_draw_tool(&mut self, draw_tool: DrawTool) {
         self.set_tool(Tool::Draw);
         self.menu().set_draw_tool(draw_tool);
+
+        // The measurement grid is drawn while the ruler is selected.
+        self.redraw_needed();
+    }
+
+    /// Place a text annotation at `at`, using the current draw details.
+    pub fn annotate(&mut self, at: Point, text: String) {
+        let details = self.menu().get_draw_details();
+        self.int.annotate(at, text, details);
     }
 
     fn enable_fog(&mut self) {
@@ -265,6 +517,278 @@ impl Viewport {
         }
     }
 
+    /// Share the viewport with other players if it has moved since it was
+    /// last shared, as happens when panning without moving the cursor.
+    fn share_viewport(&mut self) {
+        if self.viewport != self.shared_viewport {
+            self.shared_viewport = self.viewport;
+            let at = self
+                .cursor_position
+                .map(|at| self.scene_point(at))
+                .unwrap_or_else(|| self.viewport.centre());
+            self.int.move_cursor(at, self.viewport);
+        }
+    }
+
+    /// Move the camera to show `rect`, rendering at `grid_size` pixels per
+    /// tile. The view is centred on `rect`.
+    pub fn set_view(&mut self, rect: Rect, grid_size: f32) {
+        let centre = rect.centre();
+        self.grid_zoom = grid_size;
+        self.update_viewport();
+        self.viewport.x = centre.x - self.viewport.w / 2.0;
+        self.viewport.y = centre.y - self.viewport.h / 2.0;
+        self.redraw_needed();
+    }
+
+    /// As `set_view`, but the camera moves smoothly to the target over
+    /// `duration_ms`.
+    pub fn animate_to(&mut self, target_rect: Rect, target_grid_size: f32, duration_ms: u64) {
+        let start = timestamp_ms();
+        self.view_animation = Some(ViewAnimation {
+            from: self.viewport,
+            from_zoom: self.grid_zoom,
+            to: target_rect,
+            to_zoom: target_grid_size,
+            start,
+            deadline: start + duration_ms,
+        });
+    }
+
+    /// Advance the camera and path animations, if any, to time `now`.
+    pub fn tick(&mut self, now: u64) {
+        self.int.tick(now);
+        self.refresh_session(now);
+
+        let Some(animation) = &self.view_animation else {
+            return;
+        };
+
+        let (rect, grid_size, done) = animation.step(now);
+        if done {
+            self.view_animation = None;
+        }
+        self.set_view(rect, grid_size);
+    }
+
+    /// Refresh the session shortly before it expires, so that the user isn't
+    /// logged out during a long game. Failed refreshes are retried on the next
+    /// check. If the start of the session is unknown, because it didn't begin
+    /// at the login form, the session is refreshed once on load to record it.
+    fn refresh_session(&mut self, now: u64) {
+        const SESSION_MAX_AGE_MS: u64 = 180 * 24 * 60 * 60 * 1000;
+        const REFRESH_BEFORE_MS: u64 = 5 * 60 * 1000;
+        const CHECK_INTERVAL_MS: u64 = 30 * 1000;
+
+        if now < self.session_check {
+            return;
+        }
+        let first_check = self.session_check == 0;
+        self.session_check = now + CHECK_INTERVAL_MS;
+
+        let Some(start) = local_storage_get(crate::bridge::SESSION_START_KEY)
+            .and_then(|start| start.parse::<u64>().ok())
+        else {
+            if first_check {
+                self.session_state = crate::bridge::refresh_session().ok();
+            }
+            return;
+        };
+
+        if now + REFRESH_BEFORE_MS >= start + SESSION_MAX_AGE_MS {
+            self.session_state = crate::bridge::refresh_session().ok();
+        }
+    }
+
+    /// Viewport rect and grid size which fit `rect` in the canvas, centred.
+    pub fn fit_rect(&self, rect: Rect) -> (Rect, f32) {
+        let (w, h) = self.context.viewport_size();
+        let (w, h) = (w as f32, h as f32);
+        let rect = rect.positive_dimensions();
+        let grid_size = (w / rect.w)
+            .min(h / rect.h)
+            .clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
+        let (w, h) = (w / grid_size, h / grid_size);
+        (
+            Rect::at(rect.centre() - Point::new(w / 2.0, h / 2.0), w, h),
+            grid_size,
+        )
+    }
+
+    /// Move the camera to show all selected sprites.
+    pub fn zoom_to_selection(&mut self) {
+        if let Some(bounds) = self.int.selection_bounds() {
+            let (rect, grid_size) = self.fit_rect(bounds);
+            self.animate_to(rect, grid_size, self.view_duration);
+        }
+    }
+
+    /// Move the view to centre on the player's own token, keeping the zoom.
+    pub fn centre_on_own_sprite(&mut self) {
+        if let Some(rect) = self.int.own_sprite_rect() {
+            let centre = rect.centre();
+            let target = Rect::new(
+                centre.x - self.viewport.w / 2.0,
+                centre.y - self.viewport.h / 2.0,
+                self.viewport.w,
+                self.viewport.h,
+            );
+            self.animate_to(target, self.grid_zoom, self.view_duration);
+        }
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.minimap = !self.minimap;
+        self.redraw_needed();
+    }
+
+    /// Area of the canvas covered by the minimap, in viewport pixels. Sized
+    /// to the aspect ratio of the scene in the bottom right corner.
+    fn minimap_rect(&self) -> Rect {
+        const MINIMAP_SIZE_PX: f32 = 200.0;
+        const MINIMAP_MARGIN_PX: f32 = 10.0;
+
+        let (w, h) = self.int.dimensions();
+        let scale = MINIMAP_SIZE_PX / w.max(h).max(1) as f32;
+        let (w, h) = (w as f32 * scale, h as f32 * scale);
+        Rect::new(
+            self.viewport.w * self.grid_zoom - w - MINIMAP_MARGIN_PX,
+            self.viewport.h * self.grid_zoom - h - MINIMAP_MARGIN_PX,
+            w,
+            h,
+        )
+    }
+
+    /// If `at` is on the minimap, centre the view on the corresponding scene
+    /// point, returning whether it was.
+    fn minimap_click(&mut self, at: ViewportPoint) -> bool {
+        let rect = self.minimap_rect();
+        if !self.minimap || !rect.contains_point(Point::new(at.x, at.y)) {
+            return false;
+        }
+
+        let (w, _) = self.int.dimensions();
+        let scale = rect.w / w.max(1) as f32;
+        let target = (Point::new(at.x, at.y) - rect.top_left()) / scale;
+        self.set_view(Rect::at(target, 0.0, 0.0), self.grid_zoom);
+        true
+    }
+
+    pub fn view_duration(&self) -> u64 {
+        self.view_duration
+    }
+
+    pub fn set_view_duration(&mut self, duration_ms: u64) {
+        self.view_duration = duration_ms.min(Self::MAX_VIEW_DURATION_MS);
+    }
+
+    /// Open or close a second window which shows the scene in the current
+    /// view as players see it, for display on a screen facing the players.
+    pub fn set_player_screen(&mut self, open: bool) {
+        if open {
+            if let Err(e) = self.context.open_player_screen() {
+                crate::bridge::console_err(&e);
+                self.menu().set_player_screen(false);
+                return;
+            }
+        } else {
+            self.context.close_player_screen();
+        }
+        self.player_screen = open;
+        self.redraw_needed();
+    }
+
+    /// Keep the player screen sized to its window, updating the menu if the
+    /// window has been closed.
+    fn update_player_screen(&mut self) {
+        match self.context.update_player_screen() {
+            Some(resized) => {
+                if resized {
+                    self.redraw_needed();
+                }
+            }
+            None => {
+                if self.player_screen {
+                    self.player_screen = false;
+                    self.menu().set_player_screen(false);
+                }
+            }
+        }
+    }
+
+    /// Colour blindness simulation mode saved in local storage.
+    pub fn saved_colour_blind_mode() -> ColourBlindMode {
+        local_storage_get(Self::COLOUR_BLIND_MODE_KEY)
+            .and_then(|name| ColourBlindMode::from(&name))
+            .unwrap_or_default()
+    }
+
+    pub fn set_colour_blind_mode(&mut self, mode: ColourBlindMode) {
+        self.context.renderer().set_colour_blind_mode(mode);
+        local_storage_set(Self::COLOUR_BLIND_MODE_KEY, mode.to_str()).ok();
+        self.redraw_needed();
+    }
+
+    pub fn set_zoom_to_cursor(&mut self, zoom_to_cursor: bool) {
+        self.zoom_to_cursor = zoom_to_cursor;
+    }
+
+    pub fn set_nameplate_zoom(&mut self, zoom: f32) {
+        self.nameplate_zoom = zoom.max(0.0);
+        self.redraw_needed = true;
+    }
+
+    pub fn set_measurement_interval(&mut self, interval: u32) {
+        self.measurement_interval = interval.max(1);
+        self.redraw_needed = true;
+    }
+
+    /// Prompt for a name and add a waypoint showing the current view, centred
+    /// on the point the background dropdown was opened at.
+    fn add_waypoint(&mut self) {
+        let Some(at) = self.background_at.take() else {
+            return;
+        };
+
+        let default = format!("Waypoint {}", self.int.waypoints().len() + 1);
+        if let Some(name) = prompt("Waypoint name", &default) {
+            let (w, h) = (self.viewport.w, self.viewport.h);
+            let viewport = Rect::at(at - Point::new(w / 2.0, h / 2.0), w, h);
+            self.int.add_waypoint(&name, viewport, self.grid_zoom);
+        }
+    }
+
+    /// Prompt for text and add a comment at the point the background dropdown
+    /// was opened at.
+    fn add_comment(&mut self) {
+        let Some(at) = self.background_at.take() else {
+            return;
+        };
+
+        if let Some(text) = prompt("Comment", "") {
+            self.int.add_comment(&text, Some(at), timestamp_ms() / 1000);
+        }
+    }
+
+    /// Index of the comment whose marker is at viewport position `at`, if
+    /// any. Comments are only shown to editors.
+    fn comment_at(&self, at: ViewportPoint) -> Option<usize> {
+        const MARKER_RADIUS_PX: f32 = 12.0;
+
+        if !self.int.role.editor() {
+            return None;
+        }
+
+        let vp = self.view_info();
+        self.int.comments().iter().rposition(|comment| {
+            comment.at.is_some_and(|point| {
+                let marker = vp.viewport_point(point);
+                (marker.x - at.x).abs() < MARKER_RADIUS_PX
+                    && (marker.y - at.y).abs() < MARKER_RADIUS_PX
+            })
+        })
+    }
+
     fn centre_viewport(&mut self) {
         let (w, h) = self.int.dimensions();
         self.viewport.x = (w as f32 / 2.0 - self.viewport.w / 2.0).round();
@@ -273,15 +797,36 @@ impl Viewport {
     }
 
     fn grab(&mut self, at: ViewportPoint) {
+        self.view_animation = None;
         if self.grabbed_at.is_none() {
             self.grabbed_at = Some(at);
         }
-        self.update_cursor(Some(Cursor::Grabbing));
+        self.update_cursor(None);
     }
 
-    fn handle_mouse_down(&mut self, at: ViewportPoint, button: MouseButton, ctrl: bool, alt: bool) {
+    /// Prevent the view from being panned or zoomed by dragging, scrolling
+    /// or keyboard shortcuts. Moving to a waypoint or selection still moves
+    /// the view.
+    pub fn lock_viewport(&mut self, locked: bool) {
+        self.viewport_locked = locked;
+        self.update_cursor(None);
+    }
+
+    fn handle_mouse_down(
+        &mut self,
+        at: ViewportPoint,
+        button: MouseButton,
+        ctrl: bool,
+        alt: bool,
+        shift: bool,
+    ) {
         match button {
             MouseButton::Left => {
+                if self.minimap_click(at) {
+                    self.menu().hide_dropdown();
+                    return;
+                }
+
                 match self.tool {
                     Tool::Draw => {
                         let menu = self.menu();
@@ -295,8 +840,19 @@ impl Viewport {
                             draw_tool,
                         );
                     }
+                    Tool::Fog if shift && self.int.fog().active => {
+                        self.fog_rect_from = Some(self.scene_point(at));
+                    }
                     Tool::Pan => self.grab(at),
-                    Tool::Select => self.int.grab(self.scene_point(at), ctrl),
+                    Tool::Select => {
+                        if let Some(index) = self.comment_at(at) {
+                            let comment = self.int.comments()[index].clone();
+                            self.menu().open_comment(at, index, &comment);
+                        } else {
+                            self.menu().close_comment();
+                            self.int.grab(self.scene_point(at), ctrl);
+                        }
+                    }
                     _ => (),
                 };
 
@@ -305,8 +861,12 @@ impl Viewport {
             }
             MouseButton::Right => {
                 if self.int.select_at(self.scene_point(at), ctrl) {
+                    self.sprite_dropdown_at = Some(self.scene_point(at));
                     self.menu().show_dropdown(at);
                 } else {
+                    if self.int.role.editor() {
+                        self.right_click_at = Some(at);
+                    }
                     self.grab(at)
                 }
             }
@@ -327,10 +887,39 @@ impl Viewport {
                 }
                 self.int.release(alt, ctrl);
 
+                if let Some(from) = self.fog_rect_from.take()
+                    && let Some(at) = self.cursor_position
+                {
+                    let to = self.scene_point(at);
+                    self.int.set_fog_rect(from, to, !ctrl);
+                }
+
+                // Text is entered after releasing, so that focus isn't taken
+                // back by the canvas.
+                if matches!(self.tool, Tool::Draw)
+                    && matches!(self.menu().get_draw_tool(), DrawTool::Text)
+                    && let Some(at) = self.cursor_position
+                {
+                    let scene_at = self.scene_point(at);
+                    self.menu().open_annotation(at, scene_at);
+                }
+
                 self.mouse_down = Some(false);
             }
-            MouseButton::Right => self.release_grab(),
-            MouseButton::Middle => self.centre_viewport(),
+            MouseButton::Right => {
+                // Show the background dropdown for a click which didn't pan.
+                const CLICK_DISTANCE_PX: f32 = 4.0;
+                if let Some(click) = self.right_click_at.take()
+                    && let Some(at) = self.cursor_position
+                    && (click.x - at.x).abs() < CLICK_DISTANCE_PX
+                    && (click.y - at.y).abs() < CLICK_DISTANCE_PX
+                {
+                    self.background_at = Some(self.scene_point(click));
+                    self.menu().show_background_dropdown(click);
+                }
+                self.release_grab();
+            }
+            MouseButton::Middle if !self.viewport_locked => self.centre_viewport(),
             _ => {}
         };
     }
@@ -338,7 +927,11 @@ impl Viewport {
     fn handle_mouse_move(&mut self, at: ViewportPoint, ctrl: bool, shift: bool) {
         let scene_point = self.scene_point(at);
         self.int.drag(scene_point, shift);
-        if let Some(from) = self.grabbed_at {
+        self.int.move_cursor(scene_point, self.viewport);
+        self.shared_viewport = self.viewport;
+        if let Some(from) = self.grabbed_at
+            && !self.viewport_locked
+        {
             self.viewport.x += (from.x - at.x) / self.grid_zoom;
             self.viewport.y += (from.y - at.y) / self.grid_zoom;
             self.grabbed_at = Some(at);
@@ -350,6 +943,7 @@ impl Viewport {
         if matches!(self.mouse_down, Some(true))
             && matches!(self.tool, Tool::Fog)
             && self.int.fog().active
+            && self.fog_rect_from.is_none()
         {
             self.int.set_fog(scene_point, ctrl);
         }
@@ -357,17 +951,21 @@ impl Viewport {
 
     fn zoom(&mut self, delta: f32, at: Option<ViewportPoint>) {
         const ZOOM_COEFFICIENT: f32 = 3.0 / Viewport::BASE_GRID_ZOOM;
-        const ZOOM_MIN: f32 = Viewport::BASE_GRID_ZOOM / 5.0;
-        const ZOOM_MAX: f32 = Viewport::BASE_GRID_ZOOM * 5.0;
+
+        if self.viewport_locked {
+            return;
+        }
 
         let at = at.unwrap_or_else(|| self.centre());
+        self.view_animation = None;
 
         // Need to calculate these before changing the zoom level
         let scene_point = at.scene_point(self.viewport, self.grid_zoom);
         let fraction_x = at.x / (self.viewport.w * self.grid_zoom);
         let fraction_y = at.y / (self.viewport.h * self.grid_zoom);
 
-        self.grid_zoom = (self.grid_zoom - delta * ZOOM_COEFFICIENT).clamp(ZOOM_MIN, ZOOM_MAX);
+        self.grid_zoom =
+            (self.grid_zoom - delta * ZOOM_COEFFICIENT).clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
         self.update_viewport();
 
         // Update viewport such that the mouse is at the same scene
@@ -392,14 +990,19 @@ impl Viewport {
         const SCROLL_COEFFICIENT: f32 = 0.5;
         const STROKE_COEFFICIENT: f32 = 0.5;
 
+        self.view_animation = None;
+
         // We want shift + scroll to scroll horizontally but browsers (Firefox
         // anyway) only do this when the page is wider than the viewport, which
         // it never is in this case. Thus this check for shift. Likewise for
         // ctrl + scroll and zooming.
-        if shift {
+        if self.viewport_locked && !alt {
+            self.context.set_cursor(Cursor::NotAllowed);
+            return;
+        } else if shift {
             self.viewport.x += SCROLL_COEFFICIENT * delta / self.grid_zoom;
         } else if ctrl {
-            self.zoom(delta, Some(at));
+            self.zoom(delta, self.zoom_to_cursor.then_some(at));
         } else if alt {
             match self.tool {
                 Tool::Draw => self.menu().handle_stroke_change(delta * STROKE_COEFFICIENT),
@@ -421,7 +1024,11 @@ impl Viewport {
             .drag(at.scene_point(self.viewport, self.grid_zoom), shift);
     }
 
-    fn handle_arrow_key_down(&mut self, key: Key, ctrl: bool) {
+    /// Pan the viewport, or move the selection by a tile, or a tenth of a
+    /// tile when `shift` is held.
+    fn handle_arrow_key_down(&mut self, key: Key, ctrl: bool, shift: bool) {
+        const FINE_STEP: f32 = 0.1;
+
         let delta = match key {
             Key::Down => Point { x: 0.0, y: 1.0 },
             Key::Left => Point { x: -1.0, y: 0.0 },
@@ -431,8 +1038,12 @@ impl Viewport {
         };
 
         if ctrl || !self.int.has_selection() {
-            self.viewport.translate_in_place(delta);
-            self.redraw_needed();
+            if !self.viewport_locked {
+                self.viewport.translate_in_place(delta);
+                self.redraw_needed();
+            }
+        } else if shift {
+            self.int.move_selection(delta * FINE_STEP);
         } else {
             self.int.move_selection(delta);
         }
@@ -447,17 +1058,19 @@ impl Viewport {
         }
     }
 
-    fn handle_key_down(&mut self, key: Key, ctrl: bool) {
+    fn handle_key_down(&mut self, key: Key, ctrl: bool, shift: bool) {
         match key {
             Key::Control => self.set_ctrl_down(true),
             Key::Delete => self.int.remove_selection(),
             Key::Escape => {
+                self.int.discard_template();
                 self.int.clear_selection();
                 self.set_tool(Tool::Select);
             }
             Key::Plus | Key::Equals => self.zoom_in(),
             Key::Minus | Key::Underscore => self.zoom_out(),
             Key::Space => self.set_tool(Tool::Pan),
+            Key::A if ctrl && shift => self.int.select_similar_to_selection(),
             Key::A => {
                 self.int.select_all();
                 self.set_tool(Tool::Select);
@@ -466,8 +1079,11 @@ impl Viewport {
             Key::D => self.int.clear_selection(),
             Key::E => self.set_draw_tool(DrawTool::Circle),
             Key::F => self.set_draw_tool(DrawTool::Freehand),
+            Key::G => self.zoom_to_selection(),
+            Key::I if ctrl => self.int.invert_selection(),
             Key::L => self.set_draw_tool(DrawTool::Line),
             Key::O => self.set_draw_tool(DrawTool::Cone),
+            Key::P => self.export_png(),
             Key::Q => self.set_tool(Tool::Select),
             Key::R => self.set_draw_tool(DrawTool::Rectangle),
             Key::S => self.save(),
@@ -475,7 +1091,7 @@ impl Viewport {
             Key::W => self.set_tool(Tool::Fog),
             Key::Y => self.int.redo(),
             Key::Z => self.int.undo(),
-            k if k.is_arrow() => self.handle_arrow_key_down(key, ctrl),
+            k if k.is_arrow() => self.handle_arrow_key_down(key, ctrl, shift),
             _ => {}
         }
     }
@@ -507,8 +1123,16 @@ impl Viewport {
         let menu = self.menu();
         if let Some(event) = menu.dropdown_event() {
             let draw_details = menu.get_draw_details();
-            if matches!(event, CanvasDropdownEvent::Aura) {
-                self.set_tool(Tool::Select);
+            match event {
+                CanvasDropdownEvent::Aura => self.set_tool(Tool::Select),
+                CanvasDropdownEvent::Comment => self.add_comment(),
+                CanvasDropdownEvent::SplitDrawing => {
+                    if let Some(at) = self.sprite_dropdown_at.take() {
+                        self.int.split_selected_drawing(at);
+                    }
+                }
+                CanvasDropdownEvent::Waypoint => self.add_waypoint(),
+                _ => {}
             }
             self.int.handle_dropdown_event(event, draw_details);
         }
@@ -520,10 +1144,11 @@ impl Viewport {
 
         for event in &events {
             self.set_ctrl_down(event.ctrl);
+            self.int.set_pressure(event.pressure);
             match event.input {
                 Input::Mouse(at, MouseAction::Down, button) => {
                     self.handle_cursor(at);
-                    self.handle_mouse_down(at, button, event.ctrl, event.alt)
+                    self.handle_mouse_down(at, button, event.ctrl, event.alt, event.shift)
                 }
                 Input::Mouse(at, MouseAction::Enter, _) => {
                     self.handle_cursor(at);
@@ -544,7 +1169,9 @@ impl Viewport {
                     self.handle_cursor(at);
                     self.handle_scroll(at, delta, event.shift, event.ctrl, event.alt)
                 }
-                Input::Keyboard(KeyboardAction::Down, key) => self.handle_key_down(key, event.ctrl),
+                Input::Keyboard(KeyboardAction::Down, key) => {
+                    self.handle_key_down(key, event.ctrl, event.shift)
+                }
                 Input::Keyboard(KeyboardAction::Up, key) => self.handle_key_up(key),
             };
         }
@@ -554,42 +1181,86 @@ impl Viewport {
         self.redraw_needed = true;
     }
 
-    fn redraw(&mut self) {
-        let vp = crate::render::ViewInfo::new(
+    fn view_info(&self) -> crate::render::ViewInfo {
+        crate::render::ViewInfo::new(
             Rect::scaled_from(self.viewport, self.grid_zoom),
             self.grid_zoom,
-        );
+            self.context.pixel_ratio(),
+        )
+    }
+
+    fn redraw(&mut self) {
+        let vp = self.view_info();
 
         let fog_brush_outline = self
             .cursor_position
             .map(|at| self.scene_point(at))
-            .map(|at| {
-                let r = self.int.get_fog_brush();
-                Rect::at(at - Point::same(r), r * 2.0, r * 2.0)
+            .map(|at| match self.fog_rect_from {
+                Some(from) => (
+                    Rect::at(from, at.x - from.x, at.y - from.y),
+                    scene::Shape::Rectangle,
+                ),
+                None => {
+                    let r = self.int.get_fog_brush();
+                    (
+                        Rect::at(at - Point::same(r), r * 2.0, r * 2.0),
+                        scene::Shape::Ellipse,
+                    )
+                }
             });
+        let minimap_rect = self.minimap_rect();
+        let ruler_active = matches!(self.tool, Tool::Draw)
+            && matches!(self.menu().get_draw_tool(), DrawTool::Ruler);
         let renderer = self.context.renderer();
 
-        renderer.clear(vp);
-        renderer.draw_scene(vp, self.int.scene());
+        renderer.begin_frame(vp);
+        renderer.clear(vp, self.int.scene().background);
+        renderer.draw_scene(vp, self.int.scene(), self.int.layer_opacity());
+        if ruler_active {
+            renderer.draw_measurement_grid(
+                vp,
+                self.int.scene(),
+                self.measurement_interval,
+                self.grid_zoom >= Self::MEASUREMENT_LABEL_ZOOM,
+            );
+        }
 
         if self.int.fog().active {
             renderer.draw_fog(vp, self.int.fog(), self.int.role.editor());
         }
+        renderer.draw_annotations(vp, self.int.scene(), self.int.role.editor());
+        if self.grid_zoom >= self.nameplate_zoom {
+            renderer.draw_nameplates(vp, self.int.scene(), self.int.role.editor());
+        }
+        if self.int.role.editor() {
+            renderer.draw_comments(vp, self.int.comments());
+        }
 
+        renderer.draw_overlaps(vp, &self.int.overlaps());
+        renderer.draw_conflicts(
+            vp,
+            &self.int.conflicts(),
+            self.int.conflict_age(timestamp_ms()),
+        );
         renderer.draw_outlines(vp, &self.int.selections());
+        renderer.draw_guides(vp, self.int.guides());
 
+        let scene = self.int.scene();
         for (at, measurement) in self.int.active_measurements() {
-            let feet = (measurement * 5.).round();
-            renderer.draw_text(vp, at, &format!("{feet}ft"));
+            renderer.draw_text(vp, at, &scene.cell_size_display(measurement));
+        }
+
+        if let Some((from, to)) = self.int.ruler() {
+            renderer.draw_ruler(vp, from, to, &scene.cell_size_display(from.dist(to)));
         }
 
         if matches!(self.tool, Tool::Fog)
-            && let Some(position) = fog_brush_outline
+            && let Some((position, shape)) = fog_brush_outline
         {
             renderer.draw_outline(
                 vp,
                 position,
-                scene::Shape::Ellipse,
+                shape,
                 (if self.ctrl_down {
                     scene::Colour::RED
                 } else {
@@ -598,16 +1269,59 @@ impl Viewport {
                 .with_opacity(0.6),
             )
         }
+
+        if self.int.role.editor() {
+            renderer.draw_viewports(vp, &self.int.viewports());
+        }
+        renderer.draw_cursors(vp, &self.int.cursors());
+
+        if self.minimap {
+            renderer.draw_minimap(vp, self.int.scene(), minimap_rect, self.int.role.editor());
+        }
+        renderer.end_frame();
+
+        self.redraw_player_screen();
+    }
+
+    /// Draw the scene in the current view to the player screen, fitted to
+    /// its canvas. Fog is drawn opaque and editing aids are omitted.
+    fn redraw_player_screen(&mut self) {
+        let Some((renderer, (w, h), ratio)) = self.context.player_renderer() else {
+            return;
+        };
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        let zoom = (w as f32 / self.viewport.w).min(h as f32 / self.viewport.h);
+        let (view_w, view_h) = (w as f32 / zoom, h as f32 / zoom);
+        let view = Rect::at(
+            self.viewport.centre() - Point::new(view_w / 2.0, view_h / 2.0),
+            view_w,
+            view_h,
+        );
+        let vp = crate::render::ViewInfo::new(Rect::scaled_from(view, zoom), zoom, ratio);
+
+        let scene = self.int.scene();
+        renderer.clear(vp, scene.background);
+        renderer.draw_scene(vp, scene, None);
+        if scene.fog.active {
+            renderer.draw_fog(vp, &scene.fog, false);
+        }
     }
 
     pub fn animation_frame(&mut self) {
         // Handle incoming input events, server events and viewport changes.
         self.process_ui_events();
         if let Some((list, scene)) = self.int.process_server_events() {
-            self.set_scene_list(list);
+            self.set_scene_list(list.clone());
             self.menu().set_scene(scene);
+            self.follow_link_scene(&list);
         }
         self.update_viewport();
+        self.share_viewport();
+        self.add_pasted_images();
+        self.update_player_screen();
 
         // Redraw the scene if required.
         if self.redraw_needed
@@ -625,10 +1339,28 @@ impl Viewport {
 
         if self.int.changes.handle_scene_change() {
             self.update_scene_menu();
+            self.follow_link_at();
+        }
+
+        if self.int.changes.handle_initiative_change() {
+            let order = self.int.initiative().to_vec();
+            self.menu().set_initiative(&order);
+        }
+
+        if self.int.changes.handle_handout_change() {
+            let handout = self.int.handout().cloned();
+            self.menu().set_handout(handout.as_ref());
+        }
+
+        let log = self.int.take_log();
+        if !log.is_empty() {
+            self.menu().append_chat(&log);
         }
 
         // Handle selection changes by updating sprite menu.
         if self.int.changes.handle_selected_change() {
+            let players = self.int.player_names();
+            self.menu().set_players(&players);
             let details = self.int.selected_details();
             self.menu().set_sprite_info(details);
             let has_selection = self.int.has_selection();
@@ -639,6 +1371,15 @@ impl Viewport {
         if self.int.changes.handle_role_change() {
             let new_role = self.int.role;
             self.menu().update_role(new_role);
+            self.update_layers_menu();
+            self.int.changes.own_sprite_change();
+        }
+
+        // The tools menu may have been recreated, so this follows role
+        // changes.
+        if self.int.changes.handle_own_sprite_change() {
+            let found = self.int.own_sprite_rect().is_some();
+            self.menu().set_own_sprite(found);
         }
 
         // Save the scene every save interval, as required.
@@ -686,6 +1427,52 @@ impl Viewport {
         }
     }
 
+    /// Add a sprite at the centre of the view for each image pasted onto the
+    /// canvas, sized at one tile per `BASE_GRID_ZOOM` pixels.
+    fn add_pasted_images(&mut self) {
+        let pasted = self.context.pasted_textures();
+        if pasted.is_empty() {
+            return;
+        }
+
+        self.context.load_texture_queue();
+        for (texture, w, h) in pasted {
+            let size = |px: u32| (px as f32 / Self::BASE_GRID_ZOOM).round().max(1.0);
+            let at = self.placement_tile();
+            self.int.new_sprite_at(
+                Some(scene::SpriteVisual::Texture {
+                    id: texture,
+                    shape: scene::Shape::Rectangle,
+                }),
+                None,
+                Rect::at(at, size(w), size(h)),
+            );
+        }
+        self.redraw_needed();
+    }
+
+    /// Download the current view of the scene as a PNG image.
+    fn export_png(&mut self) {
+        let vp = self.view_info();
+        let transparent = self.int.role.editor();
+        let result = self
+            .context
+            .renderer()
+            .export_png(vp, self.int.scene(), transparent)
+            .and_then(|bytes| {
+                let title = &self.int.scene().title;
+                let name = if title.is_empty() { "scene" } else { title };
+                crate::bridge::download_png(&bytes, &format!("{name}.png"))
+            });
+
+        if let Err(e) = result {
+            crate::bridge::console_err(&format!("Failed to export PNG: {e}"));
+        }
+
+        // Exporting clears hover text from the canvas, so redraw.
+        self.redraw_needed();
+    }
+
     pub fn save(&mut self) {
         if self.int.save_required() {
             let active_scene = self.int.scene_uuid();
@@ -699,6 +1486,120 @@ impl Viewport {
         self.save_state = Some(state);
     }
 
+    pub fn set_templates_state(&mut self, state: ReqState) {
+        self.templates_state = Some(state);
+    }
+
+    pub fn set_sprite_templates(&mut self, templates: Vec<scene::SpriteTmpl>) {
+        self.sprite_templates = templates;
+        let templates = self.sprite_templates.clone();
+        self.menu().set_sprite_templates(&templates);
+    }
+
+    /// Create a sprite from the template chosen in the menu, at the placement
+    /// tile.
+    pub fn place_sprite_template(&mut self) {
+        let at = self.placement_tile();
+        let index = self.menu().sprite_template();
+        if let Some(template) = index.and_then(|i| self.sprite_templates.get(i)) {
+            self.int.new_sprite_from_template(template, at);
+        }
+    }
+
+    /// Save the selected sprite as a template, after prompting for a name.
+    /// If `shared`, the template is shared with this project rather than
+    /// offered in all of the user's projects.
+    pub fn save_sprite_template(&mut self, shared: bool) {
+        let Some(name) = prompt("Template name", "") else {
+            return;
+        };
+
+        let Some(template) = self.int.selected_template(&name) else {
+            crate::bridge::console_err("Select a single non-drawing sprite to save.");
+            return;
+        };
+
+        let project = shared.then(|| self.int.project_uuid());
+        if let Ok(state) = crate::bridge::save_sprite_template(&template, project) {
+            self.template_save_state = Some(state);
+            let mut templates = self.sprite_templates.clone();
+            templates.push(template);
+            self.set_sprite_templates(templates);
+        }
+    }
+
+    pub fn set_palettes_state(&mut self, state: ReqState) {
+        self.palettes_state = Some(state);
+    }
+
+    /// Offer `saved` palettes alongside the built-in palettes. Saved palettes
+    /// replace built-in palettes with the same name.
+    pub fn set_palettes(&mut self, saved: Vec<scene::ColourPalette>) {
+        let mut palettes = scene::ColourPalette::builtin();
+        for palette in saved {
+            palettes.retain(|p| p.name != palette.name);
+            palettes.push(palette);
+        }
+        self.palettes = palettes;
+        self.update_palettes_menu();
+    }
+
+    fn update_palettes_menu(&mut self) {
+        let palettes = self.palettes.clone();
+        let active = self.menu().palette();
+        self.menu().set_palettes(&palettes);
+        if let Some(palette) = active
+            .and_then(|name| palettes.iter().find(|p| p.name == name))
+            .or(palettes.first())
+        {
+            self.menu().set_palette(palette);
+        }
+    }
+
+    fn active_palette(&mut self) -> Option<scene::ColourPalette> {
+        let name = self.menu().palette()?;
+        self.palettes.iter().find(|p| p.name == name).cloned()
+    }
+
+    /// Show the swatches of the palette named `name` in the draw menu.
+    pub fn set_palette(&mut self, name: &str) {
+        if let Some(palette) = self.palettes.iter().find(|p| p.name == name).cloned() {
+            self.menu().set_palette(&palette);
+        }
+    }
+
+    pub fn set_draw_colour(&mut self, colour: scene::Colour) {
+        self.menu().set_draw_colour(colour);
+    }
+
+    /// Save the active palette, with the current draw colour added, after
+    /// prompting for a name.
+    pub fn save_palette(&mut self) {
+        let mut palette = self
+            .active_palette()
+            .unwrap_or_else(|| scene::ColourPalette::new(""));
+
+        let Some(name) = prompt("Palette name", &palette.name) else {
+            return;
+        };
+        palette.name = name.trim().to_string();
+
+        if let Some(colour) = self.menu().get_draw_details().colour
+            && !palette.add(colour)
+        {
+            crate::bridge::console_err("Palette is full, current colour not added.");
+        }
+
+        if let Ok(state) = crate::bridge::save_palette(&palette) {
+            self.palette_save_state = Some(state);
+            let name = palette.name.clone();
+            self.palettes.retain(|p| p.name != name);
+            self.palettes.push(palette);
+            self.update_palettes_menu();
+            self.set_palette(&name);
+        }
+    }
+
     pub fn set_project(&mut self, project: scene::Project) {
         self.int.change_project(project);
     }
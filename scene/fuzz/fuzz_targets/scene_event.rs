@@ -0,0 +1,17 @@
+//! Decodes arbitrary bytes as a `SceneEvent`, as the server does for each
+//! message received from a client, and applies any event decoded to a
+//! scene. Neither step should panic, whatever the input.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scene::{comms::SceneEvent, Scene};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(event) = bincode::deserialize::<SceneEvent>(data) {
+        let mut scene = Scene::default();
+        scene.new_sprite(None, 1);
+        if scene.apply_event(event.clone()) {
+            scene.unwind_event(event);
+        }
+    }
+});
@@ -0,0 +1,33 @@
+use crate::Point;
+
+/// A note left on the scene by one GM for the others. Comments are only
+/// visible to editors.
+#[derive(Clone, Debug, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+pub struct Comment {
+    pub author: String,
+    pub text: String,
+
+    /// Position of the comment in the scene, if it refers to a location.
+    pub at: Option<Point>,
+
+    /// Time the comment was written, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Comment {
+    pub const MAX_TEXT_LENGTH: usize = 1024;
+
+    pub fn new(author: &str, text: &str, at: Option<Point>, timestamp: u64) -> Option<Self> {
+        let text: String = text.trim().chars().take(Self::MAX_TEXT_LENGTH).collect();
+        if text.is_empty() {
+            None
+        } else {
+            Some(Self {
+                author: author.trim().to_string(),
+                text,
+                at,
+                timestamp,
+            })
+        }
+    }
+}
@@ -2,40 +2,68 @@ use serde_derive::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::{
-    perms::{Override, Perms, Role},
-    Id, Point, Rect, Scene, Sprite, SpriteVisual,
+    perms::{LayerPerms, Override, Perms, Role},
+    Colour, Comment, Id, Point, Rect, Scene, Sprite, SpriteVisual, StrokePattern, UnitSystem,
+    Waypoint,
 };
-use crate::DrawingMode;
+use crate::{Condition, DrawingMode, ErasedPoint};
 
 // Events processed by Scene
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum SceneEvent {
-    Dummy,                                        // To trigger redraws, etc
-    EventSet(Vec<SceneEvent>),                    // Collection of other events
-    FogActive(bool, bool),                        // (old, new)
-    FogOcclude(bool, u32, u32),                   // (occluded, x, y)
-    FogReveal(bool, u32, u32),                    // (occluded, x, y)
-    GroupNew(Id),                                 // (group_id)
-    GroupAdd(Id, Id),                             // (group_id, sprite_id)
-    GroupRemove(Id, Id),                          // (group_id, sprite_id)
-    GroupDelete(Id),                              // (group_id)
-    LayerLocked(Id, bool),                        // (layer, status)
-    LayerMove(Id, i32, bool),                     // (layer, starting_z, up)
-    LayerNew(Id, String, i32),                    // (id, title, z, player)
-    LayerRemove(Id),                              // (layer)
-    LayerRename(Id, String, String),              // (layer, old_title, new_title)
-    LayerRestore(Id),                             // (layer)
-    LayerVisibility(Id, bool),                    // (layer, status)
-    SceneDimensions(u32, u32, u32, u32),          // (old_w, old_h, new_w, new_h)
-    SceneTitle(String, String),                   // (old_title, new_title)
-    SpriteDrawingStart(Id, DrawingMode),          // (drawing, mode)
-    SpriteDrawingPoint(Id, Point),                // (drawing, npoints, point)
-    SpriteLayer(Id, Id, Id),                      // (sprite, old_layer, new_layer)
-    SpriteMove(Id, Rect, Rect),                   // (sprite, from, to)
-    SpriteNew(Sprite, Id),                        // (new_sprite, layer)
-    SpriteRemove(Id, Id),                         // (sprite, layer)
-    SpriteRestore(Id),                            // (sprite, layer)
-    SpriteVisual(Id, SpriteVisual, SpriteVisual), // (sprite, old, new)
+    CommentAdd(Comment),                                   // (comment)
+    CommentDelete(Comment),                                // (comment)
+    Dummy,                                                 // To trigger redraws, etc
+    EventSet(Vec<SceneEvent>),                             // Collection of other events
+    FogActive(bool, bool),                                 // (old, new)
+    FogOcclude(bool, u32, u32),                            // (occluded, x, y)
+    FogReveal(bool, u32, u32),                             // (occluded, x, y)
+    GroupNew(Id),                                          // (group_id)
+    GroupAdd(Id, Id),                                      // (group_id, sprite_id)
+    GroupRemove(Id, Id),                                   // (group_id, sprite_id)
+    GroupDelete(Id),                                       // (group_id)
+    GroupNest(Id, Id),                                     // (parent_id, child_id)
+    LayerDescription(Id, Option<String>, Option<String>),  // (layer, old, new)
+    LayerLocked(Id, bool),                                 // (layer, status)
+    LayerMove(Id, i32, bool),                              // (layer, starting_z, up)
+    LayerNew(Id, String, i32),                             // (id, title, z, player)
+    LayerPerms(Id, LayerPerms, LayerPerms),                // (layer, old, new)
+    LayerRemove(Id),                                       // (layer)
+    LayerRename(Id, String, String),                       // (layer, old_title, new_title)
+    LayerRestore(Id),                                      // (layer)
+    LayerVisibility(Id, bool),                             // (layer, status)
+    SceneBackground(Colour, Colour),                       // (old, new)
+    SceneBackgroundTexture(Option<Id>, Option<Id>),        // (old, new)
+    SceneDimensions(u32, u32, u32, u32),                   // (old_w, old_h, new_w, new_h)
+    SceneGridColour(Colour, Colour),                       // (old, new)
+    SceneLock(bool),                                       // (locked)
+    SceneTitle(String, String),                            // (old_title, new_title)
+    SceneUnitSystem(UnitSystem, UnitSystem),               // (old, new)
+    SpriteCondition(Id, Condition, bool),                  // (sprite, condition, added)
+    SpriteDrawingStart(Id, DrawingMode),                   // (drawing, mode)
+    SpriteDrawingPoint(Id, Point),                         // (drawing, npoints, point)
+    SpriteDrawingAnnotate(Id, String, Point),              // (drawing, text, at)
+    SpriteDrawingPressure(Id, Point, f32),                 // (drawing, point, pressure)
+    SpriteDrawingErase(Id, Vec<ErasedPoint>),              // (drawing, erased)
+    SpriteDrawingRestore(Id, Vec<ErasedPoint>),            // (drawing, restored)
+    SpriteDrawingReverse(Id),                              // (drawing)
+    SpriteDrawingJoin(Id, Id),                             // (drawing, joined)
+    SpriteDrawingUnjoin(Id, Id),                           // (drawing, unjoined)
+    SpriteFlipCaps(Id),                                    // (sprite)
+    SpriteGmNotes(Id, Option<String>, Option<String>),     // (sprite, old, new)
+    SpriteHp(Id, Option<(i32, i32)>, Option<(i32, i32)>),  // (sprite, old, new)
+    SpriteLabel(Id, Option<String>, Option<String>),       // (sprite, old, new)
+    SpriteLayer(Id, Id, Id),                               // (sprite, old_layer, new_layer)
+    SpriteMove(Id, Rect, Rect),                            // (sprite, from, to)
+    SpriteNew(Sprite, Id),                                 // (new_sprite, layer)
+    SpriteOrder(Id, usize, usize),                         // (sprite, old_index, new_index)
+    SpriteOwner(Id, Option<Uuid>, Option<Uuid>),           // (sprite, old, new)
+    SpriteRemove(Id, Id),                                  // (sprite, layer)
+    SpriteRestore(Id),                                     // (sprite, layer)
+    SpriteStrokePattern(Id, StrokePattern, StrokePattern), // (sprite, old, new)
+    SpriteVisual(Id, SpriteVisual, SpriteVisual),          // (sprite, old, new)
+    WaypointAdd(Waypoint),                                 // (waypoint)
+    WaypointRemove(Waypoint),                              // (waypoint)
 }
 
 impl SceneEvent {
@@ -65,9 +93,11 @@ impl SceneEvent {
     pub fn is_layer(&self) -> bool {
         if matches!(
             self,
-            Self::LayerLocked(..)
+            Self::LayerDescription(..)
+                | Self::LayerLocked(..)
                 | Self::LayerMove(..)
                 | Self::LayerNew(..)
+                | Self::LayerPerms(..)
                 | Self::LayerRemove(..)
                 | Self::LayerRename(..)
                 | Self::LayerRestore(..)
@@ -86,12 +116,27 @@ impl SceneEvent {
             self,
             Self::GroupAdd(..)
                 | Self::GroupRemove(..)
+                | Self::SpriteCondition(..)
+                | Self::SpriteDrawingAnnotate(..)
+                | Self::SpriteDrawingErase(..)
+                | Self::SpriteDrawingRestore(..)
                 | Self::SpriteDrawingPoint(..)
+                | Self::SpriteDrawingPressure(..)
+                | Self::SpriteDrawingReverse(..)
+                | Self::SpriteDrawingJoin(..)
+                | Self::SpriteDrawingUnjoin(..)
+                | Self::SpriteFlipCaps(..)
+                | Self::SpriteGmNotes(..)
+                | Self::SpriteHp(..)
+                | Self::SpriteLabel(..)
                 | Self::SpriteLayer(..)
                 | Self::SpriteMove(..)
                 | Self::SpriteNew(..)
+                | Self::SpriteOrder(..)
+                | Self::SpriteOwner(..)
                 | Self::SpriteRemove(..)
                 | Self::SpriteRestore(..)
+                | Self::SpriteStrokePattern(..)
                 | Self::SpriteVisual(..)
         ) {
             true
@@ -105,7 +150,18 @@ impl SceneEvent {
     pub fn is_scene(&self) -> bool {
         if matches!(
             self,
-            Self::SceneDimensions(..) | Self::SceneTitle(..) | Self::FogActive(..)
+            Self::SceneBackground(..)
+                | Self::SceneBackgroundTexture(..)
+                | Self::SceneDimensions(..)
+                | Self::SceneGridColour(..)
+                | Self::SceneLock(..)
+                | Self::SceneTitle(..)
+                | Self::SceneUnitSystem(..)
+                | Self::CommentAdd(..)
+                | Self::CommentDelete(..)
+                | Self::FogActive(..)
+                | Self::WaypointAdd(..)
+                | Self::WaypointRemove(..)
         ) {
             true
         } else if let Self::EventSet(events) = self {
@@ -115,35 +171,90 @@ impl SceneEvent {
         }
     }
 
+    /// Whether this event carries information only editors may see.
+    pub fn is_private(&self) -> bool {
+        match self {
+            Self::CommentAdd(..)
+            | Self::CommentDelete(..)
+            | Self::LayerDescription(..)
+            | Self::SpriteGmNotes(..) => true,
+            Self::SpriteNew(sprite, _) => sprite.gm_notes.is_some(),
+            Self::EventSet(events) => events.iter().any(Self::is_private),
+            _ => false,
+        }
+    }
+
+    /// This event with any private events removed, for sending to players.
+    pub fn public(self) -> Option<Self> {
+        match self {
+            Self::EventSet(events) => {
+                Self::set(events.into_iter().filter_map(Self::public).collect())
+            }
+            Self::SpriteNew(mut sprite, layer) => {
+                sprite.gm_notes = None;
+                Some(Self::SpriteNew(sprite, layer))
+            }
+            event if event.is_private() => None,
+            event => Some(event),
+        }
+    }
+
     // If is_sprite or is_layer is true, this will be safe to unwrap.
     pub fn item(&self) -> Option<Id> {
         match self {
             &Self::GroupAdd(_, id)
             | &Self::GroupRemove(_, id)
+            | &Self::LayerDescription(id, ..)
             | &Self::LayerLocked(id, ..)
             | &Self::LayerMove(id, ..)
             | &Self::LayerNew(id, ..)
+            | &Self::LayerPerms(id, ..)
             | &Self::LayerRemove(id)
             | &Self::LayerRename(id, ..)
             | &Self::LayerRestore(id)
             | &Self::LayerVisibility(id, ..)
+            | &Self::SpriteCondition(id, ..)
+            | &Self::SpriteFlipCaps(id)
+            | &Self::SpriteGmNotes(id, ..)
+            | &Self::SpriteHp(id, ..)
+            | &Self::SpriteLabel(id, ..)
             | &Self::SpriteLayer(id, ..)
             | &Self::SpriteMove(id, ..)
+            | &Self::SpriteOrder(id, ..)
+            | &Self::SpriteOwner(id, ..)
             | &Self::SpriteRemove(id, ..)
             | &Self::SpriteRestore(id)
+            | &Self::SpriteStrokePattern(id, ..)
             | &Self::SpriteVisual(id, ..)
             | &Self::SpriteDrawingStart(id, ..)
-            | &Self::SpriteDrawingPoint(id, ..) => Some(id),
+            | &Self::SpriteDrawingPoint(id, ..)
+            | &Self::SpriteDrawingAnnotate(id, ..)
+            | &Self::SpriteDrawingPressure(id, ..)
+            | &Self::SpriteDrawingErase(id, ..)
+            | &Self::SpriteDrawingRestore(id, ..)
+            | &Self::SpriteDrawingReverse(id)
+            | &Self::SpriteDrawingJoin(id, _)
+            | &Self::SpriteDrawingUnjoin(id, _) => Some(id),
             Self::SpriteNew(s, ..) => Some(s.id),
-            Self::Dummy
+            Self::CommentAdd(_)
+            | Self::CommentDelete(_)
+            | Self::Dummy
             | Self::EventSet(_)
             | Self::FogActive(_, _)
             | Self::FogOcclude(_, _, _)
             | Self::FogReveal(_, _, _)
             | Self::GroupNew(_)
             | Self::GroupDelete(_)
+            | Self::GroupNest(..)
+            | Self::SceneBackground(_, _)
+            | Self::SceneBackgroundTexture(_, _)
             | Self::SceneDimensions(_, _, _, _)
-            | Self::SceneTitle(_, _) => None,
+            | Self::SceneGridColour(_, _)
+            | Self::SceneLock(_)
+            | Self::SceneTitle(_, _)
+            | Self::SceneUnitSystem(_, _)
+            | Self::WaypointAdd(_)
+            | Self::WaypointRemove(_) => None,
         }
     }
 
@@ -151,21 +262,47 @@ impl SceneEvent {
         Some(match self {
             &Self::GroupAdd(_, id) => id,
             &Self::GroupRemove(_, id) => id,
+            &Self::SpriteCondition(id, ..) => id,
+            &Self::SpriteFlipCaps(id) => id,
+            &Self::SpriteGmNotes(id, ..) => id,
+            &Self::SpriteHp(id, ..) => id,
+            &Self::SpriteLabel(id, ..) => id,
             &Self::SpriteLayer(id, ..) => id,
             &Self::SpriteMove(id, ..) => id,
             Self::SpriteNew(s, ..) => s.id,
+            &Self::SpriteOrder(id, ..) => id,
+            &Self::SpriteOwner(id, ..) => id,
             &Self::SpriteRemove(id, ..) => id,
             &Self::SpriteRestore(id) => id,
+            &Self::SpriteStrokePattern(id, ..) => id,
             &Self::SpriteVisual(id, ..) => id,
             _ => return None,
         })
     }
 
+    /// Drawing affected by this event, if it changes a drawing.
+    pub fn drawing(&self) -> Option<Id> {
+        Some(match *self {
+            Self::SpriteDrawingAnnotate(id, ..) => id,
+            Self::SpriteDrawingErase(id, ..) => id,
+            Self::SpriteDrawingRestore(id, ..) => id,
+            Self::SpriteDrawingJoin(id, ..) => id,
+            Self::SpriteDrawingPoint(id, ..) => id,
+            Self::SpriteDrawingPressure(id, ..) => id,
+            Self::SpriteDrawingReverse(id) => id,
+            Self::SpriteDrawingStart(id, ..) => id,
+            Self::SpriteDrawingUnjoin(id, ..) => id,
+            _ => return None,
+        })
+    }
+
     pub fn layer(&self) -> Option<Id> {
         Some(match *self {
+            Self::LayerDescription(id, ..) => id,
             Self::LayerLocked(id, ..) => id,
             Self::LayerMove(id, ..) => id,
             Self::LayerNew(id, ..) => id,
+            Self::LayerPerms(id, ..) => id,
             Self::LayerRename(id, ..) => id,
             Self::LayerRestore(id) => id,
             Self::LayerVisibility(id, ..) => id,
@@ -175,6 +312,75 @@ impl SceneEvent {
             _ => return None,
         })
     }
+
+    /// Name of the event variant, e.g. "SpriteMove".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CommentAdd(..) => "CommentAdd",
+            Self::CommentDelete(..) => "CommentDelete",
+            Self::Dummy => "Dummy",
+            Self::EventSet(..) => "EventSet",
+            Self::FogActive(..) => "FogActive",
+            Self::FogOcclude(..) => "FogOcclude",
+            Self::FogReveal(..) => "FogReveal",
+            Self::GroupNew(..) => "GroupNew",
+            Self::GroupAdd(..) => "GroupAdd",
+            Self::GroupRemove(..) => "GroupRemove",
+            Self::GroupDelete(..) => "GroupDelete",
+            Self::GroupNest(..) => "GroupNest",
+            Self::LayerDescription(..) => "LayerDescription",
+            Self::LayerLocked(..) => "LayerLocked",
+            Self::LayerMove(..) => "LayerMove",
+            Self::LayerNew(..) => "LayerNew",
+            Self::LayerPerms(..) => "LayerPerms",
+            Self::LayerRemove(..) => "LayerRemove",
+            Self::LayerRename(..) => "LayerRename",
+            Self::LayerRestore(..) => "LayerRestore",
+            Self::LayerVisibility(..) => "LayerVisibility",
+            Self::SceneBackground(..) => "SceneBackground",
+            Self::SceneBackgroundTexture(..) => "SceneBackgroundTexture",
+            Self::SceneDimensions(..) => "SceneDimensions",
+            Self::SceneGridColour(..) => "SceneGridColour",
+            Self::SceneLock(..) => "SceneLock",
+            Self::SceneTitle(..) => "SceneTitle",
+            Self::SceneUnitSystem(..) => "SceneUnitSystem",
+            Self::SpriteCondition(..) => "SpriteCondition",
+            Self::SpriteDrawingStart(..) => "SpriteDrawingStart",
+            Self::SpriteDrawingPoint(..) => "SpriteDrawingPoint",
+            Self::SpriteDrawingAnnotate(..) => "SpriteDrawingAnnotate",
+            Self::SpriteDrawingPressure(..) => "SpriteDrawingPressure",
+            Self::SpriteDrawingErase(..) => "SpriteDrawingErase",
+            Self::SpriteDrawingRestore(..) => "SpriteDrawingRestore",
+            Self::SpriteDrawingReverse(..) => "SpriteDrawingReverse",
+            Self::SpriteDrawingJoin(..) => "SpriteDrawingJoin",
+            Self::SpriteDrawingUnjoin(..) => "SpriteDrawingUnjoin",
+            Self::SpriteFlipCaps(..) => "SpriteFlipCaps",
+            Self::SpriteGmNotes(..) => "SpriteGmNotes",
+            Self::SpriteHp(..) => "SpriteHp",
+            Self::SpriteLabel(..) => "SpriteLabel",
+            Self::SpriteLayer(..) => "SpriteLayer",
+            Self::SpriteMove(..) => "SpriteMove",
+            Self::SpriteNew(..) => "SpriteNew",
+            Self::SpriteOrder(..) => "SpriteOrder",
+            Self::SpriteOwner(..) => "SpriteOwner",
+            Self::SpriteRemove(..) => "SpriteRemove",
+            Self::SpriteRestore(..) => "SpriteRestore",
+            Self::SpriteStrokePattern(..) => "SpriteStrokePattern",
+            Self::SpriteVisual(..) => "SpriteVisual",
+            Self::WaypointAdd(..) => "WaypointAdd",
+            Self::WaypointRemove(..) => "WaypointRemove",
+        }
+    }
+
+    /// Position of the first sprite placed or moved by this event.
+    pub fn first_point(&self) -> Option<Point> {
+        match self {
+            Self::EventSet(events) => events.iter().find_map(|e| e.first_point()),
+            Self::SpriteMove(_, _, rect) => Some(rect.top_left()),
+            Self::SpriteNew(sprite, _) => Some(sprite.rect.top_left()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -187,9 +393,17 @@ pub enum PermsEvent {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ClientEvent {
+    ChatMessage(String),     // (text)
+    CursorMove(Point, Rect), // (at, viewport)
+    DiceRoll(String),        // (notation)
+    DismissHandout,
+    InitiativeClear,
+    InitiativeSet(String, i32), // (name, roll)
     Ping,
-    SceneUpdate(SceneEvent), // (event)
-    SceneChange(Uuid),       // (scene_uuid)
+    Reconnect(u64),                    // (last_seen_sequence)
+    SceneUpdate(SceneEvent),           // (event)
+    SceneChange(Uuid),                 // (scene_uuid)
+    ShowHandout(String, Option<Uuid>), // (media_key, recipient), all players if None
 }
 
 // Events sent by Client. The client will keep track of these after sending them
@@ -205,10 +419,18 @@ pub struct ClientMessage {
 #[derive(Deserialize, Serialize)]
 pub enum ServerEvent {
     Approval(Id),
+    ChatMessage(Uuid, String, String, u64), // (user, username, text, timestamp_s)
+    DiceResult(Uuid, String, String, Vec<u32>, i32), // (user, username, notation, rolls, total)
+    DismissHandout,
     EventSet(Vec<ServerEvent>),
     GameOver,
     Disconnect,
+    Handout(String, String), // (media_key, sender_name)
     HealthCheck,
+    InitiativeList(Vec<(String, i32)>), // [(name, roll)] in turn order
+    PlayerCursor(Uuid, Option<Point>),  // (user, at), None when user leaves
+    PlayerViewport(Uuid, Rect),         // (user, viewport), sent to editors
+    PlayerInfo(Uuid, String, Option<String>), // (user, display_name, avatar_media_key)
     Rejection(Id),
     PermsChange(Perms),
     PermsUpdate(PermsEvent),
@@ -216,7 +438,9 @@ pub enum ServerEvent {
     SceneList(Vec<(String, Uuid)>, Uuid),
     SceneUpdate(SceneEvent),
     SelectedLayer(Id),
+    Sequence(u64), // (sequence) of the last journalled event, for reconnection
     UserId(Uuid),
+    OwnSprite(Id), // (sprite) belonging to the connected player
 }
 
 impl ServerEvent {
@@ -230,3 +454,47 @@ impl ServerEvent {
         }
     }
 }
+
+// WebSocket messages are prefixed with a byte indicating whether the remainder
+// of the message is deflated. Neither actix-ws nor browser WebSockets allow
+// permessage-deflate to be configured, so larger messages are compressed here
+// instead.
+const MESSAGE_RAW: u8 = 0;
+const MESSAGE_DEFLATE: u8 = 1;
+
+/// Encoded messages at least this large are compressed before sending.
+const COMPRESSION_THRESHOLD: usize = 256;
+
+/// Refuse to inflate messages beyond this size.
+const DECOMPRESSED_LIMIT: usize = 64 * 1024 * 1024; // 64 MiB
+
+/// Encode a message for sending over a WebSocket.
+pub fn encode_message(message: &impl serde::Serialize) -> Result<Vec<u8>, String> {
+    let data = bincode::serialize(message).map_err(|e| format!("Failed to encode message: {e}"))?;
+
+    if data.len() < COMPRESSION_THRESHOLD {
+        let mut message = Vec::with_capacity(data.len() + 1);
+        message.push(MESSAGE_RAW);
+        message.extend(data);
+        Ok(message)
+    } else {
+        let mut message = vec![MESSAGE_DEFLATE];
+        message.extend(miniz_oxide::deflate::compress_to_vec(&data, 6));
+        Ok(message)
+    }
+}
+
+/// Decode a message produced by `encode_message`.
+pub fn decode_message<T: serde::de::DeserializeOwned>(message: &[u8]) -> Result<T, String> {
+    let data = match message.split_first() {
+        Some((&MESSAGE_RAW, data)) => std::borrow::Cow::Borrowed(data),
+        Some((&MESSAGE_DEFLATE, data)) => std::borrow::Cow::Owned(
+            miniz_oxide::inflate::decompress_to_vec_with_limit(data, DECOMPRESSED_LIMIT)
+                .map_err(|e| format!("Failed to inflate message: {e:?}"))?,
+        ),
+        Some((flag, _)) => return Err(format!("Unknown message encoding: {flag}")),
+        None => return Err("Empty message.".to_string()),
+    };
+
+    bincode::deserialize(&data).map_err(|e| format!("Failed to decode message: {e}"))
+}
@@ -1,47 +1,107 @@
 use serde_derive::{Deserialize, Serialize};
 
-use super::{Id, Point, PointVector, Rect};
+use super::{comms::SceneEvent, Id, Point, PointVector, Rect};
 
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum DrawingMode {
     Cone,
     Freehand,
     Line,
+    Text,
+}
+
+/// Text label placed in the scene by a text drawing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Annotation {
+    pub text: String,
+    pub at: Point,
+
+    /// Height of the text in scene units.
+    pub font_size: f32,
+}
+
+impl Annotation {
+    pub const DEFAULT_FONT_SIZE: f32 = 0.5;
+    pub const MAX_LENGTH: usize = 256;
+
+    // Approximate width of a character as a proportion of the font size, used
+    // to estimate the area covered by the text.
+    const CHARACTER_WIDTH: f32 = 0.6;
+
+    fn new(text: &str, at: Point) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            text: text.chars().take(Self::MAX_LENGTH).collect(),
+            at,
+            font_size: Self::DEFAULT_FONT_SIZE,
+        })
+    }
+
+    /// Approximate dimensions of the rendered text in scene units.
+    pub fn dimensions(&self) -> Point {
+        Point::new(
+            self.text.chars().count() as f32 * self.font_size * Self::CHARACTER_WIDTH,
+            self.font_size,
+        )
+    }
+}
+
+/// Point removed from a freehand drawing by erasing, recorded so that the
+/// erase can be undone.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ErasedPoint {
+    /// Index of the point in the drawing before it was erased.
+    pub index: u32,
+    pub point: Point,
+    pub pressure: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 enum DrawingInner {
-    Freehand(PointVector),
+    /// Points and the pen pressure at each point, if it was recorded.
+    Freehand(PointVector, Vec<f32>),
     Line(Point, Point),
 }
 
 impl DrawingInner {
     fn new(mode: DrawingMode) -> Self {
         match mode {
-            DrawingMode::Cone | DrawingMode::Line => {
+            DrawingMode::Cone | DrawingMode::Line | DrawingMode::Text => {
                 DrawingInner::Line(Point::ORIGIN, Point::ORIGIN)
             }
-            DrawingMode::Freehand => DrawingInner::Freehand(PointVector::new()),
+            DrawingMode::Freehand => DrawingInner::Freehand(PointVector::new(), Vec::new()),
         }
     }
 
     fn from(mode: DrawingMode, points: PointVector) -> Self {
         match mode {
-            DrawingMode::Cone | DrawingMode::Line => DrawingInner::Line(
+            DrawingMode::Cone | DrawingMode::Line | DrawingMode::Text => DrawingInner::Line(
                 points.nth(1).unwrap_or_default(),
                 points.last().unwrap_or_default(),
             ),
-            DrawingMode::Freehand => DrawingInner::Freehand(points),
+            DrawingMode::Freehand => DrawingInner::Freehand(points, Vec::new()),
         }
     }
 
-    fn add(&mut self, point: Point) {
+    fn add(&mut self, point: Point, pressure: Option<f32>) {
         match self {
-            DrawingInner::Freehand(points) => {
+            DrawingInner::Freehand(points, pressures) => {
                 // Adds a new point to the drawing, if it isn't too close to the previous
                 // point.
                 const MINIMUM_DISTANCE: f32 = 0.1;
 
+                // Points added before pressure was first recorded take the
+                // first pressure, and points without a pressure the last.
+                if let Some(pressure) = pressure {
+                    if pressures.is_empty() {
+                        pressures.resize(points.n(), pressure);
+                    }
+                }
+
                 if let Some(prev) = points.last() {
                     if prev.dist(point) < MINIMUM_DISTANCE {
                         return;
@@ -49,6 +109,9 @@ impl DrawingInner {
                 }
 
                 points.add(point);
+                if let Some(pressure) = pressure.or(pressures.last().copied()) {
+                    pressures.push(pressure);
+                }
             }
             DrawingInner::Line(start, end) => {
                 if *start == Point::ORIGIN && *end == Point::ORIGIN {
@@ -59,9 +122,155 @@ impl DrawingInner {
         }
     }
 
+    /// Points within `radius` of `point`, which erasing there would remove.
+    /// At least two points are always kept, so erasing never leaves an empty
+    /// drawing. Only freehand drawings can be erased.
+    fn points_near(&self, point: Point, radius: f32) -> Vec<ErasedPoint> {
+        let DrawingInner::Freehand(points, pressures) = self else {
+            return Vec::new();
+        };
+
+        let mut erased = Vec::new();
+        let mut index = 0;
+        points.iter(|p| {
+            if p.dist(point) <= radius {
+                erased.push(ErasedPoint {
+                    index,
+                    point: p,
+                    pressure: pressures.get(index as usize).copied(),
+                });
+            }
+            index += 1;
+        });
+
+        if points.n() < erased.len() + 2 {
+            erased.clear();
+        }
+        erased
+    }
+
+    /// Removes the `erased` points, which must be in ascending order of
+    /// index and match the points at those indices. At least two points are
+    /// always kept.
+    fn erase(&mut self, erased: &[ErasedPoint]) -> bool {
+        let DrawingInner::Freehand(points, pressures) = self else {
+            return false;
+        };
+
+        if erased.is_empty()
+            || points.n() < erased.len() + 2
+            || erased.windows(2).any(|w| w[0].index >= w[1].index)
+        {
+            return false;
+        }
+
+        let mut current = Vec::with_capacity(points.n());
+        points.iter(|p| current.push(p));
+        if erased
+            .iter()
+            .any(|e| current.get(e.index as usize) != Some(&e.point))
+        {
+            return false;
+        }
+
+        let mut keep = vec![true; points.n()];
+        for e in erased {
+            keep[e.index as usize] = false;
+        }
+
+        let mut kept = keep.iter();
+        points.retain(|_| kept.next().copied().unwrap_or(false));
+        if !pressures.is_empty() {
+            let mut kept = keep.iter();
+            pressures.retain(|_| kept.next().copied().unwrap_or(false));
+        }
+        true
+    }
+
+    /// Puts `erased` points, in ascending order of index, back at their
+    /// indices, undoing an erase.
+    fn restore(&mut self, erased: &[ErasedPoint]) -> bool {
+        let DrawingInner::Freehand(points, pressures) = self else {
+            return false;
+        };
+
+        let n = points.n() + erased.len();
+        if erased.is_empty()
+            || erased.windows(2).any(|w| w[0].index >= w[1].index)
+            || erased.last().is_some_and(|e| e.index as usize >= n)
+        {
+            return false;
+        }
+
+        let mut current = Vec::with_capacity(points.n());
+        points.iter(|p| current.push(p));
+        let mut current = current.into_iter();
+        let mut current_pressures = std::mem::take(pressures).into_iter();
+        let had_pressures = current_pressures.len() > 0;
+        let mut restored = erased.iter().peekable();
+
+        *points = PointVector::new();
+        for i in 0..n {
+            if let Some(e) = restored.next_if(|e| e.index as usize == i) {
+                points.add(e.point);
+                if had_pressures {
+                    pressures.push(e.pressure.unwrap_or(1.0));
+                }
+            } else if let Some(p) = current.next() {
+                points.add(p);
+                pressures.extend(current_pressures.next());
+            }
+        }
+        true
+    }
+
+    /// Adds `other`, with pressures `other_pressures` if recorded, to the end
+    /// of the drawing. Only freehand drawings can be joined onto.
+    fn join(&mut self, other: &PointVector, other_pressures: &[f32]) -> bool {
+        let DrawingInner::Freehand(points, pressures) = self else {
+            return false;
+        };
+
+        if !pressures.is_empty() || !other_pressures.is_empty() {
+            pressures.resize(points.n(), 1.0);
+            if other_pressures.is_empty() {
+                pressures.resize(points.n() + other.n(), 1.0);
+            } else {
+                pressures.extend_from_slice(other_pressures);
+            }
+        }
+        other.iter(|p| points.add(p));
+        true
+    }
+
+    /// Removes the last `n` points from the drawing, keeping at least one.
+    fn remove_last(&mut self, n: usize) -> bool {
+        let DrawingInner::Freehand(points, pressures) = self else {
+            return false;
+        };
+
+        if n == 0 || n >= points.n() {
+            return false;
+        }
+
+        points.keep_n(points.n() - n);
+        pressures.truncate(points.n());
+        true
+    }
+
+    fn reverse(&mut self) {
+        match self {
+            DrawingInner::Freehand(points, pressures) => {
+                points.reverse();
+                pressures.reverse();
+            }
+            DrawingInner::Line(start, end) => std::mem::swap(start, end),
+        }
+    }
+
     fn line(&self) -> (Point, Point) {
         match self {
-            DrawingInner::Freehand(points) => {
+            DrawingInner::Freehand(points, _) => {
                 let p = points.first().unwrap_or(Point::ORIGIN);
                 let q = points.last().unwrap_or(Point::ORIGIN);
                 (p, q)
@@ -72,7 +281,7 @@ impl DrawingInner {
 
     fn end(&self) -> Option<Point> {
         match self {
-            DrawingInner::Freehand(points) => points.last(),
+            DrawingInner::Freehand(points, _) => points.last(),
             &DrawingInner::Line(_, end) => Some(end),
         }
     }
@@ -84,7 +293,7 @@ impl DrawingInner {
         let delta = rect.top_left();
         if delta.non_zero() {
             match self {
-                DrawingInner::Freehand(points) => {
+                DrawingInner::Freehand(points, _) => {
                     points.translate(-delta);
                 }
                 DrawingInner::Line(start, end) => {
@@ -98,7 +307,7 @@ impl DrawingInner {
 
     fn length(&self) -> f32 {
         match self {
-            DrawingInner::Freehand(points) => {
+            DrawingInner::Freehand(points, _) => {
                 let mut dist = 0.0;
                 let mut prev = None;
                 points.iter(|p| {
@@ -115,7 +324,7 @@ impl DrawingInner {
 
     fn rect(&self) -> Rect {
         match self {
-            DrawingInner::Freehand(points) => points.rect(),
+            DrawingInner::Freehand(points, _) => points.rect(),
             DrawingInner::Line(p, q) => Rect {
                 x: p.x.min(q.x),
                 y: p.y.min(q.y),
@@ -130,6 +339,7 @@ impl DrawingInner {
 pub struct Drawing {
     pub id: Id,
     pub mode: DrawingMode,
+    pub annotation: Option<Annotation>,
     inner: DrawingInner,
 }
 
@@ -138,6 +348,7 @@ impl Drawing {
         Self {
             id,
             mode,
+            annotation: None,
             inner: DrawingInner::new(mode),
         }
     }
@@ -146,6 +357,7 @@ impl Drawing {
         Self {
             id,
             mode,
+            annotation: None,
             inner: DrawingInner::from(mode, points),
         }
     }
@@ -154,12 +366,75 @@ impl Drawing {
         self.inner.line()
     }
 
+    /// Reverse the direction of the drawing, so that it starts where it
+    /// used to end.
+    pub fn reverse(&mut self) -> SceneEvent {
+        self.inner.reverse();
+        SceneEvent::SpriteDrawingReverse(self.id)
+    }
+
+    /// Add `points`, in the drawing's coordinates, with their pen pressures
+    /// if recorded, to the end of a freehand drawing. Returns false if this
+    /// isn't a freehand drawing.
+    pub fn join(&mut self, points: &PointVector, pressures: &[f32]) -> bool {
+        self.inner.join(points, pressures)
+    }
+
+    /// Remove the last `n` points of a freehand drawing, undoing a join.
+    pub fn remove_last(&mut self, n: usize) -> bool {
+        self.inner.remove_last(n)
+    }
+
     pub fn last_point(&self) -> Option<Point> {
         self.inner.end()
     }
 
     pub fn add_point(&mut self, point: Point) {
-        self.inner.add(point);
+        self.inner.add(point, None);
+    }
+
+    /// Add a point drawn with a stylus at `pressure`, between 0 and 1, which
+    /// scales the width of the line at that point.
+    pub fn add_point_with_pressure(&mut self, point: Point, pressure: f32) {
+        self.inner.add(point, Some(pressure.clamp(0.0, 1.0)));
+    }
+
+    /// Remove the points of a freehand drawing within `radius` of `point`,
+    /// returning the points removed.
+    pub fn erase_near(&mut self, point: Point, radius: f32) -> Vec<ErasedPoint> {
+        let erased = self.inner.points_near(point, radius);
+        if !erased.is_empty() {
+            self.inner.erase(&erased);
+        }
+        erased
+    }
+
+    /// Remove the `erased` points of a freehand drawing, returning false if
+    /// they don't match the drawing's points.
+    pub fn erase(&mut self, erased: &[ErasedPoint]) -> bool {
+        self.inner.erase(erased)
+    }
+
+    /// Put `erased` points back into a freehand drawing, undoing an erase.
+    pub fn restore(&mut self, erased: &[ErasedPoint]) -> bool {
+        self.inner.restore(erased)
+    }
+
+    /// Sets the text of a text drawing at `at`, sizing the drawing to cover
+    /// the text. Returns false if this isn't a text drawing or the text is
+    /// empty.
+    pub fn annotate(&mut self, text: &str, at: Point) -> bool {
+        if self.mode != DrawingMode::Text {
+            return false;
+        }
+
+        let Some(annotation) = Annotation::new(text, at) else {
+            return false;
+        };
+
+        self.inner = DrawingInner::Line(at, at + annotation.dimensions());
+        self.annotation = Some(annotation);
+        true
     }
 
     /// Simplifies the drawing such that its top-left-most point is the
@@ -178,22 +453,40 @@ impl Drawing {
 
     pub fn n_points(&self) -> u32 {
         match &self.inner {
-            DrawingInner::Freehand(points) => points.n() as u32,
+            DrawingInner::Freehand(points, _) => points.n() as u32,
             DrawingInner::Line(_, _) => 2,
         }
     }
 
     pub fn points(&self) -> Option<&PointVector> {
-        if let DrawingInner::Freehand(points) = &self.inner {
+        if let DrawingInner::Freehand(points, _) = &self.inner {
             Some(points)
         } else {
             None
         }
     }
 
+    /// Set the pen pressure at each point of a freehand drawing. Ignored
+    /// unless there is a pressure for every point.
+    pub fn set_pressures(&mut self, new: Vec<f32>) {
+        if let DrawingInner::Freehand(points, pressures) = &mut self.inner
+            && new.len() == points.n()
+        {
+            *pressures = new;
+        }
+    }
+
+    /// Pen pressure at each point of a freehand drawing, if it was recorded.
+    pub fn pressures(&self) -> Option<&[f32]> {
+        match &self.inner {
+            DrawingInner::Freehand(_, pressures) if !pressures.is_empty() => Some(pressures),
+            _ => None,
+        }
+    }
+
     pub fn points_build(&self) -> PointVector {
         match &self.inner {
-            DrawingInner::Freehand(points) => points.clone(),
+            DrawingInner::Freehand(points, _) => points.clone(),
             DrawingInner::Line(p, q) => {
                 let mut points = PointVector::new();
                 points.add(*p);
@@ -214,4 +507,87 @@ mod test {
         assert!(drawing.points().is_some());
         drawing.points().unwrap();
     }
+
+    #[test]
+    fn test_erase_near() {
+        let mut drawing = Drawing::from(
+            1,
+            DrawingMode::Freehand,
+            PointVector::from(vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 3.0, 0.0]),
+        );
+
+        assert!(drawing.erase_near(Point::new(5.0, 5.0), 0.5).is_empty());
+        let erased = drawing.erase_near(Point::new(1.1, 0.0), 0.5);
+        assert_eq!(
+            erased,
+            vec![ErasedPoint {
+                index: 1,
+                point: Point::new(1.0, 0.0),
+                pressure: None
+            }]
+        );
+        assert_eq!(drawing.n_points(), 3);
+        assert_eq!(drawing.points().unwrap().first(), Some(Point::ORIGIN));
+
+        // Erasing everything is refused, leaving the drawing intact.
+        assert!(drawing.erase_near(Point::new(1.5, 0.0), 10.0).is_empty());
+        assert_eq!(drawing.n_points(), 3);
+
+        // Points which don't match the drawing aren't erased.
+        assert!(!drawing.erase(&erased));
+
+        // Restoring puts the points back where they were.
+        assert!(drawing.restore(&erased));
+        assert_eq!(
+            drawing.points(),
+            Some(&PointVector::from(vec![
+                0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 3.0, 0.0
+            ]))
+        );
+        assert!(drawing.erase(&erased));
+        assert_eq!(drawing.n_points(), 3);
+
+        let mut line = Drawing::new(2, DrawingMode::Line);
+        line.add_point(Point::ORIGIN);
+        assert!(line.erase_near(Point::ORIGIN, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_pressure() {
+        let mut drawing = Drawing::new(1, DrawingMode::Freehand);
+        drawing.add_point(Point::ORIGIN);
+        assert!(drawing.pressures().is_none());
+
+        // Earlier points take the first pressure recorded, and pressures are
+        // clamped.
+        drawing.add_point_with_pressure(Point::new(1.0, 0.0), 0.25);
+        drawing.add_point_with_pressure(Point::new(2.0, 0.0), 1.5);
+        drawing.add_point_with_pressure(Point::new(2.01, 0.0), 0.5);
+        assert_eq!(drawing.pressures(), Some(&[0.25, 0.25, 1.0][..]));
+
+        // Pressures stay with their points.
+        drawing.reverse();
+        assert_eq!(drawing.pressures(), Some(&[1.0, 0.25, 0.25][..]));
+        let erased = drawing.erase_near(Point::new(1.0, 0.0), 0.5);
+        assert_eq!(drawing.pressures(), Some(&[1.0, 0.25][..]));
+        assert!(drawing.restore(&erased));
+        assert_eq!(drawing.pressures(), Some(&[1.0, 0.25, 0.25][..]));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut drawing = Drawing::new(1, DrawingMode::Text);
+        assert!(!drawing.annotate("  ", Point::ORIGIN));
+        assert!(drawing.annotation.is_none());
+
+        assert!(drawing.annotate(" Tavern ", Point::same(2.0)));
+        let annotation = drawing.annotation.as_ref().unwrap();
+        assert_eq!(annotation.text, "Tavern");
+        assert_eq!(annotation.at, Point::same(2.0));
+        assert_eq!(drawing.rect().top_left(), Point::same(2.0));
+        assert_eq!(annotation.dimensions(), Point::new(6.0 * 0.5 * 0.6, 0.5));
+
+        let mut line = Drawing::new(2, DrawingMode::Line);
+        assert!(!line.annotate("Tavern", Point::ORIGIN));
+    }
 }
@@ -104,21 +104,23 @@ impl Fog {
         x < self.w && y < self.h
     }
 
+    /// Change the dimensions of the fog, keeping the state of tiles which
+    /// remain on the map. New tiles are occluded.
     pub fn resize(&mut self, w: u32, h: u32) {
-        let mut new_fog = Self::make_fog(w, h);
+        let mut resized = Fog {
+            active: self.active,
+            ..Fog::new(w, h)
+        };
 
-        for y in 0..h {
-            for x in 0..w {
-                if self.on_map(x, y) {
-                    let idx = (Self::row_ints(w) * y + (x / Self::BITS)) as usize;
-                    new_fog[idx] = self.fog[self.idx(x, y)];
+        for y in 0..h.min(self.h) {
+            for x in 0..w.min(self.w) {
+                if !self.occluded(x, y) {
+                    resized.reveal(x, y);
                 }
             }
         }
 
-        self.w = w;
-        self.h = h;
-        self.fog = new_fog;
+        *self = resized;
     }
 
     pub fn occluded(&self, x: u32, y: u32) -> bool {
@@ -210,11 +212,12 @@ impl Fog {
     pub fn set_circle(&mut self, at: Point, r: f32, occluded: bool) -> Option<SceneEvent> {
         let mut events = Vec::new();
 
-        // Negative values become 0 through (as u32).
+        // Negative values become 0 through (as u32). Only tiles on the map
+        // are visited, however large the radius.
         let xmin = (at.x - r).floor() as u32;
-        let xmax = (at.x + r).ceil() as u32;
+        let xmax = ((at.x + r).ceil() as u32).min(self.w.saturating_sub(1));
         let ymin = (at.y - r).floor() as u32;
-        let ymax = (at.y + r).ceil() as u32;
+        let ymax = ((at.y + r).ceil() as u32).min(self.h.saturating_sub(1));
 
         for x in xmin..=xmax {
             for y in ymin..=ymax {
@@ -229,6 +232,96 @@ impl Fog {
         SceneEvent::set(events)
     }
 
+    /// Set occluded status of all tiles whose center lies within a rect.
+    ///
+    /// * `rect`     Region in which to update tile state.
+    /// * `occluded` New occluded state for tiles in the region.
+    fn set_rect(&mut self, rect: crate::Rect, occluded: bool) -> Option<SceneEvent> {
+        let rect = rect.positive_dimensions();
+        let mut events = Vec::new();
+
+        // Negative values become 0 through (as u32).
+        let xmin = rect.x.floor() as u32;
+        let xmax = ((rect.x + rect.w).ceil() as u32).min(self.w);
+        let ymin = rect.y.floor() as u32;
+        let ymax = ((rect.y + rect.h).ceil() as u32).min(self.h);
+
+        for y in ymin..ymax {
+            for x in xmin..xmax {
+                if rect.contains_point(Self::tile_center(x, y)) {
+                    if let Some(event) = self.set(x, y, occluded) {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+
+        SceneEvent::set(events)
+    }
+
+    /// Reveal every tile whose center lies within `rect`.
+    pub fn reveal_rect(&mut self, rect: crate::Rect) -> Option<SceneEvent> {
+        self.set_rect(rect, false)
+    }
+
+    /// Occlude every tile whose center lies within `rect`.
+    pub fn occlude_rect(&mut self, rect: crate::Rect) -> Option<SceneEvent> {
+        self.set_rect(rect, true)
+    }
+
+    /// Set occluded status of every tile in the fog.
+    ///
+    /// * `occluded` New occluded state for all tiles.
+    fn set_all(&mut self, occluded: bool) -> Option<SceneEvent> {
+        let mut events = Vec::new();
+        for y in 0..self.h {
+            for x in 0..self.w {
+                if let Some(event) = self.set(x, y, occluded) {
+                    events.push(event);
+                }
+            }
+        }
+        SceneEvent::set(events)
+    }
+
+    /// Occlude every revealed tile.
+    pub fn fill_all(&mut self) -> Option<SceneEvent> {
+        self.set_all(true)
+    }
+
+    /// Reveal every occluded tile.
+    pub fn clear_all(&mut self) -> Option<SceneEvent> {
+        self.set_all(false)
+    }
+
+    /// Encode the fog as a `w` by `h` greyscale PNG mask, with occluded tiles
+    /// black and revealed tiles white.
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut data = Vec::with_capacity((self.w * self.h) as usize);
+        for y in 0..self.h {
+            for x in 0..self.w {
+                data.push(if self.occluded(x, y) { 0 } else { 255 });
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.w, self.h);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to write PNG header: {e}"))?;
+        writer
+            .write_image_data(&data)
+            .map_err(|e| format!("Failed to write PNG data: {e}"))?;
+        writer
+            .finish()
+            .map_err(|e| format!("Failed to encode PNG: {e}"))?;
+
+        Ok(bytes)
+    }
+
     pub fn set_active(&mut self, active: bool) -> Option<SceneEvent> {
         if self.active == active {
             None
@@ -321,6 +414,82 @@ mod test {
         assert!(fog.occluded(3, 3));
     }
 
+    #[test]
+    fn test_resize_shrink() {
+        let mut fog = Fog::new(40, 4);
+        fog.reveal(1, 1);
+        fog.reveal(20, 2);
+        fog.reveal(35, 3);
+
+        // Tiles beyond the new bounds are dropped, even within a row's int.
+        fog.resize(16, 3);
+        assert_eq!(fog.n_revealed, 1);
+        fog.resize(40, 4);
+        assert_eq!(fog.n_revealed, 1);
+        assert!(!fog.occluded(1, 1));
+        assert!(fog.occluded(20, 2) && fog.occluded(35, 3));
+    }
+
+    #[test]
+    fn test_fill_clear_all() {
+        let mut fog = Fog::new(40, 3);
+        fog.reveal(1, 1);
+        fog.reveal(35, 2);
+
+        // Only the two revealed tiles need to be occluded.
+        match fog.fill_all() {
+            Some(crate::comms::SceneEvent::EventSet(events)) => assert_eq!(events.len(), 2),
+            _ => panic!("Expected event set."),
+        }
+        assert_eq!(fog.n_revealed, 0);
+        assert!(fog.occluded(1, 1) && fog.occluded(35, 2));
+        assert!(fog.fill_all().is_none());
+
+        fog.clear_all();
+        assert_eq!(fog.n_revealed, 40 * 3);
+        assert!(!fog.occluded(0, 0) && !fog.occluded(39, 2));
+        assert!(fog.clear_all().is_none());
+    }
+
+    #[test]
+    fn test_reveal_rect() {
+        let mut fog = Fog::new(10, 10);
+
+        // Only tiles with centres inside the rect are revealed.
+        match fog.reveal_rect(crate::Rect::new(1.4, 2.0, 1.2, 1.0)) {
+            Some(crate::comms::SceneEvent::EventSet(events)) => assert_eq!(events.len(), 2),
+            _ => panic!("Expected event set."),
+        }
+        assert!(!fog.occluded(1, 2) && !fog.occluded(2, 2));
+        assert!(fog.occluded(3, 2) && fog.occluded(1, 3));
+
+        // Negative dimensions and regions overhanging the map are handled.
+        fog.reveal_rect(crate::Rect::new(12.0, 12.0, -4.0, -4.0));
+        assert_eq!(fog.n_revealed, 2 + 4);
+        assert!(!fog.occluded(9, 9) && !fog.occluded(8, 8));
+
+        fog.occlude_rect(crate::Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(fog.n_revealed, 0);
+        assert!(fog
+            .occlude_rect(crate::Rect::new(0.0, 0.0, 10.0, 10.0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_to_png() {
+        let mut fog = Fog::new(3, 2);
+        fog.reveal(1, 0);
+        fog.reveal(2, 1);
+
+        let png = fog.to_png().unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        assert_eq!(&data[..info.buffer_size()], &[0, 255, 0, 0, 0, 255]);
+    }
+
     #[test]
     fn test_resize() {
         let mut fog = Fog::new(5, 5);
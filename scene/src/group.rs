@@ -4,17 +4,28 @@ use crate::{comms::SceneEvent, Id};
 pub struct Group {
     pub id: Id,
     sprites: Vec<Id>,
+
+    /// IDs of groups nested within this group.
+    subgroups: Vec<Id>,
 }
 
 impl Group {
     pub fn new(id: Id, sprites: Vec<Id>) -> Self {
-        Group { id, sprites }
+        Group {
+            id,
+            sprites,
+            subgroups: Vec::new(),
+        }
     }
 
     pub fn includes(&self, sprite: Id) -> bool {
         self.sprites.contains(&sprite)
     }
 
+    pub fn includes_group(&self, group: Id) -> bool {
+        self.subgroups.contains(&group)
+    }
+
     pub fn add(&mut self, sprite: Id) -> SceneEvent {
         if !self.includes(sprite) {
             self.sprites.push(sprite);
@@ -27,11 +38,26 @@ impl Group {
         SceneEvent::GroupRemove(self.id, sprite)
     }
 
+    pub fn add_group(&mut self, subgroup: Id) -> SceneEvent {
+        if !self.includes_group(subgroup) {
+            self.subgroups.push(subgroup);
+        }
+        SceneEvent::GroupNest(self.id, subgroup)
+    }
+
+    pub fn remove_group(&mut self, subgroup: Id) {
+        self.subgroups.retain(|g| *g != subgroup);
+    }
+
     pub fn sprites(&self) -> &[Id] {
         &self.sprites
     }
 
+    pub fn subgroups(&self) -> &[Id] {
+        &self.subgroups
+    }
+
     pub fn empty(&self) -> bool {
-        self.sprites.is_empty()
+        self.sprites.is_empty() && self.subgroups.is_empty()
     }
 }
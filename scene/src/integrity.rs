@@ -0,0 +1,20 @@
+use crate::Id;
+
+/// An inconsistency between the cross-references within a scene, as found by
+/// `Scene::validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// A drawing is linked to a sprite which doesn't exist or doesn't render
+    /// it.
+    DrawingSpriteMissing { drawing: Id, sprite: Id },
+    /// A sprite is linked to a drawing which doesn't exist.
+    DrawingMissing { drawing: Id, sprite: Id },
+    /// A sprite renders a drawing but isn't linked to it.
+    DrawingUnlinked { drawing: Id, sprite: Id },
+    /// A group contains a sprite which doesn't exist.
+    GroupSpriteMissing { group: Id, sprite: Id },
+    /// A group contains a subgroup which doesn't exist.
+    GroupSubgroupMissing { group: Id, subgroup: Id },
+    /// The next ID to be allocated is already in use.
+    NextIdInUse { next_id: Id, max_id: Id },
+}
@@ -1,15 +1,18 @@
 use serde_derive::{Deserialize, Serialize};
 
 use super::{Id, Sprite};
-use crate::{comms::SceneEvent, Rect};
+use crate::{comms::SceneEvent, perms::LayerPerms, Rect};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Layer {
     pub id: Id,
     pub title: String,
+    /// Notes for editors, which aren't sent to players.
+    pub description: Option<String>,
     pub z: i32,
     pub visible: bool,
     pub locked: bool,
+    pub perms: LayerPerms,
     pub sprites: Vec<Sprite>,
     pub removed_sprites: Vec<Sprite>,
     pub z_min: i32,
@@ -17,13 +20,17 @@ pub struct Layer {
 }
 
 impl Layer {
+    pub const MAX_DESCRIPTION_LENGTH: usize = 2048;
+
     pub fn new(id: i64, title: &str, z: i32) -> Self {
         Layer {
             id,
             title: title.to_string(),
+            description: None,
             z,
             visible: true,
             locked: false,
+            perms: LayerPerms::default(),
             sprites: vec![],
             removed_sprites: vec![],
             z_min: 0,
@@ -37,6 +44,25 @@ impl Layer {
         SceneEvent::LayerRename(self.id, old_title, self.title.clone())
     }
 
+    /// Update the description, trimming whitespace and truncating to
+    /// `MAX_DESCRIPTION_LENGTH`. Blank descriptions are removed.
+    pub fn set_description(&mut self, description: Option<String>) -> Option<SceneEvent> {
+        let description = description
+            .map(|d| {
+                d.trim()
+                    .chars()
+                    .take(Self::MAX_DESCRIPTION_LENGTH)
+                    .collect::<String>()
+            })
+            .filter(|d| !d.is_empty());
+        if self.description != description {
+            let old = std::mem::replace(&mut self.description, description.clone());
+            Some(SceneEvent::LayerDescription(self.id, old, description))
+        } else {
+            None
+        }
+    }
+
     pub fn set_visible(&mut self, visible: bool) -> Option<SceneEvent> {
         if self.visible != visible {
             self.visible = visible;
@@ -55,6 +81,16 @@ impl Layer {
         }
     }
 
+    pub fn set_perms(&mut self, perms: LayerPerms) -> Option<SceneEvent> {
+        if self.perms != perms {
+            let old = self.perms;
+            self.perms = perms;
+            Some(SceneEvent::LayerPerms(self.id, old, perms))
+        } else {
+            None
+        }
+    }
+
     // Sprites can only be selected from a layer if it is both visible and
     // unlocked.
     pub fn selectable(&self) -> bool {
@@ -69,6 +105,25 @@ impl Layer {
         self.sprites.iter().find(|s| s.id == id)
     }
 
+    pub fn sprite_index(&self, id: Id) -> Option<usize> {
+        self.sprites.iter().position(|s| s.id == id)
+    }
+
+    /// Swap the sprites at indices `a` and `b`, exchanging their z values so
+    /// that the new order survives subsequent sorts. Returns `false` if either
+    /// index is out of bounds.
+    pub fn swap_sprites(&mut self, a: usize, b: usize) -> bool {
+        if a >= self.sprites.len() || b >= self.sprites.len() {
+            return false;
+        }
+
+        let z = self.sprites[a].z;
+        self.sprites[a].z = self.sprites[b].z;
+        self.sprites[b].z = z;
+        self.sprites.swap(a, b);
+        true
+    }
+
     fn sort_sprites(&mut self) {
         self.sprites.sort_by(|a, b| a.z.cmp(&b.z));
     }
@@ -114,6 +169,11 @@ impl Layer {
         }
     }
 
+    /// Sprites which overlap `view`, in the order they should be drawn.
+    pub fn sprites_in_view(&self, view: Rect) -> impl Iterator<Item = &Sprite> {
+        self.sprites.iter().filter(move |s| view.intersects(s.rect))
+    }
+
     pub fn sprites_in(&self, region: Rect) -> Vec<Id> {
         let mut ret = vec![];
         for sprite in &self.sprites {
@@ -5,35 +5,61 @@
 
 use std::collections::HashMap;
 
+pub use comment::Comment;
 use comms::SceneEvent;
-pub use drawing::{Drawing, DrawingMode};
+pub use drawing::{Annotation, Drawing, DrawingMode, ErasedPoint};
 pub use fog::Fog;
 pub use group::Group;
+pub use integrity::IntegrityError;
 pub use layer::Layer;
+pub use palette::ColourPalette;
+use perms::{LayerPerms, Perms};
 pub use point::{Point, PointVector};
 pub use project::Project;
 pub use rect::{float_eq, Dimension, Rect};
-pub use sprite::{Cap, Colour, Outline, Shape, Sprite, Visual as SpriteVisual};
+pub use sprite::{
+    Cap, Colour, Condition, Outline, Shape, Sprite, SpriteTmpl, StrokePattern,
+    Visual as SpriteVisual,
+};
+pub use units::UnitSystem;
 use uuid::Uuid;
+pub use waypoint::Waypoint;
 
 pub mod comms;
 pub mod perms;
 
+mod comment;
 mod drawing;
 mod fog;
 mod group;
+mod integrity;
 mod layer;
+mod palette;
 mod point;
 mod project;
 mod rect;
 pub mod serde;
 mod sprite;
+mod units;
+mod waypoint;
 
 #[cfg(test)]
 mod tests;
 
 pub type Id = i64;
 
+/// ID used as a texture in scenes for media with key `key`, the hexadecimal
+/// simple format of its UUID. This is the first 8 bytes of the key, so that
+/// 16 digit keys produced from texture IDs map back to the same ID. Returns
+/// 0 for invalid keys.
+pub fn media_key_id(key: &str) -> Id {
+    if !matches!(key.len(), 16 | 32) || !key.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return 0;
+    }
+
+    u64::from_str_radix(&key[..16], 16).map_or(0, |id| id as Id)
+}
+
 #[derive(Clone, serde_derive::Serialize, serde_derive::Deserialize)]
 pub struct Scene {
     canon: bool,
@@ -51,12 +77,32 @@ pub struct Scene {
     pub removed_layers: Vec<Layer>,
     pub fog: Fog,
     pub groups: Vec<Group>,
+    pub grid_colour: Colour,
+    pub background: Colour,
+
+    /// Texture drawn beneath all layers, covering the scene grid.
+    pub background_texture: Option<Id>,
+
+    /// Saved camera positions, in the order they were added.
+    pub waypoints: Vec<Waypoint>,
+
+    /// Units used to display distances.
+    pub unit_system: UnitSystem,
+
+    /// Notes left by editors for each other, in the order they were added.
+    pub comments: Vec<Comment>,
+
+    /// Whether the scene is frozen, so that it can't be changed until the
+    /// owner unlocks it.
+    pub locked: bool,
 }
 
 impl Scene {
     pub const FOREGROUND_Z: i32 = 1;
     pub const MAX_SIZE: u32 = 512;
     pub const DEFAULT_SIZE: u32 = 32;
+    pub const DEFAULT_GRID_COLOUR: Colour = Colour([0.5, 0.5, 0.5, 0.6]);
+    pub const DEFAULT_BACKGROUND: Colour = Colour([1.0, 1.0, 1.0, 1.0]);
 
     // When creating a clone of this scene for a client, this many IDs will be
     // set aside for use by that client.
@@ -84,30 +130,222 @@ impl Scene {
         SceneEvent::SceneDimensions(old_w, old_h, w, h)
     }
 
+    pub fn set_background(&mut self, colour: Colour) -> Option<SceneEvent> {
+        if self.background != colour {
+            let old = self.background;
+            self.background = colour;
+            Some(SceneEvent::SceneBackground(old, colour))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_background_texture(&mut self, texture: Option<Id>) -> Option<SceneEvent> {
+        if self.background_texture != texture {
+            let old = self.background_texture;
+            self.background_texture = texture;
+            Some(SceneEvent::SceneBackgroundTexture(old, texture))
+        } else {
+            None
+        }
+    }
+
+    /// IDs of all textures used in the scene, including by removed sprites
+    /// and layers which may yet be restored.
+    pub fn texture_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.all_sprites()
+            .filter_map(|s| s.visual.texture())
+            .chain(self.background_texture)
+    }
+
+    pub fn set_unit_system(&mut self, unit_system: UnitSystem) -> Option<SceneEvent> {
+        if self.unit_system != unit_system {
+            let old = self.unit_system;
+            self.unit_system = unit_system;
+            Some(SceneEvent::SceneUnitSystem(old, unit_system))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_locked(&mut self, locked: bool) -> Option<SceneEvent> {
+        if self.locked != locked {
+            self.locked = locked;
+            Some(SceneEvent::SceneLock(locked))
+        } else {
+            None
+        }
+    }
+
+    /// Format a distance of `cells` grid cells in this scene's units.
+    pub fn cell_size_display(&self, cells: f32) -> String {
+        self.unit_system.format(cells)
+    }
+
+    pub fn set_grid_colour(&mut self, colour: Colour) -> Option<SceneEvent> {
+        if self.grid_colour != colour {
+            let old = self.grid_colour;
+            self.grid_colour = colour;
+            Some(SceneEvent::SceneGridColour(old, colour))
+        } else {
+            None
+        }
+    }
+
+    pub fn waypoint(&self, name: &str) -> Option<&Waypoint> {
+        self.waypoints.iter().find(|w| w.name == name)
+    }
+
+    /// Add a waypoint to the scene. Waypoint names must be unique.
+    pub fn add_waypoint(&mut self, waypoint: Waypoint) -> Option<SceneEvent> {
+        if self.waypoint(&waypoint.name).is_some() {
+            None
+        } else {
+            self.waypoints.push(waypoint.clone());
+            Some(SceneEvent::WaypointAdd(waypoint))
+        }
+    }
+
+    pub fn remove_waypoint(&mut self, name: &str) -> Option<SceneEvent> {
+        let index = self.waypoints.iter().position(|w| w.name == name)?;
+        Some(SceneEvent::WaypointRemove(self.waypoints.remove(index)))
+    }
+
+    pub fn add_comment(&mut self, comment: Comment) -> SceneEvent {
+        self.comments.push(comment.clone());
+        SceneEvent::CommentAdd(comment)
+    }
+
+    pub fn remove_comment(&mut self, comment: &Comment) -> Option<SceneEvent> {
+        let index = self.comments.iter().rposition(|c| c == comment)?;
+        Some(SceneEvent::CommentDelete(self.comments.remove(index)))
+    }
+
     fn next_id(&mut self) -> Id {
         let id = self.next_id;
         self.next_id += 1;
         id
     }
 
+    /// All sprites in the scene, including removed sprites and those on
+    /// removed layers, which may yet be restored.
+    fn all_sprites(&self) -> impl Iterator<Item = &Sprite> {
+        self.layers
+            .iter()
+            .chain(self.removed_layers.iter())
+            .flat_map(|l| l.sprites.iter().chain(l.removed_sprites.iter()))
+    }
+
+    /// Largest ID allocated to a layer, sprite, drawing or group.
+    fn max_id(&self) -> Id {
+        self.layers
+            .iter()
+            .chain(self.removed_layers.iter())
+            .map(|l| l.id)
+            .chain(self.all_sprites().map(|s| s.id))
+            .chain(self.sprite_drawings.keys().copied())
+            .chain(self.groups.iter().map(|g| g.id))
+            .fold(1, Id::max)
+    }
+
     pub fn minimise_next_id(&mut self) {
-        let mut max_id = 1;
-        for l in &self.layers {
-            max_id = max_id.max(l.id);
-            for s in &l.sprites {
-                max_id = max_id.max(s.id);
+        self.next_id = self.max_id() + 1;
+    }
+
+    /// Check the cross-references between sprites, drawings and groups,
+    /// returning each inconsistency found.
+    pub fn validate(&self) -> Vec<IntegrityError> {
+        let mut errors = Vec::new();
+        let sprites: HashMap<Id, &Sprite> = self.all_sprites().map(|s| (s.id, s)).collect();
+        let renders = |sprite: Id, drawing: Id| {
+            sprites
+                .get(&sprite)
+                .is_some_and(|s| s.visual.drawing() == Some(drawing))
+        };
+
+        for (&drawing, &sprite) in &self.drawing_sprites {
+            if !self.sprite_drawings.contains_key(&drawing) {
+                errors.push(IntegrityError::DrawingMissing { drawing, sprite });
+            } else if !renders(sprite, drawing) {
+                errors.push(IntegrityError::DrawingSpriteMissing { drawing, sprite });
             }
         }
 
-        for d in self.sprite_drawings.values() {
-            max_id = max_id.max(d.id);
+        // Checked after stale links, so that repairs replace them.
+        for sprite in self.all_sprites() {
+            if let Some(drawing) = sprite.visual.drawing()
+                && self.sprite_drawings.contains_key(&drawing)
+                && !self
+                    .drawing_sprites
+                    .get(&drawing)
+                    .is_some_and(|&s| renders(s, drawing))
+            {
+                errors.push(IntegrityError::DrawingUnlinked {
+                    drawing,
+                    sprite: sprite.id,
+                });
+            }
         }
 
-        for g in &self.groups {
-            max_id = max_id.max(g.id);
+        for group in &self.groups {
+            for &sprite in group.sprites() {
+                if !sprites.contains_key(&sprite) {
+                    errors.push(IntegrityError::GroupSpriteMissing {
+                        group: group.id,
+                        sprite,
+                    });
+                }
+            }
+
+            for &subgroup in group.subgroups() {
+                if !self.groups.iter().any(|g| g.id == subgroup) {
+                    errors.push(IntegrityError::GroupSubgroupMissing {
+                        group: group.id,
+                        subgroup,
+                    });
+                }
+            }
         }
 
-        self.next_id = max_id + 1;
+        let max_id = self.max_id();
+        if self.next_id <= max_id {
+            errors.push(IntegrityError::NextIdInUse {
+                next_id: self.next_id,
+                max_id,
+            });
+        }
+
+        errors
+    }
+
+    /// Fix each inconsistency found by `validate`, removing stale references
+    /// and linking drawings to the sprites which render them. Returns the
+    /// inconsistencies which were fixed.
+    pub fn repair(&mut self) -> Vec<IntegrityError> {
+        let errors = self.validate();
+        for &error in &errors {
+            match error {
+                IntegrityError::DrawingSpriteMissing { drawing, .. }
+                | IntegrityError::DrawingMissing { drawing, .. } => {
+                    self.drawing_sprites.remove(&drawing);
+                }
+                IntegrityError::DrawingUnlinked { drawing, sprite } => {
+                    self.drawing_sprites.insert(drawing, sprite);
+                }
+                IntegrityError::GroupSpriteMissing { group, sprite } => {
+                    if let Some(g) = self.group(group) {
+                        g.remove(sprite);
+                    }
+                }
+                IntegrityError::GroupSubgroupMissing { group, subgroup } => {
+                    if let Some(g) = self.group(group) {
+                        g.remove_group(subgroup);
+                    }
+                }
+                IntegrityError::NextIdInUse { .. } => self.minimise_next_id(),
+            }
+        }
+        errors
     }
 
     pub fn new_with(project: Uuid, layers: Vec<Layer>, drawings: Vec<Drawing>) -> Self {
@@ -121,6 +359,8 @@ impl Scene {
             scene.sprite_drawings.insert(drawing.id, drawing);
         }
 
+        // Links drawings to their sprites and sets the next ID.
+        scene.repair();
         scene.minimise_next_id();
         scene.sort_layers();
 
@@ -143,7 +383,7 @@ impl Scene {
         self.layers.iter_mut().find(|l| l.id == layer)
     }
 
-    fn layer_ref(&self, layer: Id) -> Option<&Layer> {
+    pub fn layer_ref(&self, layer: Id) -> Option<&Layer> {
         self.layers.iter().find(|l| l.id == layer)
     }
 
@@ -170,6 +410,7 @@ impl Scene {
         let removed = self.layers.extract_if(.., |l| l.id == layer).last()?;
         let event = SceneEvent::LayerRemove(removed.id);
         self.removed_layers.push(removed);
+        self.sort_layers();
         Some(event)
     }
 
@@ -178,7 +419,17 @@ impl Scene {
             .removed_layers
             .extract_if(.., |l| l.id == layer)
             .last()?;
-        self.add_layer(l);
+
+        // Layers further from the grid were renumbered towards it when this
+        // layer was removed, so it goes back nearer the grid than any layer
+        // now sharing its z.
+        let index = self
+            .layers
+            .iter()
+            .position(|o| if l.z < 0 { o.z <= l.z } else { o.z < l.z })
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, l);
+        self.sort_layers();
         Some(SceneEvent::LayerRestore(layer))
     }
 
@@ -186,6 +437,30 @@ impl Scene {
         self.layer(layer).map(|l| l.rename(new_name))
     }
 
+    pub fn set_layer_description(
+        &mut self,
+        layer: Id,
+        description: Option<String>,
+    ) -> Option<SceneEvent> {
+        self.layer(layer)?.set_description(description)
+    }
+
+    /// Remove information only editors may see, before sending the scene to
+    /// players.
+    pub fn strip_private(&mut self) {
+        for layer in self.layers.iter_mut().chain(self.removed_layers.iter_mut()) {
+            layer.description = None;
+            for sprite in layer
+                .sprites
+                .iter_mut()
+                .chain(layer.removed_sprites.iter_mut())
+            {
+                sprite.gm_notes = None;
+            }
+        }
+        self.comments.clear();
+    }
+
     // Sort to place the highest layer first. Also updates layer z values to
     // simplify.
     pub fn sort_layers(&mut self) {
@@ -283,31 +558,135 @@ impl Scene {
 
     pub fn remove_group(&mut self, id: Id) -> SceneEvent {
         self.groups.retain(|g| g.id != id);
+        self.groups.iter_mut().for_each(|g| g.remove_group(id));
         SceneEvent::GroupDelete(id)
     }
 
+    /// The outermost group containing a sprite, either directly or within a
+    /// subgroup.
     pub fn sprite_group(&self, id: Id) -> Option<&Group> {
-        self.groups.iter().find(|g| g.includes(id))
+        self.groups
+            .iter()
+            .filter(|g| self.parent_group(g.id).is_none())
+            .find(|g| self.group_contains(g, id, 0))
     }
 
-    pub fn group_sprites(&mut self, sprites: &[Id]) -> Option<SceneEvent> {
-        let mut events = Vec::new();
+    /// All sprites in a group and its subgroups, depth-first.
+    pub fn group_members(&self, id: Id) -> Vec<Id> {
+        let mut members = Vec::new();
+        if let Some(group) = self.groups.iter().find(|g| g.id == id) {
+            self.collect_members(group, &mut members, 0);
+        }
+        members
+    }
+
+    /// Groups are nested at most this deep, to guard against cycles.
+    const MAX_GROUP_DEPTH: usize = 32;
+
+    fn group_contains(&self, group: &Group, sprite: Id, depth: usize) -> bool {
+        group.includes(sprite)
+            || (depth < Self::MAX_GROUP_DEPTH
+                && group
+                    .subgroups()
+                    .iter()
+                    .filter_map(|&id| self.groups.iter().find(|g| g.id == id))
+                    .any(|g| self.group_contains(g, sprite, depth + 1)))
+    }
+
+    fn collect_members(&self, group: &Group, members: &mut Vec<Id>, depth: usize) {
+        members.extend_from_slice(group.sprites());
+        if depth < Self::MAX_GROUP_DEPTH {
+            for &id in group.subgroups() {
+                if let Some(subgroup) = self.groups.iter().find(|g| g.id == id) {
+                    self.collect_members(subgroup, members, depth + 1);
+                }
+            }
+        }
+    }
+
+    /// The group which directly contains a sprite.
+    fn direct_group(&self, sprite: Id) -> Option<Id> {
+        self.groups
+            .iter()
+            .find(|g| g.includes(sprite))
+            .map(|g| g.id)
+    }
 
+    fn parent_group(&self, group: Id) -> Option<Id> {
+        self.groups
+            .iter()
+            .find(|g| g.includes_group(group))
+            .map(|g| g.id)
+    }
+
+    /// Whether `child` may be nested in `parent`, which requires that `child`
+    /// has no parent already and that nesting wouldn't form a cycle.
+    fn can_nest(&self, parent: Id, child: Id) -> bool {
+        let exists = |id| self.groups.iter().any(|g| g.id == id);
+        if parent == child || !exists(parent) || !exists(child) {
+            return false;
+        }
+        if self.parent_group(child).is_some() {
+            return false;
+        }
+
+        let mut ancestor = Some(parent);
+        for _ in 0..Self::MAX_GROUP_DEPTH {
+            match ancestor {
+                Some(id) if id == child => return false,
+                Some(id) => ancestor = self.parent_group(id),
+                None => return true,
+            }
+        }
+        false
+    }
+
+    /// Group the given sprites. Groups whose members are all included are
+    /// nested within the new group, other sprites are moved from their
+    /// groups into the new group.
+    pub fn group_sprites(&mut self, sprites: &[Id]) -> Option<SceneEvent> {
+        let mut nested: Vec<Id> = Vec::new();
         for &sprite in sprites {
-            if let Some(group) = self.sprite_group(sprite) {
-                if let Some(group) = self.group(group.id) {
-                    events.push(group.remove(sprite));
+            if let Some(group) = self.sprite_group(sprite).map(|g| g.id) {
+                if !nested.contains(&group)
+                    && self
+                        .group_members(group)
+                        .iter()
+                        .all(|member| sprites.contains(member))
+                {
+                    nested.push(group);
                 }
             }
         }
 
-        let new_group = Group::new(self.next_id(), sprites.to_owned());
+        let loose: Vec<Id> = sprites
+            .iter()
+            .copied()
+            .filter(|&sprite| {
+                !self
+                    .sprite_group(sprite)
+                    .is_some_and(|g| nested.contains(&g.id))
+            })
+            .collect();
+
+        let mut events = Vec::new();
+        for &sprite in &loose {
+            if let Some(group) = self.direct_group(sprite).and_then(|id| self.group(id)) {
+                events.push(group.remove(sprite));
+            }
+        }
+
+        let mut new_group = Group::new(self.next_id(), loose.clone());
         events.push(SceneEvent::GroupNew(new_group.id));
 
-        for &id in sprites {
+        for &id in &loose {
             events.push(SceneEvent::GroupAdd(new_group.id, id));
         }
 
+        for &group in &nested {
+            events.push(new_group.add_group(group));
+        }
+
         self.groups.push(new_group);
 
         SceneEvent::set(events)
@@ -315,7 +694,7 @@ impl Scene {
 
     /// Iterator across all selectable sprites in the scene, from top to
     /// bottom.
-    fn sprites(&self) -> impl Iterator<Item = &Sprite> {
+    pub fn sprites(&self) -> impl Iterator<Item = &Sprite> {
         // sprites.iter().rev() is because sprites are rendered in vector order
         // so the last sprite will render at the top.
         self.layers
@@ -324,6 +703,30 @@ impl Scene {
             .flat_map(|l| l.sprites.iter().rev())
     }
 
+    /// Pairs of selectable sprites which overlap one another, for instance
+    /// tokens occupying the same cell.
+    pub fn overlapping_sprites(&self) -> Vec<(Id, Id)> {
+        let sprites: Vec<&Sprite> = self.sprites().collect();
+        let mut pairs = Vec::new();
+        for (i, a) in sprites.iter().enumerate() {
+            for b in &sprites[i + 1..] {
+                if a.rect.overlaps(b.rect) {
+                    pairs.push((a.id, b.id));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// The first sprite belonging to `user`, in any layer.
+    pub fn owned_sprite(&self, user: Uuid) -> Option<Id> {
+        self.layers
+            .iter()
+            .flat_map(|l| l.sprites.iter())
+            .find(|s| s.owner == Some(user))
+            .map(|s| s.id)
+    }
+
     fn sprites_mut(&mut self) -> impl Iterator<Item = &mut Sprite> {
         self.layers
             .iter_mut()
@@ -406,6 +809,12 @@ impl Scene {
         self.add_sprite(new, l)
     }
 
+    /// Add a copy of `sprite` to `layer`, with a new ID.
+    pub fn add_sprite_copy(&mut self, mut sprite: Sprite, layer: Id) -> Option<SceneEvent> {
+        sprite.id = self.next_id();
+        self.add_sprite(sprite, layer)
+    }
+
     pub fn new_sprite(&mut self, visual: Option<SpriteVisual>, layer: Id) -> Option<SceneEvent> {
         let id = self.next_id();
         self.add_sprite(Sprite::new(id, visual), layer)
@@ -455,6 +864,8 @@ impl Scene {
     }
 
     pub fn set_sprite_layer(&mut self, sprite: Id, layer: Id) -> Option<SceneEvent> {
+        self.layer_ref(layer)?;
+
         let mut s = None;
         let mut from_id = None;
         for l in &mut self.layers {
@@ -482,6 +893,37 @@ impl Scene {
         )
     }
 
+    /// Move `sprite` from index `from` to index `to` in its layer, swapping it
+    /// with the sprite currently at `to`. Fails if `sprite` isn't at `from`.
+    fn order_sprite(&mut self, sprite: Id, from: usize, to: usize) -> Option<SceneEvent> {
+        let layer = self
+            .layers
+            .iter_mut()
+            .find(|l| l.sprite_ref(sprite).is_some())?;
+
+        if layer.sprite_index(sprite)? == from && layer.swap_sprites(from, to) {
+            Some(SceneEvent::SpriteOrder(sprite, from, to))
+        } else {
+            None
+        }
+    }
+
+    /// Move a sprite one place up in its layer, so that it renders above the
+    /// sprite which was previously in front of it.
+    pub fn bring_forward(&mut self, sprite: Id) -> Option<SceneEvent> {
+        let layer = self.get_sprite_layer(sprite)?;
+        let from = self.layer_ref(layer)?.sprite_index(sprite)?;
+        self.order_sprite(sprite, from, from + 1)
+    }
+
+    /// Move a sprite one place down in its layer, so that it renders beneath
+    /// the sprite which was previously behind it.
+    pub fn send_back(&mut self, sprite: Id) -> Option<SceneEvent> {
+        let layer = self.get_sprite_layer(sprite)?;
+        let from = self.layer_ref(layer)?.sprite_index(sprite)?;
+        self.order_sprite(sprite, from, from.checked_sub(1)?)
+    }
+
     pub fn get_sprite_layer(&self, sprite: Id) -> Option<Id> {
         self.layers
             .iter()
@@ -489,12 +931,110 @@ impl Scene {
             .map(|l| l.id)
     }
 
+    /// Permissions of the layer affected by `event`, if any.
+    pub fn event_layer_perms(&self, event: &SceneEvent) -> Option<&LayerPerms> {
+        self.event_layer(event)
+            .and_then(|l| self.layer_ref(l))
+            .map(|l| &l.perms)
+    }
+
+    /// Reveal fog within the radius of every light source sprite on a visible
+    /// layer, returning an event for the newly revealed tiles. Does nothing if
+    /// fog is inactive.
+    pub fn reveal_lights(&mut self) -> Option<SceneEvent> {
+        if !self.fog.active {
+            return None;
+        }
+
+        let lights: Vec<(Point, f32)> = self
+            .layers
+            .iter()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.sprites.iter())
+            .filter_map(|s| s.visual.light_radius().map(|r| (s.rect.centre(), r)))
+            .collect();
+
+        let events = lights
+            .into_iter()
+            .filter_map(|(at, r)| self.fog.set_circle(at, r, false))
+            .collect();
+        SceneEvent::set(events)
+    }
+
+    /// Player whose token the sprite affected by `event` is, if any.
+    pub fn event_sprite_owner(&self, event: &SceneEvent) -> Option<Uuid> {
+        self.event_sprite(event)
+            .and_then(|id| self.sprite_ref(id))
+            .and_then(|s| s.owner)
+    }
+
+    /// Whether `user` may make `event` in this scene. Each event in an event
+    /// set is checked against the layer and owner of its own sprite, and
+    /// joins against both of the drawings joined.
+    pub fn permitted(&self, perms: &Perms, user: Uuid, event: &SceneEvent) -> bool {
+        if let SceneEvent::EventSet(events) = event {
+            !perms.get_role(user).spectator()
+                && events.iter().all(|e| self.permitted(perms, user, e))
+        } else if let &SceneEvent::SpriteDrawingJoin(_, other)
+        | &SceneEvent::SpriteDrawingUnjoin(_, other) = event
+            && !self.permitted_on_drawing(perms, user, event, other)
+        {
+            false
+        } else {
+            perms.permitted_on(
+                user,
+                event,
+                self.event_sprite(event),
+                self.event_layer(event),
+                self.event_layer_perms(event),
+                self.event_sprite_owner(event),
+                self.locked,
+            )
+        }
+    }
+
+    /// Whether `user` may make `event` on `drawing`. The sprite showing the
+    /// drawing may have been removed by the change, as the sprite of a
+    /// drawing joined onto another is, in which case its layer is that it was
+    /// removed from.
+    fn permitted_on_drawing(
+        &self,
+        perms: &Perms,
+        user: Uuid,
+        event: &SceneEvent,
+        drawing: Id,
+    ) -> bool {
+        let sprite = self.sprite_drawing(drawing);
+        let layer = sprite.and_then(|id| {
+            self.layers.iter().find(|l| {
+                l.sprite_ref(id).is_some() || l.removed_sprites.iter().any(|s| s.id == id)
+            })
+        });
+        perms.permitted_on(
+            user,
+            event,
+            sprite,
+            layer.map(|l| l.id),
+            layer.map(|l| &l.perms),
+            None,
+            self.locked,
+        )
+    }
+
+    /// Sprite affected by `event`. For drawing events, this is the sprite
+    /// showing the drawing.
+    pub fn event_sprite(&self, event: &SceneEvent) -> Option<Id> {
+        event
+            .sprite()
+            .or_else(|| self.sprite_drawing(event.drawing()?))
+    }
+
     pub fn event_layer(&self, event: &SceneEvent) -> Option<Id> {
         let event_layer = event.layer();
         if event_layer.is_some() {
             event_layer
         } else if event.is_sprite() {
-            self.get_sprite_layer(event.sprite()?)
+            self.get_sprite_layer(self.event_sprite(event)?)
         } else {
             None
         }
@@ -536,10 +1076,27 @@ impl Scene {
     }
 
     pub fn add_drawing_point(&mut self, id: Id, point: Point) -> Option<SceneEvent> {
+        self.add_drawing_point_with_pressure(id, point, None)
+    }
+
+    /// Add a point to a drawing, drawn at a pen pressure between 0 and 1 if
+    /// known.
+    pub fn add_drawing_point_with_pressure(
+        &mut self,
+        id: Id,
+        point: Point,
+        pressure: Option<f32>,
+    ) -> Option<SceneEvent> {
         let (event, rect) = if let Some(drawing) = self.sprite_drawings.get_mut(&id) {
-            drawing.add_point(point);
+            let event = if let Some(pressure) = pressure {
+                drawing.add_point_with_pressure(point, pressure);
+                SceneEvent::SpriteDrawingPressure(id, point, pressure)
+            } else {
+                drawing.add_point(point);
+                SceneEvent::SpriteDrawingPoint(id, point)
+            };
 
-            (SceneEvent::SpriteDrawingPoint(id, point), drawing.rect())
+            (event, drawing.rect())
         } else {
             return None;
         };
@@ -556,10 +1113,235 @@ impl Scene {
         Some(event)
     }
 
+    /// Erase the points of a drawing within half of its sprite's stroke of
+    /// `at`, a point in scene coordinates. The drawing sprite is resized to
+    /// fit the remaining points.
+    pub fn erase_drawing_point(&mut self, id: Id, at: Point) -> Option<SceneEvent> {
+        let sprite = self
+            .drawing_sprites
+            .get(&id)
+            .copied()
+            .and_then(|id| self.sprite_ref(id))?;
+        let sprite_id = sprite.id;
+        let stroke = sprite.visual.stroke()?;
+        let position = sprite.rect;
+
+        let drawing = self.sprite_drawings.get_mut(&id)?;
+        let from = drawing.rect();
+        let scale = drawing_scale(position, from);
+
+        // Transform the point and radius into the drawing's coordinates.
+        let point = from.top_left() + (at - position.top_left()) / scale;
+        let radius = stroke / 2.0 / ((scale.x + scale.y) / 2.0);
+        let erased = drawing.erase_near(point, radius);
+        if erased.is_empty() {
+            return None;
+        }
+
+        self.fit_drawing_sprite(sprite_id, id, from);
+        Some(SceneEvent::SpriteDrawingErase(id, erased))
+    }
+
+    /// Remove the `erased` points from drawing `id`, resizing its sprite to
+    /// fit the remaining points.
+    fn erase_drawing(&mut self, id: Id, erased: Vec<ErasedPoint>) -> Option<SceneEvent> {
+        let (sprite, _) = self.drawing_position(id)?;
+        let drawing = self.sprite_drawings.get_mut(&id)?;
+        let from = drawing.rect();
+        if !drawing.erase(&erased) {
+            return None;
+        }
+
+        self.fit_drawing_sprite(sprite, id, from);
+        Some(SceneEvent::SpriteDrawingErase(id, erased))
+    }
+
+    /// Put `erased` points back into drawing `id`, undoing an erase.
+    fn restore_drawing(&mut self, id: Id, erased: Vec<ErasedPoint>) -> Option<SceneEvent> {
+        let (sprite, _) = self.drawing_position(id)?;
+        let drawing = self.sprite_drawings.get_mut(&id)?;
+        let from = drawing.rect();
+        if !drawing.restore(&erased) {
+            return None;
+        }
+
+        self.fit_drawing_sprite(sprite, id, from);
+        Some(SceneEvent::SpriteDrawingRestore(id, erased))
+    }
+
+    /// Join drawing `b` onto the end of freehand drawing `a`, starting from
+    /// whichever end of `b` is closer to the end of `a`. Points are carried
+    /// between the drawings through the positions of their sprites, and the
+    /// sprite of `a` is resized to fit the joined drawing.
+    pub fn join_drawings(&mut self, a: Id, b: Id) -> Option<SceneEvent> {
+        if a == b {
+            return None;
+        }
+
+        let (sprite_a, position_a) = self.drawing_position(a)?;
+        let (_, position_b) = self.drawing_position(b)?;
+        let drawing_b = self.sprite_drawings.get(&b)?;
+        let from_b = drawing_b.rect();
+        let scale_b = drawing_scale(position_b, from_b);
+        let mut points = drawing_b.points_build();
+        let mut pressures = drawing_b.pressures().unwrap_or_default().to_vec();
+
+        let drawing = self.sprite_drawings.get_mut(&a)?;
+        let from = drawing.rect();
+        let scale = drawing_scale(position_a, from);
+        points.map(|p| {
+            let at = position_b.top_left() + (p - from_b.top_left()) * scale_b;
+            from.top_left() + (at - position_a.top_left()) / scale
+        });
+
+        if let (Some(end), Some(first), Some(last)) =
+            (drawing.last_point(), points.first(), points.last())
+            && last.dist(end) < first.dist(end)
+        {
+            points.reverse();
+            pressures.reverse();
+        }
+
+        if !drawing.join(&points, &pressures) {
+            return None;
+        }
+
+        self.fit_drawing_sprite(sprite_a, a, from);
+        Some(SceneEvent::SpriteDrawingJoin(a, b))
+    }
+
+    /// Remove the points of drawing `b` from the end of drawing `a`, undoing
+    /// `join_drawings`.
+    pub fn unjoin_drawings(&mut self, a: Id, b: Id) -> Option<SceneEvent> {
+        let (sprite, _) = self.drawing_position(a)?;
+        let n = self.sprite_drawings.get(&b)?.n_points() as usize;
+        let drawing = self.sprite_drawings.get_mut(&a)?;
+        let from = drawing.rect();
+        if !drawing.remove_last(n) {
+            return None;
+        }
+
+        self.fit_drawing_sprite(sprite, a, from);
+        Some(SceneEvent::SpriteDrawingUnjoin(a, b))
+    }
+
+    /// Start and end of drawing `id`, in scene coordinates.
+    pub fn drawing_ends(&self, id: Id) -> Option<(Point, Point)> {
+        let (_, position) = self.drawing_position(id)?;
+        let drawing = self.sprite_drawings.get(&id)?;
+        let from = drawing.rect();
+        let scale = drawing_scale(position, from);
+        let (start, end) = drawing.line();
+        let to_scene = |p: Point| position.top_left() + (p - from.top_left()) * scale;
+        Some((to_scene(start), to_scene(end)))
+    }
+
+    /// Points of freehand drawing `id`, in scene coordinates.
+    pub fn drawing_points(&self, id: Id) -> Option<PointVector> {
+        let (_, position) = self.drawing_position(id)?;
+        let drawing = self.sprite_drawings.get(&id)?;
+        let from = drawing.rect();
+        let scale = drawing_scale(position, from);
+        let mut points = drawing.points()?.clone();
+        points.map(|p| position.top_left() + (p - from.top_left()) * scale);
+        Some(points)
+    }
+
+    /// ID of the sprite showing drawing `id`.
+    pub fn sprite_drawing(&self, id: Id) -> Option<Id> {
+        self.drawing_sprites.get(&id).copied()
+    }
+
+    /// Sprite showing drawing `id` and its position.
+    fn drawing_position(&self, id: Id) -> Option<(Id, Rect)> {
+        let sprite = self
+            .drawing_sprites
+            .get(&id)
+            .and_then(|&id| self.sprite_ref(id))?;
+        Some((sprite.id, sprite.rect))
+    }
+
+    /// Resize `sprite` to fit drawing `id`, which has changed from covering
+    /// `from`, keeping the scale at which the drawing is shown.
+    fn fit_drawing_sprite(&mut self, sprite: Id, id: Id, from: Rect) {
+        let Some(to) = self.sprite_drawings.get(&id).map(Drawing::rect) else {
+            return;
+        };
+
+        if let Some(sprite) = self.sprite(sprite) {
+            let position = sprite.rect;
+            let scale = drawing_scale(position, from);
+            sprite.rect = Rect::new(
+                position.x + (to.x - from.x) * scale.x,
+                position.y + (to.y - from.y) * scale.y,
+                to.w * scale.x,
+                to.h * scale.y,
+            );
+        }
+    }
+
+    pub fn reverse_drawing(&mut self, id: Id) -> Option<SceneEvent> {
+        self.sprite_drawings.get_mut(&id).map(Drawing::reverse)
+    }
+
+    /// Set the text of a text drawing, placing it at `at`. The drawing's
+    /// sprite is resized to cover the text.
+    pub fn annotate_drawing(&mut self, id: Id, text: String, at: Point) -> Option<SceneEvent> {
+        let drawing = self.sprite_drawings.get_mut(&id)?;
+        if !drawing.annotate(&text, at) {
+            return None;
+        }
+
+        let rect = drawing.rect();
+        let text = drawing.annotation.as_ref()?.text.clone();
+        if let Some(sprite) = self
+            .drawing_sprites
+            .get(&id)
+            .copied()
+            .and_then(|id| self.sprite(id))
+        {
+            sprite.rect = rect;
+        }
+
+        Some(SceneEvent::SpriteDrawingAnnotate(id, text, at))
+    }
+
+    /// Erase points near `at` from every drawing on a selectable layer.
+    pub fn erase_drawings(&mut self, at: Point) -> Option<SceneEvent> {
+        let drawings = self
+            .sprites()
+            .filter_map(|s| match s.visual {
+                SpriteVisual::Drawing {
+                    drawing, stroke, ..
+                } if s.rect.dist_to_point(at) <= stroke / 2.0 => Some(drawing),
+                _ => None,
+            })
+            .collect::<Vec<Id>>();
+
+        SceneEvent::set(
+            drawings
+                .into_iter()
+                .filter_map(|drawing| self.erase_drawing_point(drawing, at))
+                .collect(),
+        )
+    }
+
     pub fn apply_event(&mut self, event: SceneEvent) -> bool {
         match event {
             SceneEvent::Dummy => true,
-            SceneEvent::EventSet(events) => events.into_iter().all(|e| self.apply_event(e)),
+            SceneEvent::EventSet(events) => {
+                // Sets are applied entirely or not at all, so if any event
+                // fails, those already applied are unwound.
+                for (i, event) in events.iter().enumerate() {
+                    if !self.apply_event(event.clone()) {
+                        for applied in events[..i].iter().rev() {
+                            self.unwind_event(applied.clone());
+                        }
+                        return false;
+                    }
+                }
+                true
+            }
             SceneEvent::FogActive(old, new) => {
                 if self.fog.active == old {
                     self.fog.active = new;
@@ -604,6 +1386,24 @@ impl Scene {
                 self.group(group).map(|g| g.remove(sprite));
                 true
             }
+            SceneEvent::GroupNest(parent, child) => {
+                if self.can_nest(parent, child) {
+                    self.group(parent).map(|g| g.add_group(child));
+                    true
+                } else {
+                    false
+                }
+            }
+            SceneEvent::LayerDescription(l, old, new) => {
+                let canon = self.canon;
+                match self.layer(l) {
+                    Some(layer) if layer.description == old || !canon => {
+                        layer.set_description(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
             SceneEvent::LayerLocked(l, locked) => {
                 self.layer(l).map(|l| l.set_locked(locked));
                 true
@@ -636,18 +1436,65 @@ impl Scene {
                 }
                 false
             }
+            SceneEvent::LayerPerms(l, old, new) => {
+                let canon = self.canon;
+                match self.layer(l) {
+                    Some(layer) if layer.perms == old || !canon => {
+                        layer.set_perms(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
             SceneEvent::LayerVisibility(l, visible) => {
                 self.layer(l).map(|l| l.set_visible(visible));
                 true
             }
             SceneEvent::SceneDimensions(old_w, old_h, new_w, new_h) => {
-                if self.w() == old_w && self.h() == old_h {
+                if self.w() == old_w
+                    && self.h() == old_h
+                    && new_w <= Self::MAX_SIZE
+                    && new_h <= Self::MAX_SIZE
+                {
                     self.set_size(new_w, new_h);
                     true
                 } else {
                     false
                 }
             }
+            SceneEvent::SceneBackground(old, new) => {
+                if self.background == old || !self.canon {
+                    self.background = new;
+                    true
+                } else {
+                    false
+                }
+            }
+            SceneEvent::SceneBackgroundTexture(old, new) => {
+                if self.background_texture == old || !self.canon {
+                    self.background_texture = new;
+                    true
+                } else {
+                    false
+                }
+            }
+            SceneEvent::SceneGridColour(old, new) => {
+                if self.grid_colour == old || !self.canon {
+                    self.grid_colour = new;
+                    true
+                } else {
+                    false
+                }
+            }
+            SceneEvent::SceneUnitSystem(old, new) => {
+                if self.unit_system == old || !self.canon {
+                    self.unit_system = new;
+                    true
+                } else {
+                    false
+                }
+            }
+            SceneEvent::SceneLock(locked) => self.set_locked(locked).is_some(),
             SceneEvent::SceneTitle(old, new) => {
                 if self.title == old {
                     self.title = new;
@@ -656,6 +1503,59 @@ impl Scene {
                     false
                 }
             }
+            SceneEvent::SpriteCondition(id, condition, added) => self
+                .sprite(id)
+                .and_then(|s| s.set_condition(condition, added))
+                .is_some(),
+            SceneEvent::SpriteHp(id, old, new) => {
+                let canon = self.canon;
+                match self.sprite(id) {
+                    Some(sprite) if sprite.hp == old || !canon => {
+                        sprite.set_hp(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            SceneEvent::SpriteLabel(id, old, new) => {
+                let canon = self.canon;
+                match self.sprite(id) {
+                    Some(sprite) if sprite.label == old || !canon => {
+                        sprite.set_label(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            SceneEvent::SpriteGmNotes(id, old, new) => {
+                let canon = self.canon;
+                match self.sprite(id) {
+                    Some(sprite) if sprite.gm_notes == old || !canon => {
+                        sprite.set_gm_notes(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            SceneEvent::SpriteOwner(id, old, new) => {
+                let canon = self.canon;
+                match self.sprite(id) {
+                    Some(sprite) if sprite.owner == old || !canon => {
+                        sprite.set_owner(new);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            SceneEvent::SpriteStrokePattern(id, old, new) => {
+                let canon = self.canon;
+                match self.sprite(id) {
+                    Some(sprite) if sprite.visual.pattern() == Some(old) || !canon => {
+                        sprite.set_pattern(new).is_some()
+                    }
+                    _ => false,
+                }
+            }
             SceneEvent::SpriteDrawingStart(id, mode) => {
                 if self.get_drawing(id).is_none() {
                     self.create_drawing(id, mode);
@@ -665,8 +1565,22 @@ impl Scene {
                 }
             }
             SceneEvent::SpriteDrawingPoint(id, at) => self.add_drawing_point(id, at).is_some(),
+            SceneEvent::SpriteDrawingPressure(id, at, pressure) => self
+                .add_drawing_point_with_pressure(id, at, Some(pressure))
+                .is_some(),
+            SceneEvent::SpriteDrawingErase(id, erased) => self.erase_drawing(id, erased).is_some(),
+            SceneEvent::SpriteDrawingRestore(id, erased) => {
+                self.restore_drawing(id, erased).is_some()
+            }
+            SceneEvent::SpriteDrawingReverse(id) => self.reverse_drawing(id).is_some(),
+            SceneEvent::SpriteDrawingJoin(a, b) => self.join_drawings(a, b).is_some(),
+            SceneEvent::SpriteDrawingUnjoin(a, b) => self.unjoin_drawings(a, b).is_some(),
+            SceneEvent::SpriteFlipCaps(id) => self.sprite(id).and_then(Sprite::flip_caps).is_some(),
+            SceneEvent::SpriteDrawingAnnotate(id, text, at) => {
+                self.annotate_drawing(id, text, at).is_some()
+            }
             SceneEvent::SpriteNew(s, l) => {
-                if self.sprite(s.id).is_none() {
+                if self.sprite(s.id).is_none() && s.visual.is_valid() {
                     self.add_sprite(s, l).is_some()
                 } else {
                     false
@@ -694,6 +1608,7 @@ impl Scene {
                     _ => false,
                 }
             }
+            SceneEvent::SpriteOrder(id, from, to) => self.order_sprite(id, from, to).is_some(),
             SceneEvent::SpriteRemove(id, _layer) => {
                 self.remove_sprite(id);
 
@@ -705,13 +1620,33 @@ impl Scene {
             SceneEvent::SpriteRestore(id) => self.restore_sprite(id).is_some(),
             SceneEvent::SpriteVisual(id, old, new) => {
                 if let Some(s) = self.sprite(id) {
-                    if s.visual == old {
+                    if s.visual == old && new.is_valid() {
                         s.set_visual(new);
                         return true;
                     }
                 }
                 false
             }
+            SceneEvent::WaypointAdd(waypoint) => self.add_waypoint(waypoint).is_some(),
+            SceneEvent::WaypointRemove(waypoint) => {
+                self.waypoint(&waypoint.name) == Some(&waypoint)
+                    && self.remove_waypoint(&waypoint.name).is_some()
+            }
+            SceneEvent::CommentAdd(comment) => {
+                match Comment::new(
+                    &comment.author,
+                    &comment.text,
+                    comment.at,
+                    comment.timestamp,
+                ) {
+                    Some(comment) => {
+                        self.add_comment(comment);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            SceneEvent::CommentDelete(comment) => self.remove_comment(&comment).is_some(),
         }
     }
 
@@ -721,6 +1656,7 @@ impl Scene {
             SceneEvent::EventSet(events) => SceneEvent::set(
                 events
                     .into_iter()
+                    .rev()
                     .filter_map(|e| self.unwind_event(e))
                     .collect::<Vec<SceneEvent>>(),
             ),
@@ -735,9 +1671,15 @@ impl Scene {
                 None
             }
             SceneEvent::GroupRemove(group, sprite) => self.group(group).map(|g| g.add(sprite)),
+            SceneEvent::GroupNest(parent, child) => {
+                self.group(parent)?.remove_group(child);
+                None
+            }
+            SceneEvent::LayerDescription(l, old, _) => self.layer(l)?.set_description(old),
             SceneEvent::LayerLocked(l, locked) => self.layer(l)?.set_locked(!locked),
             SceneEvent::LayerMove(l, _, up) => self.move_layer(l, !up),
             SceneEvent::LayerNew(id, _, _) => self.remove_layer(id),
+            SceneEvent::LayerPerms(l, old, _) => self.layer(l)?.set_perms(old),
             SceneEvent::LayerRemove(l) => self.restore_layer(l),
             SceneEvent::LayerRestore(l) => self.remove_layer(l),
             SceneEvent::LayerRename(id, old_title, _) => {
@@ -745,12 +1687,27 @@ impl Scene {
             }
             SceneEvent::LayerVisibility(l, visible) => self.layer(l)?.set_visible(!visible),
             SceneEvent::SceneDimensions(old_w, old_h, new_w, new_h) => {
-                if self.w() == new_w && self.h() == new_h {
+                if self.w() == new_w
+                    && self.h() == new_h
+                    && old_w <= Self::MAX_SIZE
+                    && old_h <= Self::MAX_SIZE
+                {
                     Some(self.set_size(old_w, old_h))
                 } else {
                     None
                 }
             }
+            SceneEvent::SceneBackground(old, _) => self.set_background(old),
+            SceneEvent::SceneBackgroundTexture(old, _) => self.set_background_texture(old),
+            SceneEvent::SceneGridColour(old, _) => self.set_grid_colour(old),
+            SceneEvent::SceneUnitSystem(old, _) => self.set_unit_system(old),
+            SceneEvent::SceneLock(locked) => {
+                if self.locked == locked {
+                    self.set_locked(!locked)
+                } else {
+                    None
+                }
+            }
             SceneEvent::SceneTitle(old, new) => {
                 if self.title == new {
                     self.title = old;
@@ -758,8 +1715,59 @@ impl Scene {
                 }
                 None
             }
+            SceneEvent::SpriteCondition(id, condition, added) => {
+                self.sprite(id)?.set_condition(condition, !added)
+            }
+            SceneEvent::SpriteHp(id, old, new) => {
+                let sprite = self.sprite(id)?;
+                if sprite.hp == new {
+                    sprite.set_hp(old)
+                } else {
+                    None
+                }
+            }
+            SceneEvent::SpriteLabel(id, old, new) => {
+                let sprite = self.sprite(id)?;
+                if sprite.label == new {
+                    sprite.set_label(old)
+                } else {
+                    None
+                }
+            }
+            SceneEvent::SpriteGmNotes(id, old, new) => {
+                let sprite = self.sprite(id)?;
+                if sprite.gm_notes == new {
+                    sprite.set_gm_notes(old)
+                } else {
+                    None
+                }
+            }
+            SceneEvent::SpriteOwner(id, old, new) => {
+                let sprite = self.sprite(id)?;
+                if sprite.owner == new {
+                    sprite.set_owner(old)
+                } else {
+                    None
+                }
+            }
+            SceneEvent::SpriteStrokePattern(id, old, new) => {
+                let sprite = self.sprite(id)?;
+                if sprite.visual.pattern() == Some(new) {
+                    sprite.set_pattern(old)
+                } else {
+                    None
+                }
+            }
             SceneEvent::SpriteDrawingStart(..) => None,
             SceneEvent::SpriteDrawingPoint(..) => None,
+            SceneEvent::SpriteDrawingPressure(..) => None,
+            SceneEvent::SpriteDrawingErase(id, erased) => self.restore_drawing(id, erased),
+            SceneEvent::SpriteDrawingRestore(id, erased) => self.erase_drawing(id, erased),
+            SceneEvent::SpriteDrawingReverse(id) => self.reverse_drawing(id),
+            SceneEvent::SpriteDrawingJoin(a, b) => self.unjoin_drawings(a, b),
+            SceneEvent::SpriteDrawingUnjoin(a, b) => self.join_drawings(a, b),
+            SceneEvent::SpriteFlipCaps(id) => self.sprite(id)?.flip_caps(),
+            SceneEvent::SpriteDrawingAnnotate(..) => None,
             SceneEvent::SpriteNew(s, _) => self.remove_sprite(s.id),
             SceneEvent::SpriteLayer(id, old_layer, new_layer) => {
                 if self.layer_ref(new_layer)?.sprite_ref(id).is_some() {
@@ -768,9 +1776,16 @@ impl Scene {
                     None
                 }
             }
-            SceneEvent::SpriteMove(id, from, to) => {
-                self.sprite(id).map(|s| s.set_rect(s.rect - (to - from)))
-            }
+            SceneEvent::SpriteMove(id, from, to) => self.sprite(id).map(|s| {
+                // If the sprite has since moved, undo this move relative to
+                // its new position.
+                if s.rect == to {
+                    s.set_rect(from)
+                } else {
+                    s.set_rect(s.rect - (to - from))
+                }
+            }),
+            SceneEvent::SpriteOrder(id, from, to) => self.order_sprite(id, to, from),
             SceneEvent::SpriteRemove(id, _layer) => self.restore_sprite(id),
             SceneEvent::SpriteRestore(id) => self.remove_sprite(id),
             SceneEvent::SpriteVisual(id, old, new) => {
@@ -781,6 +1796,10 @@ impl Scene {
                     None
                 }
             }
+            SceneEvent::WaypointAdd(waypoint) => self.remove_waypoint(&waypoint.name),
+            SceneEvent::WaypointRemove(waypoint) => self.add_waypoint(waypoint),
+            SceneEvent::CommentAdd(comment) => self.remove_comment(&comment),
+            SceneEvent::CommentDelete(comment) => Some(self.add_comment(comment)),
         }
     }
 }
@@ -804,6 +1823,26 @@ impl Default for Scene {
             project: uuid::Uuid::nil(),
             fog: Fog::new(Scene::DEFAULT_SIZE, Scene::DEFAULT_SIZE),
             groups: Vec::new(),
+            grid_colour: Self::DEFAULT_GRID_COLOUR,
+            background: Self::DEFAULT_BACKGROUND,
+            background_texture: None,
+            waypoints: Vec::new(),
+            unit_system: UnitSystem::default(),
+            comments: Vec::new(),
+            locked: false,
         }
     }
 }
+
+/// Scale from the coordinates of a drawing covering `from` to those of a
+/// sprite at `position` showing it.
+fn drawing_scale(position: Rect, from: Rect) -> Point {
+    let scale = |sprite: f32, drawing: f32| {
+        if drawing > 0.0 {
+            sprite / drawing
+        } else {
+            1.0
+        }
+    };
+    Point::new(scale(position.w, from.w), scale(position.h, from.h))
+}
@@ -0,0 +1,88 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Colour;
+
+/// A named set of colours offered for quick selection when drawing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ColourPalette {
+    pub name: String,
+    pub colours: Vec<Colour>,
+}
+
+impl ColourPalette {
+    pub const MAX_COLOURS: usize = 16;
+    pub const MAX_NAME_LENGTH: usize = 64;
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            colours: Vec::new(),
+        }
+    }
+
+    /// Add `colour` to the palette, if it isn't already present. Returns
+    /// false if the palette is full.
+    pub fn add(&mut self, colour: Colour) -> bool {
+        if self.colours.contains(&colour) {
+            true
+        } else if self.colours.len() >= Self::MAX_COLOURS {
+            false
+        } else {
+            self.colours.push(colour);
+            true
+        }
+    }
+
+    /// Built-in palette of earth tones.
+    pub fn dungeon() -> Self {
+        const fn rgb(r: u8, g: u8, b: u8) -> Colour {
+            Colour([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0])
+        }
+
+        Self {
+            name: "Dungeon".to_string(),
+            colours: vec![
+                rgb(0x3B, 0x2F, 0x2F),
+                rgb(0x5C, 0x40, 0x33),
+                rgb(0x8B, 0x5A, 0x2B),
+                rgb(0xA0, 0x78, 0x55),
+                rgb(0xC2, 0xB2, 0x80),
+                rgb(0x6B, 0x8E, 0x23),
+                rgb(0x55, 0x6B, 0x2F),
+                rgb(0x70, 0x80, 0x90),
+                rgb(0x4A, 0x4A, 0x4A),
+                rgb(0x8B, 0x00, 0x00),
+                rgb(0xB8, 0x73, 0x33),
+                rgb(0xD2, 0xB4, 0x8C),
+            ],
+        }
+    }
+
+    /// Palettes available to all users.
+    pub fn builtin() -> Vec<Self> {
+        vec![Self::dungeon()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColourPalette;
+    use crate::Colour;
+
+    #[test]
+    fn test_palette_add() {
+        let mut palette = ColourPalette::new(" Mine ");
+        assert_eq!(palette.name, "Mine");
+        assert!(palette.add(Colour::RED));
+        assert!(palette.add(Colour::RED));
+        assert_eq!(palette.colours, vec![Colour::RED]);
+
+        for i in 1..ColourPalette::MAX_COLOURS {
+            assert!(palette.add(Colour([i as f32 / 100.0, 0.0, 0.0, 1.0])));
+        }
+        assert!(!palette.add(Colour::BLUE));
+        assert_eq!(palette.colours.len(), ColourPalette::MAX_COLOURS);
+
+        assert!(ColourPalette::dungeon().colours.len() <= ColourPalette::MAX_COLOURS);
+    }
+}
@@ -21,13 +21,14 @@ enum Perm {
     /// with permissions on a given layer may edit layers.
     LayerEdit,
 
-    /// Moving sprites or changing their visuals. Not adding or removing
-    /// sprites. Editors or players with permissions on a given sprite may edit
+    /// Moving sprites or changing their visuals, including altering the
+    /// drawings they show. Not adding or removing sprites. Editors or players with permissions on a given sprite may edit
     /// sprites.
     SpriteEdit,
 
     /// Creating new drawings, adding points to drawings. Players or better may
-    /// edit the scene's drawings.
+    /// make drawings, but once a drawing is shown by a sprite only those who
+    /// may edit that sprite may add to it.
     DrawingEdit,
 
     /// Changes to sprite groupings in the scene. Creation and deletion of
@@ -47,26 +48,53 @@ impl Perm {
             | SceneEvent::FogOcclude(..)
             | SceneEvent::FogReveal(..)
             | SceneEvent::LayerNew(..)
+            | SceneEvent::LayerDescription(..)
             | SceneEvent::LayerLocked(..)
             | SceneEvent::LayerMove(..)
+            | SceneEvent::LayerPerms(..)
             | SceneEvent::LayerRename(..)
             | SceneEvent::LayerVisibility(..)
             | SceneEvent::LayerRemove(..)
             | SceneEvent::LayerRestore(..)
+            | SceneEvent::SpriteGmNotes(..)
+            | SceneEvent::SpriteOwner(..)
             | SceneEvent::SpriteLayer(..)
+            | SceneEvent::SceneBackground(..)
+            | SceneEvent::SceneBackgroundTexture(..)
             | SceneEvent::SceneDimensions(..)
-            | SceneEvent::SceneTitle(..) => Perm::SceneEdit,
+            | SceneEvent::SceneGridColour(..)
+            | SceneEvent::SceneTitle(..)
+            | SceneEvent::SceneUnitSystem(..)
+            | SceneEvent::SceneLock(..)
+            | SceneEvent::WaypointAdd(..)
+            | SceneEvent::WaypointRemove(..)
+            | SceneEvent::CommentAdd(..)
+            | SceneEvent::CommentDelete(..) => Perm::SceneEdit,
             SceneEvent::SpriteNew(..)
             | SceneEvent::SpriteRemove(..)
             | SceneEvent::SpriteRestore(..) => Perm::LayerEdit,
             SceneEvent::GroupAdd(..)
             | SceneEvent::GroupRemove(..)
+            | SceneEvent::SpriteCondition(..)
+            | SceneEvent::SpriteFlipCaps(..)
+            | SceneEvent::SpriteHp(..)
+            | SceneEvent::SpriteLabel(..)
             | SceneEvent::SpriteMove(..)
-            | SceneEvent::SpriteVisual(..) => Perm::SpriteEdit,
-            SceneEvent::SpriteDrawingStart(..) | SceneEvent::SpriteDrawingPoint(..) => {
-                Perm::DrawingEdit
+            | SceneEvent::SpriteOrder(..)
+            | SceneEvent::SpriteStrokePattern(..)
+            | SceneEvent::SpriteVisual(..)
+            | SceneEvent::SpriteDrawingErase(..)
+            | SceneEvent::SpriteDrawingRestore(..)
+            | SceneEvent::SpriteDrawingReverse(..)
+            | SceneEvent::SpriteDrawingJoin(..)
+            | SceneEvent::SpriteDrawingUnjoin(..)
+            | SceneEvent::SpriteDrawingAnnotate(..) => Perm::SpriteEdit,
+            SceneEvent::SpriteDrawingStart(..)
+            | SceneEvent::SpriteDrawingPoint(..)
+            | SceneEvent::SpriteDrawingPressure(..) => Perm::DrawingEdit,
+            SceneEvent::GroupNew(..) | SceneEvent::GroupDelete(..) | SceneEvent::GroupNest(..) => {
+                Perm::GroupEdit
             }
-            SceneEvent::GroupNew(..) | SceneEvent::GroupDelete(..) => Perm::GroupEdit,
             SceneEvent::Dummy | SceneEvent::EventSet(..) => Perm::Special,
         }
     }
@@ -74,7 +102,7 @@ impl Perm {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Role {
-    /// Cannot interact with sprites or layers.
+    /// Cannot interact with sprites or layers. Observes the scene read-only.
     Spectator = 0,
     /// Can only handle sprites on specific layers.
     Player = 1,
@@ -116,7 +144,33 @@ impl Role {
     }
 }
 
-/// This user is granted certain permissions over a single item.
+/// Permissions granted to players on a single layer, in addition to those
+/// granted by their role and overrides.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LayerPerms {
+    /// Players may move sprites on this layer that they have an override for.
+    pub can_move_own: bool,
+    /// Players may move any sprite on this layer.
+    pub can_move_others: bool,
+    /// Players may add sprites to this layer.
+    pub can_add: bool,
+}
+
+impl LayerPerms {
+    /// Whether event is allowed on this layer for a player. `own` indicates
+    /// that the player has an override over the event's sprite.
+    fn allows(&self, event: &SceneEvent, own: bool) -> bool {
+        match event {
+            SceneEvent::SpriteMove(..) => self.can_move_others || (self.can_move_own && own),
+            SceneEvent::SpriteNew(..) => self.can_add,
+            _ => false,
+        }
+    }
+}
+
+/// This user is granted certain permissions over a single item. An override
+/// over a sprite allows the sprite to be edited, but it may only be moved or
+/// selected on layers which allow players to move their own sprites.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Override {
     user: Uuid,
@@ -170,13 +224,34 @@ impl Perms {
         self.get_role(user).allows(perm)
     }
 
+    /// Sprite whose overrides allow it to be moved, given the permissions of
+    /// its layer. Overrides over the layer itself always apply.
+    fn movable_sprite(sprite: Option<Id>, layer_perms: Option<&LayerPerms>) -> Option<Id> {
+        if layer_perms.is_some_and(|lp| !lp.can_move_own) {
+            None
+        } else {
+            sprite
+        }
+    }
+
+    /// Whether an override allows user to make event. Overrides over a
+    /// sprite only allow it to be moved if the layer allows players to move
+    /// their own sprites, while overrides over the layer always apply.
     fn allowed_by_override(
         &self,
         user: Uuid,
         perm: Perm,
+        event: &SceneEvent,
         sprite: Option<Id>,
         layer: Option<Id>,
+        layer_perms: Option<&LayerPerms>,
     ) -> bool {
+        let sprite = if matches!(event, SceneEvent::SpriteMove(..)) {
+            Self::movable_sprite(sprite, layer_perms)
+        } else {
+            sprite
+        };
+
         self.overrides
             .iter()
             .any(|o| o.allows(user, perm, sprite, layer))
@@ -228,24 +303,156 @@ impl Perms {
         .is_some()
     }
 
-    pub fn selectable(&self, user: Uuid, sprite: Id, layer: Id) -> bool {
-        self.get_role(user).allows(Perm::SpriteEdit)
+    /// Whether user has an override over this specific sprite.
+    fn owns_sprite(&self, user: Uuid, sprite: Option<Id>) -> bool {
+        self.overrides
+            .iter()
+            .any(|o| o.allows(user, Perm::SpriteEdit, sprite, None))
+    }
+
+    fn allowed_by_layer(
+        &self,
+        user: Uuid,
+        event: &SceneEvent,
+        sprite: Option<Id>,
+        layer_perms: Option<&LayerPerms>,
+    ) -> bool {
+        layer_perms.is_some_and(|lp| {
+            self.get_role(user).player() && lp.allows(event, self.owns_sprite(user, sprite))
+        })
+    }
+
+    /// Whether event adds a light or changes one, which only editors may do
+    /// as lights reveal the fog around them.
+    fn changes_lights(event: &SceneEvent) -> bool {
+        match event {
+            SceneEvent::SpriteNew(sprite, _) => sprite.visual.light_radius().is_some(),
+            SceneEvent::SpriteVisual(_, old, new) => {
+                old.light_radius().is_some() || new.light_radius().is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether user may make event as the owner of the event's sprite.
+    /// Players may move and resize their own tokens.
+    fn allowed_by_ownership(&self, user: Uuid, event: &SceneEvent, owner: Option<Uuid>) -> bool {
+        owner == Some(user)
+            && self.get_role(user).player()
+            && matches!(event, SceneEvent::SpriteMove(..))
+    }
+
+    /// Whether user may select sprite, which is on layer. Sprites may be
+    /// selected by those who may move them, so overrides over a sprite only
+    /// make it selectable where the layer allows moving one's own sprites.
+    pub fn selectable(
+        &self,
+        user: Uuid,
+        sprite: Id,
+        layer: Id,
+        layer_perms: Option<&LayerPerms>,
+        owner: Option<Uuid>,
+    ) -> bool {
+        let role = self.get_role(user);
+        let sprite = Self::movable_sprite(Some(sprite), layer_perms);
+        role.allows(Perm::SpriteEdit)
+            || (role.player() && owner == Some(user))
             || self
                 .overrides
                 .iter()
-                .any(|o| o.allows(user, Perm::SpriteEdit, Some(sprite), Some(layer)))
+                .any(|o| o.allows(user, Perm::SpriteEdit, sprite, Some(layer)))
+            || layer_perms.is_some_and(|lp| role.player() && lp.can_move_others)
     }
 
     /// Check if a given event is permitted for this user. The optional layer
     /// parameter should have the ID of the layer that contains the relevant
-    /// sprite for the event, if applicable.
-    pub fn permitted(&self, user: Uuid, event: &SceneEvent, layer: Option<Id>) -> bool {
-        if let SceneEvent::EventSet(events) = event {
-            events.iter().all(|e| self.permitted(user, e, layer))
+    /// sprite for the event, if applicable, and layer_perms the permissions of
+    /// that layer. owner is the player whose token the event's sprite is.
+    /// locked indicates that the scene is locked, in which case nothing may
+    /// change until the owner unlocks it. Event sets aren't permitted, as
+    /// each of their events may affect a different layer and sprite; use
+    /// `Scene::permitted` to check them.
+    pub fn permitted(
+        &self,
+        user: Uuid,
+        event: &SceneEvent,
+        layer: Option<Id>,
+        layer_perms: Option<&LayerPerms>,
+        owner: Option<Uuid>,
+        locked: bool,
+    ) -> bool {
+        self.permitted_on(
+            user,
+            event,
+            event.sprite(),
+            layer,
+            layer_perms,
+            owner,
+            locked,
+        )
+    }
+
+    /// As `permitted`, where sprite is the sprite affected by the event. For
+    /// drawing events, this is the sprite showing the drawing, if any.
+    #[allow(clippy::too_many_arguments)]
+    pub fn permitted_on(
+        &self,
+        user: Uuid,
+        event: &SceneEvent,
+        sprite: Option<Id>,
+        layer: Option<Id>,
+        layer_perms: Option<&LayerPerms>,
+        owner: Option<Uuid>,
+        locked: bool,
+    ) -> bool {
+        let role = self.get_role(user);
+        if role.spectator() {
+            // Spectators may not alter the scene, regardless of overrides.
+            false
+        } else if let SceneEvent::SceneLock(lock) = event {
+            // Editors may lock the scene, but only the owner may unlock it.
+            if *lock {
+                role.editor()
+            } else {
+                role == Role::Owner
+            }
+        } else if locked {
+            false
+        } else if Self::changes_lights(event) {
+            role.editor()
         } else {
-            let perm = Perm::of(event);
+            let perm = match event {
+                SceneEvent::SpriteDrawingPoint(..) | SceneEvent::SpriteDrawingPressure(..)
+                    if sprite.is_some() =>
+                {
+                    Perm::SpriteEdit
+                }
+                _ => Perm::of(event),
+            };
             self.allowed_by_role(user, perm)
-                || self.allowed_by_override(user, perm, event.sprite(), layer)
+                || self.allowed_by_override(user, perm, event, sprite, layer, layer_perms)
+                || self.allowed_by_layer(user, event, sprite, layer_perms)
+                || self.allowed_by_ownership(user, event, owner)
+        }
+    }
+
+    /// Grant a player overrides over any sprites they created in event, so
+    /// that they may move them on layers which allow moving their own sprites
+    /// and continue drawings they started.
+    pub fn grant_new_sprites(&mut self, user: Uuid, event: &SceneEvent) -> Vec<PermsEvent> {
+        if self.get_role(user).editor() {
+            return Vec::new();
+        }
+
+        match event {
+            SceneEvent::SpriteNew(sprite, _) => {
+                self.grant_override(user, sprite.id).into_iter().collect()
+            }
+            SceneEvent::EventSet(events) => events
+                .iter()
+                .flat_map(|e| self.grant_new_sprites(user, e))
+                .collect(),
+            _ => Vec::new(),
         }
     }
 
@@ -301,30 +508,60 @@ mod test {
         assert!(!perms.permitted(
             user,
             &SceneEvent::SpriteNew(Sprite::new(4, None), 3),
-            Some(3)
+            Some(3),
+            None,
+            None,
+            false
         ));
 
         // User should be able to create a sprite in their layer.
         let sprite_event = SceneEvent::SpriteNew(Sprite::new(sprite, None), layer);
-        assert!(perms.permitted(user, &sprite_event, Some(layer)));
+        assert!(perms.permitted(user, &sprite_event, Some(layer), None, None, false));
 
         // User should be able to modify the sprite.
         assert!(perms.permitted(
             user,
             &SceneEvent::SpriteMove(sprite, Rect::new(1., 1., 1., 1.), Rect::new(0., 1., 1., 1.)),
-            Some(layer)
+            Some(layer),
+            None,
+            None,
+            false
         ));
 
         // User to be able to remove this sprite, or any sprite from their
         // layer, but not from other layers. Other users should not by default
         // be permitted to remove sprites from this users layer.
-        assert!(perms.permitted(user, &SceneEvent::SpriteRemove(sprite, layer), Some(layer)));
-        assert!(perms.permitted(user, &SceneEvent::SpriteRemove(5, layer), Some(layer)));
-        assert!(!perms.permitted(user, &SceneEvent::SpriteRemove(6, 7), Some(7)));
+        assert!(perms.permitted(
+            user,
+            &SceneEvent::SpriteRemove(sprite, layer),
+            Some(layer),
+            None,
+            None,
+            false
+        ));
+        assert!(perms.permitted(
+            user,
+            &SceneEvent::SpriteRemove(5, layer),
+            Some(layer),
+            None,
+            None,
+            false
+        ));
+        assert!(!perms.permitted(
+            user,
+            &SceneEvent::SpriteRemove(6, 7),
+            Some(7),
+            None,
+            None,
+            false
+        ));
         assert!(!perms.permitted(
             gen_uuid(),
             &SceneEvent::SpriteRemove(sprite, layer),
-            Some(layer)
+            Some(layer),
+            None,
+            None,
+            false
         ));
     }
 
@@ -340,8 +577,272 @@ mod test {
         assert!(perms.permitted(
             user,
             &SceneEvent::SpriteDrawingPoint(drawing, crate::Point::same(1.)),
-            None
+            None,
+            None,
+            None,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_scene_lock() {
+        let owner = gen_uuid();
+        let editor = gen_uuid();
+        let player = gen_uuid();
+        let layer = 1;
+
+        let mut perms = Perms::new();
+        perms.set_owner(owner);
+        perms.role_change(owner, editor, Role::Editor);
+        perms.role_change(owner, player, Role::Player);
+        perms.grant_override(player, layer);
+
+        // Editors may lock the scene, players may not.
+        let lock = SceneEvent::SceneLock(true);
+        assert!(perms.permitted(editor, &lock, None, None, None, false));
+        assert!(!perms.permitted(player, &lock, None, None, None, false));
+
+        // Nobody may change a locked scene, including the owner.
+        let add = SceneEvent::SpriteNew(Sprite::new(2, None), layer);
+        let title = SceneEvent::SceneTitle("Old".to_string(), "New".to_string());
+        for user in [owner, editor, player] {
+            assert!(!perms.permitted(user, &add, Some(layer), None, None, true));
+            assert!(!perms.permitted(user, &title, None, None, None, true));
+        }
+        assert!(perms.permitted(player, &add, Some(layer), None, None, false));
+
+        // Only the owner may unlock it.
+        let unlock = SceneEvent::SceneLock(false);
+        assert!(perms.permitted(owner, &unlock, None, None, None, true));
+        assert!(!perms.permitted(editor, &unlock, None, None, None, true));
+        assert!(!perms.permitted(
+            owner,
+            &SceneEvent::EventSet(vec![unlock, title]),
+            None,
+            None,
+            None,
+            true
+        ));
+    }
+
+    #[test]
+    fn test_light_handling() {
+        let player = gen_uuid();
+        let sprite = 2;
+        let layer = 3;
+        let light = crate::SpriteVisual::Light {
+            radius: Sprite::DEFAULT_LIGHT_RADIUS,
+            colour: crate::Colour::DEFAULT,
+        };
+
+        let mut perms = Perms::new();
+        perms.role_change(CANONICAL_UPDATER, player, Role::Player);
+        perms.grant_override(player, layer);
+
+        // Players may add sprites to their layer, but not lights.
+        let add = SceneEvent::SpriteNew(Sprite::new(sprite, Some(light.clone())), layer);
+        assert!(!perms.permitted(player, &add, Some(layer), None, None, false));
+        assert!(perms.permitted(CANONICAL_UPDATER, &add, Some(layer), None, None, false));
+
+        // Nor may they change a light, or turn a sprite into one.
+        let mut dimmed = light.clone();
+        if let crate::SpriteVisual::Light { radius, .. } = &mut dimmed {
+            *radius = 1.0;
+        }
+        let shape = Sprite::new(sprite, None).visual;
+        for (old, new) in [(light.clone(), dimmed), (shape.clone(), light)] {
+            let event = SceneEvent::SpriteVisual(sprite, old, new);
+            assert!(!perms.permitted(player, &event, Some(layer), None, None, false));
+            assert!(perms.permitted(CANONICAL_UPDATER, &event, Some(layer), None, None, false));
+        }
+        let recolour = SceneEvent::SpriteVisual(sprite, shape.clone(), shape);
+        assert!(perms.permitted(player, &recolour, Some(layer), None, None, false));
+    }
+
+    #[test]
+    fn test_spectator_handling() {
+        let user = gen_uuid();
+        let layer = 1;
+
+        let mut perms = Perms::new();
+        perms.role_change(CANONICAL_UPDATER, user, Role::Spectator);
+        assert!(perms.get_role(user).spectator());
+        assert!(!perms.get_role(user).player());
+        assert!(!perms.get_role(user).editor());
+
+        // Even with an override, spectators can't alter the scene.
+        perms.grant_override(user, layer);
+        assert!(!perms.permitted(
+            user,
+            &SceneEvent::SpriteNew(Sprite::new(2, None), layer),
+            Some(layer),
+            None,
+            None,
+            false
+        ));
+        assert!(!perms.permitted(
+            user,
+            &SceneEvent::SpriteDrawingPoint(3, crate::Point::same(1.)),
+            None,
+            None,
+            None,
+            false
+        ));
+        assert!(!perms.permitted(user, &SceneEvent::GroupNew(4), None, None, None, false));
+    }
+
+    #[test]
+    fn test_layer_perms() {
+        let player = gen_uuid();
+        let other = gen_uuid();
+        let layer = 1;
+        let own_sprite = 2;
+        let other_sprite = 3;
+        let rect = Rect::new(0., 0., 1., 1.);
+        let moved = Rect::new(1., 1., 1., 1.);
+
+        let mut perms = Perms::new();
+        perms.role_change(CANONICAL_UPDATER, player, Role::Player);
+        perms.role_change(CANONICAL_UPDATER, other, Role::Player);
+        perms.grant_override(player, own_sprite);
+
+        let mut layer_perms = LayerPerms::default();
+        let move_own = SceneEvent::SpriteMove(own_sprite, rect, moved);
+        let move_other = SceneEvent::SpriteMove(other_sprite, rect, moved);
+        let add = SceneEvent::SpriteNew(Sprite::new(4, None), layer);
+
+        // Without layer permissions, an override over a sprite doesn't allow
+        // it to be moved, though it can still be edited.
+        assert!(!perms.permitted(
+            player,
+            &move_own,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+        assert!(perms.permitted(
+            player,
+            &SceneEvent::SpriteLabel(own_sprite, None, Some("Ranger".to_string())),
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+        assert!(!perms.permitted(
+            player,
+            &move_other,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+        assert!(!perms.permitted(player, &add, Some(layer), Some(&layer_perms), None, false));
+
+        // Players may move their own sprites but not others'.
+        layer_perms.can_move_own = true;
+        assert!(perms.permitted(
+            player,
+            &move_own,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+        assert!(!perms.permitted(other, &move_other, Some(layer), None, None, false));
+        assert!(!perms.permitted(
+            other,
+            &move_own,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+
+        // Players may move any sprite.
+        layer_perms.can_move_others = true;
+        assert!(perms.permitted(
+            other,
+            &move_own,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+        assert!(perms.permitted(
+            player,
+            &move_other,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+
+        // Players may add sprites but still not remove them.
+        layer_perms.can_add = true;
+        assert!(perms.permitted(player, &add, Some(layer), Some(&layer_perms), None, false));
+        assert!(!perms.permitted(
+            player,
+            &SceneEvent::SpriteRemove(other_sprite, layer),
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+
+        // Spectators are unaffected by layer permissions.
+        let spectator = gen_uuid();
+        assert!(!perms.permitted(
+            spectator,
+            &move_other,
+            Some(layer),
+            Some(&layer_perms),
+            None,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_sprite_owner() {
+        let player = gen_uuid();
+        let other = gen_uuid();
+        let sprite = 2;
+        let layer = 3;
+        let move_sprite =
+            SceneEvent::SpriteMove(sprite, Rect::new(0., 0., 1., 1.), Rect::new(1., 1., 2., 2.));
+
+        let mut perms = Perms::new();
+        perms.role_change(CANONICAL_UPDATER, player, Role::Player);
+        perms.role_change(CANONICAL_UPDATER, other, Role::Player);
+
+        // Players may move and select only their own tokens.
+        let owner = Some(player);
+        assert!(perms.permitted(player, &move_sprite, Some(layer), None, owner, false));
+        assert!(perms.selectable(player, sprite, layer, None, owner));
+        assert!(!perms.permitted(other, &move_sprite, Some(layer), None, owner, false));
+        assert!(!perms.selectable(other, sprite, layer, None, owner));
+
+        // Owning a token doesn't allow other changes to it.
+        assert!(!perms.permitted(
+            player,
+            &SceneEvent::SpriteRemove(sprite, layer),
+            Some(layer),
+            None,
+            owner,
+            false
+        ));
+
+        // Editors may move any token, and spectators none.
+        assert!(perms.permitted(
+            CANONICAL_UPDATER,
+            &move_sprite,
+            Some(layer),
+            None,
+            owner,
+            false
         ));
+        perms.role_change(CANONICAL_UPDATER, player, Role::Spectator);
+        assert!(!perms.permitted(player, &move_sprite, Some(layer), None, owner, false));
     }
 
     #[test]
@@ -354,7 +855,28 @@ mod test {
         let mut perms = Perms::new();
         perms.set_owner(owner);
         perms.role_change(CANONICAL_UPDATER, player, Role::Player);
-        assert!(perms.selectable(owner, sprite, layer));
-        assert!(!perms.selectable(player, sprite, layer));
+        assert!(perms.selectable(owner, sprite, layer, None, None));
+        assert!(!perms.selectable(player, sprite, layer, None, None));
+
+        // Layer permissions may allow players to select sprites.
+        let layer_perms = LayerPerms {
+            can_move_others: true,
+            ..Default::default()
+        };
+        assert!(perms.selectable(player, sprite, layer, Some(&layer_perms), None));
+
+        // Overrides over a sprite only make it selectable where the layer
+        // allows players to move their own sprites.
+        perms.grant_override(player, sprite);
+        let mut layer_perms = LayerPerms::default();
+        assert!(!perms.selectable(player, sprite, layer, Some(&layer_perms), None));
+        layer_perms.can_move_own = true;
+        assert!(perms.selectable(player, sprite, layer, Some(&layer_perms), None));
+
+        // Overrides over the layer always do.
+        let other = 5;
+        assert!(!perms.selectable(player, other, layer, Some(&LayerPerms::default()), None));
+        perms.grant_override(player, layer);
+        assert!(perms.selectable(player, other, layer, Some(&LayerPerms::default()), None));
     }
 }
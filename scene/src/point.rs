@@ -228,6 +228,19 @@ impl PointVector {
         self.find_limits();
     }
 
+    /// Keep only the points for which `func` returns true.
+    pub fn retain<F: FnMut(Point) -> bool>(&mut self, mut func: F) {
+        let mut data = Vec::with_capacity(self.data.len());
+        self.iter(|p| {
+            if func(p) {
+                data.push(p.x);
+                data.push(p.y);
+            }
+        });
+        self.data = data;
+        self.find_limits();
+    }
+
     pub fn first(&self) -> Option<Point> {
         self.nth(1)
     }
@@ -310,6 +323,11 @@ impl PointVector {
     pub fn translate(&mut self, delta: Point) {
         self.map(|p| p + delta);
     }
+
+    /// Reverse the order of the points. The limits are unchanged.
+    pub fn reverse(&mut self) {
+        self.data = self.data.chunks_exact(2).rev().flatten().copied().collect();
+    }
 }
 
 impl Default for PointVector {
@@ -203,6 +203,22 @@ impl Rect {
         b.x >= a.x && b.x + b.w <= a.x + a.w && b.y >= a.y && b.y + b.h <= a.y + a.h
     }
 
+    pub fn intersects(&self, rect: Rect) -> bool {
+        let a = self.positive_dimensions();
+        let b = rect.positive_dimensions();
+
+        a.x <= b.x + b.w && b.x <= a.x + a.w && a.y <= b.y + b.h && b.y <= a.y + a.h
+    }
+
+    /// Whether this rect and `rect` share some area. Unlike `intersects`,
+    /// rects which only touch along an edge don't overlap.
+    pub fn overlaps(&self, rect: Rect) -> bool {
+        let a = self.positive_dimensions();
+        let b = rect.positive_dimensions();
+
+        a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+    }
+
     pub fn centre(&self) -> Point {
         Point {
             x: self.x + self.w / 2.0,
@@ -210,6 +226,13 @@ impl Rect {
         }
     }
 
+    pub fn bottom_centre(&self) -> Point {
+        Point {
+            x: self.x + self.w / 2.0,
+            y: self.y + self.h,
+        }
+    }
+
     pub fn top_left(&self) -> Point {
         Point {
             x: self.x,
@@ -402,4 +425,17 @@ mod test {
             assert!(rect.containing(point).contains_point(point));
         }
     }
+
+    #[test]
+    fn test_overlaps() {
+        let rect = Rect::new(0., 0., 2., 2.);
+
+        assert!(rect.overlaps(Rect::new(1., 1., 2., 2.)));
+        assert!(rect.overlaps(Rect::new(3., 3., -2., -2.)));
+
+        // Adjacent rects intersect along their shared edge but don't overlap.
+        let adjacent = Rect::new(2., 0., 2., 2.);
+        assert!(rect.intersects(adjacent));
+        assert!(!rect.overlaps(adjacent));
+    }
 }
@@ -15,8 +15,8 @@ fn bincode_serialise(val: impl Serialize) -> Res<Vec<u8>> {
 }
 
 pub fn serialise(project: &Project) -> Res<Vec<u8>> {
-    let data = bincode_serialise(v1::prepare(project)?)?;
-    bincode_serialise(Save { version: 1, data })
+    let data = bincode_serialise(v2::prepare(project)?)?;
+    bincode_serialise(Save { version: 2, data })
 }
 
 fn bincode_deserialise<'a, T: Deserialize<'a>>(data: &'a [u8]) -> Res<T> {
@@ -27,10 +27,11 @@ pub fn deserialise(data: &[u8]) -> Res<crate::Project> {
     let save: Save = bincode_deserialise(data)?;
     match save.version {
         1 => v1::retrieve(&save.data),
+        2 => v2::retrieve(&save.data),
         v => {
-            // Unknown serialisation version. Attempt to load as v1 in case
-            // just the version is wrong.
-            match v1::retrieve(&save.data) {
+            // Unknown serialisation version. Attempt to load as a known
+            // version in case just the version is wrong.
+            match v2::retrieve(&save.data).or_else(|_| v1::retrieve(&save.data)) {
                 Ok(proj) => Ok(proj),
                 Err(_) => Err(format!("Unknown serialisation version: {v}")),
             }
@@ -47,7 +48,14 @@ mod v1 {
     use super::{bincode_deserialise, Res};
     use crate::{Id, PointVector};
 
-    type IdMap = HashMap<Id, u32>;
+    pub(super) type IdMap = HashMap<Id, u32>;
+
+    /// Map from indices in the saved scene to IDs in the retrieved scene.
+    pub(super) struct Indices {
+        pub layers: HashMap<u32, Id>,
+        pub sprites: HashMap<u32, Id>,
+        pub groups: HashMap<u32, Id>,
+    }
 
     pub fn retrieve(data: &[u8]) -> Res<crate::Project> {
         let project: Project = bincode_deserialise(data)?;
@@ -57,20 +65,22 @@ mod v1 {
             scenes: project
                 .scenes
                 .into_iter()
-                .map(|scene| retrieve_scene(scene, project.uuid))
+                .map(|scene| retrieve_scene(scene, project.uuid).0)
                 .collect(),
         })
     }
 
-    fn retrieve_scene(scene: Scene, project: Uuid) -> crate::Scene {
+    pub(super) fn retrieve_scene(scene: Scene, project: Uuid) -> (crate::Scene, Indices) {
         let mut id = 1;
 
         let mut layer_idx_to_layer = HashMap::new();
+        let mut layer_idx_to_id = HashMap::new();
         for (idx, layer) in scene.layers.into_iter().enumerate() {
             let mut new = crate::Layer::new(id, &layer.title, layer.z);
             new.locked = layer.locked;
             new.visible = layer.visible;
             layer_idx_to_layer.insert(idx as u32, new);
+            layer_idx_to_id.insert(idx as u32, id);
             id += 1;
         }
 
@@ -97,6 +107,11 @@ mod v1 {
                     rect: crate::Rect::new(sprite.x, sprite.y, sprite.w, sprite.h),
                     z: sprite.z,
                     visual,
+                    conditions: Vec::new(),
+                    hp: None,
+                    label: None,
+                    gm_notes: None,
+                    owner: None,
                 });
                 sprite_idx_to_id.insert(idx as u32, id);
                 id += 1;
@@ -104,7 +119,8 @@ mod v1 {
         }
 
         let mut groups = Vec::new();
-        for group in scene.groups {
+        let mut group_idx_to_id = HashMap::new();
+        for (idx, group) in scene.groups.into_iter().enumerate() {
             let mut group_sprites = Vec::new();
             for sprite in group.sprites {
                 if let Some(sprite_id) = sprite_idx_to_id.get(&sprite) {
@@ -112,6 +128,7 @@ mod v1 {
                 }
             }
             groups.push(crate::Group::new(id, group_sprites));
+            group_idx_to_id.insert(idx as u32, id);
             id += 1;
         }
 
@@ -121,7 +138,14 @@ mod v1 {
         sc.title = scene.title;
         sc.fog = crate::Fog::from(scene.fog, scene.fog_active, scene.w, scene.h);
         sc.groups = groups;
-        sc
+        (
+            sc,
+            Indices {
+                layers: layer_idx_to_id,
+                sprites: sprite_idx_to_id,
+                groups: group_idx_to_id,
+            },
+        )
     }
 
     fn retrieve_visual(
@@ -143,6 +167,7 @@ mod v1 {
                 stroke: *stroke,
                 solid: *solid,
                 colour: crate::Colour([colour.r, colour.g, colour.b, colour.a]),
+                pattern: crate::StrokePattern::Solid,
             }),
             SpriteVisual::Drawing {
                 drawing,
@@ -158,6 +183,8 @@ mod v1 {
                     stroke: *stroke,
                     cap_start: u8_to_cap(*cap_start),
                     cap_end: u8_to_cap(*cap_end),
+                    taper_start: false,
+                    taper_end: false,
                 }),
         }
     }
@@ -169,17 +196,20 @@ mod v1 {
             scenes: project
                 .scenes
                 .iter()
-                .map(prepare_scene)
+                .map(|scene| prepare_scene(scene).map(|(scene, ..)| scene))
                 .collect::<Res<Vec<Scene>>>()?,
         })
     }
 
-    fn prepare_scene(scene: &crate::Scene) -> Res<Scene> {
+    /// Prepare a scene for saving. Also returns maps from sprite and group IDs
+    /// to indices in the saved scene. Layers are saved in the order they
+    /// appear in the scene.
+    pub(super) fn prepare_scene(scene: &crate::Scene) -> Res<(Scene, IdMap, IdMap)> {
         let (drawings, drawing_ids_to_idxs) = prepare_drawings(scene);
         let (layers, sprites, sprite_ids_to_idxs) =
             prepare_layers_sprites(scene, &drawing_ids_to_idxs);
-        let groups = prepare_groups(scene, &sprite_ids_to_idxs);
-        Ok(Scene {
+        let (groups, group_ids_to_idxs) = prepare_groups(scene, &sprite_ids_to_idxs);
+        let scene = Scene {
             uuid: scene.uuid,
             title: scene.title.clone(),
             w: scene.fog.w,
@@ -190,7 +220,8 @@ mod v1 {
             drawings,
             sprites,
             groups,
-        })
+        };
+        Ok((scene, sprite_ids_to_idxs, group_ids_to_idxs))
     }
 
     fn prepare_drawings(scene: &crate::Scene) -> (Vec<Drawing>, IdMap) {
@@ -233,8 +264,11 @@ mod v1 {
         (layers, sprites, sprite_id_to_idx)
     }
 
-    fn prepare_groups(scene: &crate::Scene, sprites: &IdMap) -> Vec<Group> {
+    /// Groups without sprites are only kept if they have subgroups, which are
+    /// saved as v2 scene properties.
+    fn prepare_groups(scene: &crate::Scene, sprites: &IdMap) -> (Vec<Group>, IdMap) {
         let mut groups = Vec::new();
+        let mut id_to_idx = HashMap::new();
         for group in &scene.groups {
             let mut group_idxs = Vec::new();
             for sprite in group.sprites() {
@@ -242,13 +276,14 @@ mod v1 {
                     group_idxs.push(*idx);
                 }
             }
-            if !group_idxs.is_empty() {
+            if !group_idxs.is_empty() || !group.subgroups().is_empty() {
+                id_to_idx.insert(group.id, groups.len() as u32);
                 groups.push(Group {
                     sprites: group_idxs,
                 });
             }
         }
-        groups
+        (groups, id_to_idx)
     }
 
     fn prepare_sprite(sprite: &crate::Sprite, layer: u32, drawings: &IdMap) -> Option<Sprite> {
@@ -257,11 +292,13 @@ mod v1 {
                 shape: shape_to_u8(shape),
                 media: id,
             },
+            // Stroke patterns are saved as v2 sprite properties.
             crate::SpriteVisual::Shape {
                 shape,
                 stroke,
                 solid,
                 colour,
+                ..
             } => SpriteVisual::Shape {
                 shape: shape_to_u8(shape),
                 stroke,
@@ -274,6 +311,7 @@ mod v1 {
                 stroke,
                 cap_start,
                 cap_end,
+                ..
             } => SpriteVisual::Drawing {
                 drawing: drawings.get(&drawing).copied()?,
                 colour: prepare_colour(&colour),
@@ -281,6 +319,14 @@ mod v1 {
                 cap_start: cap_to_u8(cap_start),
                 cap_end: cap_to_u8(cap_end),
             },
+            // Not representable in v1; saved as a solid ellipse, with the
+            // light itself stored as a v2 sprite property.
+            crate::SpriteVisual::Light { colour, .. } => SpriteVisual::Shape {
+                shape: shape_to_u8(crate::Shape::Ellipse),
+                stroke: crate::Sprite::SOLID_STROKE,
+                solid: true,
+                colour: prepare_colour(&colour),
+            },
         };
 
         Some(Sprite {
@@ -308,6 +354,7 @@ mod v1 {
             crate::DrawingMode::Cone => 1,
             crate::DrawingMode::Freehand => 2,
             crate::DrawingMode::Line => 3,
+            crate::DrawingMode::Text => 4,
         }
     }
 
@@ -316,6 +363,7 @@ mod v1 {
             1 => crate::DrawingMode::Cone,
             2 => crate::DrawingMode::Freehand,
             3 => crate::DrawingMode::Line,
+            4 => crate::DrawingMode::Text,
             _ => crate::DrawingMode::Freehand,
         }
     }
@@ -362,7 +410,7 @@ mod v1 {
     }
 
     #[derive(Serialize, Deserialize)]
-    struct Scene {
+    pub(super) struct Scene {
         uuid: Uuid,
         title: String,
         w: u32,
@@ -435,11 +483,374 @@ mod v1 {
     }
 }
 
+/// Version 2 extends version 1 with lists of properties for the scene and its
+/// layers and sprites. New kinds of property may be added as variants at the
+/// end of the property enums without a version change, as bincode identifies
+/// variants by index. Existing variants must not be reordered or altered.
+mod v2 {
+    use serde_derive::{Deserialize, Serialize};
+    use uuid::Uuid;
+
+    use super::{bincode_deserialise, v1, Res};
+
+    pub fn retrieve(data: &[u8]) -> Res<crate::Project> {
+        let project: Project = bincode_deserialise(data)?;
+        Ok(crate::Project {
+            uuid: project.uuid,
+            title: project.title,
+            scenes: project
+                .scenes
+                .into_iter()
+                .map(|scene| retrieve_scene(scene, project.uuid))
+                .collect(),
+        })
+    }
+
+    fn retrieve_scene(scene: Scene, project: Uuid) -> crate::Scene {
+        let (mut sc, indices) = v1::retrieve_scene(scene.scene, project);
+
+        for property in scene.properties {
+            if let SceneProperty::Subgroup(parent, child) = property {
+                if let (Some(&parent), Some(&child)) =
+                    (indices.groups.get(&parent), indices.groups.get(&child))
+                {
+                    sc.apply_event(crate::comms::SceneEvent::GroupNest(parent, child));
+                }
+            } else {
+                retrieve_scene_property(&mut sc, property);
+            }
+        }
+
+        for (idx, property) in scene.layers {
+            if let Some(layer) = indices.layers.get(&idx).and_then(|id| sc.layer(*id)) {
+                retrieve_layer_property(layer, property);
+            }
+        }
+
+        for (idx, property) in scene.sprites {
+            if let Some(&id) = indices.sprites.get(&idx) {
+                retrieve_sprite_property(&mut sc, id, property);
+            }
+        }
+
+        sc
+    }
+
+    fn retrieve_scene_property(scene: &mut crate::Scene, property: SceneProperty) {
+        match property {
+            SceneProperty::GridColour(colour) => scene.grid_colour = crate::Colour(colour),
+            SceneProperty::Background(colour) => scene.background = crate::Colour(colour),
+            SceneProperty::BackgroundTexture(id) => scene.background_texture = Some(id),
+            SceneProperty::Waypoint(name, viewport, grid_size) => {
+                let viewport = crate::Rect::new(viewport[0], viewport[1], viewport[2], viewport[3]);
+                if let Some(waypoint) = crate::Waypoint::new(&name, viewport, grid_size) {
+                    scene.add_waypoint(waypoint);
+                }
+            }
+            SceneProperty::Subgroup(..) => {}
+            SceneProperty::UnitSystem(name) => {
+                if let Some(unit_system) = crate::UnitSystem::from(&name) {
+                    scene.unit_system = unit_system;
+                }
+            }
+            SceneProperty::Locked => scene.locked = true,
+            SceneProperty::Comment(author, text, at, timestamp) => {
+                let at = at.map(|[x, y]| crate::Point::new(x, y));
+                if let Some(comment) = crate::Comment::new(&author, &text, at, timestamp) {
+                    scene.add_comment(comment);
+                }
+            }
+        }
+    }
+
+    fn retrieve_layer_property(layer: &mut crate::Layer, property: LayerProperty) {
+        match property {
+            LayerProperty::Description(description) => layer.description = Some(description),
+            LayerProperty::Perms {
+                can_move_own,
+                can_move_others,
+                can_add,
+            } => {
+                layer.perms = crate::perms::LayerPerms {
+                    can_move_own,
+                    can_move_others,
+                    can_add,
+                }
+            }
+        }
+    }
+
+    fn retrieve_sprite_property(scene: &mut crate::Scene, id: crate::Id, property: SpriteProperty) {
+        // Annotations and pressures belong to the sprite's drawing rather
+        // than the sprite.
+        fn drawing(scene: &mut crate::Scene, id: crate::Id) -> Option<&mut crate::Drawing> {
+            scene
+                .sprite_ref(id)
+                .and_then(|sprite| sprite.visual.drawing())
+                .and_then(|drawing| scene.sprite_drawings.get_mut(&drawing))
+        }
+
+        match property {
+            SpriteProperty::Annotation(text, [x, y], font_size) => {
+                if let Some(drawing) = drawing(scene, id) {
+                    drawing.annotation = Some(crate::Annotation {
+                        text,
+                        at: crate::Point::new(x, y),
+                        font_size,
+                    });
+                }
+                return;
+            }
+            SpriteProperty::Pressures(pressures) => {
+                if let Some(drawing) = drawing(scene, id) {
+                    drawing.set_pressures(pressures);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(sprite) = scene.sprite(id) else {
+            return;
+        };
+
+        match property {
+            SpriteProperty::Condition(name) => {
+                sprite.set_condition(crate::Condition::from(&name), true);
+            }
+            SpriteProperty::Hp(current, max) => sprite.hp = Some((current, max)),
+            SpriteProperty::Label(label) => sprite.label = Some(label),
+            SpriteProperty::GmNotes(notes) => sprite.gm_notes = Some(notes),
+            SpriteProperty::Owner(owner) => sprite.owner = Some(owner),
+            SpriteProperty::Light(radius, colour) => {
+                sprite.visual = crate::SpriteVisual::Light {
+                    radius,
+                    colour: crate::Colour(colour),
+                };
+            }
+            SpriteProperty::StrokePattern(name, gap) => {
+                sprite.set_pattern(crate::StrokePattern::from(&name, gap));
+            }
+            SpriteProperty::Taper(start, end) => {
+                sprite.set_taper(Some(start), Some(end));
+            }
+            SpriteProperty::Annotation(..) | SpriteProperty::Pressures(..) => {}
+        }
+    }
+
+    pub fn prepare(project: &crate::Project) -> Res<impl serde::Serialize> {
+        Ok(Project {
+            uuid: project.uuid,
+            title: project.title.clone(),
+            scenes: project
+                .scenes
+                .iter()
+                .map(prepare_scene)
+                .collect::<Res<Vec<Scene>>>()?,
+        })
+    }
+
+    fn prepare_scene(scene: &crate::Scene) -> Res<Scene> {
+        let (saved, sprite_ids_to_idxs, group_ids_to_idxs) = v1::prepare_scene(scene)?;
+
+        let mut properties = Vec::new();
+        if scene.grid_colour != crate::Scene::DEFAULT_GRID_COLOUR {
+            properties.push(SceneProperty::GridColour(scene.grid_colour.raw()));
+        }
+        if scene.background != crate::Scene::DEFAULT_BACKGROUND {
+            properties.push(SceneProperty::Background(scene.background.raw()));
+        }
+        if let Some(id) = scene.background_texture {
+            properties.push(SceneProperty::BackgroundTexture(id));
+        }
+        if scene.unit_system != crate::UnitSystem::default() {
+            properties.push(SceneProperty::UnitSystem(
+                scene.unit_system.to_str().to_string(),
+            ));
+        }
+        for waypoint in &scene.waypoints {
+            let crate::Rect { x, y, w, h } = waypoint.viewport;
+            properties.push(SceneProperty::Waypoint(
+                waypoint.name.clone(),
+                [x, y, w, h],
+                waypoint.grid_size,
+            ));
+        }
+        if scene.locked {
+            properties.push(SceneProperty::Locked);
+        }
+        for comment in &scene.comments {
+            properties.push(SceneProperty::Comment(
+                comment.author.clone(),
+                comment.text.clone(),
+                comment.at.map(|at| [at.x, at.y]),
+                comment.timestamp,
+            ));
+        }
+        for group in &scene.groups {
+            for subgroup in group.subgroups() {
+                if let (Some(&parent), Some(&child)) = (
+                    group_ids_to_idxs.get(&group.id),
+                    group_ids_to_idxs.get(subgroup),
+                ) {
+                    properties.push(SceneProperty::Subgroup(parent, child));
+                }
+            }
+        }
+
+        let mut layers = Vec::new();
+        for (idx, layer) in scene.layers.iter().enumerate() {
+            let idx = idx as u32;
+            if layer.perms != crate::perms::LayerPerms::default() {
+                layers.push((
+                    idx,
+                    LayerProperty::Perms {
+                        can_move_own: layer.perms.can_move_own,
+                        can_move_others: layer.perms.can_move_others,
+                        can_add: layer.perms.can_add,
+                    },
+                ));
+            }
+            if let Some(description) = &layer.description {
+                layers.push((idx, LayerProperty::Description(description.clone())));
+            }
+        }
+
+        let mut sprites = Vec::new();
+        for sprite in scene.layers.iter().flat_map(|l| l.sprites.iter()) {
+            if let Some(&idx) = sprite_ids_to_idxs.get(&sprite.id) {
+                for condition in &sprite.conditions {
+                    sprites.push((
+                        idx,
+                        SpriteProperty::Condition(condition.to_str().to_string()),
+                    ));
+                }
+                if let Some((current, max)) = sprite.hp {
+                    sprites.push((idx, SpriteProperty::Hp(current, max)));
+                }
+                if let Some(label) = &sprite.label {
+                    sprites.push((idx, SpriteProperty::Label(label.clone())));
+                }
+                if let Some(notes) = &sprite.gm_notes {
+                    sprites.push((idx, SpriteProperty::GmNotes(notes.clone())));
+                }
+                if let Some(owner) = sprite.owner {
+                    sprites.push((idx, SpriteProperty::Owner(owner)));
+                }
+                if let crate::SpriteVisual::Light { radius, colour } = sprite.visual {
+                    sprites.push((idx, SpriteProperty::Light(radius, colour.raw())));
+                }
+                if let Some(pattern) = sprite.visual.pattern()
+                    && pattern != crate::StrokePattern::Solid
+                {
+                    sprites.push((
+                        idx,
+                        SpriteProperty::StrokePattern(
+                            pattern.to_str().to_string(),
+                            pattern.gap().unwrap_or(crate::StrokePattern::DEFAULT_GAP),
+                        ),
+                    ));
+                }
+                if let crate::SpriteVisual::Drawing {
+                    taper_start,
+                    taper_end,
+                    ..
+                } = sprite.visual
+                    && (taper_start || taper_end)
+                {
+                    sprites.push((idx, SpriteProperty::Taper(taper_start, taper_end)));
+                }
+                if let Some(pressures) = sprite
+                    .visual
+                    .drawing()
+                    .and_then(|drawing| scene.get_drawing(drawing))
+                    .and_then(|drawing| drawing.pressures())
+                {
+                    sprites.push((idx, SpriteProperty::Pressures(pressures.to_vec())));
+                }
+                if let Some(annotation) = sprite
+                    .visual
+                    .drawing()
+                    .and_then(|drawing| scene.get_drawing(drawing))
+                    .and_then(|drawing| drawing.annotation.as_ref())
+                {
+                    sprites.push((
+                        idx,
+                        SpriteProperty::Annotation(
+                            annotation.text.clone(),
+                            [annotation.at.x, annotation.at.y],
+                            annotation.font_size,
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(Scene {
+            scene: saved,
+            properties,
+            layers,
+            sprites,
+        })
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Project {
+        uuid: Uuid,
+        title: String,
+        scenes: Vec<Scene>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Scene {
+        scene: v1::Scene,
+        properties: Vec<SceneProperty>,
+        layers: Vec<(u32, LayerProperty)>, // (index into layers, property)
+        sprites: Vec<(u32, SpriteProperty)>, // (index into sprites, property)
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum SceneProperty {
+        GridColour([f32; 4]),
+        Background([f32; 4]),
+        BackgroundTexture(crate::Id),
+        Waypoint(String, [f32; 4], f32), // (name, viewport, grid_size)
+        Subgroup(u32, u32),              // (parent, child) indices into groups
+        UnitSystem(String),
+        Comment(String, String, Option<[f32; 2]>, u64), // (author, text, at, timestamp)
+        Locked,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum LayerProperty {
+        Perms {
+            can_move_own: bool,
+            can_move_others: bool,
+            can_add: bool,
+        },
+        Description(String),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum SpriteProperty {
+        Condition(String),
+        Hp(i32, i32),
+        Light(f32, [f32; 4]),              // (radius, colour)
+        Annotation(String, [f32; 2], f32), // (text, at, font_size)
+        Label(String),
+        StrokePattern(String, f32), // (name, gap)
+        GmNotes(String),
+        Owner(Uuid),
+        Taper(bool, bool), // (start, end)
+        Pressures(Vec<f32>),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use uuid::{Timestamp, Uuid};
 
-    use super::{bincode_serialise, deserialise, serialise, v1};
+    use super::{bincode_serialise, deserialise, serialise, v1, Save};
 
     fn test_project() -> crate::Project {
         let mut project = crate::Project::new(Uuid::new_v7(Timestamp::now(uuid::NoContext)));
@@ -449,6 +860,25 @@ mod test {
 
         scene.fog.active = true;
         scene.set_size(64, 64);
+        scene.set_grid_colour(crate::Colour([0.1, 0.2, 0.3, 0.4]));
+        scene.set_unit_system(crate::UnitSystem::Meters);
+        scene.set_locked(true);
+        scene.set_background(crate::Colour([0.0, 0.0, 0.0, 1.0]));
+        scene.set_background_texture(Some(0x0123_4567_89AB_CDEF));
+        scene.add_waypoint(
+            crate::Waypoint::new("Town Square", crate::Rect::new(4.0, 4.0, 16.0, 9.0), 60.0)
+                .unwrap(),
+        );
+        scene.add_comment(
+            crate::Comment::new(
+                "Owen",
+                "Move the ambush if they take the river.",
+                Some(crate::Point::new(3.5, 7.0)),
+                1_760_000_000,
+            )
+            .unwrap(),
+        );
+        scene.add_comment(crate::Comment::new("Sam", "Ready for session 4.", None, 0).unwrap());
         scene.fog.reveal(0, 0);
         scene.fog.reveal(10, 5);
         scene.fog.reveal(5, 10);
@@ -463,6 +893,12 @@ mod test {
 
         scene.layer(fg).unwrap().locked = true;
         scene.layer(bg).unwrap().visible = false;
+        scene.layer(fg).unwrap().perms = crate::perms::LayerPerms {
+            can_move_own: true,
+            can_move_others: false,
+            can_add: true,
+        };
+        scene.set_layer_description(fg, Some("Goblins ambush in round 2.".to_string()));
 
         let (drawing, ..) =
             scene.start_drawing(crate::DrawingMode::Freehand, crate::Point::new(12., 12.));
@@ -486,9 +922,19 @@ mod test {
                 stroke: 25.,
                 cap_start: crate::Cap::Arrow,
                 cap_end: crate::Cap::Round,
+                taper_start: false,
+                taper_end: false,
             }),
             bg,
         );
+        if let Some(sprite) = scene.layer(fg).and_then(|l| l.sprites.first_mut()) {
+            sprite.set_condition(crate::Condition::Prone, true);
+            sprite.set_condition(crate::Condition::from("Hasted"), true);
+            sprite.set_hp(Some((7, 12)));
+            sprite.set_label(Some("Goblin Chief".to_string()));
+            sprite.set_gm_notes(Some("Flees at half HP.".to_string()));
+            sprite.set_owner(Some(Uuid::from_u128(7)));
+        }
 
         project.update_scene(scene).expect("Update failed.");
 
@@ -562,9 +1008,126 @@ mod test {
 
     #[test]
     fn test_serialise_deserialise() {
-        let project = test_project();
+        let mut project = test_project();
+
+        // Lights can't be represented in v1, so only check them here.
+        let scene = project.scenes.first_mut().unwrap();
+        let layer = scene.first_layer();
+        scene.new_sprite(
+            Some(crate::SpriteVisual::Light {
+                radius: 4.5,
+                colour: crate::Colour([1.0, 0.8, 0.4, 0.5]),
+            }),
+            layer,
+        );
+
+        // Nor can stroke patterns.
+        let mut visual = crate::SpriteVisual::new_shape(
+            crate::Colour::DEFAULT,
+            crate::Shape::Rectangle,
+            crate::Sprite::DEFAULT_STROKE,
+            false,
+        );
+        if let crate::SpriteVisual::Shape { pattern, .. } = &mut visual {
+            *pattern = crate::StrokePattern::Dashed(0.5);
+        }
+        scene.new_sprite(Some(visual), layer);
+
+        // Or drawing tapers and pressures.
+        if let Some(sprite) = scene
+            .layers
+            .iter_mut()
+            .flat_map(|l| l.sprites.iter_mut())
+            .find(|s| s.visual.drawing().is_some())
+        {
+            sprite.set_taper(Some(true), None);
+        }
+        let drawing = scene.get_drawings()[0].id;
+        let n = scene.get_drawing(drawing).unwrap().n_points() as usize;
+        if let Some(drawing) = scene.sprite_drawings.get_mut(&drawing) {
+            drawing.set_pressures((0..n).map(|i| i as f32 / n as f32).collect());
+        }
+
         let serialised = serialise(&project).unwrap();
         let deserialised = deserialise(&serialised).unwrap();
+
+        // Properties added in v2 should be retained.
+        for (ls, rs) in project.scenes.iter().zip(deserialised.scenes.iter()) {
+            assert_eq!(ls.grid_colour, rs.grid_colour);
+            assert_eq!(ls.background, rs.background);
+            assert_eq!(ls.background_texture, rs.background_texture);
+            assert_eq!(ls.waypoints, rs.waypoints);
+            assert_eq!(ls.comments, rs.comments);
+            assert_eq!(ls.locked, rs.locked);
+            assert_eq!(ls.unit_system, rs.unit_system);
+            for (ll, rl) in ls.layers.iter().zip(rs.layers.iter()) {
+                assert_eq!(ll.perms, rl.perms);
+                assert_eq!(ll.description, rl.description);
+                for (lsp, rsp) in ll.sprites.iter().zip(rl.sprites.iter()) {
+                    assert_eq!(lsp.conditions, rsp.conditions);
+                    assert_eq!(lsp.hp, rsp.hp);
+                    assert_eq!(lsp.label, rsp.label);
+                    assert_eq!(lsp.gm_notes, rsp.gm_notes);
+                    assert_eq!(lsp.owner, rsp.owner);
+                }
+            }
+
+            let pressures = |s: &crate::Scene| {
+                s.get_drawings()
+                    .iter()
+                    .map(|d| d.pressures().map(<[f32]>::to_vec))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(pressures(ls), pressures(rs));
+            assert!(pressures(rs).iter().any(Option::is_some));
+        }
+
+        check_project_equality(project, deserialised);
+    }
+
+    #[test]
+    fn test_serialise_annotation() {
+        let mut project = crate::Project::new(Uuid::new_v7(Timestamp::now(uuid::NoContext)));
+        project.new_scene();
+        let scene = project.scenes.last_mut().unwrap();
+        let layer = scene.first_layer();
+        let (drawing, ..) = scene.start_drawing(crate::DrawingMode::Text, crate::Point::ORIGIN);
+        scene.new_sprite(
+            Some(crate::SpriteVisual::Drawing {
+                drawing,
+                colour: crate::Colour::DEFAULT,
+                stroke: crate::Sprite::DEFAULT_STROKE,
+                cap_start: crate::Cap::None,
+                cap_end: crate::Cap::None,
+                taper_start: false,
+                taper_end: false,
+            }),
+            layer,
+        );
+        scene.annotate_drawing(drawing, "Tavern".to_string(), crate::Point::new(3.0, 4.0));
+
+        let annotation = |project: &crate::Project| {
+            project.scenes[0]
+                .get_drawings()
+                .first()
+                .and_then(|d| d.annotation.clone())
+        };
+
+        let deserialised = deserialise(&serialise(&project).unwrap()).unwrap();
+        assert_eq!(
+            deserialised.scenes[0].get_drawings()[0].mode,
+            crate::DrawingMode::Text
+        );
+        assert!(annotation(&deserialised).is_some());
+        assert_eq!(annotation(&project), annotation(&deserialised));
+    }
+
+    #[test]
+    fn test_deserialise_v1() {
+        let project = test_project();
+        let data = bincode_serialise(v1::prepare(&project).unwrap()).unwrap();
+        let serialised = bincode_serialise(Save { version: 1, data }).unwrap();
+        let deserialised = deserialise(&serialised).unwrap();
         check_project_equality(project, deserialised);
     }
 }
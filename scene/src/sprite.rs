@@ -1,4 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::{comms::SceneEvent, Dimension, Id, Point, Rect};
 use crate::rect::{determine_unit_size, float_eq};
@@ -40,6 +41,15 @@ impl Colour {
         self.0[3] = opacity;
         self
     }
+
+    /// Colour from hue, saturation and value, each between 0 and 1.
+    pub fn hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let channel = |n: f32| {
+            let k = (n + hue.rem_euclid(1.0) * 6.0) % 6.0;
+            value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        Colour([channel(5.0), channel(3.0), channel(1.0), alpha])
+    }
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
@@ -71,6 +81,106 @@ impl Shape {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum Condition {
+    Blinded,
+    Charmed,
+    Deafened,
+    Frightened,
+    Grappled,
+    Incapacitated,
+    Invisible,
+    Paralysed,
+    Petrified,
+    Poisoned,
+    Prone,
+    Restrained,
+    Stunned,
+    Unconscious,
+    Custom(String),
+}
+
+impl Condition {
+    /// All conditions other than `Custom`, in display order.
+    pub const COMMON: [Condition; 14] = [
+        Condition::Blinded,
+        Condition::Charmed,
+        Condition::Deafened,
+        Condition::Frightened,
+        Condition::Grappled,
+        Condition::Incapacitated,
+        Condition::Invisible,
+        Condition::Paralysed,
+        Condition::Petrified,
+        Condition::Poisoned,
+        Condition::Prone,
+        Condition::Restrained,
+        Condition::Stunned,
+        Condition::Unconscious,
+    ];
+
+    pub fn from(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "blinded" => Self::Blinded,
+            "charmed" => Self::Charmed,
+            "deafened" => Self::Deafened,
+            "frightened" => Self::Frightened,
+            "grappled" => Self::Grappled,
+            "incapacitated" => Self::Incapacitated,
+            "invisible" => Self::Invisible,
+            "paralysed" => Self::Paralysed,
+            "petrified" => Self::Petrified,
+            "poisoned" => Self::Poisoned,
+            "prone" => Self::Prone,
+            "restrained" => Self::Restrained,
+            "stunned" => Self::Stunned,
+            "unconscious" => Self::Unconscious,
+            _ => Self::Custom(name.to_string()),
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match &self {
+            Self::Blinded => "blinded",
+            Self::Charmed => "charmed",
+            Self::Deafened => "deafened",
+            Self::Frightened => "frightened",
+            Self::Grappled => "grappled",
+            Self::Incapacitated => "incapacitated",
+            Self::Invisible => "invisible",
+            Self::Paralysed => "paralysed",
+            Self::Petrified => "petrified",
+            Self::Poisoned => "poisoned",
+            Self::Prone => "prone",
+            Self::Restrained => "restrained",
+            Self::Stunned => "stunned",
+            Self::Unconscious => "unconscious",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// Colour of the indicator drawn on sprites with this condition.
+    pub fn colour(&self) -> Colour {
+        Colour(match self {
+            Self::Blinded => [0.2, 0.2, 0.2, 1.0],
+            Self::Charmed => [1.0, 0.4, 0.7, 1.0],
+            Self::Deafened => [0.6, 0.4, 0.2, 1.0],
+            Self::Frightened => [0.5, 0.0, 0.5, 1.0],
+            Self::Grappled => [1.0, 0.5, 0.0, 1.0],
+            Self::Incapacitated => [0.5, 0.5, 0.5, 1.0],
+            Self::Invisible => [0.8, 0.9, 1.0, 1.0],
+            Self::Paralysed => [1.0, 1.0, 0.0, 1.0],
+            Self::Petrified => [0.6, 0.6, 0.5, 1.0],
+            Self::Poisoned => [0.0, 0.7, 0.0, 1.0],
+            Self::Prone => [0.0, 0.5, 1.0, 1.0],
+            Self::Restrained => [0.7, 0.2, 0.0, 1.0],
+            Self::Stunned => [0.0, 1.0, 1.0, 1.0],
+            Self::Unconscious => [1.0, 0.0, 0.0, 1.0],
+            Self::Custom(_) => [1.0, 1.0, 1.0, 1.0],
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Cap {
     Arrow,
@@ -100,6 +210,44 @@ impl Cap {
     }
 }
 
+/// Pattern with which the outline of a hollow shape is drawn.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum StrokePattern {
+    #[default]
+    Solid,
+    /// Dashes separated by gaps of the given length, in tiles.
+    Dashed(f32),
+    Dotted,
+}
+
+impl StrokePattern {
+    pub const DEFAULT_GAP: f32 = 0.25;
+
+    pub fn from(name: &str, gap: f32) -> Self {
+        match name.to_lowercase().as_str() {
+            "dashed" => Self::Dashed(gap),
+            "dotted" => Self::Dotted,
+            _ => Self::Solid,
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match &self {
+            Self::Solid => "solid",
+            Self::Dashed(_) => "dashed",
+            Self::Dotted => "dotted",
+        }
+    }
+
+    pub fn gap(&self) -> Option<f32> {
+        if let Self::Dashed(gap) = self {
+            Some(*gap)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Visual {
     Texture {
@@ -111,6 +259,8 @@ pub enum Visual {
         stroke: f32,
         solid: bool,
         colour: Colour,
+        #[serde(default)]
+        pattern: StrokePattern,
     },
     Drawing {
         drawing: Id,
@@ -118,6 +268,14 @@ pub enum Visual {
         stroke: f32,
         cap_start: Cap,
         cap_end: Cap,
+        #[serde(default)]
+        taper_start: bool,
+        #[serde(default)]
+        taper_end: bool,
+    },
+    Light {
+        radius: f32,
+        colour: Colour,
     },
 }
 
@@ -128,6 +286,7 @@ impl Visual {
             stroke,
             solid: (solid || float_eq(stroke, Sprite::SOLID_STROKE)),
             colour,
+            pattern: StrokePattern::Solid,
         }
     }
 
@@ -141,11 +300,29 @@ impl Visual {
 
     pub fn colour(&self) -> Option<Colour> {
         match self {
-            Self::Shape { colour, .. } | Self::Drawing { colour, .. } => Some(*colour),
+            Self::Shape { colour, .. }
+            | Self::Drawing { colour, .. }
+            | Self::Light { colour, .. } => Some(*colour),
             _ => None,
         }
     }
 
+    /// Radius, in tiles, of the area lit by this visual.
+    pub fn light_radius(&self) -> Option<f32> {
+        if let Self::Light { radius, .. } = self {
+            Some(*radius)
+        } else {
+            None
+        }
+    }
+
+    /// Whether this visual may be added to a scene. Lights must have a finite
+    /// radius of at most `Sprite::MAX_LIGHT_RADIUS` tiles.
+    pub fn is_valid(&self) -> bool {
+        self.light_radius()
+            .is_none_or(|r| (0.0..=Sprite::MAX_LIGHT_RADIUS).contains(&r))
+    }
+
     pub fn texture(&self) -> Option<Id> {
         match self {
             Self::Texture { id, shape: _ } => Some(*id),
@@ -175,6 +352,14 @@ impl Visual {
         }
     }
 
+    pub fn pattern(&self) -> Option<StrokePattern> {
+        if let Self::Shape { pattern, .. } = self {
+            Some(*pattern)
+        } else {
+            None
+        }
+    }
+
     pub fn solid(&self) -> Option<bool> {
         if let Self::Shape { solid, .. } = self {
             Some(*solid)
@@ -198,6 +383,22 @@ impl Visual {
             None
         }
     }
+
+    pub fn taper_start(&self) -> Option<bool> {
+        if let Self::Drawing { taper_start, .. } = self {
+            Some(*taper_start)
+        } else {
+            None
+        }
+    }
+
+    pub fn taper_end(&self) -> Option<bool> {
+        if let Self::Drawing { taper_end, .. } = self {
+            Some(*taper_end)
+        } else {
+            None
+        }
+    }
 }
 
 pub struct Outline {
@@ -220,6 +421,17 @@ pub struct Sprite {
     pub rect: Rect,
     pub z: i32,
     pub visual: Visual,
+    pub conditions: Vec<Condition>,
+    pub hp: Option<(i32, i32)>, // (current, max)
+
+    /// Name shown beneath the sprite.
+    pub label: Option<String>,
+
+    /// Notes for editors, which aren't sent to players.
+    pub gm_notes: Option<String>,
+
+    /// Player whose token this sprite is.
+    pub owner: Option<Uuid>,
 }
 
 impl Sprite {
@@ -228,6 +440,10 @@ impl Sprite {
     pub const SOLID_STROKE: f32 = 0.0;
     pub const DEFAULT_WIDTH: f32 = 1.0;
     pub const DEFAULT_HEIGHT: f32 = 1.0;
+    pub const DEFAULT_LIGHT_RADIUS: f32 = 6.0;
+    pub const MAX_LIGHT_RADIUS: f32 = 64.0;
+    pub const MAX_LABEL_LENGTH: usize = 64;
+    pub const MAX_GM_NOTES_LENGTH: usize = 2048;
 
     // Minimum size of a sprite dimension; too small and sprites can be lost.
     const MIN_SIZE: f32 = 0.25;
@@ -236,6 +452,7 @@ impl Sprite {
         shape: Shape::Rectangle,
         stroke: Self::SOLID_STROKE,
         solid: false,
+        pattern: StrokePattern::Solid,
     };
 
     pub fn new(id: Id, visual: Option<Visual>) -> Self {
@@ -243,6 +460,11 @@ impl Sprite {
             rect: Rect::new(0.0, 0.0, Self::DEFAULT_WIDTH, Self::DEFAULT_HEIGHT),
             z: 1,
             visual: visual.unwrap_or(Self::DEFAULT_VISUAL),
+            conditions: Vec::new(),
+            hp: None,
+            label: None,
+            gm_notes: None,
+            owner: None,
             id,
         }
     }
@@ -267,6 +489,80 @@ impl Sprite {
         SceneEvent::SpriteVisual(self.id, new, self.visual.clone())
     }
 
+    pub fn has_condition(&self, condition: &Condition) -> bool {
+        self.conditions.contains(condition)
+    }
+
+    pub fn set_condition(&mut self, condition: Condition, active: bool) -> Option<SceneEvent> {
+        if self.has_condition(&condition) == active {
+            return None;
+        }
+
+        if active {
+            self.conditions.push(condition.clone());
+        } else {
+            self.conditions.retain(|c| c != &condition);
+        }
+        Some(SceneEvent::SpriteCondition(self.id, condition, active))
+    }
+
+    pub fn set_hp(&mut self, hp: Option<(i32, i32)>) -> Option<SceneEvent> {
+        if self.hp == hp {
+            return None;
+        }
+
+        let old = self.hp;
+        self.hp = hp;
+        Some(SceneEvent::SpriteHp(self.id, old, hp))
+    }
+
+    /// Update the label, trimming whitespace and truncating to
+    /// `MAX_LABEL_LENGTH`. Blank labels are removed.
+    pub fn set_label(&mut self, label: Option<String>) -> Option<SceneEvent> {
+        let label = label
+            .map(|l| {
+                l.trim()
+                    .chars()
+                    .take(Self::MAX_LABEL_LENGTH)
+                    .collect::<String>()
+            })
+            .filter(|l| !l.is_empty());
+        if self.label == label {
+            return None;
+        }
+
+        let old = std::mem::replace(&mut self.label, label.clone());
+        Some(SceneEvent::SpriteLabel(self.id, old, label))
+    }
+
+    /// Update the GM notes, trimming whitespace and truncating to
+    /// `MAX_GM_NOTES_LENGTH`. Blank notes are removed.
+    pub fn set_gm_notes(&mut self, notes: Option<String>) -> Option<SceneEvent> {
+        let notes = notes
+            .map(|n| {
+                n.trim()
+                    .chars()
+                    .take(Self::MAX_GM_NOTES_LENGTH)
+                    .collect::<String>()
+            })
+            .filter(|n| !n.is_empty());
+        if self.gm_notes == notes {
+            return None;
+        }
+
+        let old = std::mem::replace(&mut self.gm_notes, notes.clone());
+        Some(SceneEvent::SpriteGmNotes(self.id, old, notes))
+    }
+
+    pub fn set_owner(&mut self, owner: Option<Uuid>) -> Option<SceneEvent> {
+        if self.owner == owner {
+            return None;
+        }
+
+        let old = std::mem::replace(&mut self.owner, owner);
+        Some(SceneEvent::SpriteOwner(self.id, old, owner))
+    }
+
     pub fn snap_pos(&mut self) -> SceneEvent {
         self.set_rect(self.rect.moved_to(Point::new(
             round_to_nearest(self.rect.x, determine_unit_size(self.rect.w)),
@@ -331,7 +627,9 @@ impl Sprite {
     pub fn set_colour(&mut self, new: Colour) -> Option<SceneEvent> {
         let old = self.visual.clone();
         match &mut self.visual {
-            Visual::Shape { colour, .. } | Visual::Drawing { colour, .. } => {
+            Visual::Shape { colour, .. }
+            | Visual::Drawing { colour, .. }
+            | Visual::Light { colour, .. } => {
                 *colour = new;
                 Some(SceneEvent::SpriteVisual(self.id, old, self.visual.clone()))
             }
@@ -342,12 +640,18 @@ impl Sprite {
     pub fn set_shape(&mut self, new: Shape) -> Option<SceneEvent> {
         let old = self.visual.clone();
         match self.visual.clone() {
-            Visual::Shape { colour, stroke, .. } => {
+            Visual::Shape {
+                colour,
+                stroke,
+                pattern,
+                ..
+            } => {
                 self.visual = Visual::Shape {
                     colour,
                     shape: new,
                     stroke,
                     solid: old.is_solid(),
+                    pattern,
                 };
                 Some(SceneEvent::SpriteVisual(self.id, old, self.visual.clone()))
             }
@@ -381,6 +685,16 @@ impl Sprite {
         }
     }
 
+    pub fn set_pattern(&mut self, new: StrokePattern) -> Option<SceneEvent> {
+        match &mut self.visual {
+            Visual::Shape { pattern, .. } if *pattern != new => {
+                let old = std::mem::replace(pattern, new);
+                Some(SceneEvent::SpriteStrokePattern(self.id, old, new))
+            }
+            _ => None,
+        }
+    }
+
     pub fn set_texture(&mut self, new: Id) -> Option<SceneEvent> {
         if let Visual::Texture { id: _, shape } = self.visual {
             let old = self.visual.clone();
@@ -391,6 +705,18 @@ impl Sprite {
         }
     }
 
+    pub fn set_light_radius(&mut self, new: f32) -> Option<SceneEvent> {
+        let new = new.clamp(0.0, Self::MAX_LIGHT_RADIUS);
+        let old = self.visual.clone();
+        match &mut self.visual {
+            Visual::Light { radius, .. } => {
+                *radius = new;
+                Some(SceneEvent::SpriteVisual(self.id, old, self.visual.clone()))
+            }
+            _ => None,
+        }
+    }
+
     pub fn set_caps(&mut self, start: Option<Cap>, end: Option<Cap>) -> Option<SceneEvent> {
         let before = self.visual.clone();
         if let Visual::Drawing {
@@ -414,11 +740,84 @@ impl Sprite {
         }
     }
 
+    /// Set whether the drawn line narrows towards its start and end.
+    pub fn set_taper(&mut self, start: Option<bool>, end: Option<bool>) -> Option<SceneEvent> {
+        let before = self.visual.clone();
+        if let Visual::Drawing {
+            taper_start,
+            taper_end,
+            ..
+        } = &mut self.visual
+        {
+            if let Some(taper) = start {
+                *taper_start = taper;
+            }
+            if let Some(taper) = end {
+                *taper_end = taper;
+            }
+
+            if self.visual == before {
+                None
+            } else {
+                Some(SceneEvent::SpriteVisual(
+                    self.id,
+                    before,
+                    self.visual.clone(),
+                ))
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Swap the caps at either end of a drawing.
+    pub fn flip_caps(&mut self) -> Option<SceneEvent> {
+        if let Visual::Drawing {
+            cap_start, cap_end, ..
+        } = &mut self.visual
+        {
+            std::mem::swap(cap_start, cap_end);
+            Some(SceneEvent::SpriteFlipCaps(self.id))
+        } else {
+            None
+        }
+    }
+
     pub fn outline(&self) -> Outline {
         Outline::rect(self.rect)
     }
 }
 
+/// A saved sprite configuration, from which new sprites can be created.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpriteTmpl {
+    pub name: String,
+    pub visual: Visual,
+    pub label: Option<String>,
+
+    /// Only the dimensions are used; sprites are created where placed.
+    pub rect: Rect,
+}
+
+impl SpriteTmpl {
+    pub const MAX_NAME_LENGTH: usize = 64;
+
+    /// Template for sprites like `sprite`. None for drawings, as these refer
+    /// to drawings in the sprite's scene.
+    pub fn of(name: &str, sprite: &Sprite) -> Option<Self> {
+        if matches!(sprite.visual, Visual::Drawing { .. }) {
+            return None;
+        }
+
+        Some(Self {
+            name: name.trim().to_string(),
+            visual: sprite.visual.clone(),
+            label: sprite.label.clone(),
+            rect: sprite.rect.positive_dimensions(),
+        })
+    }
+}
+
 fn round_dimension(d: f32) -> f32 {
     let sign = d.signum();
 
@@ -1,6 +1,6 @@
 use uuid::Uuid;
 
-use crate::{Point, Scene, SpriteVisual};
+use crate::{Point, Rect, Scene, SpriteVisual};
 
 #[test]
 fn test_layer_move() {
@@ -35,6 +35,8 @@ fn test_sprite_drawing() {
                 stroke: crate::Sprite::DEFAULT_STROKE,
                 cap_start: crate::Cap::Arrow,
                 cap_end: crate::Cap::Round,
+                taper_start: false,
+                taper_end: false,
             }),
             client.first_layer(),
         )
@@ -45,3 +47,1240 @@ fn test_sprite_drawing() {
     assert!(server.apply_event(event));
     assert!(server.get_drawing(drawing).unwrap().last_point().unwrap() == Point::same(1.0));
 }
+
+#[test]
+fn test_erase_drawing() {
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+
+    let (drawing, event) = client.start_drawing(crate::DrawingMode::Freehand, Point::ORIGIN);
+    assert!(server.apply_event(event.unwrap()));
+    let event = client
+        .new_sprite(
+            Some(SpriteVisual::Drawing {
+                drawing,
+                colour: crate::Colour::DEFAULT,
+                stroke: crate::Sprite::DEFAULT_STROKE,
+                cap_start: crate::Cap::Round,
+                cap_end: crate::Cap::Round,
+                taper_start: false,
+                taper_end: false,
+            }),
+            client.first_layer(),
+        )
+        .unwrap();
+    let sprite = event.item().unwrap();
+    assert!(server.apply_event(event));
+    for i in 1..4 {
+        let event = client.add_drawing_point(drawing, Point::same(i as f32));
+        assert!(server.apply_event(event.unwrap()));
+    }
+
+    // Nothing to erase away from the drawing.
+    assert!(client.erase_drawings(Point::new(3.0, 0.0)).is_none());
+
+    let event = client.erase_drawings(Point::same(1.0)).unwrap();
+    assert!(server.apply_event(event));
+    assert_eq!(server.get_drawing(drawing).unwrap().n_points(), 3);
+
+    // Erasing the end of the drawing shrinks its sprite to fit.
+    let event = client.erase_drawings(Point::same(3.0)).unwrap();
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(server.get_drawing(drawing).unwrap().n_points(), 2);
+    assert_eq!(
+        server.sprite_ref(sprite).unwrap().rect,
+        crate::Rect::new(0.0, 0.0, 2.0, 2.0)
+    );
+
+    // Undoing the erase puts the points back and regrows the sprite.
+    let restore = client.unwind_event(event).unwrap();
+    assert!(server.apply_event(restore.clone()));
+    assert_eq!(server.get_drawing(drawing).unwrap().n_points(), 3);
+    assert_eq!(
+        server.sprite_ref(sprite).unwrap().rect,
+        crate::Rect::new(0.0, 0.0, 3.0, 3.0)
+    );
+    assert_eq!(
+        server.drawing_points(drawing),
+        client.drawing_points(drawing)
+    );
+
+    // And redoing it erases them again.
+    let mut stale = server.non_canon();
+    let erase = client.unwind_event(restore).unwrap();
+    assert!(server.apply_event(erase));
+    assert_eq!(server.get_drawing(drawing).unwrap().n_points(), 2);
+
+    // Erases of points which are no longer in the drawing are rejected.
+    let event = stale.erase_drawings(Point::same(3.0)).unwrap();
+    assert!(!server.apply_event(event));
+}
+
+#[test]
+fn test_sprite_order() {
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+
+    let ids: Vec<crate::Id> = (0..3)
+        .filter_map(|_| match scene.new_sprite(None, layer) {
+            Some(crate::comms::SceneEvent::SpriteNew(s, _)) => Some(s.id),
+            _ => None,
+        })
+        .collect();
+    let order =
+        |s: &Scene| -> Vec<crate::Id> { s.layers[0].sprites.iter().map(|s| s.id).collect() };
+    assert_eq!(order(&scene), ids);
+
+    // Can't move the bottom sprite back or the top sprite forward.
+    assert!(scene.send_back(ids[0]).is_none());
+    assert!(scene.bring_forward(ids[2]).is_none());
+
+    let event = scene.bring_forward(ids[0]).unwrap();
+    assert_eq!(order(&scene), vec![ids[1], ids[0], ids[2]]);
+
+    // Order should be applied identically on another scene.
+    let mut other = scene.clone();
+    other.unwind_event(event.clone());
+    assert_eq!(order(&other), ids);
+    assert!(other.apply_event(event.clone()));
+    assert_eq!(order(&other), order(&scene));
+    assert!(!other.apply_event(event.clone()));
+
+    scene.unwind_event(event);
+    assert_eq!(order(&scene), ids);
+
+    scene.send_back(ids[2]).unwrap();
+    assert_eq!(order(&scene), vec![ids[0], ids[2], ids[1]]);
+}
+
+#[test]
+fn test_sprites_in_view() {
+    use crate::{comms::SceneEvent, Rect};
+
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+    let ids: Vec<crate::Id> = [
+        Rect::new(1.0, 1.0, 2.0, 2.0),   // Inside view.
+        Rect::new(-1.0, 4.0, 2.0, 2.0),  // Overlapping edge of view.
+        Rect::new(10.0, 10.0, 1.0, 1.0), // Outside view.
+    ]
+    .into_iter()
+    .filter_map(|at| match scene.new_sprite_at(None, layer, at) {
+        Some(SceneEvent::SpriteNew(s, _)) => Some(s.id),
+        _ => None,
+    })
+    .collect();
+
+    let view = Rect::new(0.0, 0.0, 5.0, 5.0);
+    let visible: Vec<crate::Id> = scene.layers[0]
+        .sprites_in_view(view)
+        .map(|s| s.id)
+        .collect();
+    assert_eq!(visible, ids[..2]);
+}
+
+#[test]
+fn test_overlapping_sprites() {
+    use crate::{comms::SceneEvent, Rect};
+
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+    let ids: Vec<crate::Id> = [
+        Rect::new(0.0, 0.0, 1.0, 1.0),
+        Rect::new(0.5, 0.5, 1.0, 1.0), // Overlapping the first.
+        Rect::new(1.5, 0.0, 1.0, 1.0), // Touching the second.
+    ]
+    .into_iter()
+    .filter_map(|at| match scene.new_sprite_at(None, layer, at) {
+        Some(SceneEvent::SpriteNew(s, _)) => Some(s.id),
+        _ => None,
+    })
+    .collect();
+
+    // Sprites are iterated from top to bottom.
+    assert_eq!(scene.overlapping_sprites(), vec![(ids[1], ids[0])]);
+
+    // Sprites on locked layers can't be selected, so aren't considered.
+    scene.layer(layer).unwrap().set_locked(true);
+    assert!(scene.overlapping_sprites().is_empty());
+}
+
+#[test]
+fn test_message_encoding() {
+    use crate::comms::{decode_message, encode_message, ServerEvent};
+
+    // Small messages are sent uncompressed.
+    let data = encode_message(&ServerEvent::HealthCheck).unwrap();
+    assert!(matches!(
+        decode_message(&data).unwrap(),
+        ServerEvent::HealthCheck
+    ));
+
+    // Large messages are deflated.
+    let scene = Scene::new(Uuid::nil());
+    let uncompressed = bincode::serialize(&ServerEvent::SceneChange(Box::new(scene.clone())));
+    let data = encode_message(&ServerEvent::SceneChange(Box::new(scene))).unwrap();
+    assert!(data.len() < uncompressed.unwrap().len());
+    assert!(matches!(
+        decode_message(&data).unwrap(),
+        ServerEvent::SceneChange(_)
+    ));
+
+    assert!(decode_message::<ServerEvent>(&[]).is_err());
+    assert!(decode_message::<ServerEvent>(&[2, 0, 0]).is_err());
+}
+
+#[test]
+fn test_media_key_id() {
+    let uuid = Uuid::from_u128(0x0123456789ABCDEF_FEDCBA9876543210);
+    let id = crate::media_key_id(&uuid.simple().to_string());
+    assert_eq!(id, 0x0123456789ABCDEF);
+
+    // Texture IDs are loaded by their 16 digit key, which maps back.
+    assert_eq!(crate::media_key_id(&format!("{id:016X}")), id);
+
+    assert_eq!(crate::media_key_id("0123456789abcdeg"), 0);
+    assert_eq!(crate::media_key_id(&uuid.hyphenated().to_string()), 0);
+}
+
+#[test]
+fn test_reveal_lights() {
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+    scene.new_sprite_at(
+        Some(SpriteVisual::Light {
+            radius: 2.0,
+            colour: crate::Colour::DEFAULT,
+        }),
+        layer,
+        crate::Rect::new(5.0, 5.0, 1.0, 1.0),
+    );
+
+    // Lights don't reveal anything while fog is inactive.
+    assert!(scene.reveal_lights().is_none());
+
+    scene.fog.active = true;
+    assert!(scene.reveal_lights().is_some());
+    assert!(!scene.fog.occluded(5, 5));
+    assert!(!scene.fog.occluded(3, 5));
+    assert!(scene.fog.occluded(2, 5));
+    assert!(scene.fog.occluded(8, 8));
+
+    // Nothing further to reveal until the light moves.
+    assert!(scene.reveal_lights().is_none());
+
+    // Lights with radii which aren't finite or are too large are rejected.
+    let light = |radius| {
+        crate::comms::SceneEvent::SpriteNew(
+            crate::Sprite::new(
+                100,
+                Some(SpriteVisual::Light {
+                    radius,
+                    colour: crate::Colour::DEFAULT,
+                }),
+            ),
+            layer,
+        )
+    };
+    assert!(!scene.apply_event(light(f32::NAN)));
+    assert!(!scene.apply_event(light(f32::INFINITY)));
+    assert!(!scene.apply_event(light(crate::Sprite::MAX_LIGHT_RADIUS + 1.0)));
+    assert!(scene.apply_event(light(crate::Sprite::MAX_LIGHT_RADIUS)));
+
+    // However large a circle is, only tiles on the map are revealed.
+    assert!(scene
+        .fog
+        .set_circle(Point::new(5.0, 5.0), 1e9, false)
+        .is_some());
+    assert_eq!(scene.fog.n_revealed, scene.fog.w * scene.fog.h);
+}
+
+#[test]
+fn test_waypoints() {
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+
+    let waypoint = crate::Waypoint::new(
+        "  Dungeon Entrance ",
+        crate::Rect::new(0.0, 0.0, 8.0, 6.0),
+        50.0,
+    )
+    .unwrap();
+    assert_eq!(waypoint.name, "Dungeon Entrance");
+    assert!(crate::Waypoint::new(" ", crate::Rect::new(0.0, 0.0, 1.0, 1.0), 50.0).is_none());
+
+    let event = client.add_waypoint(waypoint.clone()).unwrap();
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(server.waypoint("Dungeon Entrance"), Some(&waypoint));
+
+    // Names are unique.
+    assert!(client.add_waypoint(waypoint).is_none());
+    assert!(!server.apply_event(event.clone()));
+
+    server.unwind_event(event);
+    assert!(server.waypoints.is_empty());
+
+    let event = client.remove_waypoint("Dungeon Entrance").unwrap();
+    assert!(client.waypoints.is_empty());
+    assert!(!server.apply_event(event.clone()));
+
+    // Removals carry the waypoint, so they can be undone.
+    assert!(client.unwind_event(event.clone()).is_some());
+    assert_eq!(
+        client.waypoint("Dungeon Entrance").map(|w| w.viewport),
+        Some(crate::Rect::new(0.0, 0.0, 8.0, 6.0))
+    );
+
+    // A different waypoint with the same name isn't removed.
+    let moved = crate::Waypoint::new(
+        "Dungeon Entrance",
+        crate::Rect::new(2.0, 2.0, 8.0, 6.0),
+        50.0,
+    );
+    server.add_waypoint(moved.unwrap());
+    assert!(!server.apply_event(event));
+}
+
+#[test]
+fn test_comments() {
+    use crate::comms::SceneEvent;
+
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+
+    let comment = crate::Comment::new(
+        "GM",
+        "  The statue is a mimic. ",
+        Some(crate::Point::new(2.0, 3.0)),
+        1_700_000_000,
+    )
+    .unwrap();
+    assert_eq!(comment.text, "The statue is a mimic.");
+    assert!(crate::Comment::new("GM", " ", None, 0).is_none());
+
+    let event = client.add_comment(comment.clone());
+    assert!(event.is_private());
+    assert!(event.clone().public().is_none());
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(server.comments, vec![comment.clone()]);
+
+    // Players never see comments.
+    let mut player_scene = server.clone();
+    player_scene.strip_private();
+    assert!(player_scene.comments.is_empty());
+
+    server.unwind_event(event.clone());
+    assert!(server.comments.is_empty());
+    assert!(server.apply_event(event));
+
+    // Comments are normalised when applied, so blank comments are refused.
+    let mut blank = comment.clone();
+    blank.text = " ".to_string();
+    assert!(!server.apply_event(SceneEvent::CommentAdd(blank)));
+    let mut long = comment.clone();
+    long.text = "a".repeat(crate::Comment::MAX_TEXT_LENGTH * 2);
+    assert!(server.apply_event(SceneEvent::CommentAdd(long)));
+    assert_eq!(
+        server.comments[1].text.len(),
+        crate::Comment::MAX_TEXT_LENGTH
+    );
+
+    // Deletes refer to the comment rather than its position, so they are
+    // unaffected by other changes to the list and may be unwound.
+    let other = crate::Comment::new("GM", "Trapdoor", None, 0).unwrap();
+    client.add_comment(other.clone());
+    let event = client.remove_comment(&comment).unwrap();
+    assert!(event.is_private());
+    assert_eq!(client.comments, vec![other.clone()]);
+    assert!(client.remove_comment(&comment).is_none());
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(server.comments.len(), 1);
+    assert!(!server.apply_event(event.clone()));
+    assert!(client.unwind_event(event).is_some());
+    assert_eq!(client.comments, vec![other, comment]);
+}
+
+#[test]
+fn test_group_nesting() {
+    use crate::comms::SceneEvent;
+
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+    let ids: Vec<crate::Id> = (0..3)
+        .filter_map(|_| match scene.new_sprite(None, layer) {
+            Some(SceneEvent::SpriteNew(s, _)) => Some(s.id),
+            _ => None,
+        })
+        .collect();
+    let mut server = scene.clone();
+
+    let event = scene.group_sprites(&ids[..2]).unwrap();
+    assert!(server.apply_event(event));
+    let inner = scene.sprite_group(ids[0]).unwrap().id;
+
+    // Grouping a whole group with another sprite nests the group.
+    let event = scene.group_sprites(&ids).unwrap();
+    assert!(server.apply_event(event.clone()));
+    let outer = scene.sprite_group(ids[0]).unwrap().id;
+    assert_ne!(inner, outer);
+    assert_eq!(scene.sprite_group(ids[2]).unwrap().id, outer);
+    assert_eq!(server.sprite_group(ids[1]).unwrap().id, outer);
+    assert_eq!(scene.group_members(outer), vec![ids[2], ids[0], ids[1]]);
+    assert_eq!(scene.group_members(inner), ids[..2]);
+
+    // Groups can't contain themselves or their ancestors, or have two parents.
+    assert!(!server.apply_event(SceneEvent::GroupNest(inner, inner)));
+    assert!(!server.apply_event(SceneEvent::GroupNest(inner, outer)));
+    assert!(!server.apply_event(SceneEvent::GroupNest(outer, inner)));
+
+    // Nesting survives saving and loading.
+    let project = crate::Project {
+        uuid: Uuid::nil(),
+        title: String::new(),
+        scenes: vec![scene.clone()],
+    };
+    let loaded = crate::serde::deserialise(&crate::serde::serialise(&project).unwrap()).unwrap();
+    let loaded = &loaded.scenes[0];
+    let sprite = loaded.layers[0].sprites[0].id;
+    let root = loaded.sprite_group(sprite).unwrap();
+    assert_eq!(root.subgroups().len(), 1);
+    assert_eq!(loaded.group_members(root.id).len(), 3);
+
+    server.unwind_event(event);
+    assert_eq!(server.sprite_group(ids[0]).unwrap().id, inner);
+    assert!(server.sprite_group(ids[2]).is_none());
+
+    // Removing the inner group leaves the outer group's direct members.
+    scene.remove_group(inner);
+    assert_eq!(scene.group_members(outer), vec![ids[2]]);
+    assert!(scene.sprite_group(ids[0]).is_none());
+}
+
+#[test]
+fn test_layer_description() {
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+    let layer = client.first_layer();
+
+    let event = client
+        .set_layer_description(layer, Some("  Reinforcements arrive round 3. ".to_string()))
+        .unwrap();
+    assert!(event.is_private());
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(
+        server.layer_ref(layer).unwrap().description.as_deref(),
+        Some("Reinforcements arrive round 3.")
+    );
+    assert!(client
+        .set_layer_description(layer, Some("Reinforcements arrive round 3.".to_string()))
+        .is_none());
+
+    // Private events are removed from sets sent to players.
+    let rename = client.rename_layer(layer, "Enemies".to_string()).unwrap();
+    let set = crate::comms::SceneEvent::EventSet(vec![event.clone(), rename]);
+    assert!(matches!(
+        set.public(),
+        Some(crate::comms::SceneEvent::LayerRename(..))
+    ));
+    assert!(event.clone().public().is_none());
+
+    let mut player_scene = server.clone();
+    player_scene.strip_private();
+    assert!(player_scene.layer_ref(layer).unwrap().description.is_none());
+
+    server.unwind_event(event);
+    assert!(server.layer_ref(layer).unwrap().description.is_none());
+}
+
+#[test]
+fn test_reverse_drawing() {
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+
+    let (drawing, event) = client.start_drawing(crate::DrawingMode::Freehand, Point::ORIGIN);
+    assert!(server.apply_event(event.unwrap()));
+    let event = client.add_drawing_point(drawing, Point::same(1.0)).unwrap();
+    assert!(server.apply_event(event));
+    let event = client
+        .new_sprite(
+            Some(SpriteVisual::Drawing {
+                drawing,
+                colour: crate::Colour::DEFAULT,
+                stroke: crate::Sprite::DEFAULT_STROKE,
+                cap_start: crate::Cap::Round,
+                cap_end: crate::Cap::Arrow,
+                taper_start: false,
+                taper_end: false,
+            }),
+            client.first_layer(),
+        )
+        .unwrap();
+    let sprite = event.item().unwrap();
+    assert!(server.apply_event(event));
+
+    let event = client.reverse_drawing(drawing).unwrap();
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(
+        server.get_drawing(drawing).unwrap().line(),
+        (Point::same(1.0), Point::ORIGIN)
+    );
+    server.unwind_event(event);
+    assert_eq!(
+        server.get_drawing(drawing).unwrap().line(),
+        (Point::ORIGIN, Point::same(1.0))
+    );
+
+    let event = client.sprite(sprite).unwrap().flip_caps().unwrap();
+    assert!(server.apply_event(event.clone()));
+    let caps = |s: &Scene| {
+        let visual = &s.sprite_ref(sprite).unwrap().visual;
+        (visual.cap_start(), visual.cap_end())
+    };
+    assert_eq!(
+        caps(&server),
+        (Some(crate::Cap::Arrow), Some(crate::Cap::Round))
+    );
+    server.unwind_event(event);
+    assert_eq!(
+        caps(&server),
+        (Some(crate::Cap::Round), Some(crate::Cap::Arrow))
+    );
+
+    // Only drawings have caps to flip.
+    let event = client.new_sprite(None, client.first_layer()).unwrap();
+    let shape = event.item().unwrap();
+    assert!(client.sprite(shape).unwrap().flip_caps().is_none());
+}
+
+#[test]
+fn test_join_drawings() {
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+
+    let mut drawing = |scene: &mut Scene, points: &[Point]| {
+        let (drawing, event) = scene.start_drawing(crate::DrawingMode::Freehand, points[0]);
+        assert!(server.apply_event(event.unwrap()));
+        for &point in &points[1..] {
+            let event = scene.add_drawing_point(drawing, point).unwrap();
+            assert!(server.apply_event(event));
+        }
+        let event = scene
+            .new_sprite(
+                Some(SpriteVisual::Drawing {
+                    drawing,
+                    colour: crate::Colour::DEFAULT,
+                    stroke: crate::Sprite::DEFAULT_STROKE,
+                    cap_start: crate::Cap::None,
+                    cap_end: crate::Cap::None,
+                    taper_start: false,
+                    taper_end: false,
+                }),
+                scene.first_layer(),
+            )
+            .unwrap();
+        let sprite = event.item().unwrap();
+        assert!(server.apply_event(event));
+        let rect = scene.get_drawing(drawing).unwrap().rect();
+        let event = scene.sprite(sprite).unwrap().set_rect(rect);
+        assert!(server.apply_event(event));
+        (drawing, sprite)
+    };
+
+    // The second drawing ends near where the first ends, so is joined on
+    // backwards.
+    let (a, sprite) = drawing(&mut client, &[Point::ORIGIN, Point::new(1.0, 0.0)]);
+    let (b, _) = drawing(&mut client, &[Point::new(2.0, 1.0), Point::new(1.5, 0.0)]);
+    let event = client.join_drawings(a, b).unwrap();
+    assert!(server.apply_event(event.clone()));
+    for scene in [&client, &server] {
+        assert_eq!(
+            scene.get_drawing(a).unwrap().points().unwrap().data,
+            vec![0.0, 0.0, 1.0, 0.0, 1.5, 0.0, 2.0, 1.0]
+        );
+        assert_eq!(
+            scene.sprite_ref(sprite).unwrap().rect,
+            Rect::new(0.0, 0.0, 2.0, 1.0)
+        );
+    }
+
+    // Unwinding splits the drawing back to its original points.
+    let event = server.unwind_event(event).unwrap();
+    assert_eq!(server.get_drawing(a).unwrap().n_points(), 2);
+    assert_eq!(
+        server.sprite_ref(sprite).unwrap().rect,
+        Rect::new(0.0, 0.0, 1.0, 0.0)
+    );
+    assert!(server.unwind_event(event).is_some());
+    assert_eq!(server.get_drawing(a).unwrap().n_points(), 4);
+
+    // Drawings can't be joined onto themselves.
+    assert!(client.join_drawings(a, a).is_none());
+}
+
+#[test]
+fn test_sprite_gm_notes() {
+    use crate::comms::SceneEvent;
+
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+    let layer = client.first_layer();
+
+    let event = client.new_sprite(None, layer).unwrap();
+    let sprite = event.item().unwrap();
+    assert!(!event.is_private());
+    assert!(server.apply_event(event));
+
+    let event = client
+        .sprite(sprite)
+        .unwrap()
+        .set_gm_notes(Some(" Secretly a dragon. ".to_string()))
+        .unwrap();
+    assert!(event.is_private());
+    assert!(event.clone().public().is_none());
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(
+        server.sprite_ref(sprite).unwrap().gm_notes.as_deref(),
+        Some("Secretly a dragon.")
+    );
+
+    // Players receive new sprites without their notes.
+    let copy = server.sprite_ref(sprite).unwrap().clone();
+    let new = server.add_sprite_copy(copy, layer).unwrap();
+    assert!(new.is_private());
+    match new.public() {
+        Some(SceneEvent::SpriteNew(sprite, _)) => assert!(sprite.gm_notes.is_none()),
+        _ => panic!("Expected SpriteNew."),
+    }
+
+    let mut player_scene = server.clone();
+    player_scene.strip_private();
+    assert!(player_scene.sprite_ref(sprite).unwrap().gm_notes.is_none());
+
+    server.unwind_event(event);
+    assert!(server.sprite_ref(sprite).unwrap().gm_notes.is_none());
+}
+
+#[test]
+fn test_owned_sprite() {
+    use crate::{comms::SceneEvent, Id};
+
+    let user = Uuid::from_u128(1);
+    let mut server = Scene::new(Uuid::nil());
+    let mut client = server.non_canon();
+    let layer = client.first_layer();
+
+    let event = client.new_sprite(None, layer).unwrap();
+    let sprite = event.item().unwrap();
+    assert!(server.apply_event(event));
+    assert!(server.owned_sprite(user).is_none());
+
+    let event = client
+        .sprite(sprite)
+        .unwrap()
+        .set_owner(Some(user))
+        .unwrap();
+    assert!(server.apply_event(event.clone()));
+    assert_eq!(server.owned_sprite(user), Some(sprite));
+    assert!(server.owned_sprite(Uuid::nil()).is_none());
+    assert!(client
+        .sprite(sprite)
+        .unwrap()
+        .set_owner(Some(user))
+        .is_none());
+
+    // Moving several of a player's tokens together is a move of their own,
+    // but each token moved must be theirs.
+    let mut perms = crate::perms::Perms::new();
+    perms.role_change(
+        crate::perms::CANONICAL_UPDATER,
+        user,
+        crate::perms::Role::Player,
+    );
+    let other = server.new_sprite(None, layer).unwrap().item().unwrap();
+    let moves = |sprites: &[Id]| {
+        SceneEvent::EventSet(
+            sprites
+                .iter()
+                .map(|&id| {
+                    SceneEvent::SpriteMove(id, Rect::new(0., 0., 1., 1.), Rect::new(1., 1., 1., 1.))
+                })
+                .collect(),
+        )
+    };
+    assert!(server.permitted(&perms, user, &moves(&[sprite])));
+    assert!(!server.permitted(&perms, user, &moves(&[sprite, other])));
+    server.sprite(other).unwrap().set_owner(Some(user));
+    assert!(server.permitted(&perms, user, &moves(&[sprite, other])));
+    server.sprite(other).unwrap().set_owner(None);
+
+    server.unwind_event(event);
+    assert!(server.owned_sprite(user).is_none());
+}
+
+#[test]
+fn test_event_set_permitted() {
+    use crate::{
+        comms::SceneEvent,
+        perms::{Perms, Role, CANONICAL_UPDATER},
+        Id,
+    };
+
+    let player = Uuid::from_u128(1);
+    let mut perms = Perms::new();
+    perms.role_change(CANONICAL_UPDATER, player, Role::Player);
+
+    let mut scene = Scene::new(Uuid::nil());
+    let open = scene.first_layer();
+    let closed = scene.first_background_layer();
+    scene.layer(open).unwrap().perms.can_move_others = true;
+    let a = scene.new_sprite(None, open).unwrap().item().unwrap();
+    let b = scene.new_sprite(None, open).unwrap().item().unwrap();
+    let c = scene.new_sprite(None, closed).unwrap().item().unwrap();
+
+    let moves = |sprites: &[Id]| {
+        SceneEvent::EventSet(
+            sprites
+                .iter()
+                .map(|&id| {
+                    SceneEvent::SpriteMove(id, Rect::new(0., 0., 1., 1.), Rect::new(1., 1., 1., 1.))
+                })
+                .collect(),
+        )
+    };
+
+    // Each move is checked against the permissions of its sprite's layer.
+    assert!(scene.permitted(&perms, player, &moves(&[a, b])));
+    assert!(!scene.permitted(&perms, player, &moves(&[a, c])));
+    assert!(scene.permitted(&perms, CANONICAL_UPDATER, &moves(&[a, c])));
+
+    perms.role_change(CANONICAL_UPDATER, player, Role::Spectator);
+    assert!(!scene.permitted(&perms, player, &moves(&[a, b])));
+    assert!(!scene.permitted(&perms, player, &moves(&[])));
+}
+
+#[test]
+fn test_drawing_permitted() {
+    use crate::{
+        comms::SceneEvent,
+        perms::{Perms, Role, CANONICAL_UPDATER},
+        Id,
+    };
+
+    let player = Uuid::from_u128(1);
+    let mut perms = Perms::new();
+    perms.role_change(CANONICAL_UPDATER, player, Role::Player);
+
+    let mut scene = Scene::new(Uuid::nil());
+    let gm_layer = scene.first_layer();
+    let player_layer = scene.first_background_layer();
+    perms.grant_override(player, player_layer);
+
+    let draw = |scene: &mut Scene, layer: Id| {
+        let (drawing, _) = scene.start_drawing(crate::DrawingMode::Freehand, Point::ORIGIN);
+        scene.add_drawing_point(drawing, Point::new(2.0, 2.0));
+        let visual = SpriteVisual::Drawing {
+            drawing,
+            colour: crate::Colour::DEFAULT,
+            stroke: crate::Sprite::DEFAULT_STROKE,
+            cap_start: crate::Cap::Round,
+            cap_end: crate::Cap::Round,
+            taper_start: false,
+            taper_end: false,
+        };
+        scene.new_sprite(Some(visual), layer);
+        drawing
+    };
+    let gm_drawing = draw(&mut scene, gm_layer);
+    let own_drawing = draw(&mut scene, player_layer);
+
+    // Players may only alter drawings which they own.
+    let point = |drawing| SceneEvent::SpriteDrawingPoint(drawing, Point::new(3.0, 3.0));
+    let edits = |drawing| {
+        [
+            point(drawing),
+            SceneEvent::SpriteDrawingErase(drawing, Vec::new()),
+            SceneEvent::SpriteDrawingRestore(drawing, Vec::new()),
+            SceneEvent::SpriteDrawingReverse(drawing),
+            SceneEvent::SpriteDrawingAnnotate(drawing, "Lair".to_string(), Point::ORIGIN),
+        ]
+    };
+    for event in edits(gm_drawing) {
+        assert!(!scene.permitted(&perms, player, &event));
+        assert!(scene.permitted(&perms, CANONICAL_UPDATER, &event));
+    }
+    for event in edits(own_drawing) {
+        assert!(scene.permitted(&perms, player, &event));
+    }
+
+    // Joins require ownership of both drawings, including that joined, whose
+    // sprite is removed by the join.
+    let other_drawing = draw(&mut scene, player_layer);
+    for (a, b) in [(own_drawing, gm_drawing), (gm_drawing, own_drawing)] {
+        for event in [
+            SceneEvent::SpriteDrawingJoin(a, b),
+            SceneEvent::SpriteDrawingUnjoin(a, b),
+        ] {
+            assert!(!scene.permitted(&perms, player, &event));
+            assert!(scene.permitted(&perms, CANONICAL_UPDATER, &event));
+        }
+    }
+    let join = SceneEvent::SpriteDrawingJoin(own_drawing, other_drawing);
+    assert!(scene.permitted(&perms, player, &join));
+    scene.remove_sprite(scene.sprite_drawing(other_drawing).unwrap());
+    assert!(scene.permitted(&perms, player, &join));
+
+    // Drawings which aren't shown by a sprite yet are being made.
+    let (drawing, _) = scene.start_drawing(crate::DrawingMode::Freehand, Point::ORIGIN);
+    assert!(scene.permitted(&perms, player, &point(drawing)));
+}
+
+#[test]
+fn test_integrity() {
+    use crate::{comms::SceneEvent, IntegrityError};
+
+    let mut scene = Scene::new(Uuid::nil());
+    let layer = scene.first_layer();
+    let (drawing, _) = scene.start_drawing(crate::DrawingMode::Freehand, Point::ORIGIN);
+    let visual = SpriteVisual::Drawing {
+        drawing,
+        colour: crate::Colour::DEFAULT,
+        stroke: crate::Sprite::DEFAULT_STROKE,
+        cap_start: crate::Cap::Round,
+        cap_end: crate::Cap::Round,
+        taper_start: false,
+        taper_end: false,
+    };
+    let Some(SceneEvent::SpriteNew(sprite, _)) = scene.new_sprite(Some(visual), layer) else {
+        panic!("failed to create sprite");
+    };
+    scene.group_sprites(&[sprite.id]);
+    let group = scene.sprite_group(sprite.id).unwrap().id;
+    assert!(scene.validate().is_empty());
+
+    // Removed sprites may be restored, so references to them remain valid.
+    scene.remove_sprite(sprite.id);
+    assert!(scene.validate().is_empty());
+
+    scene.drawing_sprites.insert(100, sprite.id);
+    scene.drawing_sprites.insert(drawing, 101);
+    scene.groups[0].add(102);
+    scene.groups[0].add_group(103);
+    scene.next_id = 2;
+    let errors = scene.validate();
+    for error in [
+        IntegrityError::DrawingMissing {
+            drawing: 100,
+            sprite: sprite.id,
+        },
+        IntegrityError::DrawingSpriteMissing {
+            drawing,
+            sprite: 101,
+        },
+        IntegrityError::DrawingUnlinked {
+            drawing,
+            sprite: sprite.id,
+        },
+        IntegrityError::GroupSpriteMissing { group, sprite: 102 },
+        IntegrityError::GroupSubgroupMissing {
+            group,
+            subgroup: 103,
+        },
+    ] {
+        assert!(errors.contains(&error), "{error:?} not in {errors:?}");
+    }
+    assert!(matches!(
+        errors.last(),
+        Some(IntegrityError::NextIdInUse { next_id: 2, .. })
+    ));
+
+    assert_eq!(scene.repair(), errors);
+    assert!(scene.validate().is_empty());
+    assert_eq!(scene.groups[0].sprites(), [sprite.id]);
+    assert!(scene.groups[0].subgroups().is_empty());
+    assert_eq!(scene.drawing_sprites.get(&drawing), Some(&sprite.id));
+    assert!(scene.next_id > group);
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    use crate::comms::SceneEvent;
+
+    // Inputs found by fuzzing which caused a panic when decoded and applied.
+    let inputs = [SceneEvent::SceneDimensions(
+        Scene::DEFAULT_SIZE,
+        Scene::DEFAULT_SIZE,
+        u32::MAX,
+        u32::MAX,
+    )];
+
+    for input in inputs {
+        let data = bincode::serialize(&input).unwrap();
+        let event: SceneEvent = bincode::deserialize(&data).unwrap();
+        let mut scene = Scene::default();
+        assert!(!scene.apply_event(event.clone()));
+        assert!(scene.unwind_event(event).is_none());
+        assert_eq!(
+            (scene.w(), scene.h()),
+            (Scene::DEFAULT_SIZE, Scene::DEFAULT_SIZE)
+        );
+    }
+
+    // Nor can a scene be restored to oversized dimensions.
+    let mut scene = Scene::default();
+    let event = SceneEvent::SceneDimensions(u32::MAX, 1, Scene::DEFAULT_SIZE, Scene::DEFAULT_SIZE);
+    assert!(scene.unwind_event(event).is_none());
+}
+
+mod symmetry {
+    use std::collections::BTreeMap;
+
+    use proptest::prelude::*;
+    use uuid::Uuid;
+
+    use crate::{
+        comms::SceneEvent, perms::LayerPerms, Colour, Comment, Condition, Id, Rect, Scene, Shape,
+        Sprite, SpriteVisual, StrokePattern, UnitSystem, Waypoint,
+    };
+
+    const FOREGROUND: Id = 1;
+    const SCENERY: Id = 2;
+    const LAYERS: [Id; 3] = [FOREGROUND, SCENERY, 3];
+    const GROUP: Id = 10;
+
+    /// Id of the first sprite created in `base`.
+    const FIRST_SPRITE: Id = 4;
+
+    /// Cells in the top left of the scene which are revealed in `base`.
+    const REVEALED: u32 = 4;
+
+    /// A canonical scene with some sprites, a group, revealed fog, a waypoint
+    /// and a comment, for events to refer to.
+    fn base() -> Scene {
+        let mut scene = Scene::new(Uuid::nil());
+        scene.canon();
+
+        for (layer, z) in [(FOREGROUND, 1), (FOREGROUND, 2), (SCENERY, 1)] {
+            let id = scene.next_id();
+            let mut sprite = Sprite::new(id, None);
+            sprite.rect = Rect::new(id as f32, 1.0, 1.0, 1.0);
+            sprite.z = z;
+            scene.add_sprite(sprite, layer);
+        }
+        let sprite = scene.sprite(FIRST_SPRITE).unwrap();
+        sprite.set_hp(Some((5, 10)));
+        sprite.set_label(Some("Goblin".to_string()));
+        sprite.set_condition(Condition::Prone, true);
+
+        scene.new_group(Some(GROUP), Some(vec![FIRST_SPRITE]));
+        scene
+            .fog
+            .reveal_rect(Rect::new(0.0, 0.0, REVEALED as f32, REVEALED as f32));
+        scene.add_waypoint(Waypoint::new("Camp", Rect::new(0.0, 0.0, 8.0, 8.0), 50.0).unwrap());
+        scene.add_comment(Comment::new("GM", "Ambush here", None, 0).unwrap());
+        scene
+    }
+
+    /// State of a scene, for comparison. Removed layers and sprites, kept so
+    /// that their removal can be undone, are omitted, as are layer z bounds,
+    /// which only ever widen.
+    fn snapshot(scene: &Scene) -> String {
+        let layers: Vec<_> = scene
+            .layers
+            .iter()
+            .map(|l| {
+                (
+                    l.id,
+                    &l.title,
+                    &l.description,
+                    l.z,
+                    l.visible,
+                    l.locked,
+                    l.perms,
+                    &l.sprites,
+                )
+            })
+            .collect();
+        let drawings: BTreeMap<_, _> = scene.sprite_drawings.iter().collect();
+        let drawing_sprites: BTreeMap<_, _> = scene.drawing_sprites.iter().collect();
+        let groups: Vec<_> = scene
+            .groups
+            .iter()
+            .map(|g| (g.id, g.sprites(), g.subgroups()))
+            .collect();
+        let fog = &scene.fog;
+        format!(
+            "{:?}",
+            (
+                layers,
+                drawings,
+                drawing_sprites,
+                (fog.active, fog.w, fog.h, fog.n_revealed, fog.data()),
+                &scene.title,
+                groups,
+                (
+                    scene.grid_colour,
+                    scene.background,
+                    scene.background_texture
+                ),
+                &scene.waypoints,
+                scene.unit_system,
+                &scene.comments,
+                scene.locked,
+            )
+        )
+    }
+
+    fn colour() -> impl Strategy<Value = Colour> {
+        [0.0..=1.0f32, 0.0..=1.0f32, 0.0..=1.0f32, 0.0..=1.0f32].prop_map(Colour)
+    }
+
+    fn rect() -> impl Strategy<Value = Rect> {
+        (-64.0..64.0f32, -64.0..64.0f32, -8.0..8.0f32, -8.0..8.0f32)
+            .prop_map(|(x, y, w, h)| Rect::new(x, y, w, h))
+    }
+
+    /// Patterns other than the default.
+    fn pattern() -> impl Strategy<Value = StrokePattern> {
+        prop_oneof![
+            (0.1..1.0f32).prop_map(StrokePattern::Dashed),
+            Just(StrokePattern::Dotted),
+        ]
+    }
+
+    /// Text as it would be after trimming by the scene.
+    fn text() -> impl Strategy<Value = String> {
+        "[A-Za-z][A-Za-z ]{0,14}[A-Za-z]"
+    }
+
+    fn layer() -> impl Strategy<Value = Id> {
+        prop::sample::select(&LAYERS[..])
+    }
+
+    fn sprite() -> impl Strategy<Value = Id> {
+        FIRST_SPRITE..FIRST_SPRITE + 3
+    }
+
+    fn visual() -> impl Strategy<Value = SpriteVisual> {
+        prop_oneof![
+            (colour(), 0.0..1.0f32).prop_map(|(colour, stroke)| SpriteVisual::Shape {
+                colour,
+                shape: Shape::Ellipse,
+                stroke,
+                solid: true,
+                pattern: StrokePattern::Dotted,
+            }),
+            (0i64..100).prop_map(|id| SpriteVisual::Texture {
+                id,
+                shape: Shape::Rectangle,
+            }),
+            (colour(), 1.0..10.0f32)
+                .prop_map(|(colour, radius)| SpriteVisual::Light { colour, radius }),
+        ]
+    }
+
+    /// A single event which is valid to apply to `base`. Events which can't
+    /// be undone, namely drawing updates, group deletion and waypoint or
+    /// comment removal, are excluded.
+    fn event() -> impl Strategy<Value = SceneEvent> {
+        let base = base();
+        let fog = prop_oneof![
+            any::<bool>().prop_map(|active| SceneEvent::FogActive(false, active)),
+            (0..REVEALED, 0..REVEALED).prop_map(|(x, y)| SceneEvent::FogOcclude(false, x, y)),
+            (REVEALED..Scene::DEFAULT_SIZE, 0..Scene::DEFAULT_SIZE)
+                .prop_map(|(x, y)| SceneEvent::FogReveal(true, x, y)),
+        ];
+        let groups = prop_oneof![
+            (100i64..200).prop_map(SceneEvent::GroupNew),
+            (FIRST_SPRITE + 1..FIRST_SPRITE + 3).prop_map(|s| SceneEvent::GroupAdd(GROUP, s)),
+            Just(SceneEvent::GroupRemove(GROUP, FIRST_SPRITE)),
+        ];
+        let titles: Vec<String> = base.layers.iter().map(|l| l.title.clone()).collect();
+        let zs: Vec<i32> = base.layers.iter().map(|l| l.z).collect();
+        let layers = prop_oneof![
+            (layer(), text()).prop_map(|(l, d)| SceneEvent::LayerDescription(l, None, Some(d))),
+            layer().prop_map(|l| SceneEvent::LayerLocked(l, true)),
+            layer().prop_map(|l| SceneEvent::LayerVisibility(l, false)),
+            (0..3usize, any::<bool>())
+                .prop_map(move |(i, up)| SceneEvent::LayerMove(LAYERS[i], zs[i], up)),
+            (100i64..200, text(), -5..5i32).prop_map(|(id, t, z)| SceneEvent::LayerNew(id, t, z)),
+            (layer(), any::<(bool, bool, bool)>()).prop_map(|(l, (a, b, c))| {
+                let perms = LayerPerms {
+                    can_move_own: a,
+                    can_move_others: b,
+                    can_add: c,
+                };
+                SceneEvent::LayerPerms(l, LayerPerms::default(), perms)
+            }),
+            layer().prop_map(SceneEvent::LayerRemove),
+            (0..3usize, text()).prop_map(move |(i, t)| SceneEvent::LayerRename(
+                LAYERS[i],
+                titles[i].clone(),
+                t
+            )),
+        ];
+        let scene = prop_oneof![
+            colour().prop_map(|c| SceneEvent::SceneBackground(Scene::DEFAULT_BACKGROUND, c)),
+            (0i64..100).prop_map(|t| SceneEvent::SceneBackgroundTexture(None, Some(t))),
+            // Shrinking the scene discards fog beyond its new bounds, which
+            // can't be restored, so only growth is generated.
+            (
+                Scene::DEFAULT_SIZE..=Scene::MAX_SIZE,
+                Scene::DEFAULT_SIZE..=Scene::MAX_SIZE
+            )
+                .prop_map(|(w, h)| {
+                    SceneEvent::SceneDimensions(Scene::DEFAULT_SIZE, Scene::DEFAULT_SIZE, w, h)
+                }),
+            colour().prop_map(|c| SceneEvent::SceneGridColour(Scene::DEFAULT_GRID_COLOUR, c)),
+            text().prop_map(move |t| SceneEvent::SceneTitle(base.title.clone(), t)),
+            prop::sample::select(&UnitSystem::ALL[..])
+                .prop_map(|u| SceneEvent::SceneUnitSystem(UnitSystem::default(), u)),
+            Just(SceneEvent::SceneLock(true)),
+        ];
+        let sprites = prop_oneof![
+            (sprite(), text())
+                .prop_map(|(s, c)| { SceneEvent::SpriteCondition(s, Condition::Custom(c), true) }),
+            Just(SceneEvent::SpriteCondition(
+                FIRST_SPRITE,
+                Condition::Prone,
+                false
+            )),
+            any::<Option<(i32, i32)>>().prop_map(|hp| SceneEvent::SpriteHp(
+                FIRST_SPRITE,
+                Some((5, 10)),
+                hp
+            )),
+            prop::option::of(text()).prop_map(|label| {
+                SceneEvent::SpriteLabel(FIRST_SPRITE, Some("Goblin".to_string()), label)
+            }),
+            text().prop_map(|notes| SceneEvent::SpriteGmNotes(FIRST_SPRITE, None, Some(notes))),
+            any::<u128>().prop_map(|user| {
+                SceneEvent::SpriteOwner(FIRST_SPRITE, None, Some(Uuid::from_u128(user)))
+            }),
+            (sprite(), rect()).prop_map(|(s, to)| {
+                SceneEvent::SpriteMove(s, Rect::new(s as f32, 1.0, 1.0, 1.0), to)
+            }),
+            (100i64..200, rect(), -3..3i32, visual(), layer()).prop_map(
+                |(id, rect, z, visual, layer)| {
+                    let mut sprite = Sprite::new(id, Some(visual));
+                    sprite.rect = rect;
+                    sprite.z = z;
+                    SceneEvent::SpriteNew(sprite, layer)
+                }
+            ),
+            (0..2i64, prop::sample::select(&[SCENERY, 3][..]))
+                .prop_map(|(s, l)| { SceneEvent::SpriteLayer(FIRST_SPRITE + s, FOREGROUND, l) }),
+            Just(SceneEvent::SpriteOrder(FIRST_SPRITE, 0, 1)),
+            Just(SceneEvent::SpriteOrder(FIRST_SPRITE + 1, 1, 0)),
+            sprite().prop_map(|s| SceneEvent::SpriteRemove(s, FOREGROUND)),
+            (sprite(), visual())
+                .prop_map(|(s, v)| { SceneEvent::SpriteVisual(s, Sprite::new(s, None).visual, v) }),
+            (sprite(), pattern()).prop_map(|(s, p)| SceneEvent::SpriteStrokePattern(
+                s,
+                StrokePattern::Solid,
+                p
+            )),
+            (text(), rect(), 10.0..100.0f32).prop_map(|(name, rect, zoom)| {
+                SceneEvent::WaypointAdd(Waypoint::new(&name, rect, zoom).unwrap())
+            }),
+            Just(SceneEvent::WaypointRemove(
+                Waypoint::new("Camp", Rect::new(0.0, 0.0, 8.0, 8.0), 50.0).unwrap()
+            )),
+            (text(), text(), any::<u64>()).prop_map(|(author, text, timestamp)| {
+                SceneEvent::CommentAdd(Comment::new(&author, &text, None, timestamp).unwrap())
+            }),
+        ];
+        prop_oneof![fog, groups, layers, scene, sprites]
+    }
+
+    /// An event, or a set of events, each valid to apply to `base`. Sets may
+    /// contain events which conflict.
+    fn events() -> impl Strategy<Value = SceneEvent> {
+        prop_oneof![
+            event(),
+            prop::collection::vec(event(), 2..5).prop_map(SceneEvent::EventSet),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_apply_unwind(event in events()) {
+            let original = base();
+            let mut scene = original.clone();
+            if scene.apply_event(event.clone()) {
+                scene.unwind_event(event);
+            }
+            prop_assert_eq!(snapshot(&scene), snapshot(&original));
+        }
+
+        #[test]
+        fn test_event_name(event in event()) {
+            let debug = format!("{event:?}");
+            prop_assert!(debug.starts_with(event.name()));
+            prop_assert!(!debug[event.name().len()..].starts_with(char::is_alphanumeric));
+        }
+    }
+
+    #[test]
+    fn test_apply_unwind_missing() {
+        const MISSING: Id = 999;
+
+        let events = [
+            SceneEvent::GroupAdd(MISSING, FIRST_SPRITE),
+            SceneEvent::GroupRemove(MISSING, FIRST_SPRITE),
+            SceneEvent::GroupNest(GROUP, MISSING),
+            SceneEvent::LayerDescription(MISSING, None, Some("Notes".to_string())),
+            SceneEvent::LayerLocked(MISSING, true),
+            SceneEvent::LayerMove(MISSING, 1, true),
+            SceneEvent::LayerPerms(MISSING, LayerPerms::default(), LayerPerms::default()),
+            SceneEvent::LayerRemove(MISSING),
+            SceneEvent::LayerRename(MISSING, "Old".to_string(), "New".to_string()),
+            SceneEvent::LayerRestore(MISSING),
+            SceneEvent::LayerVisibility(MISSING, false),
+            SceneEvent::SpriteCondition(MISSING, Condition::Prone, true),
+            SceneEvent::SpriteHp(MISSING, None, Some((1, 1))),
+            SceneEvent::SpriteLabel(MISSING, None, Some("Orc".to_string())),
+            SceneEvent::SpriteGmNotes(MISSING, None, Some("Friendly".to_string())),
+            SceneEvent::SpriteDrawingReverse(MISSING),
+            SceneEvent::SpriteDrawingJoin(MISSING, MISSING),
+            SceneEvent::SpriteDrawingUnjoin(MISSING, MISSING),
+            SceneEvent::SpriteFlipCaps(MISSING),
+            SceneEvent::SpriteOwner(MISSING, None, Some(Uuid::nil())),
+            SceneEvent::SpriteLayer(MISSING, FOREGROUND, SCENERY),
+            SceneEvent::SpriteLayer(FIRST_SPRITE, FOREGROUND, MISSING),
+            SceneEvent::SpriteMove(
+                MISSING,
+                Rect::new(0.0, 0.0, 1.0, 1.0),
+                Rect::new(1.0, 1.0, 1.0, 1.0),
+            ),
+            SceneEvent::SpriteNew(Sprite::new(100, None), MISSING),
+            SceneEvent::SpriteOrder(MISSING, 0, 1),
+            SceneEvent::SpriteOrder(FIRST_SPRITE, 0, 5),
+            SceneEvent::SpriteRemove(MISSING, FOREGROUND),
+            SceneEvent::SpriteRestore(MISSING),
+            SceneEvent::SpriteStrokePattern(MISSING, StrokePattern::Solid, StrokePattern::Dotted),
+            SceneEvent::SpriteVisual(
+                MISSING,
+                Sprite::new(0, None).visual,
+                Sprite::new(0, None).visual,
+            ),
+            SceneEvent::FogOcclude(false, Scene::DEFAULT_SIZE, 0),
+            SceneEvent::FogReveal(true, 0, Scene::DEFAULT_SIZE),
+            // A set which fails part way through.
+            SceneEvent::EventSet(vec![
+                SceneEvent::SceneTitle("Untitled".to_string(), "Keep".to_string()),
+                SceneEvent::LayerRename(MISSING, "Old".to_string(), "New".to_string()),
+            ]),
+        ];
+
+        let original = base();
+        for event in events {
+            // Events referring to things which don't exist change nothing,
+            // whether they're accepted or not, and nor does unwinding them.
+            let mut scene = original.clone();
+            scene.apply_event(event.clone());
+            assert_eq!(snapshot(&scene), snapshot(&original), "applying {event:?}");
+            scene.unwind_event(event.clone());
+            assert_eq!(snapshot(&scene), snapshot(&original), "unwinding {event:?}");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// Units in which distances in a scene are displayed. Each scene unit is a
+/// single grid cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum UnitSystem {
+    /// Five feet per cell.
+    #[default]
+    Feet,
+    /// One and a half metres per cell.
+    Meters,
+    /// Distances in cells.
+    Squares,
+}
+
+impl UnitSystem {
+    pub const ALL: [UnitSystem; 3] = [UnitSystem::Feet, UnitSystem::Meters, UnitSystem::Squares];
+
+    /// Format a distance of `cells` grid cells for display.
+    pub fn format(&self, cells: f32) -> String {
+        match self {
+            UnitSystem::Feet => format!("{} ft", (cells * 5.0).round()),
+            UnitSystem::Meters => format!("{} m", round_tenths(cells * 1.5)),
+            UnitSystem::Squares => {
+                let squares = round_tenths(cells);
+                if squares == 1.0 {
+                    "1 square".to_string()
+                } else {
+                    format!("{squares} squares")
+                }
+            }
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            UnitSystem::Feet => "Feet",
+            UnitSystem::Meters => "Meters",
+            UnitSystem::Squares => "Squares",
+        }
+    }
+
+    pub fn from(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|u| u.to_str() == name)
+    }
+}
+
+fn round_tenths(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnitSystem;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(UnitSystem::Feet.format(3.0), "15 ft");
+        assert_eq!(UnitSystem::Feet.format(0.45), "2 ft");
+        assert_eq!(UnitSystem::Meters.format(3.0), "4.5 m");
+        assert_eq!(UnitSystem::Meters.format(2.0), "3 m");
+        assert_eq!(UnitSystem::Squares.format(3.0), "3 squares");
+        assert_eq!(UnitSystem::Squares.format(1.02), "1 square");
+        assert_eq!(UnitSystem::Squares.format(2.54), "2.5 squares");
+
+        for unit in UnitSystem::ALL {
+            assert_eq!(UnitSystem::from(unit.to_str()), Some(unit));
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::Rect;
+
+/// A named camera position in the scene which the GM can jump to.
+#[derive(Clone, Debug, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+pub struct Waypoint {
+    pub name: String,
+
+    /// Area of the scene visible at this waypoint, in scene units.
+    pub viewport: Rect,
+
+    /// Size to render a scene unit, in pixels.
+    pub grid_size: f32,
+}
+
+impl Waypoint {
+    pub const MAX_NAME_LENGTH: usize = 64;
+
+    pub fn new(name: &str, viewport: Rect, grid_size: f32) -> Option<Self> {
+        let name: String = name.trim().chars().take(Self::MAX_NAME_LENGTH).collect();
+        if name.is_empty() {
+            None
+        } else {
+            Some(Self {
+                name,
+                viewport,
+                grid_size,
+            })
+        }
+    }
+}
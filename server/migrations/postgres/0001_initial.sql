@@ -0,0 +1,122 @@
+-- Mirrors migrations/sqlite/0001_initial.sql. Columns named "user" are quoted
+-- as USER is reserved, and integers are BIGINT to hold 64-bit timestamps.
+
+CREATE TABLE IF NOT EXISTS users (
+    uuid TEXT PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    salt TEXT NOT NULL, -- CHAR(64)
+    hashed_password TEXT NOT NULL, -- CHAR(64)
+    recovery_key TEXT NOT NULL -- CHAR(64)
+);
+
+CREATE TABLE IF NOT EXISTS user_sessions (
+    session_key TEXT PRIMARY KEY, -- CHAR(64)
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    start_time BIGINT NOT NULL,
+    end_time BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS user_api_keys (
+    key_hash TEXT PRIMARY KEY, -- CHAR(64), SHA-256 of the key
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    label TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    UNIQUE ("user", label)
+);
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    uuid TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    url TEXT NOT NULL,
+    events TEXT NOT NULL, -- Comma separated event names
+    secret TEXT NOT NULL -- CHAR(64), HMAC key for signing payloads
+);
+
+-- Scene events applied during games. Not linked to scenes as scenes created
+-- in a game aren't saved immediately.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    scene TEXT NOT NULL,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    event_type TEXT NOT NULL,
+    event_json TEXT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_scene ON audit_log (scene, timestamp);
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    token_hash TEXT PRIMARY KEY, -- CHAR(64), SHA-256 of the emailed token
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS media (
+    uuid TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    relative_path TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    hashed_value TEXT NOT NULL, -- CHAR(64)
+    file_size BIGINT NOT NULL, -- Size in bytes
+    w DOUBLE PRECISION NOT NULL, -- Default width of tokens created with this media
+    h DOUBLE PRECISION NOT NULL, -- Default height of tokens created with this media
+    UNIQUE("user", hashed_value)
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    uuid TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    updated_time BIGINT NOT NULL,
+    title TEXT NOT NULL
+);
+
+-- Scenes new projects can start from. Built in templates have no user and are
+-- inserted by the server on startup.
+CREATE TABLE IF NOT EXISTS project_templates (
+    key TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    scene BYTEA NOT NULL -- Serialised scene::Project containing the scene
+);
+
+-- Saved sprite configurations. Templates with a project are shared with that
+-- project rather than offered in all of the user's projects.
+CREATE TABLE IF NOT EXISTS sprite_templates (
+    uuid TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    project TEXT REFERENCES projects(uuid) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    template TEXT NOT NULL -- JSON serialised scene::SpriteTmpl
+);
+
+-- Named colour palettes saved by users for the draw menu.
+CREATE TABLE IF NOT EXISTS palettes (
+    uuid TEXT PRIMARY KEY,
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    name TEXT NOT NULL,
+    colours TEXT NOT NULL, -- JSON serialised Vec<scene::Colour>
+    UNIQUE ("user", name)
+);
+
+CREATE TABLE IF NOT EXISTS scenes (
+    uuid TEXT PRIMARY KEY NOT NULL,
+    project TEXT REFERENCES projects(uuid) ON DELETE CASCADE NOT NULL,
+    updated_time BIGINT NOT NULL,
+    title TEXT NOT NULL,
+    thumbnail TEXT -- Relative URL for thumbnail
+);
+
+CREATE TABLE IF NOT EXISTS shared_scenes (
+    share_token TEXT PRIMARY KEY, -- CHAR(64)
+    scene TEXT REFERENCES scenes(uuid) ON DELETE CASCADE NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+-- Sprite labels, with a full-text index for searching across scenes.
+CREATE TABLE IF NOT EXISTS scene_labels (
+    scene TEXT NOT NULL, -- UUID of the scene containing the labelled sprite
+    label TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS scene_labels_label
+    ON scene_labels USING GIN (to_tsvector('simple', label));
@@ -0,0 +1,6 @@
+-- Time of each user's most recent data export, to limit how often they can
+-- export.
+CREATE TABLE IF NOT EXISTS data_exports (
+    "user" TEXT PRIMARY KEY REFERENCES users(uuid) ON DELETE CASCADE,
+    export_time BIGINT NOT NULL
+);
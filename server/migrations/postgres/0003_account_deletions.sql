@@ -0,0 +1,21 @@
+-- Record of deleted accounts, kept for compliance. Not linked to users as the
+-- user no longer exists. Rows may not be modified or removed.
+CREATE TABLE IF NOT EXISTS account_deletions (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    "user" TEXT NOT NULL, -- UUID of the deleted user
+    deleted_at BIGINT NOT NULL
+);
+
+CREATE OR REPLACE FUNCTION account_deletions_immutable() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        RAISE EXCEPTION 'Account deletions may not be modified.';
+    ELSE
+        RAISE EXCEPTION 'Account deletions may not be removed.';
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER account_deletions_immutable
+BEFORE UPDATE OR DELETE ON account_deletions
+FOR EACH ROW EXECUTE FUNCTION account_deletions_immutable();
@@ -0,0 +1,11 @@
+-- Responses to requests made with an idempotency key, replayed when the
+-- request is retried.
+CREATE TABLE IF NOT EXISTS idempotency_cache (
+    "user" TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    idempotency_key TEXT NOT NULL, -- From the X-Idempotency-Key header
+    status BIGINT NOT NULL,
+    content_type TEXT,
+    response_body BYTEA NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY ("user", idempotency_key)
+);
@@ -0,0 +1 @@
+ALTER TABLE media ADD COLUMN tags TEXT NOT NULL DEFAULT ''; -- Comma-separated list of tags
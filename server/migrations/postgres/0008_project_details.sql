@@ -0,0 +1,2 @@
+ALTER TABLE projects ADD COLUMN description TEXT;
+ALTER TABLE projects ADD COLUMN tags TEXT NOT NULL DEFAULT ''; -- Comma-separated list of tags
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN display_name TEXT NOT NULL DEFAULT ''; -- Empty to use username
+ALTER TABLE users ADD COLUMN avatar_media_key TEXT REFERENCES media(uuid) ON DELETE SET NULL;
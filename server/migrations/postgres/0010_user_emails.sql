@@ -0,0 +1,3 @@
+-- Used for password resets.
+ALTER TABLE users ADD COLUMN email TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email ON users (email);
@@ -0,0 +1,120 @@
+-- Databases created before migrations were introduced already have the tables
+-- which existed then, so these are skipped for them. Columns added to those
+-- tables since are added by later migrations rather than here.
+
+CREATE TABLE IF NOT EXISTS users (
+    uuid TEXT PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    salt TEXT NOT NULL, -- CHAR(64)
+    hashed_password TEXT NOT NULL, -- CHAR(64)
+    recovery_key TEXT NOT NULL -- CHAR(64)
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS user_sessions (
+    session_key TEXT PRIMARY KEY, -- CHAR(64)
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS user_api_keys (
+    key_hash TEXT PRIMARY KEY, -- CHAR(64), SHA-256 of the key
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    label TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (user, label)
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    uuid TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    url TEXT NOT NULL,
+    events TEXT NOT NULL, -- Comma separated event names
+    secret TEXT NOT NULL -- CHAR(64), HMAC key for signing payloads
+) STRICT;
+
+-- Scene events applied during games. Not linked to scenes as scenes created
+-- in a game aren't saved immediately.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY,
+    scene TEXT NOT NULL,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    event_type TEXT NOT NULL,
+    event_json TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+) STRICT;
+
+CREATE INDEX IF NOT EXISTS audit_log_scene ON audit_log (scene, timestamp);
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    token_hash TEXT PRIMARY KEY, -- CHAR(64), SHA-256 of the emailed token
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    expires_at INTEGER NOT NULL
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS media (
+    uuid TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    relative_path TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    hashed_value TEXT NOT NULL, -- CHAR(64)
+    file_size INTEGER NOT NULL, -- Size in bytes
+    w REAL NOT NULL, -- Default width of tokens created with this media
+    h REAL NOT NULL, -- Default height of tokens created with this media
+    UNIQUE(user, hashed_value)
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS projects (
+    uuid TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    updated_time INTEGER NOT NULL,
+    title TEXT NOT NULL
+) STRICT;
+
+-- Scenes new projects can start from. Built in templates have no user and are
+-- inserted by the server on startup.
+CREATE TABLE IF NOT EXISTS project_templates (
+    key TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    scene BLOB NOT NULL -- Serialised scene::Project containing the scene
+) STRICT;
+
+-- Saved sprite configurations. Templates with a project are shared with that
+-- project rather than offered in all of the user's projects.
+CREATE TABLE IF NOT EXISTS sprite_templates (
+    uuid TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    project TEXT REFERENCES projects(uuid) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    template TEXT NOT NULL -- JSON serialised scene::SpriteTmpl
+) STRICT;
+
+-- Named colour palettes saved by users for the draw menu.
+CREATE TABLE IF NOT EXISTS palettes (
+    uuid TEXT PRIMARY KEY,
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    name TEXT NOT NULL,
+    colours TEXT NOT NULL, -- JSON serialised Vec<scene::Colour>
+    UNIQUE (user, name)
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS scenes (
+    uuid TEXT PRIMARY KEY NOT NULL,
+    project TEXT REFERENCES projects(uuid) ON DELETE CASCADE NOT NULL,
+    updated_time INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    thumbnail TEXT -- Relative URL for thumbnail
+) STRICT;
+
+CREATE TABLE IF NOT EXISTS shared_scenes (
+    share_token TEXT PRIMARY KEY, -- CHAR(64)
+    scene TEXT REFERENCES scenes(uuid) ON DELETE CASCADE NOT NULL,
+    expires_at INTEGER NOT NULL
+) STRICT;
+
+-- Full-text index of sprite labels, for searching across scenes.
+CREATE VIRTUAL TABLE IF NOT EXISTS scene_labels USING fts5(
+    scene UNINDEXED, -- UUID of the scene containing the labelled sprite
+    label
+);
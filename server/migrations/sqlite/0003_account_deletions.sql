@@ -0,0 +1,19 @@
+-- Record of deleted accounts, kept for compliance. Not linked to users as the
+-- user no longer exists. Rows may not be modified or removed.
+CREATE TABLE IF NOT EXISTS account_deletions (
+    id INTEGER PRIMARY KEY,
+    user TEXT NOT NULL, -- UUID of the deleted user
+    deleted_at INTEGER NOT NULL
+) STRICT;
+
+CREATE TRIGGER IF NOT EXISTS account_deletions_no_update
+BEFORE UPDATE ON account_deletions
+BEGIN
+    SELECT RAISE(ABORT, 'Account deletions may not be modified.');
+END;
+
+CREATE TRIGGER IF NOT EXISTS account_deletions_no_delete
+BEFORE DELETE ON account_deletions
+BEGIN
+    SELECT RAISE(ABORT, 'Account deletions may not be removed.');
+END;
@@ -0,0 +1,4 @@
+-- Two-factor authentication secrets, base32 encoded. The pending secret awaits
+-- confirmation of a valid code before it replaces the secret.
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_pending_secret TEXT;
@@ -0,0 +1,15 @@
+-- Keys are reserved per request, identified by its method and path, before it
+-- is handled. Cached responses are short lived, so are dropped rather than
+-- migrated.
+DROP TABLE IF EXISTS idempotency_cache;
+
+CREATE TABLE idempotency_cache (
+    user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+    idempotency_key TEXT NOT NULL, -- From the X-Idempotency-Key header
+    request TEXT NOT NULL, -- Method and path, e.g. "POST /api/project/save"
+    status INTEGER, -- NULL while the request is being handled
+    content_type TEXT,
+    response_body BLOB,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (user, idempotency_key, request)
+) STRICT;
@@ -0,0 +1,4 @@
+-- Used for password resets. Columns can't be added with a UNIQUE constraint,
+-- so uniqueness is enforced by an index.
+ALTER TABLE users ADD COLUMN email TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email ON users (email);
@@ -0,0 +1,3 @@
+-- Time step of the last TOTP code accepted for the user. Codes for this or
+-- earlier steps are rejected so that they can't be replayed.
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+use actix_web::web;
+
+use super::{res_json, Resp};
+use crate::{
+    fs::{join_relative_path, CONTENT},
+    models::{Media, Project, User},
+    req::{e500, Pool},
+    utils::{format_uuid, info},
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/admin").route("/media/gc", web::post().to(media_gc))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(media_gc))]
+pub(super) struct Api;
+
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct GcQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct GcItem {
+    uuid: String,
+    title: String,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct GcResponse {
+    message: String,
+    success: bool,
+    dry_run: bool,
+    media: Vec<GcItem>,
+}
+
+/// Delete the user's media which isn't used as a texture in any scene, nor
+/// as an avatar. Recent uploads are kept as they may not have been placed
+/// yet. With `?dry_run=true`, only lists the media which would be removed.
+#[utoipa::path(
+    post,
+    path = "/media/gc",
+    params(GcQuery),
+    responses((status = 200, body = GcResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn media_gc(mut conn: Pool, user: User, query: web::Query<GcQuery>) -> Resp {
+    const MIN_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+    let conn = conn.acquire();
+
+    // Media may be referenced from any user's project, so check them all.
+    // If any project can't be read, it isn't safe to delete anything.
+    let mut textures = HashSet::new();
+    for project in Project::list_all(conn).await.map_err(e500)? {
+        let project = project.load(conn).await.map_err(e500)?;
+        for scene in &project.scenes {
+            textures.extend(scene.texture_ids());
+        }
+    }
+
+    let before = SystemTime::now() - MIN_AGE;
+    let media = Media::unreferenced(conn, user.uuid, &textures, before)
+        .await
+        .map_err(e500)?;
+
+    if !query.dry_run {
+        for item in &media {
+            Media::delete(conn, item.uuid).await.map_err(e500)?;
+            for path in item.paths() {
+                tokio::fs::remove_file(join_relative_path(&CONTENT, path))
+                    .await
+                    .ok();
+            }
+        }
+        info(format!(
+            "Removed {} unreferenced media items for {}.",
+            media.len(),
+            user.username
+        ));
+    }
+
+    let message = if query.dry_run {
+        format!("{} media items would be removed.", media.len())
+    } else {
+        format!("Removed {} media items.", media.len())
+    };
+
+    res_json(GcResponse {
+        message,
+        success: true,
+        dry_run: query.dry_run,
+        media: media
+            .into_iter()
+            .map(|item| GcItem {
+                uuid: format_uuid(item.uuid),
+                title: item.title,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, web::Data, App};
+    use uuid::{NoContext, Timestamp, Uuid};
+
+    use super::GcResponse;
+    use crate::{
+        fs::initialise_database,
+        models::{Media, Project, User},
+        utils::{format_uuid, timestamp_s},
+    };
+
+    #[actix_web::test]
+    async fn test_media_gc() {
+        // TEST
+        //   POST /api/admin/media/gc
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        // Upload media 30 days ago, and some just now.
+        let old = Timestamp::from_unix(NoContext, timestamp_s().unwrap() - 30 * 24 * 60 * 60, 0);
+        let mut media = Vec::new();
+        for title in ["placed", "unused", "avatar"] {
+            let mut item = Media::prepare(&user, "png", title, title, 1);
+            item.uuid = Uuid::new_v7(old);
+            media.push(item.create(conn).await.unwrap());
+        }
+        Media::prepare(&user, "png", "recent", "recent", 1)
+            .create(conn)
+            .await
+            .unwrap();
+
+        let project = Project::create(conn, &user, "Textures").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        let scene = proj.scenes.last_mut().unwrap();
+        let layer = scene.first_layer();
+        // Clients derive texture IDs from the media keys they're sent.
+        scene.new_sprite(
+            Some(scene::SpriteVisual::Texture {
+                id: scene::media_key_id(&format_uuid(media[0].uuid)),
+                shape: scene::Shape::Rectangle,
+            }),
+            layer,
+        );
+        Project::save(conn, &user, proj).await.unwrap();
+        User::set_profile(conn, user.uuid, "", Some(media[2].uuid), None)
+            .await
+            .unwrap();
+
+        let gc = |dry_run: bool| {
+            test::TestRequest::post()
+                .uri(&format!("/api/admin/media/gc?dry_run={dry_run}"))
+                .cookie(session.clone())
+                .to_request()
+        };
+
+        // Dry runs list the unused media without removing it.
+        let resp: GcResponse = test::call_and_read_body_json(&app, gc(true)).await;
+        assert!(resp.success);
+        let unused: Vec<String> = resp.media.into_iter().map(|m| m.title).collect();
+        assert_eq!(unused, vec!["unused"]);
+        assert!(Media::load(conn, media[1].uuid).await.is_ok());
+
+        let resp: GcResponse = test::call_and_read_body_json(&app, gc(false)).await;
+        assert_eq!(resp.media.len(), 1);
+        assert_eq!(resp.media[0].uuid, format_uuid(media[1].uuid));
+        assert!(Media::load(conn, media[1].uuid).await.is_err());
+        assert!(Media::load(conn, media[0].uuid).await.is_ok());
+
+        // Nothing further to remove.
+        let resp: GcResponse = test::call_and_read_body_json(&app, gc(false)).await;
+        assert!(resp.media.is_empty());
+    }
+}
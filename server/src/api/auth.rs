@@ -1,32 +1,54 @@
 use actix_web::{error::ErrorInternalServerError, web, HttpResponse};
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
-use super::{body_failure, body_success, resp, session_resp, Resp};
+use super::{
+    body_failure, body_success, res_failure, res_success, resp, session_resp, Binary, Resp,
+};
 use crate::crypto::Key;
-use crate::models::UserAuth;
+use crate::mail::Mailer;
+use crate::models::{PasswordReset, User, UserAuth};
 use crate::req::session::SessionOpt;
-use crate::utils::Res;
-use crate::{crypto::check_password, models::UserSession};
+use crate::utils::{warning, Res};
+use crate::{
+    crypto::{check_password, check_totp},
+    models::UserSession,
+};
 
 pub fn routes() -> actix_web::Scope {
     actix_web::web::scope("/auth")
         .route("/login", web::post().to(login))
         .route("/test", web::post().to(test))
         .route("/logout", web::post().to(logout))
+        .route("/refresh", web::put().to(refresh))
+        .route("/forgot-password", web::post().to(forgot_password))
+        .route("/reset-password", web::post().to(reset_password))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(login, test, logout, refresh, forgot_password, reset_password))]
+pub(super) struct Api;
+
 fn decode_and_check_password(provided: &str, salt: &Key, hashed_password: &Key) -> Res<bool> {
     Ok(check_password(provided, salt, hashed_password))
 }
 
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct LoginRequest {
     username: String,
     password: String,
+    #[serde(default)]
+    totp_code: Option<String>,
 }
 
-async fn login(pool: web::Data<SqlitePool>, req: web::Json<LoginRequest>) -> Resp {
+/// Log in, setting the session cookie on success.
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginRequest,
+    responses((status = 200, body = Binary))
+)]
+async fn login(pool: web::Data<AnyPool>, req: web::Json<LoginRequest>) -> Resp {
     let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
     let user = match UserAuth::get_by_username(conn, req.username.as_str()).await {
         Ok(user) => user,
@@ -39,6 +61,21 @@ async fn login(pool: web::Data<SqlitePool>, req: web::Json<LoginRequest>) -> Res
         return Ok(session_resp("").json(body_failure("Incorrect password.")));
     };
 
+    if let Some(secret) = &user.totp_secret {
+        let Some(code) = req.totp_code.as_deref().filter(|c| !c.is_empty()) else {
+            return Ok(session_resp("").json(body_failure("Two-factor code required.")));
+        };
+        let accepted = match check_totp(secret, code).map_err(ErrorInternalServerError)? {
+            Some(step) => User::use_totp_step(conn, user.uuid, step)
+                .await
+                .map_err(ErrorInternalServerError)?,
+            None => false,
+        };
+        if !accepted {
+            return Ok(session_resp("").json(body_failure("Incorrect two-factor code.")));
+        }
+    }
+
     let session = UserSession::create(conn, user.uuid)
         .await
         .map_err(ErrorInternalServerError)?;
@@ -46,6 +83,13 @@ async fn login(pool: web::Data<SqlitePool>, req: web::Json<LoginRequest>) -> Res
     Ok(session_resp(&session.key_text()).json(body_success("Logged in.")))
 }
 
+/// Check whether the session cookie is valid.
+#[utoipa::path(
+    post,
+    path = "/test",
+    responses((status = 200, body = Binary)),
+    security(("session" = []))
+)]
 async fn test(session: SessionOpt) -> HttpResponse {
     let success = !matches!(session, SessionOpt::None);
     let message = if success {
@@ -57,7 +101,13 @@ async fn test(session: SessionOpt) -> HttpResponse {
     resp(message, success)
 }
 
-async fn logout(pool: web::Data<SqlitePool>, session: SessionOpt) -> Resp {
+#[utoipa::path(
+    post,
+    path = "/logout",
+    responses((status = 200, body = Binary)),
+    security(("session" = []))
+)]
+async fn logout(pool: web::Data<AnyPool>, session: SessionOpt) -> Resp {
     if let SessionOpt::Some(session) = session {
         let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
         session.session.end(conn).await.ok();
@@ -66,15 +116,122 @@ async fn logout(pool: web::Data<SqlitePool>, session: SessionOpt) -> Resp {
     Ok(session_resp("").json(body_success("Logged out.")))
 }
 
+/// Replace the session with a new one, setting the session cookie, so that
+/// active users aren't logged out when their session expires.
+#[utoipa::path(
+    put,
+    path = "/refresh",
+    responses((status = 200, body = Binary)),
+    security(("session" = []))
+)]
+async fn refresh(pool: web::Data<AnyPool>, session: SessionOpt) -> Resp {
+    let SessionOpt::Some(session) = session else {
+        return res_failure("Invalid session.");
+    };
+
+    let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
+    let session = session
+        .session
+        .refresh(conn)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(session_resp(&session.key_text()).json(body_success("Session refreshed.")))
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct ForgotPasswordRequest {
+    email: String,
+}
+
+/// Email a password reset token to the user with the provided email. Always
+/// succeeds so as not to reveal which addresses are registered.
+#[utoipa::path(
+    post,
+    path = "/forgot-password",
+    request_body = ForgotPasswordRequest,
+    responses((status = 200, body = Binary))
+)]
+async fn forgot_password(
+    pool: web::Data<AnyPool>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<ForgotPasswordRequest>,
+) -> Resp {
+    const MESSAGE: &str = "If an account uses that email, a reset link has been sent.";
+
+    let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
+    let Some(user) = User::get_by_email(conn, &req.email)
+        .await
+        .map_err(ErrorInternalServerError)?
+    else {
+        return res_success(MESSAGE);
+    };
+    let Some(email) = user.email else {
+        return res_success(MESSAGE);
+    };
+
+    let token = PasswordReset::create(conn, user.uuid)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let body = format!(
+        "A password reset was requested for {}. \
+        Visit /reset-password?token={token} within an hour to set a new password.",
+        user.username
+    );
+    if let Err(e) = mailer.send(&email, "Password reset", &body) {
+        warning(format!("Failed to send password reset email: {e}"));
+    }
+
+    res_success(MESSAGE)
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct ResetPasswordRequest {
+    token: String,
+    new_password: String,
+}
+
+/// Set a new password using a token from a password reset email.
+#[utoipa::path(
+    post,
+    path = "/reset-password",
+    request_body = ResetPasswordRequest,
+    responses((status = 200, body = Binary))
+)]
+async fn reset_password(pool: web::Data<AnyPool>, req: web::Json<ResetPasswordRequest>) -> Resp {
+    if !super::register::valid_password(&req.new_password) {
+        return res_failure("Invalid password.");
+    }
+
+    // The token is only consumed if the password is updated.
+    let mut tx = pool.begin().await.map_err(ErrorInternalServerError)?;
+    let user = match PasswordReset::redeem(&mut tx, &req.token).await {
+        Ok(user) => user,
+        Err(e) => return res_failure(e),
+    };
+
+    UserAuth::set_password(&mut tx, user, &req.new_password)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    tx.commit().await.map_err(ErrorInternalServerError)?;
+
+    res_success("Password updated.")
+}
+
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use actix_web::{cookie::Cookie, test, web::Data, App};
 
-    use super::LoginRequest;
+    use super::{ForgotPasswordRequest, LoginRequest, ResetPasswordRequest};
     use crate::{
         api::{routes, Binary},
         fs::initialise_database,
-        models::User,
+        mail::{Mailer, TestMailer},
+        models::{ApiKey, User, UserSession},
         req::session::COOKIE_NAME,
     };
 
@@ -100,6 +257,7 @@ mod test {
             .set_json(LoginRequest {
                 username: user.username.clone(),
                 password: "wrongpassword".into(),
+                totp_code: None,
             })
             .to_request();
         let resp: Binary = test::call_and_read_body_json(&app, req).await;
@@ -111,6 +269,7 @@ mod test {
             .set_json(LoginRequest {
                 username: user.username.clone(),
                 password: "password".into(),
+                totp_code: None,
             })
             .to_request();
         let resp = test::call_service(&app, req).await;
@@ -148,4 +307,167 @@ mod test {
         let resp: Binary = test::call_and_read_body_json(&app, req).await;
         assert!(!resp.success);
     }
+
+    #[actix_web::test]
+    async fn test_session_refresh() {
+        // Test PUT /api/auth/refresh
+
+        let db = initialise_database().await.unwrap();
+        let app =
+            test::init_service(App::new().app_data(Data::new(db.clone())).service(routes())).await;
+
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let old = user.session(conn).await;
+
+        // Refreshing replaces the session cookie with a new session.
+        let req = test::TestRequest::put()
+            .uri("/api/auth/refresh")
+            .cookie(old.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = resp.headers().get("Set-Cookie").unwrap().to_str().unwrap();
+        let cookie = Cookie::parse(cookie.to_string()).unwrap();
+        let resp: Binary = test::read_body_json(resp).await;
+        assert!(resp.success);
+        assert_eq!(cookie.name(), COOKIE_NAME);
+        assert_ne!(cookie.value(), old.value());
+        assert_eq!(
+            cookie.max_age().unwrap().whole_seconds(),
+            UserSession::MAX_AGE_S
+        );
+
+        // The old session has ended.
+        let req = test::TestRequest::post()
+            .uri("/api/auth/test")
+            .cookie(old.clone())
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+        let req = test::TestRequest::put()
+            .uri("/api/auth/refresh")
+            .cookie(old)
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/test")
+            .cookie(Cookie::new(COOKIE_NAME, cookie.value().to_string()))
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+
+        // Sessions past their maximum age are invalid and can't be refreshed.
+        let start_time = crate::utils::timestamp_s().unwrap() as i64 - UserSession::MAX_AGE_S - 1;
+        let key = cookie.value();
+        sqlx::query("UPDATE user_sessions SET start_time = $1 WHERE session_key = $2")
+            .bind(start_time)
+            .bind(key)
+            .execute(&mut **conn)
+            .await
+            .unwrap();
+        let req = test::TestRequest::put()
+            .uri("/api/auth/refresh")
+            .cookie(Cookie::new(COOKIE_NAME, key.to_string()))
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+    }
+
+    #[actix_web::test]
+    async fn test_password_reset() {
+        // Test POST /api/auth/forgot-password, POST /api/auth/reset-password
+
+        let db = initialise_database().await.unwrap();
+        let mailer = Arc::new(TestMailer::default());
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .app_data(Data::from(mailer.clone() as Arc<dyn Mailer>))
+                .service(routes()),
+        )
+        .await;
+
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        User::set_profile(conn, user.uuid, "", None, Some("reset@example.com"))
+            .await
+            .unwrap();
+        let session = user.session(conn).await;
+        ApiKey::create(conn, user.uuid, "key").await.unwrap();
+
+        // Unknown emails succeed but don't send anything.
+        let req = test::TestRequest::post()
+            .uri("/api/auth/forgot-password")
+            .set_json(ForgotPasswordRequest {
+                email: "nobody@example.com".into(),
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert!(mailer.sent.lock().unwrap().is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/forgot-password")
+            .set_json(ForgotPasswordRequest {
+                email: "Reset@Example.com".into(),
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        let (to, _, body) = mailer.sent.lock().unwrap().pop().unwrap();
+        assert_eq!(to, "reset@example.com");
+        let token = body
+            .split("token=")
+            .nth(1)
+            .and_then(|s| s.split_whitespace().next())
+            .unwrap()
+            .to_string();
+
+        let reset = |token: &str, new_password: &str| {
+            test::TestRequest::post()
+                .uri("/api/auth/reset-password")
+                .set_json(ResetPasswordRequest {
+                    token: token.into(),
+                    new_password: new_password.into(),
+                })
+                .to_request()
+        };
+
+        // Weak passwords and unknown tokens are rejected.
+        let resp: Binary = test::call_and_read_body_json(&app, reset(&token, "short")).await;
+        assert!(!resp.success);
+        let resp: Binary =
+            test::call_and_read_body_json(&app, reset("0123abcd", "newpassword1")).await;
+        assert!(!resp.success);
+
+        let resp: Binary = test::call_and_read_body_json(&app, reset(&token, "newpassword1")).await;
+        assert!(resp.success);
+
+        // Existing sessions and API keys no longer grant access.
+        let req = test::TestRequest::post()
+            .uri("/api/auth/test")
+            .cookie(session)
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+        assert!(ApiKey::list(conn, user.uuid).await.unwrap().is_empty());
+
+        // Tokens can only be used once.
+        let resp: Binary = test::call_and_read_body_json(&app, reset(&token, "newpassword2")).await;
+        assert!(!resp.success);
+
+        // Log in with the new password.
+        let req = test::TestRequest::post()
+            .uri("/api/auth/login")
+            .set_json(LoginRequest {
+                username: user.username.clone(),
+                password: "newpassword1".into(),
+                totp_code: None,
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+    }
 }
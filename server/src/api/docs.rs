@@ -0,0 +1,154 @@
+use actix_web::HttpResponse;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+/// OpenAPI specification for the API, assembled from the annotations on the
+/// handlers in each module.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Realmview",
+        description = "REST API for managing projects, media and games."
+    ),
+    nest(
+        (path = "/api/admin", api = super::admin::Api, tags = ["admin"]),
+        (path = "/api/auth", api = super::auth::Api, tags = ["auth"]),
+        (path = "/api/game", api = super::game::Api, tags = ["game"]),
+        (path = "/api/media", api = super::media::Api, tags = ["media"]),
+        (path = "/api/project", api = super::project::Api, tags = ["project"]),
+        (path = "/api/register", api = super::register::Api, tags = ["register"]),
+        (path = "/api/search", api = super::search::Api, tags = ["search"]),
+        (path = "/api/share", api = super::share::Api, tags = ["share"]),
+        (path = "/api/templates", api = super::templates::Api, tags = ["templates"]),
+        (path = "/api/upload", api = super::upload::Api, tags = ["upload"]),
+        (path = "/api/user", api = super::user::Api, tags = ["user"]),
+        (path = "/api/webhooks", api = super::webhooks::Api, tags = ["webhooks"]),
+    ),
+    modifiers(&Security, &OperationIds)
+)]
+struct ApiDoc;
+
+/// Handlers which take a `User` accept either a session cookie or an API key.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(
+                crate::req::session::COOKIE_NAME,
+            ))),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Handler names are only unique within a module, so operation IDs are
+/// prefixed with the module's tag.
+struct OperationIds;
+
+impl Modify for OperationIds {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // The package has no licence to report.
+        openapi.info.license = None;
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                let tag = operation.tags.iter().flatten().next();
+                if let (Some(tag), Some(id)) = (tag, &operation.operation_id) {
+                    operation.operation_id = Some(format!("{tag}_{id}"));
+                }
+            }
+        }
+    }
+}
+
+pub(super) async fn spec() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Swagger UI for exploring the specification, loaded from a CDN.
+pub(super) async fn swagger_ui() -> HttpResponse {
+    const PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Realmview API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+    HttpResponse::Ok().content_type("text/html").body(PAGE)
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn test_openapi() {
+        // TEST
+        //   GET /api/openapi.json
+        //   GET /api/docs
+
+        let app = test::init_service(App::new().service(crate::api::routes())).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/openapi.json")
+            .to_request();
+        let spec: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(spec["openapi"], "3.1.0");
+
+        let paths = spec["paths"].as_object().unwrap();
+        for (path, method) in [
+            ("/api/auth/login", "post"),
+            ("/api/media/{uuid}", "delete"),
+            ("/api/project/{uuid}/scene/{scene_uuid}/share", "post"),
+            ("/api/register", "post"),
+            ("/api/upload", "post"),
+            ("/api/user/2fa/confirm", "post"),
+        ] {
+            assert!(paths[path].get(method).is_some(), "{method} {path}");
+        }
+
+        let mut ids = std::collections::HashSet::new();
+        for operation in paths.values().flat_map(|p| p.as_object().unwrap().values()) {
+            let id = operation["operationId"].as_str().unwrap();
+            assert!(ids.insert(id.to_string()), "{id}");
+        }
+        assert!(ids.contains("media_list"));
+
+        // Every schema referenced is defined.
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        let json = spec.to_string();
+        for reference in json.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.contains_key(name), "{name}");
+        }
+
+        let req = test::TestRequest::get().uri("/api/docs").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+}
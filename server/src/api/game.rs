@@ -1,35 +1,44 @@
 use std::{collections::HashMap, sync::Arc};
 
-use actix_web::{error::ErrorUnprocessableEntity, web, HttpRequest, HttpResponse};
-use sqlx::SqlitePool;
+use actix_web::{
+    error::{ErrorServiceUnavailable, ErrorUnprocessableEntity},
+    web, HttpRequest, HttpResponse,
+};
+use scene::perms::Role;
+use sqlx::AnyPool;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
-use super::{res_failure, res_json, res_success, Resp};
+use super::{res_failure, res_json, res_success, Binary, Resp};
 use crate::{
-    games::{close_ws, connect_client, launch_server, GameHandle, GameKey},
+    games::{close_ws, connect_client, launch_server, ConnectionLimit, GameHandle, GameKey},
     models::{Project, Scene, User},
     req::e500,
 };
 
-type Games = RwLock<HashMap<GameKey, GameHandle>>;
+pub(super) type Games = RwLock<HashMap<GameKey, GameHandle>>;
 
 pub fn routes() -> actix_web::Scope {
     web::scope("/game")
         .route("/new", web::post().to(new))
         .route("/{game_key}/end", web::post().to(end))
+        .route("/{game_key}/stats", web::get().to(stats))
         .route("/{game_key}", web::post().to(test))
         .route("/{game_key}", web::get().to(join))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(new, end, stats, test, join))]
+pub(super) struct Api;
+
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct NewGameRequest {
     scene: Uuid,
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct GameResponse {
     message: String,
     success: bool,
@@ -40,8 +49,16 @@ fn game_url(game_key: &GameKey) -> String {
     format!("/game/{game_key}")
 }
 
+/// Launch a game of a scene owned by the user.
+#[utoipa::path(
+    post,
+    path = "/new",
+    request_body = NewGameRequest,
+    responses((status = 200, body = GameResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn new(
-    pool: web::Data<SqlitePool>,
+    pool: web::Data<AnyPool>,
     games: web::Data<Games>,
     user: User,
     req: web::Json<NewGameRequest>,
@@ -85,6 +102,14 @@ async fn new(
     Ok(resp)
 }
 
+/// Close a game hosted by the user.
+#[utoipa::path(
+    post,
+    path = "/{game_key}/end",
+    params(("game_key" = String, Path, description = "Key of the game.")),
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn end(games: web::Data<Games>, user: User, path: web::Path<(String,)>) -> Resp {
     match GameKey::from(path.into_inner().0) {
         Ok(game_key) => {
@@ -103,23 +128,94 @@ async fn end(games: web::Data<Games>, user: User, path: web::Path<(String,)>) ->
     }
 }
 
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct GameStatsResponse {
+    message: String,
+    success: bool,
+    active_players: usize,
+    spectators: usize,
+    created_at: u64,
+}
+
+/// Numbers of players and spectators connected to a game. Doesn't require
+/// authentication, so that it can be shown on lobby pages.
+#[utoipa::path(
+    get,
+    path = "/{game_key}/stats",
+    params(("game_key" = String, Path, description = "Key of the game.")),
+    responses((status = 200, body = GameStatsResponse))
+)]
+async fn stats(games: web::Data<Games>, path: web::Path<(String,)>) -> Resp {
+    let game_key = GameKey::from(path.into_inner().0).map_err(ErrorUnprocessableEntity)?;
+    match games.read().await.get(&game_key) {
+        Some(handle) if handle.open() => res_json(GameStatsResponse {
+            message: "Game stats follow.".to_string(),
+            success: true,
+            active_players: handle.player_count(),
+            spectators: handle.spectator_count(),
+            created_at: handle.created_at,
+        }),
+        Some(_) => res_failure("Game has ended."),
+        None => res_failure("Game doesn't exist."),
+    }
+}
+
+/// End games hosted by `user` without saving them, and remove `user` from
+/// games hosted by others.
+pub(super) async fn remove_user(games: &Games, user: Uuid) {
+    let mut hosted = Vec::new();
+    games.write().await.retain(|_, handle| {
+        if handle.owner == user {
+            hosted.push(handle.clone());
+            false
+        } else {
+            handle.remove(user).ok();
+            true
+        }
+    });
+
+    for handle in hosted {
+        handle.discard().await;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn join_game(
     req: HttpRequest,
     stream: web::Payload,
     games: Arc<Games>,
+    connections: &ConnectionLimit,
     user: User,
+    role: Role,
+    reconnect: bool,
     game_key: &GameKey,
 ) -> Resp {
+    let Some(permit) = connections.try_acquire() else {
+        return Err(ErrorServiceUnavailable("Too many connections."));
+    };
+
     let (resp, mut session, msg_stream) = actix_ws::handle(&req, stream)?;
 
     match games.read().await.get(game_key) {
         Some(handle) => {
-            connect_client(user, handle.clone(), session, msg_stream);
+            connect_client(
+                user,
+                role,
+                reconnect,
+                handle.clone(),
+                session,
+                msg_stream,
+                permit,
+            );
         }
         None => {
             // Just send a gameover message and close the socket.
             session
-                .binary(bincode::serialize(&scene::comms::ServerEvent::GameOver).map_err(e500)?)
+                .binary(
+                    scene::comms::encode_message(&scene::comms::ServerEvent::GameOver)
+                        .map_err(e500)?,
+                )
                 .await
                 .map_err(e500)?;
 
@@ -130,17 +226,64 @@ async fn join_game(
     Ok(resp)
 }
 
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct JoinQuery {
+    /// Either `player`, the default, or `observer` for read-only access.
+    role: Option<String>,
+
+    /// Set by clients reconnecting after a dropped connection.
+    #[serde(default)]
+    reconnect: bool,
+}
+
+/// Join a game, upgrading the connection to a websocket.
+#[utoipa::path(
+    get,
+    path = "/{game_key}",
+    params(("game_key" = String, Path, description = "Key of the game."), JoinQuery),
+    responses(
+        (status = 101, description = "Switched to websocket."),
+        (status = 422, description = "Invalid game key or role."),
+        (status = 503, description = "Connection limit reached."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn join(
     req: HttpRequest,
     stream: web::Payload,
     games: web::Data<Games>,
+    connections: web::Data<ConnectionLimit>,
     user: User,
     path: web::Path<(String,)>,
+    query: web::Query<JoinQuery>,
 ) -> Resp {
     let game_key = GameKey::from(path.into_inner().0).map_err(ErrorUnprocessableEntity)?;
-    join_game(req, stream, games.into_inner(), user, &game_key).await
+    let role = match query.role.as_deref() {
+        None | Some("player") => Role::Player,
+        Some("observer" | "spectator") => Role::Spectator,
+        Some(role) => return Err(ErrorUnprocessableEntity(format!("Unknown role: {role}"))),
+    };
+    join_game(
+        req,
+        stream,
+        games.into_inner(),
+        &connections,
+        user,
+        role,
+        query.reconnect,
+        &game_key,
+    )
+    .await
 }
 
+/// Check whether a game exists and is still running.
+#[utoipa::path(
+    post,
+    path = "/{game_key}",
+    params(("game_key" = String, Path, description = "Key of the game.")),
+    responses((status = 200, body = GameResponse))
+)]
 async fn test(games: web::Data<Games>, path: web::Path<(String,)>) -> Resp {
     let game_key = GameKey::from(path.into_inner().0).map_err(ErrorUnprocessableEntity)?;
     let url = game_url(&game_key);
@@ -172,14 +315,16 @@ mod test {
     use std::collections::HashMap;
 
     use actix_web::{
+        http::StatusCode,
         test::{self, TestRequest},
         web::Data,
     };
+    use scene::perms::Role;
 
-    use super::{GameResponse, Games, NewGameRequest};
+    use super::{GameResponse, GameStatsResponse, Games, NewGameRequest};
     use crate::{
         api::{routes, Binary},
-        games::{GameHandle, GameKey},
+        games::{ConnectionLimit, GameHandle, GameKey},
         models::{Project, User},
     };
 
@@ -266,6 +411,28 @@ mod test {
         assert!(resp.success);
         assert_eq!(resp.url, client_url);
 
+        // Anyone can see how many players and spectators are connected.
+        let handle = games.read().await.values().next().unwrap().clone();
+        let (send, _host_recv) = tokio::sync::mpsc::unbounded_channel();
+        handle.join(&host, Role::Owner, send, false).unwrap();
+        let (send, _other_recv) = tokio::sync::mpsc::unbounded_channel();
+        handle.join(&other, Role::Spectator, send, false).unwrap();
+        let mut resp: GameStatsResponse;
+        let mut attempts = 0;
+        loop {
+            let req = TestRequest::get().uri(&format!("{url}/stats")).to_request();
+            resp = test::call_and_read_body_json(&app, req).await;
+            if resp.spectators == 1 || attempts == 100 {
+                break;
+            }
+            attempts += 1;
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(resp.success);
+        assert_eq!(resp.active_players, 1);
+        assert_eq!(resp.spectators, 1);
+        assert_eq!(resp.created_at, handle.created_at);
+
         // Host user should not be able to end the game.
         let req = TestRequest::post()
             .uri(&format!("{url}/end"))
@@ -274,5 +441,35 @@ mod test {
         let resp: Binary = test::call_and_read_body_json(&app, req).await;
         assert!(resp.success);
         assert!(!games.clone().read().await.values().next().unwrap().open());
+
+        let req = TestRequest::get().uri(&format!("{url}/stats")).to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+    }
+
+    #[actix_web::test]
+    async fn test_connection_limit() {
+        // Test
+        //   GET /api/game/{game_key} at capacity
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let games: Data<Games> = Data::new(tokio::sync::RwLock::new(HashMap::new()));
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(db.clone()))
+                .app_data(games)
+                .app_data(Data::new(ConnectionLimit::new(0)))
+                .service(routes()),
+        )
+        .await;
+
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let req = TestRequest::get()
+            .uri(&format!("/api/game/{}", GameKey::new().unwrap()))
+            .cookie(user.session(conn).await)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
     }
 }
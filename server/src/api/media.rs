@@ -1,8 +1,9 @@
-use actix_web::{web, HttpResponse};
-use sqlx::SqlitePool;
+use actix_files::NamedFile;
+use actix_web::{error::ErrorNotFound, web, HttpResponse};
+use sqlx::AnyPool;
 use uuid::Uuid;
 
-use super::{res_failure, res_success, Resp};
+use super::{res_failure, res_success, Binary, Resp};
 use crate::{
     fs::{join_relative_path, CONTENT},
     models::{Media, User},
@@ -12,36 +13,49 @@ use crate::{
 
 pub fn routes() -> actix_web::Scope {
     web::scope("/media")
+        .route("", web::get().to(list))
         .route("/list", web::get().to(list))
         .route("/details", web::post().to(update))
         .route("/{uuid}", web::get().to(retrieve))
         .route("/{uuid}", web::delete().to(delete))
+        .route("/{uuid}/file", web::get().to(file))
+        .route("/{uuid}/tags", web::post().to(tags))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(list, update, retrieve, delete, file, tags))]
+pub(super) struct Api;
+
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct MediaItem {
     uuid: String,
     title: String,
     url: String,
     w: f32,
     h: f32,
+    tags: Vec<String>,
 }
 
 impl MediaItem {
     fn from(record: Media) -> Self {
+        Self::with_url(record.display_path().to_string(), record)
+    }
+
+    fn with_url(url: String, record: Media) -> Self {
         Self {
             uuid: format_uuid(record.uuid),
             title: record.title,
-            url: record.relative_path,
+            url,
             w: record.w,
             h: record.h,
+            tags: record.tags,
         }
     }
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct MediaListResponse {
     items: Vec<MediaItem>,
     success: bool,
@@ -56,15 +70,34 @@ impl MediaListResponse {
     }
 }
 
-async fn list(pool: web::Data<SqlitePool>, user: User) -> Resp {
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    tag: Option<String>,
+}
+
+/// List the user's media, optionally only that with a given tag. Also
+/// available at /list.
+#[utoipa::path(
+    get,
+    path = "",
+    params(ListQuery),
+    responses((status = 200, body = MediaListResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list(pool: web::Data<AnyPool>, user: User, query: web::Query<ListQuery>) -> Resp {
     let conn = &mut pool.acquire().await.map_err(e500)?;
     let media = Media::user_media(conn, user.uuid).await.map_err(e500)?;
-    let items = media.into_iter().map(MediaItem::from).collect();
+    let items = media
+        .into_iter()
+        .filter(|m| query.tag.as_ref().is_none_or(|tag| m.has_tag(tag)))
+        .map(MediaItem::from)
+        .collect();
     Ok(HttpResponse::Ok().json(MediaListResponse::new(items)))
 }
 
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct DetailsUpdate {
     uuid: Uuid,
     title: String,
@@ -72,11 +105,14 @@ struct DetailsUpdate {
     h: f32,
 }
 
-async fn update(
-    pool: web::Data<SqlitePool>,
-    user: User,
-    details: web::Json<DetailsUpdate>,
-) -> Resp {
+#[utoipa::path(
+    post,
+    path = "/details",
+    request_body = DetailsUpdate,
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn update(pool: web::Data<AnyPool>, user: User, details: web::Json<DetailsUpdate>) -> Resp {
     let conn = &mut pool.acquire().await.map_err(e500)?;
     Media::update(
         conn,
@@ -92,8 +128,41 @@ async fn update(
     res_success("Media updated.")
 }
 
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct TagsUpdate {
+    tags: Vec<String>,
+}
+
+/// Replace the tags of a media item.
+#[utoipa::path(
+    post,
+    path = "/{uuid}/tags",
+    params(("uuid" = String, Path, description = "UUID of the media item.")),
+    request_body = TagsUpdate,
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn tags(
+    pool: web::Data<AnyPool>,
+    user: User,
+    path: web::Path<(String,)>,
+    update: web::Json<TagsUpdate>,
+) -> Resp {
+    let conn = &mut pool.acquire().await.map_err(e500)?;
+    let uuid = match Uuid::try_parse(&path.into_inner().0) {
+        Ok(uuid) => uuid,
+        _ => return res_failure("Invalid media UUID."),
+    };
+
+    match Media::set_tags(conn, user.uuid, uuid, &update.tags).await {
+        Ok(_) => res_success("Tags updated."),
+        Err(e) => res_failure(&e),
+    }
+}
+
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct MediaItemResponse {
     details: MediaItem,
     success: bool,
@@ -108,9 +177,34 @@ impl MediaItemResponse {
     }
 }
 
+/// Large images are served resized unless `?original=true` is passed.
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FileQuery {
+    #[serde(default)]
+    original: bool,
+}
+
+impl FileQuery {
+    fn path(&self, media: &Media) -> String {
+        if self.original {
+            media.relative_path.clone()
+        } else {
+            media.display_path().to_string()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{uuid}",
+    params(("uuid" = String, Path, description = "UUID of the media item."), FileQuery),
+    responses((status = 200, body = MediaItemResponse))
+)]
 async fn retrieve(
-    pool: web::Data<SqlitePool>,
+    pool: web::Data<AnyPool>,
     path: web::Path<(String,)>,
+    query: web::Query<FileQuery>,
 ) -> impl actix_web::Responder {
     let conn = &mut pool.acquire().await.map_err(e500)?;
     let uuid = match Uuid::try_parse(&path.into_inner().0) {
@@ -122,10 +216,42 @@ async fn retrieve(
         _ => return res_failure("Media not found."),
     };
 
-    Ok(HttpResponse::Ok().json(MediaItemResponse::new(MediaItem::from(media))))
+    let url = query.path(&media);
+    Ok(HttpResponse::Ok().json(MediaItemResponse::new(MediaItem::with_url(url, media))))
 }
 
-async fn delete(pool: web::Data<SqlitePool>, user: User, path: web::Path<(String,)>) -> Resp {
+#[utoipa::path(
+    get,
+    path = "/{uuid}/file",
+    params(("uuid" = Uuid, Path, description = "UUID of the media item."), FileQuery),
+    responses(
+        (status = 200, description = "Image file.", content_type = "image/*", body = [u8]),
+        (status = 404, description = "Media not found."),
+    )
+)]
+async fn file(
+    pool: web::Data<AnyPool>,
+    path: web::Path<(Uuid,)>,
+    query: web::Query<FileQuery>,
+) -> Result<NamedFile, actix_web::Error> {
+    let conn = &mut pool.acquire().await.map_err(e500)?;
+    let media = Media::load(conn, path.into_inner().0)
+        .await
+        .map_err(ErrorNotFound)?;
+
+    NamedFile::open_async(join_relative_path(&CONTENT, query.path(&media)))
+        .await
+        .map_err(|_| ErrorNotFound("Media file not found."))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{uuid}",
+    params(("uuid" = String, Path, description = "UUID of the media item.")),
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn delete(pool: web::Data<AnyPool>, user: User, path: web::Path<(String,)>) -> Resp {
     let conn = &mut pool.acquire().await.map_err(e500)?;
     let uuid = match Uuid::try_parse(&path.into_inner().0) {
         Ok(uuid) => uuid,
@@ -133,9 +259,11 @@ async fn delete(pool: web::Data<SqlitePool>, user: User, path: web::Path<(String
     };
     if let Ok(media) = Media::load(conn, uuid).await {
         if user.uuid == media.user && Media::delete(conn, uuid).await.is_ok() {
-            tokio::fs::remove_file(join_relative_path(&CONTENT, media.relative_path))
-                .await
-                .ok();
+            for path in media.paths() {
+                tokio::fs::remove_file(join_relative_path(&CONTENT, path))
+                    .await
+                    .ok();
+            }
             return res_success("Media deleted successfully.");
         }
     }
@@ -149,7 +277,7 @@ mod test {
 
     use actix_web::{test, web::Data, App};
 
-    use super::{DetailsUpdate, MediaItemResponse, MediaListResponse};
+    use super::{DetailsUpdate, MediaItemResponse, MediaListResponse, TagsUpdate};
     use crate::{
         api::{routes, Binary},
         fs::initialise_database,
@@ -247,4 +375,74 @@ mod test {
         assert_eq!(item.w, 5.);
         assert_eq!(item.h, 8.);
     }
+
+    #[actix_web::test]
+    async fn test_media_tags() {
+        // TEST
+        //   POST /api/media/{uuid}/tags
+        //   GET /api/media?tag={tag}
+
+        let db = initialise_database().await.unwrap();
+        let app =
+            test::init_service(App::new().app_data(Data::new(db.clone())).service(routes())).await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let r1 = Media::prepare(&user, "png", "wall", "TAGS_HASH", 1)
+            .create(conn)
+            .await
+            .unwrap();
+        Media::prepare(&user, "png", "goblin", "TAGS_2_HASH", 1)
+            .create(conn)
+            .await
+            .unwrap();
+
+        // Tag the first item. Tags are normalised.
+        let session = user.session(conn).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/media/{}/tags", format_uuid(r1.uuid)))
+            .cookie(session.clone())
+            .set_json(TagsUpdate {
+                tags: vec![
+                    " Cobblestone".to_string(),
+                    "cobblestone".to_string(),
+                    "".to_string(),
+                    "floor".to_string(),
+                ],
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+
+        // Tags may not contain the separator.
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/media/{}/tags", format_uuid(r1.uuid)))
+            .cookie(session.clone())
+            .set_json(TagsUpdate {
+                tags: vec!["a,b".to_string()],
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+
+        // Filtering by tag only returns the tagged item.
+        let req = test::TestRequest::get()
+            .uri("/api/media?tag=cobblestone")
+            .cookie(session.clone())
+            .to_request();
+        let resp: MediaListResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.items.len(), 1);
+        let item = resp.items.first().unwrap();
+        assert_eq!(item.uuid, format_uuid(r1.uuid));
+        assert_eq!(item.tags, vec!["cobblestone", "floor"]);
+
+        // Without a filter, all items are listed.
+        let req = test::TestRequest::get()
+            .uri("/api/media")
+            .cookie(session)
+            .to_request();
+        let resp: MediaListResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.items.len(), 2);
+    }
 }
@@ -1,26 +1,45 @@
-use actix_web::{cookie::Cookie, http::StatusCode, HttpResponse, HttpResponseBuilder};
+use actix_web::{
+    cookie::{self, Cookie},
+    http::StatusCode,
+    HttpResponse, HttpResponseBuilder,
+};
 
+mod admin;
 mod auth;
+mod docs;
 mod game;
 mod media;
 mod project;
 mod register;
+mod search;
+mod share;
+mod templates;
 mod upload;
+mod user;
+mod webhooks;
 
 pub fn routes() -> actix_web::Scope {
     actix_web::web::scope("/api")
+        .route("/openapi.json", actix_web::web::get().to(docs::spec))
+        .route("/docs", actix_web::web::get().to(docs::swagger_ui))
+        .service(admin::routes())
         .service(auth::routes())
         .service(game::routes())
         .service(project::routes())
         .service(media::routes())
         .service(register::routes())
+        .service(search::routes())
+        .service(share::routes())
+        .service(templates::routes())
         .service(upload::routes())
+        .service(user::routes())
+        .service(webhooks::routes())
 }
 
 type Resp = Result<HttpResponse, actix_web::Error>;
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct Binary {
     message: String,
     success: bool,
@@ -46,8 +65,21 @@ fn cookie_resp(cookie: &str, value: &str) -> HttpResponseBuilder {
     builder
 }
 
+/// Response setting the session cookie. An empty `session` clears it.
 fn session_resp(session: &str) -> HttpResponseBuilder {
-    cookie_resp(crate::req::session::COOKIE_NAME, session)
+    if session.is_empty() {
+        return cookie_resp(crate::req::session::COOKIE_NAME, session);
+    }
+
+    let max_age = cookie::time::Duration::seconds(crate::models::UserSession::MAX_AGE_S);
+    let mut builder = HttpResponse::build(StatusCode::OK);
+    builder.cookie(
+        Cookie::build(crate::req::session::COOKIE_NAME, session)
+            .path("/")
+            .max_age(max_age)
+            .finish(),
+    );
+    builder
 }
 
 fn resp_success(message: &str) -> HttpResponse {
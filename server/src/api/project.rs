@@ -1,32 +1,88 @@
+use actix_files::NamedFile;
 use actix_web::error::{ErrorNotFound, ErrorUnprocessableEntity};
-use actix_web::{error::ErrorInternalServerError, web, HttpResponse};
-use sqlx::SqliteConnection;
+use actix_web::{
+    error::ErrorInternalServerError, middleware::from_fn, web, HttpRequest, HttpResponse,
+};
+use sqlx::AnyConnection;
 use uuid::Uuid;
 
-use super::{res_failure, res_json, res_success, res_unproc, resp_json};
-use crate::models::{Project, Scene, User};
-use crate::req::{e500, Pool};
+use super::{res_failure, res_json, res_success, res_unproc, resp_json, Binary};
+use crate::fs::{join_relative_path, CONTENT};
+use crate::models::{AuditEntry, Project, ProjectTemplate, Scene, SceneSort, SortOrder, User};
+use crate::req::{e500, idempotency::idempotent, Pool};
 use crate::utils::{format_uuid, Res};
 
 pub fn routes() -> actix_web::Scope {
     web::scope("/project")
-        .route("/save", web::post().to(save))
+        .route("/save", web::post().to(save).wrap(from_fn(idempotent)))
         .route("/list", web::get().to(list))
         .route("/new", web::post().to(new))
         .route("/{uuid}", web::get().to(info))
         .route("/{uuid}", web::patch().to(edit_details))
+        .route("/{uuid}/meta", web::put().to(edit_meta))
         .route("/{uuid}/save", web::get().to(get))
+        .route("/{uuid}/scenes", web::get().to(scenes))
         .route("/{uuid}", web::delete().to(delete))
+        .route(
+            "/{uuid}/scene/{scene_uuid}/thumbnail",
+            web::post().to(upload_thumbnail),
+        )
+        .route(
+            "/{uuid}/scene/{scene_uuid}/thumbnail",
+            web::get().to(thumbnail),
+        )
+        .route("/{uuid}/scene/{scene_uuid}/fog", web::get().to(fog))
+        .route("/{uuid}/scene/{scene_uuid}/audit", web::get().to(audit))
+        .route(
+            "/{uuid}/scene/{scene_uuid}/duplicate",
+            web::post().to(duplicate),
+        )
+        .route("/{uuid}/scene/{scene_uuid}/share", web::post().to(share))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    save,
+    list,
+    new,
+    info,
+    edit_details,
+    edit_meta,
+    get,
+    scenes,
+    delete,
+    upload_thumbnail,
+    thumbnail,
+    fog,
+    audit,
+    duplicate,
+    share
+))]
+pub(super) struct Api;
+
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct ProjectResponse {
     message: String,
     success: bool,
     project: ProjectListEntry,
 }
 
+/// Create or update a project from its serialised form.
+#[utoipa::path(
+    post,
+    path = "/save",
+    params(
+        ("X-Idempotency-Key" = Option<Uuid>, Header,
+            description = "Repeated requests with the same key replay the first response."),
+    ),
+    request_body(content = [u8], content_type = "application/octet-stream"),
+    responses(
+        (status = 200, body = ProjectResponse),
+        (status = 422, description = "Failed to decode project."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn save(
     mut pool: Pool,
     user: User,
@@ -48,6 +104,8 @@ async fn save(
     let project = ProjectListEntry {
         uuid: format_uuid(record.uuid),
         title: record.title,
+        description: record.description,
+        tags: record.tags,
         updated_time,
         scene_list,
     };
@@ -60,7 +118,7 @@ async fn save(
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct SceneListEntry {
     uuid: String,
     title: String,
@@ -81,16 +139,18 @@ impl SceneListEntry {
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct ProjectListEntry {
     uuid: String,
     title: String,
+    description: Option<String>,
+    tags: Vec<String>,
     updated_time: u64,
     scene_list: Vec<SceneListEntry>,
 }
 
 impl ProjectListEntry {
-    async fn from(project: Project, conn: &mut SqliteConnection) -> Res<Self> {
+    async fn from(project: Project, conn: &mut AnyConnection) -> Res<Self> {
         let scene_list = project
             .list_scenes(conn)
             .await?
@@ -101,6 +161,8 @@ impl ProjectListEntry {
         Ok(ProjectListEntry {
             uuid: format_uuid(project.uuid),
             title: project.title,
+            description: project.description,
+            tags: project.tags,
             updated_time,
             scene_list,
         })
@@ -108,13 +170,21 @@ impl ProjectListEntry {
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct ProjectListResponse {
     message: String,
     success: bool,
     list: Vec<ProjectListEntry>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/list",
+    responses(
+        (status = 200, body = ProjectListResponse),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn list(mut conn: Pool, user: User) -> Result<HttpResponse, actix_web::Error> {
     let mut projects = Project::list_for_user(conn.acquire(), user.uuid)
         .await
@@ -136,13 +206,13 @@ async fn list(mut conn: Pool, user: User) -> Result<HttpResponse, actix_web::Err
 }
 
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct NewProjectRequest {
     title: String,
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct NewProjectResponse {
     message: String,
     success: bool,
@@ -151,19 +221,55 @@ struct NewProjectResponse {
     url: String,
 }
 
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NewProjectQuery {
+    template: Option<String>,
+}
+
+/// Create a project, optionally from a template.
+#[utoipa::path(
+    post,
+    path = "/new",
+    request_body = NewProjectRequest,
+    params(NewProjectQuery),
+    responses(
+        (status = 200, body = NewProjectResponse),
+        (status = 404, description = "Template not found."),
+        (status = 422, description = "Invalid title."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn new(
     mut conn: Pool,
     user: User,
     req: web::Json<NewProjectRequest>,
+    query: web::Query<NewProjectQuery>,
 ) -> Result<HttpResponse, actix_web::Error> {
     if let Err(e) = Project::validate_title(&req.title) {
         return Err(ErrorUnprocessableEntity(e));
     }
 
-    let project = Project::create(conn.acquire(), &user, &req.title)
+    let conn = conn.acquire();
+    let template = match &query.template {
+        Some(key) => Some(
+            ProjectTemplate::scene(conn, key, user.uuid)
+                .await
+                .map_err(ErrorNotFound)?,
+        ),
+        None => None,
+    };
+
+    let project = Project::create(conn, &user, &req.title)
         .await
         .map_err(e500)?;
 
+    if let Some(scene) = template {
+        let mut proj = project.load(conn).await.map_err(e500)?;
+        proj.scenes.push(scene);
+        Project::save(conn, &user, proj).await.map_err(e500)?;
+    }
+
     let uuid = format_uuid(project.uuid);
     let url = format!("/project/{}", &uuid);
     res_json(NewProjectResponse {
@@ -181,13 +287,23 @@ fn retrieve_uuid_from_path(path: web::Path<(String,)>) -> Result<Uuid, actix_web
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct ProjectInfoResponse {
     success: bool,
     message: String,
     project: ProjectListEntry,
 }
 
+#[utoipa::path(
+    get,
+    path = "/{uuid}",
+    params(("uuid" = String, Path, description = "UUID of the project.")),
+    responses(
+        (status = 200, body = ProjectInfoResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn info(
     mut conn: Pool,
     user: User,
@@ -212,6 +328,17 @@ async fn info(
     })
 }
 
+/// Serialised project data.
+#[utoipa::path(
+    get,
+    path = "/{uuid}/save",
+    params(("uuid" = String, Path, description = "UUID of the project.")),
+    responses(
+        (status = 200, description = "Serialised project.", content_type = "application/octet-stream", body = [u8]),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn get(
     mut conn: Pool,
     user: User,
@@ -231,6 +358,98 @@ async fn get(
     Ok(HttpResponse::Ok().body(data))
 }
 
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SceneQuery {
+    q: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    #[serde(default)]
+    #[param(inline)]
+    sort: SceneSort,
+    #[serde(default)]
+    #[param(inline)]
+    order: SortOrder,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SceneListResponse {
+    success: bool,
+    message: String,
+    scenes: Vec<SceneListEntry>,
+
+    /// Number of matching scenes, across all pages.
+    total: u32,
+    limit: u32,
+    offset: u32,
+}
+
+/// List a page of the scenes in a project, optionally only those with titles
+/// matching a query.
+#[utoipa::path(
+    get,
+    path = "/{uuid}/scenes",
+    params(("uuid" = String, Path, description = "UUID of the project."), SceneQuery),
+    responses(
+        (status = 200, body = SceneListResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn scenes(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(String,)>,
+    query: web::Query<SceneQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    const DEFAULT_LIMIT: u32 = 20;
+    const MAX_LIMIT: u32 = 100;
+
+    let project = match Project::lookup(conn.acquire(), retrieve_uuid_from_path(path)?).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return Err(ErrorNotFound("Project does not exist.")),
+        Err(e) => return Err(e500(e)),
+    };
+
+    if project.user != user.uuid {
+        return res_failure("Project not found.");
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let (scenes, total) = Scene::search_titles(
+        conn.acquire(),
+        project.uuid,
+        query.q.as_deref().unwrap_or(""),
+        query.sort,
+        query.order,
+        limit,
+        offset,
+    )
+    .await
+    .map_err(e500)?;
+
+    res_json(SceneListResponse {
+        success: true,
+        message: "Scene list follows.".to_string(),
+        scenes: scenes.into_iter().map(SceneListEntry::from).collect(),
+        total,
+        limit,
+        offset,
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{uuid}",
+    params(("uuid" = String, Path, description = "UUID of the project.")),
+    responses(
+        (status = 200, body = Binary),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn delete(
     mut conn: Pool,
     user: User,
@@ -251,11 +470,23 @@ async fn delete(
 }
 
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct ProjectDetailsRequest {
     title: String,
 }
 
+/// Update the title of a project.
+#[utoipa::path(
+    patch,
+    path = "/{uuid}",
+    params(("uuid" = Uuid, Path, description = "UUID of the project.")),
+    request_body = ProjectDetailsRequest,
+    responses(
+        (status = 200, body = ProjectInfoResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
 async fn edit_details(
     mut conn: Pool,
     user: User,
@@ -282,6 +513,8 @@ async fn edit_details(
             project: ProjectListEntry {
                 uuid: format_uuid(proj.uuid),
                 title: proj.title.clone(),
+                description: proj.description.clone(),
+                tags: proj.tags.clone(),
                 updated_time: proj.updated_timestamp(),
                 scene_list: scenes.into_iter().map(SceneListEntry::from).collect(),
             },
@@ -290,6 +523,379 @@ async fn edit_details(
         .map_err(e500)
 }
 
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct ProjectMetaRequest {
+    description: Option<String>,
+    tags: Vec<String>,
+}
+
+/// Update the description and tags of a project.
+#[utoipa::path(
+    put,
+    path = "/{uuid}/meta",
+    params(("uuid" = Uuid, Path, description = "UUID of the project.")),
+    request_body = ProjectMetaRequest,
+    responses(
+        (status = 200, body = ProjectInfoResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn edit_meta(
+    mut conn: Pool,
+    user: User,
+    req: web::Json<ProjectMetaRequest>,
+    path: web::Path<(Uuid,)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let conn = conn.acquire();
+    let project = Project::get_by_uuid(conn, path.into_inner().0)
+        .await
+        .map_err(ErrorNotFound)?;
+
+    if project.user != user.uuid {
+        return Err(ErrorNotFound("Project not found."));
+    }
+
+    let project =
+        match Project::set_meta(conn, project.uuid, req.description.as_deref(), &req.tags).await {
+            Ok(project) => project,
+            Err(e) => return res_failure(e),
+        };
+
+    res_json(ProjectInfoResponse {
+        success: true,
+        message: "Project info follows.".to_string(),
+        project: ProjectListEntry::from(project, conn).await.map_err(e500)?,
+    })
+}
+
+/// Look up a scene in a project, failing with 404 if either doesn't exist or
+/// the project isn't owned by `user`.
+pub(super) async fn user_scene(
+    conn: &mut AnyConnection,
+    user: &User,
+    project: Uuid,
+    scene: Uuid,
+) -> Result<Scene, actix_web::Error> {
+    match Project::lookup(conn, project).await {
+        Ok(Some(record)) if record.user == user.uuid => {}
+        Ok(_) => return Err(ErrorNotFound("Project not found.")),
+        Err(e) => return Err(e500(e)),
+    };
+
+    match Scene::get_by_uuid(conn, scene).await {
+        Ok(record) if record.project == project => Ok(record),
+        _ => Err(ErrorNotFound("Scene not found.")),
+    }
+}
+
+/// Set the thumbnail of a scene from a JPEG or PNG image.
+#[utoipa::path(
+    post,
+    path = "/{uuid}/scene/{scene_uuid}/thumbnail",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+    ),
+    request_body(content = [u8], content_type = "image/png"),
+    responses(
+        (status = 200, body = super::upload::UploadResponse),
+        (status = 404, description = "Project or scene not found."),
+        (status = 422, description = "Missing or unsupported image."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn upload_thumbnail(
+    mut conn: Pool,
+    user: User,
+    req: HttpRequest,
+    path: web::Path<(Uuid, Uuid)>,
+    body: bytes::Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (project, scene) = path.into_inner();
+    let conn = conn.acquire();
+    let scene = user_scene(conn, &user, project, scene).await?;
+
+    let ext = match req.headers().get("content-type").map(|v| v.to_str()) {
+        Some(Ok("image/jpeg")) => "jpg",
+        Some(Ok("image/png")) => "png",
+        _ => return res_unproc("Thumbnail must be a JPEG or PNG image."),
+    };
+
+    if body.is_empty() {
+        return res_unproc("Missing thumbnail image.");
+    }
+
+    super::upload::save_thumbnail(conn, &user, body.to_vec(), scene.uuid, ext)
+        .await
+        .map(resp_json)
+        .map_err(e500)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{uuid}/scene/{scene_uuid}/thumbnail",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+    ),
+    responses(
+        (status = 200, description = "Thumbnail image.", content_type = "image/*", body = [u8]),
+        (status = 404, description = "Project or scene not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn thumbnail(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<NamedFile, actix_web::Error> {
+    let (project, scene) = path.into_inner();
+    let scene = user_scene(conn.acquire(), &user, project, scene).await?;
+
+    let Some(thumbnail) = scene.thumbnail else {
+        return Err(ErrorNotFound("Scene has no thumbnail."));
+    };
+
+    NamedFile::open_async(join_relative_path(&CONTENT, thumbnail))
+        .await
+        .map_err(|_| ErrorNotFound("Thumbnail not found."))
+}
+
+/// Fog of war of a scene, as last saved, as a greyscale PNG mask with a
+/// pixel per tile.
+#[utoipa::path(
+    get,
+    path = "/{uuid}/scene/{scene_uuid}/fog",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+    ),
+    responses(
+        (status = 200, description = "Fog image.", content_type = "image/png", body = [u8]),
+        (status = 404, description = "Project or scene not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn fog(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (project, scene) = path.into_inner();
+    let conn = conn.acquire();
+    let scene = user_scene(conn, &user, project, scene).await?;
+
+    let project = Project::get_by_uuid(conn, project)
+        .await
+        .map_err(ErrorNotFound)?
+        .load(conn)
+        .await
+        .map_err(e500)?;
+    let Some(scene) = project.get_scene(scene.uuid) else {
+        return Err(ErrorNotFound("Scene not found."));
+    };
+
+    let png = scene.fog.to_png().map_err(e500)?;
+    Ok(HttpResponse::Ok().content_type("image/png").body(png))
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct DuplicateSceneResponse {
+    message: String,
+    success: bool,
+    uuid: String,
+}
+
+/// Copy a scene, as last saved, into a new scene in the same project.
+#[utoipa::path(
+    post,
+    path = "/{uuid}/scene/{scene_uuid}/duplicate",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+    ),
+    responses(
+        (status = 200, body = DuplicateSceneResponse),
+        (status = 404, description = "Project or scene not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn duplicate(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (project, scene) = path.into_inner();
+    let conn = conn.acquire();
+    let scene = user_scene(conn, &user, project, scene).await?;
+
+    let mut project = Project::get_by_uuid(conn, project)
+        .await
+        .map_err(ErrorNotFound)?
+        .load(conn)
+        .await
+        .map_err(e500)?;
+    let Some(mut copy) = project.get_scene(scene.uuid).cloned() else {
+        return Err(ErrorNotFound("Scene not found."));
+    };
+
+    // A nil UUID has no record so a fresh one will be generated on save.
+    copy.uuid = Uuid::nil();
+    copy.title = format!("{} (copy)", copy.title);
+    project.scenes.push(copy);
+
+    let (_, scenes) = Project::save(conn, &user, project).await.map_err(e500)?;
+    let Some(record) = scenes.last() else {
+        return Err(e500("Failed to save scene."));
+    };
+
+    Ok(resp_json(DuplicateSceneResponse {
+        message: "Scene duplicated.".to_string(),
+        success: true,
+        uuid: format_uuid(record.uuid),
+    }))
+}
+
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ShareQuery {
+    ttl_days: Option<u32>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct ShareSceneResponse {
+    message: String,
+    success: bool,
+    token: String,
+    url: String,
+}
+
+/// Create a link allowing anyone to view a scene, read-only.
+#[utoipa::path(
+    post,
+    path = "/{uuid}/scene/{scene_uuid}/share",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+        ShareQuery,
+    ),
+    responses(
+        (status = 200, body = ShareSceneResponse),
+        (status = 404, description = "Project or scene not found."),
+        (status = 422, description = "Invalid link duration."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn share(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(Uuid, Uuid)>,
+    query: web::Query<ShareQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    const DEFAULT_TTL_DAYS: u32 = 7;
+    const MAX_TTL_DAYS: u32 = 365;
+    const DAY_S: i64 = 60 * 60 * 24;
+
+    let (project, scene) = path.into_inner();
+    let conn = conn.acquire();
+    let scene = user_scene(conn, &user, project, scene).await?;
+
+    let ttl_days = query.ttl_days.unwrap_or(DEFAULT_TTL_DAYS);
+    if ttl_days == 0 || ttl_days > MAX_TTL_DAYS {
+        return res_unproc(&format!(
+            "Links must last between 1 and {MAX_TTL_DAYS} days."
+        ));
+    }
+
+    let token = Scene::share(conn, scene.uuid, ttl_days as i64 * DAY_S)
+        .await
+        .map_err(e500)?;
+    Ok(resp_json(ShareSceneResponse {
+        message: "Scene shared.".to_string(),
+        success: true,
+        url: format!("/share/{token}"),
+        token,
+    }))
+}
+
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AuditQuery {
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct AuditLogEntry {
+    user: String,
+    event_type: String,
+    #[schema(value_type = Object)]
+    event: serde_json::Value,
+    timestamp: i64,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct AuditLogResponse {
+    message: String,
+    success: bool,
+    entries: Vec<AuditLogEntry>,
+}
+
+/// Events applied to a scene in games, most recent first.
+#[utoipa::path(
+    get,
+    path = "/{uuid}/scene/{scene_uuid}/audit",
+    params(
+        ("uuid" = Uuid, Path, description = "UUID of the project."),
+        ("scene_uuid" = Uuid, Path, description = "UUID of the scene."),
+        AuditQuery,
+    ),
+    responses(
+        (status = 200, body = AuditLogResponse),
+        (status = 404, description = "Project or scene not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn audit(
+    mut conn: Pool,
+    user: User,
+    path: web::Path<(Uuid, Uuid)>,
+    query: web::Query<AuditQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    const DEFAULT_LIMIT: u32 = 100;
+    const MAX_LIMIT: u32 = 1000;
+
+    let (project, scene) = path.into_inner();
+    let conn = conn.acquire();
+    let scene = user_scene(conn, &user, project, scene).await?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let entries = AuditEntry::list(conn, scene.uuid, limit, offset)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|entry| AuditLogEntry {
+            user: format_uuid(entry.user),
+            event_type: entry.event_type,
+            event: serde_json::from_str(&entry.event_json).unwrap_or_default(),
+            timestamp: entry.timestamp,
+        })
+        .collect();
+
+    Ok(resp_json(AuditLogResponse {
+        message: "Audit log follows.".to_string(),
+        success: true,
+        entries,
+    }))
+}
+
 #[cfg(test)]
 mod test {
     use actix_web::{
@@ -299,13 +905,15 @@ mod test {
     };
 
     use super::{
-        NewProjectRequest, NewProjectResponse, ProjectDetailsRequest, ProjectInfoResponse,
-        ProjectListResponse, ProjectResponse,
+        AuditLogResponse, DuplicateSceneResponse, NewProjectRequest, NewProjectResponse,
+        ProjectDetailsRequest, ProjectInfoResponse, ProjectListResponse, ProjectMetaRequest,
+        ProjectResponse, SceneListResponse,
     };
     use crate::{
         api::Binary,
-        models::{User, UserAuth},
-        utils::{format_uuid, generate_uuid},
+        models::{AuditEntry, CachedResponse, Project, Reservation, Scene, User, UserAuth},
+        req::idempotency::HEADER,
+        utils::{format_uuid, generate_uuid, parse_uuid},
     };
 
     #[actix_web::test]
@@ -474,4 +1082,545 @@ mod test {
         assert!(resp.success);
         assert_eq!(resp.list.len(), 0);
     }
+
+    #[actix_web::test]
+    async fn test_scene_thumbnail() {
+        // Test
+        //   POST /api/project/{uuid}/scene/{scene_uuid}/thumbnail
+        //   GET /api/project/{uuid}/scene/{scene_uuid}/thumbnail
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "My Project").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        let (_, scenes) = Project::save(conn, &user, proj).await.unwrap();
+        let scene = scenes.first().unwrap().uuid;
+        let url = format!(
+            "/api/project/{}/scene/{}/thumbnail",
+            format_uuid(project.uuid),
+            format_uuid(scene)
+        );
+
+        // No thumbnail has been uploaded yet.
+        let req = TestRequest::get()
+            .uri(&url)
+            .cookie(session.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Other users shouldn't be able to upload a thumbnail.
+        let image_data: Vec<u8> = (0..=255).cycle().take(1024).collect();
+        let other = User::generate(conn).await;
+        let req = TestRequest::post()
+            .uri(&url)
+            .cookie(other.session(conn).await)
+            .append_header(("Content-Type", "image/jpeg"))
+            .set_payload(image_data.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Upload should fail without an image content type.
+        let req = TestRequest::post()
+            .uri(&url)
+            .cookie(session.clone())
+            .append_header(("Content-Type", "text/plain"))
+            .set_payload(image_data.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = TestRequest::post()
+            .uri(&url)
+            .cookie(session.clone())
+            .append_header(("Content-Type", "image/jpeg"))
+            .set_payload(image_data.clone())
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        let record = Scene::get_by_uuid(conn, scene).await.unwrap();
+        assert!(record.thumbnail.unwrap().ends_with(".jpg"));
+
+        // Thumbnail should now be served.
+        let req = TestRequest::get()
+            .uri(&url)
+            .cookie(session.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("Content-Type").unwrap(), "image/jpeg");
+        assert_eq!(test::read_body(resp).await, image_data);
+    }
+
+    #[actix_web::test]
+    async fn test_scene_fog() {
+        // Test
+        //   GET /api/project/{uuid}/scene/{scene_uuid}/fog
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "My Project").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        let (_, scenes) = Project::save(conn, &user, proj).await.unwrap();
+        let scene = scenes.first().unwrap().uuid;
+        let url = format!(
+            "/api/project/{}/scene/{}/fog",
+            format_uuid(project.uuid),
+            format_uuid(scene)
+        );
+
+        // Other users shouldn't be able to see the fog.
+        let other = User::generate(conn).await;
+        let req = TestRequest::get()
+            .uri(&url)
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::get().uri(&url).cookie(session).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get("Content-Type").unwrap(), "image/png");
+        assert!(test::read_body(resp).await.starts_with(b"\x89PNG"));
+    }
+
+    #[actix_web::test]
+    async fn test_scene_title_search() {
+        // Test
+        //   GET /api/project/{uuid}/scenes?q={query}
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "My Project").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        for title in ["Crystal Cave", "Town", "Cave_2"] {
+            proj.new_scene();
+            proj.scenes.last_mut().unwrap().title = title.to_string();
+        }
+        Project::save(conn, &user, proj).await.unwrap();
+
+        let search = |q: &str| {
+            TestRequest::get()
+                .uri(&format!(
+                    "/api/project/{}/scenes?q={q}",
+                    format_uuid(project.uuid)
+                ))
+                .cookie(session.clone())
+                .to_request()
+        };
+
+        // Search is case-insensitive.
+        let resp: SceneListResponse = test::call_and_read_body_json(&app, search("cAvE")).await;
+        assert!(resp.success);
+        let titles: Vec<String> = resp.scenes.into_iter().map(|s| s.title).collect();
+        assert_eq!(titles, vec!["Cave_2", "Crystal Cave"]);
+
+        // LIKE wildcards in the query are matched literally.
+        let resp: SceneListResponse = test::call_and_read_body_json(&app, search("e_")).await;
+        assert_eq!(resp.scenes.len(), 1);
+        let resp: SceneListResponse = test::call_and_read_body_json(&app, search("%25")).await;
+        assert!(resp.scenes.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_scene_list_pagination() {
+        // Test
+        //   GET /api/project/{uuid}/scenes?limit={}&offset={}&sort={}&order={}
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "My Project").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        for title in ["C", "A", "E", "B", "D"] {
+            proj.new_scene();
+            proj.scenes.last_mut().unwrap().title = title.to_string();
+        }
+        let (_, scenes) = Project::save(conn, &user, proj).await.unwrap();
+
+        // Scenes were last modified in the order they were created.
+        for (i, scene) in scenes.iter().enumerate() {
+            sqlx::query("UPDATE scenes SET updated_time = $1 WHERE uuid = $2;")
+                .bind(i as i64)
+                .bind(format_uuid(scene.uuid))
+                .execute(&mut **conn)
+                .await
+                .unwrap();
+        }
+
+        let list = |params: &str| {
+            let req = TestRequest::get()
+                .uri(&format!(
+                    "/api/project/{}/scenes?{params}",
+                    format_uuid(project.uuid)
+                ))
+                .cookie(session.clone())
+                .to_request();
+            test::call_service(&app, req)
+        };
+        let titles = |resp: SceneListResponse| -> Vec<String> {
+            resp.scenes.into_iter().map(|s| s.title).collect()
+        };
+
+        let resp: SceneListResponse = test::read_body_json(list("limit=2").await).await;
+        assert_eq!((resp.total, resp.limit, resp.offset), (5, 2, 0));
+        assert_eq!(titles(resp), vec!["A", "B"]);
+
+        let resp: SceneListResponse = test::read_body_json(list("limit=2&offset=4").await).await;
+        assert_eq!(resp.total, 5);
+        assert_eq!(titles(resp), vec!["E"]);
+
+        let resp: SceneListResponse =
+            test::read_body_json(list("limit=3&sort=title&order=desc").await).await;
+        assert_eq!(titles(resp), vec!["E", "D", "C"]);
+
+        let resp: SceneListResponse =
+            test::read_body_json(list("sort=updated&order=desc").await).await;
+        assert_eq!(titles(resp), vec!["D", "B", "E", "A", "C"]);
+
+        // Search applies to the total as well.
+        let resp: SceneListResponse = test::read_body_json(list("q=a&limit=1").await).await;
+        assert_eq!(resp.total, 1);
+
+        // Only known fields may be sorted by.
+        let resp = list("sort=uuid").await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_project_meta() {
+        // Test
+        //   PUT /api/project/{uuid}/meta
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "My Project").await.unwrap();
+        assert!(project.description.is_none());
+        assert!(project.tags.is_empty());
+        let url = format!("/api/project/{}/meta", format_uuid(project.uuid));
+
+        // Other users can't edit the project.
+        let other = User::generate(conn).await;
+        let req = TestRequest::put()
+            .uri(&url)
+            .cookie(other.session(conn).await)
+            .set_json(ProjectMetaRequest {
+                description: None,
+                tags: vec![],
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::put()
+            .uri(&url)
+            .cookie(session.clone())
+            .set_json(ProjectMetaRequest {
+                description: Some("A one-shot in the sewers.".to_string()),
+                tags: vec!["One-Shot".to_string(), " horror".to_string()],
+            })
+            .to_request();
+        let resp: ProjectInfoResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(
+            resp.project.description.as_deref(),
+            Some("A one-shot in the sewers.")
+        );
+        assert_eq!(resp.project.tags, vec!["one-shot", "horror"]);
+
+        // Metadata is included in the project list.
+        let req = TestRequest::get()
+            .uri("/api/project/list")
+            .cookie(session)
+            .to_request();
+        let resp: ProjectListResponse = test::call_and_read_body_json(&app, req).await;
+        let record = resp.list.first().unwrap();
+        assert_eq!(record.tags, vec!["one-shot", "horror"]);
+    }
+
+    #[actix_web::test]
+    async fn test_scene_audit_log() {
+        // Test
+        //   GET /api/project/{uuid}/scene/{scene_uuid}/audit
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "Audited").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        Project::save(conn, &user, proj).await.unwrap();
+        let scene = project.list_scenes(conn).await.unwrap().remove(0);
+        let entries: Vec<_> = ["One", "Two", "Three"]
+            .into_iter()
+            .map(|title| {
+                let event = scene::comms::SceneEvent::SceneTitle(String::new(), title.to_string());
+                (scene.uuid, user.uuid, event)
+            })
+            .collect();
+        AuditEntry::record_all(conn, &entries).await.unwrap();
+
+        let uri = |query: &str| {
+            format!(
+                "/api/project/{}/scene/{}/audit{query}",
+                format_uuid(project.uuid),
+                format_uuid(scene.uuid)
+            )
+        };
+
+        let req = TestRequest::get()
+            .uri(&uri(""))
+            .cookie(session.clone())
+            .to_request();
+        let resp: AuditLogResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.entries.len(), 3);
+        assert_eq!(resp.entries[0].event_type, "SceneTitle");
+        assert_eq!(resp.entries[0].user, format_uuid(user.uuid));
+
+        // Most recent entries come first.
+        let req = TestRequest::get()
+            .uri(&uri("?limit=1&offset=1"))
+            .cookie(session.clone())
+            .to_request();
+        let resp: AuditLogResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.entries.len(), 1);
+        assert_eq!(
+            resp.entries[0].event,
+            serde_json::json!({ "SceneTitle": ["", "Two"] })
+        );
+
+        // Only the project owner can read the log.
+        let other = User::generate(conn).await;
+        let req = TestRequest::get()
+            .uri(&uri(""))
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_duplicate_scene() {
+        // Test
+        //   POST /api/project/{uuid}/scene/{scene_uuid}/duplicate
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        let mut scene = scene::Scene::new(generate_uuid());
+        scene.title = "Session 1".to_string();
+        let layer = scene.first_layer();
+        let sprite = scene.new_sprite(None, layer).unwrap().item().unwrap();
+        proj.scenes.push(scene);
+        Project::save(conn, &user, proj).await.unwrap();
+        let original = project.list_scenes(conn).await.unwrap().remove(0);
+
+        let uri = format!(
+            "/api/project/{}/scene/{}/duplicate",
+            format_uuid(project.uuid),
+            format_uuid(original.uuid)
+        );
+        let req = TestRequest::post()
+            .uri(&uri)
+            .cookie(session.clone())
+            .to_request();
+        let resp: DuplicateSceneResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        let uuid = parse_uuid(&resp.uuid).unwrap();
+        assert_ne!(uuid, original.uuid);
+
+        let proj = project.load(conn).await.unwrap();
+        assert_eq!(proj.scenes.len(), 2);
+        let copy = proj.get_scene(uuid).unwrap();
+        assert_eq!(copy.title, "Session 1 (copy)");
+        assert_eq!(copy.first_layer(), layer);
+        assert!(copy.sprite_ref(sprite).is_some());
+        assert_eq!(proj.get_scene(original.uuid).unwrap().title, "Session 1");
+        assert_eq!(
+            Scene::get_by_uuid(conn, uuid).await.unwrap().project,
+            project.uuid
+        );
+
+        // Only the project owner can duplicate scenes.
+        let other = User::generate(conn).await;
+        let req = TestRequest::post()
+            .uri(&uri)
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_idempotent_save() {
+        // Test
+        //   POST /api/project/save with X-Idempotency-Key
+
+        let db = crate::fs::initialise_database().await.unwrap();
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.scenes.push(scene::Scene::new(generate_uuid()));
+
+        let key = format_uuid(generate_uuid());
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(session.clone())
+            .insert_header((HEADER, key.clone()))
+            .set_payload(scene::serde::serialise(&proj).unwrap())
+            .to_request();
+        let first: ProjectResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(first.success);
+        assert_eq!(first.project.scene_list.len(), 1);
+
+        // Retrying with the same key replays the response without saving.
+        proj.scenes.push(scene::Scene::new(generate_uuid()));
+        let data = scene::serde::serialise(&proj).unwrap();
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(session.clone())
+            .insert_header((HEADER, key.clone()))
+            .set_payload(data.clone())
+            .to_request();
+        let resp: ProjectResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.project.scene_list.len(), 1);
+        assert_eq!(resp.project.updated_time, first.project.updated_time);
+        assert_eq!(project.list_scenes(conn).await.unwrap().len(), 1);
+
+        // Keys are per user, so the same key from another user is handled,
+        // saving a copy of the project.
+        let other = User::generate(conn).await;
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(other.session(conn).await)
+            .insert_header((HEADER, key))
+            .set_payload(data.clone())
+            .to_request();
+        let resp: ProjectResponse = test::call_and_read_body_json(&app, req).await;
+        assert_ne!(resp.project.uuid, first.project.uuid);
+        assert_eq!(resp.project.scene_list.len(), 2);
+
+        // A new key saves again.
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(session.clone())
+            .insert_header((HEADER, format_uuid(generate_uuid())))
+            .set_payload(data.clone())
+            .to_request();
+        let resp: ProjectResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.project.scene_list.len(), 2);
+        assert_eq!(project.list_scenes(conn).await.unwrap().len(), 2);
+
+        // Keys are reserved while a request is handled, so a concurrent retry
+        // is refused rather than saving again.
+        let pending = generate_uuid();
+        assert!(matches!(
+            CachedResponse::reserve(conn, user.uuid, pending, "POST /api/project/save").await,
+            Ok(Reservation::Reserved)
+        ));
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(session.clone())
+            .insert_header((HEADER, format_uuid(pending)))
+            .set_payload(data.clone())
+            .to_request();
+        let err = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(err.as_response_error().status_code(), StatusCode::CONFLICT);
+        assert_eq!(project.list_scenes(conn).await.unwrap().len(), 2);
+
+        // Keys are also per endpoint.
+        assert!(matches!(
+            CachedResponse::reserve(conn, user.uuid, pending, "POST /api/upload").await,
+            Ok(Reservation::Reserved)
+        ));
+
+        // Keys must be UUIDs.
+        let req = TestRequest::post()
+            .uri("/api/project/save")
+            .cookie(session)
+            .insert_header((HEADER, "retry-1"))
+            .set_payload(data)
+            .to_request();
+        let err = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
 }
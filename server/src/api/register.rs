@@ -1,5 +1,5 @@
 use actix_web::{error::ErrorInternalServerError, web};
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
 use super::{res_json, Resp};
 use crate::{
@@ -12,15 +12,19 @@ pub fn routes() -> actix_web::Scope {
     actix_web::web::scope("/register").default_service(web::post().to(register))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(register))]
+pub(super) struct Api;
+
 #[cfg_attr(test, derive(serde_derive::Serialize))]
-#[derive(serde_derive::Deserialize)]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
 struct RegistrationRequest {
     username: String,
     password: String,
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
 struct RegistrationResponse {
     message: String,
     recovery_key: Option<String>,
@@ -58,7 +62,7 @@ fn valid_username(username: &str) -> bool {
 
 // Passwords are 8 or more characters with at least one letter and at least one
 // number
-fn valid_password(password: &str) -> bool {
+pub(super) fn valid_password(password: &str) -> bool {
     password.chars().any(char::is_numeric)
         && password.chars().any(char::is_alphabetic)
         && password.len() >= 8
@@ -71,7 +75,13 @@ fn generate_keys(password: &str) -> Res<(Key, Key, Key)> {
     Ok((salt, hashed_password, recovery_key))
 }
 
-async fn register(pool: web::Data<SqlitePool>, details: web::Json<RegistrationRequest>) -> Resp {
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = RegistrationRequest,
+    responses((status = 200, body = RegistrationResponse))
+)]
+async fn register(pool: web::Data<AnyPool>, details: web::Json<RegistrationRequest>) -> Resp {
     if !valid_username(&details.username) {
         return RegistrationResponse::failure("Invalid username.", "username");
     }
@@ -0,0 +1,141 @@
+use actix_web::web;
+
+use super::{res_json, Resp};
+use crate::{
+    models::{Scene, User},
+    req::{e500, Pool},
+    utils::format_uuid,
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/search").route("/scene", web::get().to(scene))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(scene))]
+pub(super) struct Api;
+
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    q: String,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SceneSearchEntry {
+    project_uuid: String,
+    scene_uuid: String,
+    title: String,
+    matches: Vec<String>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SceneSearchResponse {
+    success: bool,
+    message: String,
+    results: Vec<SceneSearchEntry>,
+}
+
+/// Search the labels of the user's scenes.
+#[utoipa::path(
+    get,
+    path = "/scene",
+    params(SearchQuery),
+    responses((status = 200, body = SceneSearchResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn scene(mut conn: Pool, user: User, query: web::Query<SearchQuery>) -> Resp {
+    let results = Scene::search_labels(conn.acquire(), user.uuid, &query.q)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|result| SceneSearchEntry {
+            project_uuid: format_uuid(result.project),
+            scene_uuid: format_uuid(result.scene),
+            title: result.title,
+            matches: result.matches,
+        })
+        .collect();
+
+    res_json(SceneSearchResponse {
+        success: true,
+        message: "Search results follow.".to_string(),
+        results,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, web::Data, App};
+
+    use super::SceneSearchResponse;
+    use crate::{
+        fs::initialise_database,
+        models::{Project, User},
+    };
+
+    #[actix_web::test]
+    async fn test_scene_search() {
+        // TEST
+        //   GET /api/search/scene?q={query}
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        // Create a project with a couple of scenes, one of which has a
+        // labelled sprite.
+        let user = User::generate(conn).await;
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        proj.new_scene();
+        proj.scenes[1].title = "Tavern".to_string();
+        let scene = proj.scenes.first_mut().unwrap();
+        scene.title = "Dragon's Lair".to_string();
+        let layer = scene.first_layer();
+        let (drawing, _) = scene.start_drawing(scene::DrawingMode::Text, scene::Point::ORIGIN);
+        scene.new_sprite(
+            Some(scene::SpriteVisual::Drawing {
+                drawing,
+                colour: scene::Colour::DEFAULT,
+                stroke: scene::Sprite::DEFAULT_STROKE,
+                cap_start: scene::Cap::Round,
+                cap_end: scene::Cap::Round,
+                taper_start: false,
+                taper_end: false,
+            }),
+            layer,
+        );
+        scene.annotate_drawing(drawing, "Red Dragon".to_string(), scene::Point::ORIGIN);
+        Project::save(conn, &user, proj).await.unwrap();
+
+        let session = user.session(conn).await;
+        let req = test::TestRequest::get()
+            .uri("/api/search/scene?q=drag")
+            .cookie(session.clone())
+            .to_request();
+        let resp: SceneSearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.results.len(), 1);
+        let result = resp.results.first().unwrap();
+        assert_eq!(result.title, "Dragon's Lair");
+        assert_eq!(result.matches, vec!["Red Dragon"]);
+
+        // Other users' scenes aren't searched.
+        let other = User::generate(conn).await;
+        let req = test::TestRequest::get()
+            .uri("/api/search/scene?q=dragon")
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp: SceneSearchResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.results.is_empty());
+    }
+}
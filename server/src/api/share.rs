@@ -0,0 +1,161 @@
+use actix_web::{error::ErrorNotFound, web, HttpResponse};
+
+use crate::{
+    models::{Project, Scene},
+    req::{e500, Pool},
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/share").route("/{token}", web::get().to(shared))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(shared))]
+pub(super) struct Api;
+
+/// Serialised project containing only the shared scene. Doesn't require a
+/// session; possession of an unexpired token is sufficient.
+#[utoipa::path(
+    get,
+    path = "/{token}",
+    params(("token" = String, Path, description = "Token from /api/project/{uuid}/scene/{scene_uuid}/share.")),
+    responses(
+        (status = 200, description = "Serialised project.", content_type = "application/octet-stream", body = [u8]),
+        (status = 404, description = "Invalid or expired link."),
+    )
+)]
+async fn shared(
+    mut conn: Pool,
+    path: web::Path<(String,)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let conn = conn.acquire();
+    let Some(scene) = Scene::shared(conn, &path.into_inner().0)
+        .await
+        .map_err(e500)?
+    else {
+        return Err(ErrorNotFound("Invalid or expired link."));
+    };
+
+    let project = Project::get_by_uuid(conn, scene.project)
+        .await
+        .map_err(ErrorNotFound)?
+        .load(conn)
+        .await
+        .map_err(e500)?;
+    let Some(shared) = project.get_scene(scene.uuid) else {
+        return Err(ErrorNotFound("Scene not found."));
+    };
+
+    let mut shared = shared.clone();
+    shared.strip_private();
+
+    let mut view = scene::Project::new(project.uuid);
+    view.title = project.title.clone();
+    view.scenes.push(shared);
+    let data = scene::serde::serialise(&view).map_err(e500)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web::Data,
+        App,
+    };
+
+    use crate::{
+        fs::initialise_database,
+        models::{Project, Scene, User},
+        utils::{format_uuid, generate_uuid},
+    };
+
+    #[derive(serde_derive::Deserialize)]
+    struct ShareSceneResponse {
+        success: bool,
+        token: String,
+        url: String,
+    }
+
+    #[actix_web::test]
+    async fn test_share_scene() {
+        // TEST
+        //   POST /api/project/{uuid}/scene/{scene_uuid}/share
+        //   GET /api/share/{token}
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "Shared").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        for title in ["Public", "Secret"] {
+            let mut scene = scene::Scene::new(generate_uuid());
+            scene.title = title.to_string();
+            proj.scenes.push(scene);
+        }
+        Project::save(conn, &user, proj).await.unwrap();
+        let scenes = project.list_scenes(conn).await.unwrap();
+        let public = scenes.iter().find(|s| s.title == "Public").unwrap();
+
+        let uri = |query: &str| {
+            format!(
+                "/api/project/{}/scene/{}/share{query}",
+                format_uuid(project.uuid),
+                format_uuid(public.uuid)
+            )
+        };
+        let req = TestRequest::post()
+            .uri(&uri("?ttl_days=7"))
+            .cookie(session.clone())
+            .to_request();
+        let resp: ShareSceneResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.url, format!("/share/{}", resp.token));
+
+        // Anyone with the token can view only the shared scene.
+        let req = TestRequest::get()
+            .uri(&format!("/api/share/{}", resp.token))
+            .to_request();
+        let data = test::call_and_read_body(&app, req).await;
+        let shared = scene::serde::deserialise(&data).unwrap();
+        assert_eq!(shared.scenes.len(), 1);
+        assert_eq!(shared.scenes[0].uuid, public.uuid);
+
+        let req = TestRequest::get().uri("/api/share/invalid").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Expired tokens are rejected.
+        let token = Scene::share(conn, public.uuid, -1).await.unwrap();
+        let req = TestRequest::get()
+            .uri(&format!("/api/share/{token}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::post()
+            .uri(&uri("?ttl_days=0"))
+            .cookie(session.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Only the project owner can share scenes.
+        let other = User::generate(conn).await;
+        let req = TestRequest::post()
+            .uri(&uri(""))
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,220 @@
+use actix_web::{error::ErrorNotFound, web};
+use uuid::Uuid;
+
+use super::{project::user_scene, res_failure, res_json, Resp};
+use crate::{
+    models::{Project, ProjectTemplate, User},
+    req::{e500, Pool},
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/templates")
+        .route("", web::get().to(list))
+        .route("", web::post().to(create))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(list, create))]
+pub(super) struct Api;
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct TemplateInfo {
+    key: String,
+    title: String,
+    builtin: bool,
+}
+
+impl TemplateInfo {
+    fn new(template: ProjectTemplate) -> Self {
+        Self {
+            key: template.key,
+            title: template.title,
+            builtin: template.user.is_none(),
+        }
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct TemplateListResponse {
+    message: String,
+    success: bool,
+    templates: Vec<TemplateInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = TemplateListResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list(mut conn: Pool, user: User) -> Resp {
+    let templates = ProjectTemplate::list(conn.acquire(), user.uuid)
+        .await
+        .map_err(e500)?;
+    res_json(TemplateListResponse {
+        message: "Templates follow.".to_string(),
+        success: true,
+        templates: templates.into_iter().map(TemplateInfo::new).collect(),
+    })
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct NewTemplateRequest {
+    title: String,
+    project_uuid: Uuid,
+    scene_uuid: Uuid,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct TemplateResponse {
+    message: String,
+    success: bool,
+    template: TemplateInfo,
+}
+
+/// Save one of the user's scenes, as last saved, as a template.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = NewTemplateRequest,
+    responses(
+        (status = 200, body = TemplateResponse),
+        (status = 404, description = "Project or scene not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn create(mut conn: Pool, user: User, req: web::Json<NewTemplateRequest>) -> Resp {
+    let conn = conn.acquire();
+    let record = user_scene(conn, &user, req.project_uuid, req.scene_uuid).await?;
+    let project = Project::get_by_uuid(conn, record.project)
+        .await
+        .map_err(ErrorNotFound)?
+        .load(conn)
+        .await
+        .map_err(e500)?;
+    let Some(scene) = project.scenes.into_iter().find(|s| s.uuid == record.uuid) else {
+        return Err(ErrorNotFound("Scene not found."));
+    };
+
+    match ProjectTemplate::create(conn, user.uuid, &req.title, scene).await {
+        Ok(template) => res_json(TemplateResponse {
+            message: "Template created.".to_string(),
+            success: true,
+            template: TemplateInfo::new(template),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, web::Data, App};
+
+    use super::{NewTemplateRequest, TemplateListResponse, TemplateResponse};
+    use crate::{
+        fs::initialise_database,
+        models::{Project, ProjectTemplate, User},
+        utils::parse_uuid,
+    };
+
+    #[derive(serde_derive::Serialize)]
+    struct NewProjectRequest {
+        title: String,
+    }
+
+    #[derive(serde_derive::Deserialize)]
+    struct NewProjectResponse {
+        uuid: String,
+    }
+
+    #[actix_web::test]
+    async fn test_templates() {
+        // TEST
+        //   GET /api/templates
+        //   POST /api/templates
+        //   POST /api/project/new?template={key}
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        ProjectTemplate::seed(conn).await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        let list = || {
+            test::TestRequest::get()
+                .uri("/api/templates")
+                .cookie(session.clone())
+                .to_request()
+        };
+        let resp: TemplateListResponse = test::call_and_read_body_json(&app, list()).await;
+        let keys: Vec<String> = resp.templates.iter().map(|t| t.key.clone()).collect();
+        assert_eq!(keys, vec!["dungeon", "outdoor", "urban", "ship_deck"]);
+        assert!(resp.templates.iter().all(|t| t.builtin));
+
+        // New projects start with the template's scene.
+        let new_project = |template: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/api/project/new?template={template}"))
+                .cookie(session.clone())
+                .set_json(NewProjectRequest {
+                    title: "Crypt".to_string(),
+                })
+                .to_request()
+        };
+        let resp: NewProjectResponse =
+            test::call_and_read_body_json(&app, new_project("dungeon")).await;
+        let project = Project::get_by_uuid(conn, parse_uuid(&resp.uuid).unwrap())
+            .await
+            .unwrap();
+        let proj = project.load(conn).await.unwrap();
+        assert_eq!(proj.scenes.len(), 1);
+        let scene = &proj.scenes[0];
+        assert_eq!(scene.title, "Dungeon");
+        assert_eq!(scene.layers.len(), 4);
+        assert!(scene.fog.active);
+        assert!(scene.fog.occluded(0, 0));
+
+        let resp = test::call_service(&app, new_project("castle")).await;
+        assert!(resp.status().is_client_error());
+
+        // Save the scene as a template of our own.
+        let req = test::TestRequest::post()
+            .uri("/api/templates")
+            .cookie(session.clone())
+            .set_json(NewTemplateRequest {
+                title: "My Crypt".to_string(),
+                project_uuid: project.uuid,
+                scene_uuid: project.list_scenes(conn).await.unwrap()[0].uuid,
+            })
+            .to_request();
+        let resp: TemplateResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert!(!resp.template.builtin);
+        let key = resp.template.key;
+
+        let resp: TemplateListResponse = test::call_and_read_body_json(&app, list()).await;
+        assert_eq!(resp.templates.last().unwrap().key, key);
+
+        // Other users can't see or use the template.
+        let other = User::generate(conn).await;
+        assert_eq!(
+            ProjectTemplate::list(conn, other.uuid).await.unwrap().len(),
+            4
+        );
+        assert!(ProjectTemplate::scene(conn, &key, other.uuid)
+            .await
+            .is_err());
+        let scene = ProjectTemplate::scene(conn, &key, user.uuid).await.unwrap();
+        assert_eq!(scene.title, "My Crypt");
+    }
+}
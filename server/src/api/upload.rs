@@ -1,8 +1,8 @@
 use actix_multipart::{Field, Multipart};
-use actix_web::{error::ErrorInternalServerError, web};
+use actix_web::{error::ErrorInternalServerError, middleware::from_fn, web};
 use futures::{StreamExt, TryStreamExt};
 use ring::digest;
-use sqlx::{SqliteConnection, SqlitePool};
+use sqlx::{AnyConnection, AnyPool};
 use uuid::Uuid;
 
 use super::{res_failure, res_json, Resp};
@@ -10,17 +10,27 @@ use crate::{
     crypto::to_hex_string,
     fs::{join_relative_path, write_file, CONTENT},
     models::{Media, User},
-    req::e500,
+    req::{e500, idempotency::idempotent},
     utils::{err, format_uuid, Res},
 };
 
 // Maximum total size of media a single use can upload, in bytes
 const UPLOAD_LIMIT: usize = 10 * 1024 * 1024 * 1024; // 10 GB
 
+// Images larger than this on either side are also stored downscaled.
+const MAX_IMAGE_SIZE: u32 = 2048;
+const RESIZED_JPEG_QUALITY: u8 = 85;
+
 pub fn routes() -> actix_web::Scope {
-    web::scope("/upload").default_service(web::route().to(upload))
+    web::scope("/upload")
+        .route("/batch", web::post().to(batch).wrap(from_fn(idempotent)))
+        .default_service(web::route().to(upload).wrap(from_fn(idempotent)))
 }
 
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(upload, batch))]
+pub(super) struct Api;
+
 #[derive(Debug)]
 enum ImageRole {
     Media,
@@ -54,7 +64,7 @@ impl UploadImage {
         }
     }
 
-    async fn submit(self, pool: &SqlitePool, user: &User) -> Res<UploadResponse> {
+    async fn submit(self, pool: &AnyPool, user: &User) -> Res<UploadResponse> {
         let conn = &mut pool
             .acquire()
             .await
@@ -63,7 +73,9 @@ impl UploadImage {
         match self.data {
             Some(data) => match self.role {
                 ImageRole::Media => save_media(conn, user, data, self.title, self.ext).await,
-                ImageRole::Thumbnail(scene) => save_thumbnail(conn, user, data, scene).await,
+                ImageRole::Thumbnail(scene) => {
+                    save_thumbnail(conn, user, data, scene, &self.ext).await
+                }
             },
             None => err("No image data provided."),
         }
@@ -71,7 +83,7 @@ impl UploadImage {
 }
 
 async fn save_media(
-    conn: &mut SqliteConnection,
+    conn: &mut AnyConnection,
     user: &User,
     data: Vec<u8>,
     title: String,
@@ -89,36 +101,90 @@ async fn save_media(
         };
     }
 
-    let record = Media::prepare(user, &ext, title, hash, data.len());
+    let mut record = Media::prepare(user, &ext, title, hash, data.len());
 
     let path = join_relative_path(&CONTENT, &record.relative_path);
     write_file(&path, &data).await?;
 
+    let resized_path = match save_resized(&record.relative_path, data).await {
+        Ok(resized) => resized,
+        Err(e) => {
+            tokio::fs::remove_file(&path).await.ok();
+            return Err(e);
+        }
+    };
+    record.resized_path = resized_path.clone();
+
     match record.create(conn).await {
         Ok(record) => Ok(UploadResponse::new(
             Some(format_uuid(record.uuid)),
-            record.relative_path,
+            record.display_path().to_string(),
         )),
         Err(e) => {
-            // Remove file as part of cleanup.
+            // Remove files as part of cleanup.
             tokio::fs::remove_file(&path).await.ok();
+            if let Some(resized) = resized_path {
+                tokio::fs::remove_file(join_relative_path(&CONTENT, resized))
+                    .await
+                    .ok();
+            }
             Err(format!("Database error: {e}"))
         }
     }
 }
 
-async fn save_thumbnail(
-    conn: &mut SqliteConnection,
+/// If the image is too large, store a downscaled JPEG copy alongside the
+/// original, returning its relative path. Files which can't be decoded as
+/// images are stored as is.
+async fn save_resized(relative_path: &str, data: Vec<u8>) -> Res<Option<String>> {
+    let resized = tokio::task::spawn_blocking(move || resize_image(&data))
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(resized) = resized else {
+        return Ok(None);
+    };
+
+    let stem = relative_path
+        .rsplit_once('.')
+        .map_or(relative_path, |(stem, _)| stem);
+    let resized_path = format!("{stem}.resized.jpg");
+    write_file(&join_relative_path(&CONTENT, &resized_path), &resized).await?;
+    Ok(Some(resized_path))
+}
+
+fn resize_image(data: &[u8]) -> Option<Vec<u8>> {
+    let image = image::load_from_memory(data).ok()?;
+    if image.width().max(image.height()) <= MAX_IMAGE_SIZE {
+        return None;
+    }
+
+    let resized = image
+        .resize(
+            MAX_IMAGE_SIZE,
+            MAX_IMAGE_SIZE,
+            image::imageops::FilterType::Lanczos3,
+        )
+        .into_rgb8();
+    let mut bytes = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, RESIZED_JPEG_QUALITY)
+        .encode_image(&resized)
+        .ok()?;
+    Some(bytes)
+}
+
+pub(super) async fn save_thumbnail(
+    conn: &mut AnyConnection,
     user: &User,
     data: Vec<u8>,
     scene: Uuid,
+    ext: &str,
 ) -> Res<UploadResponse> {
     if crate::models::Project::for_scene(conn, scene).await?.user != user.uuid {
         return err("User does not own scene.");
     }
 
     let relative_path = format!(
-        "/uploads/{}/thumbnails/{}.png",
+        "/uploads/{}/thumbnails/{}.{ext}",
         &user.username,
         format_uuid(scene),
     );
@@ -154,6 +220,17 @@ fn ext_from_filename(filename: &str) -> Option<String> {
     None
 }
 
+/// File extension and title for an image part, or `None` if the file type
+/// can't be determined.
+fn image_details(part: &Field) -> Option<(String, String)> {
+    let ext = choose_file_extension(part)?;
+    let title = match part.content_disposition().get_filename() {
+        Some(s) => s.to_owned(),
+        None => format!("untitled.{ext}"),
+    };
+    Some((ext, title))
+}
+
 fn choose_file_extension(part: &Field) -> Option<String> {
     if let Some((t, st)) = part.content_type().map(|m| (m.type_(), m.subtype())) {
         match (t, st) {
@@ -169,8 +246,8 @@ fn choose_file_extension(part: &Field) -> Option<String> {
 }
 
 #[cfg_attr(test, derive(serde_derive::Deserialize))]
-#[derive(serde_derive::Serialize)]
-struct UploadResponse {
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+pub(super) struct UploadResponse {
     message: String,
     success: bool,
     uuid: Option<String>,
@@ -188,7 +265,23 @@ impl UploadResponse {
     }
 }
 
-async fn upload(pool: web::Data<SqlitePool>, user: User, mut form: Multipart) -> Resp {
+/// Upload an image as media, or as the thumbnail of a scene.
+#[utoipa::path(
+    post,
+    path = "",
+    params(
+        ("X-Idempotency-Key" = Option<Uuid>, Header,
+            description = "Repeated requests with the same key replay the first response."),
+    ),
+    request_body(
+        content_type = "multipart/form-data",
+        description = "An `image` part, and optionally a `thumbnail` part containing the UUID \
+            of the scene to set the thumbnail of."
+    ),
+    responses((status = 200, body = UploadResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn upload(pool: web::Data<AnyPool>, user: User, mut form: Multipart) -> Resp {
     let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
     let total_uploaded = Media::user_total_size(conn, user.uuid)
         .await
@@ -212,17 +305,13 @@ async fn upload(pool: web::Data<SqlitePool>, user: User, mut form: Multipart) ->
                 _ => return res_failure("Bad thumbnail scene ID."),
             },
             "image" => {
-                if let Some(ext) = choose_file_extension(&part) {
+                if let Some((ext, title)) = image_details(&part) {
                     upload.ext = ext;
+                    upload.title = title;
                 } else {
                     return res_failure("Missing file type.");
                 }
 
-                match part.content_disposition().get_filename() {
-                    Some(s) => s.clone_into(&mut upload.title),
-                    None => upload.title = format!("untitled.{}", upload.ext),
-                };
-
                 upload.data = Some(collect_part(part).await.map_err(e500)?);
             }
             _ => (),
@@ -237,6 +326,74 @@ async fn upload(pool: web::Data<SqlitePool>, user: User, mut form: Multipart) ->
     res_json(res)
 }
 
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+pub(super) struct BatchUploadItem {
+    uuid: String,
+    title: String,
+    // Media items are keyed by their UUID.
+    media_key: String,
+}
+
+/// Upload several images as media at once.
+#[utoipa::path(
+    post,
+    path = "/batch",
+    params(
+        ("X-Idempotency-Key" = Option<Uuid>, Header,
+            description = "Repeated requests with the same key replay the first response."),
+    ),
+    request_body(
+        content_type = "multipart/form-data",
+        description = "Any number of `image` parts."
+    ),
+    responses((status = 200, body = [BatchUploadItem])),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn batch(pool: web::Data<AnyPool>, user: User, mut form: Multipart) -> Resp {
+    let conn = &mut pool.acquire().await.map_err(ErrorInternalServerError)?;
+    let mut total_uploaded = Media::user_total_size(conn, user.uuid)
+        .await
+        .map_err(e500)?;
+
+    if total_uploaded >= UPLOAD_LIMIT {
+        return res_failure("Upload limit exceeded.");
+    }
+
+    let mut items = Vec::new();
+    while let Some(Ok(part)) = form.next().await {
+        if part.name() != "image" {
+            continue;
+        }
+
+        let Some((ext, title)) = image_details(&part) else {
+            return res_failure("Missing file type.");
+        };
+        let data = collect_part(part).await.map_err(e500)?;
+
+        total_uploaded += data.len();
+        if total_uploaded >= UPLOAD_LIMIT {
+            return res_failure("Upload limit exceeded.");
+        }
+
+        let res = save_media(conn, &user, data, title.clone(), ext)
+            .await
+            .map_err(e500)?;
+        let uuid = res.uuid.unwrap_or_default();
+        items.push(BatchUploadItem {
+            media_key: uuid.clone(),
+            uuid,
+            title,
+        });
+    }
+
+    if items.is_empty() {
+        return res_failure("No image data provided.");
+    }
+
+    res_json(items)
+}
+
 #[cfg(test)]
 mod test {
     use std::io::Write;
@@ -248,7 +405,7 @@ mod test {
         App,
     };
 
-    use super::UploadResponse;
+    use super::{BatchUploadItem, UploadResponse};
     use crate::{
         fs::{initialise_database, join_relative_path, CONTENT},
         models::{Media, Project, Scene, User},
@@ -263,12 +420,6 @@ mod test {
         let boundary = format_uuid(generate_uuid());
         let mut body = Vec::new();
 
-        let ct = if filename.ends_with("jpg") || filename.ends_with("jpeg") {
-            "jpeg"
-        } else {
-            "png"
-        };
-
         if let Some((key, value)) = extra {
             write!(&mut body, "--{}\r\n", &boundary).unwrap();
             write!(&mut body, "Content-Disposition: form-data; ").unwrap();
@@ -276,19 +427,43 @@ mod test {
             write!(&mut body, "{}\r\n", value).unwrap();
         }
 
-        write!(&mut body, "--{}\r\n", &boundary).unwrap();
-        write!(&mut body, "Content-Disposition: form-data; ").unwrap();
-        write!(&mut body, "name=\"image\"; filename=\"{}\"\r\n", filename).unwrap();
-        write!(&mut body, "Content-Type: image/{}\r\n\r\n", ct).unwrap();
+        write_image_part(&mut body, &boundary, filename, image_data);
+        write!(&mut body, "--{}--", &boundary).unwrap();
+
+        (body, multipart_header(&boundary))
+    }
+
+    fn batch_request(files: &[(&str, &[u8])]) -> (Vec<u8>, (HeaderName, HeaderValue)) {
+        let boundary = format_uuid(generate_uuid());
+        let mut body = Vec::new();
+        for (filename, image_data) in files {
+            write_image_part(&mut body, &boundary, filename, image_data);
+        }
+        write!(&mut body, "--{}--", &boundary).unwrap();
+
+        (body, multipart_header(&boundary))
+    }
+
+    fn write_image_part(body: &mut Vec<u8>, boundary: &str, filename: &str, image_data: &[u8]) {
+        let ct = if filename.ends_with("jpg") || filename.ends_with("jpeg") {
+            "jpeg"
+        } else {
+            "png"
+        };
+
+        write!(body, "--{}\r\n", boundary).unwrap();
+        write!(body, "Content-Disposition: form-data; ").unwrap();
+        write!(body, "name=\"image\"; filename=\"{}\"\r\n", filename).unwrap();
+        write!(body, "Content-Type: image/{}\r\n\r\n", ct).unwrap();
         body.write_all(image_data).unwrap();
-        write!(&mut body, "\r\n--{}--", &boundary).unwrap();
+        write!(body, "\r\n").unwrap();
+    }
 
-        let header = (
+    fn multipart_header(boundary: &str) -> (HeaderName, HeaderValue) {
+        (
             actix_web::http::header::CONTENT_TYPE,
-            HeaderValue::from_str(&format!("multipart/form-data; boundary={}", &boundary)).unwrap(),
-        );
-
-        (body, header)
+            HeaderValue::from_str(&format!("multipart/form-data; boundary={}", boundary)).unwrap(),
+        )
     }
 
     #[actix_web::test]
@@ -349,6 +524,95 @@ mod test {
         assert_eq!(test::read_body(resp).await, image_data);
     }
 
+    #[actix_web::test]
+    async fn test_upload_resized() {
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes())
+                .service(crate::content::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let mut image_data = Vec::new();
+        image::DynamicImage::new_rgb8(4096, 100)
+            .write_to(
+                &mut std::io::Cursor::new(&mut image_data),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        let (payload, header) = multipart_request("map.png", &image_data, None);
+
+        let user = User::generate(conn).await;
+        let req = test::TestRequest::post()
+            .uri("/api/upload")
+            .cookie(user.session(conn).await)
+            .append_header(header)
+            .set_payload(payload)
+            .to_request();
+        let resp: UploadResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        let uuid = resp.uuid.unwrap();
+        let record = Media::load(conn, parse_uuid(&uuid).unwrap()).await.unwrap();
+        assert_eq!(record.resized_path.as_deref(), Some(resp.url.as_str()));
+
+        // The resized copy is served by default.
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/media/{uuid}/file"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("Content-Type").unwrap(), "image/jpeg");
+        let resized = image::load_from_memory(&test::read_body(resp).await).unwrap();
+        assert_eq!((resized.width(), resized.height()), (2048, 50));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/media/{uuid}/file?original=true"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(test::read_body(resp).await, image_data);
+    }
+
+    #[actix_web::test]
+    async fn test_upload_batch() {
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes())
+                .service(crate::content::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        let first: Vec<u8> = (0..=255).collect();
+        let second: Vec<u8> = (0..=255).rev().collect();
+        let (payload, header) = batch_request(&[("first.png", &first), ("second.jpg", &second)]);
+
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let req = test::TestRequest::post()
+            .uri("/api/upload/batch")
+            .cookie(session)
+            .append_header(header)
+            .set_payload(payload)
+            .to_request();
+        let resp: Vec<BatchUploadItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 2);
+        assert_eq!(resp[0].title, "first.png");
+        assert_eq!(resp[1].title, "second.jpg");
+
+        for (item, data) in resp.iter().zip([&first, &second]) {
+            assert_eq!(item.uuid, item.media_key);
+            let record = Media::load(conn, parse_uuid(&item.uuid).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(record.user, user.uuid);
+            assert_eq!(record.file_size, data.len());
+        }
+    }
+
     #[actix_web::test]
     async fn test_upload_thumbnail() {
         let db = initialise_database().await.unwrap();
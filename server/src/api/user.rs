@@ -0,0 +1,1209 @@
+use std::io::{Cursor, Write};
+
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use super::{
+    body_failure, body_success, game::Games, res_failure, res_json, res_success, session_resp,
+    Binary, Resp,
+};
+use crate::{
+    crypto::{check_password, check_totp, generate_totp_secret, totp_uri},
+    models::{ApiKey, DataExport, Media, Palette, Project, SpriteTemplate, User, UserAuth},
+    req::{e500, Pool},
+    utils::{format_uuid, Res},
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/user")
+        .route("/profile", web::get().to(profile))
+        .route("/profile", web::put().to(update_profile))
+        .route("/api-keys", web::get().to(list_api_keys))
+        .route("/api-keys", web::post().to(create_api_key))
+        .route("/api-keys/{label}", web::delete().to(revoke_api_key))
+        .route("/sprite-templates", web::get().to(list_sprite_templates))
+        .route("/sprite-templates", web::post().to(create_sprite_template))
+        .route("/palettes", web::get().to(list_palettes))
+        .route("/palettes", web::post().to(save_palette))
+        .route("/export", web::get().to(export))
+        .route("/account", web::delete().to(delete_account))
+        .route("/2fa/setup", web::post().to(setup_two_factor))
+        .route("/2fa/confirm", web::post().to(confirm_two_factor))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    profile,
+    update_profile,
+    list_api_keys,
+    create_api_key,
+    revoke_api_key,
+    list_sprite_templates,
+    create_sprite_template,
+    list_palettes,
+    save_palette,
+    export,
+    delete_account,
+    setup_two_factor,
+    confirm_two_factor
+))]
+pub(super) struct Api;
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct ProfileResponse {
+    message: String,
+    success: bool,
+    username: String,
+    display_name: String,
+    avatar_media_key: Option<String>,
+    email: Option<String>,
+    two_factor_enabled: bool,
+}
+
+impl ProfileResponse {
+    fn new(user: User) -> Self {
+        Self {
+            message: "Profile follows.".to_string(),
+            success: true,
+            username: user.username,
+            display_name: user.display_name,
+            avatar_media_key: user.avatar_media_key,
+            email: user.email,
+            two_factor_enabled: user.totp_secret.is_some(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/profile",
+    responses((status = 200, body = ProfileResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn profile(user: User) -> Resp {
+    res_json(ProfileResponse::new(user))
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct ProfileRequest {
+    display_name: String,
+    avatar_media_key: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+/// Update the display name, avatar and email of the user.
+#[utoipa::path(
+    put,
+    path = "/profile",
+    request_body = ProfileRequest,
+    responses((status = 200, body = ProfileResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn update_profile(mut conn: Pool, user: User, req: web::Json<ProfileRequest>) -> Resp {
+    let avatar = match req.avatar_media_key.as_deref().filter(|k| !k.is_empty()) {
+        Some(key) => match Uuid::try_parse(key) {
+            Ok(uuid) => Some(uuid),
+            Err(_) => return res_failure("Invalid avatar media key."),
+        },
+        None => None,
+    };
+
+    match User::set_profile(
+        conn.acquire(),
+        user.uuid,
+        &req.display_name,
+        avatar,
+        req.email.as_deref(),
+    )
+    .await
+    {
+        Ok(user) => res_json(ProfileResponse::new(user)),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct ApiKeyInfo {
+    label: String,
+    created_time: u64,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct ApiKeyListResponse {
+    message: String,
+    success: bool,
+    api_keys: Vec<ApiKeyInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    responses((status = 200, body = ApiKeyListResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list_api_keys(mut conn: Pool, user: User) -> Resp {
+    match ApiKey::list(conn.acquire(), user.uuid).await {
+        Ok(keys) => res_json(ApiKeyListResponse {
+            message: "API keys follow.".to_string(),
+            success: true,
+            api_keys: keys
+                .into_iter()
+                .map(|key| ApiKeyInfo {
+                    created_time: key.created_timestamp(),
+                    label: key.label,
+                })
+                .collect(),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct ApiKeyRequest {
+    label: String,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct ApiKeyResponse {
+    message: String,
+    success: bool,
+    label: String,
+    key: String,
+}
+
+/// Create an API key. The key is only ever returned by this request.
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    request_body = ApiKeyRequest,
+    responses((status = 200, body = ApiKeyResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn create_api_key(mut conn: Pool, user: User, req: web::Json<ApiKeyRequest>) -> Resp {
+    match ApiKey::create(conn.acquire(), user.uuid, &req.label).await {
+        Ok(key) => res_json(ApiKeyResponse {
+            message: "API key created.".to_string(),
+            success: true,
+            label: req.label.trim().to_string(),
+            key,
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{label}",
+    params(("label" = String, Path, description = "Label of the API key.")),
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn revoke_api_key(mut conn: Pool, user: User, label: web::Path<String>) -> Resp {
+    match ApiKey::revoke(conn.acquire(), user.uuid, &label).await {
+        Ok(()) => res_success("API key revoked."),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SpriteTemplateInfo {
+    uuid: String,
+    project: Option<String>,
+    #[schema(value_type = Object)]
+    template: scene::SpriteTmpl,
+}
+
+impl SpriteTemplateInfo {
+    fn new(template: SpriteTemplate) -> Self {
+        Self {
+            uuid: format_uuid(template.uuid),
+            project: template.project.map(format_uuid),
+            template: template.template,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SpriteTemplateListResponse {
+    message: String,
+    success: bool,
+    templates: Vec<SpriteTemplateInfo>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SpriteTemplateQuery {
+    project: Option<Uuid>,
+}
+
+/// Whether `project` exists and belongs to `user`.
+async fn owns_project(
+    conn: &mut sqlx::AnyConnection,
+    user: &User,
+    project: Uuid,
+) -> Result<(), actix_web::Error> {
+    match Project::lookup(conn, project).await.map_err(e500)? {
+        Some(record) if record.user == user.uuid => Ok(()),
+        _ => Err(actix_web::error::ErrorNotFound("Project not found.")),
+    }
+}
+
+/// List the user's templates, along with those shared with the project given,
+/// if any.
+#[utoipa::path(
+    get,
+    path = "/sprite-templates",
+    params(SpriteTemplateQuery),
+    responses(
+        (status = 200, body = SpriteTemplateListResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list_sprite_templates(
+    mut conn: Pool,
+    user: User,
+    query: web::Query<SpriteTemplateQuery>,
+) -> Resp {
+    let conn = conn.acquire();
+    if let Some(project) = query.project {
+        owns_project(conn, &user, project).await?;
+    }
+
+    match SpriteTemplate::list(conn, user.uuid, query.project).await {
+        Ok(templates) => res_json(SpriteTemplateListResponse {
+            message: "Sprite templates follow.".to_string(),
+            success: true,
+            templates: templates.into_iter().map(SpriteTemplateInfo::new).collect(),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct SpriteTemplateRequest {
+    #[schema(value_type = Object)]
+    template: scene::SpriteTmpl,
+
+    /// Project to share the template with, if it shouldn't be offered in all
+    /// of the user's projects.
+    #[serde(default)]
+    project: Option<Uuid>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct SpriteTemplateResponse {
+    message: String,
+    success: bool,
+    template: SpriteTemplateInfo,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sprite-templates",
+    request_body = SpriteTemplateRequest,
+    responses(
+        (status = 200, body = SpriteTemplateResponse),
+        (status = 404, description = "Project not found."),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn create_sprite_template(
+    mut conn: Pool,
+    user: User,
+    req: web::Json<SpriteTemplateRequest>,
+) -> Resp {
+    let conn = conn.acquire();
+    let req = req.into_inner();
+    if let Some(project) = req.project {
+        owns_project(conn, &user, project).await?;
+    }
+
+    match SpriteTemplate::create(conn, user.uuid, req.project, req.template).await {
+        Ok(template) => res_json(SpriteTemplateResponse {
+            message: "Sprite template saved.".to_string(),
+            success: true,
+            template: SpriteTemplateInfo::new(template),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct PaletteListResponse {
+    message: String,
+    success: bool,
+    #[schema(value_type = Vec<Object>)]
+    palettes: Vec<scene::ColourPalette>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/palettes",
+    responses((status = 200, body = PaletteListResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list_palettes(mut conn: Pool, user: User) -> Resp {
+    match Palette::list(conn.acquire(), user.uuid).await {
+        Ok(palettes) => res_json(PaletteListResponse {
+            message: "Palettes follow.".to_string(),
+            success: true,
+            palettes: palettes.into_iter().map(|p| p.palette).collect(),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct PaletteResponse {
+    message: String,
+    success: bool,
+    #[schema(value_type = Object)]
+    palette: scene::ColourPalette,
+}
+
+/// Save a palette, replacing any existing palette with the same name.
+#[utoipa::path(
+    post,
+    path = "/palettes",
+    request_body(content = Object, description = "Palette to save."),
+    responses((status = 200, body = PaletteResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn save_palette(mut conn: Pool, user: User, req: web::Json<scene::ColourPalette>) -> Resp {
+    match Palette::save(conn.acquire(), user.uuid, req.into_inner()).await {
+        Ok(palette) => res_json(PaletteResponse {
+            message: "Palette saved.".to_string(),
+            success: true,
+            palette: palette.palette,
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize)]
+struct UserRecord {
+    uuid: String,
+    username: String,
+    display_name: String,
+    avatar_media_key: Option<String>,
+    email: Option<String>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize)]
+struct MediaRecord {
+    uuid: String,
+    relative_path: String,
+    title: String,
+    hashed_value: String,
+    file_size: usize,
+    w: f32,
+    h: f32,
+    tags: Vec<String>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize)]
+struct ProjectRecord {
+    uuid: String,
+    updated_time: u64,
+    title: String,
+    description: Option<String>,
+    tags: Vec<String>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize)]
+struct SceneRecord {
+    uuid: String,
+    project: String,
+    updated_time: u64,
+    title: String,
+    thumbnail: Option<String>,
+}
+
+/// Add `records` to `zip` as a JSON file called `name`.
+fn write_json<T: serde::Serialize>(
+    zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+    name: &str,
+    records: &[T],
+) -> Res<()> {
+    let json = serde_json::to_vec_pretty(records).map_err(|e| e.to_string())?;
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())
+}
+
+/// Archive of the user's records, with a JSON file for each table.
+async fn export_archive(conn: &mut sqlx::AnyConnection, user: User) -> Res<Vec<u8>> {
+    let media: Vec<MediaRecord> = Media::user_media(conn, user.uuid)
+        .await?
+        .into_iter()
+        .map(|media| MediaRecord {
+            uuid: format_uuid(media.uuid),
+            relative_path: media.relative_path,
+            title: media.title,
+            hashed_value: media.hashed_value,
+            file_size: media.file_size,
+            w: media.w,
+            h: media.h,
+            tags: media.tags,
+        })
+        .collect();
+
+    let mut projects = Vec::new();
+    let mut scenes = Vec::new();
+    for project in Project::list_for_user(conn, user.uuid).await? {
+        for scene in project.list_scenes(conn).await? {
+            scenes.push(SceneRecord {
+                uuid: format_uuid(scene.uuid),
+                project: format_uuid(scene.project),
+                updated_time: scene.updated_timestamp(),
+                title: scene.title,
+                thumbnail: scene.thumbnail,
+            });
+        }
+        projects.push(ProjectRecord {
+            uuid: format_uuid(project.uuid),
+            updated_time: project.updated_timestamp(),
+            title: project.title,
+            description: project.description,
+            tags: project.tags,
+        });
+    }
+
+    let api_keys: Vec<ApiKeyInfo> = ApiKey::list(conn, user.uuid)
+        .await?
+        .into_iter()
+        .map(|key| ApiKeyInfo {
+            created_time: key.created_timestamp(),
+            label: key.label,
+        })
+        .collect();
+
+    // Credentials are excluded, as they are only stored hashed.
+    let users = [UserRecord {
+        uuid: format_uuid(user.uuid),
+        username: user.username,
+        display_name: user.display_name,
+        avatar_media_key: user.avatar_media_key,
+        email: user.email,
+    }];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    write_json(&mut zip, "users.json", &users)?;
+    write_json(&mut zip, "media.json", &media)?;
+    write_json(&mut zip, "projects.json", &projects)?;
+    write_json(&mut zip, "scenes.json", &scenes)?;
+    write_json(&mut zip, "user_api_keys.json", &api_keys)?;
+    let archive = zip.finish().map_err(|e| e.to_string())?;
+    Ok(archive.into_inner())
+}
+
+/// Download a ZIP archive of everything stored about the user. This may be
+/// done once a day.
+#[utoipa::path(
+    get,
+    path = "/export",
+    responses(
+        (status = 200, description = "ZIP archive of the user's data.", content_type = "application/zip", body = [u8]),
+        (status = 429, description = "Data has already been exported today.", body = Binary),
+    ),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn export(mut conn: Pool, user: User) -> Resp {
+    let conn = conn.acquire();
+    let uuid = user.uuid;
+    let reservation = match DataExport::reserve(conn, uuid).await {
+        Ok(reservation) => reservation,
+        Err(e) => return Ok(HttpResponse::TooManyRequests().json(body_failure(e))),
+    };
+
+    // Only exports which succeed count towards the limit, so the reservation
+    // is released if the archive can't be built.
+    let archive = match export_archive(conn, user).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            DataExport::release(conn, uuid, reservation).await.ok();
+            return Err(e500(e));
+        }
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((
+            "Content-Disposition",
+            "attachment; filename=realmview-export.zip",
+        ))
+        .body(archive))
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct DeleteAccountRequest {
+    password: String,
+}
+
+/// Permanently delete the user's account and all of their data. The user's
+/// password is required to confirm the deletion.
+#[utoipa::path(
+    delete,
+    path = "/account",
+    request_body = DeleteAccountRequest,
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn delete_account(
+    mut conn: Pool,
+    games: web::Data<Games>,
+    user: User,
+    req: web::Json<DeleteAccountRequest>,
+) -> Resp {
+    let conn = conn.acquire();
+    let auth = UserAuth::get_by_username(conn, &user.username)
+        .await
+        .map_err(e500)?;
+    if !check_password(&req.password, &auth.salt, &auth.hashed_password) {
+        return res_failure("Incorrect password.");
+    }
+
+    super::game::remove_user(&games, user.uuid).await;
+    user.delete(conn).await.map_err(e500)?;
+    Ok(session_resp("").json(body_success("Account deleted.")))
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct TwoFactorSetupResponse {
+    message: String,
+    success: bool,
+    secret: String,
+    uri: String,
+}
+
+/// Generate a new TOTP secret for the user. The secret isn't used for login
+/// until the user confirms a valid code with /2fa/confirm.
+#[utoipa::path(
+    post,
+    path = "/2fa/setup",
+    responses((status = 200, body = TwoFactorSetupResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn setup_two_factor(mut conn: Pool, user: User) -> Resp {
+    if user.totp_secret.is_some() {
+        return res_failure("Two-factor authentication is already enabled.");
+    }
+
+    let secret = generate_totp_secret();
+    let uri = totp_uri(&secret, &user.username).map_err(e500)?;
+    User::set_pending_totp_secret(conn.acquire(), user.uuid, &secret)
+        .await
+        .map_err(e500)?;
+    res_json(TwoFactorSetupResponse {
+        message: "Two-factor secret generated.".to_string(),
+        success: true,
+        secret,
+        uri,
+    })
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct TwoFactorConfirmRequest {
+    code: String,
+}
+
+/// Enable two-factor authentication, given a valid code for the secret from
+/// /2fa/setup.
+#[utoipa::path(
+    post,
+    path = "/2fa/confirm",
+    request_body = TwoFactorConfirmRequest,
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn confirm_two_factor(
+    mut conn: Pool,
+    user: User,
+    req: web::Json<TwoFactorConfirmRequest>,
+) -> Resp {
+    let conn = conn.acquire();
+    let Some(secret) = User::pending_totp_secret(conn, user.uuid)
+        .await
+        .map_err(e500)?
+    else {
+        return res_failure("No two-factor setup in progress.");
+    };
+
+    let accepted = match check_totp(&secret, &req.code).map_err(e500)? {
+        Some(step) => User::use_totp_step(conn, user.uuid, step)
+            .await
+            .map_err(e500)?,
+        None => false,
+    };
+    if !accepted {
+        return res_failure("Incorrect two-factor code.");
+    }
+
+    User::enable_totp(conn, user.uuid).await.map_err(e500)?;
+    res_success("Two-factor authentication enabled.")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use actix_web::{test, web::Data, App};
+
+    use super::{
+        ApiKeyListResponse, ApiKeyRequest, ApiKeyResponse, DeleteAccountRequest, MediaRecord,
+        PaletteListResponse, PaletteResponse, ProfileRequest, ProfileResponse, ProjectRecord,
+        SpriteTemplateListResponse, SpriteTemplateRequest, SpriteTemplateResponse,
+        TwoFactorConfirmRequest, TwoFactorSetupResponse, UserRecord,
+    };
+    use crate::{
+        api::{game::Games, Binary},
+        crypto::totp_code,
+        fs::{initialise_database, join_relative_path, write_file, CONTENT, SAVES},
+        models::{DataExport, Media, Project, User, UserAuth},
+        utils::format_uuid,
+    };
+
+    #[actix_web::test]
+    async fn test_user_profile() {
+        // TEST
+        //   GET /api/user/profile
+        //   PUT /api/user/profile
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+
+        // Display name defaults to the username.
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let req = test::TestRequest::get()
+            .uri("/api/user/profile")
+            .cookie(session.clone())
+            .to_request();
+        let resp: ProfileResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.display_name, user.username);
+        assert!(resp.avatar_media_key.is_none());
+
+        // Set a display name and avatar.
+        let media = Media::prepare(&user, "png", "avatar", "AVATAR_HASH", 1)
+            .create(conn)
+            .await
+            .unwrap();
+        let req = test::TestRequest::put()
+            .uri("/api/user/profile")
+            .cookie(session.clone())
+            .set_json(ProfileRequest {
+                display_name: " Grog ".to_string(),
+                avatar_media_key: Some(format_uuid(media.uuid)),
+                email: Some(" Grog@Example.com".to_string()),
+            })
+            .to_request();
+        let resp: ProfileResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.display_name, "Grog");
+        assert_eq!(resp.avatar_media_key, Some(format_uuid(media.uuid)));
+        assert_eq!(resp.email.as_deref(), Some("grog@example.com"));
+
+        // Another user's media can't be used as an avatar.
+        let other = User::generate(conn).await;
+        let req = test::TestRequest::put()
+            .uri("/api/user/profile")
+            .cookie(other.session(conn).await)
+            .set_json(ProfileRequest {
+                display_name: "Pike".to_string(),
+                avatar_media_key: Some(format_uuid(media.uuid)),
+                email: None,
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+
+        // Nor can another user's email, without saying whose it is.
+        let req = test::TestRequest::put()
+            .uri("/api/user/profile")
+            .cookie(other.session(conn).await)
+            .set_json(ProfileRequest {
+                display_name: "Pike".to_string(),
+                avatar_media_key: None,
+                email: Some("grog@example.com".to_string()),
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+        assert_eq!(resp.message, "Failed to update profile.");
+    }
+
+    #[actix_web::test]
+    async fn test_api_keys() {
+        // TEST
+        //   POST /api/user/api-keys
+        //   GET /api/user/api-keys
+        //   DELETE /api/user/api-keys/{label}
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/api-keys")
+            .cookie(session.clone())
+            .set_json(ApiKeyRequest {
+                label: " script ".to_string(),
+            })
+            .to_request();
+        let resp: ApiKeyResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.label, "script");
+        let key = resp.key;
+
+        // Labels are unique.
+        let req = test::TestRequest::post()
+            .uri("/api/user/api-keys")
+            .cookie(session.clone())
+            .set_json(ApiKeyRequest {
+                label: "script".to_string(),
+            })
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+
+        // The key authenticates in place of the session cookie.
+        let req = test::TestRequest::get()
+            .uri("/api/user/api-keys")
+            .insert_header(("Authorization", format!("Bearer {key}")))
+            .to_request();
+        let resp: ApiKeyListResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.api_keys.len(), 1);
+        assert_eq!(resp.api_keys[0].label, "script");
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/profile")
+            .insert_header(("Authorization", format!("Bearer {}", "0".repeat(64))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        // Revoked keys no longer authenticate.
+        let req = test::TestRequest::delete()
+            .uri("/api/user/api-keys/script")
+            .cookie(session.clone())
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/profile")
+            .insert_header(("Authorization", format!("Bearer {key}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_sprite_templates() {
+        // TEST
+        //   POST /api/user/sprite-templates
+        //   GET /api/user/sprite-templates
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+
+        let mut sprite = scene::Sprite::new(1, None);
+        sprite.set_rect(scene::Rect::new(3.0, 4.0, 2.0, 2.0));
+        sprite.set_label(Some("Ogre".to_string()));
+        let template = scene::SpriteTmpl::of(" Ogre ", &sprite).unwrap();
+
+        let save = |project: Option<uuid::Uuid>| {
+            test::TestRequest::post()
+                .uri("/api/user/sprite-templates")
+                .cookie(session.clone())
+                .set_json(SpriteTemplateRequest {
+                    template: template.clone(),
+                    project,
+                })
+                .to_request()
+        };
+        let resp: SpriteTemplateResponse = test::call_and_read_body_json(&app, save(None)).await;
+        assert!(resp.success);
+        assert_eq!(resp.template.template.name, "Ogre");
+        assert_eq!(resp.template.template.label.as_deref(), Some("Ogre"));
+        assert!(resp.template.project.is_none());
+
+        let resp: SpriteTemplateResponse =
+            test::call_and_read_body_json(&app, save(Some(project.uuid))).await;
+        assert_eq!(resp.template.project, Some(format_uuid(project.uuid)));
+
+        let list = |uri: String, session| {
+            test::TestRequest::get()
+                .uri(&uri)
+                .cookie(session)
+                .to_request()
+        };
+
+        // Templates shared with a project are only listed for that project.
+        let uri = "/api/user/sprite-templates".to_string();
+        let resp: SpriteTemplateListResponse =
+            test::call_and_read_body_json(&app, list(uri.clone(), session.clone())).await;
+        assert_eq!(resp.templates.len(), 1);
+        let project_uri = format!("{uri}?project={}", format_uuid(project.uuid));
+        let resp: SpriteTemplateListResponse =
+            test::call_and_read_body_json(&app, list(project_uri.clone(), session.clone())).await;
+        assert_eq!(resp.templates.len(), 2);
+        assert_eq!(resp.templates[0].template.rect.w, 2.0);
+
+        // Other users can't see the user's templates, or use their project.
+        let other = User::generate(conn).await;
+        let other_session = other.session(conn).await;
+        let resp: SpriteTemplateListResponse =
+            test::call_and_read_body_json(&app, list(uri, other_session.clone())).await;
+        assert!(resp.templates.is_empty());
+        let resp = test::call_service(&app, list(project_uri, other_session)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_palettes() {
+        // TEST
+        //   POST /api/user/palettes
+        //   GET /api/user/palettes
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        let save = |palette: &scene::ColourPalette| {
+            test::TestRequest::post()
+                .uri("/api/user/palettes")
+                .cookie(session.clone())
+                .set_json(palette)
+                .to_request()
+        };
+
+        let mut palette = scene::ColourPalette::new("Forest");
+        palette.add(scene::Colour::GREEN);
+        let resp: PaletteResponse = test::call_and_read_body_json(&app, save(&palette)).await;
+        assert!(resp.success);
+        assert_eq!(resp.palette, palette);
+
+        // Saving under the same name replaces the palette.
+        palette.add(scene::Colour::BLUE);
+        let resp: PaletteResponse = test::call_and_read_body_json(&app, save(&palette)).await;
+        assert_eq!(resp.palette.colours.len(), 2);
+        let resp: PaletteResponse =
+            test::call_and_read_body_json(&app, save(&scene::ColourPalette::dungeon())).await;
+        assert!(resp.success);
+
+        // Palettes with too many colours are rejected.
+        let mut full = scene::ColourPalette::new("Full");
+        full.colours = vec![scene::Colour::RED; scene::ColourPalette::MAX_COLOURS + 1];
+        let resp: Binary = test::call_and_read_body_json(&app, save(&full)).await;
+        assert!(!resp.success);
+
+        let list = |session| {
+            test::TestRequest::get()
+                .uri("/api/user/palettes")
+                .cookie(session)
+                .to_request()
+        };
+        let resp: PaletteListResponse =
+            test::call_and_read_body_json(&app, list(session.clone())).await;
+        assert_eq!(
+            resp.palettes,
+            vec![palette, scene::ColourPalette::dungeon()]
+        );
+
+        // Other users' palettes are separate.
+        let other = User::generate(conn).await;
+        let resp: PaletteListResponse =
+            test::call_and_read_body_json(&app, list(other.session(conn).await)).await;
+        assert!(resp.palettes.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_export() {
+        // TEST
+        //   GET /api/user/export
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let media = Media::prepare(&user, "png", "map", "EXPORT_HASH", 1)
+            .create(conn)
+            .await
+            .unwrap();
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+
+        let export = || {
+            test::TestRequest::get()
+                .uri("/api/user/export")
+                .cookie(session.clone())
+                .to_request()
+        };
+        let resp = test::call_service(&app, export()).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.headers().get("Content-Disposition").unwrap(),
+            "attachment; filename=realmview-export.zip"
+        );
+
+        let body = test::read_body(resp).await;
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(body.to_vec())).unwrap();
+        let users: Vec<UserRecord> =
+            serde_json::from_reader(zip.by_name("users.json").unwrap()).unwrap();
+        assert_eq!(users[0].username, user.username);
+        let records: Vec<MediaRecord> =
+            serde_json::from_reader(zip.by_name("media.json").unwrap()).unwrap();
+        assert_eq!(records[0].uuid, format_uuid(media.uuid));
+        let projects: Vec<ProjectRecord> =
+            serde_json::from_reader(zip.by_name("projects.json").unwrap()).unwrap();
+        assert_eq!(projects[0].uuid, format_uuid(project.uuid));
+        assert!(zip.by_name("scenes.json").is_ok());
+        assert!(zip.by_name("user_api_keys.json").is_ok());
+
+        // Exports are limited to one a day.
+        let resp = test::call_service(&app, export()).await;
+        assert_eq!(resp.status(), 429);
+
+        // Released reservations don't count, so an export which fails part way
+        // through doesn't use up the day's export.
+        let other = User::generate(conn).await;
+        let reservation = DataExport::reserve(conn, other.uuid).await.unwrap();
+        assert!(DataExport::reserve(conn, other.uuid).await.is_err());
+        DataExport::release(conn, other.uuid, reservation)
+            .await
+            .unwrap();
+        assert!(DataExport::reserve(conn, other.uuid).await.is_ok());
+        assert!(DataExport::reserve(conn, other.uuid).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_delete_account() {
+        // TEST
+        //   DELETE /api/user/account
+
+        let db = initialise_database().await.unwrap();
+        let games: Data<Games> = Data::new(tokio::sync::RwLock::new(HashMap::new()));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .app_data(games.clone())
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+        let media = Media::prepare(&user, "png", "map", "DELETE_HASH", 1)
+            .create(conn)
+            .await
+            .unwrap();
+        let media_path = join_relative_path(&CONTENT, &media.relative_path);
+        write_file(&media_path, b"PNG").await.unwrap();
+        let project = Project::create(conn, &user, "Campaign").await.unwrap();
+        let mut proj = project.load(conn).await.unwrap();
+        proj.new_scene();
+        let (_, scenes) = Project::save(conn, &user, proj).await.unwrap();
+        let scene = scenes[0].uuid;
+
+        // Games hosted by the user are ended.
+        let req = test::TestRequest::post()
+            .uri("/api/game/new")
+            .cookie(session.clone())
+            .set_json(serde_json::json!({ "scene": scene }))
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        let handle = games.read().await.values().next().unwrap().clone();
+
+        let delete = |password: &str| {
+            test::TestRequest::delete()
+                .uri("/api/user/account")
+                .cookie(session.clone())
+                .set_json(DeleteAccountRequest {
+                    password: password.to_string(),
+                })
+                .to_request()
+        };
+
+        // The password must be provided.
+        let resp: Binary = test::call_and_read_body_json(&app, delete("wrong")).await;
+        assert!(!resp.success);
+        assert!(User::get_by_uuid(conn, user.uuid).await.is_ok());
+
+        let resp: Binary =
+            test::call_and_read_body_json(&app, delete(UserAuth::GENERATED_USER_PASSWORD)).await;
+        assert!(resp.success);
+        assert!(games.read().await.is_empty());
+        assert!(!handle.open());
+        assert!(User::get_by_uuid(conn, user.uuid).await.is_err());
+        assert!(Media::load(conn, media.uuid).await.is_err());
+        assert!(Project::lookup(conn, project.uuid).await.unwrap().is_none());
+        assert!(!media_path.exists());
+        assert!(!SAVES.join(&user.username).exists());
+
+        // The deletion is logged, and the log can't be altered.
+        let uuid = format_uuid(user.uuid);
+        let count: i64 =
+            sqlx::query_scalar(r#"SELECT COUNT(*) FROM account_deletions WHERE "user" = $1;"#)
+                .bind(&uuid)
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+        assert_eq!(count, 1);
+        assert!(
+            sqlx::query(r#"DELETE FROM account_deletions WHERE "user" = $1;"#)
+                .bind(&uuid)
+                .execute(&mut **conn)
+                .await
+                .is_err()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_two_factor() {
+        // TEST
+        //   POST /api/user/2fa/setup
+        //   POST /api/user/2fa/confirm
+        //   POST /api/auth/login
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        let login = |code: Option<String>| {
+            test::TestRequest::post()
+                .uri("/api/auth/login")
+                .set_json(serde_json::json!({
+                    "username": user.username,
+                    "password": UserAuth::GENERATED_USER_PASSWORD,
+                    "totp_code": code,
+                }))
+                .to_request()
+        };
+        let confirm = |code: String| {
+            test::TestRequest::post()
+                .uri("/api/user/2fa/confirm")
+                .cookie(session.clone())
+                .set_json(TwoFactorConfirmRequest { code })
+                .to_request()
+        };
+
+        // Nothing to confirm before setup.
+        let resp: Binary = test::call_and_read_body_json(&app, confirm("000000".into())).await;
+        assert!(!resp.success);
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/2fa/setup")
+            .cookie(session.clone())
+            .to_request();
+        let setup: TwoFactorSetupResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(setup.success);
+        assert!(setup.uri.starts_with("otpauth://totp/"));
+        assert!(setup.uri.contains(&setup.secret));
+
+        // Until confirmed, login doesn't require a code.
+        let resp: Binary = test::call_and_read_body_json(&app, login(None)).await;
+        assert!(resp.success);
+
+        let code = totp_code(&setup.secret, 0);
+        let wrong = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+        let resp: Binary = test::call_and_read_body_json(&app, confirm(wrong.clone())).await;
+        assert!(!resp.success);
+        let resp: Binary = test::call_and_read_body_json(&app, confirm(code.clone())).await;
+        assert!(resp.success);
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/profile")
+            .cookie(session.clone())
+            .to_request();
+        let resp: ProfileResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.two_factor_enabled);
+
+        // Login now requires a valid code.
+        let resp: Binary = test::call_and_read_body_json(&app, login(None)).await;
+        assert!(!resp.success);
+        let resp: Binary = test::call_and_read_body_json(&app, login(Some(wrong))).await;
+        assert!(!resp.success);
+        let next = totp_code(&setup.secret, 1);
+        let resp: Binary = test::call_and_read_body_json(&app, login(Some(next.clone()))).await;
+        assert!(resp.success);
+
+        // Codes can't be reused, including the one used to confirm setup.
+        let resp: Binary = test::call_and_read_body_json(&app, login(Some(code))).await;
+        assert!(!resp.success);
+        let resp: Binary = test::call_and_read_body_json(&app, login(Some(next))).await;
+        assert!(!resp.success);
+
+        // Setup can't be restarted once enabled.
+        let req = test::TestRequest::post()
+            .uri("/api/user/2fa/setup")
+            .cookie(session.clone())
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+    }
+}
@@ -0,0 +1,213 @@
+use actix_web::web;
+use uuid::Uuid;
+
+use super::{res_failure, res_json, res_success, Binary, Resp};
+use crate::{
+    models::{User, Webhook, WebhookEvent},
+    req::Pool,
+    utils::format_uuid,
+};
+
+pub fn routes() -> actix_web::Scope {
+    web::scope("/webhooks")
+        .route("", web::get().to(list))
+        .route("", web::post().to(create))
+        .route("/{uuid}", web::delete().to(delete))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(list, create, delete))]
+pub(super) struct Api;
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct WebhookInfo {
+    uuid: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+}
+
+impl WebhookInfo {
+    fn new(hook: Webhook) -> Self {
+        Self {
+            uuid: format_uuid(hook.uuid),
+            url: hook.url,
+            events: hook.events,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct WebhookListResponse {
+    message: String,
+    success: bool,
+    webhooks: Vec<WebhookInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses((status = 200, body = WebhookListResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn list(mut conn: Pool, user: User) -> Resp {
+    match Webhook::list(conn.acquire(), user.uuid).await {
+        Ok(hooks) => res_json(WebhookListResponse {
+            message: "Webhooks follow.".to_string(),
+            success: true,
+            webhooks: hooks.into_iter().map(WebhookInfo::new).collect(),
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+#[derive(serde_derive::Deserialize, utoipa::ToSchema)]
+struct WebhookRequest {
+    url: String,
+    events: Vec<WebhookEvent>,
+}
+
+#[cfg_attr(test, derive(serde_derive::Deserialize))]
+#[derive(serde_derive::Serialize, utoipa::ToSchema)]
+struct WebhookResponse {
+    message: String,
+    success: bool,
+    uuid: String,
+    url: String,
+    events: Vec<WebhookEvent>,
+
+    /// Key used to sign payloads. Only ever returned on creation.
+    secret: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = WebhookRequest,
+    responses((status = 200, body = WebhookResponse)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn create(mut conn: Pool, user: User, req: web::Json<WebhookRequest>) -> Resp {
+    match Webhook::create(conn.acquire(), user.uuid, &req.url, &req.events).await {
+        Ok(hook) => res_json(WebhookResponse {
+            message: "Webhook created.".to_string(),
+            success: true,
+            uuid: format_uuid(hook.uuid),
+            url: hook.url,
+            events: hook.events,
+            secret: hook.secret,
+        }),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{uuid}",
+    params(("uuid" = String, Path, description = "UUID of the webhook.")),
+    responses((status = 200, body = Binary)),
+    security(("session" = []), ("api_key" = []))
+)]
+async fn delete(mut conn: Pool, user: User, path: web::Path<(String,)>) -> Resp {
+    let uuid = match Uuid::try_parse(&path.into_inner().0) {
+        Ok(uuid) => uuid,
+        Err(_) => return res_failure("Invalid webhook UUID."),
+    };
+
+    match Webhook::delete(conn.acquire(), user.uuid, uuid).await {
+        Ok(()) => res_success("Webhook deleted."),
+        Err(e) => res_failure(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{test, web::Data, App};
+
+    use super::{WebhookListResponse, WebhookRequest, WebhookResponse};
+    use crate::{
+        api::Binary,
+        fs::initialise_database,
+        models::{User, WebhookEvent},
+    };
+
+    #[actix_web::test]
+    async fn test_webhooks_api() {
+        // TEST
+        //   POST /api/webhooks
+        //   GET /api/webhooks
+        //   DELETE /api/webhooks/{uuid}
+
+        let db = initialise_database().await.unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db.clone()))
+                .service(crate::api::routes()),
+        )
+        .await;
+        let conn = &mut db.acquire().await.unwrap();
+        let user = User::generate(conn).await;
+        let session = user.session(conn).await;
+
+        // URLs must be HTTP, and not on the server's network.
+        for url in ["ftp://example.com", "http://127.0.0.1:8080/hook"] {
+            let req = test::TestRequest::post()
+                .uri("/api/webhooks")
+                .cookie(session.clone())
+                .set_json(WebhookRequest {
+                    url: url.to_string(),
+                    events: vec![WebhookEvent::GameStart],
+                })
+                .to_request();
+            let resp: Binary = test::call_and_read_body_json(&app, req).await;
+            assert!(!resp.success);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/webhooks")
+            .cookie(session.clone())
+            .set_json(WebhookRequest {
+                url: "https://example.com/hook".to_string(),
+                events: vec![WebhookEvent::PlayerJoin, WebhookEvent::PlayerJoin],
+            })
+            .to_request();
+        let resp: WebhookResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+        assert_eq!(resp.events, vec![WebhookEvent::PlayerJoin]);
+        assert_eq!(resp.secret.len(), 64);
+        let uuid = resp.uuid;
+
+        let req = test::TestRequest::get()
+            .uri("/api/webhooks")
+            .cookie(session.clone())
+            .to_request();
+        let resp: WebhookListResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.webhooks.len(), 1);
+        assert_eq!(resp.webhooks[0].url, "https://example.com/hook");
+
+        // Other users can't delete the webhook.
+        let other = User::generate(conn).await;
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/webhooks/{uuid}"))
+            .cookie(other.session(conn).await)
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.success);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/webhooks/{uuid}"))
+            .cookie(session.clone())
+            .to_request();
+        let resp: Binary = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.success);
+
+        let req = test::TestRequest::get()
+            .uri("/api/webhooks")
+            .cookie(session)
+            .to_request();
+        let resp: WebhookListResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.webhooks.is_empty());
+    }
+}
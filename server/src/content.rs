@@ -16,13 +16,17 @@ pub fn routes() -> actix_web::Scope {
     web::scope("")
         .route("/login", public(files::LOGIN))
         .route("/register", public(files::REGISTER))
+        .route("/forgot-password", public(files::FORGOT_PASSWORD))
+        .route("/reset-password", public(files::RESET_PASSWORD))
         .route("/media", loggedin(files::MEDIA))
+        .route("/profile", loggedin(files::PROFILE))
         .route("/game_over", public(files::GAME_OVER))
         .route("/disconnected", public(files::DISCONNECTED))
         .route("/landing", loggedin(files::LANDING))
         .route("/not_found", public(files::NOT_FOUND))
         .service(projects())
         .service(game())
+        .route("/share/{token}", public(files::EDITOR))
         .route("/", public(files::INDEX))
         .service(actix_files::Files::new("/", &*CONTENT).index_file(files::INDEX))
 }
@@ -99,6 +103,7 @@ async fn new_scene(
 
 mod files {
     pub const EDIT_PROJECT: &str = "edit_project.html";
+    pub const FORGOT_PASSWORD: &str = "forgot_password.html";
     pub const GAME: &str = "game.html";
     pub const GAME_OVER: &str = "game_over.html";
     pub const DISCONNECTED: &str = "disconnected.html";
@@ -108,7 +113,9 @@ mod files {
     pub const MEDIA: &str = "media.html";
     pub const NEW_PROJECT: &str = "new_project.html";
     pub const NOT_FOUND: &str = "not_found.html";
+    pub const PROFILE: &str = "profile.html";
     pub const PROJECTS: &str = "projects.html";
     pub const REGISTER: &str = "register.html";
+    pub const RESET_PASSWORD: &str = "reset_password.html";
     pub const EDITOR: &str = "editor.html";
 }
@@ -4,8 +4,9 @@ use ring::{
     pbkdf2,
     rand::{SecureRandom, SystemRandom},
 };
+use totp_rs::{Algorithm, Secret, TOTP};
 
-use crate::utils::{err, Res};
+use crate::utils::{err, timestamp_s, Res};
 
 pub const KEY_LENGTH: usize = ring::digest::SHA256_OUTPUT_LEN;
 pub type Key = [u8; KEY_LENGTH];
@@ -41,6 +42,12 @@ pub fn random_hex_string(length: usize) -> Res<String> {
     Ok(to_hex_string(&generate_key()?)[..length].to_string())
 }
 
+/// Hex-encoded SHA-256 hash of a token, for storing tokens which are only
+/// ever compared on lookup.
+pub fn hash_token(token: &str) -> String {
+    to_hex_string(ring::digest::digest(&ring::digest::SHA256, token.as_bytes()).as_ref())
+}
+
 const ITERATIONS: u32 = 10_000;
 pub fn hash_password(salt: &Key, password: &str) -> Key {
     let mut hashed = [0u8; KEY_LENGTH];
@@ -66,6 +73,51 @@ pub fn check_password(provided: &str, salt: &Key, hashed_password: &Key) -> bool
     .is_ok()
 }
 
+/// Generate a new base32-encoded secret for TOTP two-factor authentication.
+pub fn generate_totp_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn totp(secret: &str, account: &str) -> Res<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| e.to_string())?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some("Realmview".to_string()),
+        account.to_string(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// otpauth:// URI which authenticator apps can be provisioned from.
+pub fn totp_uri(secret: &str, account: &str) -> Res<String> {
+    Ok(totp(secret, account)?.get_url())
+}
+
+/// Check a TOTP code against a secret, allowing one step of clock skew.
+/// Returns the time step the code is valid for, so that the caller can reject
+/// codes for steps which have already been used.
+pub fn check_totp(secret: &str, code: &str) -> Res<Option<u64>> {
+    let mut totp = totp(secret, "")?;
+    let skew = u64::from(totp.skew);
+    totp.skew = 0;
+    let current = timestamp_s()? / totp.step;
+    Ok((current.saturating_sub(skew)..=current + skew)
+        .find(|step| totp.check(code.trim(), step * totp.step)))
+}
+
+/// Code for the time step `offset` steps after the current one.
+#[cfg(test)]
+pub fn totp_code(secret: &str, offset: u64) -> String {
+    let totp = totp(secret, "").unwrap();
+    totp.generate((timestamp_s().unwrap() / totp.step + offset) * totp.step)
+}
+
 #[cfg(test)]
 mod test {
     use crate::crypto::{from_hex_string, hash_password};
@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
-use sqlx::{pool::PoolConnection, Sqlite, SqlitePool};
+use sqlx::{pool::PoolConnection, Any, AnyPool};
 use tokio::sync::OnceCell;
 
 use crate::utils::Res;
@@ -12,19 +12,21 @@ static DATA: Lazy<PathBuf> =
 pub static CONTENT: Lazy<PathBuf> = Lazy::new(|| DATA.join("content"));
 pub static SAVES: Lazy<PathBuf> = Lazy::new(|| DATA.join("saves"));
 
-// For production we have a single sqlite pool, initialised once in a OnceCell
-// and then cloned for future accesses.
+// For production we have a single pool, initialised once in a OnceCell and
+// then cloned for future accesses. The database is DATABASE_URL if set, or
+// else a SQLite database in DATA_DIR.
 #[cfg(not(test))]
-static DATABASE: Lazy<OnceCell<SqlitePool>> = Lazy::new(OnceCell::new);
+static DATABASE: Lazy<OnceCell<AnyPool>> = Lazy::new(OnceCell::new);
 
 #[cfg(not(test))]
-pub async fn initialise_database() -> Res<SqlitePool> {
+pub async fn initialise_database() -> Res<AnyPool> {
     if DATABASE.initialized() {
         return Ok(DATABASE.get().unwrap().clone());
     }
 
-    let database_url = std::env::var("DATABASE_URL").expect(super::USAGE);
-    let pool = SqlitePool::connect(&database_url)
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| crate::req::Sqlite::url(&DATA.join("database.db")));
+    let pool = crate::req::connect(&database_url)
         .await
         .expect("Database pool creation failed.");
 
@@ -38,11 +40,11 @@ pub async fn initialise_database() -> Res<SqlitePool> {
 #[cfg(test)]
 thread_local! {
     static DATABASE_INITIALISED: std::sync::atomic::AtomicBool = const { std::sync::atomic::AtomicBool::new(false) };
-    static DATABASE: OnceCell<SqlitePool> = OnceCell::new();
+    static DATABASE: OnceCell<AnyPool> = OnceCell::new();
 }
 
 #[cfg(test)]
-pub async fn initialise_database() -> Res<SqlitePool> {
+pub async fn initialise_database() -> Res<AnyPool> {
     if DATABASE_INITIALISED.with(|ab| ab.load(std::sync::atomic::Ordering::SeqCst)) {
         DATABASE.with(|cell| Ok(cell.get().unwrap().clone()))
     } else {
@@ -52,11 +54,8 @@ pub async fn initialise_database() -> Res<SqlitePool> {
         ));
         println!("Database path: {}", path.display());
 
-        tokio::fs::copy(DATA.join("database.db"), &path)
-            .await
-            .expect("Failed to copy database.");
-        let database_url = format!("sqlite://{}", path.to_string_lossy());
-        let pool = SqlitePool::connect(&database_url)
+        tokio::fs::remove_file(&path).await.ok();
+        let pool = crate::req::connect(&crate::req::Sqlite::url(&path))
             .await
             .expect("Database pool creation failed.");
 
@@ -70,7 +69,7 @@ pub async fn initialise_database() -> Res<SqlitePool> {
     }
 }
 
-pub async fn database_connection() -> Res<PoolConnection<Sqlite>> {
+pub async fn database_connection() -> Res<PoolConnection<Any>> {
     initialise_database()
         .await?
         .acquire()
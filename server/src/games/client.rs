@@ -5,9 +5,10 @@ use futures::{
     future::{select, Either},
     StreamExt,
 };
-use tokio::sync::mpsc::unbounded_channel;
+use scene::{comms::decode_message, perms::Role};
+use tokio::sync::{mpsc::unbounded_channel, OwnedSemaphorePermit};
 
-use super::{close_ws, GameHandle};
+use super::{close_ws, server::ClientFrame, GameHandle};
 use crate::{
     models::User,
     utils::{debug, warning},
@@ -15,14 +16,19 @@ use crate::{
 
 pub fn connect_game_client(
     user: User,
+    role: Role,
+    reconnect: bool,
     server: GameHandle,
     mut session: actix_ws::Session,
     mut stream: actix_ws::MessageStream,
+    permit: OwnedSemaphorePermit,
 ) {
     tokio::task::spawn_local(async move {
+        // Held until the connection closes, counting towards the limit.
+        let _permit = permit;
         let (send, recv) = unbounded_channel();
 
-        if server.join(user.uuid, user.username, send).is_err() {
+        if server.join(&user, role, send, reconnect).is_err() {
             close_ws(session).await; // Server closed.
             return;
         }
@@ -32,7 +38,7 @@ pub fn connect_game_client(
         loop {
             match select(stream.next(), recv.next()).await {
                 Either::Left((Some(Ok(message)), _)) => match message {
-                    Message::Binary(bytes) => match bincode::deserialize(&bytes) {
+                    Message::Binary(bytes) => match decode_message(&bytes) {
                         Ok(message) => {
                             if server.message(user.uuid, message).is_err() {
                                 close_ws(session).await; // Server closed.
@@ -49,6 +55,12 @@ pub fn connect_game_client(
                         break;
                     }
                     Message::Ping(_) => warn_err(session.pong(b"PONG").await),
+                    Message::Pong(_) => {
+                        if server.pong(user.uuid).is_err() {
+                            close_ws(session).await; // Server closed.
+                            break;
+                        }
+                    }
                     msg => warning(format!("Unexpected WS message: {msg:?}")),
                 },
                 Either::Left((Some(Err(e)), _)) => {
@@ -61,8 +73,12 @@ pub fn connect_game_client(
                     ));
                     break;
                 }
-                Either::Right((Some(msg), _)) => {
-                    if session.binary(msg).await.is_err() {
+                Either::Right((Some(frame), _)) => {
+                    let result = match frame {
+                        ClientFrame::Binary(msg) => session.binary(msg).await,
+                        ClientFrame::Ping => session.ping(b"PING").await,
+                    };
+                    if result.is_err() {
                         debug(format!(
                             "Client ({}) disconnected without reason.",
                             user.uuid
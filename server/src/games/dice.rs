@@ -0,0 +1,138 @@
+use rand::Rng;
+
+use crate::utils::{err, Res};
+
+/// Largest number of dice that may be rolled at once.
+const MAX_DICE: u32 = 100;
+
+/// Largest number of sides a die may have.
+const MAX_SIDES: u32 = 1000;
+
+/// A roll in dice notation, of the form `NdX+M`. A bare constant has no dice.
+#[derive(Debug, PartialEq)]
+struct Dice {
+    count: u32,
+    sides: u32,
+    modifier: i32,
+}
+
+pub struct Roll {
+    pub rolls: Vec<u32>,
+    pub total: i32,
+}
+
+fn parse_number<T: std::str::FromStr>(string: &str, notation: &str) -> Res<T> {
+    string
+        .parse()
+        .map_err(|_| format!("Invalid dice notation: {notation}"))
+}
+
+/// Parse dice notation, handling `NdX`, `NdX+M`, `NdX-M` and bare constants.
+/// The count may be omitted to roll a single die, as in `d20`.
+fn parse(notation: &str) -> Res<Dice> {
+    let string: String = notation
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+
+    let Some(d) = string.find('d') else {
+        return Ok(Dice {
+            count: 0,
+            sides: 0,
+            modifier: parse_number(&string, notation)?,
+        });
+    };
+
+    let count = if d == 0 {
+        1
+    } else {
+        parse_number(&string[..d], notation)?
+    };
+
+    let rest = &string[d + 1..];
+    let (sides, modifier) = match rest.find(['+', '-']) {
+        Some(i) => (
+            parse_number(&rest[..i], notation)?,
+            parse_number(&rest[i..], notation)?,
+        ),
+        None => (parse_number(rest, notation)?, 0),
+    };
+
+    if count == 0 || count > MAX_DICE {
+        return err(format!("Number of dice must be between 1 and {MAX_DICE}."));
+    }
+
+    if sides == 0 || sides > MAX_SIDES {
+        return err(format!(
+            "Number of sides must be between 1 and {MAX_SIDES}."
+        ));
+    }
+
+    Ok(Dice {
+        count,
+        sides,
+        modifier,
+    })
+}
+
+pub fn roll(notation: &str) -> Res<Roll> {
+    let dice = parse(notation)?;
+    let mut rng = rand::thread_rng();
+    let rolls: Vec<u32> = (0..dice.count)
+        .map(|_| rng.gen_range(1..=dice.sides))
+        .collect();
+    let Some(total) = i32::try_from(rolls.iter().sum::<u32>())
+        .ok()
+        .and_then(|sum| sum.checked_add(dice.modifier))
+    else {
+        return err(format!("Roll total out of range: {notation}"));
+    };
+    Ok(Roll { rolls, total })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, roll, Dice};
+
+    #[test]
+    fn test_parse() {
+        let dice = |count, sides, modifier| Dice {
+            count,
+            sides,
+            modifier,
+        };
+
+        assert_eq!(parse("2d6").unwrap(), dice(2, 6, 0));
+        assert_eq!(parse("2d6+3").unwrap(), dice(2, 6, 3));
+        assert_eq!(parse("1d20 - 1").unwrap(), dice(1, 20, -1));
+        assert_eq!(parse("D8").unwrap(), dice(1, 8, 0));
+        assert_eq!(parse("5").unwrap(), dice(0, 0, 5));
+        assert_eq!(parse("-2").unwrap(), dice(0, 0, -2));
+
+        assert!(parse("").is_err());
+        assert!(parse("2d").is_err());
+        assert!(parse("0d6").is_err());
+        assert!(parse("2d0").is_err());
+        assert!(parse("1000d6").is_err());
+        assert!(parse("2d6+").is_err());
+        assert!(parse("abc").is_err());
+    }
+
+    #[test]
+    fn test_roll() {
+        for _ in 0..100 {
+            let result = roll("3d6+2").unwrap();
+            assert_eq!(result.rolls.len(), 3);
+            assert!(result.rolls.iter().all(|r| (1..=6).contains(r)));
+            assert_eq!(result.total, result.rolls.iter().sum::<u32>() as i32 + 2);
+        }
+
+        let result = roll("7").unwrap();
+        assert!(result.rolls.is_empty());
+        assert_eq!(result.total, 7);
+
+        assert!(roll("1d6+2147483647").is_err());
+        assert!(roll("2147483647").is_ok());
+    }
+}
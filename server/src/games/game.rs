@@ -3,6 +3,7 @@ use std::{collections::HashMap, fmt::Display};
 use scene::Id;
 use uuid::Uuid;
 
+use super::initiative::Initiative;
 use crate::{
     scene::{
         comms::{PermsEvent, SceneEvent},
@@ -46,12 +47,24 @@ impl Display for GameKey {
     }
 }
 
+/// An image shown to a player by an editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Handout {
+    pub media_key: String,
+    pub sender: String,
+    recipient: Uuid,
+}
+
 pub struct Game {
     pub key: GameKey,
     project: scene::Project,
     scene: scene::Scene,
     perms: Perms,
     users: HashMap<Uuid, String>,
+    initiative: Initiative,
+
+    /// Handouts waiting to be sent to players who are offline.
+    handouts: Vec<Handout>,
 }
 
 impl Game {
@@ -66,6 +79,8 @@ impl Game {
             scene,
             perms,
             users: HashMap::new(),
+            initiative: Initiative::default(),
+            handouts: Vec::new(),
         }
     }
 
@@ -85,6 +100,10 @@ impl Game {
         self.perms.handle_event(user, event)
     }
 
+    pub fn role(&self, user: Uuid) -> perms::Role {
+        self.perms.get_role(user)
+    }
+
     pub fn owner_is(&self, user: Uuid) -> bool {
         matches!(self.perms.get_role(user), perms::Role::Owner)
     }
@@ -124,25 +143,28 @@ impl Game {
         }
     }
 
-    /// Adds a player to the permissions set up and creates a layer for that
-    /// player if none exists.
+    /// Adds a player to the permissions set up with the given role and
+    /// creates a layer for that player if none exists. Spectators don't
+    /// receive a layer.
     ///
     /// Returns a tuple of (perms_event, scene_event, player_layer)
     pub fn add_player(
         &mut self,
         user: Uuid,
         name: &str,
+        role: perms::Role,
     ) -> (Vec<PermsEvent>, Option<SceneEvent>, Option<scene::Id>) {
         self.users.insert(user, name.to_string());
 
         let mut perms = Vec::new();
-        if let Some(event) =
-            self.perms
-                .role_change(perms::CANONICAL_UPDATER, user, perms::Role::Player)
-        {
+        if let Some(event) = self.perms.role_change(perms::CANONICAL_UPDATER, user, role) {
             perms.push(event);
         }
 
+        if self.perms.get_role(user).spectator() {
+            return (perms, None, None);
+        }
+
         let (scene, layer) = self.player_layer(user);
         if let Some(id) = layer
             && let Some(event) = self.perms.grant_override(user, id)
@@ -179,8 +201,76 @@ impl Game {
     }
 
     pub fn handle_event(&mut self, user: Uuid, event: SceneEvent) -> bool {
-        let layer = self.scene.event_layer(&event);
-        self.perms.permitted(user, &event, layer) && self.scene.apply_event(event.clone())
+        self.scene.permitted(&self.perms, user, &event) && self.scene.apply_event(event.clone())
+    }
+
+    /// Grant a player overrides over any sprites they created in event, so
+    /// that they may move them on layers which allow moving their own sprites.
+    pub fn grant_new_sprites(&mut self, user: Uuid, event: &SceneEvent) -> Vec<PermsEvent> {
+        self.perms.grant_new_sprites(user, event)
+    }
+
+    /// Reveal fog around light sources after a change to sprites, which may
+    /// have moved or added a light, or after fog is turned on. Returns the
+    /// reveal event, if any tiles were revealed.
+    pub fn reveal_lights(&mut self, event: &SceneEvent) -> Option<SceneEvent> {
+        if event.is_sprite() || matches!(event, SceneEvent::FogActive(..)) {
+            self.scene.reveal_lights()
+        } else {
+            None
+        }
+    }
+
+    /// Record an initiative roll. Spectators may not take part in the
+    /// initiative order. Returns whether the roll was accepted.
+    pub fn set_initiative(&mut self, user: Uuid, name: String, roll: i32) -> bool {
+        self.perms.get_role(user).player() && self.initiative.set(user, name, roll)
+    }
+
+    /// Clear the initiative order. Only editors may do this. Returns whether
+    /// the order was cleared.
+    pub fn clear_initiative(&mut self, user: Uuid) -> bool {
+        if self.perms.get_role(user).editor() {
+            self.initiative.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn initiative_list(&self) -> Vec<(String, i32)> {
+        self.initiative.list()
+    }
+
+    /// Keep a handout for `recipient` until they next connect. Handouts may
+    /// only be queued for users who have joined the game. Returns whether
+    /// the handout was queued.
+    pub fn queue_handout(&mut self, recipient: Uuid, media_key: String, sender: String) -> bool {
+        const MAX_QUEUED: usize = 32;
+
+        if !self.users.contains_key(&recipient) || self.handouts.len() >= MAX_QUEUED {
+            return false;
+        }
+
+        self.handouts.push(Handout {
+            media_key,
+            sender,
+            recipient,
+        });
+        true
+    }
+
+    /// Remove and return the handouts queued for `user`, oldest first.
+    pub fn take_handouts(&mut self, user: Uuid) -> Vec<Handout> {
+        let (taken, kept) = std::mem::take(&mut self.handouts)
+            .into_iter()
+            .partition(|h| h.recipient == user);
+        self.handouts = kept;
+        taken
+    }
+
+    pub fn clear_handouts(&mut self) {
+        self.handouts.clear();
     }
 
     pub fn switch_to_scene(&mut self, scene: Uuid) -> Res<()> {
@@ -202,8 +292,19 @@ impl Game {
         self.scene.clone()
     }
 
-    pub fn client_scene(&mut self) -> scene::Scene {
-        self.scene.non_canon()
+    /// The scene to send to a client, without private information unless
+    /// the client is an editor.
+    pub fn client_scene(&mut self, role: perms::Role) -> scene::Scene {
+        let mut scene = self.scene.non_canon();
+        if !role.editor() {
+            scene.strip_private();
+        }
+        scene
+    }
+
+    /// The sprite which is `user`'s token, if any.
+    pub fn own_sprite(&self, user: Uuid) -> Option<Id> {
+        self.scene.owned_sprite(user)
     }
 
     pub fn scene_list(&self) -> (Vec<(String, Uuid)>, Uuid) {
@@ -226,7 +327,7 @@ mod test {
     use scene::{comms::SceneEvent, Colour, Point, Project, Rect, Sprite, SpriteVisual};
 
     use super::Game;
-    use crate::{games::game::GameKey, utils::generate_uuid};
+    use crate::{games::game::GameKey, scene::perms::Role, utils::generate_uuid};
 
     #[test]
     fn test_permissions() {
@@ -246,7 +347,7 @@ mod test {
         ));
 
         // Adding a new player should create a layer for that player.
-        let (perms, layer_event, layer_opt) = game.add_player(player, "player");
+        let (perms, layer_event, layer_opt) = game.add_player(player, "player", Role::Player);
         assert!(!perms.is_empty());
         assert!(layer_event.is_some());
         assert!(layer_opt.is_some());
@@ -291,6 +392,29 @@ mod test {
         assert!(game.scene.sprite(owner_sprite).is_none());
     }
 
+    #[test]
+    fn test_scene_lock() {
+        let mut project = Project::new(generate_uuid());
+        let scene = project.new_scene().uuid;
+        let owner = generate_uuid();
+        let editor = generate_uuid();
+        let mut game = Game::new(project, scene, owner, GameKey::new().unwrap());
+        game.add_player(editor, "editor", Role::Editor);
+        let layer = game.scene.first_layer();
+
+        assert!(game.handle_event(editor, SceneEvent::SceneLock(true)));
+        assert!(game.scene.locked);
+
+        // Nothing changes while the scene is locked, and only the owner may
+        // unlock it.
+        for user in [owner, editor] {
+            assert!(!game.handle_event(user, SceneEvent::SpriteNew(Sprite::new(8, None), layer)));
+        }
+        assert!(!game.handle_event(editor, SceneEvent::SceneLock(false)));
+        assert!(game.handle_event(owner, SceneEvent::SceneLock(false)));
+        assert!(game.handle_event(editor, SceneEvent::SpriteNew(Sprite::new(8, None), layer)));
+    }
+
     #[test]
     fn test_drawings() {
         let mut project = Project::new(generate_uuid());
@@ -301,7 +425,7 @@ mod test {
         let sprite = 5;
 
         let mut game = Game::new(project, scene, owner, GameKey::new().unwrap());
-        let (_, _, layer) = game.add_player(player, "player");
+        let (_, _, layer) = game.add_player(player, "player", Role::Player);
         let layer = layer.unwrap();
 
         assert!(game.handle_event(
@@ -318,7 +442,9 @@ mod test {
                         colour: Colour::DEFAULT,
                         stroke: 1.,
                         cap_start: scene::Cap::Arrow,
-                        cap_end: scene::Cap::Round
+                        cap_end: scene::Cap::Round,
+                        taper_start: false,
+                        taper_end: false
                     })
                 ),
                 layer
@@ -329,4 +455,56 @@ mod test {
             SceneEvent::SpriteDrawingPoint(drawing, Point::same(1.))
         ));
     }
+
+    #[test]
+    fn test_handouts() {
+        let mut project = Project::new(generate_uuid());
+        let scene = project.new_scene().uuid;
+        let owner = generate_uuid();
+        let player = generate_uuid();
+        let other = generate_uuid();
+        let mut game = Game::new(project, scene, owner, GameKey::new().unwrap());
+
+        // Handouts can't be queued for users who haven't joined.
+        assert!(!game.queue_handout(player, "1".into(), "GM".into()));
+
+        game.add_player(player, "player", Role::Player);
+        game.add_player(other, "other", Role::Player);
+        assert!(game.queue_handout(player, "1".into(), "GM".into()));
+        assert!(game.queue_handout(other, "2".into(), "GM".into()));
+        assert!(game.queue_handout(player, "3".into(), "GM".into()));
+
+        let keys = |handouts: Vec<super::Handout>| -> Vec<String> {
+            handouts.into_iter().map(|h| h.media_key).collect()
+        };
+        assert_eq!(keys(game.take_handouts(player)), vec!["1", "3"]);
+        assert!(game.take_handouts(player).is_empty());
+
+        game.clear_handouts();
+        assert!(game.take_handouts(other).is_empty());
+    }
+
+    #[test]
+    fn test_observers() {
+        let mut project = Project::new(generate_uuid());
+        let scene = project.new_scene().uuid;
+        let owner = generate_uuid();
+        let observer = generate_uuid();
+        let mut game = Game::new(project, scene, owner, GameKey::new().unwrap());
+
+        // Observers shouldn't get a layer.
+        let (perms, layer_event, layer) = game.add_player(observer, "observer", Role::Spectator);
+        assert!(!perms.is_empty());
+        assert!(layer_event.is_none());
+        assert!(layer.is_none());
+        assert_eq!(game.role(observer), Role::Spectator);
+
+        // Observers shouldn't be able to alter the scene.
+        let layer = game.scene.first_layer();
+        assert!(!game.handle_event(observer, SceneEvent::SpriteNew(Sprite::new(5, None), layer)));
+        assert!(!game.handle_event(
+            observer,
+            SceneEvent::SpriteDrawingStart(3, scene::DrawingMode::Freehand)
+        ));
+    }
 }
@@ -0,0 +1,110 @@
+use uuid::Uuid;
+
+/// Turn order for a game. Entries are kept sorted from highest roll to
+/// lowest, with ties broken by the order in which rolls were submitted.
+#[derive(Default)]
+pub struct Initiative {
+    entries: Vec<(Uuid, String, i32)>, // (user, name, roll)
+}
+
+impl Initiative {
+    /// Names are truncated to this many characters.
+    const MAX_NAME_LENGTH: usize = 64;
+
+    /// Largest number of entries in the order.
+    const MAX_ENTRIES: usize = 100;
+
+    /// Record a roll for the named character. A user may have several
+    /// characters in the order; rolling again for the same name replaces the
+    /// earlier roll. Returns whether the roll was recorded, which it isn't if
+    /// the name is blank or the order is full.
+    pub fn set(&mut self, user: Uuid, name: String, roll: i32) -> bool {
+        let name: String = name.trim().chars().take(Self::MAX_NAME_LENGTH).collect();
+        if name.is_empty() {
+            return false;
+        }
+
+        self.entries.retain(|(u, n, _)| !(*u == user && n == &name));
+        if self.entries.len() >= Self::MAX_ENTRIES {
+            return false;
+        }
+
+        let idx = self
+            .entries
+            .iter()
+            .position(|(.., r)| *r < roll)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(idx, (user, name, roll));
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn list(&self) -> Vec<(String, i32)> {
+        self.entries
+            .iter()
+            .map(|(_, name, roll)| (name.clone(), *roll))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Initiative;
+    use crate::utils::generate_uuid;
+
+    #[test]
+    fn test_initiative_order() {
+        let mut initiative = Initiative::default();
+        let player = generate_uuid();
+        let other = generate_uuid();
+
+        initiative.set(player, "Alice".into(), 12);
+        initiative.set(other, "Bob".into(), 15);
+        initiative.set(other, "Wolf".into(), 12);
+        assert_eq!(
+            initiative.list(),
+            vec![
+                ("Bob".into(), 15),
+                ("Alice".into(), 12),
+                ("Wolf".into(), 12)
+            ]
+        );
+
+        // Rolling again replaces the previous entry.
+        initiative.set(player, "Alice".into(), 20);
+        assert_eq!(
+            initiative.list(),
+            vec![
+                ("Alice".into(), 20),
+                ("Bob".into(), 15),
+                ("Wolf".into(), 12)
+            ]
+        );
+
+        initiative.clear();
+        assert!(initiative.list().is_empty());
+    }
+
+    #[test]
+    fn test_initiative_limits() {
+        let mut initiative = Initiative::default();
+        let player = generate_uuid();
+
+        assert!(!initiative.set(player, " ".into(), 10));
+        assert!(initiative.set(player, "a".repeat(Initiative::MAX_NAME_LENGTH * 2), 10));
+        assert_eq!(initiative.list()[0].0.len(), Initiative::MAX_NAME_LENGTH);
+
+        for i in 1..Initiative::MAX_ENTRIES {
+            assert!(initiative.set(player, format!("Goblin {i}"), 5));
+        }
+        assert!(!initiative.set(player, "Straggler".into(), 5));
+
+        // Entries already in the order may still be rerolled.
+        assert!(initiative.set(player, "Goblin 1".into(), 20));
+        assert_eq!(initiative.list()[0], ("Goblin 1".into(), 20));
+        assert_eq!(initiative.list().len(), Initiative::MAX_ENTRIES);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+
+use uuid::Uuid;
+
+/// Recently broadcast events, kept so that a client reconnecting after a
+/// brief drop can be sent only the events it missed instead of the whole
+/// scene.
+pub struct Journal {
+    entries: VecDeque<Entry>,
+    next: u64,
+}
+
+struct Entry {
+    sequence: u64,
+    origin: Option<Uuid>,
+    audience: Audience,
+    message: Vec<u8>,
+}
+
+/// Clients a message was sent to, by role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Audience {
+    All,
+    /// Clients below the editor role, who receive a filtered version of
+    /// messages carrying private information.
+    Players,
+    Editors,
+}
+
+impl Audience {
+    pub fn includes(self, editor: bool) -> bool {
+        match self {
+            Audience::All => true,
+            Audience::Players => !editor,
+            Audience::Editors => editor,
+        }
+    }
+}
+
+impl Journal {
+    const CAPACITY: usize = 500;
+
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(Self::CAPACITY),
+            next: 1,
+        }
+    }
+
+    /// Sequence number of the most recent entry.
+    pub fn latest(&self) -> u64 {
+        self.next - 1
+    }
+
+    /// Sequence number the next entry will have.
+    pub fn next_sequence(&self) -> u64 {
+        self.next
+    }
+
+    /// Record an encoded message, which was broadcast to clients in
+    /// `audience` other than `origin`. The message should include the
+    /// sequence number, as given by `next_sequence`.
+    pub fn push(&mut self, origin: Option<Uuid>, audience: Audience, message: Vec<u8>) {
+        if self.entries.len() >= Self::CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry {
+            sequence: self.next,
+            origin,
+            audience,
+            message,
+        });
+        self.next += 1;
+    }
+
+    /// Drop all entries, for when clients are sent a new scene. Returns a
+    /// sequence number marking the new state, so that clients which missed
+    /// the new scene can't replay from before it.
+    pub fn reset(&mut self) -> u64 {
+        self.entries.clear();
+        self.next += 1;
+        self.latest()
+    }
+
+    /// Messages sent after `sequence` to a user with the given editor status,
+    /// excluding those `user` sent. None if the journal doesn't extend back
+    /// that far.
+    pub fn since(&self, sequence: u64, user: Uuid, editor: bool) -> Option<Vec<&[u8]>> {
+        let first = self.entries.front().map_or(self.next, |e| e.sequence);
+        if sequence >= self.next || sequence + 1 < first {
+            return None;
+        }
+
+        Some(
+            self.entries
+                .iter()
+                .filter(|e| {
+                    e.sequence > sequence && e.origin != Some(user) && e.audience.includes(editor)
+                })
+                .map(|e| e.message.as_slice())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Audience, Journal};
+    use crate::utils::generate_uuid;
+
+    #[test]
+    fn test_journal_replay() {
+        let mut journal = Journal::new();
+        let player = generate_uuid();
+        let other = generate_uuid();
+
+        // Clients up to date before any events get nothing.
+        assert_eq!(journal.since(0, player, false), Some(Vec::new()));
+        assert!(journal.since(1, player, false).is_none());
+
+        journal.push(None, Audience::All, vec![1]);
+        journal.push(Some(player), Audience::All, vec![2]);
+        journal.push(Some(other), Audience::All, vec![3]);
+        assert_eq!(journal.latest(), 3);
+        assert_eq!(
+            journal.since(0, player, false),
+            Some(vec![&[1u8][..], &[3u8][..]])
+        );
+        assert_eq!(journal.since(2, other, false), Some(Vec::new()));
+
+        // Private messages are only replayed to their audience.
+        journal.push(None, Audience::Players, vec![4]);
+        journal.push(None, Audience::Editors, vec![5]);
+        assert_eq!(journal.since(3, player, false), Some(vec![&[4u8][..]]));
+        assert_eq!(journal.since(3, player, true), Some(vec![&[5u8][..]]));
+
+        // After a reset, only clients which saw the reset may replay.
+        let marker = journal.reset();
+        assert!(journal.since(5, player, false).is_none());
+        assert_eq!(journal.since(marker, player, false), Some(Vec::new()));
+
+        // Old entries are evicted.
+        for i in 0..Journal::CAPACITY {
+            journal.push(None, Audience::All, vec![i as u8]);
+        }
+        assert_eq!(
+            journal.since(marker, player, false).unwrap().len(),
+            Journal::CAPACITY
+        );
+        journal.push(None, Audience::All, vec![0]);
+        assert!(journal.since(marker, player, false).is_none());
+        assert_eq!(
+            journal.since(marker + 1, player, false).unwrap().len(),
+            Journal::CAPACITY
+        );
+    }
+}
@@ -1,20 +1,28 @@
+use scene::perms::Role;
+
 use crate::{models::User, utils::warning};
 
 mod client;
+mod dice;
 mod game;
+mod initiative;
+mod journal;
 mod server;
 
 pub use game::GameKey;
 pub use server::launch as launch_server;
-pub use server::GameHandle;
+pub use server::{ConnectionLimit, GameHandle};
 
 pub fn connect_client(
     user: User,
+    role: Role,
+    reconnect: bool,
     server: GameHandle,
     session: actix_ws::Session,
     stream: actix_ws::MessageStream,
+    permit: tokio::sync::OwnedSemaphorePermit,
 ) {
-    client::connect_game_client(user, server, session, stream);
+    client::connect_game_client(user, role, reconnect, server, session, stream, permit);
 }
 
 pub async fn close_ws(session: actix_ws::Session) {
@@ -1,40 +1,118 @@
 use std::collections::HashMap;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use scene::comms::SceneEvent;
-use sqlx::{pool::PoolConnection, SqlitePool};
+use scene::{comms::SceneEvent, perms::Role};
+use sqlx::{pool::PoolConnection, AnyPool};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
 use tokio::time::Instant;
 use uuid::Uuid;
 
+use super::dice;
 use super::game::{Game, GameKey};
-use crate::models::User;
+use super::journal::{Audience, Journal};
+use crate::models::{AuditEntry, User, WebhookEvent};
+use crate::webhooks::{self, Payload};
 use crate::{
     models::Project,
-    scene::comms::{ClientEvent, ClientMessage, ServerEvent},
-    utils::{log, timestamp_us, LogLevel, Res},
+    scene::comms::{encode_message, ClientEvent, ClientMessage, ServerEvent},
+    utils::{log, timestamp_s, timestamp_us, LogLevel, Res},
 };
 
+/// Chat messages are truncated to this many characters.
+const MAX_CHAT_LENGTH: usize = 512;
+
+/// Limit on the number of concurrent WebSocket connections to games. Each
+/// connection holds a permit until it closes.
+#[derive(Clone)]
+pub struct ConnectionLimit {
+    capacity: u32,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConnectionLimit {
+    pub const DEFAULT_CAPACITY: u32 = 1000;
+
+    pub fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            semaphore: Arc::new(Semaphore::new(capacity as usize)),
+        }
+    }
+
+    /// Permit for a new connection, or None if the limit has been reached.
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore.clone().try_acquire_owned().ok()
+    }
+
+    /// Number of connections currently open.
+    pub fn active(&self) -> u32 {
+        self.capacity - self.semaphore.available_permits() as u32
+    }
+
+    /// Wait up to `timeout` for all connections to close, then stop accepting
+    /// connections. Returns the number of connections still open.
+    pub async fn drain(&self, timeout: Duration) -> u32 {
+        let drained = tokio::time::timeout(timeout, self.semaphore.acquire_many(self.capacity))
+            .await
+            .is_ok_and(|permits| permits.is_ok());
+        let remaining = if drained { 0 } else { self.active() };
+        self.semaphore.close();
+        remaining
+    }
+}
+
+/// Frames sent to a client's WebSocket.
+#[derive(Debug)]
+pub enum ClientFrame {
+    Binary(Vec<u8>),
+    Ping,
+}
+
 #[derive(Debug)]
 pub enum ServerCommand {
     Close,
+    /// Close without saving, signalling the sender once closed.
+    Discard(oneshot::Sender<()>),
+    /// Close as the server is shutting down, signalling the sender once saved.
+    Shutdown(oneshot::Sender<()>),
     Join {
         user: Uuid,
         username: String,
-        sender: UnboundedSender<Vec<u8>>,
+        display_name: String,
+        avatar: Option<String>,
+        role: Role,
+        sender: UnboundedSender<ClientFrame>,
+        reconnect: bool,
     },
     Message {
         user: Uuid,
         message: ClientMessage,
     },
+    Pong {
+        user: Uuid,
+    },
+    /// End the game for a user, closing their connection.
+    Remove {
+        user: Uuid,
+    },
+}
+
+/// State of a game, shared between the server and its handles.
+struct GameStatus {
+    open: AtomicBool,
+    player_count: AtomicUsize,
+    spectator_count: AtomicUsize,
 }
 
 #[derive(Clone)]
 pub struct GameHandle {
     pub owner: Uuid,
-    open: Arc<AtomicBool>,
+    /// Unix timestamp in seconds at which the game was launched.
+    pub created_at: u64,
+    status: Arc<GameStatus>,
     chan: UnboundedSender<ServerCommand>,
 }
 
@@ -50,24 +128,75 @@ impl GameHandle {
         // our command. In either case, we can mark this game as closed to
         // prevent new players from joining, etc.
         self.send(ServerCommand::Close).ok();
-        self.open.store(false, std::sync::atomic::Ordering::Release);
+        self.status.open.store(false, Ordering::Release);
+    }
+
+    /// Close the game without saving it, for example because the owner's
+    /// account has been deleted. Returns once the server has stopped.
+    pub async fn discard(&self) {
+        let (send, recv) = oneshot::channel();
+        if self.send(ServerCommand::Discard(send)).is_ok() {
+            recv.await.ok();
+        }
+        self.status.open.store(false, Ordering::Release);
+    }
+
+    /// Close the game as the server is shutting down, telling clients that
+    /// the game is over. Returns once the game has been saved.
+    pub async fn shutdown(&self) {
+        let (send, recv) = oneshot::channel();
+        if self.send(ServerCommand::Shutdown(send)).is_ok() {
+            recv.await.ok();
+        }
+        self.status.open.store(false, Ordering::Release);
     }
 
     pub fn open(&self) -> bool {
-        self.open.load(std::sync::atomic::Ordering::Acquire)
+        self.status.open.load(Ordering::Acquire)
+    }
+
+    /// Number of connected clients who are players, including the owner.
+    pub fn player_count(&self) -> usize {
+        self.status.player_count.load(Ordering::Relaxed)
     }
 
-    pub fn join(&self, user: Uuid, username: String, sender: UnboundedSender<Vec<u8>>) -> Res<()> {
+    /// Number of connected clients who are spectators.
+    pub fn spectator_count(&self) -> usize {
+        self.status.spectator_count.load(Ordering::Relaxed)
+    }
+
+    /// Add a client to the game. A reconnecting client isn't sent the game
+    /// state on joining, instead it is expected to send
+    /// `ClientEvent::Reconnect` to catch up on the events it missed.
+    pub fn join(
+        &self,
+        user: &User,
+        role: Role,
+        sender: UnboundedSender<ClientFrame>,
+        reconnect: bool,
+    ) -> Res<()> {
         self.send(ServerCommand::Join {
-            user,
-            username,
+            user: user.uuid,
+            username: user.username.clone(),
+            display_name: user.display_name.clone(),
+            avatar: user.avatar_media_key.clone(),
+            role,
             sender,
+            reconnect,
         })
     }
 
     pub fn message(&self, user: Uuid, message: ClientMessage) -> Res<()> {
         self.send(ServerCommand::Message { user, message })
     }
+
+    pub fn pong(&self, user: Uuid) -> Res<()> {
+        self.send(ServerCommand::Pong { user })
+    }
+
+    pub fn remove(&self, user: Uuid) -> Res<()> {
+        self.send(ServerCommand::Remove { user })
+    }
 }
 
 pub fn launch(
@@ -75,22 +204,27 @@ pub fn launch(
     owner: User,
     project: scene::Project,
     scene: Uuid,
-    pool: SqlitePool,
+    pool: AnyPool,
 ) -> GameHandle {
     let owner_uuid = owner.uuid;
 
-    let open = Arc::new(AtomicBool::new(true));
+    let status = Arc::new(GameStatus {
+        open: AtomicBool::new(true),
+        player_count: AtomicUsize::new(0),
+        spectator_count: AtomicUsize::new(0),
+    });
     let (send, recv) = unbounded_channel();
 
-    let open_ref = open.clone();
+    let status_ref = status.clone();
     tokio::task::spawn(async move {
-        let mut server = Server::new(open_ref, key, owner, project, scene, pool, recv);
+        let mut server = Server::new(status_ref, key, owner, project, scene, pool, recv);
         server.run().await;
     });
 
     GameHandle {
         owner: owner_uuid,
-        open,
+        created_at: timestamp_s().unwrap_or_default(),
+        status,
         chan: send,
     }
 }
@@ -98,15 +232,28 @@ pub fn launch(
 struct Client {
     user: Uuid,
     username: String,
-    sender: Option<UnboundedSender<Vec<u8>>>,
+    display_name: String,
+    avatar: Option<String>,
+    sender: Option<UnboundedSender<ClientFrame>>,
     check_time: Option<Instant>,
     last_event: Instant,
+
+    /// Time at which a WebSocket ping was sent, cleared on receiving a pong.
+    ping_time: Option<Instant>,
 }
 
 impl Client {
+    fn player_info(&self) -> ServerEvent {
+        ServerEvent::PlayerInfo(self.user, self.display_name.clone(), self.avatar.clone())
+    }
+
     fn send(&mut self, message: Vec<u8>) {
+        self.send_frame(ClientFrame::Binary(message));
+    }
+
+    fn send_frame(&mut self, frame: ClientFrame) {
         if let Some(sender) = &self.sender {
-            if sender.send(message).is_err() {
+            if sender.send(frame).is_err() {
                 self.sender = None;
             }
         }
@@ -121,38 +268,48 @@ impl Client {
 }
 
 struct Server {
-    open: Arc<AtomicBool>,
+    status: Arc<GameStatus>,
     owner: User,
     game: Game,
-    pool: SqlitePool,
+    pool: AnyPool,
     handle: UnboundedReceiver<ServerCommand>,
+
+    /// Events to record in the audit log, as `(scene, user, event)`.
+    audit: UnboundedSender<(Uuid, Uuid, SceneEvent)>,
     clients: HashMap<Uuid, Client>,
+    journal: Journal,
     last_save: Instant,
     last_action: Instant,
+    last_ping: Instant,
     empty_time: Option<Instant>,
 }
 
 impl Server {
     fn new(
-        open: Arc<AtomicBool>,
+        status: Arc<GameStatus>,
         key: GameKey,
         owner: User,
         project: scene::Project,
         scene: Uuid,
-        pool: SqlitePool,
+        pool: AnyPool,
         handle: UnboundedReceiver<ServerCommand>,
     ) -> Self {
         let now = Instant::now();
         let owner_uuid = owner.uuid;
+        let (audit, entries) = unbounded_channel();
+        tokio::task::spawn(write_audit_log(pool.clone(), entries));
         Self {
-            open,
+            status,
             owner,
             game: Game::new(project, scene, owner_uuid, key),
             pool,
             handle,
+            audit,
             clients: HashMap::new(),
+            journal: Journal::new(),
             last_save: now,
             last_action: now,
+            last_ping: now,
             empty_time: Some(now),
         }
     }
@@ -171,8 +328,14 @@ impl Server {
         // Time to keep the game open with no clients.
         const EMPTY_TIMEOUT: Duration = Duration::from_secs(30);
 
+        // Interval at which to ping clients' WebSockets.
+        const PING_INTERVAL: Duration = Duration::from_secs(30);
+
         self.log(LogLevel::Debug, "Opened server");
+        self.notify(WebhookEvent::GameStart, None);
 
+        let mut discarded = None;
+        let mut shutdown = None;
         loop {
             match tokio::time::timeout(CHECK_INTERVAL, self.handle.recv()).await {
                 Ok(Some(command)) => match command {
@@ -180,15 +343,51 @@ impl Server {
                         self.log(LogLevel::Debug, "Closed by user.");
                         break;
                     }
+                    ServerCommand::Discard(done) => {
+                        self.log(LogLevel::Debug, "Discarded by user.");
+                        discarded = Some(done);
+                        break;
+                    }
+                    ServerCommand::Shutdown(done) => {
+                        self.log(LogLevel::Debug, "Closing for server shutdown.");
+                        shutdown = Some(done);
+                        break;
+                    }
                     ServerCommand::Join {
                         sender,
                         user,
                         username,
-                    } => self.connect_client(user, username, sender).await,
+                        display_name,
+                        avatar,
+                        role,
+                        reconnect,
+                    } => {
+                        let client = Client {
+                            user,
+                            username,
+                            display_name,
+                            avatar,
+                            sender: Some(sender),
+                            check_time: None,
+                            last_event: Instant::now(),
+                            ping_time: None,
+                        };
+                        self.connect_client(client, role, reconnect).await
+                    }
                     ServerCommand::Message { user, message } => {
                         self.handle_message(message, user).await;
                         continue; // Skip checks on a message.
                     }
+                    ServerCommand::Pong { user } => {
+                        if let Some(client) = self.clients.get_mut(&user) {
+                            client.ping_time = None;
+                        }
+                        continue;
+                    }
+                    ServerCommand::Remove { user } => {
+                        self.send_event(ServerEvent::GameOver, user);
+                        self.drop_client(user);
+                    }
                 },
                 Ok(None) => {
                     // All server handles dropped. Closed.
@@ -200,6 +399,11 @@ impl Server {
 
             // Check if any clients have died.
             self.health_check();
+            self.drop_stale_clients();
+
+            if self.last_ping.elapsed() >= PING_INTERVAL {
+                self.ping_clients();
+            }
 
             if self.last_action.elapsed() >= INACTIVITY_TIMEOUT {
                 self.log(LogLevel::Debug, "Closing due to inactivity.");
@@ -220,8 +424,20 @@ impl Server {
 
         self.broadcast_event(ServerEvent::GameOver, None);
         self.clients.clear();
-        self.save().await;
-        self.open.store(false, std::sync::atomic::Ordering::Release);
+        self.update_counts();
+        if discarded.is_none() {
+            self.save().await;
+        }
+        self.status.open.store(false, Ordering::Release);
+        match discarded {
+            Some(done) => {
+                done.send(()).ok();
+            }
+            None => self.notify(WebhookEvent::GameEnd, None),
+        }
+        if let Some(done) = shutdown {
+            done.send(()).ok();
+        }
         self.log(LogLevel::Debug, "Closed server");
     }
 
@@ -242,25 +458,123 @@ impl Server {
         }
 
         match message.event {
+            ClientEvent::ChatMessage(text) => {
+                let text: String = text.trim().chars().take(MAX_CHAT_LENGTH).collect();
+                if text.is_empty() {
+                    self.send_rejection(message.id, from);
+                    return;
+                }
+
+                self.send_approval(message.id, from);
+                let name = self.display_name(from);
+                let timestamp = timestamp_s().unwrap_or_default();
+                self.broadcast_event(ServerEvent::ChatMessage(from, name, text, timestamp), None);
+            }
+            ClientEvent::CursorMove(at, viewport) => {
+                // Cursor moves are frequent and need no reply.
+                self.broadcast_event(ServerEvent::PlayerCursor(from, Some(at)), Some(from));
+                if self.game.role(from).editor() {
+                    self.broadcast_to(
+                        ServerEvent::PlayerViewport(from, viewport),
+                        Some(from),
+                        Audience::Editors,
+                    );
+                }
+            }
+            ClientEvent::DiceRoll(notation) => {
+                if !self.game.role(from).player() {
+                    self.send_rejection(message.id, from);
+                    return;
+                }
+
+                match dice::roll(&notation) {
+                    Ok(roll) => {
+                        self.send_approval(message.id, from);
+                        let name = self.display_name(from);
+                        self.broadcast_event(
+                            ServerEvent::DiceResult(from, name, notation, roll.rolls, roll.total),
+                            None,
+                        );
+                    }
+                    Err(e) => {
+                        self.log(LogLevel::Debug, format!("Rejected dice roll: {e}"));
+                        self.send_rejection(message.id, from);
+                    }
+                }
+            }
+            ClientEvent::DismissHandout => {
+                if self.game.role(from).editor() {
+                    self.send_approval(message.id, from);
+                    self.game.clear_handouts();
+                    self.broadcast_event(ServerEvent::DismissHandout, Some(from));
+                } else {
+                    self.send_rejection(message.id, from);
+                }
+            }
+            ClientEvent::InitiativeClear => {
+                if self.game.clear_initiative(from) {
+                    self.send_approval(message.id, from);
+                    self.broadcast_initiative();
+                } else {
+                    self.send_rejection(message.id, from);
+                }
+            }
+            ClientEvent::InitiativeSet(name, roll) => {
+                if self.game.set_initiative(from, name, roll) {
+                    self.send_approval(message.id, from);
+                    self.broadcast_initiative();
+                } else {
+                    self.send_rejection(message.id, from);
+                }
+            }
             ClientEvent::Ping => {
                 self.send_approval(message.id, from);
             }
+            ClientEvent::Reconnect(sequence) => {
+                self.send_approval(message.id, from);
+                self.replay(from, sequence);
+            }
             ClientEvent::SceneChange(scene) => {
                 if self.game.owner_is(from) {
                     if let Err(e) = self.game.switch_to_scene(scene) {
                         self.log(LogLevel::Error, format!("Failed to load scene: {e}"));
                         self.send_rejection(message.id, from);
                     } else {
+                        self.journal.reset();
                         self.send_approval(message.id, from);
                     }
                 } else {
                     self.send_rejection(message.id, from);
                 }
             }
-            ClientEvent::SceneUpdate(event) => {
-                if self.game.handle_event(from, event.clone()) {
+            ClientEvent::ShowHandout(media_key, recipient) => {
+                if self.game.role(from).editor() && self.show_handout(from, media_key, recipient) {
                     self.send_approval(message.id, from);
-                    self.broadcast_event(ServerEvent::SceneUpdate(event.clone()), Some(from));
+                } else {
+                    self.send_rejection(message.id, from);
+                }
+            }
+            ClientEvent::SceneUpdate(mut event) => {
+                let attributed = self.attribute_comments(from, &mut event);
+                if self.game.handle_event(from, event.clone()) {
+                    self.audit(from, event.clone());
+                    if attributed {
+                        // The sender's copy of the event differs from the one
+                        // applied, so have it unwind that and take this one.
+                        self.send_rejection(message.id, from);
+                        self.broadcast_scene_update(event.clone(), None);
+                    } else {
+                        self.send_approval(message.id, from);
+                        self.broadcast_scene_update(event.clone(), Some(from));
+                    }
+
+                    for perms in self.game.grant_new_sprites(from, &event) {
+                        self.broadcast_event(ServerEvent::PermsUpdate(perms), None);
+                    }
+
+                    if let Some(reveal) = self.game.reveal_lights(&event) {
+                        self.broadcast_event(ServerEvent::SceneUpdate(reveal), None);
+                    }
                 } else {
                     self.log(LogLevel::Debug, format!("Rejected event: {event:?}"));
                     self.send_rejection(message.id, from);
@@ -280,21 +594,16 @@ impl Server {
         };
     }
 
-    async fn connect_client(&mut self, user: Uuid, name: String, sender: UnboundedSender<Vec<u8>>) {
+    async fn connect_client(&mut self, client: Client, role: Role, reconnect: bool) {
+        let user = client.user;
+        let name = client.username.clone();
         self.disconnect_client(user);
-        self.clients.insert(
-            user,
-            Client {
-                user,
-                username: name.clone(),
-                sender: Some(sender),
-                check_time: None,
-                last_event: Instant::now(),
-            },
-        );
+        self.broadcast_event(client.player_info(), None);
+        self.clients.insert(user, client);
         self.empty_time = None;
 
-        let (perms, scene, layer) = self.game.add_player(user, &name);
+        let (perms, scene, layer) = self.game.add_player(user, &name, role);
+        self.notify(WebhookEvent::PlayerJoin, Some(self.display_name(user)));
 
         for event in perms {
             self.broadcast_event(ServerEvent::PermsUpdate(event), Some(user));
@@ -304,18 +613,45 @@ impl Server {
             self.broadcast_event(ServerEvent::SceneUpdate(event), Some(user));
         }
 
-        let scene = self.game.client_scene();
+        if !reconnect {
+            self.send_state(user, layer);
+        }
+
+        for handout in self.game.take_handouts(user) {
+            self.send_event(
+                ServerEvent::Handout(handout.media_key, handout.sender),
+                user,
+            );
+        }
+
+        self.update_counts();
+        self.log(
+            LogLevel::Debug,
+            format!("Client ({user}) connected. Layer: {layer:?}. Reconnect: {reconnect}."),
+        );
+    }
+
+    /// Send the full game state to a client.
+    fn send_state(&mut self, user: Uuid, layer: Option<scene::Id>) {
+        let scene = self.game.client_scene(self.game.role(user));
         let perms = self.game.client_perms();
         let mut events = vec![
             ServerEvent::UserId(user),
             ServerEvent::SceneChange(Box::new(scene)),
             ServerEvent::PermsChange(perms),
+            ServerEvent::InitiativeList(self.game.initiative_list()),
+            ServerEvent::Sequence(self.journal.latest()),
         ];
+        events.extend(self.clients.values().map(Client::player_info));
 
         if let Some(layer) = layer {
             events.push(ServerEvent::SelectedLayer(layer));
         }
 
+        if let Some(sprite) = self.game.own_sprite(user) {
+            events.push(ServerEvent::OwnSprite(sprite));
+        }
+
         if let Some(event) = ServerEvent::set(events) {
             self.send_event(event, user);
         }
@@ -325,21 +661,110 @@ impl Server {
             let (list, selected) = self.game.scene_list();
             self.send_event(ServerEvent::SceneList(list, selected), user);
         }
+    }
 
-        self.log(
-            LogLevel::Debug,
-            format!("Client ({user}) connected. Layer: {layer:?}."),
-        );
+    /// Send a reconnected client the events it missed since `sequence`, or
+    /// the full game state if the journal doesn't go back that far.
+    fn replay(&mut self, user: Uuid, sequence: u64) {
+        let editor = self.game.role(user).editor();
+        if let Some(messages) = self.journal.since(sequence, user, editor) {
+            let messages: Vec<Vec<u8>> = messages.into_iter().map(<[u8]>::to_vec).collect();
+            self.log(
+                LogLevel::Debug,
+                format!("Replaying {} events to client ({user}).", messages.len()),
+            );
+            if let Some(client) = self.clients.get_mut(&user) {
+                for message in messages {
+                    client.send(message);
+                }
+            }
+        } else {
+            let name = self
+                .clients
+                .get(&user)
+                .map(|c| c.username.clone())
+                .unwrap_or_default();
+            let role = self.game.role(user);
+            let (_, _, layer) = self.game.add_player(user, &name, role);
+            self.send_state(user, layer);
+        }
+    }
+
+    /// Send a handout from `from` to `recipient`, or to all other clients if
+    /// None. Handouts for an offline recipient are sent once they connect.
+    /// Returns false if the handout couldn't be sent or queued.
+    fn show_handout(&mut self, from: Uuid, media_key: String, recipient: Option<Uuid>) -> bool {
+        let sender = self.display_name(from);
+        match recipient {
+            Some(user) if self.clients.get(&user).is_some_and(Client::active) => {
+                self.send_event(ServerEvent::Handout(media_key, sender), user);
+                true
+            }
+            Some(user) => self.game.queue_handout(user, media_key, sender),
+            None => {
+                self.broadcast_event(ServerEvent::Handout(media_key, sender), Some(from));
+                true
+            }
+        }
     }
 
     fn disconnect_client(&mut self, user: Uuid) {
         self.send_event(ServerEvent::Disconnect, user);
+        self.drop_client(user);
+    }
+
+    /// Remove a client, closing their connection by dropping its sender.
+    fn drop_client(&mut self, user: Uuid) {
         if self.clients.remove_entry(&user).is_some() {
             self.log(LogLevel::Debug, format!("Client ({user}) disconnected."));
+            self.broadcast_event(ServerEvent::PlayerCursor(user, None), None);
         }
         if self.clients.is_empty() {
             self.empty_time = Some(Instant::now());
         }
+        self.update_counts();
+    }
+
+    /// Update the counts of connected players and spectators available
+    /// through the game's handles.
+    fn update_counts(&self) {
+        let spectators = self
+            .clients
+            .keys()
+            .filter(|&&user| self.game.role(user).spectator())
+            .count();
+        let status = &self.status;
+        status.spectator_count.store(spectators, Ordering::Relaxed);
+        status
+            .player_count
+            .store(self.clients.len() - spectators, Ordering::Relaxed);
+    }
+
+    fn display_name(&self, user: Uuid) -> String {
+        self.clients
+            .get(&user)
+            .map(|c| c.display_name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Set the author and time of comments added in event to the sending
+    /// user and the current time, so that clients can't choose these. Returns
+    /// whether any comments were changed.
+    fn attribute_comments(&self, user: Uuid, event: &mut SceneEvent) -> bool {
+        match event {
+            SceneEvent::CommentAdd(comment) => {
+                let author = self.display_name(user);
+                let timestamp = timestamp_s().unwrap_or_default();
+                let changed = comment.author != author || comment.timestamp != timestamp;
+                comment.author = author;
+                comment.timestamp = timestamp;
+                changed
+            }
+            SceneEvent::EventSet(events) => events.iter_mut().fold(false, |changed, e| {
+                self.attribute_comments(user, e) || changed
+            }),
+            _ => false,
+        }
     }
 
     fn client_active(&mut self, user: Uuid, time: Instant) -> bool {
@@ -370,22 +795,58 @@ impl Server {
     }
 
     fn broadcast_event(&mut self, event: ServerEvent, exclude: Option<Uuid>) {
+        self.broadcast_to(event, exclude, Audience::All);
+    }
+
+    /// Broadcast a scene event, sending players a version without any
+    /// private information.
+    fn broadcast_scene_update(&mut self, event: SceneEvent, exclude: Option<Uuid>) {
+        if event.is_private() {
+            if let Some(public) = event.clone().public() {
+                self.broadcast_to(ServerEvent::SceneUpdate(public), exclude, Audience::Players);
+            }
+            self.broadcast_to(ServerEvent::SceneUpdate(event), exclude, Audience::Editors);
+        } else {
+            self.broadcast_event(ServerEvent::SceneUpdate(event), exclude);
+        }
+    }
+
+    fn broadcast_to(&mut self, event: ServerEvent, exclude: Option<Uuid>, audience: Audience) {
+        let journalled = matches!(
+            event,
+            ServerEvent::ChatMessage(..)
+                | ServerEvent::DiceResult(..)
+                | ServerEvent::InitiativeList(..)
+                | ServerEvent::PermsUpdate(..)
+                | ServerEvent::PlayerInfo(..)
+                | ServerEvent::SceneUpdate(..)
+        );
+        let event = if journalled {
+            let sequence = ServerEvent::Sequence(self.journal.next_sequence());
+            ServerEvent::EventSet(vec![event, sequence])
+        } else {
+            event
+        };
+
         let Some(message) = self.serialise(event) else {
             return;
         };
 
-        if let Some(user) = exclude {
-            self.clients
-                .iter_mut()
-                .filter(|(id, _)| **id != user)
-                .for_each(|(_, client)| {
-                    client.send(message.clone());
-                })
-        } else {
-            self.clients
-                .values_mut()
-                .for_each(|client| client.send(message.clone()));
+        if journalled {
+            self.journal.push(exclude, audience, message.clone());
         }
+
+        let game = &self.game;
+        self.clients
+            .iter_mut()
+            .filter(|(id, _)| Some(**id) != exclude)
+            .filter(|(id, _)| audience.includes(game.role(**id).editor()))
+            .for_each(|(_, client)| client.send(message.clone()));
+    }
+
+    fn broadcast_initiative(&mut self) {
+        let list = self.game.initiative_list();
+        self.broadcast_event(ServerEvent::InitiativeList(list), None);
     }
 
     fn health_check(&mut self) {
@@ -411,7 +872,7 @@ impl Server {
                     }
                 } else {
                     client.check_time = Some(now);
-                    if let Ok(message) = bincode::serialize(&ServerEvent::HealthCheck) {
+                    if let Ok(message) = encode_message(&ServerEvent::HealthCheck) {
                         client.send(message);
                     }
                 }
@@ -426,8 +887,39 @@ impl Server {
         }
     }
 
+    /// Send a WebSocket ping to each client which isn't awaiting a pong.
+    fn ping_clients(&mut self) {
+        let now = Instant::now();
+        for client in self.clients.values_mut() {
+            if client.ping_time.is_none() {
+                client.ping_time = Some(now);
+                client.send_frame(ClientFrame::Ping);
+            }
+        }
+        self.last_ping = now;
+    }
+
+    /// Disconnect clients which haven't responded to a ping in time. This
+    /// catches connections which dropped silently.
+    fn drop_stale_clients(&mut self) {
+        /// Time to wait for a pong before presuming the connection dead.
+        const PONG_TIMEOUT: Duration = Duration::from_secs(10);
+
+        let stale: Vec<Uuid> = self
+            .clients
+            .values()
+            .filter(|c| c.ping_time.is_some_and(|t| t.elapsed() >= PONG_TIMEOUT))
+            .map(|c| c.user)
+            .collect();
+
+        for user in stale {
+            self.log(LogLevel::Info, format!("User ({user}) connection stale."));
+            self.disconnect_client(user);
+        }
+    }
+
     fn serialise(&self, event: ServerEvent) -> Option<Vec<u8>> {
-        if let Ok(message) = bincode::serialize(&event) {
+        if let Ok(message) = encode_message(&event) {
             Some(message)
         } else {
             log(LogLevel::Error, "Failed to encode server event as message.");
@@ -469,17 +961,20 @@ impl Server {
         }
 
         // Send the new scene and perms to all clients
+        let sequence = self.journal.reset();
         let keys: Vec<(Uuid, String)> = self
             .clients
             .iter()
             .map(|(u, c)| (*u, c.username.clone()))
             .collect();
         for (user, name) in keys {
-            let (_, _, layer) = self.game.add_player(user, &name);
+            let role = self.game.role(user);
+            let (_, _, layer) = self.game.add_player(user, &name, role);
 
             let mut events = vec![
-                ServerEvent::SceneChange(Box::new(self.game.client_scene())),
+                ServerEvent::SceneChange(Box::new(self.game.client_scene(role))),
                 ServerEvent::PermsChange(self.game.client_perms()),
+                ServerEvent::Sequence(sequence),
             ];
 
             if let Some(layer) = layer {
@@ -492,10 +987,79 @@ impl Server {
         }
     }
 
-    async fn acquire_conn(&self) -> Res<PoolConnection<sqlx::Sqlite>> {
+    /// Record an applied scene event in the audit log in the background.
+    fn audit(&self, user: Uuid, event: SceneEvent) {
+        // The writer only stops once this server has been dropped.
+        self.audit.send((self.game.scene_uuid(), user, event)).ok();
+    }
+
+    /// Send an event to the owner's webhooks.
+    fn notify(&self, event: WebhookEvent, player: Option<String>) {
+        let mut payload = Payload::new(event, self.game.key.to_string(), self.game.project_uuid());
+        if let Some(player) = player {
+            payload = payload.with_player(player);
+        }
+        webhooks::notify(self.pool.clone(), self.owner.uuid, payload);
+    }
+
+    async fn acquire_conn(&self) -> Res<PoolConnection<sqlx::Any>> {
         self.pool
             .acquire()
             .await
             .map_err(|e| format!("Failed to acquire connection: {e}"))
     }
 }
+
+/// Record events sent by a server in the audit log until the server closes.
+/// Events which arrive while a batch is being written are written together.
+async fn write_audit_log(pool: AnyPool, mut entries: UnboundedReceiver<(Uuid, Uuid, SceneEvent)>) {
+    while let Some(entry) = entries.recv().await {
+        let mut batch = vec![entry];
+        while let Ok(entry) = entries.try_recv() {
+            batch.push(entry);
+        }
+
+        let result = match pool.acquire().await {
+            Ok(mut conn) => AuditEntry::record_all(&mut conn, &batch).await,
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            crate::utils::warning(format!("Failed to record audit log entries: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::ConnectionLimit;
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        let limit = ConnectionLimit::new(2);
+        let first = limit.try_acquire().unwrap();
+        let second = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+        assert_eq!(limit.active(), 2);
+
+        // Closed connections free up their permit.
+        drop(first);
+        assert_eq!(limit.active(), 1);
+
+        // Draining waits for connections to close, then no more are allowed.
+        assert_eq!(limit.drain(Duration::from_millis(10)).await, 1);
+        assert!(limit.try_acquire().is_none());
+        drop(second);
+        assert_eq!(limit.active(), 0);
+
+        let limit = ConnectionLimit::new(1);
+        let permit = limit.try_acquire().unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(permit);
+        });
+        assert_eq!(limit.drain(Duration::from_secs(5)).await, 0);
+        assert!(limit.try_acquire().is_none());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::utils::{info, Res};
+
+/// Sends email to users. Handlers take this as app data so that the sender
+/// can be replaced in tests.
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Res<()>;
+}
+
+/// Logs emails instead of sending them, for use until an email provider is
+/// configured.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Res<()> {
+        info(format!("Email to {to}: {subject}\n{body}"));
+        Ok(())
+    }
+}
+
+/// Records sent emails as (to, subject, body).
+#[cfg(test)]
+#[derive(Default)]
+pub struct TestMailer {
+    pub sent: std::sync::Mutex<Vec<(String, String, String)>>,
+}
+
+#[cfg(test)]
+impl Mailer for TestMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Res<()> {
+        self.sent.lock().map_err(|e| e.to_string())?.push((
+            to.to_string(),
+            subject.to_string(),
+            body.to_string(),
+        ));
+        Ok(())
+    }
+}
@@ -3,23 +3,26 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use actix_web::{middleware::Logger, web::Data, App, HttpServer};
-use games::{GameHandle, GameKey};
+use games::{ConnectionLimit, GameHandle, GameKey};
 
 mod api;
 mod content;
 mod crypto;
 mod fs;
 mod games;
+mod mail;
 mod models;
 mod req;
 mod utils;
+mod webhooks;
 
 pub use scene;
 use tokio::sync::RwLock;
 
-const USAGE: &str = "Usage: DATA_DIR=. ./server 80";
+const USAGE: &str = "Usage: DATA_DIR=. [DATABASE_URL=postgres://...] ./server 80";
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -34,6 +37,12 @@ async fn main() -> std::io::Result<()> {
     let db = fs::initialise_database()
         .await
         .expect("Database initialisation failed.");
+    {
+        let conn = &mut db.acquire().await.expect("Database connection failed.");
+        models::ProjectTemplate::seed(conn)
+            .await
+            .expect("Template seeding failed.");
+    }
 
     let games: Data<RwLock<HashMap<GameKey, GameHandle>>> =
         Data::new(RwLock::new(HashMap::<GameKey, GameHandle>::new()));
@@ -49,15 +58,90 @@ async fn main() -> std::io::Result<()> {
         }
     });
 
-    HttpServer::new(move || {
+    // Every interval, remove audit log entries and cached idempotent
+    // responses past their retention period.
+    const AUDIT_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60 * 24);
+    let db_ref = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(AUDIT_PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let result = match db_ref.acquire().await {
+                Ok(mut conn) => models::AuditEntry::prune(&mut conn).await,
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = result {
+                utils::warning(format!("Failed to prune audit log: {e}"));
+            }
+
+            let result = match db_ref.acquire().await {
+                Ok(mut conn) => models::CachedResponse::prune(&mut conn).await.map(|_| ()),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = result {
+                utils::warning(format!("Failed to prune idempotency cache: {e}"));
+            }
+        }
+    });
+
+    let mailer: Data<dyn mail::Mailer> =
+        Data::from(Arc::new(mail::LogMailer) as Arc<dyn mail::Mailer>);
+
+    // Limit on concurrent game connections, configurable with MAX_CONNECTIONS.
+    let capacity = match std::env::var("MAX_CONNECTIONS") {
+        Ok(capacity) => capacity.parse().expect("Invalid MAX_CONNECTIONS."),
+        Err(_) => ConnectionLimit::DEFAULT_CAPACITY,
+    };
+    let connections = Data::new(ConnectionLimit::new(capacity));
+
+    let games_ref = games.clone();
+    let connections_ref = connections.clone();
+    let server = HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .app_data(Data::new(db.clone()))
             .app_data(Data::clone(&games))
+            .app_data(Data::clone(&connections))
+            .app_data(Data::clone(&mailer))
             .service(api::routes())
             .service(content::routes())
     })
+    .disable_signals()
     .bind((std::net::Ipv4Addr::new(0, 0, 0, 0), port))?
-    .run()
-    .await
+    .run();
+
+    // On SIGTERM or SIGINT, end all games, telling clients that the game is
+    // over, and give their connections time to close before stopping.
+    const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+    let handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        utils::info("Shutting down.");
+
+        let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+        let hosted: Vec<GameHandle> = games_ref.write().await.drain().map(|(_, h)| h).collect();
+        let ended = futures::future::join_all(hosted.iter().map(GameHandle::shutdown));
+        if tokio::time::timeout_at(deadline, ended).await.is_err() {
+            utils::warning("Timed out saving games on shutdown.");
+        }
+
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let forced = connections_ref.drain(remaining).await;
+        if forced > 0 {
+            utils::warning(format!("Forcibly closing {forced} connections."));
+        }
+        handle.stop(true).await;
+    });
+
+    server.await
+}
+
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM.");
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
 }
@@ -0,0 +1,86 @@
+use scene::comms::SceneEvent;
+use sqlx::Connection;
+use uuid::Uuid;
+
+use super::{timestamp_s, Conn};
+use crate::utils::{format_uuid, parse_uuid, Res};
+
+/// Record of a scene event applied during a game.
+pub struct AuditEntry {
+    pub user: Uuid,
+    pub event_type: String,
+    pub event_json: String,
+    pub timestamp: i64,
+}
+
+impl AuditEntry {
+    /// Entries older than this are removed by `prune`.
+    pub const RETENTION_S: i64 = 90 * 24 * 60 * 60;
+
+    /// Record a batch of events, each `(scene, user, event)`, in a single
+    /// transaction.
+    pub async fn record_all(conn: &mut Conn, entries: &[(Uuid, Uuid, SceneEvent)]) -> Res<()> {
+        let timestamp = timestamp_s();
+        let mut tx = conn.begin().await.map_err(|e| e.to_string())?;
+        for (scene, user, event) in entries {
+            let scene = format_uuid(*scene);
+            let user = format_uuid(*user);
+            let event_type = event.name();
+            let event_json = serde_json::to_string(event).map_err(|e| e.to_string())?;
+            sqlx::query(
+                r#"
+                INSERT INTO audit_log (scene, "user", event_type, event_json, timestamp)
+                VALUES ($1, $2, $3, $4, $5);
+                "#,
+            )
+            .bind(scene)
+            .bind(user)
+            .bind(event_type)
+            .bind(event_json)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    /// Entries for `scene`, most recent first.
+    pub async fn list(conn: &mut Conn, scene: Uuid, limit: u32, offset: u32) -> Res<Vec<Self>> {
+        let scene = format_uuid(scene);
+        sqlx::query_as::<_, (String, String, String, i64)>(
+            r#"
+            SELECT "user", event_type, event_json, timestamp FROM audit_log
+            WHERE scene = $1 ORDER BY timestamp DESC, id DESC LIMIT $2 OFFSET $3;
+            "#,
+        )
+        .bind(scene)
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(user, event_type, event_json, timestamp)| {
+            Ok(Self {
+                user: parse_uuid(&user)?,
+                event_type,
+                event_json,
+                timestamp,
+            })
+        })
+        .collect()
+    }
+
+    /// Remove entries older than the retention period, returning the number
+    /// removed.
+    pub async fn prune(conn: &mut Conn) -> Res<u64> {
+        let cutoff = timestamp_s() - Self::RETENTION_S;
+        sqlx::query("DELETE FROM audit_log WHERE timestamp < $1;")
+            .bind(cutoff)
+            .execute(conn)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(|e| e.to_string())
+    }
+}
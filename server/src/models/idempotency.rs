@@ -0,0 +1,167 @@
+use uuid::Uuid;
+
+use super::{timestamp_s, Conn, Nullable};
+use crate::utils::{format_uuid, Res};
+
+/// Response to a request made with an idempotency key, replayed if the
+/// request is retried with the same key.
+pub struct CachedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Result of reserving an idempotency key for a request.
+pub enum Reservation {
+    /// The key was free and is now held for this request.
+    Reserved,
+
+    /// Another request with this key is still being handled.
+    Pending,
+
+    /// A request with this key has been handled, and gave this response.
+    Done(CachedResponse),
+}
+
+impl CachedResponse {
+    /// Responses are replayed for this long, after which they are removed
+    /// by `prune`.
+    pub const RETENTION_S: i64 = 24 * 60 * 60;
+
+    /// Reservations for requests which never finished, for example because
+    /// the server stopped while handling them, are released after this long.
+    pub const PENDING_S: i64 = 5 * 60;
+
+    /// Reserve `key` for `request`, a method and path, unless it has already
+    /// been used. Only one of several concurrent requests with the same key
+    /// will be given the reservation.
+    pub async fn reserve(
+        conn: &mut Conn,
+        user: Uuid,
+        key: Uuid,
+        request: &str,
+    ) -> Res<Reservation> {
+        let user = format_uuid(user);
+        let key = format_uuid(key);
+        let now = timestamp_s();
+        let cutoff = now - Self::RETENTION_S;
+        let pending_cutoff = now - Self::PENDING_S;
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_cache
+            WHERE "user" = $1 AND idempotency_key = $2 AND request = $3 AND (
+                created_at < $4 OR (status IS NULL AND created_at < $5)
+            );
+            "#,
+        )
+        .bind(&user)
+        .bind(&key)
+        .bind(request)
+        .bind(cutoff)
+        .bind(pending_cutoff)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO idempotency_cache ("user", idempotency_key, request, created_at)
+            VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .bind(&user)
+        .bind(&key)
+        .bind(request)
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+        if inserted > 0 {
+            return Ok(Reservation::Reserved);
+        }
+
+        let row = sqlx::query_as::<_, (Nullable<i64>, Nullable<String>, Nullable<Vec<u8>>)>(
+            r#"
+            SELECT status, content_type, response_body FROM idempotency_cache
+            WHERE "user" = $1 AND idempotency_key = $2 AND request = $3;
+            "#,
+        )
+        .bind(user)
+        .bind(key)
+        .bind(request)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(
+            match row
+                .and_then(|(status, content_type, body)| Some((status.0?, content_type.0, body.0?)))
+            {
+                Some((status, content_type, body)) => Reservation::Done(Self {
+                    status: status as u16,
+                    content_type,
+                    body,
+                }),
+                None => Reservation::Pending,
+            },
+        )
+    }
+
+    /// Release a reservation made with `reserve`, so that the request may be
+    /// retried.
+    pub async fn release(conn: &mut Conn, user: Uuid, key: Uuid, request: &str) -> Res<()> {
+        let user = format_uuid(user);
+        let key = format_uuid(key);
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_cache
+            WHERE "user" = $1 AND idempotency_key = $2 AND request = $3 AND status IS NULL;
+            "#,
+        )
+        .bind(user)
+        .bind(key)
+        .bind(request)
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    /// Store this response against a key reserved with `reserve`.
+    pub async fn store(&self, conn: &mut Conn, user: Uuid, key: Uuid, request: &str) -> Res<()> {
+        let user = format_uuid(user);
+        let key = format_uuid(key);
+        let status = self.status as i64;
+        let created_at = timestamp_s();
+        sqlx::query(
+            r#"
+            UPDATE idempotency_cache
+            SET status = $1, content_type = $2, response_body = $3, created_at = $4
+            WHERE "user" = $5 AND idempotency_key = $6 AND request = $7;
+            "#,
+        )
+        .bind(status)
+        .bind(&self.content_type)
+        .bind(&self.body)
+        .bind(created_at)
+        .bind(user)
+        .bind(key)
+        .bind(request)
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    /// Remove responses older than the retention period, returning the
+    /// number removed.
+    pub async fn prune(conn: &mut Conn) -> Res<u64> {
+        let cutoff = timestamp_s() - Self::RETENTION_S;
+        sqlx::query("DELETE FROM idempotency_cache WHERE created_at < $1;")
+            .bind(cutoff)
+            .execute(conn)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -1,17 +1,24 @@
 use uuid::Uuid;
 
-use super::{Conn, User};
+use super::{normalise_tags, parse_tags, returned, Conn, Nullable, User};
 use crate::utils::{err, format_uuid, generate_uuid, parse_uuid, Res};
 
 pub struct Media {
     pub uuid: Uuid,
     pub user: Uuid,
+
+    /// Path to the file as uploaded.
     pub relative_path: String,
+
+    /// Path to a downscaled copy, for images too large to serve as is.
+    pub resized_path: Option<String>,
+
     pub title: String,
     pub hashed_value: String,
     pub file_size: usize,
     pub w: f32,
     pub h: f32,
+    pub tags: Vec<String>,
 }
 
 impl Media {
@@ -30,14 +37,26 @@ impl Media {
             uuid: generate_uuid(),
             user: user.uuid,
             relative_path: format!("/uploads/{}/{}.{ext}", &user.username, format_uuid(uuid)),
+            resized_path: None,
             title: title.to_string(),
             hashed_value: hash.to_string(),
             file_size: size,
             w: Self::DEFAULT_SIZE,
             h: Self::DEFAULT_SIZE,
+            tags: Vec::new(),
         }
     }
 
+    /// Path to serve by default; the resized copy if there is one.
+    pub fn display_path(&self) -> &str {
+        self.resized_path.as_deref().unwrap_or(&self.relative_path)
+    }
+
+    /// Paths of all files stored for this media.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.relative_path.as_str()).chain(self.resized_path.as_deref())
+    }
+
     pub async fn create(self, conn: &mut Conn) -> Res<Self> {
         create_media(conn, &self).await.and_then(Self::try_from)
     }
@@ -51,7 +70,8 @@ impl Media {
 
     pub async fn delete(conn: &mut Conn, uuid: Uuid) -> Res<()> {
         let uuid = format_uuid(uuid);
-        sqlx::query!("DELETE FROM media WHERE uuid = ?1;", uuid)
+        sqlx::query("DELETE FROM media WHERE uuid = $1;")
+            .bind(uuid)
             .execute(conn)
             .await
             .map(|_| ())
@@ -71,23 +91,70 @@ impl Media {
             .and_then(Self::try_from)
     }
 
-    pub async fn user_total_size(conn: &mut Conn, user: Uuid) -> Res<usize> {
-        #[derive(sqlx::FromRow)]
-        struct QueryRow {
-            total_file_size: Option<i64>,
-        }
+    /// Replace the tags on a media item. Tags are trimmed and lowercased,
+    /// and empty or duplicate tags are dropped.
+    pub async fn set_tags(conn: &mut Conn, user: Uuid, uuid: Uuid, tags: &[String]) -> Res<Self> {
+        let tags = normalise_tags(tags)?;
+        update_tags(conn, user, uuid, &tags.join(","))
+            .await
+            .and_then(Self::try_from)
+    }
 
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags.iter().any(|t| *t == tag)
+    }
+
+    pub async fn user_total_size(conn: &mut Conn, user: Uuid) -> Res<usize> {
+        // SUM is NUMERIC on Postgres, so is cast back to an integer.
         let user = format_uuid(user);
-        let record = sqlx::query_as!(
-            QueryRow,
-            "SELECT SUM(file_size) as total_file_size FROM media WHERE user = ?1;",
-            user
+        let total_file_size = sqlx::query_scalar::<_, Nullable<i64>>(
+            r#"SELECT CAST(SUM(file_size) AS BIGINT) FROM media WHERE "user" = $1;"#,
         )
+        .bind(user)
         .fetch_one(conn)
         .await
         .map_err(|e| e.to_string())?;
 
-        Ok(record.total_file_size.unwrap_or(0) as usize)
+        Ok(total_file_size.0.unwrap_or(0) as usize)
+    }
+
+    /// ID used to reference this media as a texture in scenes, as derived by
+    /// clients from its media key.
+    pub fn texture_id(&self) -> scene::Id {
+        scene::media_key_id(&format_uuid(self.uuid))
+    }
+
+    /// Time the media was uploaded, from its v7 UUID.
+    pub fn created_time(&self) -> Option<std::time::SystemTime> {
+        let (secs, nanos) = self.uuid.get_timestamp()?.to_unix();
+        Some(std::time::UNIX_EPOCH + std::time::Duration::new(secs, nanos))
+    }
+
+    /// Media belonging to `user` which was uploaded before `before` and is
+    /// neither used as a texture in `textures` nor as an avatar.
+    pub async fn unreferenced(
+        conn: &mut Conn,
+        user: Uuid,
+        textures: &std::collections::HashSet<scene::Id>,
+        before: std::time::SystemTime,
+    ) -> Res<Vec<Media>> {
+        let avatars: Vec<String> = sqlx::query_scalar::<_, String>(
+            "SELECT avatar_media_key FROM users WHERE avatar_media_key IS NOT NULL;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(Self::user_media(conn, user)
+            .await?
+            .into_iter()
+            .filter(|media| {
+                !textures.contains(&media.texture_id())
+                    && !avatars.contains(&format_uuid(media.uuid))
+                    && media.created_time().is_some_and(|t| t < before)
+            })
+            .collect())
     }
 
     pub async fn user_media(conn: &mut Conn, user: Uuid) -> Res<Vec<Media>> {
@@ -99,12 +166,13 @@ impl Media {
     }
 
     pub async fn exists(conn: &mut Conn, user: Uuid, hash: &str) -> Res<Option<String>> {
-        let row_opt = sqlx::query("SELECT title FROM media WHERE user = ?1 AND hashed_value = ?2;")
-            .bind(format_uuid(user))
-            .bind(hash)
-            .fetch_optional(conn)
-            .await
-            .map_err(|e| e.to_string())?;
+        let row_opt =
+            sqlx::query(r#"SELECT title FROM media WHERE "user" = $1 AND hashed_value = $2;"#)
+                .bind(format_uuid(user))
+                .bind(hash)
+                .fetch_optional(conn)
+                .await
+                .map_err(|e| e.to_string())?;
 
         if let Some(row) = row_opt {
             match sqlx::Row::try_get(&row, 0) {
@@ -121,19 +189,45 @@ impl TryFrom<MediaRow> for Media {
     type Error = String;
 
     fn try_from(value: MediaRow) -> Result<Self, Self::Error> {
+        let (relative_path, resized_path) = parse_paths(value.relative_path);
         Ok(Self {
             uuid: parse_uuid(&value.uuid)?,
             user: parse_uuid(&value.user)?,
-            relative_path: value.relative_path,
+            relative_path,
+            resized_path,
             title: value.title,
             hashed_value: value.hashed_value,
             file_size: value.file_size as usize,
             w: value.w as f32,
             h: value.h as f32,
+            tags: parse_tags(&value.tags),
         })
     }
 }
 
+/// Paths are stored as `{ "original": path, "resized": path | null }`. Media
+/// uploaded before resizing was introduced stores the original path alone.
+#[derive(serde_derive::Deserialize, serde_derive::Serialize)]
+struct StoredPaths {
+    original: String,
+    resized: Option<String>,
+}
+
+fn parse_paths(stored: String) -> (String, Option<String>) {
+    match serde_json::from_str::<StoredPaths>(&stored) {
+        Ok(paths) => (paths.original, paths.resized),
+        Err(_) => (stored, None),
+    }
+}
+
+fn format_paths(record: &Media) -> Res<String> {
+    serde_json::to_string(&StoredPaths {
+        original: record.relative_path.clone(),
+        resized: record.resized_path.clone(),
+    })
+    .map_err(|e| e.to_string())
+}
+
 #[derive(sqlx::FromRow)]
 struct MediaRow {
     uuid: String,
@@ -144,11 +238,13 @@ struct MediaRow {
     file_size: i64,
     w: f64,
     h: f64,
+    tags: String,
 }
 
 async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<MediaRow>> {
     let uuid = format_uuid(uuid);
-    sqlx::query_as!(MediaRow, "SELECT * FROM media WHERE uuid = ?1;", uuid)
+    sqlx::query_as::<_, MediaRow>("SELECT * FROM media WHERE uuid = $1;")
+        .bind(uuid)
         .fetch_optional(conn)
         .await
         .map_err(|e| format!("Media item not found: {e}"))
@@ -157,36 +253,37 @@ async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<MediaRow>> {
 async fn create_media(conn: &mut Conn, record: &Media) -> Res<MediaRow> {
     let uuid = format_uuid(record.uuid);
     let user = format_uuid(record.user);
-    let relative_path = &record.relative_path;
-    let title = &record.title;
-    let hashed_value = &record.hashed_value;
+    let relative_path = format_paths(record)?;
     let file_size = record.file_size as i64;
     let w = record.w as f64;
     let h = record.h as f64;
-    sqlx::query_as!(
-        MediaRow,
-        "
+    let tags = record.tags.join(",");
+    sqlx::query_as::<_, MediaRow>(
+        r#"
         INSERT INTO media (
-            uuid, user, relative_path, title, hashed_value, file_size, w, h
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) RETURNING *;
-        ",
-        uuid,
-        user,
-        relative_path,
-        title,
-        hashed_value,
-        file_size,
-        w,
-        h
+            uuid, "user", relative_path, title, hashed_value, file_size, w, h, tags
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *;
+        "#,
     )
-    .fetch_one(conn)
+    .bind(uuid)
+    .bind(user)
+    .bind(relative_path)
+    .bind(&record.title)
+    .bind(&record.hashed_value)
+    .bind(file_size)
+    .bind(w)
+    .bind(h)
+    .bind(tags)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
 async fn user_media(pool: &mut Conn, user: Uuid) -> Res<Vec<MediaRow>> {
     let user = format_uuid(user);
-    sqlx::query_as!(MediaRow, "SELECT * FROM media WHERE user = ?1;", user)
+    sqlx::query_as::<_, MediaRow>(r#"SELECT * FROM media WHERE "user" = $1;"#)
+        .bind(user)
         .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())
@@ -204,16 +301,31 @@ async fn update_media(
     let h = h as f64;
     let uuid = format_uuid(uuid);
     let user = format_uuid(user);
-    sqlx::query_as!(
-        MediaRow,
-        "UPDATE media SET title = ?1, w = ?2, h = ?3 WHERE uuid = ?4 AND user = ?5 RETURNING *;",
-        title,
-        w,
-        h,
-        uuid,
-        user
+    sqlx::query_as::<_, MediaRow>(
+        r#"UPDATE media SET title = $1, w = $2, h = $3 WHERE uuid = $4 AND "user" = $5 RETURNING *;"#,
+    )
+    .bind(title)
+    .bind(w)
+    .bind(h)
+    .bind(uuid)
+    .bind(user)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(returned)
+}
+
+async fn update_tags(conn: &mut Conn, user: Uuid, uuid: Uuid, tags: &str) -> Res<MediaRow> {
+    let uuid = format_uuid(uuid);
+    let user = format_uuid(user);
+    sqlx::query_as::<_, MediaRow>(
+        r#"UPDATE media SET tags = $1 WHERE uuid = $2 AND "user" = $3 RETURNING *;"#,
     )
-    .fetch_one(conn)
+    .bind(tags)
+    .bind(uuid)
+    .bind(user)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
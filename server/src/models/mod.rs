@@ -1,14 +1,68 @@
+mod audit;
+mod idempotency;
 mod media;
+mod palette;
 mod project;
 mod scene;
+mod sprite_template;
+mod template;
 mod user;
+mod webhook;
 
-type Conn = sqlx::SqliteConnection;
+use sqlx::{
+    any::{AnyTypeInfo, AnyValueRef},
+    error::BoxDynError,
+    Any, Decode, Type, TypeInfo, ValueRef,
+};
 
+type Conn = sqlx::AnyConnection;
+
+/// A column which may be NULL. In sqlx 0.7 `sqlx::Any` never reports a value
+/// as NULL, so `Option<T>` fails to decode NULLs. Decode as this instead, by
+/// `#[sqlx(try_from = "Nullable<T>")]` on an `Option<T>` field.
+struct Nullable<T>(Option<T>);
+
+impl<T: Type<Any>> Type<Any> for Nullable<T> {
+    fn type_info() -> AnyTypeInfo {
+        T::type_info()
+    }
+
+    fn compatible(ty: &AnyTypeInfo) -> bool {
+        is_null(ty) || T::compatible(ty)
+    }
+}
+
+impl<'r, T: Decode<'r, Any>> Decode<'r, Any> for Nullable<T> {
+    fn decode(value: AnyValueRef<'r>) -> Result<Self, BoxDynError> {
+        if is_null(&value.type_info()) {
+            Ok(Nullable(None))
+        } else {
+            T::decode(value).map(|v| Nullable(Some(v)))
+        }
+    }
+}
+
+impl<T> From<Nullable<T>> for Option<T> {
+    fn from(value: Nullable<T>) -> Self {
+        value.0
+    }
+}
+
+/// `AnyTypeInfo::is_null` is also always false, so check the name instead.
+fn is_null(ty: &AnyTypeInfo) -> bool {
+    ty.name() == "NULL"
+}
+
+pub use self::audit::AuditEntry;
+pub use self::idempotency::{CachedResponse, Reservation};
 pub use self::media::Media;
+pub use self::palette::Palette;
 pub use self::project::Project;
-pub use self::scene::Scene;
-pub use self::user::{User, UserAuth, UserSession};
+pub use self::scene::{Scene, SceneSort, SortOrder};
+pub use self::sprite_template::SpriteTemplate;
+pub use self::template::ProjectTemplate;
+pub use self::user::{ApiKey, DataExport, PasswordReset, User, UserAuth, UserSession};
+pub use self::webhook::{Webhook, WebhookEvent};
 
 fn timestamp_s() -> i64 {
     crate::utils::timestamp_s().unwrap_or(0) as i64
@@ -17,3 +71,46 @@ fn timestamp_s() -> i64 {
 fn timestamp_to_system(timestamp: i64) -> std::time::SystemTime {
     std::time::UNIX_EPOCH + std::time::Duration::from_secs(timestamp as u64)
 }
+
+/// The row written by a statement with a `RETURNING` clause. These are run
+/// with `fetch_all`, as `fetch_one` can stop stepping the statement after the
+/// first row, leaving the write uncommitted and invisible to other
+/// connections until this one is next used.
+fn returned<T>(mut rows: Vec<T>) -> crate::utils::Res<T> {
+    rows.pop()
+        .ok_or_else(|| sqlx::Error::RowNotFound.to_string())
+}
+
+const MAX_TAGS: usize = 32;
+const MAX_TAG_LENGTH: usize = 32;
+
+/// Trim and lowercase a list of tags, dropping empty and duplicate tags.
+fn normalise_tags(tags: &[String]) -> crate::utils::Res<Vec<String>> {
+    let mut normalised: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.contains(',') {
+            return crate::utils::err("Tags may not contain commas.");
+        } else if tag.len() > MAX_TAG_LENGTH {
+            return Err(format!(
+                "Tags may be at most {MAX_TAG_LENGTH} characters long."
+            ));
+        } else if !tag.is_empty() && !normalised.contains(&tag) {
+            normalised.push(tag);
+        }
+    }
+
+    if normalised.len() > MAX_TAGS {
+        Err(format!("At most {MAX_TAGS} tags may be used."))
+    } else {
+        Ok(normalised)
+    }
+}
+
+/// Split tags stored as a comma-separated string.
+fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
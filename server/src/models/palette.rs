@@ -0,0 +1,94 @@
+use scene::{Colour, ColourPalette};
+use uuid::Uuid;
+
+use super::{returned, Conn};
+use crate::utils::{err, format_uuid, generate_uuid, parse_uuid, Res};
+
+/// A colour palette saved by a user.
+pub struct Palette {
+    pub uuid: Uuid,
+    pub user: Uuid,
+    pub palette: ColourPalette,
+}
+
+impl Palette {
+    /// Save `palette` for `user`, replacing any of their palettes with the
+    /// same name.
+    pub async fn save(conn: &mut Conn, user: Uuid, mut palette: ColourPalette) -> Res<Self> {
+        palette.name = palette.name.trim().to_string();
+        if palette.name.is_empty() {
+            return err("Palette name must not be empty.");
+        } else if palette.name.chars().count() > ColourPalette::MAX_NAME_LENGTH {
+            return Err(format!(
+                "Palette name too long, max length is {}.",
+                ColourPalette::MAX_NAME_LENGTH
+            ));
+        } else if palette.colours.len() > ColourPalette::MAX_COLOURS {
+            return Err(format!(
+                "Too many colours, max is {}.",
+                ColourPalette::MAX_COLOURS
+            ));
+        }
+
+        let uuid = format_uuid(generate_uuid());
+        let user = format_uuid(user);
+        let colours = serde_json::to_string(&palette.colours).map_err(|e| e.to_string())?;
+        sqlx::query_as::<_, PaletteRow>(
+            r#"
+            INSERT INTO palettes (uuid, "user", name, colours) VALUES ($1, $2, $3, $4)
+            ON CONFLICT ("user", name) DO UPDATE SET colours = excluded.colours
+            RETURNING *;
+            "#,
+        )
+        .bind(uuid)
+        .bind(user)
+        .bind(&palette.name)
+        .bind(colours)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(returned)
+        .and_then(Self::try_from)
+    }
+
+    /// Palettes saved by `user`, in the order they were first saved. UUIDs
+    /// are time ordered, so this is the order of their UUIDs.
+    pub async fn list(conn: &mut Conn, user: Uuid) -> Res<Vec<Self>> {
+        let user = format_uuid(user);
+        sqlx::query_as::<_, PaletteRow>(
+            r#"SELECT * FROM palettes WHERE "user" = $1 ORDER BY uuid;"#,
+        )
+        .bind(user)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+}
+
+impl TryFrom<PaletteRow> for Palette {
+    type Error = String;
+
+    fn try_from(value: PaletteRow) -> Result<Self, Self::Error> {
+        let colours: Vec<Colour> =
+            serde_json::from_str(&value.colours).map_err(|e| e.to_string())?;
+        Ok(Self {
+            uuid: parse_uuid(&value.uuid)?,
+            user: parse_uuid(&value.user)?,
+            palette: ColourPalette {
+                name: value.name,
+                colours,
+            },
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PaletteRow {
+    uuid: String,
+    user: String,
+    name: String,
+    colours: String,
+}
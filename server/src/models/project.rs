@@ -3,7 +3,10 @@ use std::{path::PathBuf, time::SystemTime};
 use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
-use super::{timestamp_s, timestamp_to_system, Conn, Scene, User};
+use super::{
+    normalise_tags, parse_tags, returned, timestamp_s, timestamp_to_system, Conn, Nullable, Scene,
+    User,
+};
 use crate::{
     fs::{join_relative_path, write_file, SAVES},
     utils::{err, format_uuid, generate_uuid, parse_uuid, Res},
@@ -15,10 +18,13 @@ pub struct Project {
     pub user: Uuid,
     pub updated_time: std::time::SystemTime,
     pub title: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
 }
 
 impl Project {
     pub const MAX_TITLE_LENGTH: usize = 256;
+    pub const MAX_DESCRIPTION_LENGTH: usize = 4096;
 
     pub fn updated_timestamp(&self) -> u64 {
         self.updated_time
@@ -36,7 +42,15 @@ impl Project {
         let mut scenes = Vec::new();
         for scene in &mut project.scenes {
             scene.project = record.uuid;
-            scenes.push(Scene::update_or_create(conn, scene).await?);
+            let record = Scene::update_or_create(conn, scene).await?;
+            let labels: Vec<&str> = scene
+                .get_drawings()
+                .into_iter()
+                .filter_map(|drawing| drawing.annotation.as_ref())
+                .map(|annotation| annotation.text.as_str())
+                .collect();
+            Scene::index_labels(conn, record.uuid, &labels).await?;
+            scenes.push(record);
         }
         record.remove_deleted_scenes(conn, &scenes).await;
 
@@ -97,6 +111,18 @@ impl Project {
             .collect()
     }
 
+    pub async fn list_all(conn: &mut Conn) -> Res<Vec<Self>> {
+        sqlx::query_as::<_, ProjectRow>(
+            r#"SELECT uuid, "user", updated_time, title, description, tags FROM projects;"#,
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+
     pub fn validate_title(title: &str) -> Res<()> {
         if title.len() > Self::MAX_TITLE_LENGTH {
             Err(format!(
@@ -109,6 +135,28 @@ impl Project {
         }
     }
 
+    /// Update the description and tags of a project. An empty description
+    /// is stored as `None`.
+    pub async fn set_meta(
+        conn: &mut Conn,
+        uuid: Uuid,
+        description: Option<&str>,
+        tags: &[String],
+    ) -> Res<Self> {
+        let description = description.map(str::trim).filter(|d| !d.is_empty());
+        if description.is_some_and(|d| d.len() > Self::MAX_DESCRIPTION_LENGTH) {
+            return Err(format!(
+                "Description too long, max length is {}.",
+                Self::MAX_DESCRIPTION_LENGTH
+            ));
+        }
+
+        let tags = normalise_tags(tags)?;
+        update_meta(conn, uuid, description, &tags.join(","))
+            .await
+            .and_then(Self::try_from)
+    }
+
     pub async fn create(conn: &mut Conn, owner: &User, title: &str) -> Res<Self> {
         Self::validate_title(title)?;
         let record = create_project(conn, generate_uuid(), owner.uuid, title)
@@ -126,7 +174,8 @@ impl Project {
         }
 
         let uuid = format_uuid(self.uuid);
-        sqlx::query!("DELETE FROM projects WHERE uuid = ?1;", uuid)
+        sqlx::query("DELETE FROM projects WHERE uuid = $1;")
+            .bind(uuid)
             .execute(conn)
             .await
             .map_err(|e| e.to_string())?;
@@ -170,13 +219,18 @@ impl Project {
             .collect::<Vec<String>>()
             .join(", ");
         sqlx::query(&format!(
-            "DELETE FROM scenes WHERE project = ?1 AND uuid NOT IN ({})",
+            "DELETE FROM scenes WHERE project = $1 AND uuid NOT IN ({})",
             scene_ids
         ))
         .bind(format_uuid(self.uuid))
-        .execute(conn)
+        .execute(&mut *conn)
         .await
         .ok();
+
+        sqlx::query("DELETE FROM scene_labels WHERE scene NOT IN (SELECT uuid FROM scenes);")
+            .execute(conn)
+            .await
+            .ok();
     }
 }
 
@@ -189,6 +243,8 @@ impl TryFrom<ProjectRow> for Project {
             user: parse_uuid(&value.user)?,
             updated_time: timestamp_to_system(value.updated_time),
             title: value.title,
+            description: value.description,
+            tags: parse_tags(&value.tags),
         })
     }
 }
@@ -199,53 +255,74 @@ pub struct ProjectRow {
     user: String,
     updated_time: i64,
     title: String,
+    #[sqlx(try_from = "Nullable<String>")]
+    description: Option<String>,
+    tags: String,
 }
 
 async fn create_project(conn: &mut Conn, uuid: Uuid, user: Uuid, title: &str) -> Res<ProjectRow> {
     let uuid = format_uuid(uuid);
     let user = format_uuid(user);
     let updated_time = timestamp_s();
-    sqlx::query_as!(
-        ProjectRow,
-        "
-        INSERT INTO projects (uuid, user, updated_time, title)
-        VALUES (?1, ?2, ?3, ?4) RETURNING *;
-        ",
-        uuid,
-        user,
-        updated_time,
-        title
+    sqlx::query_as::<_, ProjectRow>(
+        r#"
+        INSERT INTO projects (uuid, "user", updated_time, title)
+        VALUES ($1, $2, $3, $4) RETURNING *;
+        "#,
     )
-    .fetch_one(conn)
+    .bind(uuid)
+    .bind(user)
+    .bind(updated_time)
+    .bind(title)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
 async fn update_project(conn: &mut Conn, uuid: Uuid, title: &str) -> Res<ProjectRow> {
     let updated_time = timestamp_s();
     let uuid = format_uuid(uuid);
-    sqlx::query_as!(
-        ProjectRow,
-        "UPDATE projects SET updated_time = ?1, title = ?2 WHERE uuid = ?3 RETURNING *;",
-        updated_time,
-        title,
-        uuid
+    sqlx::query_as::<_, ProjectRow>(
+        "UPDATE projects SET updated_time = $1, title = $2 WHERE uuid = $3 RETURNING *;",
     )
-    .fetch_one(conn)
+    .bind(updated_time)
+    .bind(title)
+    .bind(uuid)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(returned)
+}
+
+async fn update_meta(
+    conn: &mut Conn,
+    uuid: Uuid,
+    description: Option<&str>,
+    tags: &str,
+) -> Res<ProjectRow> {
+    let uuid = format_uuid(uuid);
+    sqlx::query_as::<_, ProjectRow>(
+        "UPDATE projects SET description = $1, tags = $2 WHERE uuid = $3 RETURNING *;",
+    )
+    .bind(description)
+    .bind(tags)
+    .bind(uuid)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
 async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<ProjectRow>> {
     let uuid = format_uuid(uuid);
-    sqlx::query_as!(
-        ProjectRow,
-        "
-        SELECT uuid, user, updated_time, title
-        FROM projects WHERE uuid = ?1;
-        ",
-        uuid
+    sqlx::query_as::<_, ProjectRow>(
+        r#"
+        SELECT uuid, "user", updated_time, title, description, tags
+        FROM projects WHERE uuid = $1;
+        "#,
     )
+    .bind(uuid)
     .fetch_optional(conn)
     .await
     .map_err(|e| e.to_string())
@@ -253,15 +330,14 @@ async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<ProjectRow>> {
 
 async fn for_scene(conn: &mut Conn, scene: Uuid) -> Res<ProjectRow> {
     let scene = format_uuid(scene);
-    sqlx::query_as!(
-        ProjectRow,
-        "
-        SELECT p.uuid, p.user, p.updated_time, p.title
+    sqlx::query_as::<_, ProjectRow>(
+        r#"
+        SELECT p.uuid, p."user", p.updated_time, p.title, p.description, p.tags
         FROM projects p, scenes
-        WHERE scenes.uuid = ?1 AND p.uuid = scenes.project
-        ",
-        scene
+        WHERE scenes.uuid = $1 AND p.uuid = scenes.project
+        "#,
     )
+    .bind(scene)
     .fetch_one(conn)
     .await
     .map_err(|e| e.to_string())
@@ -269,14 +345,13 @@ async fn for_scene(conn: &mut Conn, scene: Uuid) -> Res<ProjectRow> {
 
 async fn list_for_user(conn: &mut Conn, user: Uuid) -> Res<Vec<ProjectRow>> {
     let user = format_uuid(user);
-    sqlx::query_as!(
-        ProjectRow,
-        "
-        SELECT uuid, user, updated_time, title
-        FROM projects WHERE user = ?1;
-        ",
-        user
+    sqlx::query_as::<_, ProjectRow>(
+        r#"
+        SELECT uuid, "user", updated_time, title, description, tags
+        FROM projects WHERE "user" = $1;
+        "#,
     )
+    .bind(user)
     .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
@@ -1,7 +1,28 @@
 use uuid::Uuid;
 
-use super::{timestamp_s, Conn};
-use crate::utils::{err, format_uuid, generate_uuid, parse_uuid, Res};
+use super::{returned, timestamp_s, Conn, Nullable};
+use crate::{
+    crypto::{generate_key, to_hex_string},
+    req::{DbPool, Postgres},
+    utils::{err, format_uuid, generate_uuid, parse_uuid, Res},
+};
+
+/// Field by which to sort lists of scenes.
+#[derive(Clone, Copy, Debug, Default, serde_derive::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SceneSort {
+    #[default]
+    Title,
+    Updated,
+}
+
+#[derive(Clone, Copy, Debug, Default, serde_derive::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
 
 pub struct Scene {
     pub uuid: Uuid,
@@ -65,6 +86,190 @@ impl Scene {
     pub async fn set_thumbnail(conn: &mut Conn, uuid: Uuid, thumbnail: &str) -> Res<()> {
         set_thumbnail(conn, uuid, thumbnail).await
     }
+
+    /// Create a token granting read-only access to a scene for `ttl_s`
+    /// seconds, returning the token.
+    pub async fn share(conn: &mut Conn, uuid: Uuid, ttl_s: i64) -> Res<String> {
+        let token = to_hex_string(&generate_key()?);
+        let scene = format_uuid(uuid);
+        let expires_at = timestamp_s() + ttl_s;
+        sqlx::query(
+            "
+            INSERT INTO shared_scenes (share_token, scene, expires_at)
+            VALUES ($1, $2, $3);
+            ",
+        )
+        .bind(&token)
+        .bind(scene)
+        .bind(expires_at)
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(token)
+    }
+
+    /// Look up the scene shared with `token`, if the token hasn't expired.
+    pub async fn shared(conn: &mut Conn, token: &str) -> Res<Option<Self>> {
+        let now = timestamp_s();
+        let row = sqlx::query_as::<_, SceneRow>(
+            "
+            SELECT scenes.uuid, scenes.project, scenes.updated_time, scenes.title,
+                scenes.thumbnail
+            FROM shared_scenes JOIN scenes ON scenes.uuid = shared_scenes.scene
+            WHERE shared_scenes.share_token = $1 AND shared_scenes.expires_at > $2;
+            ",
+        )
+        .bind(token)
+        .bind(now)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        row.map(Self::try_from).transpose()
+    }
+
+    /// List a page of the scenes in `project` with titles containing
+    /// `query`, ignoring case, along with the total number of matches.
+    pub async fn search_titles(
+        conn: &mut Conn,
+        project: Uuid,
+        query: &str,
+        sort: SceneSort,
+        order: SortOrder,
+        limit: u32,
+        offset: u32,
+    ) -> Res<(Vec<Self>, u32)> {
+        let scenes = search_titles(conn, project, query, sort, order, limit, offset)
+            .await?
+            .into_iter()
+            .map(Self::try_from)
+            .collect::<Res<_>>()?;
+        let total = count_titles(conn, project, query).await?;
+        Ok((scenes, total))
+    }
+
+    /// Replace the indexed sprite labels for a scene.
+    pub async fn index_labels(conn: &mut Conn, uuid: Uuid, labels: &[&str]) -> Res<()> {
+        let uuid = format_uuid(uuid);
+        sqlx::query("DELETE FROM scene_labels WHERE scene = $1;")
+            .bind(&uuid)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for label in labels {
+            sqlx::query("INSERT INTO scene_labels (scene, label) VALUES ($1, $2);")
+                .bind(&uuid)
+                .bind(label)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Search sprite labels across all scenes in projects owned by `user`.
+    /// Each word of `query` is matched as a prefix.
+    pub async fn search_labels(
+        conn: &mut Conn,
+        user: Uuid,
+        query: &str,
+    ) -> Res<Vec<SceneSearchResult>> {
+        // SQLite indexes labels with FTS5, Postgres with a tsvector index.
+        let (query, sql) = if conn.backend_name() == Postgres::BACKEND {
+            (
+                tsquery(query),
+                r#"
+                SELECT scenes.project, scenes.uuid, scenes.title, scene_labels.label
+                FROM scene_labels
+                    JOIN scenes ON scenes.uuid = scene_labels.scene
+                    JOIN projects ON projects.uuid = scenes.project
+                WHERE to_tsvector('simple', scene_labels.label) @@ to_tsquery('simple', $1)
+                    AND projects."user" = $2
+                ORDER BY scenes.title, scenes.uuid,
+                    ts_rank(to_tsvector('simple', scene_labels.label), to_tsquery('simple', $1))
+                    DESC;
+                "#,
+            )
+        } else {
+            (
+                fts_query(query),
+                r#"
+                SELECT scenes.project, scenes.uuid, scenes.title, scene_labels.label
+                FROM scene_labels
+                    JOIN scenes ON scenes.uuid = scene_labels.scene
+                    JOIN projects ON projects.uuid = scenes.project
+                WHERE scene_labels MATCH $1 AND projects."user" = $2
+                ORDER BY scenes.title, scenes.uuid, scene_labels.rank;
+                "#,
+            )
+        };
+        let Some(query) = query else {
+            return Ok(Vec::new());
+        };
+
+        let rows: Vec<LabelMatchRow> = sqlx::query_as(sql)
+            .bind(query)
+            .bind(format_uuid(user))
+            .fetch_all(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut results: Vec<SceneSearchResult> = Vec::new();
+        for row in rows {
+            let scene = parse_uuid(&row.uuid)?;
+            match results.last_mut() {
+                Some(result) if result.scene == scene => result.matches.push(row.label),
+                _ => results.push(SceneSearchResult {
+                    project: parse_uuid(&row.project)?,
+                    scene,
+                    title: row.title,
+                    matches: vec![row.label],
+                }),
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// A scene with sprite labels matching a search query.
+pub struct SceneSearchResult {
+    pub project: Uuid,
+    pub scene: Uuid,
+    pub title: String,
+    pub matches: Vec<String>,
+}
+
+/// Convert a user's search into an FTS5 query, quoting each word so that
+/// FTS5 syntax in the input is treated literally.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Convert a user's search into a Postgres tsquery matching each word as a
+/// prefix, quoting each word so that tsquery syntax in the input is treated
+/// literally.
+fn tsquery(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("'{}':*", word.replace('\\', "\\\\").replace('\'', "''")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
 }
 
 impl TryFrom<SceneRow> for Scene {
@@ -88,19 +293,27 @@ struct SceneRow {
     project: String,
     updated_time: i64,
     title: String,
+    #[sqlx(try_from = "Nullable<String>")]
     thumbnail: Option<String>,
 }
 
+#[derive(sqlx::FromRow)]
+struct LabelMatchRow {
+    project: String,
+    uuid: String,
+    title: String,
+    label: String,
+}
+
 async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<SceneRow>> {
     let uuid_string = format_uuid(uuid);
-    sqlx::query_as!(
-        SceneRow,
+    sqlx::query_as::<_, SceneRow>(
         "
         SELECT uuid, project, updated_time, title, thumbnail
-        FROM scenes WHERE uuid = ?; 
+        FROM scenes WHERE uuid = $1;
         ",
-        uuid_string
     )
+    .bind(uuid_string)
     .fetch_optional(conn)
     .await
     .map_err(|e| e.to_string())
@@ -108,63 +321,121 @@ async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<SceneRow>> {
 
 async fn list_for_project(conn: &mut Conn, project: Uuid) -> Res<Vec<SceneRow>> {
     let uuid_string = format_uuid(project);
-    sqlx::query_as!(
-        SceneRow,
+    sqlx::query_as::<_, SceneRow>(
         "
         SELECT uuid, project, updated_time, title, thumbnail
-        FROM scenes WHERE project = ?;
+        FROM scenes WHERE project = $1;
         ",
-        uuid_string
     )
+    .bind(uuid_string)
     .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
 }
 
-async fn set_thumbnail(conn: &mut Conn, uuid: Uuid, thumbnail: &str) -> Res<()> {
-    let uuid_string = format_uuid(uuid);
-    sqlx::query!(
-        "UPDATE scenes SET thumbnail = ?1 WHERE uuid = ?2;",
-        thumbnail,
-        uuid_string
+/// Pattern for a LIKE clause matching titles containing `query` literally.
+fn title_pattern(query: &str) -> String {
+    format!(
+        "%{}%",
+        query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
     )
-    .execute(conn)
+}
+
+async fn search_titles(
+    conn: &mut Conn,
+    project: Uuid,
+    query: &str,
+    sort: SceneSort,
+    order: SortOrder,
+    limit: u32,
+    offset: u32,
+) -> Res<Vec<SceneRow>> {
+    // Columns can't be bound as parameters, so the ORDER BY clause is chosen
+    // from fixed strings. UUID breaks ties so that pages don't overlap.
+    let column = match sort {
+        SceneSort::Title => "title",
+        SceneSort::Updated => "updated_time",
+    };
+    let direction = match order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    let sql = format!(
+        r"
+        SELECT uuid, project, updated_time, title, thumbnail
+        FROM scenes WHERE project = $1 AND LOWER(title) LIKE LOWER($2) ESCAPE '\'
+        ORDER BY {column} {direction}, uuid LIMIT $3 OFFSET $4;
+        "
+    );
+    sqlx::query_as::<_, SceneRow>(&sql)
+        .bind(format_uuid(project))
+        .bind(title_pattern(query))
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn count_titles(conn: &mut Conn, project: Uuid, query: &str) -> Res<u32> {
+    let uuid_string = format_uuid(project);
+    let pattern = title_pattern(query);
+    sqlx::query_scalar::<_, i64>(
+        r"SELECT COUNT(*) FROM scenes WHERE project = $1 AND LOWER(title) LIKE LOWER($2) ESCAPE '\';",
+    )
+    .bind(uuid_string)
+    .bind(pattern)
+    .fetch_one(conn)
     .await
-    .map_err(|e| e.to_string())?;
+    .map(|count| count as u32)
+    .map_err(|e| e.to_string())
+}
+
+async fn set_thumbnail(conn: &mut Conn, uuid: Uuid, thumbnail: &str) -> Res<()> {
+    let uuid_string = format_uuid(uuid);
+    sqlx::query("UPDATE scenes SET thumbnail = $1 WHERE uuid = $2;")
+        .bind(thumbnail)
+        .bind(uuid_string)
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
 async fn update(conn: &mut Conn, uuid: Uuid, title: &str) -> Res<SceneRow> {
     let timestamp = timestamp_s();
     let uuid_string = format_uuid(uuid);
-    sqlx::query_as!(
-        SceneRow,
-        "UPDATE scenes SET updated_time = ?1, title = ?2 WHERE uuid = ?3 RETURNING *;",
-        timestamp,
-        title,
-        uuid_string,
+    sqlx::query_as::<_, SceneRow>(
+        "UPDATE scenes SET updated_time = $1, title = $2 WHERE uuid = $3 RETURNING *;",
     )
-    .fetch_one(conn)
+    .bind(timestamp)
+    .bind(title)
+    .bind(uuid_string)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
 async fn create(conn: &mut Conn, uuid: Uuid, project: Uuid, title: &str) -> Res<SceneRow> {
     let uuid = format_uuid(uuid);
     let project = format_uuid(project);
     let updated_time = timestamp_s();
-    sqlx::query_as!(
-        SceneRow,
+    sqlx::query_as::<_, SceneRow>(
         "
         INSERT INTO scenes (uuid, project, updated_time, title)
-        VALUES (?1, ?2, ?3, ?4) RETURNING *;
+        VALUES ($1, $2, $3, $4) RETURNING *;
         ",
-        uuid,
-        project,
-        updated_time,
-        title,
     )
-    .fetch_one(conn)
+    .bind(uuid)
+    .bind(project)
+    .bind(updated_time)
+    .bind(title)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
@@ -0,0 +1,104 @@
+use scene::SpriteTmpl;
+use uuid::Uuid;
+
+use super::{returned, Conn, Nullable};
+use crate::utils::{err, format_uuid, generate_uuid, parse_uuid, Res};
+
+/// A sprite configuration saved by a user, optionally shared with one of
+/// their projects.
+pub struct SpriteTemplate {
+    pub uuid: Uuid,
+    pub user: Uuid,
+    pub project: Option<Uuid>,
+    pub template: SpriteTmpl,
+}
+
+impl SpriteTemplate {
+    pub async fn create(
+        conn: &mut Conn,
+        user: Uuid,
+        project: Option<Uuid>,
+        mut template: SpriteTmpl,
+    ) -> Res<Self> {
+        template.name = template.name.trim().to_string();
+        if template.name.is_empty() {
+            return err("Template name must not be empty.");
+        } else if template.name.chars().count() > SpriteTmpl::MAX_NAME_LENGTH {
+            return Err(format!(
+                "Template name too long, max length is {}.",
+                SpriteTmpl::MAX_NAME_LENGTH
+            ));
+        } else if matches!(template.visual, scene::SpriteVisual::Drawing { .. }) {
+            return err("Drawings can't be saved as templates.");
+        }
+
+        let uuid = format_uuid(generate_uuid());
+        let user = format_uuid(user);
+        let project = project.map(format_uuid);
+        let json = serde_json::to_string(&template).map_err(|e| e.to_string())?;
+        sqlx::query_as::<_, SpriteTemplateRow>(
+            r#"
+            INSERT INTO sprite_templates (uuid, "user", project, name, template)
+            VALUES ($1, $2, $3, $4, $5) RETURNING *;
+            "#,
+        )
+        .bind(uuid)
+        .bind(user)
+        .bind(project)
+        .bind(&template.name)
+        .bind(json)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(returned)
+        .and_then(Self::try_from)
+    }
+
+    /// Templates saved by `user` which aren't shared with a project, followed
+    /// by those shared with `project`, if given.
+    pub async fn list(conn: &mut Conn, user: Uuid, project: Option<Uuid>) -> Res<Vec<Self>> {
+        let user = format_uuid(user);
+        let project = project.map(format_uuid);
+        sqlx::query_as::<_, SpriteTemplateRow>(
+            r#"
+            SELECT * FROM sprite_templates
+            WHERE ("user" = $1 AND project IS NULL) OR project = $2
+            ORDER BY project IS NOT NULL, uuid;
+            "#,
+        )
+        .bind(user)
+        .bind(project)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Self::try_from)
+        .collect()
+    }
+}
+
+impl TryFrom<SpriteTemplateRow> for SpriteTemplate {
+    type Error = String;
+
+    fn try_from(value: SpriteTemplateRow) -> Result<Self, Self::Error> {
+        let mut template: SpriteTmpl =
+            serde_json::from_str(&value.template).map_err(|e| e.to_string())?;
+        template.name = value.name;
+        Ok(Self {
+            uuid: parse_uuid(&value.uuid)?,
+            user: parse_uuid(&value.user)?,
+            project: value.project.as_deref().map(parse_uuid).transpose()?,
+            template,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SpriteTemplateRow {
+    uuid: String,
+    user: String,
+    #[sqlx(try_from = "Nullable<String>")]
+    project: Option<String>,
+    name: String,
+    template: String,
+}
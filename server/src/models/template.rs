@@ -0,0 +1,221 @@
+use scene::{Colour, Layer};
+use uuid::Uuid;
+
+use super::{Conn, Nullable};
+use crate::utils::{err, format_uuid, generate_uuid, parse_uuid, Res};
+
+/// Scene which new projects can be created with, either built in or saved by
+/// a user.
+pub struct ProjectTemplate {
+    pub key: String,
+    pub title: String,
+
+    /// User who saved this template, `None` for built in templates.
+    pub user: Option<Uuid>,
+}
+
+struct Builtin {
+    key: &'static str,
+    title: &'static str,
+    size: (u32, u32),
+    background: Colour,
+    fog: bool,
+
+    /// Layer titles, from top to bottom.
+    layers: &'static [&'static str],
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        key: "dungeon",
+        title: "Dungeon",
+        size: (40, 40),
+        background: Colour([0.2, 0.2, 0.22, 1.0]),
+        fog: true,
+        layers: &["Tokens", "Traps", "Walls", "Floor"],
+    },
+    Builtin {
+        key: "outdoor",
+        title: "Outdoor",
+        size: (64, 64),
+        background: Colour([0.45, 0.6, 0.35, 1.0]),
+        fog: false,
+        layers: &["Tokens", "Foliage", "Terrain"],
+    },
+    Builtin {
+        key: "urban",
+        title: "Urban",
+        size: (48, 48),
+        background: Colour([0.6, 0.6, 0.6, 1.0]),
+        fog: false,
+        layers: &["Tokens", "Roofs", "Buildings", "Streets"],
+    },
+    Builtin {
+        key: "ship_deck",
+        title: "Ship Deck",
+        size: (24, 48),
+        background: Colour([0.15, 0.3, 0.5, 1.0]),
+        fog: false,
+        layers: &["Tokens", "Rigging", "Deck"],
+    },
+];
+
+impl Builtin {
+    fn scene(&self) -> scene::Scene {
+        // Top layer is the foreground, with the rest below it.
+        let layers = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                let z = scene::Scene::FOREGROUND_Z - i as i32;
+                Layer::new(i as i64 + 1, title, z)
+            })
+            .collect();
+
+        let mut scene = scene::Scene::new_with(Uuid::nil(), layers, Vec::new());
+        scene.title = self.title.to_string();
+        scene.background = self.background;
+        scene.set_size(self.size.0, self.size.1);
+        if self.fog {
+            scene.fog.active = true;
+            scene.fog.fill_all();
+        }
+        scene
+    }
+}
+
+impl ProjectTemplate {
+    pub const MAX_TITLE_LENGTH: usize = 64;
+
+    /// Insert any missing built in templates.
+    pub async fn seed(conn: &mut Conn) -> Res<()> {
+        for builtin in BUILTINS {
+            let blob = serialise(builtin.scene())?;
+            sqlx::query(
+                r#"
+                INSERT INTO project_templates (key, "user", title, scene)
+                VALUES ($1, NULL, $2, $3) ON CONFLICT DO NOTHING;
+                "#,
+            )
+            .bind(builtin.key)
+            .bind(builtin.title)
+            .bind(blob)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Built in templates in the order they're defined, followed by those
+    /// saved by `user` in the order they were saved.
+    pub async fn list(conn: &mut Conn, user: Uuid) -> Res<Vec<Self>> {
+        let user = format_uuid(user);
+        let mut templates = sqlx::query_as::<_, (String, Nullable<String>, String)>(
+            r#"
+            SELECT key, "user", title FROM project_templates
+            WHERE "user" IS NULL OR "user" = $1 ORDER BY key;
+            "#,
+        )
+        .bind(user)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(key, user, title)| {
+            Ok(Self {
+                key,
+                title,
+                user: user.0.as_deref().map(parse_uuid).transpose()?,
+            })
+        })
+        .collect::<Res<Vec<Self>>>()?;
+
+        // User template keys are time ordered UUIDs, so ordering by key lists
+        // them in the order saved. Built in templates are moved to the front.
+        templates.sort_by_key(|template| {
+            BUILTINS
+                .iter()
+                .position(|builtin| template.user.is_none() && builtin.key == template.key)
+                .unwrap_or(BUILTINS.len())
+        });
+        Ok(templates)
+    }
+
+    /// Load the scene for the template `key`, which must be built in or
+    /// belong to `user`. The scene is given a new UUID.
+    pub async fn scene(conn: &mut Conn, key: &str, user: Uuid) -> Res<scene::Scene> {
+        let user = format_uuid(user);
+        let Some(blob) = sqlx::query_scalar::<_, Vec<u8>>(
+            r#"
+            SELECT scene FROM project_templates
+            WHERE key = $1 AND ("user" IS NULL OR "user" = $2);
+            "#,
+        )
+        .bind(key)
+        .bind(user)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        else {
+            return err("Template not found.");
+        };
+
+        let Some(mut scene) = scene::serde::deserialise(&blob)?.scenes.pop() else {
+            return err("Template has no scene.");
+        };
+        scene.uuid = generate_uuid();
+        Ok(scene)
+    }
+
+    /// Save `scene` as a new template belonging to `user`.
+    pub async fn create(
+        conn: &mut Conn,
+        user: Uuid,
+        title: &str,
+        mut scene: scene::Scene,
+    ) -> Res<Self> {
+        let title = title.trim();
+        if title.is_empty() {
+            return err("Template title must not be empty.");
+        } else if title.chars().count() > Self::MAX_TITLE_LENGTH {
+            return Err(format!(
+                "Template title too long, max length is {}.",
+                Self::MAX_TITLE_LENGTH
+            ));
+        }
+
+        let key = format_uuid(generate_uuid());
+        scene.title = title.to_string();
+        let blob = serialise(scene)?;
+        let user_str = format_uuid(user);
+        sqlx::query(
+            r#"
+            INSERT INTO project_templates (key, "user", title, scene)
+            VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(&key)
+        .bind(user_str)
+        .bind(title)
+        .bind(blob)
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            key,
+            title: title.to_string(),
+            user: Some(user),
+        })
+    }
+}
+
+/// Templates are stored as a project containing just the template scene, to
+/// reuse the versioned project serialisation.
+fn serialise(scene: scene::Scene) -> Res<Vec<u8>> {
+    let mut project = scene::Project::new(Uuid::nil());
+    project.scenes.push(scene);
+    scene::serde::serialise(&project)
+}
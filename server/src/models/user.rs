@@ -1,9 +1,10 @@
-use sqlx::prelude::FromRow;
+use sqlx::{prelude::FromRow, Connection};
 use uuid::Uuid;
 
-use super::{timestamp_s, timestamp_to_system, Conn};
+use super::{returned, timestamp_s, timestamp_to_system, Conn, Nullable};
 use crate::{
     crypto::{from_hex_string, generate_key, to_hex_string, Key},
+    fs::{join_relative_path, CONTENT, SAVES},
     utils::{err, format_uuid, generate_uuid, parse_uuid, Res},
 };
 
@@ -11,9 +12,24 @@ use crate::{
 pub struct User {
     pub uuid: Uuid,
     pub username: String,
+
+    /// Name shown to other players. Defaults to the username.
+    pub display_name: String,
+
+    /// UUID of the media item used as this user's avatar.
+    pub avatar_media_key: Option<String>,
+
+    /// Address to send password reset emails to.
+    pub email: Option<String>,
+
+    /// Base32 TOTP secret, if two-factor authentication is enabled.
+    pub totp_secret: Option<String>,
 }
 
 impl User {
+    pub const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+    pub const MAX_EMAIL_LENGTH: usize = 254;
+
     pub async fn get_by_uuid(conn: &mut Conn, uuid: Uuid) -> Res<Self> {
         match lookup(conn, uuid).await? {
             Some(record) => Self::try_from(record),
@@ -21,14 +37,186 @@ impl User {
         }
     }
 
+    pub async fn get_by_email(conn: &mut Conn, email: &str) -> Res<Option<Self>> {
+        let email = email.trim().to_lowercase();
+        sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE email = $1;")
+            .bind(email)
+            .fetch_optional(conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(Self::try_from)
+            .transpose()
+    }
+
     pub async fn username_taken(conn: &mut Conn, username: &str) -> Res<bool> {
-        sqlx::query!("SELECT uuid FROM users WHERE username = ?1;", username)
+        sqlx::query("SELECT uuid FROM users WHERE username = $1;")
+            .bind(username)
             .fetch_optional(conn)
             .await
             .map_err(|e| e.to_string())
             .map(|opt| opt.is_some())
     }
 
+    /// Update the display name, avatar and email of a user. An empty display
+    /// name resets it to the username. The avatar must be a media item owned
+    /// by the user.
+    pub async fn set_profile(
+        conn: &mut Conn,
+        uuid: Uuid,
+        display_name: &str,
+        avatar: Option<Uuid>,
+        email: Option<&str>,
+    ) -> Res<Self> {
+        let display_name = display_name.trim();
+        if display_name.chars().count() > Self::MAX_DISPLAY_NAME_LENGTH {
+            return Err(format!(
+                "Display name too long, max length is {}.",
+                Self::MAX_DISPLAY_NAME_LENGTH
+            ));
+        }
+
+        if let Some(avatar) = avatar {
+            match super::Media::load(conn, avatar).await {
+                Ok(media) if media.user == uuid => {}
+                _ => return err("Avatar media not found."),
+            }
+        }
+
+        let email = email
+            .map(|e| e.trim().to_lowercase())
+            .filter(|e| !e.is_empty());
+        if let Some(email) = &email {
+            if email.len() > Self::MAX_EMAIL_LENGTH || !email.contains('@') {
+                return err("Invalid email address.");
+            }
+        }
+
+        update_profile(conn, uuid, display_name, avatar.map(format_uuid), email)
+            .await
+            .and_then(Self::try_from)
+    }
+
+    /// Store a TOTP secret for the user, to be enabled once they confirm a
+    /// valid code with `enable_totp`.
+    pub async fn set_pending_totp_secret(conn: &mut Conn, uuid: Uuid, secret: &str) -> Res<()> {
+        let uuid = format_uuid(uuid);
+        sqlx::query("UPDATE users SET totp_pending_secret = $1 WHERE uuid = $2;")
+            .bind(secret)
+            .bind(uuid)
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn pending_totp_secret(conn: &mut Conn, uuid: Uuid) -> Res<Option<String>> {
+        let uuid = format_uuid(uuid);
+        sqlx::query_scalar::<_, Nullable<String>>(
+            "SELECT totp_pending_secret FROM users WHERE uuid = $1;",
+        )
+        .bind(uuid)
+        .fetch_optional(conn)
+        .await
+        .map(|secret| secret.and_then(|secret| secret.0))
+        .map_err(|e| e.to_string())
+    }
+
+    /// Enable two-factor authentication with the user's pending secret.
+    pub async fn enable_totp(conn: &mut Conn, uuid: Uuid) -> Res<()> {
+        let uuid = format_uuid(uuid);
+        sqlx::query(
+            "
+            UPDATE users SET totp_secret = totp_pending_secret, totp_pending_secret = NULL
+            WHERE uuid = $1 AND totp_pending_secret IS NOT NULL;
+            ",
+        )
+        .bind(uuid)
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    /// Record that the user has used a TOTP code for `step`, returning false
+    /// if a code for this or a later step has already been used.
+    pub async fn use_totp_step(conn: &mut Conn, uuid: Uuid, step: u64) -> Res<bool> {
+        let uuid = format_uuid(uuid);
+        let step = i64::try_from(step).map_err(|e| e.to_string())?;
+        sqlx::query(
+            "
+            UPDATE users SET totp_last_step = $1
+            WHERE uuid = $2 AND (totp_last_step IS NULL OR totp_last_step < $1);
+            ",
+        )
+        .bind(step)
+        .bind(uuid)
+        .execute(conn)
+        .await
+        .map(|res| res.rows_affected() > 0)
+        .map_err(|e| e.to_string())
+    }
+
+    /// Delete the user along with all of their data, including files, and
+    /// record the deletion in the account deletion log.
+    pub async fn delete(self, conn: &mut Conn) -> Res<()> {
+        let uuid = format_uuid(self.uuid);
+        let deleted_at = timestamp_s();
+        let mut tx = conn.begin().await.map_err(|e| e.to_string())?;
+
+        // Audit entries and labels refer to scenes without a foreign key, so
+        // aren't removed along with the user's scenes.
+        sqlx::query(
+            r#"
+            DELETE FROM audit_log WHERE "user" = $1 OR scene IN (
+                SELECT scenes.uuid FROM scenes
+                JOIN projects ON projects.uuid = scenes.project
+                WHERE projects."user" = $1
+            );
+            "#,
+        )
+        .bind(&uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        sqlx::query(
+            r#"
+            DELETE FROM scene_labels WHERE scene IN (
+                SELECT scenes.uuid FROM scenes
+                JOIN projects ON projects.uuid = scenes.project
+                WHERE projects."user" = $1
+            );
+            "#,
+        )
+        .bind(&uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        // All other records cascade from the user.
+        sqlx::query("DELETE FROM users WHERE uuid = $1;")
+            .bind(&uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query(r#"INSERT INTO account_deletions ("user", deleted_at) VALUES ($1, $2);"#)
+            .bind(&uuid)
+            .bind(deleted_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())?;
+
+        let uploads = join_relative_path(&CONTENT, format!("/uploads/{}", self.username));
+        for dir in [uploads, SAVES.join(&self.username)] {
+            match tokio::fs::remove_dir_all(dir).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(test)]
     pub async fn generate(conn: &mut Conn) -> Self {
         UserAuth::generate(conn)
@@ -52,9 +240,19 @@ impl TryFrom<UserRow> for User {
     type Error = String;
 
     fn try_from(value: UserRow) -> Result<Self, Self::Error> {
+        let display_name = if value.display_name.is_empty() {
+            value.username.clone()
+        } else {
+            value.display_name
+        };
+
         Ok(Self {
             uuid: parse_uuid(&value.uuid)?,
             username: value.username,
+            display_name,
+            avatar_media_key: value.avatar_media_key,
+            email: value.email,
+            totp_secret: value.totp_secret,
         })
     }
 }
@@ -63,7 +261,11 @@ impl From<UserAuth> for User {
     fn from(value: UserAuth) -> Self {
         Self {
             uuid: value.uuid,
+            display_name: value.username.clone(),
             username: value.username,
+            avatar_media_key: None,
+            email: None,
+            totp_secret: value.totp_secret,
         }
     }
 }
@@ -75,6 +277,7 @@ pub struct UserAuth {
     pub salt: Key,
     pub hashed_password: Key,
     pub recovery_key: Key,
+    pub totp_secret: Option<String>,
 }
 
 impl UserAuth {
@@ -103,6 +306,40 @@ impl UserAuth {
         .and_then(Self::try_from)
     }
 
+    /// Replace a user's password, ending all of their sessions and revoking
+    /// their API keys so that anyone who knew the old password loses access.
+    pub async fn set_password(conn: &mut Conn, user: Uuid, password: &str) -> Res<()> {
+        let salt = to_hex_string(&generate_key()?);
+        let hashed_password = to_hex_string(&crate::crypto::hash_password(
+            &from_hex_string(&salt)?,
+            password,
+        ));
+        let end_time = timestamp_s();
+        let user = format_uuid(user);
+        let mut tx = conn.begin().await.map_err(|e| e.to_string())?;
+        sqlx::query("UPDATE users SET salt = $1, hashed_password = $2 WHERE uuid = $3;")
+            .bind(salt)
+            .bind(hashed_password)
+            .bind(&user)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        sqlx::query(
+            r#"UPDATE user_sessions SET end_time = $1 WHERE "user" = $2 AND end_time IS NULL;"#,
+        )
+        .bind(end_time)
+        .bind(&user)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        sqlx::query(r#"DELETE FROM user_api_keys WHERE "user" = $1;"#)
+            .bind(&user)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
     #[cfg(test)]
     pub const GENERATED_USER_PASSWORD: &'static str = "password";
 
@@ -134,6 +371,7 @@ impl TryFrom<UserRow> for UserAuth {
             salt: from_hex_string(&value.salt)?,
             hashed_password: from_hex_string(&value.hashed_password)?,
             recovery_key: from_hex_string(&value.hashed_password)?,
+            totp_secret: value.totp_secret,
         })
     }
 }
@@ -145,11 +383,21 @@ struct UserRow {
     salt: String,
     hashed_password: String,
     recovery_key: String,
+    display_name: String,
+    #[sqlx(try_from = "Nullable<String>")]
+    avatar_media_key: Option<String>,
+    #[sqlx(try_from = "Nullable<String>")]
+    email: Option<String>,
+    #[sqlx(try_from = "Nullable<String>")]
+    totp_secret: Option<String>,
+    #[sqlx(try_from = "Nullable<String>")]
+    totp_pending_secret: Option<String>,
 }
 
 async fn lookup(conn: &mut Conn, uuid: Uuid) -> Res<Option<UserRow>> {
     let uuid = format_uuid(uuid);
-    sqlx::query_as!(UserRow, "SELECT * FROM users WHERE uuid = ?1;", uuid)
+    sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE uuid = $1;")
+        .bind(uuid)
         .fetch_optional(conn)
         .await
         .map_err(|e| e.to_string())
@@ -163,32 +411,278 @@ async fn register(
     recovery_key: &str,
 ) -> Res<UserRow> {
     let uuid = format_uuid(generate_uuid());
-    sqlx::query_as!(
-        UserRow,
+    sqlx::query_as::<_, UserRow>(
         "
         INSERT INTO users (uuid, username, salt, hashed_password, recovery_key)
-        VALUES (?1, ?2, ?3, ?4, ?5) RETURNING *;
+        VALUES ($1, $2, $3, $4, $5) RETURNING *;
         ",
-        uuid,
-        username,
-        salt,
-        hashed_password,
-        recovery_key
     )
-    .fetch_one(conn)
+    .bind(uuid)
+    .bind(username)
+    .bind(salt)
+    .bind(hashed_password)
+    .bind(recovery_key)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
-async fn lookup_by_username(conn: &mut Conn, username: &str) -> Res<Option<UserRow>> {
-    sqlx::query_as!(
-        UserRow,
-        "SELECT * FROM users WHERE username = ?1;",
-        username
+async fn update_profile(
+    conn: &mut Conn,
+    uuid: Uuid,
+    display_name: &str,
+    avatar: Option<String>,
+    email: Option<String>,
+) -> Res<UserRow> {
+    let uuid = format_uuid(uuid);
+    sqlx::query_as::<_, UserRow>(
+        "
+        UPDATE users SET display_name = $1, avatar_media_key = $2, email = $3
+        WHERE uuid = $4 RETURNING *;
+        ",
     )
-    .fetch_optional(conn)
+    .bind(display_name)
+    .bind(avatar)
+    .bind(email)
+    .bind(uuid)
+    .fetch_all(conn)
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| {
+        // Don't reveal that the email address belongs to another user.
+        if e.as_database_error()
+            .is_some_and(|e| e.is_unique_violation())
+        {
+            "Failed to update profile.".to_string()
+        } else {
+            e.to_string()
+        }
+    })
+    .and_then(returned)
+}
+
+async fn lookup_by_username(conn: &mut Conn, username: &str) -> Res<Option<UserRow>> {
+    sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE username = $1;")
+        .bind(username)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Single use token allowing a user to set a new password. Only a hash of
+/// the token is stored.
+pub struct PasswordReset;
+
+impl PasswordReset {
+    /// Tokens expire after an hour.
+    const VALIDITY_S: i64 = 60 * 60;
+
+    /// Create a reset token for `user`, returning the token to send to them.
+    pub async fn create(conn: &mut Conn, user: Uuid) -> Res<String> {
+        let token = to_hex_string(&generate_key()?);
+        let token_hash = crate::crypto::hash_token(&token);
+        let user = format_uuid(user);
+        let expires_at = timestamp_s() + Self::VALIDITY_S;
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens (token_hash, "user", expires_at)
+            VALUES ($1, $2, $3);
+            "#,
+        )
+        .bind(token_hash)
+        .bind(user)
+        .bind(expires_at)
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(token)
+    }
+
+    /// Consume a token, returning the user it was issued to. Fails if the
+    /// token doesn't exist or has expired.
+    pub async fn redeem(conn: &mut Conn, token: &str) -> Res<Uuid> {
+        let token_hash = crate::crypto::hash_token(token);
+        let record = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            DELETE FROM password_reset_tokens WHERE token_hash = $1
+            RETURNING "user", expires_at;
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .pop();
+
+        match record {
+            Some((user, expires_at)) if expires_at > timestamp_s() => parse_uuid(&user),
+            _ => err("Invalid or expired reset token."),
+        }
+    }
+}
+
+/// Record of when a user last exported their data. Exports are expensive, so
+/// are limited to one a day.
+pub struct DataExport;
+
+impl DataExport {
+    const INTERVAL_S: i64 = 24 * 60 * 60;
+
+    /// Reserve today's export for `user`, failing if they have already
+    /// exported their data within the last day. Returns the time of the
+    /// reservation, with which it can be released.
+    pub async fn reserve(conn: &mut Conn, user: Uuid) -> Res<i64> {
+        let user = format_uuid(user);
+        let now = timestamp_s();
+        let cutoff = now - Self::INTERVAL_S;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO data_exports ("user", export_time) VALUES ($1, $2)
+            ON CONFLICT ("user") DO UPDATE SET export_time = $2
+            WHERE data_exports.export_time <= $3;
+            "#,
+        )
+        .bind(user)
+        .bind(now)
+        .bind(cutoff)
+        .execute(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if result.rows_affected() == 0 {
+            err("Data may only be exported once a day.")
+        } else {
+            Ok(now)
+        }
+    }
+
+    /// Release a reservation made at `time` by an export which failed, so
+    /// that it doesn't count towards the limit.
+    pub async fn release(conn: &mut Conn, user: Uuid, time: i64) -> Res<()> {
+        let user = format_uuid(user);
+        sqlx::query(r#"DELETE FROM data_exports WHERE "user" = $1 AND export_time = $2;"#)
+            .bind(user)
+            .bind(time)
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Key allowing programmatic clients to authenticate with an
+/// `Authorization: Bearer` header instead of a session cookie. Only a hash
+/// of the key is stored.
+#[derive(Debug)]
+pub struct ApiKey {
+    pub label: String,
+    pub created: std::time::SystemTime,
+}
+
+impl ApiKey {
+    pub const MAX_LABEL_LENGTH: usize = 64;
+
+    pub fn created_timestamp(&self) -> u64 {
+        self.created
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Create a key for `user` with the provided label, returning the key.
+    /// Labels are unique for each user.
+    pub async fn create(conn: &mut Conn, user: Uuid, label: &str) -> Res<String> {
+        let label = label.trim();
+        if label.is_empty() {
+            return err("API key label must not be empty.");
+        } else if label.chars().count() > Self::MAX_LABEL_LENGTH {
+            return Err(format!(
+                "API key label too long, max length is {}.",
+                Self::MAX_LABEL_LENGTH
+            ));
+        }
+
+        let key = to_hex_string(&generate_key()?);
+        let key_hash = crate::crypto::hash_token(&key);
+        let user = format_uuid(user);
+        let created_at = timestamp_s();
+        sqlx::query(
+            r#"
+            INSERT INTO user_api_keys (key_hash, "user", label, created_at)
+            VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(key_hash)
+        .bind(user)
+        .bind(label)
+        .bind(created_at)
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            if e.as_database_error()
+                .is_some_and(|e| e.is_unique_violation())
+            {
+                "An API key with that label already exists.".to_string()
+            } else {
+                e.to_string()
+            }
+        })?;
+        Ok(key)
+    }
+
+    pub async fn list(conn: &mut Conn, user: Uuid) -> Res<Vec<Self>> {
+        let user = format_uuid(user);
+        let records = sqlx::query_as::<_, (String, i64)>(
+            r#"SELECT label, created_at FROM user_api_keys WHERE "user" = $1 ORDER BY created_at;"#,
+        )
+        .bind(user)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(records
+            .into_iter()
+            .map(|(label, created_at)| Self {
+                label,
+                created: timestamp_to_system(created_at),
+            })
+            .collect())
+    }
+
+    /// Delete the key with the provided label, failing if there is none.
+    pub async fn revoke(conn: &mut Conn, user: Uuid, label: &str) -> Res<()> {
+        let user = format_uuid(user);
+        let result = sqlx::query(r#"DELETE FROM user_api_keys WHERE "user" = $1 AND label = $2;"#)
+            .bind(user)
+            .bind(label)
+            .execute(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if result.rows_affected() == 0 {
+            err("API key not found.")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Look up the user who owns a key.
+    pub async fn user(conn: &mut Conn, key: &str) -> Res<Option<User>> {
+        let key_hash = crate::crypto::hash_token(key);
+        sqlx::query_as::<_, UserRow>(
+            r#"
+            SELECT users.* FROM users
+            JOIN user_api_keys ON user_api_keys."user" = users.uuid
+            WHERE user_api_keys.key_hash = $1;
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(User::try_from)
+        .transpose()
+    }
 }
 
 #[derive(Debug)]
@@ -200,6 +694,9 @@ pub struct UserSession {
 }
 
 impl UserSession {
+    /// Sessions expire this long after they are started, unless refreshed.
+    pub const MAX_AGE_S: i64 = 180 * 24 * 60 * 60;
+
     pub fn key_text(&self) -> String {
         to_hex_string(&self.session_key)
     }
@@ -220,18 +717,24 @@ impl UserSession {
         }
     }
 
+    /// Replace this session with a new session for the same user, ending
+    /// this one.
+    pub async fn refresh(self, conn: &mut Conn) -> Res<Self> {
+        let session = Self::create(conn, self.user).await?;
+        self.end(conn).await?;
+        Ok(session)
+    }
+
     pub async fn end(self, conn: &mut Conn) -> Res<()> {
         let end_time = timestamp_s();
         let session_key = to_hex_string(&self.session_key);
-        sqlx::query!(
-            "UPDATE user_sessions SET end_time = ?1 WHERE session_key = ?2",
-            end_time,
-            session_key
-        )
-        .execute(conn)
-        .await
-        .map(|_| ())
-        .map_err(|e| e.to_string())
+        sqlx::query("UPDATE user_sessions SET end_time = $1 WHERE session_key = $2")
+            .bind(end_time)
+            .bind(session_key)
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
     }
 }
 
@@ -253,41 +756,39 @@ struct UserSessionRow {
     session_key: String,
     user: String,
     start_time: i64,
+    #[sqlx(try_from = "Nullable<i64>")]
     end_time: Option<i64>,
 }
 
 async fn lookup_user_session(conn: &mut Conn, session_key: &str) -> Res<Option<UserSessionRow>> {
-    sqlx::query_as!(
-        UserSessionRow,
-        "SELECT * FROM user_sessions WHERE session_key = ?1;",
-        session_key
-    )
-    .fetch_optional(conn)
-    .await
-    .map_err(|e| e.to_string())
+    sqlx::query_as::<_, UserSessionRow>("SELECT * FROM user_sessions WHERE session_key = $1;")
+        .bind(session_key)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 async fn create_user_session(conn: &mut Conn, user: Uuid) -> Res<UserSessionRow> {
     let session_key = to_hex_string(&generate_key()?);
     let user = format_uuid(user);
     let start_time = timestamp_s();
-    sqlx::query_as!(
-        UserSessionRow,
-        "
-        INSERT INTO user_sessions (session_key, user, start_time)
-        VALUES (?1, ?2, ?3) RETURNING *;
-        ",
-        session_key,
-        user,
-        start_time
+    sqlx::query_as::<_, UserSessionRow>(
+        r#"
+        INSERT INTO user_sessions (session_key, "user", start_time)
+        VALUES ($1, $2, $3) RETURNING *;
+        "#,
     )
-    .fetch_one(conn)
+    .bind(session_key)
+    .bind(user)
+    .bind(start_time)
+    .fetch_all(conn)
     .await
     .map_err(|e| e.to_string())
+    .and_then(returned)
 }
 
 /// Given a valid session key, return the associated user. None if session
-/// has expired.
+/// has ended or expired.
 async fn get_user_with_session(
     conn: &mut Conn,
     session: &str,
@@ -299,28 +800,46 @@ async fn get_user_with_session(
         salt: String,
         hashed_password: String,
         recovery_key: String,
+        display_name: String,
+        #[sqlx(try_from = "Nullable<String>")]
+        avatar_media_key: Option<String>,
+        #[sqlx(try_from = "Nullable<String>")]
+        email: Option<String>,
+        #[sqlx(try_from = "Nullable<String>")]
+        totp_secret: Option<String>,
+        #[sqlx(try_from = "Nullable<String>")]
+        totp_pending_secret: Option<String>,
         session_key: String,
         start_time: i64,
+        #[sqlx(try_from = "Nullable<i64>")]
         end_time: Option<i64>,
     }
 
-    let row = sqlx::query_as!(
-        QueryRow,
-        "
+    let cutoff = timestamp_s() - UserSession::MAX_AGE_S;
+    let row = sqlx::query_as::<_, QueryRow>(
+        r#"
         SELECT
             uuid,
             username,
             salt,
             hashed_password,
             recovery_key,
+            display_name,
+            avatar_media_key,
+            email,
+            totp_secret,
+            totp_pending_secret,
             session_key,
             start_time,
             end_time
-        FROM users LEFT JOIN user_sessions ON user_sessions.user = users.uuid
-        WHERE user_sessions.session_key = ?1 AND user_sessions.end_time IS NULL;
-        ",
-        session
+        FROM users LEFT JOIN user_sessions ON user_sessions."user" = users.uuid
+        WHERE user_sessions.session_key = $1
+            AND user_sessions.end_time IS NULL
+            AND user_sessions.start_time >= $2;
+        "#,
     )
+    .bind(session)
+    .bind(cutoff)
     .fetch_optional(conn)
     .await
     .map_err(|e| e.to_string())?;
@@ -333,6 +852,11 @@ async fn get_user_with_session(
                 salt: row.salt,
                 hashed_password: row.hashed_password,
                 recovery_key: row.recovery_key,
+                display_name: row.display_name,
+                avatar_media_key: row.avatar_media_key,
+                email: row.email,
+                totp_secret: row.totp_secret,
+                totp_pending_secret: row.totp_pending_secret,
             },
             UserSessionRow {
                 session_key: row.session_key,
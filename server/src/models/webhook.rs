@@ -0,0 +1,169 @@
+use uuid::Uuid;
+
+use super::{returned, Conn};
+use crate::{
+    crypto::{generate_key, to_hex_string},
+    utils::{err, format_uuid, generate_uuid, parse_uuid, Res},
+};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    serde_derive::Deserialize,
+    serde_derive::Serialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    GameStart,
+    GameEnd,
+    PlayerJoin,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GameStart => "game_start",
+            Self::GameEnd => "game_end",
+            Self::PlayerJoin => "player_join",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "game_start" => Some(Self::GameStart),
+            "game_end" => Some(Self::GameEnd),
+            "player_join" => Some(Self::PlayerJoin),
+            _ => None,
+        }
+    }
+}
+
+/// URL to POST to when events occur in the user's games.
+pub struct Webhook {
+    pub uuid: Uuid,
+    pub user: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+
+    /// Key used to sign payloads, so receivers can verify their origin.
+    pub secret: String,
+}
+
+impl Webhook {
+    pub const MAX_URL_LENGTH: usize = 2048;
+
+    pub async fn create(
+        conn: &mut Conn,
+        user: Uuid,
+        url: &str,
+        events: &[WebhookEvent],
+    ) -> Res<Self> {
+        let url = url.trim();
+        if url.len() > Self::MAX_URL_LENGTH {
+            return Err(format!(
+                "Webhook URL too long, max length is {}.",
+                Self::MAX_URL_LENGTH
+            ));
+        } else if events.is_empty() {
+            return err("Webhooks must subscribe to at least one event.");
+        }
+        crate::webhooks::check_url(url)?;
+
+        let mut names: Vec<&str> = Vec::new();
+        for event in events {
+            if !names.contains(&event.as_str()) {
+                names.push(event.as_str());
+            }
+        }
+
+        let uuid = format_uuid(generate_uuid());
+        let user = format_uuid(user);
+        let events = names.join(",");
+        let secret = to_hex_string(&generate_key()?);
+        sqlx::query_as::<_, WebhookRow>(
+            r#"
+            INSERT INTO webhooks (uuid, "user", url, events, secret)
+            VALUES ($1, $2, $3, $4, $5) RETURNING *;
+            "#,
+        )
+        .bind(uuid)
+        .bind(user)
+        .bind(url)
+        .bind(events)
+        .bind(secret)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(returned)
+        .and_then(Self::try_from)
+    }
+
+    pub async fn list(conn: &mut Conn, user: Uuid) -> Res<Vec<Self>> {
+        let user = format_uuid(user);
+        sqlx::query_as::<_, WebhookRow>(r#"SELECT * FROM webhooks WHERE "user" = $1;"#)
+            .bind(user)
+            .fetch_all(conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(Self::try_from)
+            .collect()
+    }
+
+    /// Webhooks belonging to `user` which subscribe to `event`.
+    pub async fn for_event(conn: &mut Conn, user: Uuid, event: WebhookEvent) -> Res<Vec<Self>> {
+        Ok(Self::list(conn, user)
+            .await?
+            .into_iter()
+            .filter(|hook| hook.events.contains(&event))
+            .collect())
+    }
+
+    /// Delete a webhook belonging to `user`, failing if there is none.
+    pub async fn delete(conn: &mut Conn, user: Uuid, uuid: Uuid) -> Res<()> {
+        let user = format_uuid(user);
+        let uuid = format_uuid(uuid);
+        let result = sqlx::query(r#"DELETE FROM webhooks WHERE uuid = $1 AND "user" = $2;"#)
+            .bind(uuid)
+            .bind(user)
+            .execute(conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if result.rows_affected() == 0 {
+            err("Webhook not found.")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl TryFrom<WebhookRow> for Webhook {
+    type Error = String;
+
+    fn try_from(value: WebhookRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            uuid: parse_uuid(&value.uuid)?,
+            user: parse_uuid(&value.user)?,
+            url: value.url,
+            events: value
+                .events
+                .split(',')
+                .filter_map(WebhookEvent::parse)
+                .collect(),
+            secret: value.secret,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct WebhookRow {
+    uuid: String,
+    user: String,
+    url: String,
+    events: String,
+    secret: String,
+}
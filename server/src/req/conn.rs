@@ -1,16 +1,91 @@
-use std::pin::Pin;
+use std::{path::Path, pin::Pin};
 
 use actix_web::FromRequest;
 use futures::Future;
-use sqlx::{pool::PoolConnection, Sqlite, SqliteConnection};
+use sqlx::{migrate::Migrator, pool::PoolConnection, Any, AnyConnection, AnyPool};
 
 use super::e500;
-use crate::fs::database_connection;
+use crate::{fs::database_connection, utils::Res};
 
-pub struct Pool(PoolConnection<Sqlite>);
+/// A database backend the server can store its data in. Queries are run
+/// through `sqlx::Any`, so are written in SQL which all backends accept, with
+/// `$N` parameters and `"user"` quoted.
+pub trait DbPool {
+    /// Name of the backend, as given by `AnyConnection::backend_name`.
+    const BACKEND: &'static str;
+
+    /// Schemes of database URLs for this backend.
+    const SCHEMES: &'static [&'static str];
+
+    /// Migrations which bring a database on this backend up to date, from
+    /// `migrations/{backend}` in the server directory. These can also be
+    /// applied with `sqlx migrate run --source`.
+    fn migrator() -> &'static Migrator;
+
+    fn handles(url: &str) -> bool {
+        url.split_once(':')
+            .is_some_and(|(scheme, _)| Self::SCHEMES.contains(&scheme))
+    }
+
+    /// Connect to the database at `url`, applying any pending migrations.
+    async fn connect(url: &str) -> Res<AnyPool> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPool::connect(url).await.map_err(|e| e.to_string())?;
+        Self::migrator()
+            .run(&pool)
+            .await
+            .map_err(|e| format!("Database migration failed: {e}"))?;
+        Ok(pool)
+    }
+}
+
+pub struct Sqlite;
+
+impl Sqlite {
+    /// URL of the SQLite database at `path`, which is created if it doesn't
+    /// exist.
+    pub fn url(path: &Path) -> String {
+        format!("sqlite://{}?mode=rwc", path.display())
+    }
+}
+
+impl DbPool for Sqlite {
+    const BACKEND: &'static str = "SQLite";
+    const SCHEMES: &'static [&'static str] = &["sqlite"];
+
+    fn migrator() -> &'static Migrator {
+        static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+        &MIGRATOR
+    }
+}
+
+pub struct Postgres;
+
+impl DbPool for Postgres {
+    const BACKEND: &'static str = "PostgreSQL";
+    const SCHEMES: &'static [&'static str] = &["postgres", "postgresql"];
+
+    fn migrator() -> &'static Migrator {
+        static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+        &MIGRATOR
+    }
+}
+
+/// Connect to the database at `url` with the backend its scheme selects.
+pub async fn connect(url: &str) -> Res<AnyPool> {
+    if Sqlite::handles(url) {
+        Sqlite::connect(url).await
+    } else if Postgres::handles(url) {
+        Postgres::connect(url).await
+    } else {
+        Err(format!("Unsupported database URL: {url}"))
+    }
+}
+
+pub struct Pool(PoolConnection<Any>);
 
 impl Pool {
-    pub fn acquire(&mut self) -> &mut SqliteConnection {
+    pub fn acquire(&mut self) -> &mut AnyConnection {
         &mut self.0
     }
 }
@@ -26,3 +101,97 @@ impl FromRequest for Pool {
         Box::pin(async { Ok(Pool(database_connection().await.map_err(e500)?)) })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use sqlx::{Connection, Executor};
+
+    use super::{connect, Sqlite};
+    use crate::utils::{format_uuid, generate_uuid};
+
+    /// Tables of a database created from the schema used before migrations
+    /// were introduced.
+    const LEGACY_SCHEMA: &str = "
+        CREATE TABLE users (
+            uuid TEXT PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE,
+            salt TEXT NOT NULL,
+            hashed_password TEXT NOT NULL,
+            recovery_key TEXT NOT NULL
+        ) STRICT;
+        CREATE TABLE user_sessions (
+            session_key TEXT PRIMARY KEY,
+            user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+            start_time INTEGER NOT NULL,
+            end_time INTEGER
+        ) STRICT;
+        CREATE TABLE media (
+            uuid TEXT PRIMARY KEY,
+            user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+            relative_path TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL,
+            hashed_value TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            w REAL NOT NULL,
+            h REAL NOT NULL,
+            UNIQUE(user, hashed_value)
+        ) STRICT;
+        CREATE TABLE projects (
+            uuid TEXT PRIMARY KEY,
+            user TEXT REFERENCES users(uuid) ON DELETE CASCADE NOT NULL,
+            updated_time INTEGER NOT NULL,
+            title TEXT NOT NULL
+        ) STRICT;
+        CREATE TABLE scenes (
+            uuid TEXT PRIMARY KEY NOT NULL,
+            project TEXT REFERENCES projects(uuid) ON DELETE CASCADE NOT NULL,
+            updated_time INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            thumbnail TEXT
+        ) STRICT;
+        INSERT INTO users VALUES ('user', 'username', 'salt', 'password', 'key');
+        INSERT INTO media VALUES ('media', 'user', 'path', 'title', 'hash', 1, 1.0, 1.0);
+        INSERT INTO projects VALUES ('project', 'user', 0, 'title');
+    ";
+
+    #[tokio::test]
+    async fn test_migrate_legacy_database() {
+        let path = std::env::temp_dir().join(format!("{}.db", format_uuid(generate_uuid())));
+        let url = Sqlite::url(&path);
+        sqlx::any::install_default_drivers();
+        let mut conn = sqlx::AnyConnection::connect(&url).await.unwrap();
+        conn.execute(LEGACY_SCHEMA).await.unwrap();
+        conn.close().await.unwrap();
+
+        // Columns added since are added to existing rows.
+        let pool = connect(&url).await.unwrap();
+        let tags: String = sqlx::query_scalar("SELECT tags FROM media WHERE uuid = 'media';")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tags, "");
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM projects WHERE description IS NULL AND tags = '';",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+        let count: i64 = sqlx::query_scalar(
+            "
+            SELECT COUNT(*) FROM users
+            WHERE display_name = '' AND avatar_media_key IS NULL AND email IS NULL
+                AND totp_last_step IS NULL;
+            ",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+
+        // Migrations are only applied once.
+        pool.close().await;
+        connect(&url).await.unwrap();
+        std::fs::remove_file(path).ok();
+    }
+}
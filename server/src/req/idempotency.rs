@@ -0,0 +1,114 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::{ErrorConflict, ErrorUnprocessableEntity},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::Next,
+    FromRequest, HttpResponse,
+};
+use uuid::Uuid;
+
+use super::{e500, Pool};
+use crate::{
+    models::{CachedResponse, Reservation, User},
+    utils::error,
+};
+
+pub const HEADER: &str = "X-Idempotency-Key";
+
+/// Middleware for endpoints which aren't safe to retry. If a request has an
+/// idempotency key which the user has already used, the earlier response is
+/// replayed rather than handling the request again. Keys are reserved before
+/// the request is handled, so concurrent retries are refused while the first
+/// is in progress. Use with `actix_web::middleware::from_fn`.
+pub async fn idempotent(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(key) = req.headers().get(HEADER) else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_boxed_body);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .and_then(|key| Uuid::try_parse(key).ok())
+        .ok_or_else(|| ErrorUnprocessableEntity("Invalid idempotency key."))?;
+    let user = req.extract::<User>().await?;
+    let request = format!("{} {}", req.method(), req.path());
+
+    // The connection is released before handling the request, as the handler
+    // will need one of its own.
+    let reservation = CachedResponse::reserve(
+        req.extract::<Pool>().await?.acquire(),
+        user.uuid,
+        key,
+        &request,
+    )
+    .await
+    .map_err(e500)?;
+    match reservation {
+        Reservation::Reserved => {}
+        Reservation::Pending => {
+            return Err(ErrorConflict(
+                "A request with this idempotency key is in progress.",
+            ))
+        }
+        Reservation::Done(cached) => {
+            let mut resp = HttpResponse::build(StatusCode::from_u16(cached.status).map_err(e500)?);
+            if let Some(content_type) = cached.content_type {
+                resp.content_type(content_type);
+            }
+            return Ok(req.into_response(resp.body(cached.body)));
+        }
+    }
+
+    // Errors aren't cached, so that the request may be retried.
+    let http_req = req.request().clone();
+    let release = || async {
+        let result = match Pool::extract(&http_req).await {
+            Ok(mut pool) => CachedResponse::release(pool.acquire(), user.uuid, key, &request).await,
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            error(format!("Failed to release idempotency key: {e}"));
+        }
+    };
+    let resp = match next.call(req).await {
+        Ok(resp) if !resp.status().is_server_error() => resp,
+        result => {
+            release().await;
+            return result.map(ServiceResponse::map_into_boxed_body);
+        }
+    };
+
+    let (req, resp) = resp.into_parts();
+    let (resp, body) = resp.into_parts();
+    let body = actix_web::body::to_bytes(body)
+        .await
+        .map_err(|e| e500(e.into().to_string()))?;
+
+    let cached = CachedResponse {
+        status: resp.status().as_u16(),
+        content_type: resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    };
+    // The request has been handled, so its response is returned even if it
+    // can't be cached.
+    let stored = match Pool::extract(&req).await {
+        Ok(mut pool) => cached.store(pool.acquire(), user.uuid, key, &request).await,
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = stored {
+        error(format!("Failed to cache idempotent response: {e}"));
+        release().await;
+    }
+
+    Ok(ServiceResponse::new(req, resp.set_body(body)).map_into_boxed_body())
+}
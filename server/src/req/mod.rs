@@ -1,8 +1,9 @@
 mod conn;
+pub mod idempotency;
 pub mod session;
 
 use actix_web::{http::StatusCode, HttpResponse};
-pub use conn::Pool;
+pub use conn::{connect, DbPool, Pool, Postgres, Sqlite};
 
 pub fn e500<E>(error: E) -> actix_web::Error
 where
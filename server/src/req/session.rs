@@ -3,13 +3,13 @@ use std::{fmt::Display, pin::Pin};
 
 use actix_web::{
     body::BoxBody,
-    error::{ErrorInternalServerError, ErrorUnprocessableEntity},
-    http::StatusCode,
+    error::{ErrorInternalServerError, ErrorUnauthorized, ErrorUnprocessableEntity},
+    http::{header::AUTHORIZATION, StatusCode},
     FromRequest, HttpResponse, ResponseError,
 };
 use futures::Future;
 
-use crate::models::{User, UserSession};
+use crate::models::{ApiKey, User, UserSession};
 
 pub const COOKIE_NAME: &str = "realmview_session";
 
@@ -58,7 +58,7 @@ async fn session_from_req(req: &actix_web::HttpRequest) -> Result<SessionOpt, ac
             .await
             .map_err(ErrorInternalServerError)?
         {
-            Some((session, user)) => SessionOpt::Some(Session { session, user }),
+            Some((session, user)) => SessionOpt::Some(Box::new(Session { session, user })),
             None => SessionOpt::None,
         };
         Ok(session)
@@ -69,11 +69,42 @@ async fn session_from_req(req: &actix_web::HttpRequest) -> Result<SessionOpt, ac
 
 async fn session_or_redirect(req: &actix_web::HttpRequest) -> Result<Session, actix_web::Error> {
     match session_from_req(req).await {
-        Ok(SessionOpt::Some(session)) => Ok(session),
+        Ok(SessionOpt::Some(session)) => Ok(*session),
         Ok(SessionOpt::None) | Err(_) => login_redirect(req.path()),
     }
 }
 
+/// Key provided in an `Authorization: Bearer <key>` header, if any.
+fn bearer_key(req: &actix_web::HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Authenticate with an API key if one was provided, else with the session
+/// cookie. Invalid API keys are rejected rather than redirected, as they
+/// aren't used from the browser.
+async fn user_or_redirect(req: &actix_web::HttpRequest) -> Result<User, actix_web::Error> {
+    let Some(key) = bearer_key(req) else {
+        return session_or_redirect(req).await.map(|s| s.user);
+    };
+
+    if key.len() != crate::crypto::KEY_LENGTH * 2 {
+        return Err(ErrorUnauthorized("Invalid API key."));
+    }
+
+    let conn = &mut crate::fs::database_connection()
+        .await
+        .map_err(ErrorInternalServerError)?;
+    ApiKey::user(conn, key)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorUnauthorized("Invalid API key."))
+}
+
 #[derive(Debug)]
 pub struct Session {
     pub session: UserSession,
@@ -82,7 +113,7 @@ pub struct Session {
 
 #[derive(Debug)]
 pub enum SessionOpt {
-    Some(Session),
+    Some(Box<Session>),
     None,
 }
 
@@ -108,7 +139,7 @@ impl FromRequest for User {
         _payload: &mut actix_web::dev::Payload,
     ) -> Self::Future {
         let req = req.clone();
-        Box::pin(async move { session_or_redirect(&req).await.map(|s| s.user) })
+        Box::pin(async move { user_or_redirect(&req).await })
     }
 }
 
@@ -0,0 +1,258 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ring::hmac;
+use sqlx::AnyPool;
+use uuid::Uuid;
+
+use crate::{
+    crypto::{from_hex_string, to_hex_string},
+    models::{Webhook, WebhookEvent},
+    utils::{err, format_uuid, timestamp_s, warning, Res},
+};
+
+/// Header containing the hex HMAC-SHA256 of the request body, keyed with the
+/// webhook's secret.
+const SIGNATURE_HEADER: &str = "X-Realmview-Signature";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Failed deliveries are retried this many times, backing off linearly.
+const RETRIES: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(serde_derive::Serialize)]
+pub struct Payload {
+    event: WebhookEvent,
+    game: String,
+    project: String,
+    timestamp: u64,
+
+    /// Name of the player who joined, for player_join events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player: Option<String>,
+}
+
+impl Payload {
+    pub fn new(event: WebhookEvent, game: String, project: Uuid) -> Self {
+        Self {
+            event,
+            game,
+            project: format_uuid(project),
+            timestamp: timestamp_s().unwrap_or(0),
+            player: None,
+        }
+    }
+
+    pub fn with_player(mut self, player: String) -> Self {
+        self.player = Some(player);
+        self
+    }
+}
+
+/// Send `payload` to each of `user`'s webhooks which subscribe to its event.
+/// Delivery happens in the background.
+pub fn notify(pool: AnyPool, user: Uuid, payload: Payload) {
+    tokio::task::spawn(async move {
+        if let Err(e) = deliver_all(&pool, user, payload).await {
+            warning(format!("Failed to deliver webhooks: {e}"));
+        }
+    });
+}
+
+async fn deliver_all(pool: &AnyPool, user: Uuid, payload: Payload) -> Res<()> {
+    let conn = &mut pool.acquire().await.map_err(|e| e.to_string())?;
+    let hooks = Webhook::for_event(conn, user, payload.event).await?;
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let body = Arc::new(serde_json::to_vec(&payload).map_err(|e| e.to_string())?);
+    for hook in hooks {
+        let body = body.clone();
+        tokio::task::spawn(async move { deliver(&hook, &body).await });
+    }
+
+    Ok(())
+}
+
+/// Check that `url` is a HTTP(S) URL whose host isn't on the server's own
+/// network, so that webhooks can't be used to reach internal services.
+pub fn check_url(url: &str) -> Res<reqwest::Url> {
+    let url = reqwest::Url::parse(url).map_err(|_| "Invalid webhook URL.".to_string())?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return err("Webhook URL must be a HTTP(S) URL.");
+    }
+
+    let public = match url.host_str() {
+        Some(host) => match host_address(host) {
+            Some(ip) => public_address(ip),
+            None => {
+                let domain = host.trim_end_matches('.').to_lowercase();
+                domain != "localhost" && !domain.ends_with(".localhost")
+            }
+        },
+        None => false,
+    };
+
+    if public {
+        Ok(url)
+    } else {
+        err("Webhook URL must be a public address.")
+    }
+}
+
+/// Address of a URL host which is an IP address rather than a domain.
+fn host_address(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+/// Whether `ip` is a public address, rather than a loopback, private or link
+/// local one.
+fn public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Shared address space, 100.64.0.0/10.
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return public_address(IpAddr::V4(ip));
+            }
+
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local, fc00::/7, and link local, fe80::/10.
+                || (first & 0xFE00) == 0xFC00
+                || (first & 0xFFC0) == 0xFE80)
+        }
+    }
+}
+
+/// Client to deliver to `url` with. The host is resolved ahead of time and
+/// the client pinned to the address found, so that it can be checked to be
+/// public. Redirects aren't followed, as they could lead anywhere.
+async fn client_for(url: &str) -> Res<reqwest::Client> {
+    let url = check_url(url)?;
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return err("Webhook URL has no host.");
+    };
+
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {host}: {e}"))?
+        .collect();
+    let Some(&address) = addresses.first() else {
+        return Err(format!("Failed to resolve {host}."));
+    };
+    if !addresses.iter().all(|a| public_address(a.ip())) {
+        return Err(format!("{host} resolves to a non-public address."));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    if host_address(host).is_none() {
+        builder = builder.resolve(host, address);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+async fn deliver(hook: &Webhook, body: &[u8]) {
+    let client = match client_for(&hook.url).await {
+        Ok(client) => client,
+        Err(e) => {
+            warning(format!("Not delivering webhook to {}: {e}", hook.url));
+            return;
+        }
+    };
+
+    let signature = match sign(&hook.secret, body) {
+        Ok(signature) => signature,
+        Err(e) => {
+            warning(format!("Failed to sign webhook payload: {e}"));
+            return;
+        }
+    };
+
+    for attempt in 0..=RETRIES {
+        let result = client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.to_vec())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        match result {
+            Ok(_) => return,
+            Err(e) if attempt == RETRIES => {
+                warning(format!("Webhook delivery to {} failed: {e}", hook.url))
+            }
+            Err(_) => tokio::time::sleep(RETRY_DELAY * (attempt + 1)).await,
+        }
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> Res<String> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &from_hex_string(secret)?);
+    Ok(to_hex_string(hmac::sign(&key, body).as_ref()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_url, sign};
+
+    #[test]
+    fn test_sign() {
+        let secret = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        assert_eq!(
+            sign(secret, br#"{"event":"game_start"}"#).unwrap(),
+            "79f3b493779668b857c8a7399270561b9e710e424d5ff17a7f5ec1acb91ef4b8"
+        );
+        assert!(sign("not hex", b"").is_err());
+    }
+
+    #[test]
+    fn test_check_url() {
+        for url in [
+            "https://example.com/hook",
+            "http://93.184.216.34:8080/hook",
+            "https://[2606:2800:220:1:248:1893:25c8:1946]/",
+        ] {
+            assert!(check_url(url).is_ok(), "{url}");
+        }
+
+        for url in [
+            "ftp://example.com",
+            "https://localhost/hook",
+            "http://api.localhost./",
+            "http://127.0.0.1:8000/",
+            "http://10.0.0.4/",
+            "http://172.16.8.1/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "not a url",
+        ] {
+            assert!(check_url(url).is_err(), "{url}");
+        }
+    }
+}
@@ -1,21 +1,50 @@
 class MediaItem {
-    constructor(key, title, url, w, h) {
+    constructor(key, title, url, w, h, tags = []) {
         this.key = key;
         this.title = title;
         this.url = url;
         this.w = w;
         this.h = h;
+        this.tags = tags;
 
         this.card = template_to_element(
             `{{ media/card(IFDEF(SCENE) {{ scene=1 }}) }}`
         );
 
         this.image = this.card.querySelector("img");
+        this.render_tags();
     }
 
     set_attr(key, value) {
+        if (key == "tags" && typeof value === "string") {
+            value = parse_tags(value);
+        }
+
         this[key] = value;
         this.image.setAttribute("data-" + key, value);
+
+        if (key == "tags") {
+            this.render_tags();
+        }
+    }
+
+    render_tags() {
+        let container = this.card.querySelector(".media-tags");
+        container.innerHTML = "";
+        this.tags.forEach(tag => {
+            let chip = document.createElement("span");
+            chip.classList.add("badge", "rounded-pill", "text-bg-secondary", "me-1");
+            chip.style.cursor = "pointer";
+            chip.innerText = tag;
+            chip.onclick = () => set_tag_filter(tag);
+            container.appendChild(chip);
+        });
+    }
+
+    // Whether this item matches a search query, by title or by tag.
+    matches(query) {
+        return this.title.toLowerCase().includes(query)
+            || this.tags.some(tag => tag.includes(query));
     }
 
     update(obj) {
@@ -39,7 +68,9 @@ class MediaManager {
 
     add_item(resp_item) {
         let i = resp_item;
-        let media_item = new MediaItem(i.media_key, i.title, i.url, i.w, i.h);
+        let media_item = new MediaItem(
+            i.media_key, i.title, i.url, i.w, i.h, i.tags ?? []
+        );
         this.media.set(resp_item.media_key, media_item);
         return media_item;
     }
@@ -219,12 +250,24 @@ function view_media() {
     req.send();
 }
 
-function search_filter(query) {
-    query = query.toLowerCase();
+// Split a comma-separated list of tags, as stored in the data-tags attribute.
+function parse_tags(tags) {
+    return tags
+        .split(",")
+        .map(tag => tag.trim().toLowerCase())
+        .filter(tag => tag.length);
+}
+
+var media_filter = { query: "", tag: "" };
+
+function apply_media_filter() {
     let matching = [];
 
     for (const item of media_manager.media.values()) {
-        if (item.title.toLowerCase().includes(query)) {
+        if (
+            item.matches(media_filter.query)
+            && (!media_filter.tag || item.tags.includes(media_filter.tag))
+        ) {
             matching.push(item);
         }
     }
@@ -232,5 +275,34 @@ function search_filter(query) {
     show_media(matching);
 }
 
+function search_filter(query) {
+    media_filter.query = query.toLowerCase();
+    apply_media_filter();
+}
+
+function set_tag_filter(tag) {
+    media_filter.tag = tag.trim().toLowerCase();
+    document
+        .querySelectorAll(".media-tag-filter")
+        .forEach(input => input.value = media_filter.tag);
+    apply_media_filter();
+}
+
+function update_media_tags(media_key, tags, callback = null) {
+    post(
+        `/api/media/${media_key}/tags`,
+        { tags: parse_tags(tags) },
+        resp => {
+            if (resp?.success) {
+                media_manager.update_item(media_key, { tags: tags });
+            }
+
+            if (callback) {
+                callback(resp);
+            }
+        }
+    );
+}
+
 var media_manager = new MediaManager();
 window.addEventListener("load", view_media);
@@ -1,5 +1,127 @@
 function record_to_element(project) {
-    return template_to_element(`{{ projects/project.html }}`);
+    const element = template_to_element(`{{ projects/project.html }}`);
+    load_scenes(element, project.uuid);
+    return element;
+}
+
+function scene_to_element(project, scene) {
+    return template_to_element(`{{ projects/scene() }}`);
+}
+
+const SCENE_PAGE_SIZE = 20;
+
+// Show the first page of the scenes in a project matching the search query,
+// in the selected order. With `more`, add the next page to those shown.
+function load_scenes(project_element, project_uuid, more = false) {
+    const list = project_element.querySelector("[data-role='scene_list']");
+    const [sort, order] = (
+        project_element.querySelector("[data-role='scene_sort']").value.split(":")
+    );
+    const params = new URLSearchParams({
+        q: project_element.querySelector("[data-role='scene_search']").value,
+        sort,
+        order,
+        limit: SCENE_PAGE_SIZE,
+        offset: more ? list.childElementCount : 0,
+    });
+
+    const path = `/api/project/${project_uuid}/scenes?${params}`;
+    fetch(path).then(resp => resp.json().then(body => {
+        if (!body.success) {
+            return;
+        }
+
+        if (!more) {
+            list.innerHTML = "";
+        }
+        body.scenes.forEach(scene => list.appendChild(
+            scene_to_element({ uuid: project_uuid }, scene)
+        ));
+        project_element
+            .querySelector("[data-role='scene_more']")
+            .classList.toggle(
+                "d-none", body.offset + body.scenes.length >= body.total
+            );
+    }));
+}
+
+function reload_scenes(project_uuid, more = false) {
+    const element = document.getElementById("project_" + project_uuid);
+    load_scenes(element, project_uuid, more);
+}
+
+// Copy a scene and refresh the project's scene list to include the copy.
+function duplicate_scene(project_uuid, scene_uuid) {
+    const path = `/api/project/${project_uuid}/scene/${scene_uuid}/duplicate`;
+    fetch(path, { method: "POST" }).then(resp => resp.json().then(body => {
+        if (body.success) {
+            reload_scenes(project_uuid);
+        }
+    }));
+}
+
+// Create a read-only link to a scene and show it to the user to copy.
+function share_scene(project_uuid, scene_uuid) {
+    const path = `/api/project/${project_uuid}/scene/${scene_uuid}/share`;
+    fetch(path, { method: "POST" }).then(resp => resp.json().then(body => {
+        if (body.success) {
+            window.prompt(
+                "Read-only link to this scene, valid for 7 days:",
+                window.location.origin + body.url
+            );
+        }
+    }));
+}
+
+function project_tag_chips(tags) {
+    return tags.map(tag => (
+        `<span
+            class="badge rounded-pill text-bg-secondary me-1"
+            style="cursor: pointer;"
+            onclick="filter_projects_by_tag('${tag}')"
+        >${tag}</span>`
+    )).join("");
+}
+
+// Show only projects with the given tag, or all projects if tag is empty.
+function filter_projects_by_tag(tag) {
+    tag = tag.trim().toLowerCase();
+    const input = document.getElementById("project_tag_filter");
+    if (input) {
+        input.value = tag;
+    }
+
+    document.querySelectorAll("[id^='project_'][data-tags]").forEach(el => {
+        let tags = el.getAttribute("data-tags").split(",");
+        el.classList.toggle("d-none", tag.length > 0 && !tags.includes(tag));
+    });
+}
+
+// Update the description or tags of a project. Tags are given as a
+// comma-separated string.
+function update_project_meta(project_uuid, meta) {
+    const el = document.getElementById("project_" + project_uuid);
+    let body = {
+        description: meta.description ?? el.getAttribute("data-description"),
+        tags: (meta.tags ?? el.getAttribute("data-tags")).split(","),
+    };
+
+    fetch("/api/project/" + project_uuid + "/meta", {
+        method: "PUT",
+        body: JSON.stringify(body),
+        headers: { "Content-Type": "application/json" }
+    }).then(resp => resp.json().then(resp => {
+        if (!resp.success) {
+            return;
+        }
+
+        const project = resp.project;
+        el.setAttribute("data-description", project.description ?? "");
+        el.setAttribute("data-tags", project.tags.join(","));
+        el.querySelector("[data-role='project_tags']").innerHTML = (
+            project_tag_chips(project.tags)
+        );
+    }));
 }
 
 function update_project_title(project_uuid, project_title) {
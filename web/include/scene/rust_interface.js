@@ -12,6 +12,13 @@ var RustFuncs = {
     texture if necessary.
     */
 
+    set_background: missing_func,
+    /*
+    function set_background(media_key: string)
+
+    Sets the background image of the active scene to the provided texture.
+    */
+
     active_scene: missing_func
     /**
      * function active_scene(): string
@@ -67,9 +74,9 @@ function load_texture(media_key) {
 // scene/project/project.js
 // function set_active_scene(scene_key: string)
 
-// Uploads the currently visible canvas area to the server as a 256x196 png as
+// Uploads the currently visible canvas area to the server as a 256x192 jpeg as
 // a thumbnail for the specified scene.
-function upload_thumbnail(scene_uuid) {
+function upload_thumbnail(project_uuid, scene_uuid) {
     const ASPECT = 4 / 3;
     const WIDTH = 256;
     const HEIGHT = WIDTH / ASPECT;
@@ -98,12 +105,47 @@ function upload_thumbnail(scene_uuid) {
 
     // Upload the thumbnail to the server.
     thumbnail.toBlob(blob => {
-        let data = new FormData();
-        data.append("image", blob, "thumbnail.png");
-        data.append("thumbnail", scene_uuid);
+        fetch(
+            `/api/project/${project_uuid}/scene/${scene_uuid}/thumbnail`,
+            {
+                method: "POST",
+                headers: { "Content-Type": "image/jpeg" },
+                body: blob
+            }
+        );
+    }, "image/jpeg");
+}
+
+// Opens a window for a second screen showing the scene as players see it.
+// Returns a canvas filling the window to render onto, or null if the window
+// couldn't be opened.
+function open_player_window() {
+    const player_window = window.open("", "player_screen", "popup");
+    if (!player_window) {
+        return null;
+    }
 
-        fetch("/api/upload", { method: "POST", body: data });
-    });
+    // Reopening the window gives the existing one, so reuse its canvas.
+    const doc = player_window.document;
+    let canvas = doc.getElementById("canvas");
+    if (!canvas) {
+        doc.title = "Player Screen";
+        doc.body.style.margin = "0";
+        doc.body.style.overflow = "hidden";
+        doc.body.style.background = "black";
+
+        canvas = doc.createElement("canvas");
+        canvas.id = "canvas";
+        canvas.style.display = "block";
+        canvas.style.width = "100vw";
+        canvas.style.height = "100vh";
+        doc.body.appendChild(canvas);
+
+        // Close the player screen along with the editor.
+        window.addEventListener("unload", () => player_window.close());
+    }
+
+    return canvas;
 }
 
 // End :: Externs
@@ -119,6 +161,15 @@ function add_to_scene(image) {
     ));
 }
 
+// Given an HTML image, load the texture for this image and set it as the
+// background image of the active scene.
+function set_scene_background(image) {
+    texture_queue.push(image);
+    call_when_ready("set_background", () => RustFuncs.set_background(
+        image.getAttribute("data-media_key"),
+    ));
+}
+
 function active_scene() {
     return RustFuncs.active_scene();
 }